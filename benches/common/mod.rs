@@ -0,0 +1,73 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Message histories shaped like real channels, for the benchmarks
+
+use burrow::types::{ChannelId, Message, MessageContent, MessageId, PeerId, VectorClock};
+
+/// Channel sizes every benchmark runs at
+pub const SIZES: [(&str, usize); 3] = [("small", 100), ("medium", 1_000), ("large", 10_000)];
+
+/// Peers taking turns to post
+const AUTHORS: usize = 4;
+
+/// Every this many messages two authors post concurrently, forking the history
+const FORK_EVERY: usize = 10;
+
+/// A channel history of `count` messages in causal order
+///
+/// Mostly a chain, as in a conversation where everyone sees each message
+/// before replying. Every `FORK_EVERY` messages two authors post at once
+/// on the same parent, and the next message merges both branches, the
+/// way concurrent posts get reconciled by the following reply.
+pub fn channel_history(channel: ChannelId, count: usize) -> Vec<Message> {
+    let authors: Vec<PeerId> = (0..AUTHORS).map(|_| PeerId::new()).collect();
+    let mut clock = VectorClock::new();
+    let mut heads: Vec<MessageId> = Vec::new();
+    let mut messages = Vec::with_capacity(count);
+
+    while messages.len() < count {
+        let index = messages.len();
+        let forking = index % FORK_EVERY == FORK_EVERY - 1 && index + 2 <= count;
+        let branches = if forking { 2 } else { 1 };
+
+        let parents = std::mem::take(&mut heads);
+        for branch in 0..branches {
+            let author = authors[(index + branch) % AUTHORS];
+            let mut branch_clock = clock.clone();
+            branch_clock.increment(author);
+
+            let mut message = Message::new(
+                channel,
+                author,
+                MessageContent::new(format!(
+                    "message {} from author {}",
+                    index + branch,
+                    (index + branch) % AUTHORS
+                )),
+                branch_clock.clone(),
+                index as u64 + 1,
+            );
+            message.parent_hashes = parents.clone();
+            message.created_at_ms = 1_700_000_000_000 + (index + branch) as u64 * 1_000;
+
+            heads.push(message.id);
+            clock.merge(&branch_clock);
+            messages.push(message);
+        }
+    }
+
+    messages
+}
@@ -0,0 +1,137 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! MessageDAG hot paths: adding messages and reading a channel in order
+
+mod common;
+
+use burrow::dag::{IngestMode, MessageDAG};
+use burrow::types::{ChannelId, Message, MessageContent, MessageId, PeerId, VectorClock};
+use criterion::{BatchSize, BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use std::hint::black_box;
+
+/// Messages arriving one at a time, as live gossip does
+fn bench_ingest(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dag/ingest");
+    for (name, size) in common::SIZES {
+        let history = common::channel_history(ChannelId::new(), size);
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &history, |b, history| {
+            b.iter_batched(
+                || history.clone(),
+                |history| {
+                    let mut dag = MessageDAG::new();
+                    for message in history {
+                        dag.ingest(message, IngestMode::Strict).unwrap();
+                    }
+                    dag
+                },
+                BatchSize::LargeInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+/// A channel's history loaded in one batch, as at startup
+fn bench_ingest_all(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dag/ingest_all");
+    for (name, size) in common::SIZES {
+        let history = common::channel_history(ChannelId::new(), size);
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &history, |b, history| {
+            b.iter_batched(
+                || history.clone(),
+                |history| {
+                    let mut dag = MessageDAG::new();
+                    dag.ingest_all(history, IngestMode::Strict).unwrap();
+                    dag
+                },
+                BatchSize::LargeInput,
+            );
+        });
+    }
+    group.finish();
+}
+
+/// A single author's unbroken chain of `count` messages, each replying to the last
+fn linear_history(channel: ChannelId, count: usize) -> Vec<Message> {
+    let author = PeerId::new();
+    let mut clock = VectorClock::new();
+    let mut parents: Vec<MessageId> = Vec::new();
+    let mut messages = Vec::with_capacity(count);
+
+    for index in 0..count {
+        clock.increment(author);
+        let mut message = Message::new(
+            channel,
+            author,
+            MessageContent::new(format!("message {}", index)),
+            clock.clone(),
+            index as u64 + 1,
+        );
+        message.parent_hashes = parents;
+        parents = vec![message.id];
+        message.created_at_ms = 1_700_000_000_000 + index as u64 * 1_000;
+        messages.push(message);
+    }
+
+    messages
+}
+
+/// One long chain appended a message at a time, where re-sorting the whole
+/// channel per message would be quadratic
+fn bench_append_linear(c: &mut Criterion) {
+    const CHAIN_LENGTH: usize = 50_000;
+
+    let mut group = c.benchmark_group("dag/append_linear");
+    group.sample_size(10);
+    let history = linear_history(ChannelId::new(), CHAIN_LENGTH);
+    group.throughput(Throughput::Elements(CHAIN_LENGTH as u64));
+    group.bench_with_input(BenchmarkId::from_parameter(CHAIN_LENGTH), &history, |b, history| {
+        b.iter_batched(
+            || history.clone(),
+            |history| {
+                let mut dag = MessageDAG::new();
+                for message in history {
+                    dag.ingest(message, IngestMode::Strict).unwrap();
+                }
+                dag
+            },
+            BatchSize::LargeInput,
+        );
+    });
+    group.finish();
+}
+
+/// Reading a channel in causal order, as every redraw of the message list does
+fn bench_get_ordered_messages(c: &mut Criterion) {
+    let mut group = c.benchmark_group("dag/get_ordered_messages");
+    for (name, size) in common::SIZES {
+        let channel = ChannelId::new();
+        let mut dag = MessageDAG::new();
+        dag.ingest_all(common::channel_history(channel, size), IngestMode::Strict)
+            .unwrap();
+
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter(|| dag.get_ordered_messages(black_box(&channel)));
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_ingest, bench_ingest_all, bench_append_linear, bench_get_ordered_messages);
+criterion_main!(benches);
@@ -0,0 +1,127 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Storage hot paths: writing a batch of synced messages, loading a channel
+//! and reading messages by ID to answer a peer's request
+
+mod common;
+
+use burrow::storage::{DEFAULT_FETCH_CONCURRENCY, Storage};
+use burrow::types::{ChannelId, MessageId};
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::runtime::Runtime;
+
+/// A fresh database file, so each run starts from an empty table
+async fn fresh_storage() -> (Storage, PathBuf) {
+    let db_path = std::env::temp_dir().join(format!("burrow-bench-{}.db", uuid::Uuid::now_v7()));
+    std::fs::File::create(&db_path).unwrap();
+    (Storage::new(&db_path).await.unwrap(), db_path)
+}
+
+fn remove_storage(storage: Storage, db_path: PathBuf) {
+    drop(storage);
+    let _ = std::fs::remove_file(&db_path);
+}
+
+/// A batch of history arriving from a peer, written to an empty channel
+fn bench_store_messages(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("storage/store_messages");
+    group.sample_size(10);
+    for (name, size) in common::SIZES {
+        let history = common::channel_history(ChannelId::new(), size);
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), &history, |b, history| {
+            // Only the write is timed; creating and removing the database isn't
+            b.iter_custom(|iters| {
+                runtime.block_on(async {
+                    let mut total = Duration::ZERO;
+                    for _ in 0..iters {
+                        let (storage, db_path) = fresh_storage().await;
+                        let start = Instant::now();
+                        storage.store_messages(history).await.into_result().unwrap();
+                        total += start.elapsed();
+                        remove_storage(storage, db_path);
+                    }
+                    total
+                })
+            });
+        });
+    }
+    group.finish();
+}
+
+/// Loading a channel's full history, as at startup and on channel switch
+fn bench_get_channel_messages(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("storage/get_channel_messages");
+    group.sample_size(10);
+    for (name, size) in common::SIZES {
+        let channel = ChannelId::new();
+        let (storage, db_path) = runtime.block_on(async {
+            let (storage, db_path) = fresh_storage().await;
+            storage
+                .store_messages(&common::channel_history(channel, size))
+                .await
+                .into_result()
+                .unwrap();
+            (storage, db_path)
+        });
+
+        group.throughput(Throughput::Elements(size as u64));
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter(|| runtime.block_on(storage.get_channel_messages(channel)).unwrap());
+        });
+
+        remove_storage(storage, db_path);
+    }
+    group.finish();
+}
+
+/// Reading 1,000 messages by ID, one at a time versus concurrently
+fn bench_get_messages_by_ids(c: &mut Criterion) {
+    let runtime = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("storage/get_messages_by_ids");
+    group.sample_size(10);
+
+    let history = common::channel_history(ChannelId::new(), 1_000);
+    let ids: Vec<MessageId> = history.iter().map(|m| m.id).collect();
+    let (mut storage, db_path) = runtime.block_on(async {
+        let (storage, db_path) = fresh_storage().await;
+        storage.store_messages(&history).await.into_result().unwrap();
+        (storage, db_path)
+    });
+
+    group.throughput(Throughput::Elements(ids.len() as u64));
+    for (name, concurrency) in [("sequential", 1), ("concurrent", DEFAULT_FETCH_CONCURRENCY)] {
+        storage.set_fetch_concurrency(concurrency);
+        group.bench_function(BenchmarkId::from_parameter(name), |b| {
+            b.iter(|| runtime.block_on(storage.get_messages_by_ids(&ids)).unwrap());
+        });
+    }
+
+    remove_storage(storage, db_path);
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    bench_store_messages,
+    bench_get_channel_messages,
+    bench_get_messages_by_ids
+);
+criterion_main!(benches);
@@ -0,0 +1,173 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::dag::gossip::{DEFAULT_ANTI_ENTROPY_FANOUT, DEFAULT_MAX_OUTSTANDING_REQUESTS};
+use crate::identity::store::KeyBackend;
+use crate::storage::DEFAULT_FETCH_CONCURRENCY;
+use crate::types::ChannelId;
+use std::str::FromStr;
+use std::time::Duration;
+
+/// Runtime configuration, read from `BURROW_*` environment variables
+#[derive(Debug, Clone)]
+pub struct Config {
+    /// TCP port to listen on (`BURROW_PORT`)
+    pub listen_port: u16,
+
+    /// Only instances sharing this name find each other; empty for the
+    /// default network (`BURROW_NETWORK`)
+    pub network_namespace: String,
+
+    /// Maximum message requests in flight during sync (`BURROW_MAX_MESSAGE_REQUESTS`)
+    pub max_outstanding_requests: usize,
+
+    /// Peers asked for their inventory when a peer connects (`BURROW_ANTI_ENTROPY_FANOUT`)
+    pub anti_entropy_fanout: usize,
+
+    /// Stored messages read at once when answering a peer's request
+    /// (`BURROW_FETCH_CONCURRENCY`)
+    pub fetch_concurrency: usize,
+
+    /// Announce our listen addresses to peers that dial us, so they can
+    /// dial back later (`BURROW_AUTO_CONNECT_BACK`)
+    pub auto_connect_back: bool,
+
+    /// List the most recently active channels first (`BURROW_SORT_CHANNELS_BY_ACTIVITY`)
+    pub sort_channels_by_activity: bool,
+
+    /// Show how many channel members have causally seen each message (`BURROW_SHOW_SEEN_BY`)
+    pub show_seen_by: bool,
+
+    /// Most notifications kept in the history view (`BURROW_NOTIFICATION_LOG_MAX_ENTRIES`)
+    pub notification_log_max_entries: usize,
+
+    /// How long notifications stay in the history view
+    /// (`BURROW_NOTIFICATION_LOG_MAX_AGE_SECS`)
+    pub notification_log_max_age: Duration,
+
+    /// Channel list width as a percentage of the screen, until resized with
+    /// Ctrl+Left/Right (`BURROW_SIDEBAR_PERCENT`)
+    pub sidebar_percent: u16,
+
+    /// Which connection events raise notifications: `all`, `manual` or
+    /// `none` (`BURROW_CONNECTION_NOTICES`)
+    pub connection_notices: ConnectionNotices,
+
+    /// Channels to always follow, created or joined at startup; comma-separated
+    /// IDs or names (`BURROW_CHANNELS`)
+    pub startup_channels: Vec<ChannelRef>,
+
+    /// Where the identity's private key is kept: `file` or `keychain`
+    /// (`BURROW_KEY_STORE`)
+    pub key_backend: KeyBackend,
+
+    /// Browse a database without networking or sending (set by `--archive`)
+    pub read_only: bool,
+}
+
+impl Config {
+    /// Load configuration from the environment, using defaults for unset or invalid values
+    pub fn from_env() -> Self {
+        Self {
+            listen_port: env_or("BURROW_PORT", 9000),
+            network_namespace: env_or("BURROW_NETWORK", String::new()),
+            max_outstanding_requests: env_or(
+                "BURROW_MAX_MESSAGE_REQUESTS",
+                DEFAULT_MAX_OUTSTANDING_REQUESTS,
+            )
+            .max(1),
+            anti_entropy_fanout: env_or("BURROW_ANTI_ENTROPY_FANOUT", DEFAULT_ANTI_ENTROPY_FANOUT).max(1),
+            fetch_concurrency: env_or("BURROW_FETCH_CONCURRENCY", DEFAULT_FETCH_CONCURRENCY).max(1),
+            auto_connect_back: env_or("BURROW_AUTO_CONNECT_BACK", true),
+            sort_channels_by_activity: env_or("BURROW_SORT_CHANNELS_BY_ACTIVITY", false),
+            show_seen_by: env_or("BURROW_SHOW_SEEN_BY", false),
+            notification_log_max_entries: env_or("BURROW_NOTIFICATION_LOG_MAX_ENTRIES", 200),
+            notification_log_max_age: Duration::from_secs(env_or(
+                "BURROW_NOTIFICATION_LOG_MAX_AGE_SECS",
+                24 * 60 * 60,
+            )),
+            sidebar_percent: env_or("BURROW_SIDEBAR_PERCENT", 25),
+            connection_notices: env_or("BURROW_CONNECTION_NOTICES", ConnectionNotices::Manual),
+            startup_channels: std::env::var("BURROW_CHANNELS")
+                .map(|value| parse_channel_list(&value))
+                .unwrap_or_default(),
+            key_backend: env_or("BURROW_KEY_STORE", KeyBackend::File),
+            read_only: false,
+        }
+    }
+}
+
+/// How much connection activity is announced with notifications
+///
+/// Failures of addresses the user dialed themselves are always shown.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ConnectionNotices {
+    /// Also peers found on the local network and failed automatic dials,
+    /// for debugging discovery
+    All,
+    /// Connected peers and the progress of manual dials, but not discovery
+    #[default]
+    Manual,
+    /// Only failed manual dials
+    None,
+}
+
+impl FromStr for ConnectionNotices {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "all" => Ok(Self::All),
+            "manual" => Ok(Self::Manual),
+            "none" => Ok(Self::None),
+            other => Err(format!("unknown connection notice level {:?} (expected all, manual or none)", other)),
+        }
+    }
+}
+
+/// A channel named in configuration, by ID or by name
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ChannelRef {
+    Id(ChannelId),
+    Name(String),
+}
+
+impl FromStr for ChannelRef {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        if s.is_empty() {
+            return Err("empty channel".to_string());
+        }
+        Ok(match s.parse() {
+            Ok(uuid) => Self::Id(ChannelId(uuid)),
+            Err(_) => Self::Name(s.to_string()),
+        })
+    }
+}
+
+/// Parse a comma-separated list of channels, skipping empty entries
+pub fn parse_channel_list(value: &str) -> Vec<ChannelRef> {
+    value.split(',').filter_map(|entry| entry.parse().ok()).collect()
+}
+
+/// Parse an environment variable, falling back to a default
+fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    std::env::var(name)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(default)
+}
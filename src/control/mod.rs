@@ -0,0 +1,199 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Local control interface for scripts and bots
+//!
+//! Clients connect to a Unix domain socket and exchange JSON-RPC 2.0
+//! messages, one JSON object per line:
+//!
+//! ```text
+//! -> {"jsonrpc":"2.0","id":1,"method":"send_message","params":{"channel":"general","text":"hi"}}
+//! <- {"jsonrpc":"2.0","id":1,"result":{"message_id":"0190..."}}
+//! ```
+//!
+//! Methods:
+//! - `list_channels` → `[{"id", "name", "type", "members"}]`
+//! - `list_peers` → `[{"peer_id", "addresses", "agent_version", "quality", "rtt_ms"}]`
+//! - `send_message` `{"channel": <id or name>, "text"}` → `{"message_id"}`
+//! - `connect` `{"address": <multiaddr>}` → `{"dialing"}`
+//!
+//! Requests are carried out by the running app, through the same code paths
+//! as the equivalent keyboard actions.
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::sync::oneshot;
+
+#[cfg(unix)]
+mod server;
+
+#[cfg(unix)]
+pub use server::{bind, serve};
+
+/// A command the app can carry out for a control client
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ControlCommand {
+    ListChannels,
+    ListPeers,
+    /// `channel` is a channel ID or name
+    SendMessage { channel: String, text: String },
+    Connect { address: String },
+}
+
+impl ControlCommand {
+    /// Build a command from a JSON-RPC method name and its params
+    pub fn parse(method: &str, params: Value) -> Result<Self, RpcError> {
+        #[derive(Deserialize)]
+        struct SendParams {
+            channel: String,
+            text: String,
+        }
+
+        #[derive(Deserialize)]
+        struct ConnectParams {
+            address: String,
+        }
+
+        fn params_as<T: serde::de::DeserializeOwned>(params: Value) -> Result<T, RpcError> {
+            serde_json::from_value(params).map_err(|e| RpcError::new(RpcError::INVALID_PARAMS, e.to_string()))
+        }
+
+        match method {
+            "list_channels" => Ok(Self::ListChannels),
+            "list_peers" => Ok(Self::ListPeers),
+            "send_message" => {
+                let SendParams { channel, text } = params_as(params)?;
+                Ok(Self::SendMessage { channel, text })
+            }
+            "connect" => {
+                let ConnectParams { address } = params_as(params)?;
+                Ok(Self::Connect { address })
+            }
+            other => Err(RpcError::new(
+                RpcError::METHOD_NOT_FOUND,
+                format!("Unknown method {:?}", other),
+            )),
+        }
+    }
+}
+
+/// A JSON-RPC error object
+#[derive(Debug, Clone, PartialEq, Eq, thiserror::Error)]
+#[error("{message} (code {code})")]
+pub struct RpcError {
+    pub code: i64,
+    pub message: String,
+}
+
+impl RpcError {
+    pub const PARSE_ERROR: i64 = -32700;
+    pub const INVALID_REQUEST: i64 = -32600;
+    pub const METHOD_NOT_FOUND: i64 = -32601;
+    pub const INVALID_PARAMS: i64 = -32602;
+    pub const INTERNAL_ERROR: i64 = -32603;
+    /// The request was understood but the app refused it (e.g. no such channel)
+    pub const REJECTED: i64 = 1;
+
+    pub fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// A parsed request waiting for the app's answer
+pub struct ControlCall {
+    pub command: ControlCommand,
+    pub reply: oneshot::Sender<Result<Value, RpcError>>,
+}
+
+/// Split a request line into its ID and command
+///
+/// Errors carry the request ID when it could be read, so the client can
+/// match the error response to its request.
+pub fn parse_request(line: &str) -> Result<(Value, ControlCommand), (Value, RpcError)> {
+    #[derive(Deserialize)]
+    struct Request {
+        #[serde(default)]
+        id: Value,
+        method: String,
+        #[serde(default)]
+        params: Value,
+    }
+
+    let value: Value = serde_json::from_str(line)
+        .map_err(|e| (Value::Null, RpcError::new(RpcError::PARSE_ERROR, e.to_string())))?;
+    let id = value.get("id").cloned().unwrap_or(Value::Null);
+    let request: Request = serde_json::from_value(value)
+        .map_err(|e| (id.clone(), RpcError::new(RpcError::INVALID_REQUEST, e.to_string())))?;
+
+    let command = ControlCommand::parse(&request.method, request.params).map_err(|e| (id, e))?;
+    Ok((request.id, command))
+}
+
+/// Render a response line for a request ID
+pub fn response_line(id: Value, result: Result<Value, RpcError>) -> String {
+    let response = match result {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }),
+        Err(error) => json!({
+            "jsonrpc": "2.0",
+            "id": id,
+            "error": { "code": error.code, "message": error.message },
+        }),
+    };
+    response.to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requests_parsed() {
+        let (id, command) = parse_request(
+            r#"{"jsonrpc":"2.0","id":7,"method":"send_message","params":{"channel":"general","text":"hi"}}"#,
+        )
+        .unwrap();
+        assert_eq!(id, json!(7));
+        assert_eq!(
+            command,
+            ControlCommand::SendMessage {
+                channel: "general".to_string(),
+                text: "hi".to_string(),
+            }
+        );
+
+        let (_, command) = parse_request(r#"{"jsonrpc":"2.0","id":"a","method":"list_channels"}"#).unwrap();
+        assert_eq!(command, ControlCommand::ListChannels);
+    }
+
+    #[test]
+    fn test_bad_requests_answer_with_error_codes() {
+        let (id, error) = parse_request(r#"{"id":3,"method":"reboot"}"#).unwrap_err();
+        assert_eq!((id, error.code), (json!(3), RpcError::METHOD_NOT_FOUND));
+
+        let (_, error) = parse_request(r#"{"id":4,"method":"connect","params":{}}"#).unwrap_err();
+        assert_eq!(error.code, RpcError::INVALID_PARAMS);
+
+        let (id, error) = parse_request("not json").unwrap_err();
+        assert_eq!((id, error.code), (Value::Null, RpcError::PARSE_ERROR));
+
+        let line = response_line(json!(4), Err(RpcError::new(RpcError::REJECTED, "No channel named x")));
+        let response: Value = serde_json::from_str(&line).unwrap();
+        assert_eq!(response["error"]["code"], json!(1));
+        assert_eq!(response["id"], json!(4));
+    }
+}
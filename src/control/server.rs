@@ -0,0 +1,134 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::{parse_request, response_line, ControlCall, RpcError};
+use anyhow::{bail, Context, Result};
+use std::fs::{DirBuilder, Permissions};
+use std::os::unix::fs::{DirBuilderExt, FileTypeExt, PermissionsExt};
+use std::path::Path;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{mpsc, oneshot};
+
+/// Bind the control socket, accessible to the owner only
+///
+/// A socket left behind by an earlier run is replaced; any other file at
+/// the path is left alone and reported. The socket is bound inside a
+/// private directory and only moved to `path` once it's restricted, so no
+/// one else can connect in between.
+pub fn bind(path: &Path) -> Result<UnixListener> {
+    if let Ok(metadata) = std::fs::symlink_metadata(path) {
+        if !metadata.file_type().is_socket() {
+            bail!("{:?} exists and is not a socket", path);
+        }
+        std::fs::remove_file(path).with_context(|| format!("Failed to remove stale socket {:?}", path))?;
+    }
+
+    let file_name = path.file_name().with_context(|| format!("{:?} is not a socket path", path))?;
+    let staging = path.with_file_name(format!(".{}.{}", file_name.to_string_lossy(), std::process::id()));
+    DirBuilder::new()
+        .mode(0o700)
+        .create(&staging)
+        .with_context(|| format!("Failed to create {:?}", staging))?;
+
+    let staged = staging.join("sock");
+    let bound = bind_private(&staged).and_then(|listener| {
+        std::fs::rename(&staged, path).with_context(|| format!("Failed to move control socket to {:?}", path))?;
+        Ok(listener)
+    });
+    let _ = std::fs::remove_dir_all(&staging);
+    bound
+}
+
+fn bind_private(path: &Path) -> Result<UnixListener> {
+    let listener = UnixListener::bind(path).with_context(|| format!("Failed to bind control socket {:?}", path))?;
+    std::fs::set_permissions(path, Permissions::from_mode(0o600))
+        .with_context(|| format!("Failed to restrict permissions on {:?}", path))?;
+
+    Ok(listener)
+}
+
+/// Accept control clients until the app goes away
+pub async fn serve(listener: UnixListener, calls: mpsc::UnboundedSender<ControlCall>) {
+    loop {
+        match listener.accept().await {
+            Ok((stream, _)) => {
+                let calls = calls.clone();
+                tokio::spawn(async move {
+                    if let Err(e) = handle_connection(stream, calls).await {
+                        tracing::debug!("Control connection closed: {:#}", e);
+                    }
+                });
+            }
+            Err(e) => tracing::warn!("Control socket accept failed: {}", e),
+        }
+
+        if calls.is_closed() {
+            break;
+        }
+    }
+}
+
+/// Answer one request per line until the client hangs up
+async fn handle_connection(stream: UnixStream, calls: mpsc::UnboundedSender<ControlCall>) -> Result<()> {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+
+    while let Some(line) = lines.next_line().await? {
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let response = match parse_request(&line) {
+            Ok((id, command)) => {
+                let (reply, answer) = oneshot::channel();
+                calls
+                    .send(ControlCall { command, reply })
+                    .context("Burrow is shutting down")?;
+                let result = answer
+                    .await
+                    .unwrap_or_else(|_| Err(RpcError::new(RpcError::INTERNAL_ERROR, "Burrow is shutting down")));
+                response_line(id, result)
+            }
+            Err((id, error)) => response_line(id, Err(error)),
+        };
+
+        writer.write_all(response.as_bytes()).await?;
+        writer.write_all(b"\n").await?;
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_socket_owner_only_and_staging_removed() {
+        let dir = std::env::temp_dir().join(format!("burrow-control-{}", uuid::Uuid::now_v7()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("control.sock");
+
+        let _listener = bind(&path).unwrap();
+        let metadata = std::fs::symlink_metadata(&path).unwrap();
+        assert!(metadata.file_type().is_socket());
+        assert_eq!(metadata.permissions().mode() & 0o777, 0o600);
+        assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+        UnixStream::connect(&path).await.unwrap();
+
+        let _ = std::fs::remove_dir_all(&dir);
+    }
+}
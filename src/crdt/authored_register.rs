@@ -0,0 +1,130 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::{LWWRegister, Timestamp};
+use crate::types::PeerId;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+/// Last-Write-Wins Register for values only some peers may set
+///
+/// Keeps each writer's latest write rather than just the newest one. The
+/// value in force is the newest write by a peer who may write it now, judged
+/// when it's read. Dropping unauthorized writes while merging instead would
+/// make the outcome depend on whether a write arrived before or after its
+/// writer lost the right to make it.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AuthoredRegister<T> {
+    /// Latest write by each peer, keyed by the peer in its timestamp
+    writes: HashMap<PeerId, LWWRegister<T>>,
+}
+
+impl<T> AuthoredRegister<T> {
+    pub fn new() -> Self {
+        Self { writes: HashMap::new() }
+    }
+
+    /// Every writer's latest write, in no particular order
+    pub fn writes(&self) -> impl Iterator<Item = &LWWRegister<T>> {
+        self.writes.values()
+    }
+}
+
+impl<T> Default for AuthoredRegister<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T: Clone> AuthoredRegister<T> {
+    /// The newest write by a peer `allowed` accepts, if there is one
+    pub fn latest(&self, allowed: impl Fn(&PeerId) -> bool) -> Option<&LWWRegister<T>> {
+        self.writes
+            .values()
+            .filter(|write| allowed(&write.timestamp().peer_id))
+            .max_by_key(|write| write.timestamp())
+    }
+}
+
+impl<T: Clone + Ord> AuthoredRegister<T> {
+    /// Record a write by the peer in its timestamp
+    pub fn set(&mut self, value: T, timestamp: Timestamp) {
+        match self.writes.get_mut(&timestamp.peer_id) {
+            Some(write) => write.set(value, timestamp),
+            None => {
+                self.writes.insert(timestamp.peer_id, LWWRegister::new(value, timestamp));
+            }
+        }
+    }
+
+    /// Merge with another register, keeping each writer's latest write
+    pub fn merge(&mut self, other: &AuthoredRegister<T>) {
+        for write in other.writes() {
+            self.set(write.value().clone(), write.timestamp());
+        }
+    }
+}
+
+impl<T: Clone + Ord> FromIterator<LWWRegister<T>> for AuthoredRegister<T> {
+    fn from_iter<I: IntoIterator<Item = LWWRegister<T>>>(writes: I) -> Self {
+        let mut register = Self::new();
+        for write in writes {
+            register.set(write.value().clone(), write.timestamp());
+        }
+        register
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_latest_allowed_write_wins() {
+        let (admin, other) = (PeerId::new(), PeerId::new());
+        let mut register = AuthoredRegister::new();
+        register.set("first", Timestamp::new(1000, 0, admin));
+        register.set("second", Timestamp::new(2000, 0, other));
+
+        assert_eq!(register.latest(|_| true).map(|write| *write.value()), Some("second"));
+        assert_eq!(register.latest(|peer| *peer == admin).map(|write| *write.value()), Some("first"));
+        assert!(register.latest(|_| false).is_none());
+    }
+
+    #[test]
+    fn test_merge_converges_in_any_order() {
+        let (alice, bob) = (PeerId::new(), PeerId::new());
+        let mut a = AuthoredRegister::new();
+        a.set("a1", Timestamp::new(1000, 0, alice));
+        a.set("a2", Timestamp::new(3000, 0, alice));
+        let mut b = AuthoredRegister::new();
+        b.set("a1", Timestamp::new(1000, 0, alice));
+        b.set("b1", Timestamp::new(2000, 0, bob));
+
+        let mut ab = a.clone();
+        ab.merge(&b);
+        let mut ba = b.clone();
+        ba.merge(&a);
+
+        for allowed in [alice, bob] {
+            let value = |register: &AuthoredRegister<&str>| {
+                register.latest(|peer| *peer == allowed).map(|write| *write.value())
+            };
+            assert_eq!(value(&ab), value(&ba));
+        }
+        assert_eq!(ab.latest(|peer| *peer == bob).map(|write| *write.value()), Some("b1"));
+        assert_eq!(ab.writes().count(), 2, "only each writer's latest write is kept");
+    }
+}
@@ -38,6 +38,16 @@ impl HybridLogicalClock {
         }
     }
 
+    /// Restore a clock that had reached `latest`
+    pub fn with_latest(peer_id: PeerId, latest: Timestamp) -> Self {
+        Self { peer_id, latest }
+    }
+
+    /// The peer whose events this clock stamps
+    pub fn peer_id(&self) -> PeerId {
+        self.peer_id
+    }
+
     /// Generate a new timestamp for a local event
     pub fn tick(&mut self) -> Timestamp {
         let physical_now = SystemTime::now()
@@ -85,6 +95,19 @@ impl HybridLogicalClock {
     pub fn latest(&self) -> Timestamp {
         self.latest
     }
+
+    /// Current physical time as seen by this clock, without ticking it
+    ///
+    /// Never behind any timestamp the clock has observed, so peers whose wall
+    /// clocks lag still agree on deadlines derived from remote timestamps.
+    pub fn physical_now(&self) -> u64 {
+        let physical_now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap()
+            .as_millis() as u64;
+
+        physical_now.max(self.latest.physical)
+    }
 }
 
 #[cfg(test)]
@@ -38,11 +38,12 @@ impl<T: Clone> LWWRegister<T> {
     pub fn timestamp(&self) -> Timestamp {
         self.timestamp
     }
+}
 
+impl<T: Clone + Ord> LWWRegister<T> {
     /// Set a new value with a timestamp
     pub fn set(&mut self, value: T, timestamp: Timestamp) {
-        // Only update if the new timestamp is greater
-        if timestamp > self.timestamp {
+        if self.wins(&value, timestamp) {
             self.value = value;
             self.timestamp = timestamp;
         }
@@ -50,11 +51,24 @@ impl<T: Clone> LWWRegister<T> {
 
     /// Merge with another LWWRegister, keeping the value with the latest timestamp
     pub fn merge(&mut self, other: &LWWRegister<T>) {
-        if other.timestamp > self.timestamp {
+        if self.wins(&other.value, other.timestamp) {
             self.value = other.value.clone();
             self.timestamp = other.timestamp;
         }
     }
+
+    /// Whether a write replaces the current value
+    ///
+    /// The later timestamp wins. Equal timestamps (possible after HLC
+    /// clamping) fall back to the greater value, so every replica picks the
+    /// same one whatever order the writes arrived in.
+    fn wins(&self, value: &T, timestamp: Timestamp) -> bool {
+        match timestamp.cmp(&self.timestamp) {
+            std::cmp::Ordering::Greater => true,
+            std::cmp::Ordering::Equal => *value > self.value,
+            std::cmp::Ordering::Less => false,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -89,4 +103,23 @@ mod tests {
 
         assert_eq!(reg.value(), "value1", "Should not update with earlier timestamp");
     }
+
+    #[test]
+    fn test_lww_register_tie_converges() {
+        let peer = PeerId::new();
+        let tie = Timestamp::new(1000, 3, peer);
+
+        let mut reg_a = LWWRegister::new("alpha".to_string(), tie);
+        let mut reg_b = LWWRegister::new("beta".to_string(), tie);
+        let (snapshot_a, snapshot_b) = (reg_a.clone(), reg_b.clone());
+
+        reg_a.merge(&snapshot_b);
+        reg_b.merge(&snapshot_a);
+        assert_eq!(reg_a.value(), "beta");
+        assert_eq!(reg_b.value(), "beta", "Replicas agree on a tie whatever the merge order");
+
+        let mut reg = LWWRegister::new("beta".to_string(), tie);
+        reg.set("alpha".to_string(), tie);
+        assert_eq!(reg.value(), "beta", "set breaks ties the same way as merge");
+    }
 }
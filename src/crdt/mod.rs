@@ -17,10 +17,12 @@ use crate::types::PeerId;
 use serde::{Deserialize, Serialize};
 use std::time::{SystemTime, UNIX_EPOCH};
 
+pub mod authored_register;
 pub mod hlc;
 pub mod lww_register;
 pub mod or_set;
 
+pub use authored_register::AuthoredRegister;
 pub use hlc::HybridLogicalClock;
 pub use lww_register::LWWRegister;
 pub use or_set::ORSet;
@@ -25,12 +25,15 @@ use uuid::Uuid;
 pub struct ORSet<T: Eq + Hash + Clone> {
     /// Maps elements to their unique add tags
     elements: HashMap<T, HashSet<Uuid>>,
+    /// Add tags that have been removed; they never come back in a merge
+    removed: HashSet<Uuid>,
 }
 
 impl<T: Eq + Hash + Clone> ORSet<T> {
     pub fn new() -> Self {
         Self {
             elements: HashMap::new(),
+            removed: HashSet::new(),
         }
     }
 
@@ -44,13 +47,25 @@ impl<T: Eq + Hash + Clone> ORSet<T> {
         tag
     }
 
-    /// Remove an element by removing all its tags
+    /// Remove an element by removing all its observed tags
+    ///
+    /// Tags added concurrently elsewhere haven't been observed, so they survive
+    /// the next merge.
     pub fn remove(&mut self, element: &T) {
-        self.elements.remove(element);
+        let observed: Vec<Uuid> = self
+            .elements
+            .get(element)
+            .map(|tags| tags.iter().copied().collect())
+            .unwrap_or_default();
+
+        for tag in observed {
+            self.remove_tag(element, tag);
+        }
     }
 
     /// Remove an element with a specific tag (for precise removal in merges)
     pub fn remove_tag(&mut self, element: &T, tag: Uuid) {
+        self.removed.insert(tag);
         if let Some(tags) = self.elements.get_mut(element) {
             tags.remove(&tag);
             if tags.is_empty() {
@@ -81,12 +96,55 @@ impl<T: Eq + Hash + Clone> ORSet<T> {
         self.elements.get(element)
     }
 
+    /// Every element with its add tags, including elements whose tags were all removed
+    pub fn entries(&self) -> impl Iterator<Item = (&T, &HashSet<Uuid>)> {
+        self.elements.iter()
+    }
+
+    /// Add tags removed so far, which no merge brings back
+    pub fn removed_tags(&self) -> &HashSet<Uuid> {
+        &self.removed
+    }
+
+    /// Rebuild a set from its entries and removed tags, as taken apart by
+    /// `entries` and `removed_tags`
+    pub fn from_parts(elements: HashMap<T, HashSet<Uuid>>, removed: HashSet<Uuid>) -> Self {
+        Self { elements, removed }
+    }
+
     /// Merge with another OR-Set
+    ///
+    /// Adds from either side survive unless their tag was removed on either
+    /// side, so observed removes stick and concurrent adds win.
     pub fn merge(&mut self, other: &ORSet<T>) {
         for (element, other_tags) in &other.elements {
-            let tags = self.elements.entry(element.clone()).or_insert_with(HashSet::new);
-            tags.extend(other_tags);
+            let new_tags: Vec<Uuid> = other_tags
+                .iter()
+                .filter(|tag| !self.removed.contains(tag))
+                .copied()
+                .collect();
+            if !new_tags.is_empty() {
+                self.elements
+                    .entry(element.clone())
+                    .or_insert_with(HashSet::new)
+                    .extend(new_tags);
+            }
+        }
+
+        // Apply the removes the other replica observed
+        let removed_here: Vec<(T, Uuid)> = self
+            .elements
+            .iter()
+            .flat_map(|(element, tags)| {
+                tags.iter()
+                    .filter(|tag| other.removed.contains(tag))
+                    .map(move |tag| (element.clone(), *tag))
+            })
+            .collect();
+        for (element, tag) in removed_here {
+            self.remove_tag(&element, tag);
         }
+        self.removed.extend(&other.removed);
     }
 
     /// Get the number of elements
@@ -155,4 +213,55 @@ mod tests {
 
         assert!(set1.contains(&"alice"), "Concurrent add should win over remove");
     }
+
+    #[test]
+    fn test_or_set_removed_element_not_resurrected() {
+        let mut set1 = ORSet::new();
+        set1.add("alice");
+        let stale = set1.clone();
+
+        set1.remove(&"alice");
+        set1.merge(&stale);
+        assert!(!set1.contains(&"alice"), "Observed remove should stick");
+
+        // The stale replica learns about the remove too
+        let mut set2 = stale.clone();
+        set2.merge(&set1);
+        assert!(!set2.contains(&"alice"));
+    }
+
+    #[test]
+    fn test_or_set_three_replica_convergence() {
+        let mut a = ORSet::new();
+        a.add("alice");
+        a.add("bob");
+
+        let mut b = a.clone();
+        let mut c = a.clone();
+
+        // Concurrently: b removes alice and bob, c re-adds alice, a adds carol
+        b.remove(&"alice");
+        b.remove(&"bob");
+        c.add("alice");
+        a.add("carol");
+
+        // Merge in different orders on each replica
+        a.merge(&b);
+        a.merge(&c);
+
+        c.merge(&a);
+
+        b.merge(&c);
+
+        // Stale state merged again must not bring anything back
+        let snapshot = b.clone();
+        c.merge(&snapshot);
+        a.merge(&snapshot);
+
+        for replica in [&a, &b, &c] {
+            let mut elements = replica.elements();
+            elements.sort();
+            assert_eq!(elements, vec!["alice", "carol"]);
+        }
+    }
 }
@@ -13,23 +13,251 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use crate::crdt::Timestamp;
 use crate::dag::MessageDAG;
 use crate::network::NetworkCommand;
 use crate::storage::Storage;
 use crate::types::{ChannelId, MessageId};
 use anyhow::Result;
-use std::collections::HashSet;
+use libp2p::PeerId;
+use rand::seq::IndexedRandom;
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
+
+/// Default cap on message requests in flight at once
+pub const DEFAULT_MAX_OUTSTANDING_REQUESTS: usize = 4;
+
+/// Default number of peers asked for their inventory per anti-entropy round
+pub const DEFAULT_ANTI_ENTROPY_FANOUT: usize = 3;
+
+/// Most message IDs asked for in one request
+///
+/// Large gaps are split so they spread over the in-flight slots instead of
+/// waiting on one peer to read and send everything.
+const MAX_IDS_PER_REQUEST: usize = 100;
+
+/// Most messages sent back in one response, keeping each publish well
+/// under gossipsub's transmit size limit
+const MAX_MESSAGES_PER_RESPONSE: usize = 50;
+
+/// How long an unanswered message request holds its slot before it is dropped
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// How long a catch-up waits for peers' inventories before calling it done
+const CATCH_UP_SETTLE: Duration = Duration::from_secs(5);
+
+/// A catch-up still missing messages gives up after this long
+const CATCH_UP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// How long a sync preview waits for peers' inventories
+const PREVIEW_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// A message request sent to peers that has not been fully answered yet
+struct OutstandingRequest {
+    remaining: HashSet<MessageId>,
+    sent_at: Instant,
+}
+
+/// A resync of one channel the user asked for
+struct CatchUp {
+    /// Everything requested for the channel since the catch-up started
+    requested: HashSet<MessageId>,
+    started_at: Instant,
+}
+
+/// A "what would sync" check of one channel the user asked for
+struct SyncPreview {
+    /// Our message IDs in the channel when the preview started
+    ours: HashSet<MessageId>,
+    /// Peers asked that haven't answered yet
+    waiting: HashSet<PeerId>,
+    diffs: Vec<(PeerId, SyncDiff)>,
+    started_at: Instant,
+}
+
+/// How one peer's copy of a channel differs from ours
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SyncDiff {
+    /// Messages they have that we don't
+    pub we_lack: usize,
+    /// Messages we have that they don't
+    pub they_lack: usize,
+}
+
+/// What a sync preview found
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SyncPreviewReport {
+    /// Per peer that answered, in the order they answered
+    pub diffs: Vec<(PeerId, SyncDiff)>,
+    /// Peers that didn't answer in time
+    pub unanswered: usize,
+}
+
+/// How far a catch-up has got
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CatchUpProgress {
+    pub received: usize,
+    pub requested: usize,
+}
+
+impl CatchUpProgress {
+    pub fn missing(&self) -> usize {
+        self.requested - self.received
+    }
+}
 
 /// Gossip protocol manager for anti-entropy and message synchronization
 pub struct GossipManager {
     network_tx: mpsc::UnboundedSender<NetworkCommand>,
+    /// Maximum number of message requests in flight at once
+    max_outstanding_requests: usize,
+    /// Peers asked for their inventory per anti-entropy round
+    fanout: usize,
+    outstanding: Vec<OutstandingRequest>,
+    /// Requests waiting for an in-flight slot
+    queued: VecDeque<(ChannelId, Vec<MessageId>)>,
+    /// Messages known to exist per channel that haven't arrived yet
+    /// (kept after a request times out, until the message shows up)
+    wanted: HashMap<ChannelId, HashSet<MessageId>>,
+    catch_ups: HashMap<ChannelId, CatchUp>,
+    previews: HashMap<ChannelId, SyncPreview>,
 }
 
 impl GossipManager {
-    pub fn new(network_tx: mpsc::UnboundedSender<NetworkCommand>) -> Self {
-        Self { network_tx }
+    pub fn new(
+        network_tx: mpsc::UnboundedSender<NetworkCommand>,
+        max_outstanding_requests: usize,
+        fanout: usize,
+    ) -> Self {
+        Self {
+            network_tx,
+            max_outstanding_requests,
+            fanout: fanout.max(1),
+            outstanding: Vec::new(),
+            queued: VecDeque::new(),
+            wanted: HashMap::new(),
+            catch_ups: HashMap::new(),
+            previews: HashMap::new(),
+        }
+    }
+
+    /// Request messages from peers, queueing the request if too many are in flight
+    ///
+    /// IDs already requested or queued are skipped, unless their request
+    /// went unanswered for `REQUEST_TIMEOUT`.
+    pub fn request_messages(
+        &mut self,
+        channel_id: ChannelId,
+        message_ids: Vec<MessageId>,
+    ) -> Result<()> {
+        self.drop_unanswered_requests();
+        self.wanted
+            .entry(channel_id)
+            .or_default()
+            .extend(message_ids.iter().copied());
+        if let Some(catch_up) = self.catch_ups.get_mut(&channel_id) {
+            catch_up.requested.extend(message_ids.iter().copied());
+        }
+
+        let message_ids: Vec<MessageId> = message_ids
+            .into_iter()
+            .filter(|id| !self.is_requested(id))
+            .collect();
+
+        for batch in message_ids.chunks(MAX_IDS_PER_REQUEST) {
+            self.queued.push_back((channel_id, batch.to_vec()));
+        }
+
+        self.dispatch_queued()
+    }
+
+    /// Record that messages arrived, completing any requests they answer
+    pub fn handle_messages_received(&mut self, message_ids: &[MessageId]) -> Result<()> {
+        for request in &mut self.outstanding {
+            for message_id in message_ids {
+                request.remaining.remove(message_id);
+            }
+        }
+        self.outstanding.retain(|request| !request.remaining.is_empty());
+
+        for wanted in self.wanted.values_mut() {
+            for message_id in message_ids {
+                wanted.remove(message_id);
+            }
+        }
+        self.wanted.retain(|_, wanted| !wanted.is_empty());
+
+        self.dispatch_queued()
+    }
+
+    /// Check whether every message peers have told us about for a channel has arrived
+    pub fn is_channel_synced(&self, channel_id: &ChannelId) -> bool {
+        !self.wanted.contains_key(channel_id)
+    }
+
+    /// Drop everything wanted or running for a deleted channel
+    ///
+    /// Requests already in flight are left to finish or time out; what they
+    /// bring back is ignored along with the channel.
+    pub fn forget_channel(&mut self, channel_id: &ChannelId) {
+        self.wanted.remove(channel_id);
+        self.queued.retain(|(queued_channel, _)| queued_channel != channel_id);
+        self.catch_ups.remove(channel_id);
+        self.previews.remove(channel_id);
+    }
+
+    /// Number of message requests currently in flight
+    pub fn outstanding_requests(&self) -> usize {
+        self.outstanding.len()
+    }
+
+    /// Check whether a message is already in flight or queued
+    fn is_requested(&self, message_id: &MessageId) -> bool {
+        self.outstanding
+            .iter()
+            .any(|request| request.remaining.contains(message_id))
+            || self
+                .queued
+                .iter()
+                .any(|(_, message_ids)| message_ids.contains(message_id))
+    }
+
+    /// Free the slots of requests that timed out, so their IDs can be asked for again
+    fn drop_unanswered_requests(&mut self) {
+        let before = self.outstanding.len();
+        self.outstanding
+            .retain(|request| request.sent_at.elapsed() < REQUEST_TIMEOUT);
+        if self.outstanding.len() < before {
+            warn!(
+                "Dropped {} message requests that went unanswered",
+                before - self.outstanding.len()
+            );
+        }
+    }
+
+    /// Send queued requests while there are free in-flight slots
+    fn dispatch_queued(&mut self) -> Result<()> {
+        self.drop_unanswered_requests();
+
+        while self.outstanding.len() < self.max_outstanding_requests {
+            let Some((channel_id, message_ids)) = self.queued.pop_front() else {
+                break;
+            };
+
+            self.network_tx.send(NetworkCommand::RequestMessages {
+                channel_id,
+                message_ids: message_ids.clone(),
+            })?;
+
+            self.outstanding.push(OutstandingRequest {
+                remaining: message_ids.into_iter().collect(),
+                sent_at: Instant::now(),
+            });
+        }
+
+        Ok(())
     }
 
     /// Request inventory from peers for a channel
@@ -40,6 +268,199 @@ impl GossipManager {
         Ok(())
     }
 
+    /// Resync a channel now instead of waiting for anti-entropy
+    ///
+    /// Asks every connected peer for the channel's inventory, and requests
+    /// again the missing parents and anything wanted from an earlier
+    /// inventory, including requests that timed out. Follow it with
+    /// `catch_up_progress` and `finish_catch_ups`.
+    pub fn catch_up(&mut self, channel_id: ChannelId, dag: &MessageDAG) -> Result<()> {
+        info!("Catching up on channel {:?}", channel_id);
+        self.catch_ups.insert(
+            channel_id,
+            CatchUp {
+                requested: HashSet::new(),
+                started_at: Instant::now(),
+            },
+        );
+        self.request_inventory(channel_id)?;
+
+        let mut missing = dag.find_missing_messages_for_channel(&channel_id);
+        if let Some(wanted) = self.wanted.get(&channel_id) {
+            missing.extend(wanted.iter().copied());
+        }
+        if !missing.is_empty() {
+            self.request_messages(channel_id, missing.into_iter().collect())?;
+        }
+
+        Ok(())
+    }
+
+    /// Progress of a channel's catch-up, if one is running
+    pub fn catch_up_progress(&self, channel_id: &ChannelId) -> Option<CatchUpProgress> {
+        let catch_up = self.catch_ups.get(channel_id)?;
+        let still_wanted = self
+            .wanted
+            .get(channel_id)
+            .map(|wanted| catch_up.requested.intersection(wanted).count())
+            .unwrap_or(0);
+        Some(CatchUpProgress {
+            received: catch_up.requested.len() - still_wanted,
+            requested: catch_up.requested.len(),
+        })
+    }
+
+    /// Take the catch-ups that are over, with how they ended
+    ///
+    /// A catch-up is over once peers have had time to answer and nothing it
+    /// requested is still missing, or when it times out.
+    pub fn finish_catch_ups(&mut self) -> Vec<(ChannelId, CatchUpProgress)> {
+        let finished: Vec<(ChannelId, CatchUpProgress)> = self
+            .catch_ups
+            .iter()
+            .filter_map(|(channel_id, catch_up)| {
+                let progress = self.catch_up_progress(channel_id)?;
+                let elapsed = catch_up.started_at.elapsed();
+                let over = (elapsed >= CATCH_UP_SETTLE && progress.missing() == 0) || elapsed >= CATCH_UP_TIMEOUT;
+                over.then_some((*channel_id, progress))
+            })
+            .collect();
+
+        for (channel_id, _) in &finished {
+            self.catch_ups.remove(channel_id);
+        }
+        finished
+    }
+
+    /// Ask every connected peer for a channel's inventory, only to compare
+    ///
+    /// `ours` is what we'd advertise for the channel. Pass the answers to
+    /// `preview_inventory` instead of `handle_inventory`, so nothing gets
+    /// requested, and collect the result with `finish_sync_previews`.
+    pub fn preview_sync(
+        &mut self,
+        channel_id: ChannelId,
+        ours: HashSet<MessageId>,
+        connected: &[PeerId],
+    ) -> Result<()> {
+        info!("Previewing sync of channel {:?} with {} peers", channel_id, connected.len());
+        self.previews.insert(
+            channel_id,
+            SyncPreview {
+                ours,
+                waiting: connected.iter().copied().collect(),
+                diffs: Vec::new(),
+                started_at: Instant::now(),
+            },
+        );
+        self.network_tx.send(NetworkCommand::RequestInventoryFrom {
+            channel_id,
+            peers: connected.to_vec(),
+        })?;
+        Ok(())
+    }
+
+    /// Record a peer's inventory if a sync preview is waiting for it
+    ///
+    /// Returns false if no preview wanted it; it should then go to
+    /// `handle_inventory` as usual.
+    pub fn preview_inventory(
+        &mut self,
+        channel_id: ChannelId,
+        from_peer: PeerId,
+        their_message_ids: &HashSet<MessageId>,
+    ) -> bool {
+        let Some(preview) = self.previews.get_mut(&channel_id) else {
+            return false;
+        };
+        if !preview.waiting.remove(&from_peer) {
+            return false;
+        }
+
+        let diff = SyncDiff {
+            we_lack: their_message_ids.difference(&preview.ours).count(),
+            they_lack: preview.ours.difference(their_message_ids).count(),
+        };
+        debug!("Sync preview: {} differs by {:?} on channel {:?}", from_peer, diff, channel_id);
+        preview.diffs.push((from_peer, diff));
+        true
+    }
+
+    /// Take the sync previews that are over: every peer answered, or the
+    /// rest ran out of time
+    pub fn finish_sync_previews(&mut self) -> Vec<(ChannelId, SyncPreviewReport)> {
+        let finished: Vec<ChannelId> = self
+            .previews
+            .iter()
+            .filter(|(_, preview)| preview.waiting.is_empty() || preview.started_at.elapsed() >= PREVIEW_TIMEOUT)
+            .map(|(channel_id, _)| *channel_id)
+            .collect();
+
+        finished
+            .into_iter()
+            .filter_map(|channel_id| {
+                let preview = self.previews.remove(&channel_id)?;
+                Some((
+                    channel_id,
+                    SyncPreviewReport {
+                        diffs: preview.diffs,
+                        unanswered: preview.waiting.len(),
+                    },
+                ))
+            })
+            .collect()
+    }
+
+    /// Ask a random `fanout` of the connected peers for their inventory of
+    /// each channel, returning the peers asked
+    ///
+    /// `newcomer`, a peer that just connected, is always among them, since it
+    /// is the one most likely to have messages we haven't seen. Asking only
+    /// some peers keeps a large mesh from answering every round N² times;
+    /// anything the others have still reaches us in later rounds, as peers
+    /// connect and are picked, or through the peers we did ask once they
+    /// have synced with them. A lower fanout means less traffic but slower
+    /// convergence.
+    pub fn anti_entropy_round(
+        &self,
+        channel_ids: &[ChannelId],
+        connected: &[PeerId],
+        newcomer: Option<PeerId>,
+    ) -> Result<Vec<PeerId>> {
+        let peers = self.select_peers(connected, newcomer);
+        if peers.is_empty() {
+            return Ok(peers);
+        }
+
+        debug!(
+            "Anti-entropy round: asking {} of {} peers about {} channels",
+            peers.len(),
+            connected.len(),
+            channel_ids.len()
+        );
+        for &channel_id in channel_ids {
+            self.network_tx.send(NetworkCommand::RequestInventoryFrom {
+                channel_id,
+                peers: peers.clone(),
+            })?;
+        }
+
+        Ok(peers)
+    }
+
+    /// Pick up to `fanout` distinct peers at random, starting with `newcomer`
+    fn select_peers(&self, connected: &[PeerId], newcomer: Option<PeerId>) -> Vec<PeerId> {
+        let mut peers: Vec<PeerId> = newcomer.into_iter().collect();
+        let others: Vec<PeerId> = connected
+            .iter()
+            .filter(|peer| Some(**peer) != newcomer)
+            .copied()
+            .collect();
+        let wanted = self.fanout.saturating_sub(peers.len());
+        peers.extend(others.choose_multiple(&mut rand::rng(), wanted).copied());
+        peers
+    }
+
     /// Send our inventory for a channel
     pub async fn send_inventory(
         &self,
@@ -48,6 +469,7 @@ impl GossipManager {
     ) -> Result<()> {
         let message_ids = storage.get_channel_message_ids(channel_id).await?;
         let message_id_set: HashSet<MessageId> = message_ids.into_iter().collect();
+        let edits = storage.get_channel_edit_times(channel_id).await?;
 
         debug!(
             "Sending inventory for channel {:?} with {} messages",
@@ -58,25 +480,36 @@ impl GossipManager {
         self.network_tx.send(NetworkCommand::BroadcastInventory {
             channel_id,
             message_ids: message_id_set,
+            edits,
         })?;
 
         Ok(())
     }
 
     /// Handle received inventory: compare with our DAG and request missing messages
+    ///
+    /// `our_edits` is when each of our edited messages in the channel was
+    /// last edited; messages they have a later edit of are requested again.
     pub fn handle_inventory(
-        &self,
+        &mut self,
         channel_id: ChannelId,
         their_message_ids: HashSet<MessageId>,
+        their_edits: &HashMap<MessageId, Timestamp>,
+        our_edits: &HashMap<MessageId, Timestamp>,
         dag: &MessageDAG,
     ) -> Result<()> {
         let our_message_ids = dag.all_message_ids();
 
-        // Find messages they have that we don't
-        let missing: Vec<MessageId> = their_message_ids
+        // Find messages they have that we don't, or have an older edit of
+        let mut missing: Vec<MessageId> = their_message_ids
             .difference(&our_message_ids)
             .copied()
             .collect();
+        missing.extend(their_edits.iter().filter_map(|(message_id, edited_at)| {
+            let behind = our_message_ids.contains(message_id)
+                && our_edits.get(message_id).is_none_or(|ours| ours < edited_at);
+            behind.then_some(*message_id)
+        }));
 
         if !missing.is_empty() {
             info!(
@@ -85,10 +518,7 @@ impl GossipManager {
                 channel_id
             );
 
-            self.network_tx.send(NetworkCommand::RequestMessages {
-                channel_id,
-                message_ids: missing,
-            })?;
+            self.request_messages(channel_id, missing)?;
         } else {
             debug!(
                 "No missing messages for channel {:?}",
@@ -121,22 +551,28 @@ impl GossipManager {
                 channel_id
             );
 
-            self.network_tx.send(NetworkCommand::RespondWithMessages {
-                channel_id,
-                messages,
-            })?;
+            for batch in messages.chunks(MAX_MESSAGES_PER_RESPONSE) {
+                self.network_tx.send(NetworkCommand::RespondWithMessages {
+                    channel_id,
+                    messages: batch.to_vec(),
+                })?;
+            }
         }
 
         Ok(())
     }
 
-    /// Detect missing messages in DAG and request them
+    /// Request a channel's missing parents right away, rather than waiting
+    /// for the next anti-entropy round to turn them up
+    ///
+    /// Parents already in flight aren't asked for again, so this is safe to
+    /// call for every message that arrives ahead of its parents.
     pub fn detect_and_request_missing(
-        &self,
+        &mut self,
         channel_id: ChannelId,
         dag: &MessageDAG,
     ) -> Result<()> {
-        let missing_ids: Vec<MessageId> = dag.find_missing_messages().into_iter().collect();
+        let missing_ids: Vec<MessageId> = dag.find_missing_messages_for_channel(&channel_id).into_iter().collect();
 
         if !missing_ids.is_empty() {
             info!(
@@ -145,10 +581,7 @@ impl GossipManager {
                 channel_id
             );
 
-            self.network_tx.send(NetworkCommand::RequestMessages {
-                channel_id,
-                message_ids: missing_ids,
-            })?;
+            self.request_messages(channel_id, missing_ids)?;
         }
 
         Ok(())
@@ -158,11 +591,282 @@ impl GossipManager {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::dag::IngestMode;
+    use crate::types::{Message, MessageContent, VectorClock};
 
     #[tokio::test]
     async fn test_gossip_manager_creation() {
         let (tx, _rx) = mpsc::unbounded_channel();
-        let _manager = GossipManager::new(tx);
+        let _manager = GossipManager::new(tx, DEFAULT_MAX_OUTSTANDING_REQUESTS, DEFAULT_ANTI_ENTROPY_FANOUT);
         // Just test that it can be created
     }
+
+    #[tokio::test]
+    async fn test_outstanding_requests_capped() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut manager = GossipManager::new(tx, 2, DEFAULT_ANTI_ENTROPY_FANOUT);
+        let channel_id = ChannelId::new();
+
+        let batches: Vec<Vec<MessageId>> = (0..3).map(|_| vec![MessageId::new()]).collect();
+        for batch in &batches {
+            manager.request_messages(channel_id, batch.clone()).unwrap();
+        }
+
+        // Only two requests go out; the third waits for a free slot
+        assert!(rx.try_recv().is_ok());
+        assert!(rx.try_recv().is_ok());
+        assert!(rx.try_recv().is_err());
+        assert_eq!(manager.outstanding_requests(), 2);
+
+        // Re-requesting an in-flight message doesn't send a duplicate
+        manager.request_messages(channel_id, batches[0].clone()).unwrap();
+        assert!(rx.try_recv().is_err());
+
+        // Completing the first request releases the queued one
+        manager.handle_messages_received(&batches[0]).unwrap();
+        match rx.try_recv() {
+            Ok(NetworkCommand::RequestMessages { message_ids, .. }) => {
+                assert_eq!(message_ids, batches[2]);
+            }
+            other => panic!("Expected queued request to be sent, got {:?}", other),
+        }
+        assert_eq!(manager.outstanding_requests(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_large_requests_split_into_batches() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut manager = GossipManager::new(tx, DEFAULT_MAX_OUTSTANDING_REQUESTS, DEFAULT_ANTI_ENTROPY_FANOUT);
+        let channel_id = ChannelId::new();
+
+        let message_ids: Vec<MessageId> = (0..MAX_IDS_PER_REQUEST * 2 + 1).map(|_| MessageId::new()).collect();
+        manager.request_messages(channel_id, message_ids.clone()).unwrap();
+
+        let mut sent = Vec::new();
+        while let Ok(NetworkCommand::RequestMessages { message_ids, .. }) = rx.try_recv() {
+            assert!(message_ids.len() <= MAX_IDS_PER_REQUEST);
+            sent.extend(message_ids);
+        }
+        assert_eq!(sent, message_ids);
+        assert_eq!(manager.outstanding_requests(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_channel_synced_once_wanted_messages_arrive() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut manager = GossipManager::new(tx, DEFAULT_MAX_OUTSTANDING_REQUESTS, DEFAULT_ANTI_ENTROPY_FANOUT);
+        let syncing = ChannelId::new();
+        let idle = ChannelId::new();
+
+        let wanted = vec![MessageId::new(), MessageId::new()];
+        manager.request_messages(syncing, wanted.clone()).unwrap();
+
+        assert!(!manager.is_channel_synced(&syncing));
+        assert!(manager.is_channel_synced(&idle));
+
+        manager.handle_messages_received(&wanted[..1]).unwrap();
+        assert!(!manager.is_channel_synced(&syncing));
+
+        manager.handle_messages_received(&wanted[1..]).unwrap();
+        assert!(manager.is_channel_synced(&syncing));
+    }
+
+    #[tokio::test]
+    async fn test_forgotten_channel_wants_nothing() {
+        let (tx, _rx) = mpsc::unbounded_channel();
+        let mut manager = GossipManager::new(tx, 1, DEFAULT_ANTI_ENTROPY_FANOUT);
+        let (deleted, kept) = (ChannelId::new(), ChannelId::new());
+        manager.request_messages(deleted, vec![MessageId::new()]).unwrap();
+        manager.request_messages(deleted, vec![MessageId::new()]).unwrap();
+        manager.request_messages(kept, vec![MessageId::new()]).unwrap();
+
+        manager.forget_channel(&deleted);
+        assert!(manager.is_channel_synced(&deleted));
+        assert!(!manager.is_channel_synced(&kept));
+        assert!(manager.queued.iter().all(|(channel_id, _)| *channel_id == kept));
+    }
+
+    #[tokio::test]
+    async fn test_catch_up_re_requests_and_reports_progress() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut manager = GossipManager::new(tx, DEFAULT_MAX_OUTSTANDING_REQUESTS, DEFAULT_ANTI_ENTROPY_FANOUT);
+        let channel_id = ChannelId::new();
+
+        // Known from an earlier inventory, but the request went unanswered
+        let stuck = MessageId::new();
+        manager.wanted.entry(channel_id).or_default().insert(stuck);
+
+        manager.catch_up(channel_id, &MessageDAG::new()).unwrap();
+        assert!(matches!(rx.try_recv(), Ok(NetworkCommand::RequestInventory { .. })));
+        match rx.try_recv() {
+            Ok(NetworkCommand::RequestMessages { message_ids, .. }) => assert_eq!(message_ids, vec![stuck]),
+            other => panic!("Expected the stuck message to be requested again, got {:?}", other),
+        }
+
+        // A peer's inventory turns up another missing message
+        let fresh = MessageId::new();
+        manager.request_messages(channel_id, vec![fresh]).unwrap();
+        manager.handle_messages_received(&[stuck]).unwrap();
+        assert_eq!(
+            manager.catch_up_progress(&channel_id),
+            Some(CatchUpProgress { received: 1, requested: 2 })
+        );
+
+        // Not over while peers may still answer or messages are missing
+        assert!(manager.finish_catch_ups().is_empty());
+
+        manager.handle_messages_received(&[fresh]).unwrap();
+        manager.catch_ups.get_mut(&channel_id).unwrap().started_at -= CATCH_UP_SETTLE;
+        assert_eq!(
+            manager.finish_catch_ups(),
+            vec![(channel_id, CatchUpProgress { received: 2, requested: 2 })]
+        );
+        assert_eq!(manager.catch_up_progress(&channel_id), None);
+    }
+
+    #[test]
+    fn test_anti_entropy_asks_only_fanout_peers() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let manager = GossipManager::new(tx, DEFAULT_MAX_OUTSTANDING_REQUESTS, 3);
+        let channels = [ChannelId::new(), ChannelId::new()];
+        let connected: Vec<PeerId> = (0..10).map(|_| PeerId::random()).collect();
+        let newcomer = connected[7];
+
+        for _ in 0..20 {
+            let asked = manager.anti_entropy_round(&channels, &connected, Some(newcomer)).unwrap();
+            assert_eq!(asked.len(), 3);
+            assert_eq!(asked[0], newcomer);
+            assert_eq!(asked.iter().collect::<HashSet<_>>().len(), 3);
+            assert!(asked.iter().all(|peer| connected.contains(peer)));
+
+            // One directed request per channel, to exactly those peers
+            for channel_id in channels {
+                match rx.try_recv() {
+                    Ok(NetworkCommand::RequestInventoryFrom { channel_id: requested, peers }) => {
+                        assert_eq!(requested, channel_id);
+                        assert_eq!(peers, asked);
+                    }
+                    other => panic!("Expected a directed inventory request, got {:?}", other),
+                }
+            }
+            assert!(rx.try_recv().is_err());
+        }
+
+        // Fewer peers than the fanout: ask them all
+        let asked = manager.anti_entropy_round(&channels, &connected[..2], None).unwrap();
+        assert_eq!(asked.len(), 2);
+    }
+
+    #[test]
+    fn test_sync_preview_reports_without_requesting() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut manager = GossipManager::new(tx, DEFAULT_MAX_OUTSTANDING_REQUESTS, DEFAULT_ANTI_ENTROPY_FANOUT);
+        let channel_id = ChannelId::new();
+        let (ahead, behind, silent) = (PeerId::random(), PeerId::random(), PeerId::random());
+
+        let shared = MessageId::new();
+        let only_ours = MessageId::new();
+        let ours = HashSet::from([shared, only_ours]);
+        manager.preview_sync(channel_id, ours, &[ahead, behind, silent]).unwrap();
+        assert!(matches!(
+            rx.try_recv(),
+            Ok(NetworkCommand::RequestInventoryFrom { peers, .. }) if peers == vec![ahead, behind, silent]
+        ));
+
+        let ahead_ids = HashSet::from([shared, only_ours, MessageId::new(), MessageId::new()]);
+        assert!(manager.preview_inventory(channel_id, ahead, &ahead_ids));
+        assert!(manager.preview_inventory(channel_id, behind, &HashSet::from([shared])));
+        // A second answer, or one for another channel, is ordinary anti-entropy
+        assert!(!manager.preview_inventory(channel_id, ahead, &ahead_ids));
+        assert!(!manager.preview_inventory(ChannelId::new(), silent, &ahead_ids));
+
+        // Report only: nothing was requested
+        assert!(rx.try_recv().is_err());
+        assert!(manager.is_channel_synced(&channel_id));
+
+        // Still waiting on the silent peer until the preview times out
+        assert!(manager.finish_sync_previews().is_empty());
+        manager.previews.get_mut(&channel_id).unwrap().started_at -= PREVIEW_TIMEOUT;
+        assert_eq!(
+            manager.finish_sync_previews(),
+            vec![(
+                channel_id,
+                SyncPreviewReport {
+                    diffs: vec![
+                        (ahead, SyncDiff { we_lack: 2, they_lack: 0 }),
+                        (behind, SyncDiff { we_lack: 0, they_lack: 1 }),
+                    ],
+                    unanswered: 1,
+                }
+            )]
+        );
+        assert!(manager.previews.is_empty());
+    }
+
+    #[test]
+    fn test_missing_parents_requested_once_in_flight() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut manager = GossipManager::new(tx, DEFAULT_MAX_OUTSTANDING_REQUESTS, DEFAULT_ANTI_ENTROPY_FANOUT);
+        let (channel_id, elsewhere) = (ChannelId::new(), ChannelId::new());
+        let author = crate::types::PeerId::new();
+        let orphan = |channel_id, parents| {
+            let mut message = Message::new(channel_id, author, MessageContent::new("hi"), VectorClock::new(), 2);
+            message.parent_hashes = parents;
+            message
+        };
+
+        let lost = vec![MessageId::new(), MessageId::new()];
+        let mut dag = MessageDAG::new();
+        dag.ingest(orphan(channel_id, lost.clone()), IngestMode::BufferOrphans).unwrap();
+        dag.ingest(orphan(elsewhere, vec![MessageId::new()]), IngestMode::BufferOrphans).unwrap();
+
+        // Only this channel's gap, all of it
+        manager.detect_and_request_missing(channel_id, &dag).unwrap();
+        match rx.try_recv() {
+            Ok(NetworkCommand::RequestMessages { channel_id: requested, message_ids }) => {
+                assert_eq!(requested, channel_id);
+                assert_eq!(message_ids.into_iter().collect::<HashSet<_>>(), lost.iter().copied().collect());
+            }
+            other => panic!("Expected the missing parents to be requested, got {:?}", other),
+        }
+
+        // Another message on the same gap doesn't ask again while in flight
+        dag.ingest(orphan(channel_id, lost[..1].to_vec()), IngestMode::BufferOrphans).unwrap();
+        manager.detect_and_request_missing(channel_id, &dag).unwrap();
+        assert!(rx.try_recv().is_err());
+
+        // An unanswered request stops holding the gap back once it times out
+        manager.outstanding[0].sent_at -= REQUEST_TIMEOUT;
+        manager.detect_and_request_missing(channel_id, &dag).unwrap();
+        assert!(matches!(rx.try_recv(), Ok(NetworkCommand::RequestMessages { .. })));
+        assert_eq!(manager.outstanding_requests(), 1);
+    }
+    #[test]
+    fn test_inventory_requests_messages_with_newer_edits() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut manager = GossipManager::new(tx, DEFAULT_MAX_OUTSTANDING_REQUESTS, DEFAULT_ANTI_ENTROPY_FANOUT);
+        let channel_id = ChannelId::new();
+        let author = crate::types::PeerId::new();
+        let mut dag = MessageDAG::new();
+        let message = Message::new(channel_id, author, MessageContent::new("hi"), VectorClock::new(), 1);
+        dag.ingest(message.clone(), IngestMode::BufferOrphans).unwrap();
+        let (older, newer) = (Timestamp::new(1, 0, author), Timestamp::new(2, 0, author));
+        let ids = HashSet::from([message.id]);
+
+        let theirs = HashMap::from([(message.id, newer)]);
+
+        // Same edit on both sides: nothing to fetch
+        manager.handle_inventory(channel_id, ids.clone(), &theirs, &theirs.clone(), &dag).unwrap();
+        assert!(rx.try_recv().is_err());
+
+        // Their edit is later than ours, or we have none: fetch it again
+        for ours in [HashMap::from([(message.id, older)]), HashMap::new()] {
+            manager.handle_inventory(channel_id, ids.clone(), &theirs, &ours, &dag).unwrap();
+            match rx.try_recv() {
+                Ok(NetworkCommand::RequestMessages { message_ids, .. }) => assert_eq!(message_ids, vec![message.id]),
+                other => panic!("Expected the edited message to be requested, got {:?}", other),
+            }
+            manager.handle_messages_received(&[message.id]).unwrap();
+        }
+    }
 }
@@ -18,6 +18,11 @@ pub mod gossip;
 use crate::types::{ChannelId, Message, MessageId};
 use std::collections::{HashMap, HashSet, VecDeque};
 
+/// Most orphans held per channel; past it the longest-waiting is dropped.
+/// Keeps a peer sending messages with made-up parents from growing the
+/// pool without bound
+pub const MAX_ORPHANS_PER_CHANNEL: usize = 1000;
+
 /// Message DAG (Directed Acyclic Graph) for causal ordering
 ///
 /// The DAG tracks the causal relationships between messages using parent hashes.
@@ -33,6 +38,35 @@ pub struct MessageDAG {
 
     /// Current heads (messages with no children) per channel
     heads: HashMap<ChannelId, HashSet<MessageId>>,
+
+    /// Topological order per channel, kept up to date as messages arrive
+    order: HashMap<ChannelId, Vec<MessageId>>,
+
+    /// Messages waiting for parents that haven't arrived yet
+    orphans: HashMap<MessageId, Message>,
+
+    /// Missing parent -> orphans waiting on it
+    waiting_on: HashMap<MessageId, HashSet<MessageId>>,
+
+    /// Each channel's orphans, longest-waiting first
+    orphan_arrivals: HashMap<ChannelId, VecDeque<MessageId>>,
+
+    /// Stored messages left out by `load_from_heads`, and their channels
+    ///
+    /// They count as present, so later messages attach on top of them and
+    /// they aren't requested again, but they stay out of the order until
+    /// `load_history` brings them in.
+    unloaded: HashMap<MessageId, ChannelId>,
+}
+
+/// How to treat a message whose parents haven't all arrived
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum IngestMode {
+    /// Reject it, for checking that a history is complete
+    Strict,
+    /// Keep it aside and add it once its parents arrive
+    #[default]
+    BufferOrphans,
 }
 
 impl MessageDAG {
@@ -41,30 +75,258 @@ impl MessageDAG {
             messages: HashMap::new(),
             children: HashMap::new(),
             heads: HashMap::new(),
+            order: HashMap::new(),
+            orphans: HashMap::new(),
+            waiting_on: HashMap::new(),
+            orphan_arrivals: HashMap::new(),
+            unloaded: HashMap::new(),
         }
     }
 
     /// Add a message to the DAG
-    pub fn add_message(&mut self, message: Message) -> Result<(), DagError> {
+    ///
+    /// A message whose parents haven't all arrived is rejected in
+    /// `IngestMode::Strict`, and held back in `IngestMode::BufferOrphans`
+    /// until they do. Returns the IDs that joined the DAG: the message itself
+    /// plus any orphans it completed, or nothing if it was buffered.
+    ///
+    /// A message that's already in the DAG is left alone and returns nothing;
+    /// the same message can arrive more than once (a resend, or a sync that
+    /// overlaps a broadcast) and re-linking it would make it a head again.
+    /// A message listing itself as a parent is rejected in either mode.
+    pub fn ingest(&mut self, message: Message, mode: IngestMode) -> Result<Vec<MessageId>, DagError> {
+        if self.is_known(&message.id) {
+            return Ok(Vec::new());
+        }
+        if message.parent_hashes.contains(&message.id) {
+            return Err(DagError::SelfParent { message_id: message.id });
+        }
+
+        if let Some(missing_parent) = self.first_missing_parent(&message) {
+            return match mode {
+                IngestMode::Strict => Err(DagError::MissingParent {
+                    message_id: message.id,
+                    missing_parent,
+                }),
+                IngestMode::BufferOrphans => {
+                    self.buffer_orphan(message);
+                    Ok(Vec::new())
+                }
+            };
+        }
+
         let message_id = message.id;
-        let channel_id = message.channel_id;
+        self.attach(message, true);
+        let mut added = vec![message_id];
+        added.extend(self.release_orphans(message_id, true));
+        Ok(added)
+    }
+
+    /// Add many messages at once, e.g. a channel's history from storage
+    ///
+    /// Messages may come in any order; parents later in the batch are
+    /// waited for. Each touched channel's heads and order are recomputed
+    /// once at the end instead of per message. In `IngestMode::Strict`, batch messages still missing a
+    /// parent afterwards are dropped and the first is reported. Messages
+    /// listing themselves as a parent are dropped in either mode, and the
+    /// first is reported.
+    pub fn ingest_all(&mut self, messages: Vec<Message>, mode: IngestMode) -> Result<Vec<MessageId>, DagError> {
+        let mut sorted_messages = messages;
+        sorted_messages.sort_by_key(|m| m.created_at_ms);
 
-        // Verify all parents exist (unless this is a root message)
+        let batch: Vec<(MessageId, ChannelId)> = sorted_messages.iter().map(|m| (m.id, m.channel_id)).collect();
+        let mut added = Vec::new();
+        let mut first_error = None;
+        for message in sorted_messages {
+            if self.is_known(&message.id) {
+                continue;
+            }
+            if message.parent_hashes.contains(&message.id) {
+                first_error.get_or_insert(DagError::SelfParent { message_id: message.id });
+                continue;
+            }
+            if self.first_missing_parent(&message).is_some() {
+                self.buffer_orphan(message);
+                continue;
+            }
+            let message_id = message.id;
+            self.attach(message, false);
+            added.push(message_id);
+            added.extend(self.release_orphans(message_id, false));
+        }
+
+        let channels: HashSet<ChannelId> = batch.iter().map(|(_, channel_id)| *channel_id).collect();
+        for channel_id in channels {
+            self.rebuild_heads(channel_id);
+            self.rebuild_order(channel_id);
+        }
+
+        if mode == IngestMode::Strict {
+            for (message_id, _) in &batch {
+                let missing_parent = self.orphans.get(message_id).and_then(|o| self.first_missing_parent(o));
+                if let Some(missing_parent) = missing_parent {
+                    self.take_orphan(message_id);
+                    first_error.get_or_insert(DagError::MissingParent {
+                        message_id: *message_id,
+                        missing_parent,
+                    });
+                }
+            }
+        }
+        if let Some(error) = first_error {
+            return Err(error);
+        }
+
+        Ok(added)
+    }
+
+    /// Load a channel from its saved heads and orphans and a window of its
+    /// recent messages
+    ///
+    /// `window` must include the heads and orphans themselves, and `stored`
+    /// lists every message ID stored for the channel. Stored messages outside
+    /// the window count as present without being loaded; `load_history`
+    /// pulls them in. Orphans never do, so their missing parents are still
+    /// asked for and nothing attaches to them before those arrive.
+    /// Returns false and leaves the DAG untouched when the heads don't match
+    /// the window, e.g. after a crash between storing a message and saving
+    /// the heads; the channel then needs a full rebuild with `ingest_all`.
+    pub fn load_from_heads(
+        &mut self,
+        channel_id: ChannelId,
+        heads: &[MessageId],
+        orphans: &[MessageId],
+        stored: &[MessageId],
+        window: Vec<Message>,
+    ) -> bool {
+        let saved: HashSet<MessageId> = heads.iter().copied().collect();
+        let orphans: HashSet<MessageId> = orphans.iter().copied().collect();
+        let consistent = window
+            .iter()
+            .all(|m| m.channel_id == channel_id && !m.parent_hashes.contains(&m.id));
+        if !consistent || heads_within(window.iter().filter(|m| !orphans.contains(&m.id))) != saved {
+            return false;
+        }
+
+        let loaded: HashSet<MessageId> = window.iter().map(|m| m.id).collect();
+        for message_id in stored {
+            if !loaded.contains(message_id) && !orphans.contains(message_id) {
+                self.unloaded.insert(*message_id, channel_id);
+            }
+        }
+        // Self-parented messages were ruled out above, so this can't fail
+        let _ = self.ingest_all(window, IngestMode::BufferOrphans);
+        true
+    }
+
+    /// Add stored messages that `load_from_heads` left out
+    ///
+    /// Messages already loaded are skipped, so a channel's whole history can
+    /// be passed in. The heads stay as they are: everything left out is an
+    /// ancestor of them.
+    pub fn load_history(&mut self, messages: Vec<Message>) -> Result<Vec<MessageId>, DagError> {
+        for message in &messages {
+            self.unloaded.remove(&message.id);
+        }
+        self.ingest_all(messages, IngestMode::BufferOrphans)
+    }
+
+    /// Whether some of a channel's stored history hasn't been loaded yet
+    pub fn has_unloaded(&self, channel_id: &ChannelId) -> bool {
+        self.unloaded.values().any(|c| c == channel_id)
+    }
+
+    /// Whether a message is in the DAG or stored but not loaded yet
+    fn is_known(&self, message_id: &MessageId) -> bool {
+        self.messages.contains_key(message_id) || self.unloaded.contains_key(message_id)
+    }
+
+    fn first_missing_parent(&self, message: &Message) -> Option<MessageId> {
+        message
+            .parent_hashes
+            .iter()
+            .find(|parent_id| !self.is_known(parent_id))
+            .copied()
+    }
+
+    /// Hold a message back until all its parents are in the DAG
+    ///
+    /// A channel already holding `MAX_ORPHANS_PER_CHANNEL` drops its
+    /// longest-waiting orphan to make room.
+    fn buffer_orphan(&mut self, message: Message) {
+        if self.orphans.contains_key(&message.id) {
+            return;
+        }
         for parent_id in &message.parent_hashes {
-            if !self.messages.contains_key(parent_id) {
-                return Err(DagError::MissingParent {
-                    message_id,
-                    missing_parent: *parent_id,
-                });
+            if !self.is_known(parent_id) {
+                self.waiting_on.entry(*parent_id).or_default().insert(message.id);
             }
         }
+        let arrivals = self.orphan_arrivals.entry(message.channel_id).or_default();
+        arrivals.push_back(message.id);
+        let evicted = if arrivals.len() > MAX_ORPHANS_PER_CHANNEL { arrivals.front().copied() } else { None };
+        self.orphans.insert(message.id, message);
+
+        if let Some(evicted) = evicted {
+            tracing::warn!("Orphan pool full, dropping message {:?} still missing parents", evicted);
+            self.take_orphan(&evicted);
+        }
+    }
 
-        // Remove parents from heads (they now have a child)
-        if let Some(channel_heads) = self.heads.get_mut(&channel_id) {
-            for parent_id in &message.parent_hashes {
-                channel_heads.remove(parent_id);
+    fn take_orphan(&mut self, message_id: &MessageId) -> Option<Message> {
+        let orphan = self.orphans.remove(message_id)?;
+        if let Some(arrivals) = self.orphan_arrivals.get_mut(&orphan.channel_id) {
+            if let Some(position) = arrivals.iter().position(|id| id == message_id) {
+                arrivals.remove(position);
+            }
+            if arrivals.is_empty() {
+                self.orphan_arrivals.remove(&orphan.channel_id);
+            }
+        }
+        for parent_id in &orphan.parent_hashes {
+            if let Some(waiting) = self.waiting_on.get_mut(parent_id) {
+                waiting.remove(message_id);
+                if waiting.is_empty() {
+                    self.waiting_on.remove(parent_id);
+                }
             }
         }
+        Some(orphan)
+    }
+
+    /// Attach orphans that were waiting on `parent_id` and now have every
+    /// parent, and in turn the orphans waiting on those
+    fn release_orphans(&mut self, parent_id: MessageId, incremental: bool) -> Vec<MessageId> {
+        let mut released = Vec::new();
+        let mut arrived = VecDeque::from([parent_id]);
+        while let Some(parent_id) = arrived.pop_front() {
+            let Some(waiting) = self.waiting_on.remove(&parent_id) else {
+                continue;
+            };
+            for orphan_id in waiting {
+                let ready = self
+                    .orphans
+                    .get(&orphan_id)
+                    .is_some_and(|orphan| self.first_missing_parent(orphan).is_none());
+                if ready {
+                    if let Some(orphan) = self.take_orphan(&orphan_id) {
+                        self.attach(orphan, incremental);
+                        released.push(orphan_id);
+                        arrived.push_back(orphan_id);
+                    }
+                }
+            }
+        }
+        released
+    }
+
+    /// Link a message whose parents are all present into the DAG
+    ///
+    /// With `incremental`, the channel's heads and order are updated right
+    /// away; batches leave that to `rebuild_heads` and `rebuild_order`.
+    fn attach(&mut self, message: Message, incremental: bool) {
+        let message_id = message.id;
+        let channel_id = message.channel_id;
 
         // Add child relationships
         for parent_id in &message.parent_hashes {
@@ -74,6 +336,27 @@ impl MessageDAG {
                 .insert(message_id);
         }
 
+        if !incremental {
+            self.messages.insert(message_id, message);
+            return;
+        }
+
+        // A message descending from every current head comes after everything
+        // already in the channel, so the existing order just gains one entry.
+        // This covers the common case of replying to a linear history.
+        let appends = !self.messages.contains_key(&message_id)
+            && self
+                .heads
+                .get(&channel_id)
+                .is_none_or(|heads| heads.iter().all(|head| message.parent_hashes.contains(head)));
+
+        // Remove parents from heads (they now have a child)
+        if let Some(channel_heads) = self.heads.get_mut(&channel_id) {
+            for parent_id in &message.parent_hashes {
+                channel_heads.remove(parent_id);
+            }
+        }
+
         // Add message as a new head
         self.heads
             .entry(channel_id)
@@ -83,7 +366,29 @@ impl MessageDAG {
         // Store the message
         self.messages.insert(message_id, message);
 
-        Ok(())
+        if appends {
+            self.order.entry(channel_id).or_default().push(message_id);
+        } else {
+            self.rebuild_order(channel_id);
+        }
+    }
+
+    /// Recompute a channel's heads from the messages in the DAG
+    fn rebuild_heads(&mut self, channel_id: ChannelId) {
+        let heads = heads_within(self.messages.values().filter(|m| m.channel_id == channel_id));
+        self.heads.insert(channel_id, heads);
+    }
+
+    /// Recompute a channel's topological order from scratch
+    fn rebuild_order(&mut self, channel_id: ChannelId) {
+        let channel_messages: Vec<_> = self
+            .messages
+            .values()
+            .filter(|m| m.channel_id == channel_id)
+            .collect();
+
+        let order = self.topological_sort(channel_messages);
+        self.order.insert(channel_id, order);
     }
 
     /// Get current heads for a channel (messages to use as parents for new messages)
@@ -94,6 +399,59 @@ impl MessageDAG {
             .unwrap_or_default()
     }
 
+    /// Parents for a reply quoting `quoted`: the current heads, plus `quoted`
+    /// itself when it isn't one of them
+    ///
+    /// Quoting an older message makes it an extra parent, so the reply both
+    /// merges the current frontier and hangs off the message it answers.
+    pub fn reply_parents(&self, channel_id: &ChannelId, quoted: MessageId) -> Vec<MessageId> {
+        let mut parents = self.get_heads(channel_id);
+        let in_channel = self.messages.get(&quoted).is_some_and(|m| m.channel_id == *channel_id);
+        if in_channel && !parents.contains(&quoted) {
+            parents.push(quoted);
+        }
+        parents
+    }
+
+    /// Parents a message quoted explicitly, i.e. ones already reachable
+    /// through another of its parents
+    ///
+    /// Heads-only parents never include an ancestor of each other, so a
+    /// redundant parent can only come from `reply_parents`.
+    pub fn quoted_parents(&self, message: &Message) -> Vec<MessageId> {
+        message
+            .parent_hashes
+            .iter()
+            .copied()
+            .filter(|candidate| {
+                message
+                    .parent_hashes
+                    .iter()
+                    .any(|other| other != candidate && self.is_ancestor(*candidate, *other))
+            })
+            .collect()
+    }
+
+    /// Whether `ancestor` is reachable from `descendant` through parent links
+    fn is_ancestor(&self, ancestor: MessageId, descendant: MessageId) -> bool {
+        let mut visited = HashSet::new();
+        let mut queue = VecDeque::from([descendant]);
+        while let Some(id) = queue.pop_front() {
+            let Some(message) = self.messages.get(&id) else {
+                continue;
+            };
+            for parent in &message.parent_hashes {
+                if *parent == ancestor {
+                    return true;
+                }
+                if visited.insert(*parent) {
+                    queue.push_back(*parent);
+                }
+            }
+        }
+        false
+    }
+
     /// Get a message by ID
     pub fn get_message(&self, message_id: &MessageId) -> Option<&Message> {
         self.messages.get(message_id)
@@ -106,19 +464,31 @@ impl MessageDAG {
 
     /// Get messages for a specific channel in topological order
     pub fn get_ordered_messages(&self, channel_id: &ChannelId) -> Vec<Message> {
-        let channel_messages: Vec<_> = self
-            .messages
-            .values()
-            .filter(|m| m.channel_id == *channel_id)
-            .collect();
+        self.order
+            .get(channel_id)
+            .map(|order| order.iter().filter_map(|id| self.messages.get(id).cloned()).collect())
+            .unwrap_or_default()
+    }
 
-        self.topological_sort(channel_messages)
+    /// Get messages for a channel in topological order, leaving out hidden ones
+    ///
+    /// Hidden messages are only dropped from the result; they stay in the DAG
+    /// and keep serving as parents and heads.
+    pub fn get_visible_messages(
+        &self,
+        channel_id: &ChannelId,
+        hidden: &HashSet<MessageId>,
+    ) -> Vec<Message> {
+        self.get_ordered_messages(channel_id)
+            .into_iter()
+            .filter(|m| !hidden.contains(&m.id))
+            .collect()
     }
 
     /// Perform topological sort on messages using Kahn's algorithm
     /// Messages with the same causal depth are ordered by Lamport timestamp,
     /// then by message ID (UUID v7, which is time-ordered)
-    fn topological_sort(&self, messages: Vec<&Message>) -> Vec<Message> {
+    fn topological_sort(&self, messages: Vec<&Message>) -> Vec<MessageId> {
         if messages.is_empty() {
             return Vec::new();
         }
@@ -167,8 +537,7 @@ impl MessageDAG {
         let mut sorted = Vec::new();
 
         while let Some(message_id) = queue.pop_front() {
-            let message = self.messages.get(&message_id).unwrap().clone();
-            sorted.push(message);
+            sorted.push(message_id);
 
             // Process children
             if let Some(children) = local_children.get(&message_id) {
@@ -204,9 +573,9 @@ impl MessageDAG {
     pub fn find_missing_messages(&self) -> HashSet<MessageId> {
         let mut missing = HashSet::new();
 
-        for message in self.messages.values() {
+        for message in self.messages.values().chain(self.orphans.values()) {
             for parent_id in &message.parent_hashes {
-                if !self.messages.contains_key(parent_id) {
+                if !self.is_known(parent_id) {
                     missing.insert(*parent_id);
                 }
             }
@@ -215,56 +584,102 @@ impl MessageDAG {
         missing
     }
 
+    /// Find missing parents referenced by messages in one channel
+    pub fn find_missing_messages_for_channel(&self, channel_id: &ChannelId) -> HashSet<MessageId> {
+        self.messages
+            .values()
+            .chain(self.orphans.values())
+            .filter(|m| m.channel_id == *channel_id)
+            .flat_map(|m| m.parent_hashes.iter())
+            .filter(|parent_id| !self.is_known(parent_id))
+            .copied()
+            .collect()
+    }
+
     /// Get all message IDs we currently have
+    ///
+    /// Includes orphans waiting for their parents and stored messages not
+    /// loaded yet, so they aren't requested again.
     pub fn all_message_ids(&self) -> HashSet<MessageId> {
-        self.messages.keys().copied().collect()
+        self.messages.keys().chain(self.orphans.keys()).chain(self.unloaded.keys()).copied().collect()
     }
 
-    /// Check if we have a specific message
+    /// Check if we have a specific message: placed in the DAG, still an
+    /// orphan, or stored but not loaded yet
     pub fn has_message(&self, message_id: &MessageId) -> bool {
-        self.messages.contains_key(message_id)
+        self.is_known(message_id) || self.orphans.contains_key(message_id)
     }
 
-    /// Load messages from storage into the DAG
-    pub fn load_messages(&mut self, messages: Vec<Message>) -> Result<(), DagError> {
-        // Sort messages by created_at to ensure parents come before children
-        let mut sorted_messages = messages;
-        sorted_messages.sort_by_key(|m| m.created_at);
+    /// Whether a message is held back until its parents arrive
+    pub fn is_orphan(&self, message_id: &MessageId) -> bool {
+        self.orphans.contains_key(message_id)
+    }
 
-        // First pass: add all messages without parent validation
-        // This handles the case where messages may be out of order
-        for message in sorted_messages {
-            let message_id = message.id;
+    /// Whether a channel has messages held back until their parents arrive
+    ///
+    /// Placed messages always have their parents, so this is exactly when
+    /// `find_missing_messages_for_channel` finds something, without the scan.
+    pub fn has_orphans(&self, channel_id: &ChannelId) -> bool {
+        self.orphan_arrivals.contains_key(channel_id)
+    }
 
-            // Add child relationships for existing parents
-            for parent_id in &message.parent_hashes {
-                if self.messages.contains_key(parent_id) {
-                    self.children
-                        .entry(*parent_id)
-                        .or_insert_with(HashSet::new)
-                        .insert(message_id);
-                }
-            }
+    /// Channels with messages held back until their parents arrive
+    pub fn channels_with_orphans(&self) -> Vec<ChannelId> {
+        self.orphan_arrivals.keys().copied().collect()
+    }
 
-            // Store the message
-            self.messages.insert(message_id, message);
+    /// A channel's orphans, longest-waiting first
+    pub fn orphans_in(&self, channel_id: &ChannelId) -> Vec<MessageId> {
+        self.orphan_arrivals.get(channel_id).map(|arrivals| arrivals.iter().copied().collect()).unwrap_or_default()
+    }
+
+    /// Take over the edit of a message changed outside the DAG
+    ///
+    /// The DAG's copy, placed or still an orphan, gets `message`'s edit.
+    pub fn update_edit(&mut self, message: &Message) {
+        if let Some(held) = self.messages.get_mut(&message.id).or_else(|| self.orphans.get_mut(&message.id)) {
+            held.edit = message.edit.clone();
         }
+    }
 
-        // Second pass: rebuild heads
-        self.heads.clear();
-        for message in self.messages.values() {
-            // A message is a head if it has no children
-            if !self.children.contains_key(&message.id)
-                || self.children.get(&message.id).unwrap().is_empty()
-            {
-                self.heads
-                    .entry(message.channel_id)
-                    .or_insert_with(HashSet::new)
-                    .insert(message.id);
-            }
+    /// Forget a deleted channel's messages, placed, orphaned or not loaded yet
+    ///
+    /// Returns how many placed messages were removed.
+    pub fn remove_channel(&mut self, channel_id: &ChannelId) -> usize {
+        let removed: HashSet<MessageId> =
+            self.messages.values().filter(|m| m.channel_id == *channel_id).map(|m| m.id).collect();
+        self.messages.retain(|id, _| !removed.contains(id));
+        self.children.retain(|id, _| !removed.contains(id));
+        for children in self.children.values_mut() {
+            children.retain(|id| !removed.contains(id));
         }
+        self.heads.remove(channel_id);
+        self.order.remove(channel_id);
+
+        self.orphans.retain(|_, m| m.channel_id != *channel_id);
+        self.orphan_arrivals.remove(channel_id);
+        self.waiting_on.retain(|_, waiting| {
+            waiting.retain(|id| self.orphans.contains_key(id));
+            !waiting.is_empty()
+        });
+        self.unloaded.retain(|_, unloaded_channel| unloaded_channel != channel_id);
+
+        removed.len()
+    }
 
-        Ok(())
+    /// Wipe the content of messages that expired by `now_ms` (HLC physical time)
+    ///
+    /// Expired messages stay in the DAG as tombstones so their children keep a
+    /// connected history. Returns the IDs of the newly wiped messages.
+    pub fn expire_messages(&mut self, now_ms: u64) -> Vec<MessageId> {
+        let mut expired = Vec::new();
+        for message in self.messages.values_mut() {
+            if message.is_expired(now_ms) && !message.content.text.is_empty() {
+                message.expire();
+                expired.push(message.id);
+            }
+        }
+        expired
     }
 }
 
@@ -274,6 +689,22 @@ impl Default for MessageDAG {
     }
 }
 
+/// Heads of a set of loaded messages: those with no child in the set
+///
+/// Parents and children outside the set are ignored, so this works on a
+/// window of a channel's history as well as on all of it. One pass collects
+/// the loaded IDs and the IDs that something loaded names as a parent.
+pub fn heads_within<'a>(messages: impl IntoIterator<Item = &'a Message>) -> HashSet<MessageId> {
+    let mut loaded = HashSet::new();
+    let mut has_child = HashSet::new();
+    for message in messages {
+        loaded.insert(message.id);
+        has_child.extend(message.parent_hashes.iter().copied());
+    }
+    loaded.retain(|id| !has_child.contains(id));
+    loaded
+}
+
 #[derive(Debug, thiserror::Error)]
 pub enum DagError {
     #[error("Message {message_id:?} references missing parent {missing_parent:?}")]
@@ -281,12 +712,14 @@ pub enum DagError {
         message_id: MessageId,
         missing_parent: MessageId,
     },
+    #[error("Message {message_id:?} lists itself as a parent")]
+    SelfParent { message_id: MessageId },
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::types::{MessageContent, PeerId, VectorClock};
+    use crate::types::{AuthorClocks, MessageContent, PeerId, VectorClock};
 
     fn create_test_message(
         channel_id: ChannelId,
@@ -300,9 +733,7 @@ mod tests {
         let mut msg = Message::new(
             channel_id,
             author,
-            MessageContent {
-                text: format!("Message {}", lamport),
-            },
+            MessageContent::new(format!("Message {}", lamport)),
             vc,
             lamport,
         );
@@ -326,9 +757,9 @@ mod tests {
         let m3 = create_test_message(channel, author, 3, vec![m2_id]);
         let m3_id = m3.id;
 
-        dag.add_message(m1).unwrap();
-        dag.add_message(m2).unwrap();
-        dag.add_message(m3).unwrap();
+        dag.ingest(m1, IngestMode::Strict).unwrap();
+        dag.ingest(m2, IngestMode::Strict).unwrap();
+        dag.ingest(m3, IngestMode::Strict).unwrap();
 
         let heads = dag.get_heads(&channel);
         assert_eq!(heads.len(), 1);
@@ -348,9 +779,9 @@ mod tests {
         let m2 = create_test_message(channel, author, 2, vec![m1_id]);
         let m3 = create_test_message(channel, author, 3, vec![m1_id]);
 
-        dag.add_message(m1).unwrap();
-        dag.add_message(m2).unwrap();
-        dag.add_message(m3).unwrap();
+        dag.ingest(m1, IngestMode::Strict).unwrap();
+        dag.ingest(m2, IngestMode::Strict).unwrap();
+        dag.ingest(m3, IngestMode::Strict).unwrap();
 
         let heads = dag.get_heads(&channel);
         assert_eq!(heads.len(), 2);
@@ -375,16 +806,188 @@ mod tests {
         let m4 = create_test_message(channel, author, 4, vec![m2_id, m3_id]);
         let m4_id = m4.id;
 
-        dag.add_message(m1).unwrap();
-        dag.add_message(m2).unwrap();
-        dag.add_message(m3).unwrap();
-        dag.add_message(m4).unwrap();
+        dag.ingest(m1, IngestMode::Strict).unwrap();
+        dag.ingest(m2, IngestMode::Strict).unwrap();
+        dag.ingest(m3, IngestMode::Strict).unwrap();
+        dag.ingest(m4, IngestMode::Strict).unwrap();
 
         let heads = dag.get_heads(&channel);
         assert_eq!(heads.len(), 1);
         assert_eq!(heads[0], m4_id);
     }
 
+    #[test]
+    fn test_duplicate_ingest_leaves_dag_unchanged() {
+        let mut dag = MessageDAG::new();
+        let channel = ChannelId::new();
+        let author = PeerId::new();
+
+        // m1 <- m2, then m1 and m2 arrive again
+        let m1 = create_test_message(channel, author, 1, vec![]);
+        let m1_id = m1.id;
+        let m2 = create_test_message(channel, author, 2, vec![m1_id]);
+        let m2_id = m2.id;
+
+        dag.ingest(m1.clone(), IngestMode::Strict).unwrap();
+        dag.ingest(m2.clone(), IngestMode::Strict).unwrap();
+        let order = dag.get_ordered_messages(&channel);
+
+        assert!(dag.ingest(m1.clone(), IngestMode::Strict).unwrap().is_empty());
+        assert!(dag.ingest(m2.clone(), IngestMode::BufferOrphans).unwrap().is_empty());
+        assert!(dag.ingest_all(vec![m1, m2], IngestMode::Strict).unwrap().is_empty());
+
+        assert_eq!(dag.get_heads(&channel), vec![m2_id]);
+        let reordered = dag.get_ordered_messages(&channel);
+        assert_eq!(
+            reordered.iter().map(|m| m.id).collect::<Vec<_>>(),
+            order.iter().map(|m| m.id).collect::<Vec<_>>()
+        );
+        assert_eq!(dag.children[&m1_id], HashSet::from([m2_id]));
+    }
+
+    #[test]
+    fn test_self_parent_rejected() {
+        let mut dag = MessageDAG::new();
+        let channel = ChannelId::new();
+        let author = PeerId::new();
+
+        let m1 = create_test_message(channel, author, 1, vec![]);
+        let m1_id = m1.id;
+        dag.ingest(m1, IngestMode::Strict).unwrap();
+
+        let mut looped = create_test_message(channel, author, 2, vec![m1_id]);
+        looped.parent_hashes.push(looped.id);
+        let looped_id = looped.id;
+
+        for mode in [IngestMode::Strict, IngestMode::BufferOrphans] {
+            assert!(matches!(
+                dag.ingest(looped.clone(), mode),
+                Err(DagError::SelfParent { message_id }) if message_id == looped_id
+            ));
+            assert!(matches!(
+                dag.ingest_all(vec![looped.clone()], mode),
+                Err(DagError::SelfParent { message_id }) if message_id == looped_id
+            ));
+        }
+
+        // Not added, not buffered waiting on itself, m1 still the only head
+        assert!(!dag.has_message(&looped_id));
+        assert!(dag.orphans.is_empty() && dag.waiting_on.is_empty());
+        assert!(!dag.children.contains_key(&m1_id));
+        assert_eq!(dag.get_heads(&channel), vec![m1_id]);
+        assert_eq!(dag.get_ordered_messages(&channel).len(), 1);
+    }
+
+    #[test]
+    fn test_reply_to_non_head_adds_parent_edge() {
+        let mut dag = MessageDAG::new();
+        let channel = ChannelId::new();
+        let author = PeerId::new();
+
+        // m1 <- m2 <- m3, then a reply quoting m1
+        let m1 = create_test_message(channel, author, 1, vec![]);
+        let m1_id = m1.id;
+        let m2 = create_test_message(channel, author, 2, vec![m1_id]);
+        let m2_id = m2.id;
+        let m3 = create_test_message(channel, author, 3, vec![m2_id]);
+        let m3_id = m3.id;
+        dag.ingest(m1, IngestMode::Strict).unwrap();
+        dag.ingest(m2, IngestMode::Strict).unwrap();
+        dag.ingest(m3, IngestMode::Strict).unwrap();
+
+        let parents = dag.reply_parents(&channel, m1_id);
+        assert_eq!(parents.len(), 2);
+        assert!(parents.contains(&m3_id) && parents.contains(&m1_id));
+
+        let reply = create_test_message(channel, author, 4, parents);
+        let reply_id = reply.id;
+        dag.ingest(reply, IngestMode::Strict).unwrap();
+
+        let reply = dag.get_message(&reply_id).unwrap();
+        assert_eq!(dag.quoted_parents(reply), vec![m1_id]);
+        assert_eq!(dag.get_heads(&channel), vec![reply_id]);
+        let order: Vec<_> = dag.get_ordered_messages(&channel).iter().map(|m| m.id).collect();
+        assert_eq!(order.last(), Some(&reply_id));
+
+        // Quoting the head itself just uses the heads
+        assert_eq!(dag.reply_parents(&channel, reply_id), vec![reply_id]);
+    }
+
+    #[test]
+    fn test_strict_mode_rejects_missing_parent() {
+        let mut dag = MessageDAG::new();
+        let channel = ChannelId::new();
+        let author = PeerId::new();
+
+        let m1 = create_test_message(channel, author, 1, vec![]);
+        let m2 = create_test_message(channel, author, 2, vec![m1.id]);
+        let m2_id = m2.id;
+
+        assert!(matches!(
+            dag.ingest(m2.clone(), IngestMode::Strict),
+            Err(DagError::MissingParent { missing_parent, .. }) if missing_parent == m1.id
+        ));
+        assert!(!dag.has_message(&m2_id));
+
+        // A strict batch keeps what it can place and reports the rest
+        let stray = create_test_message(channel, author, 3, vec![MessageId::new()]);
+        assert!(dag.ingest_all(vec![m2, m1, stray.clone()], IngestMode::Strict).is_err());
+        assert_eq!(dag.get_heads(&channel), vec![m2_id]);
+        assert!(!dag.has_message(&stray.id));
+    }
+
+    #[test]
+    fn test_orphans_buffered_until_parents_arrive() {
+        let mut dag = MessageDAG::new();
+        let channel = ChannelId::new();
+        let author = PeerId::new();
+
+        // m1 <- m2 <- m3, arriving newest first
+        let m1 = create_test_message(channel, author, 1, vec![]);
+        let m2 = create_test_message(channel, author, 2, vec![m1.id]);
+        let m3 = create_test_message(channel, author, 3, vec![m2.id]);
+        let (m1_id, m2_id, m3_id) = (m1.id, m2.id, m3.id);
+
+        assert!(dag.ingest(m3, IngestMode::BufferOrphans).unwrap().is_empty());
+        assert!(dag.ingest(m2, IngestMode::BufferOrphans).unwrap().is_empty());
+        assert!(dag.get_ordered_messages(&channel).is_empty());
+        assert!(dag.has_message(&m3_id));
+        assert_eq!(
+            dag.find_missing_messages_for_channel(&channel),
+            [m1_id].into_iter().collect::<HashSet<_>>()
+        );
+
+        let added = dag.ingest(m1, IngestMode::BufferOrphans).unwrap();
+        assert_eq!(added, vec![m1_id, m2_id, m3_id]);
+        assert_eq!(dag.get_heads(&channel), vec![m3_id]);
+        assert!(dag.find_missing_messages().is_empty());
+        let order: Vec<_> = dag.get_ordered_messages(&channel).iter().map(|m| m.id).collect();
+        assert_eq!(order, vec![m1_id, m2_id, m3_id]);
+    }
+
+    #[test]
+    fn test_expired_message_stays_in_dag() {
+        let mut dag = MessageDAG::new();
+        let channel = ChannelId::new();
+        let author = PeerId::new();
+
+        let mut m1 = create_test_message(channel, author, 1, vec![]);
+        m1.expires_at = Some(1_000);
+        let m1_id = m1.id;
+        let m2 = create_test_message(channel, author, 2, vec![m1_id]);
+
+        dag.ingest(m1, IngestMode::Strict).unwrap();
+        dag.ingest(m2, IngestMode::Strict).unwrap();
+
+        assert!(dag.expire_messages(999).is_empty());
+        assert_eq!(dag.expire_messages(1_000), vec![m1_id]);
+        assert!(dag.expire_messages(2_000).is_empty());
+
+        assert!(dag.get_message(&m1_id).unwrap().content.text.is_empty());
+        assert_eq!(dag.get_ordered_messages(&channel).len(), 2);
+        assert!(dag.find_missing_messages_for_channel(&channel).is_empty());
+    }
+
     #[test]
     fn test_topological_sort() {
         let mut dag = MessageDAG::new();
@@ -399,9 +1002,9 @@ mod tests {
 
         let m3 = create_test_message(channel, author, 3, vec![m2_id]);
 
-        dag.add_message(m1).unwrap();
-        dag.add_message(m2).unwrap();
-        dag.add_message(m3).unwrap();
+        dag.ingest(m1, IngestMode::Strict).unwrap();
+        dag.ingest(m2, IngestMode::Strict).unwrap();
+        dag.ingest(m3, IngestMode::Strict).unwrap();
 
         let ordered = dag.get_ordered_messages(&channel);
         assert_eq!(ordered.len(), 3);
@@ -409,4 +1012,278 @@ mod tests {
         assert_eq!(ordered[1].lamport_timestamp, 2);
         assert_eq!(ordered[2].lamport_timestamp, 3);
     }
+
+    /// The order a full sort produces right now, for checking the cached one
+    fn full_order(dag: &MessageDAG, channel: ChannelId) -> Vec<MessageId> {
+        let messages = dag.messages.values().filter(|m| m.channel_id == channel).collect();
+        dag.topological_sort(messages)
+    }
+
+    #[test]
+    fn test_heads_within_partial_windows() {
+        let channel = ChannelId::new();
+        let author = PeerId::new();
+
+        // m1 <- m2 <- {m3, m4} <- m5
+        let m1 = create_test_message(channel, author, 1, vec![]);
+        let m2 = create_test_message(channel, author, 2, vec![m1.id]);
+        let m3 = create_test_message(channel, author, 3, vec![m2.id]);
+        let m4 = create_test_message(channel, author, 3, vec![m2.id]);
+        let m5 = create_test_message(channel, author, 4, vec![m3.id, m4.id]);
+
+        // Newest window: parents outside it don't matter
+        assert_eq!(heads_within([&m3, &m4, &m5]), HashSet::from([m5.id]));
+        // Older window: children outside it don't count, so the fork tips are heads
+        assert_eq!(heads_within([&m1, &m2, &m3, &m4]), HashSet::from([m3.id, m4.id]));
+        // A window cutting one branch short
+        assert_eq!(heads_within([&m2, &m3]), HashSet::from([m3.id]));
+        assert_eq!(heads_within([&m4, &m1]), HashSet::from([m4.id, m1.id]));
+    }
+
+    #[test]
+    fn test_load_from_heads_then_history() {
+        let channel = ChannelId::new();
+        let author = PeerId::new();
+
+        // m1 <- m2 <- {m3, m4} <- m5
+        let m1 = create_test_message(channel, author, 1, vec![]);
+        let m2 = create_test_message(channel, author, 2, vec![m1.id]);
+        let m3 = create_test_message(channel, author, 3, vec![m2.id]);
+        let m4 = create_test_message(channel, author, 3, vec![m2.id]);
+        let m5 = create_test_message(channel, author, 4, vec![m3.id, m4.id]);
+        let history = vec![m1.clone(), m2.clone(), m3.clone(), m4.clone(), m5.clone()];
+        let stored: Vec<MessageId> = history.iter().map(|m| m.id).collect();
+
+        let mut full = MessageDAG::new();
+        full.ingest_all(history.clone(), IngestMode::Strict).unwrap();
+
+        // Saved heads that a newer stored message builds on are stale
+        let mut dag = MessageDAG::new();
+        assert!(!dag.load_from_heads(channel, &[m4.id], &[], &stored, vec![m4.clone(), m5.clone()]));
+        assert!(dag.get_heads(&channel).is_empty());
+        assert!(!dag.has_message(&m1.id));
+
+        assert!(dag.load_from_heads(channel, &[m5.id], &[], &stored, vec![m3.clone(), m4.clone(), m5.clone()]));
+        assert_eq!(dag.get_heads(&channel), vec![m5.id]);
+        assert_eq!(dag.get_ordered_messages(&channel).len(), 3);
+        assert!(dag.has_unloaded(&channel));
+
+        // Left-out history is neither missing nor new
+        assert!(dag.has_message(&m1.id));
+        assert!(dag.find_missing_messages().is_empty());
+        assert!(dag.ingest(m2.clone(), IngestMode::Strict).unwrap().is_empty());
+        let m6 = create_test_message(channel, author, 5, vec![m5.id]);
+        dag.ingest(m6.clone(), IngestMode::Strict).unwrap();
+        full.ingest(m6, IngestMode::Strict).unwrap();
+
+        dag.load_history(history).unwrap();
+        assert!(!dag.has_unloaded(&channel));
+        assert_eq!(dag.get_heads(&channel), full.get_heads(&channel));
+        let ids = |dag: &MessageDAG| dag.get_ordered_messages(&channel).iter().map(|m| m.id).collect::<Vec<_>>();
+        assert_eq!(ids(&dag), ids(&full));
+    }
+
+    #[test]
+    fn test_batch_heads_match_incremental() {
+        let channel = ChannelId::new();
+        let author = PeerId::new();
+
+        let m1 = create_test_message(channel, author, 1, vec![]);
+        let m2 = create_test_message(channel, author, 2, vec![m1.id]);
+        let m3 = create_test_message(channel, author, 3, vec![m2.id]);
+        let m4 = create_test_message(channel, author, 3, vec![m2.id]);
+        let m5 = create_test_message(channel, author, 3, vec![m1.id]);
+        let messages = vec![m1, m2, m3, m4, m5];
+
+        let mut incremental = MessageDAG::new();
+        for message in messages.clone() {
+            incremental.ingest(message, IngestMode::Strict).unwrap();
+        }
+
+        // Batches may arrive in any order, including on top of existing state
+        let mut batched = MessageDAG::new();
+        batched.ingest(messages[0].clone(), IngestMode::Strict).unwrap();
+        batched
+            .ingest_all(messages[1..].iter().rev().cloned().collect(), IngestMode::Strict)
+            .unwrap();
+
+        let heads = |dag: &MessageDAG| dag.get_heads(&channel).into_iter().collect::<HashSet<_>>();
+        assert_eq!(heads(&batched), heads(&incremental));
+        assert_eq!(heads(&batched).len(), 3);
+    }
+
+    #[test]
+    fn test_incremental_order_matches_full_sort() {
+        let mut dag = MessageDAG::new();
+        let channel = ChannelId::new();
+        let author = PeerId::new();
+
+        // Linear appends, a fork, a merge, then a late message on an old branch
+        let m1 = create_test_message(channel, author, 1, vec![]);
+        let m2 = create_test_message(channel, author, 2, vec![m1.id]);
+        let m3 = create_test_message(channel, author, 3, vec![m2.id]);
+        let m4 = create_test_message(channel, author, 3, vec![m2.id]);
+        let m5 = create_test_message(channel, author, 4, vec![m3.id, m4.id]);
+        let m6 = create_test_message(channel, author, 2, vec![m1.id]);
+        let m7 = create_test_message(channel, author, 5, vec![m5.id, m6.id]);
+
+        for message in [m1, m2, m3, m4, m5, m6, m7] {
+            dag.ingest(message, IngestMode::Strict).unwrap();
+            let cached: Vec<_> = dag.get_ordered_messages(&channel).iter().map(|m| m.id).collect();
+            assert_eq!(cached, full_order(&dag, channel));
+        }
+    }
+
+    #[test]
+    fn test_saved_orphans_stay_orphans() {
+        let channel = ChannelId::new();
+        let author = PeerId::new();
+        let m1 = create_test_message(channel, author, 1, vec![]);
+        let m2 = create_test_message(channel, author, 2, vec![m1.id]);
+        let lost = MessageId::new();
+        let orphan = create_test_message(channel, author, 3, vec![lost]);
+        let stored = vec![m1.id, m2.id, orphan.id];
+
+        // The orphan is older than the window; it's loaded anyway, not counted as placed
+        let mut dag = MessageDAG::new();
+        assert!(dag.load_from_heads(channel, &[m2.id], &[orphan.id], &stored, vec![m2.clone(), orphan.clone()]));
+        assert!(dag.is_orphan(&orphan.id));
+        assert_eq!(dag.orphans_in(&channel), vec![orphan.id]);
+        assert_eq!(dag.find_missing_messages_for_channel(&channel), HashSet::from([lost]));
+
+        // A reply to it waits too, rather than attaching to a message that isn't placed
+        let reply = create_test_message(channel, author, 4, vec![orphan.id]);
+        assert!(dag.ingest(reply.clone(), IngestMode::BufferOrphans).unwrap().is_empty());
+        assert!(dag.is_orphan(&reply.id));
+    }
+
+    #[test]
+    fn test_orphan_pool_capped_per_channel() {
+        let mut dag = MessageDAG::new();
+        let (crowded, quiet) = (ChannelId::new(), ChannelId::new());
+        let author = PeerId::new();
+
+        let orphans: Vec<Message> = (0..=MAX_ORPHANS_PER_CHANNEL as u64)
+            .map(|lamport| create_test_message(crowded, author, lamport, vec![MessageId::new()]))
+            .collect();
+        let (oldest, newest) = (orphans[0].id, orphans[MAX_ORPHANS_PER_CHANNEL].id);
+        for orphan in orphans {
+            dag.ingest(orphan, IngestMode::BufferOrphans).unwrap();
+        }
+        let waiting = create_test_message(quiet, author, 1, vec![MessageId::new()]);
+        dag.ingest(waiting.clone(), IngestMode::BufferOrphans).unwrap();
+
+        // The longest-waiting orphan made room, and only in its own channel
+        assert!(!dag.has_message(&oldest));
+        assert!(dag.is_orphan(&newest));
+        assert!(dag.is_orphan(&waiting.id));
+        assert_eq!(dag.find_missing_messages_for_channel(&crowded).len(), MAX_ORPHANS_PER_CHANNEL);
+        let with_orphans: HashSet<ChannelId> = dag.channels_with_orphans().into_iter().collect();
+        assert_eq!(with_orphans, HashSet::from([crowded, quiet]));
+    }
+
+    #[test]
+    fn test_missing_messages_scoped_to_channel() {
+        let mut dag = MessageDAG::new();
+        let complete = ChannelId::new();
+        let gappy = ChannelId::new();
+        let author = PeerId::new();
+
+        let absent = MessageId::new();
+        dag.ingest_all(
+            vec![
+                create_test_message(complete, author, 1, vec![]),
+                create_test_message(gappy, author, 2, vec![absent]),
+            ],
+            IngestMode::BufferOrphans,
+        )
+        .unwrap();
+
+        assert!(dag.find_missing_messages_for_channel(&complete).is_empty());
+        assert!(!dag.has_orphans(&complete));
+        assert!(dag.has_orphans(&gappy));
+        assert_eq!(
+            dag.find_missing_messages_for_channel(&gappy),
+            [absent].into_iter().collect::<HashSet<_>>()
+        );
+    }
+
+    #[test]
+    fn test_hidden_messages_still_serve_as_parents() {
+        let mut dag = MessageDAG::new();
+        let channel = ChannelId::new();
+        let author = PeerId::new();
+
+        let m1 = create_test_message(channel, author, 1, vec![]);
+        let m1_id = m1.id;
+
+        let m2 = create_test_message(channel, author, 2, vec![m1_id]);
+        let m2_id = m2.id;
+
+        dag.ingest(m1, IngestMode::Strict).unwrap();
+        dag.ingest(m2, IngestMode::Strict).unwrap();
+
+        let hidden: HashSet<MessageId> = [m2_id].into_iter().collect();
+
+        let visible = dag.get_visible_messages(&channel, &hidden);
+        assert_eq!(visible.len(), 1);
+        assert_eq!(visible[0].id, m1_id);
+
+        // The hidden head is still used as the parent for the next message
+        assert_eq!(dag.get_heads(&channel), vec![m2_id]);
+        let m3 = create_test_message(channel, author, 3, dag.get_heads(&channel));
+        let m3_id = m3.id;
+        dag.ingest(m3, IngestMode::Strict).unwrap();
+
+        let visible = dag.get_visible_messages(&channel, &hidden);
+        assert_eq!(visible.iter().map(|m| m.id).collect::<Vec<_>>(), vec![m1_id, m3_id]);
+    }
+
+    #[test]
+    fn test_removed_channel_leaves_others_alone() {
+        let mut dag = MessageDAG::new();
+        let (deleted, kept) = (ChannelId::new(), ChannelId::new());
+        let author = PeerId::new();
+
+        let m1 = create_test_message(deleted, author, 1, vec![]);
+        let m2 = create_test_message(deleted, author, 2, vec![m1.id]);
+        let orphan = create_test_message(deleted, author, 4, vec![MessageId::new()]);
+        let other = create_test_message(kept, author, 1, vec![]);
+        let (m2_id, orphan_id, other_id) = (m2.id, orphan.id, other.id);
+        dag.ingest_all(vec![m1, m2, orphan, other], IngestMode::BufferOrphans).unwrap();
+
+        assert_eq!(dag.remove_channel(&deleted), 2);
+        for id in [m2_id, orphan_id] {
+            assert!(!dag.has_message(&id));
+        }
+        assert!(dag.get_heads(&deleted).is_empty());
+        assert!(dag.get_ordered_messages(&deleted).is_empty());
+        assert!(dag.find_missing_messages_for_channel(&deleted).is_empty());
+
+        assert_eq!(dag.get_heads(&kept), vec![other_id]);
+        let next = create_test_message(kept, author, 2, vec![other_id]);
+        let next_id = next.id;
+        dag.ingest(next, IngestMode::Strict).unwrap();
+        assert_eq!(dag.get_heads(&kept), vec![next_id]);
+    }
+
+    #[test]
+    fn test_author_message_overtaken_by_its_successor_still_joins() {
+        let mut dag = MessageDAG::new();
+        let mut clocks = AuthorClocks::new();
+        let (channel, author) = (ChannelId::new(), PeerId::new());
+        let mut m1 = create_test_message(channel, author, 1, vec![]);
+        let mut m2 = create_test_message(channel, author, 2, vec![m1.id]);
+        m1.vector_clock.clocks.insert(author, 1);
+        m2.vector_clock.clocks.insert(author, 2);
+
+        // Received the way the app takes live messages: the clock check first
+        for message in [m2.clone(), m1.clone()] {
+            assert!(clocks.observe(&message).accepted());
+            dag.ingest(message, IngestMode::BufferOrphans).unwrap();
+        }
+
+        assert_eq!(dag.get_ordered_messages(&channel).iter().map(|m| m.id).collect::<Vec<_>>(), vec![m1.id, m2.id]);
+        assert!(!dag.has_orphans(&channel));
+    }
 }
@@ -1,7 +1,7 @@
 use anyhow::Result;
 use libsignal_protocol::{
-    GenericSignedPreKey, IdentityKeyPair, KeyPair, PreKeyId, PreKeyRecord, SignedPreKeyId,
-    SignedPreKeyRecord, Timestamp,
+    kem, GenericSignedPreKey, IdentityKeyPair, KeyPair, KyberPreKeyId, KyberPreKeyRecord, PreKeyId,
+    PreKeyRecord, SignedPreKeyId, SignedPreKeyRecord, Timestamp,
 };
 use rand::rngs::OsRng;
 use rand::{Rng, TryRngCore as _};
@@ -14,6 +14,13 @@ pub fn generate_registration_id() -> u32 {
     rng.random_range(1..16384)
 }
 
+/// Generate a prekey ID (random 24-bit number), so IDs handed out in
+/// separate bundles don't collide
+pub fn generate_prekey_id() -> u32 {
+    let mut rng = OsRng.unwrap_err();
+    rng.random_range(1..0x100_0000)
+}
+
 /// Generate a new identity key pair
 pub fn generate_identity_keypair() -> Result<IdentityKeyPair> {
     let mut rng = OsRng.unwrap_err();
@@ -61,3 +68,12 @@ pub fn generate_signed_prekey(
         &signature,
     ))
 }
+
+/// Generate a Kyber prekey, signed by our identity
+pub fn generate_kyber_prekey(id: u32, identity_keypair: &IdentityKeyPair) -> Result<KyberPreKeyRecord> {
+    Ok(KyberPreKeyRecord::generate(
+        kem::KeyType::Kyber1024,
+        KyberPreKeyId::from(id),
+        identity_keypair.private_key(),
+    )?)
+}
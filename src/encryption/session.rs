@@ -1,16 +1,167 @@
+use crate::encryption::keys::{generate_kyber_prekey, generate_prekey_id, generate_prekeys, generate_signed_prekey};
 use crate::encryption::storage::SignalStore;
-use crate::types::PeerId;
-use anyhow::Result;
+use crate::logging::ERROR_LOG_TARGET;
+use crate::types::{Channel, PeerId};
+use anyhow::{bail, Context, Result};
 use libsignal_protocol::{
-    message_decrypt, message_encrypt, process_prekey_bundle, CiphertextMessage, DeviceId,
-    PreKeyBundle, ProtocolAddress,
+    kem, message_decrypt, message_encrypt, process_prekey_bundle, CiphertextMessage, CiphertextMessageType,
+    DeviceId, GenericSignedPreKey, IdentityKey, KyberPreKeyId, KyberPreKeyStore, PreKeyBundle, PreKeyId,
+    PreKeySignalMessage, PreKeyStore, ProtocolAddress, PublicKey, SessionStore, SignalMessage,
+    SignalProtocolError, SignedPreKeyId, SignedPreKeyStore,
 };
 use rand::rngs::OsRng;
 use rand::TryRngCore as _;
 use std::sync::Arc;
-use std::time::SystemTime;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tokio::sync::Mutex;
 
+/// Protection currently in place for a channel's messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChannelProtection {
+    /// Messages travel in plaintext (over the encrypted transport)
+    Plaintext,
+    /// Messages are sealed with an established ratcheting session (forward secrecy)
+    ForwardSecret,
+}
+
+/// How long our signed and Kyber prekeys go out in bundles before new
+/// ones replace them
+pub const PREKEY_ROTATION: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// How long a replaced signed or Kyber prekey, or an unused one-time
+/// prekey, is kept for a peer still starting a session from it
+pub const PREKEY_GRACE: Duration = Duration::from_secs(30 * 24 * 60 * 60);
+
+/// Class of a failed decrypt, deciding how it is handled
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecryptFailure {
+    /// A duplicate or already-consumed message; drop it silently
+    Stale,
+    /// Our session no longer matches the sender's; reset it and re-handshake
+    SessionMismatch,
+    /// Malformed, forged or otherwise undecryptable; surface to the user
+    Corrupt,
+}
+
+/// Classify a libsignal decrypt error
+pub fn classify_decrypt_error(error: &SignalProtocolError) -> DecryptFailure {
+    match error {
+        SignalProtocolError::DuplicatedMessage(..) => DecryptFailure::Stale,
+        // No session, or a prekey we no longer hold: the peer is talking to a
+        // session we don't have. A message no session state can decrypt
+        // (InvalidMessage) may just be damaged or forged, and resetting on it
+        // would let anyone tear our sessions down, so it stays Corrupt
+        SignalProtocolError::SessionNotFound(_)
+        | SignalProtocolError::InvalidSessionStructure(_)
+        | SignalProtocolError::InvalidPreKeyId
+        | SignalProtocolError::InvalidSignedPreKeyId
+        | SignalProtocolError::InvalidKyberPreKeyId => DecryptFailure::SessionMismatch,
+        _ => DecryptFailure::Corrupt,
+    }
+}
+
+/// Serialize a pairwise ciphertext for the wire, led by its type
+pub fn ciphertext_to_bytes(ciphertext: &CiphertextMessage) -> Vec<u8> {
+    let mut bytes = vec![ciphertext.message_type() as u8];
+    bytes.extend_from_slice(ciphertext.serialize());
+    bytes
+}
+
+/// Parse a pairwise ciphertext written by `ciphertext_to_bytes`
+pub fn ciphertext_from_bytes(bytes: &[u8]) -> Result<CiphertextMessage> {
+    let Some((&message_type, body)) = bytes.split_first() else {
+        bail!("Empty ciphertext");
+    };
+    Ok(match message_type {
+        t if t == CiphertextMessageType::Whisper as u8 => {
+            CiphertextMessage::SignalMessage(SignalMessage::try_from(body)?)
+        }
+        t if t == CiphertextMessageType::PreKey as u8 => {
+            CiphertextMessage::PreKeySignalMessage(PreKeySignalMessage::try_from(body)?)
+        }
+        other => bail!("Unexpected ciphertext type {}", other),
+    })
+}
+
+/// The public parts of a `PreKeyBundle`, which isn't serializable itself
+///
+/// Keys are held in their serialized forms; `to_bundle` checks them.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PreKeyBundleParts {
+    pub registration_id: u32,
+    pub device_id: u32,
+    /// The one-time prekey's ID and public key, if the bundle has one
+    pub pre_key: Option<(u32, Vec<u8>)>,
+    pub signed_pre_key_id: u32,
+    pub signed_pre_key: Vec<u8>,
+    pub signed_pre_key_signature: Vec<u8>,
+    pub kyber_pre_key_id: u32,
+    pub kyber_pre_key: Vec<u8>,
+    pub kyber_pre_key_signature: Vec<u8>,
+    pub identity_key: Vec<u8>,
+}
+
+impl PreKeyBundleParts {
+    /// Take a bundle apart for sending
+    pub fn from_bundle(bundle: &PreKeyBundle) -> Result<Self> {
+        let pre_key = match (bundle.pre_key_id()?, bundle.pre_key_public()?) {
+            (Some(id), Some(key)) => Some((u32::from(id), key.serialize().to_vec())),
+            _ => None,
+        };
+
+        Ok(Self {
+            registration_id: bundle.registration_id()?,
+            device_id: u32::from(bundle.device_id()?),
+            pre_key,
+            signed_pre_key_id: u32::from(bundle.signed_pre_key_id()?),
+            signed_pre_key: bundle.signed_pre_key_public()?.serialize().to_vec(),
+            signed_pre_key_signature: bundle.signed_pre_key_signature()?.to_vec(),
+            kyber_pre_key_id: u32::from(bundle.kyber_pre_key_id()?),
+            kyber_pre_key: bundle.kyber_pre_key_public()?.serialize().to_vec(),
+            kyber_pre_key_signature: bundle.kyber_pre_key_signature()?.to_vec(),
+            identity_key: bundle.identity_key()?.serialize().to_vec(),
+        })
+    }
+
+    /// Put a received bundle back together, failing on any malformed key
+    ///
+    /// Signatures are checked when the bundle is processed, not here.
+    pub fn to_bundle(&self) -> Result<PreKeyBundle> {
+        let pre_key = match &self.pre_key {
+            Some((id, key)) => Some((PreKeyId::from(*id), PublicKey::deserialize(key)?)),
+            None => None,
+        };
+        let device_id = u8::try_from(self.device_id)
+            .ok()
+            .and_then(|id| DeviceId::new(id).ok())
+            .with_context(|| format!("Invalid device ID {}", self.device_id))?;
+
+        Ok(PreKeyBundle::new(
+            self.registration_id,
+            device_id,
+            pre_key,
+            SignedPreKeyId::from(self.signed_pre_key_id),
+            PublicKey::deserialize(&self.signed_pre_key)?,
+            self.signed_pre_key_signature.clone(),
+            KyberPreKeyId::from(self.kyber_pre_key_id),
+            kem::PublicKey::deserialize(&self.kyber_pre_key)?,
+            self.kyber_pre_key_signature.clone(),
+            IdentityKey::decode(&self.identity_key)?,
+        )?)
+    }
+}
+
+/// Outcome of receiving an encrypted message
+#[derive(Debug)]
+pub enum Decrypted {
+    /// The decrypted message
+    Plaintext(Vec<u8>),
+    /// A duplicate or stale message that was dropped
+    Ignored,
+    /// The session was wedged and has been reset; a new handshake is needed
+    SessionReset,
+}
+
 /// Session manager for Signal Protocol encryption
 ///
 /// Wraps libsignal operations with proper store management
@@ -35,6 +186,158 @@ impl SessionManager {
         self.store.clone()
     }
 
+    /// Check whether a ratcheting session has been established with a peer
+    pub async fn has_session(&self, peer_id: &PeerId) -> Result<bool> {
+        let address = Self::peer_to_address(peer_id);
+        let store = self.store.lock().await;
+        let session_store = store.session_store.lock().await;
+
+        Ok(session_store.load_session(&address).await?.is_some())
+    }
+
+    /// Drop the session with a peer, so the next handshake starts fresh
+    pub async fn reset_session(&self, peer_id: &PeerId) -> Result<()> {
+        let address = Self::peer_to_address(peer_id);
+        let store = self.store.lock().await;
+        let session_store = store.session_store.lock().await;
+
+        session_store.delete_session(&address).await
+    }
+
+    /// Decrypt a message from a peer, recovering from a wedged session
+    ///
+    /// Stale messages are ignored and session mismatches reset the session;
+    /// only corrupt messages are returned as errors.
+    pub async fn receive_message(
+        &self,
+        peer_id: &PeerId,
+        ciphertext: &CiphertextMessage,
+    ) -> Result<Decrypted> {
+        let address = Self::peer_to_address(peer_id);
+        let error = match self.decrypt_message(&address, ciphertext).await {
+            Ok(plaintext) => return Ok(Decrypted::Plaintext(plaintext)),
+            Err(e) => e,
+        };
+
+        // Storage and other non-protocol errors aren't ours to repair
+        let Some(signal_error) = error.downcast_ref::<SignalProtocolError>() else {
+            return Err(error);
+        };
+
+        match classify_decrypt_error(signal_error) {
+            DecryptFailure::Stale => {
+                tracing::debug!("Ignoring stale message from {}: {}", peer_id.short(), signal_error);
+                Ok(Decrypted::Ignored)
+            }
+            DecryptFailure::SessionMismatch => {
+                tracing::warn!(
+                    target: ERROR_LOG_TARGET,
+                    kind = "decrypt_failure",
+                    "Resetting session with {}: {}",
+                    peer_id.short(),
+                    signal_error
+                );
+                self.reset_session(peer_id).await?;
+                Ok(Decrypted::SessionReset)
+            }
+            DecryptFailure::Corrupt => {
+                tracing::warn!(
+                    target: ERROR_LOG_TARGET,
+                    kind = "decrypt_failure",
+                    "Corrupt message from {}: {}",
+                    peer_id.short(),
+                    signal_error
+                );
+                Err(error).context(format!("Corrupt message from {}", peer_id.short()))
+            }
+        }
+    }
+
+    /// Derive the protection status of a channel from how its messages are sent
+    ///
+    /// Only direct channels are sealed with a pairwise session, once there is
+    /// one with the other member. Group messages aren't sealed with Sender Keys yet,
+    /// so sessions with their members protect nothing and they stay plaintext.
+    pub async fn channel_protection(
+        &self,
+        channel: &Channel,
+        local_peer: &PeerId,
+    ) -> Result<ChannelProtection> {
+        let Some(recipient) = channel.direct_recipient(local_peer) else {
+            return Ok(ChannelProtection::Plaintext);
+        };
+
+        Ok(if self.has_session(&recipient).await? {
+            ChannelProtection::ForwardSecret
+        } else {
+            ChannelProtection::Plaintext
+        })
+    }
+
+    /// A prekey bundle a peer can start a session with us from
+    ///
+    /// Every bundle gets a one-time prekey of its own, saved for when the
+    /// peer's first message arrives, so handing one to several peers never
+    /// has them share it. The signed and Kyber prekeys are shared by every
+    /// bundle until they're `PREKEY_ROTATION` old and replaced. Keys past
+    /// `PREKEY_GRACE` are pruned on the way.
+    pub async fn local_bundle(&self) -> Result<PreKeyBundle> {
+        let store = self.store.lock().await;
+        let identity_key_pair = store.identity_key_pair().await;
+        let registration_id = store.registration_id().await;
+        let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64;
+        let rotated_before = now_ms.saturating_sub(PREKEY_ROTATION.as_millis() as u64);
+
+        let pre_key = generate_prekeys(generate_prekey_id(), 1)?.remove(0);
+        let mut pre_key_store = store.pre_key_store.lock().await;
+        pre_key_store.save_pre_key(pre_key.id()?, &pre_key).await?;
+
+        let mut signed_pre_key_store = store.signed_pre_key_store.lock().await;
+        let signed_pre_key = match signed_pre_key_store.current(rotated_before).await? {
+            Some(signed_pre_key) => signed_pre_key,
+            None => {
+                let signed_pre_key = generate_signed_prekey(generate_prekey_id(), &identity_key_pair)?;
+                signed_pre_key_store.save_signed_pre_key(signed_pre_key.id()?, &signed_pre_key).await?;
+                signed_pre_key
+            }
+        };
+
+        let mut kyber_pre_key_store = store.kyber_pre_key_store.lock().await;
+        let kyber_pre_key = match kyber_pre_key_store.current(rotated_before).await? {
+            Some(kyber_pre_key) => kyber_pre_key,
+            None => {
+                let kyber_pre_key = generate_kyber_prekey(generate_prekey_id(), &identity_key_pair)?;
+                kyber_pre_key_store.save_kyber_pre_key(kyber_pre_key.id()?, &kyber_pre_key).await?;
+                kyber_pre_key
+            }
+        };
+
+        // Handshakes started from an older bundle get `PREKEY_GRACE` to finish
+        let grace = PREKEY_GRACE.as_millis() as u64;
+        pre_key_store.prune(now_ms.saturating_sub(grace)).await?;
+        signed_pre_key_store.prune(rotated_before.saturating_sub(grace)).await?;
+        kyber_pre_key_store.prune(rotated_before.saturating_sub(grace)).await?;
+
+        Ok(PreKeyBundle::new(
+            registration_id,
+            DeviceId::new(1).expect("Device ID 1 is valid"),
+            Some((pre_key.id()?, pre_key.public_key()?)),
+            signed_pre_key.id()?,
+            signed_pre_key.public_key()?,
+            signed_pre_key.signature()?.to_vec(),
+            kyber_pre_key.id()?,
+            kyber_pre_key.public_key()?,
+            kyber_pre_key.signature()?.to_vec(),
+            *identity_key_pair.identity_key(),
+        )?)
+    }
+
+    /// Start a session with a peer from the bundle they sent us
+    pub async fn accept_bundle(&self, peer_id: &PeerId, bundle: &PreKeyBundleParts) -> Result<()> {
+        let bundle = bundle.to_bundle().context("Malformed prekey bundle")?;
+        self.process_prekey_bundle(&Self::peer_to_address(peer_id), &bundle).await
+    }
+
     /// Process a prekey bundle to establish a session
     pub async fn process_prekey_bundle(
         &self,
@@ -61,6 +364,18 @@ impl SessionManager {
         Ok(())
     }
 
+    /// Encrypt for a peer, ready for the wire, if we have a session with them
+    ///
+    /// Returns `None` without a session; callers hold the message back
+    /// rather than send it in plaintext.
+    pub async fn seal(&self, peer_id: &PeerId, plaintext: &[u8]) -> Result<Option<Vec<u8>>> {
+        if !self.has_session(peer_id).await? {
+            return Ok(None);
+        }
+        let ciphertext = self.encrypt_message(&Self::peer_to_address(peer_id), plaintext).await?;
+        Ok(Some(ciphertext_to_bytes(&ciphertext)))
+    }
+
     /// Encrypt a message for a remote peer
     pub async fn encrypt_message(
         &self,
@@ -120,6 +435,8 @@ impl SessionManager {
 }
 
 /// Group session manager for Sender Keys encryption
+///
+/// Channels don't use it yet: only direct channels are encrypted, pairwise.
 pub struct GroupSessionManager {
     store: Arc<Mutex<SignalStore>>,
 }
@@ -133,3 +450,183 @@ impl GroupSessionManager {
 
     // TODO: Phase 5 - Implement sender key encryption for groups
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::encryption::keys::{generate_identity_keypair, generate_registration_id};
+    use crate::storage::Storage;
+
+    /// A database file deleted when the test is done with it
+    struct TempDb(std::path::PathBuf);
+
+    impl TempDb {
+        async fn open() -> (Self, Storage) {
+            let db_path = std::env::temp_dir().join(format!("burrow-signal-{}.db", uuid::Uuid::now_v7()));
+            std::fs::File::create(&db_path).unwrap();
+            let storage = Storage::new(&db_path).await.unwrap();
+            (Self(db_path), storage)
+        }
+    }
+
+    impl Drop for TempDb {
+        fn drop(&mut self) {
+            let _ = std::fs::remove_file(&self.0);
+        }
+    }
+
+    /// A session manager with a database of its own, as peers never share stores
+    async fn session_manager() -> (SessionManager, TempDb) {
+        let (db, storage) = TempDb::open().await;
+        let store = storage.signal_store(generate_identity_keypair().unwrap(), generate_registration_id());
+        (SessionManager::new(store), db)
+    }
+
+    #[test]
+    fn test_duplicate_message_is_stale() {
+        let error = SignalProtocolError::DuplicatedMessage(3, 7);
+        assert_eq!(classify_decrypt_error(&error), DecryptFailure::Stale);
+    }
+
+    #[test]
+    fn test_lost_session_is_mismatch() {
+        let address = SessionManager::peer_to_address(&PeerId::new());
+
+        let errors = [
+            SignalProtocolError::SessionNotFound(address),
+            SignalProtocolError::InvalidPreKeyId,
+            SignalProtocolError::InvalidSignedPreKeyId,
+        ];
+        for error in &errors {
+            assert_eq!(classify_decrypt_error(error), DecryptFailure::SessionMismatch, "{}", error);
+        }
+    }
+
+    #[test]
+    fn test_unreadable_ciphertext_bytes_rejected() {
+        assert!(ciphertext_from_bytes(&[]).is_err());
+        // Sender key messages travel as group ciphertexts, never pairwise
+        assert!(ciphertext_from_bytes(&[CiphertextMessageType::SenderKey as u8, 1, 2, 3]).is_err());
+        assert!(ciphertext_from_bytes(&[CiphertextMessageType::Whisper as u8, 0xff]).is_err());
+        assert!(ciphertext_from_bytes(&[CiphertextMessageType::PreKey as u8]).is_err());
+    }
+
+    #[test]
+    fn test_malformed_message_is_corrupt() {
+        let errors = [
+            SignalProtocolError::InvalidProtobufEncoding,
+            SignalProtocolError::CiphertextMessageTooShort(2),
+            SignalProtocolError::UnrecognizedMessageVersion(99),
+            SignalProtocolError::SignatureValidationFailed,
+            // Drops just this message; the session is left alone
+            SignalProtocolError::InvalidMessage(CiphertextMessageType::Whisper, "decryption failed"),
+        ];
+        for error in &errors {
+            assert_eq!(classify_decrypt_error(error), DecryptFailure::Corrupt, "{}", error);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_bundle_exchange_establishes_session() {
+        let (alice_id, bob_id) = (PeerId::new(), PeerId::new());
+        let (alice, _alice_db) = session_manager().await;
+        let (bob, _bob_db) = session_manager().await;
+
+        // Bob's bundle crosses the wire as its parts
+        let bundle = PreKeyBundleParts::from_bundle(&bob.local_bundle().await.unwrap()).unwrap();
+        alice.accept_bundle(&bob_id, &bundle).await.unwrap();
+        assert!(alice.has_session(&bob_id).await.unwrap());
+        assert!(!bob.has_session(&alice_id).await.unwrap());
+
+        let sealed = alice.seal(&bob_id, b"hello bob").await.unwrap().unwrap();
+        match bob.receive_message(&alice_id, &ciphertext_from_bytes(&sealed).unwrap()).await.unwrap() {
+            Decrypted::Plaintext(plaintext) => assert_eq!(plaintext, b"hello bob"),
+            other => panic!("expected plaintext, got {:?}", other),
+        }
+        // The first message completes the handshake on Bob's side
+        assert!(bob.has_session(&alice_id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_only_direct_channels_with_a_session_are_forward_secret() {
+        let (alice_id, bob_id) = (PeerId::new(), PeerId::new());
+        let (alice, _alice_db) = session_manager().await;
+        let (bob, _bob_db) = session_manager().await;
+        let direct = Channel::new_peer_to_peer(alice_id, bob_id);
+        let mut group = Channel::new("team".to_string(), alice_id);
+        group.add_member(bob_id);
+
+        assert_eq!(alice.channel_protection(&direct, &alice_id).await.unwrap(), ChannelProtection::Plaintext);
+
+        let bundle = PreKeyBundleParts::from_bundle(&bob.local_bundle().await.unwrap()).unwrap();
+        alice.accept_bundle(&bob_id, &bundle).await.unwrap();
+        assert_eq!(alice.channel_protection(&direct, &alice_id).await.unwrap(), ChannelProtection::ForwardSecret);
+        // Group messages aren't sealed, whatever sessions exist with the members
+        assert_eq!(alice.channel_protection(&group, &alice_id).await.unwrap(), ChannelProtection::Plaintext);
+    }
+
+    #[tokio::test]
+    async fn test_tampered_bundle_rejected() {
+        let (bob, _bob_db) = session_manager().await;
+        let bundle = PreKeyBundleParts::from_bundle(&bob.local_bundle().await.unwrap()).unwrap();
+        assert_eq!(PreKeyBundleParts::from_bundle(&bundle.to_bundle().unwrap()).unwrap(), bundle);
+
+        let mut forged = bundle.clone();
+        forged.signed_pre_key_signature[0] ^= 1;
+        let (alice, _alice_db) = session_manager().await;
+        let bob_id = PeerId::new();
+        assert!(alice.accept_bundle(&bob_id, &forged).await.is_err());
+        assert!(!alice.has_session(&bob_id).await.unwrap());
+
+        let mut truncated = bundle;
+        truncated.identity_key.pop();
+        assert!(truncated.to_bundle().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_bundles_share_signed_and_kyber_prekeys() {
+        let (bob, _bob_db) = session_manager().await;
+        let first = PreKeyBundleParts::from_bundle(&bob.local_bundle().await.unwrap()).unwrap();
+        let second = PreKeyBundleParts::from_bundle(&bob.local_bundle().await.unwrap()).unwrap();
+
+        assert_ne!(first.pre_key.as_ref().unwrap().0, second.pre_key.as_ref().unwrap().0);
+        assert_eq!(first.signed_pre_key_id, second.signed_pre_key_id);
+        assert_eq!(first.kyber_pre_key_id, second.kyber_pre_key_id);
+        assert_eq!(first.kyber_pre_key, second.kyber_pre_key);
+    }
+
+    #[tokio::test]
+    async fn test_unused_prekeys_pruned() {
+        let (bob, _bob_db) = session_manager().await;
+        bob.local_bundle().await.unwrap();
+        bob.local_bundle().await.unwrap();
+
+        let store = bob.store();
+        let store = store.lock().await;
+        let far_future = u64::MAX >> 1;
+        assert_eq!(store.pre_key_store.lock().await.prune(0).await.unwrap(), 0);
+        assert_eq!(store.pre_key_store.lock().await.prune(far_future).await.unwrap(), 2);
+        assert_eq!(store.kyber_pre_key_store.lock().await.prune(far_future).await.unwrap(), 1);
+        assert!(store.kyber_pre_key_store.lock().await.current(0).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_kyber_prekeys_survive_restart() {
+        let (_db, storage) = TempDb::open().await;
+        let (identity, registration_id) = (generate_identity_keypair().unwrap(), generate_registration_id());
+        let bob = SessionManager::new(storage.signal_store(identity.clone(), registration_id));
+        let bundle = PreKeyBundleParts::from_bundle(&bob.local_bundle().await.unwrap()).unwrap();
+        drop(bob);
+
+        // Bob restarts before Alice's first message arrives
+        let bob = SessionManager::new(storage.signal_store(identity, registration_id));
+        let (alice, _alice_db) = session_manager().await;
+        let (alice_id, bob_id) = (PeerId::new(), PeerId::new());
+        alice.accept_bundle(&bob_id, &bundle).await.unwrap();
+        let sealed = alice.seal(&bob_id, b"still there?").await.unwrap().unwrap();
+        match bob.receive_message(&alice_id, &ciphertext_from_bytes(&sealed).unwrap()).await.unwrap() {
+            Decrypted::Plaintext(plaintext) => assert_eq!(plaintext, b"still there?"),
+            other => panic!("expected plaintext, got {:?}", other),
+        }
+    }
+}
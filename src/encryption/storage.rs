@@ -6,8 +6,8 @@ use libsignal_protocol::{
     SessionRecord, SessionStore, SignedPreKeyId, SignedPreKeyRecord, SignedPreKeyStore,
 };
 use sqlx::{Row, SqlitePool};
-use std::collections::HashMap;
 use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
 use tokio::sync::Mutex as TokioMutex;
 
 // Individual store implementations that share the SQLite pool
@@ -40,8 +40,8 @@ pub struct SqliteSenderKeyStore {
 }
 
 #[derive(Clone)]
-pub struct InMemoryKyberPreKeyStore {
-    kyber_pre_keys: Arc<TokioMutex<HashMap<u32, KyberPreKeyRecord>>>,
+pub struct SqliteKyberPreKeyStore {
+    pool: Arc<SqlitePool>,
 }
 
 /// SQLite-backed Signal Protocol storage
@@ -55,7 +55,7 @@ pub struct SignalStore {
     pub signed_pre_key_store: Arc<TokioMutex<SqliteSignedPreKeyStore>>,
     pub identity_store: Arc<TokioMutex<SqliteIdentityKeyStore>>,
     pub sender_key_store: Arc<TokioMutex<SqliteSenderKeyStore>>,
-    pub kyber_pre_key_store: Arc<TokioMutex<InMemoryKyberPreKeyStore>>,
+    pub kyber_pre_key_store: Arc<TokioMutex<SqliteKyberPreKeyStore>>,
 }
 
 impl SignalStore {
@@ -80,8 +80,8 @@ impl SignalStore {
             sender_key_store: Arc::new(TokioMutex::new(SqliteSenderKeyStore {
                 pool: pool.clone(),
             })),
-            kyber_pre_key_store: Arc::new(TokioMutex::new(InMemoryKyberPreKeyStore {
-                kyber_pre_keys: Arc::new(TokioMutex::new(HashMap::new())),
+            kyber_pre_key_store: Arc::new(TokioMutex::new(SqliteKyberPreKeyStore {
+                pool: pool.clone(),
             })),
         }
     }
@@ -221,12 +221,14 @@ impl PreKeyStore for SqlitePreKeyStore {
     ) -> Result<(), libsignal_protocol::SignalProtocolError> {
         let id_value: u32 = prekey_id.into();
         let record_bytes = record.serialize()?;
+        let created_at = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as i64;
 
         sqlx::query(
-            "INSERT OR REPLACE INTO pre_keys (pre_key_id, record) VALUES (?, ?)"
+            "INSERT OR REPLACE INTO pre_keys (pre_key_id, record, created_at) VALUES (?, ?, ?)"
         )
         .bind(id_value as i64)
         .bind(&record_bytes[..])
+        .bind(created_at)
         .execute(&*self.pool)
         .await
         .map_err(|e| libsignal_protocol::SignalProtocolError::InvalidState(
@@ -300,6 +302,99 @@ impl SignedPreKeyStore for SqliteSignedPreKeyStore {
     }
 }
 
+impl SqlitePreKeyStore {
+    /// Delete one-time prekeys handed out before `before_ms` and never used
+    ///
+    /// Used ones are already gone: libsignal removes a prekey when the
+    /// session it started is established.
+    pub async fn prune(&self, before_ms: u64) -> Result<u64> {
+        let pruned = sqlx::query("DELETE FROM pre_keys WHERE created_at < ?")
+            .bind(before_ms as i64)
+            .execute(&*self.pool)
+            .await
+            .context("Failed to prune prekeys")?;
+        Ok(pruned.rows_affected())
+    }
+}
+
+impl SqliteSignedPreKeyStore {
+    /// The newest signed prekey, if it was made at or after `since_ms`
+    pub async fn current(&self, since_ms: u64) -> Result<Option<SignedPreKeyRecord>> {
+        let row = sqlx::query(
+            "SELECT record FROM signed_pre_keys WHERE timestamp >= ? ORDER BY timestamp DESC LIMIT 1"
+        )
+        .bind(since_ms as i64)
+        .fetch_optional(&*self.pool)
+        .await
+        .context("Failed to load signed prekey")?;
+
+        match row {
+            Some(row) => {
+                let record_bytes: Vec<u8> = row.get("record");
+                Ok(Some(SignedPreKeyRecord::deserialize(&record_bytes)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Delete signed prekeys made before `before_ms`
+    pub async fn prune(&self, before_ms: u64) -> Result<u64> {
+        let pruned = sqlx::query("DELETE FROM signed_pre_keys WHERE timestamp < ?")
+            .bind(before_ms as i64)
+            .execute(&*self.pool)
+            .await
+            .context("Failed to prune signed prekeys")?;
+        Ok(pruned.rows_affected())
+    }
+}
+
+impl SqliteKyberPreKeyStore {
+    /// The newest Kyber prekey, if it was made at or after `since_ms`
+    pub async fn current(&self, since_ms: u64) -> Result<Option<KyberPreKeyRecord>> {
+        let row = sqlx::query(
+            "SELECT record FROM kyber_pre_keys WHERE timestamp >= ? ORDER BY timestamp DESC LIMIT 1"
+        )
+        .bind(since_ms as i64)
+        .fetch_optional(&*self.pool)
+        .await
+        .context("Failed to load Kyber prekey")?;
+
+        match row {
+            Some(row) => {
+                let record_bytes: Vec<u8> = row.get("record");
+                Ok(Some(KyberPreKeyRecord::deserialize(&record_bytes)?))
+            }
+            None => Ok(None),
+        }
+    }
+
+    /// Delete Kyber prekeys made before `before_ms`
+    pub async fn prune(&self, before_ms: u64) -> Result<u64> {
+        let pruned = sqlx::query("DELETE FROM kyber_pre_keys WHERE timestamp < ?")
+            .bind(before_ms as i64)
+            .execute(&*self.pool)
+            .await
+            .context("Failed to prune Kyber prekeys")?;
+        Ok(pruned.rows_affected())
+    }
+}
+
+impl SqliteSessionStore {
+    /// Delete the session with a peer so a fresh handshake can replace it
+    pub async fn delete_session(&self, address: &ProtocolAddress) -> Result<()> {
+        let device_id: u32 = address.device_id().into();
+
+        sqlx::query("DELETE FROM sessions WHERE address = ? AND device_id = ?")
+            .bind(address.name())
+            .bind(device_id as i64)
+            .execute(&*self.pool)
+            .await
+            .context("Failed to delete session")?;
+
+        Ok(())
+    }
+}
+
 // Implement SessionStore for SqliteSessionStore
 #[async_trait::async_trait(?Send)]
 impl SessionStore for SqliteSessionStore {
@@ -418,20 +513,25 @@ impl SenderKeyStore for SqliteSenderKeyStore {
     }
 }
 
-// Implement KyberPreKeyStore for InMemoryKyberPreKeyStore
+// Implement KyberPreKeyStore for SqliteKyberPreKeyStore
 #[async_trait::async_trait(?Send)]
-impl KyberPreKeyStore for InMemoryKyberPreKeyStore {
+impl KyberPreKeyStore for SqliteKyberPreKeyStore {
     async fn get_kyber_pre_key(
         &self,
         kyber_prekey_id: KyberPreKeyId,
     ) -> Result<KyberPreKeyRecord, libsignal_protocol::SignalProtocolError> {
         let id_value: u32 = kyber_prekey_id.into();
-        let store = self.kyber_pre_keys.lock().await;
 
-        store
-            .get(&id_value)
-            .cloned()
-            .ok_or(libsignal_protocol::SignalProtocolError::InvalidKyberPreKeyId)
+        let row = sqlx::query(
+            "SELECT record FROM kyber_pre_keys WHERE kyber_pre_key_id = ?"
+        )
+        .bind(id_value as i64)
+        .fetch_one(&*self.pool)
+        .await
+        .map_err(|_| libsignal_protocol::SignalProtocolError::InvalidKyberPreKeyId)?;
+
+        let record_bytes: Vec<u8> = row.get("record");
+        KyberPreKeyRecord::deserialize(&record_bytes)
     }
 
     async fn save_kyber_pre_key(
@@ -440,8 +540,22 @@ impl KyberPreKeyStore for InMemoryKyberPreKeyStore {
         record: &KyberPreKeyRecord,
     ) -> Result<(), libsignal_protocol::SignalProtocolError> {
         let id_value: u32 = kyber_prekey_id.into();
-        let mut store = self.kyber_pre_keys.lock().await;
-        store.insert(id_value, record.clone());
+        let record_bytes = record.serialize()?;
+        let timestamp = record.timestamp()?.epoch_millis() as i64;
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO kyber_pre_keys (kyber_pre_key_id, record, timestamp) VALUES (?, ?, ?)"
+        )
+        .bind(id_value as i64)
+        .bind(&record_bytes[..])
+        .bind(timestamp)
+        .execute(&*self.pool)
+        .await
+        .map_err(|e| libsignal_protocol::SignalProtocolError::InvalidState(
+            "save_kyber_pre_key",
+            format!("Database error: {}", e)
+        ))?;
+
         Ok(())
     }
 
@@ -451,8 +565,8 @@ impl KyberPreKeyStore for InMemoryKyberPreKeyStore {
         _ec_prekey_id: SignedPreKeyId,
         _base_key: &PublicKey,
     ) -> Result<(), libsignal_protocol::SignalProtocolError> {
-        // For in-memory store, we just keep the key
-        // In a production implementation, you'd mark it as used in the database
+        // Our Kyber prekeys are last-resort keys, shared by every bundle
+        // until they're rotated out, so a used one stays until it's pruned
         Ok(())
     }
 }
@@ -13,9 +13,41 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+pub mod nickname;
+pub mod store;
+
 use anyhow::{Context, Result};
-use libp2p::identity::Keypair;
-use std::path::Path;
+use libp2p::identity::{KeyType, Keypair};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Public identity material to give a contact before first contact
+///
+/// Only public keys go in here; the secret half never leaves the key store.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PublicBundle {
+    pub peer_id: String,
+    /// libp2p public key, protobuf-encoded, in hex
+    pub public_key: String,
+    pub fingerprint: String,
+    /// Signal identity public key in hex, once end-to-end encryption is set up
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub signal_identity_key: Option<String>,
+}
+
+impl PublicBundle {
+    /// Add the Signal identity key contacts should expect from us
+    pub fn with_signal_identity(mut self, key: &libsignal_protocol::IdentityKey) -> Self {
+        self.signal_identity_key = Some(to_hex(&key.serialize()));
+        self
+    }
+
+    /// Single-line JSON, for copying
+    pub fn to_json(&self) -> String {
+        serde_json::to_string(self).expect("public bundles always serialize")
+    }
+}
 
 /// Manages persistent cryptographic identity for the peer
 pub struct Identity {
@@ -25,19 +57,97 @@ pub struct Identity {
 impl Identity {
     /// Load identity from disk, or generate a new one if it doesn't exist
     pub fn load_or_generate(path: &Path) -> Result<Self> {
-        let keypair = if path.exists() {
-            tracing::info!("Loading existing identity from {:?}", path);
-            Self::load_keypair(path)?
-        } else {
-            tracing::info!("Generating new identity at {:?}", path);
-            let keypair = Keypair::generate_ed25519();
-            Self::save_keypair(&keypair, path)?;
-            keypair
+        Self::load_from(&store::FileKeyStore::new(path))
+    }
+
+    /// Load identity from a key store, generating and storing one if it's empty
+    pub fn load_from(store: &dyn store::KeyStore) -> Result<Self> {
+        if let Some(identity) = Self::load_existing(store)? {
+            return Ok(identity);
+        }
+
+        tracing::info!("Generating new identity in {}", store.location());
+        let keypair = Keypair::generate_ed25519();
+        store.save(&keypair)?;
+        Ok(Self { keypair })
+    }
+
+    /// Load identity from a key store without creating one
+    pub fn load_existing(store: &dyn store::KeyStore) -> Result<Option<Self>> {
+        let Some(keypair) = store.load()? else {
+            return Ok(None);
         };
+        tracing::info!("Loaded existing identity from {}", store.location());
+        Ok(Some(Self { keypair }))
+    }
 
+    /// Read a keypair from another libp2p application, to import as the app identity
+    ///
+    /// Accepts a protobuf-encoded libp2p keypair, or a raw Ed25519 key (the
+    /// 32-byte secret, or the 64-byte secret followed by the public key).
+    /// Nothing is installed until `install` is called.
+    pub fn read_import(source: &Path) -> Result<Self> {
+        let bytes = std::fs::read(source)
+            .with_context(|| format!("Failed to read key file: {:?}", source))?;
+        let keypair = Self::decode_imported_keypair(bytes)
+            .with_context(|| format!("Failed to import identity from {:?}", source))?;
         Ok(Self { keypair })
     }
 
+    /// Save this identity to `identity_path`, moving any existing identity
+    /// there aside first; the backup's path is returned
+    pub fn install(&self, identity_path: &Path) -> Result<Option<PathBuf>> {
+        let backup = if identity_path.exists() {
+            let secs = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_secs();
+            let backup = identity_path.with_extension(format!("key.{}.bak", secs));
+            std::fs::rename(identity_path, &backup)
+                .with_context(|| format!("Failed to back up existing identity to {:?}", backup))?;
+            tracing::info!("Backed up previous identity to {:?}", backup);
+            Some(backup)
+        } else {
+            None
+        };
+
+        Self::save_keypair(&self.keypair, identity_path)?;
+        tracing::info!("Imported identity {}", self.keypair.public().to_peer_id());
+
+        Ok(backup)
+    }
+
+    /// Decode a key file's contents, accepting only Ed25519 keys
+    fn decode_imported_keypair(mut bytes: Vec<u8>) -> Result<Keypair> {
+        let keypair = match Keypair::from_protobuf_encoding(&bytes) {
+            Ok(keypair) => keypair,
+            Err(_) if bytes.len() == 32 => Keypair::ed25519_from_bytes(&mut bytes)
+                .context("Invalid raw Ed25519 secret key")?,
+            Err(_) if bytes.len() == 64 => libp2p::identity::ed25519::Keypair::try_from_bytes(&mut bytes)
+                .context("Invalid raw Ed25519 keypair")?
+                .into(),
+            Err(e) => anyhow::bail!(
+                "Unsupported key format ({} bytes): expected a protobuf-encoded libp2p keypair \
+                 or a raw 32/64-byte Ed25519 key ({})",
+                bytes.len(),
+                e
+            ),
+        };
+
+        if keypair.key_type() != KeyType::Ed25519 {
+            anyhow::bail!(
+                "Unsupported key type {:?}: only Ed25519 identities can be imported",
+                keypair.key_type()
+            );
+        }
+
+        Ok(keypair)
+    }
+
+    /// Generate a throwaway identity that is never written to disk
+    pub fn ephemeral() -> Self {
+        Self {
+            keypair: Keypair::generate_ed25519(),
+        }
+    }
+
     /// Get the libp2p keypair
     pub fn keypair(&self) -> &Keypair {
         &self.keypair
@@ -48,6 +158,33 @@ impl Identity {
         self.keypair.public().to_peer_id()
     }
 
+    /// Fingerprint of the public key, for comparing identities out of band
+    ///
+    /// The Ed25519 public key in hex, in groups of four.
+    pub fn fingerprint(&self) -> String {
+        let public = self.keypair.public();
+        let bytes = match public.clone().try_into_ed25519() {
+            Ok(ed25519) => ed25519.to_bytes().to_vec(),
+            Err(_) => public.encode_protobuf(),
+        };
+        let hex = to_hex(&bytes);
+        hex.as_bytes()
+            .chunks(4)
+            .map(|group| std::str::from_utf8(group).unwrap_or_default())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// The public half of this identity, to share out of band
+    pub fn public_bundle(&self) -> PublicBundle {
+        PublicBundle {
+            peer_id: self.peer_id().to_string(),
+            public_key: to_hex(&self.keypair.public().encode_protobuf()),
+            fingerprint: self.fingerprint(),
+            signal_identity_key: None,
+        }
+    }
+
     /// Load keypair from file
     fn load_keypair(path: &Path) -> Result<Keypair> {
         let bytes = std::fs::read(path)
@@ -57,6 +194,26 @@ impl Identity {
             .with_context(|| format!("Failed to decode identity from {:?}", path))
     }
 
+    /// Restrict a key file to its owner if group or others can access it
+    ///
+    /// Returns the previous mode when it had to be changed.
+    #[cfg(unix)]
+    fn tighten_permissions(path: &Path) -> Result<Option<u32>> {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(path)
+            .with_context(|| format!("Failed to read permissions of {:?}", path))?
+            .permissions()
+            .mode()
+            & 0o777;
+        if mode & 0o077 == 0 {
+            return Ok(None);
+        }
+
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))
+            .with_context(|| format!("Failed to restrict permissions on {:?}", path))?;
+        Ok(Some(mode))
+    }
+
     /// Save keypair to file
     fn save_keypair(keypair: &Keypair, path: &Path) -> Result<()> {
         let bytes = keypair.to_protobuf_encoding()
@@ -83,3 +240,132 @@ impl Identity {
         Ok(())
     }
 }
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn temp_dir() -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("burrow-identity-{}", uuid::Uuid::now_v7()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_import_round_trip() {
+        let dir = temp_dir();
+        let source = dir.join("exported.key");
+        let identity_path = dir.join("identity.key");
+
+        let keypair = Keypair::generate_ed25519();
+        std::fs::write(&source, keypair.to_protobuf_encoding().unwrap()).unwrap();
+
+        // An existing identity is kept as a backup
+        let previous = Identity::load_or_generate(&identity_path).unwrap();
+        let imported = Identity::read_import(&source).unwrap();
+        assert_eq!(imported.peer_id(), keypair.public().to_peer_id());
+        // Reading alone leaves the current identity in place
+        assert_eq!(Identity::load_or_generate(&identity_path).unwrap().peer_id(), previous.peer_id());
+        let backup = imported.install(&identity_path).unwrap();
+
+        let backup = backup.expect("previous identity backed up");
+        assert_eq!(Identity::load_or_generate(&backup).unwrap().peer_id(), previous.peer_id());
+
+        // The import is what loads on the next start
+        let reloaded = Identity::load_or_generate(&identity_path).unwrap();
+        assert_eq!(reloaded.peer_id(), keypair.public().to_peer_id());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_import_raw_ed25519_secret() {
+        let dir = temp_dir();
+        let source = dir.join("raw.key");
+        let identity_path = dir.join("identity.key");
+
+        let ed25519 = Keypair::generate_ed25519().try_into_ed25519().unwrap();
+        std::fs::write(&source, ed25519.secret().as_ref()).unwrap();
+
+        let imported = Identity::read_import(&source).unwrap();
+        assert_eq!(imported.peer_id(), Keypair::from(ed25519).public().to_peer_id());
+        assert!(imported.install(&identity_path).unwrap().is_none());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_fingerprint_is_grouped_public_key() {
+        let identity = Identity::ephemeral();
+        let fingerprint = identity.fingerprint();
+        let public = identity.keypair().public().try_into_ed25519().unwrap().to_bytes();
+
+        assert_eq!(fingerprint.split(' ').count(), 16);
+        assert!(fingerprint.replace(' ', "").starts_with(&format!("{:02x}{:02x}", public[0], public[1])));
+        assert_ne!(fingerprint, Identity::ephemeral().fingerprint());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_open_key_file_is_tightened_on_load() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = temp_dir();
+        let identity_path = dir.join("identity.key");
+
+        let keypair = Keypair::generate_ed25519();
+        std::fs::write(&identity_path, keypair.to_protobuf_encoding().unwrap()).unwrap();
+        std::fs::set_permissions(&identity_path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        assert_eq!(Identity::tighten_permissions(&identity_path).unwrap(), Some(0o644));
+        std::fs::set_permissions(&identity_path, std::fs::Permissions::from_mode(0o644)).unwrap();
+
+        let loaded = Identity::load_or_generate(&identity_path).unwrap();
+        assert_eq!(loaded.peer_id(), keypair.public().to_peer_id());
+        let mode = std::fs::metadata(&identity_path).unwrap().permissions().mode() & 0o777;
+        assert_eq!(mode, 0o600);
+        // Already private: nothing to repair
+        assert_eq!(Identity::tighten_permissions(&identity_path).unwrap(), None);
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_import_rejects_unknown_format() {
+        let dir = temp_dir();
+        let source = dir.join("garbage.key");
+        let identity_path = dir.join("identity.key");
+        std::fs::write(&source, b"not a key").unwrap();
+
+        let err = Identity::read_import(&source).unwrap_err();
+        assert!(format!("{:#}", err).contains("Unsupported key format"));
+        // Nothing is installed on failure
+        assert!(!identity_path.exists());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_public_bundle_has_no_secrets() {
+        let identity = Identity::ephemeral();
+        let bundle = identity.public_bundle();
+        let json = bundle.to_json();
+
+        let ed25519 = identity.keypair().clone().try_into_ed25519().unwrap();
+        assert!(!json.contains(&to_hex(ed25519.secret().as_ref())));
+        assert!(!json.contains(&to_hex(&identity.keypair().to_protobuf_encoding().unwrap())));
+
+        // What's there is enough to recognise the peer
+        let public_key: Vec<u8> = (0..bundle.public_key.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&bundle.public_key[i..i + 2], 16).unwrap())
+            .collect();
+        let public_key = libp2p::identity::PublicKey::try_decode_protobuf(&public_key).unwrap();
+        assert_eq!(public_key.to_peer_id().to_string(), bundle.peer_id);
+        assert_eq!(serde_json::from_str::<PublicBundle>(&json).unwrap(), bundle);
+        assert!(!json.contains("signal_identity_key"));
+    }
+}
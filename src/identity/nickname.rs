@@ -0,0 +1,283 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Maximum nickname length in characters (longer input is truncated)
+pub const MAX_NICKNAME_CHARS: usize = 32;
+
+/// Minimum length of an all-hex nickname that is refused as peer ID lookalike
+const MIN_HEX_LOOKALIKE_CHARS: usize = 6;
+
+#[derive(Debug, PartialEq, Eq, thiserror::Error)]
+pub enum NicknameError {
+    #[error("Name is empty")]
+    Empty,
+
+    #[error("Name {0:?} looks like a peer ID")]
+    ResemblesPeerId(String),
+}
+
+/// Normalize and validate a nickname or channel name
+///
+/// Control, zero-width and bidi-override characters are stripped, whitespace runs
+/// are collapsed, and the result is capped at `MAX_NICKNAME_CHARS`. Names that
+/// read as a hex peer ID (including via lookalike unicode) are refused.
+pub fn normalize_nickname(input: &str) -> Result<String, NicknameError> {
+    let normalized = collapse(input, MAX_NICKNAME_CHARS);
+    if normalized.is_empty() {
+        return Err(NicknameError::Empty);
+    }
+
+    if resembles_peer_id(&normalized) {
+        return Err(NicknameError::ResemblesPeerId(normalized));
+    }
+
+    Ok(normalized)
+}
+
+/// Normalize free text shown next to a name, such as a channel description
+///
+/// Strips the same invisible characters as `normalize_nickname` and collapses
+/// whitespace, but neither caps the length nor refuses anything.
+pub fn normalize_description(input: &str) -> String {
+    collapse(input, usize::MAX)
+}
+
+/// Drop control and invisible characters and collapse whitespace runs,
+/// keeping at most `max_chars` characters
+fn collapse(input: &str, max_chars: usize) -> String {
+    let mut collapsed = String::new();
+    let mut count = 0;
+    let mut pending_space = false;
+
+    for c in input.chars() {
+        if c.is_whitespace() {
+            pending_space = count > 0;
+            continue;
+        }
+        if c.is_control() || is_invisible(c) {
+            continue;
+        }
+        if count + usize::from(pending_space) >= max_chars {
+            break;
+        }
+        if pending_space {
+            collapsed.push(' ');
+            count += 1;
+            pending_space = false;
+        }
+        collapsed.push(c);
+        count += 1;
+    }
+
+    collapsed
+}
+
+/// Reduce a nickname to its visual skeleton for lookalike comparison
+///
+/// Common Cyrillic/Greek homoglyphs and fullwidth forms map to their ASCII
+/// counterparts and the result is lowercased.
+pub fn skeleton(nickname: &str) -> String {
+    nickname
+        .chars()
+        .filter(|c| !c.is_control() && !is_invisible(*c))
+        .map(confusable_to_ascii)
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+/// Build display names for a set of names, appending the short ID to any name
+/// that visually collides with another
+pub fn disambiguate<K: Copy + Eq + Hash>(
+    names: &HashMap<K, String>,
+    short_id: impl Fn(&K) -> String,
+) -> HashMap<K, String> {
+    let mut skeleton_counts: HashMap<String, usize> = HashMap::new();
+    for name in names.values() {
+        *skeleton_counts.entry(skeleton(name)).or_insert(0) += 1;
+    }
+
+    names
+        .iter()
+        .map(|(id, name)| {
+            let display = if skeleton_counts[&skeleton(name)] > 1 {
+                format!("{} ({})", name, short_id(id))
+            } else {
+                name.clone()
+            };
+            (*id, display)
+        })
+        .collect()
+}
+
+/// Check whether a nickname reads as a hex peer ID once separators are ignored
+fn resembles_peer_id(nickname: &str) -> bool {
+    let hex: String = skeleton(nickname)
+        .chars()
+        .filter(|c| !matches!(c, '-' | '.' | '…' | ' '))
+        .collect();
+
+    hex.len() >= MIN_HEX_LOOKALIKE_CHARS && hex.chars().all(|c| c.is_ascii_hexdigit())
+}
+
+/// Zero-width, bidi-override and other invisible formatting characters
+fn is_invisible(c: char) -> bool {
+    matches!(
+        c,
+        '\u{00AD}'
+            | '\u{034F}'
+            | '\u{061C}'
+            | '\u{115F}'
+            | '\u{1160}'
+            | '\u{180E}'
+            | '\u{200B}'..='\u{200F}'
+            | '\u{202A}'..='\u{202E}'
+            | '\u{2060}'..='\u{2069}'
+            | '\u{3164}'
+            | '\u{FE00}'..='\u{FE0F}'
+            | '\u{FEFF}'
+            | '\u{FFA0}'
+    )
+}
+
+/// Map common homoglyphs to the ASCII character they imitate
+fn confusable_to_ascii(c: char) -> char {
+    match c {
+        // Fullwidth ASCII forms
+        '\u{FF01}'..='\u{FF5E}' => char::from_u32(c as u32 - 0xFEE0).unwrap_or(c),
+        // Cyrillic
+        'а' | 'А' => 'a',
+        'в' | 'В' => 'b',
+        'е' | 'Е' | 'ё' | 'Ё' => 'e',
+        'ԁ' => 'd',
+        'һ' | 'Н' => 'h',
+        'і' | 'І' => 'i',
+        'ј' | 'Ј' => 'j',
+        'к' | 'К' => 'k',
+        'М' => 'm',
+        'о' | 'О' => 'o',
+        'р' | 'Р' => 'p',
+        'с' | 'С' => 'c',
+        'ѕ' | 'Ѕ' => 's',
+        'Т' => 't',
+        'у' | 'У' => 'y',
+        'х' | 'Х' => 'x',
+        // Greek
+        'α' | 'Α' => 'a',
+        'Β' => 'b',
+        'ε' | 'Ε' => 'e',
+        'Η' => 'h',
+        'ι' | 'Ι' => 'i',
+        'κ' | 'Κ' => 'k',
+        'Μ' => 'm',
+        'ν' | 'Ν' => 'n',
+        'ο' | 'Ο' => 'o',
+        'ρ' | 'Ρ' => 'p',
+        'Τ' => 't',
+        'υ' | 'Υ' => 'y',
+        'χ' | 'Χ' => 'x',
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ChannelId, PeerId};
+
+    #[test]
+    fn test_strips_control_and_zero_width() {
+        let nickname = normalize_nickname("al\u{200B}ice\u{0007}\u{202E}\n").unwrap();
+        assert_eq!(nickname, "alice");
+    }
+
+    #[test]
+    fn test_collapses_whitespace_and_caps_length() {
+        assert_eq!(normalize_nickname("  bob \t the\n builder ").unwrap(), "bob the builder");
+
+        let long = "x".repeat(MAX_NICKNAME_CHARS * 2);
+        assert_eq!(normalize_nickname(&long).unwrap().chars().count(), MAX_NICKNAME_CHARS);
+    }
+
+    #[test]
+    fn test_rejects_empty_after_stripping() {
+        assert_eq!(normalize_nickname("\u{200B}\u{FEFF} \t"), Err(NicknameError::Empty));
+    }
+
+    #[test]
+    fn test_rejects_hex_peer_id_lookalikes() {
+        assert!(matches!(
+            normalize_nickname("0192a3f4"),
+            Err(NicknameError::ResemblesPeerId(_))
+        ));
+        assert!(matches!(
+            normalize_nickname("0192a3...b4c5d6"),
+            Err(NicknameError::ResemblesPeerId(_))
+        ));
+        // Cyrillic а/е/с posing as hex digits
+        assert!(matches!(
+            normalize_nickname("dеаdbееf"),
+            Err(NicknameError::ResemblesPeerId(_))
+        ));
+        // Fullwidth digits
+        assert!(matches!(
+            normalize_nickname("１２３４５６"),
+            Err(NicknameError::ResemblesPeerId(_))
+        ));
+
+        assert!(normalize_nickname("cafe").is_ok());
+        assert!(normalize_nickname("carol").is_ok());
+    }
+
+    #[test]
+    fn test_disambiguates_homoglyph_collisions() {
+        let alice = PeerId::new();
+        let impostor = PeerId::new();
+        let bob = PeerId::new();
+
+        let mut nicknames = HashMap::new();
+        nicknames.insert(alice, "alice".to_string());
+        nicknames.insert(impostor, "аlicе".to_string()); // Cyrillic а and е
+        nicknames.insert(bob, "bob".to_string());
+
+        let display = disambiguate(&nicknames, PeerId::short);
+
+        assert_eq!(display[&alice], format!("alice ({})", alice.short()));
+        assert!(display[&impostor].ends_with(&format!("({})", impostor.short())));
+        assert_eq!(display[&bob], "bob");
+    }
+
+    #[test]
+    fn test_description_keeps_length_and_hex() {
+        let description = format!("{} \u{200B}deadbeef\n\u{202E}", "notes ".repeat(20));
+        let normalized = normalize_description(&description);
+
+        assert!(normalized.ends_with("notes deadbeef"));
+        assert!(normalized.chars().count() > MAX_NICKNAME_CHARS);
+    }
+
+    #[test]
+    fn test_disambiguates_channel_names() {
+        let (general, lookalike) = (ChannelId::new(), ChannelId::new());
+        let names = HashMap::from([(general, "general".to_string()), (lookalike, "gеnеral".to_string())]);
+
+        let display = disambiguate(&names, ChannelId::short);
+
+        assert_eq!(display[&general], format!("general ({})", general.short()));
+        assert_eq!(display[&lookalike], format!("gеnеral ({})", lookalike.short()));
+    }
+}
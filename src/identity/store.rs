@@ -0,0 +1,263 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Where the identity's private key is kept
+//!
+//! By default it's the `identity.key` file in the data directory. Builds
+//! with the `keychain` feature can keep it in the OS keychain or secret
+//! service instead, so no plaintext key file sits on disk.
+
+use super::Identity;
+use crate::logging::ERROR_LOG_TARGET;
+use anyhow::{bail, Context, Result};
+use libp2p::identity::Keypair;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+/// Storage for the identity keypair
+pub trait KeyStore {
+    /// Where the key lives, for logs and error messages
+    fn location(&self) -> String;
+
+    /// Load the stored keypair, or None if nothing is stored yet
+    fn load(&self) -> Result<Option<Keypair>>;
+
+    /// Store a keypair, replacing any stored one
+    fn save(&self, keypair: &Keypair) -> Result<()>;
+}
+
+/// Which key store holds the identity (`BURROW_KEY_STORE`)
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyBackend {
+    /// `identity.key` in the data directory, readable by the owner only
+    #[default]
+    File,
+    /// The OS keychain (macOS Keychain, Windows Credential Manager, or the
+    /// freedesktop secret service)
+    Keychain,
+}
+
+impl FromStr for KeyBackend {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "file" => Ok(Self::File),
+            "keychain" => Ok(Self::Keychain),
+            other => Err(format!("unknown key store {:?} (expected \"file\" or \"keychain\")", other)),
+        }
+    }
+}
+
+/// Open the configured key store
+///
+/// `identity_path` is the key file used by the file backend.
+pub fn open_key_store(backend: KeyBackend, identity_path: &Path) -> Result<Box<dyn KeyStore>> {
+    match backend {
+        KeyBackend::File => Ok(Box::new(FileKeyStore::new(identity_path))),
+        #[cfg(feature = "keychain")]
+        KeyBackend::Keychain => Ok(Box::new(KeychainKeyStore::new(KEYCHAIN_ACCOUNT)?)),
+        #[cfg(not(feature = "keychain"))]
+        KeyBackend::Keychain => bail!(
+            "BURROW_KEY_STORE=keychain needs a build with keychain support \
+             (cargo build --features keychain); unset it to use {:?}",
+            identity_path
+        ),
+    }
+}
+
+/// Move a key file into another store, so switching backends keeps the identity
+///
+/// Only happens while the store is empty. The file is deleted once the key
+/// reads back from the store unchanged. Returns whether a key was moved.
+pub fn migrate_key_file(store: &dyn KeyStore, identity_path: &Path) -> Result<bool> {
+    if !identity_path.exists() || store.load()?.is_some() {
+        return Ok(false);
+    }
+
+    let keypair = Identity::load_keypair(identity_path)?;
+    store.save(&keypair)?;
+    let stored = store
+        .load()?
+        .with_context(|| format!("Key written to {} could not be read back", store.location()))?;
+    if stored.public() != keypair.public() {
+        bail!("Key read back from {} doesn't match {:?}", store.location(), identity_path);
+    }
+
+    std::fs::remove_file(identity_path)
+        .with_context(|| format!("Moved identity to {} but failed to remove {:?}", store.location(), identity_path))?;
+    tracing::info!("Moved identity from {:?} to {}", identity_path, store.location());
+    Ok(true)
+}
+
+/// The identity kept in a key file
+pub struct FileKeyStore {
+    path: PathBuf,
+}
+
+impl FileKeyStore {
+    pub fn new(path: &Path) -> Self {
+        Self { path: path.to_path_buf() }
+    }
+}
+
+impl KeyStore for FileKeyStore {
+    fn location(&self) -> String {
+        format!("{:?}", self.path)
+    }
+
+    fn load(&self) -> Result<Option<Keypair>> {
+        if !self.path.exists() {
+            return Ok(None);
+        }
+
+        #[cfg(unix)]
+        if let Some(mode) = Identity::tighten_permissions(&self.path)? {
+            tracing::warn!(
+                target: ERROR_LOG_TARGET,
+                kind = "identity_permissions",
+                "Identity file {:?} was accessible to other users (mode {:o}); restricted it to 0600. \
+                 Anyone who read it can impersonate this peer",
+                self.path,
+                mode
+            );
+        }
+        Identity::load_keypair(&self.path).map(Some)
+    }
+
+    fn save(&self, keypair: &Keypair) -> Result<()> {
+        Identity::save_keypair(keypair, &self.path)
+    }
+}
+
+/// Keychain service name the identity is stored under
+#[cfg(feature = "keychain")]
+const KEYCHAIN_SERVICE: &str = "burrow";
+
+/// Keychain account holding the protobuf-encoded keypair
+#[cfg(feature = "keychain")]
+const KEYCHAIN_ACCOUNT: &str = "identity";
+
+/// The identity kept in the OS keychain
+#[cfg(feature = "keychain")]
+pub struct KeychainKeyStore {
+    entry: keyring::Entry,
+    account: String,
+}
+
+#[cfg(feature = "keychain")]
+impl KeychainKeyStore {
+    pub fn new(account: &str) -> Result<Self> {
+        let entry = keyring::Entry::new(KEYCHAIN_SERVICE, account).map_err(unavailable)?;
+        Ok(Self {
+            entry,
+            account: account.to_string(),
+        })
+    }
+}
+
+#[cfg(feature = "keychain")]
+impl KeyStore for KeychainKeyStore {
+    fn location(&self) -> String {
+        format!("keychain entry {}/{}", KEYCHAIN_SERVICE, self.account)
+    }
+
+    fn load(&self) -> Result<Option<Keypair>> {
+        let bytes = match self.entry.get_secret() {
+            Ok(bytes) => bytes,
+            Err(keyring::Error::NoEntry) => return Ok(None),
+            Err(e) => return Err(unavailable(e)),
+        };
+        Keypair::from_protobuf_encoding(&bytes)
+            .map(Some)
+            .with_context(|| format!("Failed to decode identity from {}", self.location()))
+    }
+
+    fn save(&self, keypair: &Keypair) -> Result<()> {
+        let bytes = keypair.to_protobuf_encoding().context("Failed to encode keypair")?;
+        self.entry.set_secret(&bytes).map_err(unavailable)
+    }
+}
+
+/// Explain a keychain failure and how to fall back
+#[cfg(feature = "keychain")]
+fn unavailable(e: keyring::Error) -> anyhow::Error {
+    anyhow::anyhow!(
+        "System keychain unavailable: {}. Is a keychain or secret service running and unlocked? \
+         Set BURROW_KEY_STORE=file to keep the identity in identity.key instead",
+        e
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Mutex;
+
+    /// Stands in for the keychain
+    #[derive(Default)]
+    struct MemoryKeyStore(Mutex<Option<Vec<u8>>>);
+
+    impl KeyStore for MemoryKeyStore {
+        fn location(&self) -> String {
+            "memory".to_string()
+        }
+
+        fn load(&self) -> Result<Option<Keypair>> {
+            let bytes = self.0.lock().unwrap().clone();
+            Ok(bytes.map(|bytes| Keypair::from_protobuf_encoding(&bytes)).transpose()?)
+        }
+
+        fn save(&self, keypair: &Keypair) -> Result<()> {
+            *self.0.lock().unwrap() = Some(keypair.to_protobuf_encoding()?);
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_identity_generated_once_per_store() {
+        let store = MemoryKeyStore::default();
+        let first = Identity::load_from(&store).unwrap();
+        let second = Identity::load_from(&store).unwrap();
+        assert_eq!(first.peer_id(), second.peer_id());
+    }
+
+    #[test]
+    fn test_key_file_moved_into_store() {
+        let dir = std::env::temp_dir().join(format!("burrow-keystore-{}", uuid::Uuid::now_v7()));
+        let identity_path = dir.join("identity.key");
+        let original = Identity::load_or_generate(&identity_path).unwrap();
+
+        let store = MemoryKeyStore::default();
+        assert!(migrate_key_file(&store, &identity_path).unwrap());
+        assert!(!identity_path.exists());
+        assert_eq!(Identity::load_from(&store).unwrap().peer_id(), original.peer_id());
+
+        // A store that already holds a key is never overwritten
+        Identity::load_or_generate(&identity_path).unwrap();
+        assert!(!migrate_key_file(&store, &identity_path).unwrap());
+        assert!(identity_path.exists());
+        assert_eq!(Identity::load_from(&store).unwrap().peer_id(), original.peer_id());
+
+        std::fs::remove_dir_all(&dir).ok();
+    }
+
+    #[test]
+    fn test_backend_parsed_from_config() {
+        assert_eq!("keychain".parse(), Ok(KeyBackend::Keychain));
+        assert_eq!("File".parse(), Ok(KeyBackend::File));
+        assert!("vault".parse::<KeyBackend>().is_err());
+    }
+}
@@ -0,0 +1,39 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Burrow's core: CRDT channel state, the message DAG, storage, networking
+//! and encryption, usable without the terminal UI.
+//!
+//! The `burrow` binary is a thin `main` over this crate. `tui` and `logging`
+//! are public only so the binary can reach them and aren't part of the
+//! supported API.
+
+pub mod config;
+pub mod control;
+pub mod crdt;
+pub mod dag;
+pub mod encryption;
+pub mod identity;
+pub mod network;
+pub mod node;
+pub mod protocol;
+pub mod storage;
+pub mod transform;
+pub mod types;
+
+#[doc(hidden)]
+pub mod logging;
+#[doc(hidden)]
+pub mod tui;
@@ -0,0 +1,163 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Tracing target for the error log
+///
+/// Events logged with this target (at any level) go to `errors.log` along
+/// with every ERROR-level event, giving a concise record for bug reports.
+pub const ERROR_LOG_TARGET: &str = "error_log";
+
+/// Size at which the error log is rotated
+pub const ERROR_LOG_MAX_BYTES: u64 = 1024 * 1024;
+
+/// Rotated error logs kept alongside the current one
+pub const ERROR_LOG_KEEP: usize = 1;
+
+/// Size at which the main log (`burrow.log`) is rotated
+pub const TRACE_LOG_MAX_BYTES: u64 = 10 * 1024 * 1024;
+
+/// Rotated main logs kept alongside the current one
+pub const TRACE_LOG_KEEP: usize = 3;
+
+/// Whether an event belongs in the error log
+pub fn is_error_log_event(metadata: &tracing::Metadata<'_>) -> bool {
+    metadata.target() == ERROR_LOG_TARGET || *metadata.level() == tracing::Level::ERROR
+}
+
+/// A log file that moves itself to `<name>.1` once it grows past a size limit
+///
+/// Older rotations shift up to `<name>.2`, `<name>.3` and so on, and only
+/// `keep` of them are kept, so the log never takes more than `keep + 1`
+/// times the limit.
+pub struct RotatingFile {
+    path: PathBuf,
+    max_bytes: u64,
+    keep: usize,
+    file: File,
+    written: u64,
+}
+
+impl RotatingFile {
+    /// Open (appending to) the log at `path`
+    pub fn open(path: impl AsRef<Path>, max_bytes: u64, keep: usize) -> io::Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let written = file.metadata()?.len();
+
+        Ok(Self {
+            path,
+            max_bytes,
+            keep: keep.max(1),
+            file,
+            written,
+        })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{}", index));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+
+        // Renaming onto an existing file fails on Windows, so clear the way
+        match fs::remove_file(self.rotated_path(self.keep)) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e),
+            _ => {}
+        }
+        for index in (1..self.keep).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+        fs::rename(&self.path, self.rotated_path(1))?;
+
+        self.file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+        self.written = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        // Records are written whole, so rotating before a write never splits one
+        if self.written > 0 && self.written + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+
+        let written = self.file.write(buf)?;
+        self.written += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotates_past_limit() {
+        let dir = std::env::temp_dir().join(format!("burrow-logs-{}", uuid::Uuid::now_v7()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("errors.log");
+
+        let mut log = RotatingFile::open(&path, 16, 1).unwrap();
+        log.write_all(b"first record\n").unwrap();
+        log.write_all(b"second record\n").unwrap();
+        log.flush().unwrap();
+
+        assert_eq!(fs::read_to_string(dir.join("errors.log.1")).unwrap(), "first record\n");
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second record\n");
+
+        // Reopening keeps appending to the current file
+        let mut log = RotatingFile::open(&path, 64, 1).unwrap();
+        log.write_all(b"third\n").unwrap();
+        log.flush().unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "second record\nthird\n");
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_keeps_only_the_newest_rotations() {
+        let dir = std::env::temp_dir().join(format!("burrow-logs-{}", uuid::Uuid::now_v7()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("burrow.log");
+
+        let mut log = RotatingFile::open(&path, 4, 2).unwrap();
+        for record in ["one\n", "two\n", "three\n", "four\n"] {
+            log.write_all(record.as_bytes()).unwrap();
+        }
+        log.flush().unwrap();
+
+        assert_eq!(fs::read_to_string(&path).unwrap(), "four\n");
+        assert_eq!(fs::read_to_string(dir.join("burrow.log.1")).unwrap(), "three\n");
+        assert_eq!(fs::read_to_string(dir.join("burrow.log.2")).unwrap(), "two\n");
+        assert!(!dir.join("burrow.log.3").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
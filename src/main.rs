@@ -13,25 +13,36 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-mod crdt;
-mod dag;
-mod encryption;
-mod identity;
-mod network;
-mod protocol;
-mod storage;
-mod tui;
-mod types;
-
-use anyhow::Result;
-use identity::Identity;
-use network::Network;
-use storage::Storage;
-use tracing_subscriber::EnvFilter;
-use types::PeerId;
+use anyhow::{Context, Result};
+use burrow::config::Config;
+use burrow::encryption::session::SessionManager;
+use burrow::identity::store::{self as key_store, KeyBackend};
+use burrow::identity::{Identity, PublicBundle};
+use burrow::network::{self, Network, NetworkNamespace};
+use burrow::storage::{ContentCipher, Storage};
+use burrow::types::PeerId;
+use burrow::{logging, tui};
+use std::path::PathBuf;
+use std::sync::Arc;
+use tracing_subscriber::filter::{filter_fn, EnvFilter};
+use tracing_subscriber::prelude::*;
+
+/// How long `--selftest` runs the network before reporting
+const SELFTEST_DURATION: std::time::Duration = std::time::Duration::from_secs(5);
 
 #[tokio::main]
 async fn main() -> Result<()> {
+    // `--archive <db-path>` browses an existing database read-only
+    let archive_path = parse_path_arg("--archive")?;
+    // `--import-identity <key-path>` installs a libp2p keypair as our identity
+    let import_path = parse_path_arg("--import-identity")?;
+    // `--export-public-key` prints the identity's public keys as JSON and exits
+    let export_public_key = std::env::args().skip(1).any(|arg| arg == "--export-public-key");
+    // `--selftest` checks connectivity, prints a report and exits
+    let selftest = std::env::args().skip(1).any(|arg| arg == "--selftest");
+    // `--control-socket <path>` accepts JSON-RPC requests from local scripts
+    let control_socket = parse_path_arg("--control-socket")?;
+
     // Initialize storage directory
     let data_dir = dirs::data_local_dir()
         .unwrap_or_else(|| std::path::PathBuf::from("."))
@@ -40,65 +51,250 @@ async fn main() -> Result<()> {
     // Create directory if it doesn't exist
     std::fs::create_dir_all(&data_dir)?;
 
-    // Initialize logging to file (not stdout, to avoid interfering with TUI)
-    let log_file = std::fs::File::create(data_dir.join("burrow.log"))?;
-    tracing_subscriber::fmt()
-        .with_env_filter(EnvFilter::from_default_env().add_directive("burrow=info".parse()?))
+    // Initialize logging to file (not stdout, to avoid interfering with TUI).
+    // Appended to across runs and rotated, so a crash's log survives the restart
+    let log_file = logging::RotatingFile::open(
+        data_dir.join("burrow.log"),
+        logging::TRACE_LOG_MAX_BYTES,
+        logging::TRACE_LOG_KEEP,
+    )?;
+    let trace_layer = tracing_subscriber::fmt::layer()
         .with_writer(std::sync::Mutex::new(log_file))
         .with_ansi(false) // Disable ANSI colors in log file
+        .with_filter(
+            EnvFilter::from_default_env()
+                .add_directive("burrow=info".parse()?)
+                .add_directive(format!("{}=info", logging::ERROR_LOG_TARGET).parse()?),
+        );
+
+    // Errors also go to a concise JSON-lines log that survives restarts
+    let error_log = logging::RotatingFile::open(
+        data_dir.join("errors.log"),
+        logging::ERROR_LOG_MAX_BYTES,
+        logging::ERROR_LOG_KEEP,
+    )?;
+    let error_layer = tracing_subscriber::fmt::layer()
+        .json()
+        .with_writer(std::sync::Mutex::new(error_log))
+        .with_filter(filter_fn(logging::is_error_log_event));
+
+    tracing_subscriber::registry()
+        .with(trace_layer)
+        .with(error_layer)
         .init();
 
     tracing::info!("Starting Burrow...");
 
-    // Initialize storage
-    let db_path = data_dir.join("burrow.db");
-
-    tracing::info!("Database path: {:?}", db_path);
+    let mut config = Config::from_env();
+    config.read_only = archive_path.is_some();
 
-    let storage = Storage::new(&db_path).await?;
+    // Initialize storage
+    let mut storage = if let Some(archive_path) = &archive_path {
+        tracing::info!("Opening archive read-only: {:?}", archive_path);
+        Storage::open_read_only(archive_path).await?
+    } else {
+        let db_path = data_dir.join("burrow.db");
+        tracing::info!("Database path: {:?}", db_path);
+        Storage::new(&db_path).await?
+    };
+    storage.set_fetch_concurrency(config.fetch_concurrency);
 
-    // Load or generate persistent identity
+    // Load or generate persistent identity (archive mode never creates one)
     let identity_path = data_dir.join("identity.key");
-    let identity = Identity::load_or_generate(&identity_path)?;
+    if let Some(import_path) = &import_path {
+        if config.key_backend != KeyBackend::File {
+            anyhow::bail!("--import-identity installs a key file; unset BURROW_KEY_STORE to import");
+        }
+        // The live database has to be re-keyed along with the key file, and
+        // an archive is opened read-only
+        if config.read_only {
+            anyhow::bail!("--import-identity can't be used with --archive");
+        }
+        let identity = Identity::read_import(import_path)?;
+        // Stored content is encrypted under the current identity; carry it
+        // over, swapping the key file in only once every row is re-encrypted
+        if identity_path.exists() {
+            let previous = Identity::load_or_generate(&identity_path)?;
+            storage.set_content_cipher(ContentCipher::from_identity(previous.keypair())?);
+        }
+        let mut backup = None;
+        let rekeyed = storage
+            .rekey_content(ContentCipher::from_identity(identity.keypair())?, || {
+                backup = identity.install(&identity_path)?;
+                Ok(())
+            })
+            .await?;
+        if rekeyed.unreadable > 0 {
+            println!(
+                "{} stored messages couldn't be decrypted with the previous identity and were left as they were",
+                rekeyed.unreadable
+            );
+        }
+        println!("Imported identity {}", identity.peer_id());
+        if let Some(backup) = backup {
+            println!("Previous identity backed up to {}", backup.display());
+        }
+        return Ok(());
+    }
+    let key_store = key_store::open_key_store(config.key_backend, &identity_path)?;
+    let identity = if config.read_only {
+        Identity::load_existing(&*key_store)?.unwrap_or_else(Identity::ephemeral)
+    } else {
+        if config.key_backend != KeyBackend::File {
+            key_store::migrate_key_file(&*key_store, &identity_path)?;
+        }
+        Identity::load_from(&*key_store)?
+    };
+
+    // Pairwise Signal sessions, from the Signal identity kept in the database
+    let session_manager = if config.read_only {
+        None
+    } else {
+        Some(Arc::new(SessionManager::new(storage.load_signal_store().await?)))
+    };
+    let public_bundle = public_bundle(&identity, &storage, config.read_only).await?;
+    if export_public_key {
+        println!("{}", serde_json::to_string_pretty(&public_bundle)?);
+        return Ok(());
+    }
     let libp2p_peer_id = identity.peer_id();
     let peer_id = PeerId::from_libp2p(&libp2p_peer_id);
 
+    // Message content is encrypted at rest with a key derived from the identity
+    storage.set_content_cipher(ContentCipher::from_identity(identity.keypair())?);
+    if !config.read_only {
+        let converted = storage.encrypt_stored_content().await?;
+        if converted > 0 {
+            tracing::info!("Encrypted {} stored messages", converted);
+        }
+    }
+
+    if selftest {
+        println!("Running connectivity self-test for {} seconds...", SELFTEST_DURATION.as_secs());
+        let report = network::selftest::run(
+            identity.keypair().clone(),
+            config.listen_port,
+            NetworkNamespace::new(&config.network_namespace),
+            SELFTEST_DURATION,
+        )
+        .await?;
+        println!("{}", report);
+        if !report.passed() {
+            std::process::exit(1);
+        }
+        return Ok(());
+    }
+
     tracing::info!("Peer ID: {}", libp2p_peer_id);
     tracing::info!("App Peer UUID: {}", peer_id.0);
 
     // Create network channels
     let (event_tx, event_rx, command_tx, command_rx) = network::create_network_channels();
 
-    // Create and configure network with persistent keypair
-    let mut network = Network::new(identity.keypair().clone(), event_tx, command_rx).await?;
+    // libsignal's stores aren't Send, so the network runs on this thread
+    // alongside the TUI rather than on the runtime's workers
+    let local = tokio::task::LocalSet::new();
 
-    // Start listening on a port (default: 9000)
-    let listen_port = std::env::var("BURROW_PORT")
-        .ok()
-        .and_then(|p| p.parse().ok())
-        .unwrap_or(9000);
+    // Archive mode skips the network entirely
+    let network_handle = if let Some(session_manager) = &session_manager {
+        // Create and configure network with persistent keypair
+        let mut network = Network::new(
+            identity.keypair().clone(),
+            event_tx,
+            command_rx,
+            NetworkNamespace::new(&config.network_namespace),
+        )
+        .await?;
+        network.set_auto_connect_back(config.auto_connect_back);
+        network.set_session_manager(session_manager.clone());
 
-    network.listen(listen_port)?;
-    tracing::info!("Network listening on port {}", listen_port);
+        // Start listening on a port (default: 9000)
+        network.listen(config.listen_port)?;
+        tracing::info!("Network listening on port {}", config.listen_port);
 
-    // Spawn network task
-    let network_handle = tokio::spawn(async move {
-        if let Err(e) = network.run().await {
-            tracing::error!("Network error: {}", e);
-        }
-    });
+        // Spawn network task
+        Some(local.spawn_local(async move {
+            if let Err(e) = network.run().await {
+                tracing::error!("Network error: {}", e);
+            }
+        }))
+    } else {
+        None
+    };
 
     // Run TUI with network channels
-    let mut app = tui::App::new(storage, peer_id, libp2p_peer_id, event_rx, command_tx).await?;
-    let tui_result = app.run().await;
+    let mut app = tui::App::new(&config, storage, peer_id, libp2p_peer_id, event_rx, command_tx).await?;
+    app.describe_identity(&identity, public_bundle, data_dir.clone());
+    if let Some(session_manager) = session_manager {
+        app.set_session_manager(session_manager).await;
+    }
+    if let Some(path) = &control_socket {
+        start_control_socket(&mut app, path, config.read_only)?;
+    }
+    let tui_result = local.run_until(app.run()).await;
+    if let Some(path) = &control_socket {
+        let _ = std::fs::remove_file(path);
+    }
 
     // Cleanup
     tracing::info!("Burrow shutting down...");
-    network_handle.abort();
+    if let Some(network_handle) = network_handle {
+        network_handle.abort();
+    }
 
     tui_result
 }
 
+#[cfg(unix)]
+fn start_control_socket(app: &mut tui::App, path: &std::path::Path, read_only: bool) -> Result<()> {
+    if read_only {
+        anyhow::bail!("--control-socket can't be used with --archive");
+    }
+
+    let listener = burrow::control::bind(path)?;
+    let (call_tx, call_rx) = tokio::sync::mpsc::unbounded_channel();
+    tokio::spawn(burrow::control::serve(listener, call_tx));
+    app.attach_control(call_rx);
+    tracing::info!("Control socket listening on {:?}", path);
+
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn start_control_socket(_app: &mut tui::App, _path: &std::path::Path, _read_only: bool) -> Result<()> {
+    anyhow::bail!("--control-socket needs Unix domain sockets, which this platform doesn't support")
+}
+
+/// The identity's public keys, with the Signal identity key once one exists
+async fn public_bundle(identity: &Identity, storage: &Storage, read_only: bool) -> Result<PublicBundle> {
+    let bundle = identity.public_bundle();
+    let signal_identity = match storage.get_signal_identity().await {
+        Ok(signal_identity) => signal_identity,
+        // Archives from older versions may predate the signal_identity table
+        Err(e) if read_only => {
+            tracing::warn!("Failed to load Signal identity from archive: {}", e);
+            None
+        }
+        Err(e) => return Err(e),
+    };
+    Ok(match signal_identity {
+        Some((identity_key_pair, _)) => bundle.with_signal_identity(identity_key_pair.identity_key()),
+        None => bundle,
+    })
+}
+
+/// Parse an optional `<flag> <path>` argument
+fn parse_path_arg(flag: &str) -> Result<Option<PathBuf>> {
+    let mut args = std::env::args().skip(1);
+    while let Some(arg) = args.next() {
+        if arg == flag {
+            let path = args.next().with_context(|| format!("{} requires a path", flag))?;
+            return Ok(Some(PathBuf::from(path)));
+        }
+    }
+    Ok(None)
+}
+
 // Helper to get user directories (will add this as a dependency)
 mod dirs {
     use std::path::PathBuf;
@@ -0,0 +1,83 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::network::NetworkEvent;
+use crate::protocol::WireError;
+use libp2p::{gossipsub, Multiaddr, TransportError};
+use tokio::sync::mpsc::error::SendError;
+
+/// Why a network operation failed
+#[derive(Debug, thiserror::Error)]
+pub enum NetworkError {
+    /// A libp2p component couldn't be created
+    #[error("Failed to set up {component}")]
+    Setup {
+        component: &'static str,
+        #[source]
+        source: Box<dyn std::error::Error + Send + Sync>,
+    },
+
+    /// The listen address couldn't be bound (e.g. the port is in use)
+    #[error("Failed to listen on {address}")]
+    Listen {
+        address: Multiaddr,
+        #[source]
+        source: TransportError<std::io::Error>,
+    },
+
+    /// A message exceeds the gossipsub size limit; resending won't help
+    #[error("Message is too large to publish")]
+    MessageTooLarge,
+
+    /// Gossipsub refused a publish for another reason
+    #[error("Failed to publish message")]
+    Publish(#[source] gossipsub::PublishError),
+
+    /// A protocol message couldn't be serialized
+    #[error("Failed to encode network message")]
+    Encode(#[from] WireError),
+
+    /// The application stopped taking network events (it's shutting down)
+    #[error("Application is no longer receiving network events")]
+    AppClosed,
+}
+
+impl NetworkError {
+    /// Map a component's construction error, for `map_err`
+    pub(crate) fn setup<E>(component: &'static str) -> impl FnOnce(E) -> Self
+    where
+        E: Into<Box<dyn std::error::Error + Send + Sync>>,
+    {
+        move |source| Self::Setup {
+            component,
+            source: source.into(),
+        }
+    }
+}
+
+impl From<SendError<NetworkEvent>> for NetworkError {
+    fn from(_: SendError<NetworkEvent>) -> Self {
+        Self::AppClosed
+    }
+}
+
+impl From<gossipsub::PublishError> for NetworkError {
+    fn from(error: gossipsub::PublishError) -> Self {
+        match error {
+            gossipsub::PublishError::MessageTooLarge => Self::MessageTooLarge,
+            error => Self::Publish(error),
+        }
+    }
+}
@@ -13,35 +13,133 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+use crate::crdt::Timestamp;
+use crate::encryption::session::{ciphertext_from_bytes, Decrypted, PreKeyBundleParts, SessionManager};
 use crate::protocol::NetworkMessage;
-use crate::types::{Channel, ChannelId, Message, MessageId};
-use anyhow::{Context, Result};
+use crate::types::{Channel, ChannelId, Message, MessageContent, MessageId};
+use crate::types::PeerId as BurrowPeerId;
+use anyhow::Result;
 use libp2p::{
     core::upgrade,
-    dns, gossipsub, identify, mdns, noise,
+    dns, gossipsub, identify, mdns, noise, ping, request_response,
     futures::StreamExt,
-    swarm::{NetworkBehaviour, SwarmEvent},
-    tcp, yamux, Multiaddr, PeerId, Swarm, Transport,
+    multiaddr::Protocol,
+    swarm::{
+        behaviour::toggle::Toggle,
+        dial_opts::{DialOpts, PeerCondition},
+        ConnectionId, DialError, NetworkBehaviour, SwarmEvent,
+    },
+    tcp, yamux, Multiaddr, PeerId, StreamProtocol, Swarm, Transport, TransportError,
 };
-use std::collections::hash_map::DefaultHasher;
-use std::hash::{Hash, Hasher};
-use std::time::Duration;
+use sha2::{Digest, Sha256};
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
 use tokio::sync::mpsc;
 use tracing::{debug, error, info, warn};
 
+mod error;
+mod namespace;
 pub mod peer;
+pub mod selftest;
+mod topics;
+
+pub use error::NetworkError;
+pub use namespace::NetworkNamespace;
+pub use topics::Subscriptions;
 
 /// Network events sent to the application
 #[derive(Debug, Clone)]
 pub enum NetworkEvent {
-    /// A new peer has connected
-    PeerConnected(PeerId),
+    /// A peer has connected (its first connection; more to it aren't reported)
+    PeerConnected {
+        peer_id: PeerId,
+        /// The remote address of the connection
+        address: Multiaddr,
+    },
 
-    /// A peer has disconnected
+    /// A peer's last connection has closed
     PeerDisconnected(PeerId),
 
-    /// Received a chat message from a peer
-    MessageReceived(Message),
+    /// A chat message couldn't be published to any peer (it stays local
+    /// until anti-entropy syncs it)
+    MessageUndelivered(MessageId),
+
+    /// A peer reported its agent and protocol versions via identify
+    PeerIdentified {
+        peer_id: PeerId,
+        agent_version: String,
+        protocol_version: String,
+        /// Our address as the peer sees it
+        observed_addr: Multiaddr,
+    },
+
+    /// mDNS found a peer on the local network
+    PeerDiscovered(PeerId),
+
+    /// The number of peers sharing one of our gossip topics changed
+    TopicPeersChanged {
+        topic: String,
+        peers: usize,
+    },
+
+    /// The number of peers present in a channel we've joined changed
+    /// (zero once the last one leaves or disconnects)
+    TopicPeers {
+        channel_id: ChannelId,
+        count: usize,
+    },
+
+    /// A ping to a connected peer came back
+    PeerPinged {
+        peer_id: PeerId,
+        rtt: Duration,
+    },
+
+    /// Outcome of dialing our own listener (see `NetworkCommand::DialSelf`)
+    SelfDialed {
+        reachable: bool,
+        detail: String,
+    },
+
+    /// A peer told us the addresses it listens on, so it can be redialed later
+    PeerAnnounced {
+        peer_id: PeerId,
+        addresses: Vec<Multiaddr>,
+    },
+
+    /// Received a chat message
+    ///
+    /// `source` is the peer that handed it to us, which for gossip needn't
+    /// be its author.
+    MessageReceived { message: Message, source: PeerId },
+
+    /// No working encryption session with the other member of a direct
+    /// channel: a message to them was held back, or one from them couldn't
+    /// be read and the session was reset
+    EncryptionUnavailable {
+        channel_id: ChannelId,
+        peer_id: BurrowPeerId,
+    },
+
+    /// A pairwise encryption session with a peer was started from their
+    /// prekey bundle
+    SessionEstablished(BurrowPeerId),
+
+    /// An encrypted message from the other member of a direct channel was
+    /// malformed, forged or otherwise undecryptable, and was dropped
+    DecryptFailed {
+        channel_id: ChannelId,
+        peer_id: BurrowPeerId,
+    },
+
+    /// A peer edited one of its messages
+    MessageEdited {
+        channel_id: ChannelId,
+        message_id: MessageId,
+        content: MessageContent,
+        edited_at: Timestamp,
+    },
 
     /// Local listening address established
     ListeningOn(Multiaddr),
@@ -52,11 +150,27 @@ pub enum NetworkEvent {
         error: String,
     },
 
+    /// A dial we made on our own (discovery, reconnecting) failed
+    AutoDialFailed {
+        peer_id: Option<PeerId>,
+        error: String,
+    },
+
     /// Successfully initiated connection to a peer
     ConnectionDialing {
         address: String,
     },
 
+    /// A manually dialed address connected
+    DialSucceeded {
+        address: String,
+    },
+
+    /// A pending dial was cancelled by the user
+    ConnectionCancelled {
+        address: String,
+    },
+
     /// A peer announced a new channel
     ChannelAnnounced(Channel),
 
@@ -66,6 +180,12 @@ pub enum NetworkEvent {
     /// A peer sent an incremental channel update
     ChannelUpdated(Channel),
 
+    /// A peer deleted a channel
+    ChannelTombstoneReceived {
+        channel_id: ChannelId,
+        deleted_at: Timestamp,
+    },
+
     /// Received a request for channel state (we should respond)
     ChannelStateRequested {
         channel_id: ChannelId,
@@ -82,15 +202,19 @@ pub enum NetworkEvent {
     },
 
     /// Received messages in response to a request
+    ///
+    /// `source` is the peer that answered, so has the messages.
     MessagesReceived {
         channel_id: ChannelId,
         messages: Vec<Message>,
+        source: PeerId,
     },
 
     /// Received message inventory from a peer
     InventoryReceived {
         channel_id: ChannelId,
         message_ids: std::collections::HashSet<MessageId>,
+        edits: HashMap<MessageId, Timestamp>,
         from_peer: PeerId,
     },
 
@@ -99,6 +223,29 @@ pub enum NetworkEvent {
         channel_id: ChannelId,
         requesting_peer: PeerId,
     },
+
+    /// A peer asked for an attached image's data
+    AttachmentRequested {
+        hash: [u8; 32],
+        requesting_peer: PeerId,
+    },
+
+    /// A peer sent an attached image's data; it's unchecked against the hash
+    AttachmentReceived {
+        hash: [u8; 32],
+        data: Vec<u8>,
+    },
+}
+
+/// Who a channel's messages, and edits of them, are sent to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Audience {
+    /// Everyone on the channel's topic
+    Everyone,
+    /// The channel's members, directly where connected
+    Members(Vec<BurrowPeerId>),
+    /// The other member of a direct channel, encrypted once sessions are set
+    Encrypted(BurrowPeerId),
 }
 
 /// Commands sent to the network layer
@@ -107,21 +254,89 @@ pub enum NetworkCommand {
     /// Broadcast a message to all peers
     BroadcastMessage(Message),
 
+    /// Send an edit of one of our messages to the same audience as the
+    /// message itself
+    ///
+    /// An edit that can't be delivered now isn't retried; it reaches peers
+    /// with the message when they next sync the channel.
+    EditMessage {
+        channel_id: ChannelId,
+        message_id: MessageId,
+        content: MessageContent,
+        edited_at: Timestamp,
+        audience: Audience,
+    },
+
+    /// Send a message straight to the given channel members, gossiping it
+    /// only if some member isn't directly connected
+    SendToMembers {
+        message: Message,
+        members: Vec<BurrowPeerId>,
+    },
+
+    /// Send a direct channel's message to the other member, encrypted once
+    /// a session manager is set
+    ///
+    /// Encrypted messages only go over a direct connection; without one, or
+    /// without a session with the recipient, the message is undelivered.
+    SendEncrypted {
+        message: Message,
+        recipient: BurrowPeerId,
+    },
+
     /// Connect to a specific peer address
     ConnectToPeer(Multiaddr),
 
+    /// Cancel pending dials to an address
+    CancelDial(Multiaddr),
+
+    /// Cancel all pending dials
+    CancelAllDials,
+
+    /// Quietly reconnect to a known peer at its announced addresses
+    RedialPeer {
+        peer_id: PeerId,
+        addresses: Vec<Multiaddr>,
+    },
+
+    /// Dial one of our own listen addresses to check that connections get
+    /// through the transport and handshake
+    DialSelf(Multiaddr),
+
     /// Get list of connected peers
     ListPeers,
 
+    /// Join a gossip topic (nothing happens if already subscribed)
+    Subscribe(String),
+
+    /// Leave a gossip topic; the chat topic itself can't be left
+    Unsubscribe(String),
+
+    /// Join a channel's presence topic, to be counted among its listeners
+    /// and get `TopicPeers` updates (nothing happens if already joined)
+    JoinChannel(ChannelId),
+
+    /// Leave a channel's presence topic
+    LeaveChannel(ChannelId),
+
     /// Broadcast a new channel announcement
     AnnounceChannel(Channel),
 
     /// Request full channel state from peers
     RequestChannelState(ChannelId),
 
+    /// Send full channel state in response to a request
+    RespondWithChannelState(Channel),
+
     /// Broadcast a channel update (name change, member change, etc)
     BroadcastChannelUpdate(Channel),
 
+    /// Broadcast that we deleted a channel
+    BroadcastChannelTombstone {
+        channel_id: ChannelId,
+        deleted_at: Timestamp,
+    },
+
     // Phase 4: DAG Synchronization Commands
 
     /// Request specific messages by ID
@@ -140,20 +355,44 @@ pub enum NetworkCommand {
     BroadcastInventory {
         channel_id: ChannelId,
         message_ids: std::collections::HashSet<MessageId>,
+        edits: HashMap<MessageId, Timestamp>,
     },
 
     /// Request message inventory from peers
     RequestInventory {
         channel_id: ChannelId,
     },
+    /// Request message inventory from specific connected peers only
+    RequestInventoryFrom {
+        channel_id: ChannelId,
+        peers: Vec<PeerId>,
+    },
+
+    /// Ask peers directly for an attached image's data
+    RequestAttachment {
+        hash: [u8; 32],
+        peers: Vec<PeerId>,
+    },
+
+    /// Send an attached image's data to the peer that asked for it
+    SendAttachment {
+        hash: [u8; 32],
+        data: Vec<u8>,
+        peer: PeerId,
+    },
 }
 
 /// Network behavior combining multiple protocols
 #[derive(NetworkBehaviour)]
 pub struct BurrowBehaviour {
     pub gossipsub: gossipsub::Behaviour,
-    pub mdns: mdns::tokio::Behaviour,
+    /// Off when the host won't give us a multicast socket; peers can still be dialed
+    pub mdns: Toggle<mdns::tokio::Behaviour>,
     pub identify: identify::Behaviour,
+    /// Round-trip times for the connection quality indicator
+    pub ping: ping::Behaviour,
+    /// Point-to-point frames (serialized `NetworkMessage`s) for channel members
+    pub direct: request_response::cbor::Behaviour<Vec<u8>, ()>,
 }
 
 /// Network manager handling P2P communication
@@ -162,6 +401,33 @@ pub struct Network {
     event_tx: mpsc::UnboundedSender<NetworkEvent>,
     command_rx: mpsc::UnboundedReceiver<NetworkCommand>,
     gossip_topic: gossipsub::IdentTopic,
+    /// Topics we're in, and which peers are in each
+    subscriptions: Subscriptions,
+    /// Manual dials that have not connected or failed yet
+    pending_dials: HashMap<ConnectionId, Multiaddr>,
+    /// Dials cancelled by the user; closed or ignored once libp2p resolves them
+    cancelled_dials: HashSet<ConnectionId>,
+    /// Our own listen addresses, sent in peer announcements
+    listen_addrs: Vec<Multiaddr>,
+    /// Announce ourselves to peers that dialed us, so they learn where to reach us
+    auto_connect_back: bool,
+    /// Inbound peers waiting for our announcement once they join the topic
+    awaiting_announce: HashSet<PeerId>,
+    /// Peers that have already seen our announcement on their current connection
+    announced_to: HashSet<PeerId>,
+    /// A pending dial to our own listener
+    self_dial: Option<ConnectionId>,
+    /// Only peers in the same namespace are kept
+    namespace: NetworkNamespace,
+    /// Peers identified as belonging to another namespace; not redialed
+    foreign_peers: HashSet<PeerId>,
+    /// Pairwise Signal sessions for direct channels; until set, their
+    /// messages travel in plaintext
+    sessions: Option<Arc<SessionManager>>,
+    /// Peers recently sent a bundle at their asking
+    bundle_requests: BundleThrottle,
+    /// Why mDNS discovery couldn't start, if it couldn't
+    mdns_error: Option<String>,
 }
 
 impl Network {
@@ -170,7 +436,8 @@ impl Network {
         keypair: libp2p::identity::Keypair,
         event_tx: mpsc::UnboundedSender<NetworkEvent>,
         command_rx: mpsc::UnboundedReceiver<NetworkCommand>,
-    ) -> Result<Self> {
+        namespace: NetworkNamespace,
+    ) -> Result<Self, NetworkError> {
         // Use provided keypair for persistent identity
         let local_key = keypair;
         let local_peer_id = PeerId::from(local_key.public());
@@ -179,23 +446,18 @@ impl Network {
         // Set up TCP transport with noise encryption and yamux multiplexing
         let tcp_transport = tcp::tokio::Transport::new(tcp::Config::default().nodelay(true));
 
-        let transport = dns::tokio::Transport::system(tcp_transport)?
+        let transport = dns::tokio::Transport::system(tcp_transport)
+            .map_err(NetworkError::setup("DNS resolution"))?
             .upgrade(upgrade::Version::V1)
-            .authenticate(noise::Config::new(&local_key)?)
+            .authenticate(noise::Config::new(&local_key).map_err(NetworkError::setup("noise encryption"))?)
             .multiplex(yamux::Config::default())
             .boxed();
 
         // Configure gossipsub for message broadcasting
-        let message_id_fn = |message: &gossipsub::Message| {
-            let mut s = DefaultHasher::new();
-            message.data.hash(&mut s);
-            gossipsub::MessageId::from(s.finish().to_string())
-        };
-
         let gossipsub_config = gossipsub::ConfigBuilder::default()
             .heartbeat_interval(Duration::from_secs(1))
             .validation_mode(gossipsub::ValidationMode::Strict)
-            .message_id_fn(message_id_fn)
+            .message_id_fn(|message: &gossipsub::Message| gossip_message_id(&message.data))
             .build()
             .expect("Valid gossipsub config");
 
@@ -203,29 +465,48 @@ impl Network {
             gossipsub::MessageAuthenticity::Signed(local_key.clone()),
             gossipsub_config,
         )
-        .map_err(|e| anyhow::anyhow!("Failed to create gossipsub: {}", e))?;
+        .map_err(NetworkError::setup("gossipsub"))?;
 
         // Create the gossipsub topic for chat messages
-        let gossip_topic = gossipsub::IdentTopic::new("burrow-chat");
-        gossipsub.subscribe(&gossip_topic)?;
-
-        // Set up mDNS for local peer discovery
-        let mdns = mdns::tokio::Behaviour::new(
-            mdns::Config::default(),
-            local_peer_id,
-        )?;
+        let gossip_topic = gossipsub::IdentTopic::new(namespace.gossip_topic());
+        gossipsub
+            .subscribe(&gossip_topic)
+            .map_err(NetworkError::setup("gossipsub"))?;
+        let mut subscriptions = Subscriptions::default();
+        subscriptions.subscribe(gossip_topic.hash());
+
+        // Set up mDNS for local peer discovery. Without it (no multicast in
+        // a container, say) the network still runs and peers are dialed by hand
+        let (mdns, mdns_error) = match mdns::tokio::Behaviour::new(mdns::Config::default(), local_peer_id) {
+            Ok(mdns) => (Some(mdns), None),
+            Err(e) => {
+                warn!("mDNS discovery unavailable, peers must be connected manually: {}", e);
+                (None, Some(e.to_string()))
+            }
+        };
 
         // Set up identify protocol
-        let identify = identify::Behaviour::new(identify::Config::new(
-            "/burrow/0.1.0".to_string(),
-            local_key.public(),
-        ));
+        let identify = identify::Behaviour::new(
+            identify::Config::new(namespace.protocol_version(), local_key.public())
+                .with_agent_version(format!("burrow/{}", env!("CARGO_PKG_VERSION"))),
+        );
+
+        // Periodic pings measure round-trip time to each peer
+        let ping = ping::Behaviour::new(ping::Config::new());
+
+        // Set up direct delivery to channel members
+        let direct = request_response::cbor::Behaviour::new(
+            [(StreamProtocol::new("/burrow/direct/1.0.0"), request_response::ProtocolSupport::Full)],
+            request_response::Config::default(),
+        );
 
         // Combine behaviors
         let behaviour = BurrowBehaviour {
             gossipsub,
-            mdns,
+            mdns: Toggle::from(mdns),
             identify,
+            ping,
+            direct,
         };
 
         // Create the swarm
@@ -242,24 +523,55 @@ impl Network {
             event_tx,
             command_rx,
             gossip_topic,
+            subscriptions,
+            pending_dials: HashMap::new(),
+            cancelled_dials: HashSet::new(),
+            listen_addrs: Vec::new(),
+            auto_connect_back: true,
+            awaiting_announce: HashSet::new(),
+            announced_to: HashSet::new(),
+            self_dial: None,
+            namespace,
+            foreign_peers: HashSet::new(),
+            sessions: None,
+            bundle_requests: BundleThrottle::default(),
+            mdns_error,
         })
     }
 
+    /// Why mDNS discovery isn't running, or None when it is
+    pub fn mdns_error(&self) -> Option<&str> {
+        self.mdns_error.as_deref()
+    }
+
+    /// Choose whether to start the address exchange when a peer dials us
+    pub fn set_auto_connect_back(&mut self, enabled: bool) {
+        self.auto_connect_back = enabled;
+    }
+
+    /// Encrypt direct channels' messages with these sessions from now on
+    pub fn set_session_manager(&mut self, sessions: Arc<SessionManager>) {
+        self.sessions = Some(sessions);
+    }
+
     /// Start listening on a TCP port
-    pub fn listen(&mut self, port: u16) -> Result<()> {
-        let listen_addr: Multiaddr = format!("/ip4/0.0.0.0/tcp/{}", port)
-            .parse()
-            .context("Invalid listen address")?;
+    pub fn listen(&mut self, port: u16) -> Result<(), NetworkError> {
+        let address = Multiaddr::empty()
+            .with(Protocol::Ip4(std::net::Ipv4Addr::UNSPECIFIED))
+            .with(Protocol::Tcp(port));
 
         self.swarm
-            .listen_on(listen_addr)
-            .context("Failed to start listening")?;
+            .listen_on(address.clone())
+            .map_err(|source| NetworkError::Listen { address, source })?;
 
         Ok(())
     }
 
     /// Run the network event loop
-    pub async fn run(mut self) -> Result<()> {
+    ///
+    /// The future isn't Send, as libsignal's stores aren't: run it on a
+    /// `LocalSet` rather than with `tokio::spawn`.
+    pub async fn run(mut self) -> Result<(), NetworkError> {
         info!("Starting network event loop");
 
         loop {
@@ -286,9 +598,37 @@ impl Network {
         match event {
             SwarmEvent::NewListenAddr { address, .. } => {
                 info!("Listening on {}", address);
+                self.listen_addrs.push(address.clone());
                 self.event_tx.send(NetworkEvent::ListeningOn(address))?;
             }
 
+            SwarmEvent::ExpiredListenAddr { address, .. } => {
+                self.listen_addrs.retain(|a| *a != address);
+            }
+
+            SwarmEvent::Behaviour(BurrowBehaviourEvent::Gossipsub(
+                gossipsub::Event::Subscribed { peer_id, topic },
+            )) => {
+                debug!("{} subscribed to {}", peer_id, topic);
+                if self.subscriptions.peer_subscribed(peer_id, topic.clone()) {
+                    self.report_topic_peers(&topic)?;
+                }
+
+                // Publishing only reaches a peer once it has joined the chat topic
+                if topic == self.gossip_topic.hash() && self.awaiting_announce.remove(&peer_id) {
+                    self.announce_to(peer_id)?;
+                }
+            }
+
+            SwarmEvent::Behaviour(BurrowBehaviourEvent::Gossipsub(
+                gossipsub::Event::Unsubscribed { peer_id, topic },
+            )) => {
+                debug!("{} unsubscribed from {}", peer_id, topic);
+                if self.subscriptions.peer_unsubscribed(&peer_id, &topic) {
+                    self.report_topic_peers(&topic)?;
+                }
+            }
+
             SwarmEvent::Behaviour(BurrowBehaviourEvent::Gossipsub(
                 gossipsub::Event::Message {
                     propagation_source: peer_id,
@@ -299,9 +639,38 @@ impl Network {
                 debug!("Received message from {}", peer_id);
                 if let Ok(network_msg) = NetworkMessage::from_bytes(&message.data) {
                     match network_msg {
+                        NetworkMessage::PeerAnnounce { listen_addresses, .. } => {
+                            // Signed messages always carry the original author
+                            let Some(source) = message.source else {
+                                return Ok(());
+                            };
+                            let addresses = parse_announced_addresses(&listen_addresses);
+                            debug!("Peer {} announced {} addresses", source, addresses.len());
+                            for address in &addresses {
+                                self.swarm.add_peer_address(source, address.clone());
+                            }
+                            self.event_tx.send(NetworkEvent::PeerAnnounced {
+                                peer_id: source,
+                                addresses,
+                            })?;
+
+                            // Complete the exchange so the announcer learns our addresses too
+                            if self.swarm.is_connected(&source) && !self.announced_to.contains(&source) {
+                                self.announce_to(source)?;
+                            }
+                        }
                         NetworkMessage::ChatMessage(msg) => {
                             debug!("Chat message: {:?}", msg);
-                            self.event_tx.send(NetworkEvent::MessageReceived(msg))?;
+                            self.event_tx.send(NetworkEvent::MessageReceived { message: msg, source: peer_id })?;
+                        }
+                        NetworkMessage::EditMessage { channel_id, message_id, content, edited_at } => {
+                            debug!("Edit of message {:?} from {}", message_id, peer_id);
+                            self.event_tx.send(NetworkEvent::MessageEdited {
+                                channel_id,
+                                message_id,
+                                content,
+                                edited_at,
+                            })?;
                         }
                         NetworkMessage::ChannelAnnounce { channel } => {
                             debug!("Channel announcement from {}: {}", peer_id, channel.get_name());
@@ -322,6 +691,13 @@ impl Network {
                             debug!("Channel update from {}: {}", peer_id, channel.get_name());
                             self.event_tx.send(NetworkEvent::ChannelUpdated(channel))?;
                         }
+                        NetworkMessage::ChannelTombstone { channel_id, deleted_at } => {
+                            debug!("Channel tombstone from {} for {:?}", peer_id, channel_id);
+                            self.event_tx.send(NetworkEvent::ChannelTombstoneReceived {
+                                channel_id,
+                                deleted_at,
+                            })?;
+                        }
                         NetworkMessage::MessageRequest { channel_id, message_ids } => {
                             debug!("Message request from {} for {} messages", peer_id, message_ids.len());
                             self.event_tx.send(NetworkEvent::MessageRequested {
@@ -335,14 +711,17 @@ impl Network {
                             self.event_tx.send(NetworkEvent::MessagesReceived {
                                 channel_id,
                                 messages,
+                                source: message.source.unwrap_or(peer_id),
                             })?;
                         }
-                        NetworkMessage::MessageInventory { channel_id, message_ids } => {
+                        NetworkMessage::MessageInventory { channel_id, message_ids, edits } => {
                             debug!("Message inventory from {} with {} messages", peer_id, message_ids.len());
+                            // Attribute it to whoever published it, not the peer that forwarded it
                             self.event_tx.send(NetworkEvent::InventoryReceived {
                                 channel_id,
                                 message_ids,
-                                from_peer: peer_id,
+                                edits,
+                                from_peer: message.source.unwrap_or(peer_id),
                             })?;
                         }
                         NetworkMessage::InventoryRequest { channel_id } => {
@@ -359,14 +738,87 @@ impl Network {
                 }
             }
 
+            SwarmEvent::Behaviour(BurrowBehaviourEvent::Direct(request_response::Event::Message {
+                peer,
+                message: request_response::Message::Request { request, channel, .. },
+                ..
+            })) => {
+                // Acknowledge first; the frame was delivered either way
+                let _ = self.swarm.behaviour_mut().direct.send_response(channel, ());
+
+                match NetworkMessage::from_bytes(&request) {
+                    Ok(NetworkMessage::ChatMessage(msg)) => {
+                        debug!("Direct chat message from {}", peer);
+                        self.event_tx.send(NetworkEvent::MessageReceived { message: msg, source: peer })?;
+                    }
+                    // Edits of closed channels' messages come the same way as the messages
+                    Ok(NetworkMessage::EditMessage { channel_id, message_id, content, edited_at })
+                        if edited_at.peer_id == BurrowPeerId::from_libp2p(&peer) =>
+                    {
+                        debug!("Direct edit of message {:?} from {}", message_id, peer);
+                        self.event_tx.send(NetworkEvent::MessageEdited { channel_id, message_id, content, edited_at })?;
+                    }
+                    Ok(NetworkMessage::EncryptedChatMessage { channel_id, sender, ciphertext }) => {
+                        debug!("Encrypted chat message from {}", peer);
+                        self.receive_encrypted(peer, channel_id, sender, &ciphertext).await?;
+                    }
+                    Ok(NetworkMessage::PreKeyBundleAnnounce { sender, bundle }) => {
+                        debug!("Prekey bundle from {}", peer);
+                        self.accept_bundle(peer, sender, &bundle).await?;
+                    }
+                    Ok(NetworkMessage::PreKeyBundleRequest { sender }) => {
+                        debug!("Prekey bundle request from {}", peer);
+                        if BurrowPeerId::from_libp2p(&peer) != sender {
+                            warn!("Dropping prekey bundle request from {} claiming to be {}", peer, sender.short());
+                        } else if self.bundle_requests.allow(peer, Instant::now()) {
+                            self.send_bundle(peer).await;
+                        } else {
+                            debug!("Not answering {} again so soon with a prekey bundle", peer);
+                        }
+                    }
+                    Ok(NetworkMessage::InventoryRequest { channel_id }) => {
+                        debug!("Direct inventory request from {} for channel {:?}", peer, channel_id);
+                        self.event_tx.send(NetworkEvent::InventoryRequested {
+                            channel_id,
+                            requesting_peer: peer,
+                        })?;
+                    }
+                    // Attachments go only to and from the peers involved
+                    Ok(NetworkMessage::AttachmentRequest { hash }) => {
+                        debug!("Attachment request from {}", peer);
+                        self.event_tx.send(NetworkEvent::AttachmentRequested { hash, requesting_peer: peer })?;
+                    }
+                    Ok(NetworkMessage::AttachmentResponse { hash, data }) => {
+                        debug!("Attachment of {} bytes from {}", data.len(), peer);
+                        self.event_tx.send(NetworkEvent::AttachmentReceived { hash, data })?;
+                    }
+                    Ok(_) => debug!("Ignoring unexpected direct frame from {}", peer),
+                    Err(e) => warn!("Undecodable direct frame from {}: {}", peer, e),
+                }
+            }
+
+            SwarmEvent::Behaviour(BurrowBehaviourEvent::Direct(request_response::Event::OutboundFailure {
+                peer, error, ..
+            })) => {
+                warn!("Direct delivery to {} failed: {}", peer, error);
+            }
+
             SwarmEvent::Behaviour(BurrowBehaviourEvent::Mdns(mdns::Event::Discovered(
                 peers,
             ))) => {
                 for (peer_id, addr) in peers {
+                    if self.foreign_peers.contains(&peer_id) {
+                        continue;
+                    }
                     info!("Discovered peer via mDNS: {} at {}", peer_id, addr);
-                    // Auto-dial discovered peers silently (no notification for auto-discovery)
+                    self.event_tx.send(NetworkEvent::PeerDiscovered(peer_id))?;
+                    // Auto-dial discovered peers; the app decides whether failures are worth mentioning
                     if let Err(e) = self.swarm.dial(addr.clone()) {
                         debug!("Failed to auto-dial discovered peer {}: {}", peer_id, e);
+                        self.event_tx.send(NetworkEvent::AutoDialFailed {
+                            peer_id: Some(peer_id),
+                            error: describe_dial_error(&e),
+                        })?;
                     }
                 }
             }
@@ -386,30 +838,130 @@ impl Network {
                     "Identified peer {}: protocol={} agent={}",
                     peer_id, info.protocol_version, info.agent_version
                 );
+                if !self.namespace.accepts(&info.protocol_version) {
+                    info!(
+                        "Disconnecting {}: it's in network {:?}, not ours ({:?})",
+                        peer_id,
+                        info.protocol_version,
+                        self.namespace.protocol_version()
+                    );
+                    self.foreign_peers.insert(peer_id);
+                    let _ = self.swarm.disconnect_peer_id(peer_id);
+                    return Ok(());
+                }
+                self.event_tx.send(NetworkEvent::PeerIdentified {
+                    peer_id,
+                    agent_version: info.agent_version,
+                    protocol_version: info.protocol_version,
+                    observed_addr: info.observed_addr,
+                })?;
             }
 
+            SwarmEvent::Behaviour(BurrowBehaviourEvent::Ping(ping::Event { peer, result, .. })) => match result {
+                Ok(rtt) => self.event_tx.send(NetworkEvent::PeerPinged { peer_id: peer, rtt })?,
+                Err(e) => debug!("Ping to {} failed: {}", peer, e),
+            },
+
             SwarmEvent::ConnectionEstablished {
-                peer_id, endpoint, ..
+                peer_id, connection_id, endpoint, num_established, ..
             } => {
+                if self.cancelled_dials.contains(&connection_id) {
+                    // The user gave up on this dial before it connected
+                    debug!("Closing cancelled dial to {}", peer_id);
+                    self.swarm.close_connection(connection_id);
+                    return Ok(());
+                }
+                if self.self_dial == Some(connection_id) {
+                    // Only possible if something else answered at our address
+                    self.self_dial = None;
+                    self.swarm.close_connection(connection_id);
+                    self.event_tx.send(NetworkEvent::SelfDialed {
+                        reachable: false,
+                        detail: format!("a different peer ({}) answered at our address", peer_id),
+                    })?;
+                    return Ok(());
+                }
+
                 info!("Connection established with {} via {}", peer_id, endpoint.get_remote_address());
-                self.event_tx.send(NetworkEvent::PeerConnected(peer_id))?;
+                // Inbound connections come from ephemeral ports, so we can't
+                // dial back until the peer announces where it listens
+                if endpoint.is_listener() && self.auto_connect_back && !self.announced_to.contains(&peer_id) {
+                    self.awaiting_announce.insert(peer_id);
+                }
+                if let Some(address) = self.pending_dials.remove(&connection_id) {
+                    self.event_tx.send(NetworkEvent::DialSucceeded {
+                        address: address.to_string(),
+                    })?;
+                }
+                // Further connections to a peer already connected (both
+                // sides dialing, say) aren't a new peer
+                if num_established.get() == 1 {
+                    self.event_tx.send(NetworkEvent::PeerConnected {
+                        peer_id,
+                        address: endpoint.get_remote_address().clone(),
+                    })?;
+
+                    // Give a new peer what it needs to open a session with us
+                    if self.needs_session(&peer_id).await {
+                        self.send_bundle(peer_id).await;
+                    }
+                }
             }
 
             SwarmEvent::ConnectionClosed {
-                peer_id, cause, ..
+                peer_id, connection_id, cause, num_established, ..
             } => {
+                if num_established == 0 {
+                    self.awaiting_announce.remove(&peer_id);
+                    self.announced_to.remove(&peer_id);
+                    // gossipsub drops the peer's subscriptions without telling us
+                    for topic in self.subscriptions.peer_disconnected(&peer_id) {
+                        self.report_topic_peers(&topic)?;
+                    }
+                }
+
+                if self.cancelled_dials.remove(&connection_id) {
+                    return Ok(());
+                }
+
                 info!("Connection closed with {}: {:?}", peer_id, cause);
-                self.event_tx.send(NetworkEvent::PeerDisconnected(peer_id))?;
+                if num_established == 0 {
+                    self.event_tx.send(NetworkEvent::PeerDisconnected(peer_id))?;
+                }
             }
 
             SwarmEvent::IncomingConnection { .. } => {
                 debug!("Incoming connection");
             }
 
-            SwarmEvent::OutgoingConnectionError { peer_id, error, .. } => {
-                // Log but don't send notification - this is often from auto-discovery
-                // Manual dial failures are caught immediately in handle_command
-                debug!("Outgoing connection error to {:?}: {}", peer_id, error);
+            SwarmEvent::OutgoingConnectionError { peer_id, connection_id, error, .. } => {
+                if self.self_dial == Some(connection_id) {
+                    // Meeting ourselves means the listener accepted the
+                    // connection and the handshake completed
+                    self.self_dial = None;
+                    let reachable = matches!(error, DialError::LocalPeerId { .. });
+                    let detail = if reachable {
+                        "connected and completed the handshake".to_string()
+                    } else {
+                        describe_dial_error(&error)
+                    };
+                    self.event_tx.send(NetworkEvent::SelfDialed { reachable, detail })?;
+                } else if self.cancelled_dials.remove(&connection_id) {
+                    debug!("Cancelled dial to {:?} ended: {}", peer_id, error);
+                } else if let Some(address) = self.pending_dials.remove(&connection_id) {
+                    warn!("Failed to connect to {}: {}", address, error);
+                    self.event_tx.send(NetworkEvent::ConnectionFailed {
+                        address: address.to_string(),
+                        error: describe_dial_error(&error),
+                    })?;
+                } else {
+                    // From auto-discovery or reconnecting, not something the user dialed
+                    debug!("Outgoing connection error to {:?}: {}", peer_id, error);
+                    self.event_tx.send(NetworkEvent::AutoDialFailed {
+                        peer_id,
+                        error: describe_dial_error(&error),
+                    })?;
+                }
             }
 
             SwarmEvent::IncomingConnectionError { error, .. } => {
@@ -427,20 +979,48 @@ impl Network {
         match command {
             NetworkCommand::BroadcastMessage(message) => {
                 debug!("Broadcasting message: {:?}", message.id);
+                let message_id = message.id;
                 let network_msg = NetworkMessage::ChatMessage(message);
-                let bytes = network_msg.to_bytes()?;
+                // A duplicate is a resend of bytes gossipsub still remembers
+                // publishing, so nothing went out this time either
+                if self.publish(network_msg)? != Published::Sent {
+                    self.event_tx.send(NetworkEvent::MessageUndelivered(message_id))?;
+                }
+            }
 
-                self.swarm
-                    .behaviour_mut()
-                    .gossipsub
-                    .publish(self.gossip_topic.clone(), bytes)?;
+            NetworkCommand::EditMessage { channel_id, message_id, content, edited_at, audience } => {
+                debug!("Sending edit of message {:?} to {:?}", message_id, audience);
+                let network_msg = NetworkMessage::EditMessage { channel_id, message_id, content, edited_at };
+                match audience {
+                    Audience::Everyone => {
+                        self.publish(network_msg)?;
+                    }
+                    Audience::Members(members) => self.send_to_members(network_msg, None, &members)?,
+                    Audience::Encrypted(recipient) => {
+                        self.send_encrypted(channel_id, network_msg, None, recipient).await?;
+                    }
+                }
+            }
+
+            NetworkCommand::SendToMembers { message, members } => {
+                let message_id = message.id;
+                self.send_to_members(NetworkMessage::ChatMessage(message), Some(message_id), &members)?;
+            }
+
+            NetworkCommand::SendEncrypted { message, recipient } => {
+                let (message_id, channel_id) = (message.id, message.channel_id);
+                self.send_encrypted(channel_id, NetworkMessage::ChatMessage(message), Some(message_id), recipient)
+                    .await?;
             }
 
             NetworkCommand::ConnectToPeer(addr) => {
                 info!("Attempting to connect to peer at {}", addr);
-                match self.swarm.dial(addr.clone()) {
+                let opts = DialOpts::from(addr.clone());
+                let connection_id = opts.connection_id();
+                match self.swarm.dial(opts) {
                     Ok(_) => {
                         info!("Dialing {}", addr);
+                        self.pending_dials.insert(connection_id, addr.clone());
                         self.event_tx.send(NetworkEvent::ConnectionDialing {
                             address: addr.to_string(),
                         })?;
@@ -449,12 +1029,60 @@ impl Network {
                         warn!("Failed to dial {}: {}", addr, e);
                         self.event_tx.send(NetworkEvent::ConnectionFailed {
                             address: addr.to_string(),
-                            error: e.to_string(),
+                            error: describe_dial_error(&e),
                         })?;
                     }
                 }
             }
 
+            NetworkCommand::DialSelf(addr) => {
+                let opts = DialOpts::from(addr);
+                let connection_id = opts.connection_id();
+                match self.swarm.dial(opts) {
+                    Ok(()) => self.self_dial = Some(connection_id),
+                    Err(e) => self.event_tx.send(NetworkEvent::SelfDialed {
+                        reachable: false,
+                        detail: describe_dial_error(&e),
+                    })?,
+                }
+            }
+
+            NetworkCommand::CancelDial(addr) => {
+                let connection_ids: Vec<ConnectionId> = self
+                    .pending_dials
+                    .iter()
+                    .filter(|(_, pending)| **pending == addr)
+                    .map(|(id, _)| *id)
+                    .collect();
+
+                for connection_id in connection_ids {
+                    self.cancel_dial(connection_id)?;
+                }
+            }
+
+            NetworkCommand::CancelAllDials => {
+                let connection_ids: Vec<ConnectionId> = self.pending_dials.keys().copied().collect();
+
+                for connection_id in connection_ids {
+                    self.cancel_dial(connection_id)?;
+                }
+            }
+
+            NetworkCommand::RedialPeer { peer_id, addresses } => {
+                if addresses.is_empty() {
+                    return Ok(());
+                }
+                debug!("Redialing known peer {}", peer_id);
+                let opts = DialOpts::peer_id(peer_id)
+                    .addresses(addresses)
+                    .condition(PeerCondition::DisconnectedAndNotDialing)
+                    .build();
+                // Like mDNS auto-dials, failures are only logged
+                if let Err(e) = self.swarm.dial(opts) {
+                    debug!("Failed to redial {}: {}", peer_id, e);
+                }
+            }
+
             NetworkCommand::ListPeers => {
                 let peers: Vec<_> = self.swarm.connected_peers().collect();
                 info!("Connected peers: {:?}", peers);
@@ -463,85 +1091,587 @@ impl Network {
             NetworkCommand::AnnounceChannel(channel) => {
                 debug!("Broadcasting channel announcement: {}", channel.get_name());
                 let network_msg = NetworkMessage::ChannelAnnounce { channel };
-                let bytes = network_msg.to_bytes()?;
-
-                self.swarm
-                    .behaviour_mut()
-                    .gossipsub
-                    .publish(self.gossip_topic.clone(), bytes)?;
+                self.publish(network_msg)?;
             }
 
             NetworkCommand::RequestChannelState(channel_id) => {
                 debug!("Requesting channel state for {:?}", channel_id);
                 let network_msg = NetworkMessage::ChannelStateRequest { channel_id };
-                let bytes = network_msg.to_bytes()?;
+                self.publish(network_msg)?;
+            }
 
-                self.swarm
-                    .behaviour_mut()
-                    .gossipsub
-                    .publish(self.gossip_topic.clone(), bytes)?;
+            NetworkCommand::RespondWithChannelState(channel) => {
+                debug!("Sending channel state for {}", channel.get_name());
+                let network_msg = NetworkMessage::ChannelStateResponse { channel };
+                self.publish(network_msg)?;
             }
 
             NetworkCommand::BroadcastChannelUpdate(channel) => {
                 debug!("Broadcasting channel update: {}", channel.get_name());
                 let network_msg = NetworkMessage::ChannelUpdate { channel };
-                let bytes = network_msg.to_bytes()?;
+                self.publish(network_msg)?;
+            }
 
-                self.swarm
-                    .behaviour_mut()
-                    .gossipsub
-                    .publish(self.gossip_topic.clone(), bytes)?;
+            NetworkCommand::BroadcastChannelTombstone { channel_id, deleted_at } => {
+                debug!("Broadcasting tombstone for channel {:?}", channel_id);
+                let network_msg = NetworkMessage::ChannelTombstone { channel_id, deleted_at };
+                self.publish(network_msg)?;
             }
 
             NetworkCommand::RequestMessages { channel_id, message_ids } => {
                 debug!("Requesting {} messages for channel {:?}", message_ids.len(), channel_id);
                 let network_msg = NetworkMessage::MessageRequest { channel_id, message_ids };
-                let bytes = network_msg.to_bytes()?;
-
-                self.swarm
-                    .behaviour_mut()
-                    .gossipsub
-                    .publish(self.gossip_topic.clone(), bytes)?;
+                self.publish(network_msg)?;
             }
 
             NetworkCommand::RespondWithMessages { channel_id, messages } => {
                 debug!("Sending {} messages for channel {:?}", messages.len(), channel_id);
                 let network_msg = NetworkMessage::MessageResponse { channel_id, messages };
-                let bytes = network_msg.to_bytes()?;
-
-                self.swarm
-                    .behaviour_mut()
-                    .gossipsub
-                    .publish(self.gossip_topic.clone(), bytes)?;
+                self.publish(network_msg)?;
             }
 
-            NetworkCommand::BroadcastInventory { channel_id, message_ids } => {
+            NetworkCommand::BroadcastInventory { channel_id, message_ids, edits } => {
                 debug!("Broadcasting inventory with {} messages for channel {:?}", message_ids.len(), channel_id);
-                let network_msg = NetworkMessage::MessageInventory { channel_id, message_ids };
-                let bytes = network_msg.to_bytes()?;
-
-                self.swarm
-                    .behaviour_mut()
-                    .gossipsub
-                    .publish(self.gossip_topic.clone(), bytes)?;
+                let network_msg = NetworkMessage::MessageInventory { channel_id, message_ids, edits };
+                self.publish(network_msg)?;
             }
 
             NetworkCommand::RequestInventory { channel_id } => {
                 debug!("Requesting inventory for channel {:?}", channel_id);
                 let network_msg = NetworkMessage::InventoryRequest { channel_id };
-                let bytes = network_msg.to_bytes()?;
+                self.publish(network_msg)?;
+            }
 
-                self.swarm
-                    .behaviour_mut()
-                    .gossipsub
-                    .publish(self.gossip_topic.clone(), bytes)?;
+            NetworkCommand::RequestInventoryFrom { channel_id, peers } => {
+                debug!("Requesting inventory for channel {:?} from {} peers", channel_id, peers.len());
+                let bytes = NetworkMessage::InventoryRequest { channel_id }.to_bytes()?;
+                for peer in &peers {
+                    self.swarm.behaviour_mut().direct.send_request(peer, bytes.clone());
+                }
             }
+
+            NetworkCommand::RequestAttachment { hash, peers } => {
+                debug!("Requesting an attachment from {} peers", peers.len());
+                let bytes = NetworkMessage::AttachmentRequest { hash }.to_bytes()?;
+                for peer in &peers {
+                    self.swarm.behaviour_mut().direct.send_request(peer, bytes.clone());
+                }
+            }
+
+            NetworkCommand::SendAttachment { hash, data, peer } => {
+                debug!("Sending an attachment of {} bytes to {}", data.len(), peer);
+                let bytes = NetworkMessage::AttachmentResponse { hash, data }.to_bytes()?;
+                self.swarm.behaviour_mut().direct.send_request(&peer, bytes);
+            }
+
+            NetworkCommand::Subscribe(topic) => {
+                self.subscribe(gossipsub::IdentTopic::new(topic), None)?;
+            }
+
+            NetworkCommand::JoinChannel(channel_id) => {
+                let topic = gossipsub::IdentTopic::new(self.namespace.channel_topic(&channel_id));
+                self.subscribe(topic, Some(channel_id))?;
+            }
+
+            NetworkCommand::LeaveChannel(channel_id) => {
+                let topic = gossipsub::IdentTopic::new(self.namespace.channel_topic(&channel_id));
+                if self.swarm.behaviour_mut().gossipsub.unsubscribe(&topic) {
+                    self.subscriptions.unsubscribe(&topic.hash());
+                    info!("Left channel topic {}", topic);
+                }
+            }
+
+            NetworkCommand::Unsubscribe(topic) => {
+                let topic = gossipsub::IdentTopic::new(topic);
+                if topic.hash() == self.gossip_topic.hash() {
+                    warn!("Not leaving the chat topic {}", topic);
+                } else if self.swarm.behaviour_mut().gossipsub.unsubscribe(&topic) {
+                    self.subscriptions.unsubscribe(&topic.hash());
+                    info!("Unsubscribed from {}", topic);
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Send a frame to channel members: directly where connected, through
+    /// the mesh otherwise
+    ///
+    /// `undelivered` is the message to report if no member can be reached.
+    fn send_to_members(
+        &mut self,
+        network_msg: NetworkMessage,
+        undelivered: Option<MessageId>,
+        members: &[BurrowPeerId],
+    ) -> Result<()> {
+        let local = BurrowPeerId::from_libp2p(self.swarm.local_peer_id());
+        let connected: HashMap<BurrowPeerId, PeerId> = self
+            .swarm
+            .connected_peers()
+            .map(|peer| (BurrowPeerId::from_libp2p(peer), *peer))
+            .collect();
+        let plan = plan_delivery(members, local, &connected);
+
+        let bytes = network_msg.to_bytes()?;
+        for peer in &plan.direct {
+            self.swarm.behaviour_mut().direct.send_request(peer, bytes.clone());
+        }
+        debug!("Sent frame directly to {} members ({} unreachable)", plan.direct.len(), plan.unreachable);
+
+        // Members we aren't connected to may still be reached through the mesh
+        let gossiped = plan.unreachable > 0 && self.publish(network_msg)? == Published::Sent;
+        if plan.direct.is_empty() && !gossiped && plan.unreachable > 0 {
+            self.report_undelivered(undelivered)?;
+        }
+        Ok(())
+    }
+
+    fn report_undelivered(&self, undelivered: Option<MessageId>) -> Result<()> {
+        if let Some(message_id) = undelivered {
+            self.event_tx.send(NetworkEvent::MessageUndelivered(message_id))?;
+        }
+        Ok(())
+    }
+
+    /// Encrypt a direct channel's message or edit for the other member and
+    /// send it
+    ///
+    /// Never falls back to plaintext once sessions are set: without a
+    /// connection or a session the frame isn't sent, and `undelivered` is
+    /// reported so the message can be resent later.
+    async fn send_encrypted(
+        &mut self,
+        channel_id: ChannelId,
+        network_msg: NetworkMessage,
+        undelivered: Option<MessageId>,
+        recipient: BurrowPeerId,
+    ) -> Result<()> {
+        let Some(sessions) = self.sessions.clone() else {
+            return self.send_to_members(network_msg, undelivered, &[recipient]);
+        };
+
+        // Checked before encrypting, so an unsent frame doesn't advance the ratchet
+        let peer = self.swarm.connected_peers().find(|peer| BurrowPeerId::from_libp2p(peer) == recipient).copied();
+        let Some(peer) = peer else {
+            debug!("{} isn't connected; encrypted frame not sent", recipient.short());
+            self.report_undelivered(undelivered)?;
+            return Ok(());
+        };
+
+        let plaintext = network_msg.to_bytes()?;
+        let ciphertext = match sessions.seal(&recipient, &plaintext).await {
+            Ok(Some(ciphertext)) => ciphertext,
+            Ok(None) => {
+                self.report_undelivered(undelivered)?;
+                self.event_tx.send(NetworkEvent::EncryptionUnavailable { channel_id, peer_id: recipient })?;
+                // So a resend can go out once the bundle arrives
+                self.request_bundle(peer)?;
+                return Ok(());
+            }
+            Err(e) => {
+                warn!("Failed to encrypt frame for {}: {:#}", recipient.short(), e);
+                self.report_undelivered(undelivered)?;
+                return Ok(());
+            }
+        };
+
+        let sender = BurrowPeerId::from_libp2p(self.swarm.local_peer_id());
+        let bytes = NetworkMessage::EncryptedChatMessage { channel_id, sender, ciphertext }.to_bytes()?;
+        self.swarm.behaviour_mut().direct.send_request(&peer, bytes);
+        debug!("Sent encrypted frame to {}", recipient.short());
+        Ok(())
+    }
+
+    /// Decrypt a direct channel's message and pass it on like a plaintext one
+    async fn receive_encrypted(
+        &mut self,
+        peer: PeerId,
+        channel_id: ChannelId,
+        sender: BurrowPeerId,
+        ciphertext: &[u8],
+    ) -> Result<()> {
+        // Only the sender's own connection can carry their session's messages
+        if BurrowPeerId::from_libp2p(&peer) != sender {
+            warn!("Dropping encrypted message from {} claiming to be from {}", peer, sender.short());
+            return Ok(());
+        }
+        let Some(sessions) = self.sessions.clone() else {
+            warn!("Dropping encrypted message from {}: encryption isn't set up", peer);
+            return Ok(());
+        };
+        let ciphertext = match ciphertext_from_bytes(ciphertext) {
+            Ok(ciphertext) => ciphertext,
+            Err(e) => {
+                warn!("Unreadable ciphertext from {}: {:#}", peer, e);
+                self.event_tx.send(NetworkEvent::DecryptFailed { channel_id, peer_id: sender })?;
+                return Ok(());
+            }
+        };
+
+        match sessions.receive_message(&sender, &ciphertext).await {
+            Ok(Decrypted::Plaintext(plaintext)) => match NetworkMessage::from_bytes(&plaintext) {
+                // The sealed message must be the sender's own, in the channel it was sent for
+                Ok(NetworkMessage::ChatMessage(message))
+                    if message.author == sender && message.channel_id == channel_id =>
+                {
+                    self.event_tx.send(NetworkEvent::MessageReceived { message, source: peer })?;
+                }
+                // Likewise an edit must be the sender's own
+                Ok(NetworkMessage::EditMessage { channel_id: edited_in, message_id, content, edited_at })
+                    if edited_at.peer_id == sender && edited_in == channel_id =>
+                {
+                    self.event_tx.send(NetworkEvent::MessageEdited { channel_id, message_id, content, edited_at })?;
+                }
+                Ok(_) => warn!("Dropping encrypted frame from {}: not its own message in {:?}", peer, channel_id),
+                Err(e) => warn!("Undecodable encrypted frame from {}: {}", peer, e),
+            },
+            Ok(Decrypted::Ignored) => {}
+            Ok(Decrypted::SessionReset) => {
+                self.event_tx.send(NetworkEvent::EncryptionUnavailable { channel_id, peer_id: sender })?;
+                // The sender needs a fresh bundle to start over with us. A
+                // peer can provoke resets at will, so they're throttled too
+                if self.bundle_requests.allow(peer, Instant::now()) {
+                    self.send_bundle(peer).await;
+                }
+            }
+            // Corrupt: nothing to retry, but the user should know a message was lost
+            Err(e) => {
+                warn!("Failed to decrypt message from {}: {:#}", peer, e);
+                self.event_tx.send(NetworkEvent::DecryptFailed { channel_id, peer_id: sender })?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Whether encryption is set up and we have no session with a peer yet
+    async fn needs_session(&self, peer: &PeerId) -> bool {
+        let Some(sessions) = &self.sessions else {
+            return false;
+        };
+        match sessions.has_session(&BurrowPeerId::from_libp2p(peer)).await {
+            Ok(established) => !established,
+            Err(e) => {
+                warn!("Failed to read session state for {}: {:#}", peer, e);
+                false
+            }
+        }
+    }
+
+    /// Send a peer a fresh prekey bundle of ours, directly
+    ///
+    /// Failing to build one only costs the peer a session, so it's logged
+    /// rather than returned.
+    async fn send_bundle(&mut self, peer: PeerId) {
+        let Some(sessions) = self.sessions.clone() else {
+            return;
+        };
+        let bundle = match sessions.local_bundle().await.and_then(|bundle| PreKeyBundleParts::from_bundle(&bundle)) {
+            Ok(bundle) => bundle,
+            Err(e) => {
+                warn!("Failed to build a prekey bundle for {}: {:#}", peer, e);
+                return;
+            }
+        };
+
+        let sender = BurrowPeerId::from_libp2p(self.swarm.local_peer_id());
+        match (NetworkMessage::PreKeyBundleAnnounce { sender, bundle }).to_bytes() {
+            Ok(bytes) => {
+                self.swarm.behaviour_mut().direct.send_request(&peer, bytes);
+                debug!("Sent prekey bundle to {}", peer);
+            }
+            Err(e) => warn!("Failed to encode prekey bundle: {}", e),
+        }
+    }
+
+    /// Ask a peer for a prekey bundle, to open a session with them
+    fn request_bundle(&mut self, peer: PeerId) -> Result<()> {
+        let sender = BurrowPeerId::from_libp2p(self.swarm.local_peer_id());
+        let bytes = NetworkMessage::PreKeyBundleRequest { sender }.to_bytes()?;
+        self.swarm.behaviour_mut().direct.send_request(&peer, bytes);
+        Ok(())
+    }
+
+    /// Start a session with a peer from the bundle they sent
+    ///
+    /// A newer bundle replaces the session we had, so a peer that lost its
+    /// state can always start over. Its identity key must still match the
+    /// one we first saw for it.
+    async fn accept_bundle(&mut self, peer: PeerId, sender: BurrowPeerId, bundle: &PreKeyBundleParts) -> Result<()> {
+        if BurrowPeerId::from_libp2p(&peer) != sender {
+            warn!("Dropping prekey bundle from {} claiming to be from {}", peer, sender.short());
+            return Ok(());
+        }
+        let Some(sessions) = self.sessions.clone() else {
+            debug!("Ignoring prekey bundle from {}: encryption isn't set up", peer);
+            return Ok(());
+        };
+
+        match sessions.accept_bundle(&sender, bundle).await {
+            Ok(()) => {
+                info!("Established encryption session with {}", peer);
+                self.event_tx.send(NetworkEvent::SessionEstablished(sender))?;
+            }
+            Err(e) => warn!("Rejected prekey bundle from {}: {:#}", peer, e),
+        }
+        Ok(())
+    }
+
+    /// Publish a message on the gossip topic
+    ///
+    /// Genuine failures are returned as errors.
+    fn publish(&mut self, network_msg: NetworkMessage) -> Result<Published, NetworkError> {
+        let bytes = network_msg.to_bytes()?;
+
+        match self
+            .swarm
+            .behaviour_mut()
+            .gossipsub
+            .publish(self.gossip_topic.clone(), bytes)
+        {
+            Ok(_) => Ok(Published::Sent),
+            Err(e) => match classify_publish_error(&e) {
+                PublishFailure::Duplicate => {
+                    debug!("Skipping duplicate publish");
+                    Ok(Published::Duplicate)
+                }
+                PublishFailure::Undelivered => {
+                    debug!("Publish not delivered: {}", e);
+                    Ok(Published::Undelivered)
+                }
+                PublishFailure::Failed => Err(e.into()),
+            },
+        }
+    }
+
+    /// Join a gossip topic, recording the channel if it's a presence topic
+    fn subscribe(&mut self, topic: gossipsub::IdentTopic, channel_id: Option<ChannelId>) -> Result<()> {
+        if let Err(e) = self.swarm.behaviour_mut().gossipsub.subscribe(&topic) {
+            warn!("Failed to subscribe to {}: {}", topic, e);
+            return Ok(());
+        }
+
+        let joined = match channel_id {
+            Some(channel_id) => self.subscriptions.subscribe_channel(topic.hash(), channel_id),
+            None => self.subscriptions.subscribe(topic.hash()),
+        };
+        if joined {
+            info!("Subscribed to {}", topic);
+            self.report_topic_peers(&topic.hash())?;
+        }
+        Ok(())
+    }
+
+    /// Tell the application how many peers share one of our topics
+    ///
+    /// Peers come and go on topics we've left too; those aren't reported.
+    fn report_topic_peers(&mut self, topic: &gossipsub::TopicHash) -> Result<()> {
+        if !self.subscriptions.is_subscribed(topic) {
+            return Ok(());
+        }
+
+        let peers = self.subscriptions.peer_count(topic);
+        let event = match self.subscriptions.channel_of(topic) {
+            Some(channel_id) => NetworkEvent::TopicPeers { channel_id, count: peers },
+            None => NetworkEvent::TopicPeersChanged {
+                topic: topic.to_string(),
+                peers,
+            },
+        };
+        self.event_tx.send(event)?;
+        Ok(())
+    }
+
+    /// Publish our listen addresses after `peer_id` connected or announced itself
+    fn announce_to(&mut self, peer_id: PeerId) -> Result<()> {
+        let local_peer_id = *self.swarm.local_peer_id();
+        let network_msg = NetworkMessage::PeerAnnounce {
+            peer_id: BurrowPeerId::from_libp2p(&local_peer_id),
+            listen_addresses: self.listen_addrs.iter().map(|a| a.to_string()).collect(),
+        };
+
+        // An identical announcement already went out, which is as good
+        if self.publish(network_msg)? != Published::Undelivered {
+            self.announced_to.insert(peer_id);
+        }
+        Ok(())
+    }
+
+    /// Stop tracking a pending dial and tell the application it was cancelled
+    ///
+    /// libp2p cannot abort a dial to a bare address, so the attempt is left to
+    /// resolve and its connection closed (or error ignored) when it does.
+    fn cancel_dial(&mut self, connection_id: ConnectionId) -> Result<()> {
+        if let Some(address) = self.pending_dials.remove(&connection_id) {
+            info!("Cancelled dial to {}", address);
+            self.cancelled_dials.insert(connection_id);
+            self.event_tx.send(NetworkEvent::ConnectionCancelled {
+                address: address.to_string(),
+            })?;
         }
 
         Ok(())
     }
 }
 
+/// Gossipsub's ID for a published payload, which it deduplicates on
+///
+/// Derived from the content, so republishing identical bytes (a resend, or
+/// a second announcement of unchanged state) is a duplicate rather than new
+/// traffic. A hash collision would drop a distinct message as a duplicate,
+/// so it's SHA-256 rather than a fast hash; chat messages also lead with
+/// their own ID. The ID is kept to 32 bytes, as gossipsub remembers the IDs
+/// it has seen for a while.
+fn gossip_message_id(data: &[u8]) -> gossipsub::MessageId {
+    let digest = Sha256::new().chain_update(b"burrow gossip id v1").chain_update(data).finalize();
+    let id = match NetworkMessage::peek_message_id(data) {
+        Some(message_id) => [&message_id.0.as_bytes()[..], &digest[..16]].concat(),
+        None => digest.to_vec(),
+    };
+    gossipsub::MessageId::new(&id)
+}
+
+/// What became of a gossipsub publish
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Published {
+    /// Handed to at least one peer
+    Sent,
+    /// Identical to a message published recently, so gossipsub dropped it
+    Duplicate,
+    /// No subscribed peer could take it
+    Undelivered,
+}
+
+/// How a failed gossipsub publish should be treated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum PublishFailure {
+    /// Identical to a message already published; nothing to do
+    Duplicate,
+    /// No subscribed peer could take it (none connected, or all queues full)
+    Undelivered,
+    /// A real problem, such as signing or an oversized message
+    Failed,
+}
+
+fn classify_publish_error(error: &gossipsub::PublishError) -> PublishFailure {
+    match error {
+        gossipsub::PublishError::Duplicate => PublishFailure::Duplicate,
+        gossipsub::PublishError::NoPeersSubscribedToTopic
+        | gossipsub::PublishError::AllQueuesFull(_) => PublishFailure::Undelivered,
+        _ => PublishFailure::Failed,
+    }
+}
+
+/// Explain a failed dial in terms a user can act on
+///
+/// libp2p's own messages nest every transport attempt; this picks out the
+/// underlying cause, falling back to the full error when it isn't recognised.
+fn describe_dial_error(error: &DialError) -> String {
+    match error {
+        DialError::LocalPeerId { .. } => "that address is this node".to_string(),
+        DialError::NoAddresses => "no address to dial".to_string(),
+        DialError::DialPeerConditionFalse(_) => "already connected or connecting to that peer".to_string(),
+        DialError::Aborted => "connection attempt was aborted".to_string(),
+        DialError::WrongPeerId { obtained, .. } => {
+            format!("a different peer ({}) answered at that address", obtained)
+        }
+        DialError::Denied { cause } => format!("connection limit reached ({})", cause),
+        DialError::Transport(attempts) => match attempts.first() {
+            Some((_, TransportError::MultiaddrNotSupported(address))) => {
+                format!("unsupported address {} (expected /ip4/.../tcp/...)", address)
+            }
+            Some((_, TransportError::Other(e))) => describe_io_error(e),
+            None => error.to_string(),
+        },
+    }
+}
+
+/// Describe a transport-level I/O failure
+fn describe_io_error(error: &std::io::Error) -> String {
+    use std::io::ErrorKind;
+
+    match error.kind() {
+        ErrorKind::ConnectionRefused => "connection refused (nothing listening on that port?)".to_string(),
+        ErrorKind::TimedOut => "timed out".to_string(),
+        ErrorKind::HostUnreachable | ErrorKind::NetworkUnreachable => "host unreachable".to_string(),
+        ErrorKind::AddrNotAvailable => "address not available".to_string(),
+        // Security and multiplexer upgrades surface as generic I/O errors
+        _ if error.to_string().to_lowercase().contains("noise") => {
+            format!("encryption handshake failed ({})", error)
+        }
+        _ => error.to_string(),
+    }
+}
+
+/// Least time between prekey bundles sent to one peer at its asking
+///
+/// Every bundle saves a fresh one-time prekey of ours, so answering each
+/// request would let a peer grow our store without bound.
+const BUNDLE_REQUEST_INTERVAL: Duration = Duration::from_secs(30);
+
+/// When peers were last sent a bundle they asked for
+#[derive(Debug, Default)]
+struct BundleThrottle {
+    sent_at: HashMap<PeerId, Instant>,
+}
+
+impl BundleThrottle {
+    /// Whether to answer a bundle request from `peer` now, noting it if so
+    fn allow(&mut self, peer: PeerId, now: Instant) -> bool {
+        self.sent_at.retain(|_, sent_at| now.duration_since(*sent_at) < BUNDLE_REQUEST_INTERVAL);
+        if self.sent_at.contains_key(&peer) {
+            return false;
+        }
+        self.sent_at.insert(peer, now);
+        true
+    }
+}
+
+/// Where a message for a set of channel members goes
+#[derive(Debug, Default, PartialEq, Eq)]
+struct DeliveryPlan {
+    /// Connected members, sent the message directly
+    direct: Vec<PeerId>,
+    /// Members without a direct connection (the message falls back to gossip)
+    unreachable: usize,
+}
+
+/// Decide how to deliver a message to channel members
+///
+/// Only members are ever sent the message directly; we skip ourselves.
+fn plan_delivery(
+    members: &[BurrowPeerId],
+    local: BurrowPeerId,
+    connected: &HashMap<BurrowPeerId, PeerId>,
+) -> DeliveryPlan {
+    let mut plan = DeliveryPlan::default();
+    for member in members.iter().filter(|m| **m != local) {
+        match connected.get(member) {
+            Some(peer) => plan.direct.push(*peer),
+            None => plan.unreachable += 1,
+        }
+    }
+    plan
+}
+
+/// Parse the addresses from a peer announcement, keeping only dialable ones
+///
+/// Wildcard addresses (0.0.0.0, ::) name no particular interface and invalid
+/// strings come from misbehaving peers; both are dropped.
+fn parse_announced_addresses(addresses: &[String]) -> Vec<Multiaddr> {
+    addresses
+        .iter()
+        .filter_map(|address| address.parse::<Multiaddr>().ok())
+        .filter(|address| {
+            !address.iter().any(|protocol| match protocol {
+                Protocol::Ip4(ip) => ip.is_unspecified(),
+                Protocol::Ip6(ip) => ip.is_unspecified(),
+                _ => false,
+            })
+        })
+        .collect()
+}
+
 /// Create network channels for communication
 pub fn create_network_channels() -> (
     mpsc::UnboundedSender<NetworkEvent>,
@@ -553,3 +1683,160 @@ pub fn create_network_channels() -> (
     let (command_tx, command_rx) = mpsc::unbounded_channel();
     (event_tx, event_rx, command_tx, command_rx)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::Storage;
+
+    #[tokio::test]
+    async fn test_undecryptable_message_reported() {
+        let db_path = std::env::temp_dir().join(format!("burrow-network-{}.db", uuid::Uuid::now_v7()));
+        std::fs::File::create(&db_path).unwrap();
+        let storage = Storage::new(&db_path).await.unwrap();
+        let (event_tx, mut event_rx, _command_tx, command_rx) = create_network_channels();
+        let keypair = libp2p::identity::Keypair::generate_ed25519();
+        let mut network = Network::new(keypair, event_tx, command_rx, NetworkNamespace::default()).await.unwrap();
+        network.set_session_manager(Arc::new(SessionManager::new(storage.load_signal_store().await.unwrap())));
+
+        let peer = PeerId::random();
+        let (channel_id, sender) = (ChannelId::new(), BurrowPeerId::from_libp2p(&peer));
+        network.receive_encrypted(peer, channel_id, sender, &[0xff, 1, 2, 3]).await.unwrap();
+
+        match event_rx.try_recv() {
+            Ok(NetworkEvent::DecryptFailed { channel_id: failed_in, peer_id }) => {
+                assert_eq!((failed_in, peer_id), (channel_id, sender));
+            }
+            other => panic!("Expected the failed decrypt to be reported, got {:?}", other),
+        }
+        let _ = std::fs::remove_file(&db_path);
+    }
+
+    #[test]
+    fn test_gossip_ids_differ_by_payload() {
+        let message = |text: &str| {
+            let content = crate::types::MessageContent::new(text);
+            Message::new(ChannelId::new(), BurrowPeerId::new(), content, crate::types::VectorClock::new(), 1)
+        };
+        let chat = NetworkMessage::ChatMessage(message("hello")).to_bytes().unwrap();
+        let other_chat = NetworkMessage::ChatMessage(message("hello")).to_bytes().unwrap();
+        let inventory = NetworkMessage::InventoryRequest { channel_id: ChannelId::new() }.to_bytes().unwrap();
+        let other_inventory = NetworkMessage::InventoryRequest { channel_id: ChannelId::new() }.to_bytes().unwrap();
+
+        let ids = [&chat, &other_chat, &inventory, &other_inventory].map(|bytes| gossip_message_id(bytes));
+        assert_eq!(ids.iter().collect::<HashSet<_>>().len(), 4);
+        assert!(ids.iter().all(|id| id.0.len() == 32));
+
+        // The same bytes published again are a duplicate
+        assert_eq!(gossip_message_id(&chat), ids[0]);
+    }
+
+    #[test]
+    fn test_duplicate_publish_is_ignored() {
+        assert_eq!(
+            classify_publish_error(&gossipsub::PublishError::Duplicate),
+            PublishFailure::Duplicate
+        );
+    }
+
+    #[test]
+    fn test_unreachable_publish_is_undelivered() {
+        assert_eq!(
+            classify_publish_error(&gossipsub::PublishError::NoPeersSubscribedToTopic),
+            PublishFailure::Undelivered
+        );
+        assert_eq!(
+            classify_publish_error(&gossipsub::PublishError::AllQueuesFull(3)),
+            PublishFailure::Undelivered
+        );
+    }
+
+    #[test]
+    fn test_dial_errors_described() {
+        let address: Multiaddr = "/ip4/192.168.1.20/tcp/9000".parse().unwrap();
+
+        let refused = DialError::Transport(vec![(
+            address.clone(),
+            TransportError::Other(std::io::Error::from(std::io::ErrorKind::ConnectionRefused)),
+        )]);
+        assert!(describe_dial_error(&refused).starts_with("connection refused"));
+
+        let unsupported = DialError::Transport(vec![(
+            address.clone(),
+            TransportError::MultiaddrNotSupported(address),
+        )]);
+        assert!(describe_dial_error(&unsupported).starts_with("unsupported address"));
+
+        assert_eq!(describe_dial_error(&DialError::NoAddresses), "no address to dial");
+    }
+
+    #[test]
+    fn test_announced_addresses_filtered() {
+        let addresses = parse_announced_addresses(&[
+            "/ip4/192.168.1.20/tcp/9000".to_string(),
+            "/ip4/0.0.0.0/tcp/9000".to_string(),
+            "/ip6/::/tcp/9000".to_string(),
+            "not an address".to_string(),
+        ]);
+
+        assert_eq!(addresses, vec!["/ip4/192.168.1.20/tcp/9000".parse::<Multiaddr>().unwrap()]);
+    }
+
+    #[test]
+    fn test_bundle_requests_answered_once_per_interval() {
+        let mut throttle = BundleThrottle::default();
+        let (peer, other) = (PeerId::random(), PeerId::random());
+        let start = Instant::now();
+
+        assert!(throttle.allow(peer, start));
+        assert!(!throttle.allow(peer, start + BUNDLE_REQUEST_INTERVAL / 2));
+        assert!(throttle.allow(other, start + BUNDLE_REQUEST_INTERVAL / 2));
+        assert!(throttle.allow(peer, start + BUNDLE_REQUEST_INTERVAL));
+        // Peers that went quiet are forgotten
+        assert_eq!(throttle.sent_at.len(), 2);
+        throttle.allow(peer, start + BUNDLE_REQUEST_INTERVAL * 3);
+        assert_eq!(throttle.sent_at.len(), 1);
+    }
+
+    #[test]
+    fn test_direct_delivery_only_reaches_members() {
+        let local = BurrowPeerId::new();
+        let member = PeerId::random();
+        let non_member = PeerId::random();
+        let connected: HashMap<BurrowPeerId, PeerId> = [member, non_member]
+            .iter()
+            .map(|peer| (BurrowPeerId::from_libp2p(peer), *peer))
+            .collect();
+
+        let plan = plan_delivery(&[local, BurrowPeerId::from_libp2p(&member)], local, &connected);
+
+        assert_eq!(plan.direct, vec![member]);
+        // Every member was reachable, so nothing is gossiped for the non-member to see
+        assert_eq!(plan.unreachable, 0);
+    }
+
+    #[test]
+    fn test_unconnected_members_fall_back_to_gossip() {
+        let local = BurrowPeerId::new();
+        let offline_member = BurrowPeerId::new();
+
+        let plan = plan_delivery(&[local, offline_member], local, &HashMap::new());
+
+        assert!(plan.direct.is_empty());
+        assert_eq!(plan.unreachable, 1);
+    }
+
+    #[test]
+    fn test_other_publish_errors_fail() {
+        assert_eq!(
+            classify_publish_error(&gossipsub::PublishError::MessageTooLarge),
+            PublishFailure::Failed
+        );
+        assert_eq!(
+            classify_publish_error(&gossipsub::PublishError::TransformFailed(
+                std::io::Error::other("compression failed")
+            )),
+            PublishFailure::Failed
+        );
+    }
+}
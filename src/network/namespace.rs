@@ -0,0 +1,105 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::types::ChannelId;
+
+/// Protocol version announced via identify by instances outside any namespace
+const DEFAULT_PROTOCOL_VERSION: &str = "/burrow/0.1.0";
+
+/// Gossip topic shared by instances outside any namespace
+const DEFAULT_GOSSIP_TOPIC: &str = "burrow-chat";
+
+/// Which group of Burrow instances this one belongs to
+///
+/// Instances only talk to others in the same namespace: the namespace is part
+/// of the identify protocol version and the gossip topic. mDNS itself can't be
+/// scoped, so peers it finds from other namespaces are dropped once identify
+/// shows where they belong. The default namespace keeps the original strings,
+/// so it interoperates with older versions.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct NetworkNamespace {
+    name: Option<String>,
+}
+
+impl NetworkNamespace {
+    /// A named namespace; blank names (after trimming) mean the default one
+    pub fn new(name: &str) -> Self {
+        let name = name.trim();
+        Self {
+            name: (!name.is_empty()).then(|| name.to_string()),
+        }
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    /// Protocol version to announce via identify
+    pub fn protocol_version(&self) -> String {
+        match &self.name {
+            Some(name) => format!("/burrow/{}/0.1.0", name),
+            None => DEFAULT_PROTOCOL_VERSION.to_string(),
+        }
+    }
+
+    /// Gossip topic carrying this namespace's messages
+    pub fn gossip_topic(&self) -> String {
+        match &self.name {
+            Some(name) => format!("{}/{}", DEFAULT_GOSSIP_TOPIC, name),
+            None => DEFAULT_GOSSIP_TOPIC.to_string(),
+        }
+    }
+
+    /// Presence topic for one channel
+    ///
+    /// Nothing is published on it; peers subscribe to show they're in the
+    /// channel, which is how the UI counts who is listening.
+    pub fn channel_topic(&self, channel_id: &ChannelId) -> String {
+        format!("{}/channel/{}", self.gossip_topic(), channel_id.0)
+    }
+
+    /// Whether a peer announcing `protocol_version` is in this namespace
+    pub fn accepts(&self, protocol_version: &str) -> bool {
+        protocol_version == self.protocol_version()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_default_namespace_keeps_original_strings() {
+        let default = NetworkNamespace::new("  ");
+        assert_eq!(default, NetworkNamespace::default());
+        assert_eq!(default.protocol_version(), "/burrow/0.1.0");
+        assert_eq!(default.gossip_topic(), "burrow-chat");
+    }
+
+    #[test]
+    fn test_namespaces_do_not_cross_discover() {
+        let team_a = NetworkNamespace::new("team-a");
+        let team_b = NetworkNamespace::new("team-b");
+        let default = NetworkNamespace::default();
+
+        assert!(team_a.accepts(&NetworkNamespace::new("team-a").protocol_version()));
+        for (ours, theirs) in [(&team_a, &team_b), (&team_a, &default), (&default, &team_b)] {
+            assert!(!ours.accepts(&theirs.protocol_version()));
+            assert_ne!(ours.gossip_topic(), theirs.gossip_topic());
+            let channel_id = ChannelId::new();
+            assert_ne!(ours.channel_topic(&channel_id), theirs.channel_topic(&channel_id));
+        }
+    }
+}
@@ -14,8 +14,40 @@
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
 use libp2p::{Multiaddr, PeerId};
-use std::collections::HashMap;
-use std::time::SystemTime;
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Reconnects and messages older than this no longer affect a peer's quality
+const QUALITY_WINDOW: Duration = Duration::from_secs(10 * 60);
+
+/// Weight of the newest ping in the smoothed round-trip time
+const RTT_SMOOTHING: f64 = 0.3;
+
+/// Round-trip times up to these count as good, then fair; slower is poor
+const GOOD_RTT: Duration = Duration::from_millis(150);
+const FAIR_RTT: Duration = Duration::from_millis(500);
+
+/// Reconnects within the window up to these count as fair, then poor
+const FAIR_RECONNECTS: usize = 2;
+
+/// How healthy the link to a peer looks
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum ConnectionQuality {
+    Poor,
+    Fair,
+    Good,
+}
+
+impl fmt::Display for ConnectionQuality {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Self::Poor => "poor",
+            Self::Fair => "fair",
+            Self::Good => "good",
+        })
+    }
+}
 
 /// Information about a connected peer
 #[derive(Debug, Clone)]
@@ -24,25 +56,61 @@ pub struct PeerInfo {
     pub addresses: Vec<Multiaddr>,
     pub connected_at: SystemTime,
     pub last_seen: SystemTime,
+    /// Agent version reported via identify (e.g. "burrow/0.1.0")
+    pub agent_version: Option<String>,
+    /// Protocol version reported via identify
+    pub protocol_version: Option<String>,
+    /// Smoothed ping round-trip time, once the first ping comes back
+    pub rtt: Option<Duration>,
+    /// When recent live messages from this peer arrived
+    recent_messages: VecDeque<Instant>,
+}
+
+/// Change in overall connectivity caused by a peer joining or leaving
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Connectivity {
+    /// Still connected to at least one peer, or still connected to none
+    Unchanged,
+    /// The first peer connected
+    Online,
+    /// The last peer disconnected
+    Offline,
 }
 
 /// Peer manager tracking connected peers
 #[derive(Debug, Default)]
 pub struct PeerManager {
     peers: HashMap<PeerId, PeerInfo>,
+    /// Announced listen addresses, kept after a peer disconnects for redialing
+    known_addresses: HashMap<PeerId, Vec<Multiaddr>>,
+    /// When each peer connected recently, kept across disconnects
+    recent_connects: HashMap<PeerId, VecDeque<Instant>>,
 }
 
 impl PeerManager {
     pub fn new() -> Self {
         Self {
             peers: HashMap::new(),
+            known_addresses: HashMap::new(),
+            recent_connects: HashMap::new(),
         }
     }
 
-    /// Add a new peer
-    pub fn add_peer(&mut self, peer_id: PeerId, address: Option<Multiaddr>) {
+    /// Add a connected peer
+    ///
+    /// A peer already connected keeps what's known about it (when it
+    /// connected, identify info, RTT); only the address is added.
+    pub fn add_peer(&mut self, peer_id: PeerId, address: Option<Multiaddr>) -> Connectivity {
         let now = SystemTime::now();
+        if let Some(peer) = self.peers.get_mut(&peer_id) {
+            if let Some(address) = address.filter(|a| !peer.addresses.contains(a)) {
+                peer.addresses.push(address);
+            }
+            peer.last_seen = now;
+            return Connectivity::Unchanged;
+        }
         let addresses = address.into_iter().collect();
+        let was_offline = self.peers.is_empty();
 
         self.peers.insert(
             peer_id,
@@ -51,13 +119,35 @@ impl PeerManager {
                 addresses,
                 connected_at: now,
                 last_seen: now,
+                agent_version: None,
+                protocol_version: None,
+                rtt: None,
+                recent_messages: VecDeque::new(),
             },
         );
+
+        let now = Instant::now();
+        self.recent_connects.entry(peer_id).or_default().push_back(now);
+        // Peers that haven't connected within the window are forgotten
+        self.recent_connects.retain(|_, connects| {
+            prune(connects, now);
+            !connects.is_empty()
+        });
+
+        if was_offline {
+            Connectivity::Online
+        } else {
+            Connectivity::Unchanged
+        }
     }
 
     /// Remove a peer
-    pub fn remove_peer(&mut self, peer_id: &PeerId) {
-        self.peers.remove(peer_id);
+    pub fn remove_peer(&mut self, peer_id: &PeerId) -> Connectivity {
+        if self.peers.remove(peer_id).is_some() && self.peers.is_empty() {
+            Connectivity::Offline
+        } else {
+            Connectivity::Unchanged
+        }
     }
 
     /// Update last seen time for a peer
@@ -67,6 +157,101 @@ impl PeerManager {
         }
     }
 
+    /// Record the agent and protocol versions a peer reported via identify
+    pub fn set_identify_info(
+        &mut self,
+        peer_id: &PeerId,
+        agent_version: String,
+        protocol_version: String,
+    ) {
+        if let Some(peer) = self.peers.get_mut(peer_id) {
+            peer.agent_version = Some(agent_version);
+            peer.protocol_version = Some(protocol_version);
+        }
+    }
+
+    /// Fold a ping round-trip time into the peer's smoothed RTT
+    pub fn record_ping(&mut self, peer_id: &PeerId, rtt: Duration) {
+        if let Some(peer) = self.peers.get_mut(peer_id) {
+            peer.rtt = Some(match peer.rtt {
+                Some(smoothed) => smoothed.mul_f64(1.0 - RTT_SMOOTHING) + rtt.mul_f64(RTT_SMOOTHING),
+                None => rtt,
+            });
+            peer.last_seen = SystemTime::now();
+        }
+    }
+
+    /// Count a live message a connected peer handed us towards its throughput
+    ///
+    /// That's the peer it came from, not its author: gossip relays other
+    /// peers' messages, and it's the relaying link that just worked.
+    pub fn record_message(&mut self, source: &PeerId) {
+        let now = Instant::now();
+        if let Some(peer) = self.peers.get_mut(source) {
+            peer.recent_messages.push_back(now);
+            prune(&mut peer.recent_messages, now);
+            peer.last_seen = SystemTime::now();
+        }
+    }
+
+    /// Connection quality of a connected peer
+    pub fn quality(&self, peer_id: &PeerId) -> Option<ConnectionQuality> {
+        self.quality_at(peer_id, Instant::now())
+    }
+
+    /// Connection quality as of `now`
+    ///
+    /// Slow pings and repeated reconnects each count against a peer, and
+    /// recent messages count for it. RTT is smoothed and the other signals
+    /// only look back over `QUALITY_WINDOW`, so a peer that recovers climbs
+    /// back to good.
+    pub fn quality_at(&self, peer_id: &PeerId, now: Instant) -> Option<ConnectionQuality> {
+        let peer = self.peers.get(peer_id)?;
+        let within_window = |times: &VecDeque<Instant>| {
+            times.iter().filter(|t| now.saturating_duration_since(**t) < QUALITY_WINDOW).count()
+        };
+
+        let rtt_penalty = match peer.rtt {
+            Some(rtt) if rtt > FAIR_RTT => 2,
+            Some(rtt) if rtt > GOOD_RTT => 1,
+            _ => 0,
+        };
+        // The first connect isn't a reconnect
+        let reconnects = self
+            .recent_connects
+            .get(peer_id)
+            .map_or(0, |connects| within_window(connects).saturating_sub(1));
+        let reconnect_penalty = match reconnects {
+            0 => 0,
+            n if n <= FAIR_RECONNECTS => 1,
+            _ => 2,
+        };
+        // Messages getting through are evidence the link works
+        let throughput_credit = usize::from(within_window(&peer.recent_messages) > 0);
+
+        Some(match (rtt_penalty + reconnect_penalty).saturating_sub(throughput_credit) {
+            0 => ConnectionQuality::Good,
+            1 | 2 => ConnectionQuality::Fair,
+            _ => ConnectionQuality::Poor,
+        })
+    }
+
+    /// Record the listen addresses a peer announced, replacing older ones
+    pub fn record_addresses(&mut self, peer_id: PeerId, addresses: Vec<Multiaddr>) {
+        if addresses.is_empty() {
+            return;
+        }
+        if let Some(peer) = self.peers.get_mut(&peer_id) {
+            peer.addresses = addresses.clone();
+        }
+        self.known_addresses.insert(peer_id, addresses);
+    }
+
+    /// Addresses a peer can be redialed at (empty if it never announced any)
+    pub fn known_addresses(&self, peer_id: &PeerId) -> &[Multiaddr] {
+        self.known_addresses.get(peer_id).map(Vec::as_slice).unwrap_or_default()
+    }
+
     /// Get peer info
     pub fn get_peer(&self, peer_id: &PeerId) -> Option<&PeerInfo> {
         self.peers.get(peer_id)
@@ -82,3 +267,95 @@ impl PeerManager {
         self.peers.len()
     }
 }
+
+/// Drop times that have fallen out of the quality window
+fn prune(times: &mut VecDeque<Instant>, now: Instant) {
+    while times.front().is_some_and(|t| now.saturating_duration_since(*t) >= QUALITY_WINDOW) {
+        times.pop_front();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_inbound_peer_redialable_after_announce() {
+        let mut manager = PeerManager::new();
+        let peer_id = PeerId::random();
+
+        // Inbound connections carry no dialable address
+        manager.add_peer(peer_id, None);
+        assert!(manager.known_addresses(&peer_id).is_empty());
+
+        let address: Multiaddr = "/ip4/192.168.1.20/tcp/9000".parse().unwrap();
+        manager.record_addresses(peer_id, vec![address.clone()]);
+        assert_eq!(manager.get_peer(&peer_id).unwrap().addresses, vec![address.clone()]);
+
+        // Still known after the connection goes away
+        manager.remove_peer(&peer_id);
+        assert_eq!(manager.known_addresses(&peer_id), &[address][..]);
+    }
+
+    #[test]
+    fn test_adding_connected_peer_keeps_its_info() {
+        let mut manager = PeerManager::new();
+        let peer_id = PeerId::random();
+        let first: Multiaddr = "/ip4/192.168.1.20/tcp/9000".parse().unwrap();
+        let second: Multiaddr = "/ip4/10.0.0.5/tcp/9000".parse().unwrap();
+
+        assert_eq!(manager.add_peer(peer_id, Some(first.clone())), Connectivity::Online);
+        manager.set_identify_info(&peer_id, "burrow/0.1.0".to_string(), "/burrow/1.0.0".to_string());
+        let connected_at = manager.get_peer(&peer_id).unwrap().connected_at;
+
+        assert_eq!(manager.add_peer(peer_id, Some(second.clone())), Connectivity::Unchanged);
+        let peer = manager.get_peer(&peer_id).unwrap();
+        assert_eq!(peer.connected_at, connected_at);
+        assert_eq!(peer.agent_version.as_deref(), Some("burrow/0.1.0"));
+        assert_eq!(peer.addresses, vec![first, second]);
+        assert_eq!(manager.quality(&peer_id), Some(ConnectionQuality::Good), "not a reconnect");
+    }
+
+    #[test]
+    fn test_quality_degrades_and_recovers() {
+        let mut manager = PeerManager::new();
+        let peer_id = PeerId::random();
+
+        manager.add_peer(peer_id, None);
+        assert_eq!(manager.quality(&peer_id), Some(ConnectionQuality::Good));
+
+        // A slow link is fair, and flapping on top of it is poor
+        manager.record_ping(&peer_id, Duration::from_millis(300));
+        assert_eq!(manager.quality(&peer_id), Some(ConnectionQuality::Fair));
+        for _ in 0..3 {
+            manager.remove_peer(&peer_id);
+            manager.add_peer(peer_id, None);
+        }
+        manager.record_ping(&peer_id, Duration::from_millis(900));
+        assert_eq!(manager.quality(&peer_id), Some(ConnectionQuality::Poor));
+
+        // Fast pings pull the smoothed RTT back down, and the reconnects
+        // age out of the window
+        for _ in 0..20 {
+            manager.record_ping(&peer_id, Duration::from_millis(20));
+        }
+        assert!(manager.get_peer(&peer_id).unwrap().rtt.unwrap() < GOOD_RTT);
+        let later = Instant::now() + QUALITY_WINDOW;
+        assert_eq!(manager.quality_at(&peer_id, later), Some(ConnectionQuality::Good));
+
+        manager.remove_peer(&peer_id);
+        assert_eq!(manager.quality(&peer_id), None);
+    }
+
+    #[test]
+    fn test_messages_offset_one_penalty() {
+        let mut manager = PeerManager::new();
+        let peer_id = PeerId::random();
+        manager.add_peer(peer_id, None);
+        manager.record_ping(&peer_id, Duration::from_millis(300));
+        assert_eq!(manager.quality(&peer_id), Some(ConnectionQuality::Fair));
+
+        manager.record_message(&peer_id);
+        assert_eq!(manager.quality(&peer_id), Some(ConnectionQuality::Good));
+    }
+}
@@ -0,0 +1,217 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::{create_network_channels, Network, NetworkCommand, NetworkEvent, NetworkNamespace};
+use anyhow::Result;
+use libp2p::{identity::Keypair, multiaddr::Protocol, Multiaddr, PeerId};
+use std::collections::HashSet;
+use std::fmt;
+use std::time::Duration;
+
+/// What a short run of the network found out about our connectivity
+#[derive(Debug)]
+pub struct SelfTestReport {
+    pub peer_id: PeerId,
+    pub port: u16,
+    /// Why the listener couldn't be started, if it couldn't
+    pub listener_error: Option<String>,
+    pub listen_addrs: Vec<Multiaddr>,
+    /// Our addresses as seen by peers that identified us
+    pub observed_addrs: Vec<Multiaddr>,
+    /// Why mDNS discovery couldn't start, if it couldn't
+    pub mdns_error: Option<String>,
+    pub discovered_peers: usize,
+    pub connected_peers: usize,
+    /// Outcome of dialing our own listener (None if there was nothing to dial)
+    pub self_dial: Option<Result<String, String>>,
+}
+
+impl SelfTestReport {
+    /// Whether others should be able to connect to us
+    pub fn passed(&self) -> bool {
+        self.listener_error.is_none() && matches!(self.self_dial, Some(Ok(_)))
+    }
+}
+
+impl fmt::Display for SelfTestReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "Burrow connectivity self-test")?;
+        writeln!(f, "  peer id:        {}", self.peer_id)?;
+
+        match &self.listener_error {
+            None => writeln!(f, "  listener:       ok (tcp port {})", self.port)?,
+            Some(error) => writeln!(f, "  listener:       FAILED on tcp port {}: {}", self.port, error)?,
+        }
+
+        writeln!(f, "  listening on:   {}", format_addrs(&self.listen_addrs))?;
+        writeln!(f, "  seen by peers:  {}", format_addrs(&self.observed_addrs))?;
+        match &self.mdns_error {
+            None => writeln!(
+                f,
+                "  mDNS:           running, {} peers discovered, {} connected",
+                self.discovered_peers, self.connected_peers
+            )?,
+            Some(error) => writeln!(
+                f,
+                "  mDNS:           unavailable ({}), {} peers connected",
+                error, self.connected_peers
+            )?,
+        }
+
+        match &self.self_dial {
+            Some(Ok(detail)) => writeln!(f, "  loopback dial:  ok ({})", detail)?,
+            Some(Err(detail)) => writeln!(f, "  loopback dial:  FAILED ({})", detail)?,
+            None => writeln!(f, "  loopback dial:  not attempted (no listen address)")?,
+        }
+
+        write!(f, "Result: {}", if self.passed() { "ok" } else { "problems found" })
+    }
+}
+
+fn format_addrs(addrs: &[Multiaddr]) -> String {
+    if addrs.is_empty() {
+        return "(none)".to_string();
+    }
+    addrs.iter().map(|a| a.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+/// Pick the listen address to dial ourselves on, preferring loopback
+fn self_dial_address(listen_addrs: &[Multiaddr]) -> Option<Multiaddr> {
+    listen_addrs
+        .iter()
+        .find(|addr| addr.iter().any(|p| matches!(p, Protocol::Ip4(ip) if ip.is_loopback())))
+        .or(listen_addrs.first())
+        .cloned()
+}
+
+/// Run the network for `duration` and report what worked
+///
+/// Listens on `port` like a normal start, dials its own listener once it's
+/// up, and collects discoveries and identify results from any peers found
+/// in the meantime.
+pub async fn run(keypair: Keypair, port: u16, namespace: NetworkNamespace, duration: Duration) -> Result<SelfTestReport> {
+    let (event_tx, mut event_rx, command_tx, command_rx) = create_network_channels();
+    let mut network = Network::new(keypair.clone(), event_tx, command_rx, namespace).await?;
+
+    let mut report = SelfTestReport {
+        peer_id: keypair.public().to_peer_id(),
+        port,
+        listener_error: network.listen(port).err().map(|e| format!("{:#}", anyhow::Error::from(e))),
+        listen_addrs: Vec::new(),
+        observed_addrs: Vec::new(),
+        mdns_error: network.mdns_error().map(str::to_string),
+        discovered_peers: 0,
+        connected_peers: 0,
+        self_dial: None,
+    };
+
+    // The network's future isn't Send (see `Network::run`), so it runs on this thread
+    let local = tokio::task::LocalSet::new();
+    let network_handle = local.spawn_local(network.run());
+    let deadline = tokio::time::sleep(duration);
+    tokio::pin!(deadline);
+
+    let mut discovered = HashSet::new();
+    let mut connected = HashSet::new();
+    let mut dialed_self = false;
+
+    local
+        .run_until(async {
+            loop {
+                tokio::select! {
+                    _ = &mut deadline => break,
+                    Some(event) = event_rx.recv() => match event {
+                        NetworkEvent::ListeningOn(addr) => report.listen_addrs.push(addr),
+                        NetworkEvent::PeerDiscovered(peer_id) => {
+                            discovered.insert(peer_id);
+                        }
+                        NetworkEvent::PeerConnected { peer_id, .. } => {
+                            connected.insert(peer_id);
+                        }
+                        NetworkEvent::PeerIdentified { observed_addr, .. } => {
+                            if !report.observed_addrs.contains(&observed_addr) {
+                                report.observed_addrs.push(observed_addr);
+                            }
+                        }
+                        NetworkEvent::SelfDialed { reachable, detail } => {
+                            report.self_dial = Some(if reachable { Ok(detail) } else { Err(detail) });
+                        }
+                        _ => {}
+                    }
+                }
+
+                // Listen addresses arrive one per interface; dial once the first is up
+                if !dialed_self {
+                    if let Some(addr) = self_dial_address(&report.listen_addrs) {
+                        command_tx.send(NetworkCommand::DialSelf(addr))?;
+                        dialed_self = true;
+                    }
+                }
+            }
+            Ok::<_, anyhow::Error>(())
+        })
+        .await?;
+
+    network_handle.abort();
+
+    if dialed_self && report.self_dial.is_none() {
+        report.self_dial = Some(Err("no answer before the test ended".to_string()));
+    }
+    report.discovered_peers = discovered.len();
+    report.connected_peers = connected.len();
+
+    Ok(report)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_self_dial_prefers_loopback() {
+        let lan: Multiaddr = "/ip4/192.168.1.20/tcp/9000".parse().unwrap();
+        let loopback: Multiaddr = "/ip4/127.0.0.1/tcp/9000".parse().unwrap();
+
+        assert_eq!(self_dial_address(&[lan.clone(), loopback.clone()]), Some(loopback));
+        assert_eq!(self_dial_address(&[lan.clone()]), Some(lan));
+        assert_eq!(self_dial_address(&[]), None);
+    }
+
+    #[test]
+    fn test_report_fails_without_self_dial() {
+        let mut report = SelfTestReport {
+            peer_id: PeerId::random(),
+            port: 9000,
+            listener_error: None,
+            listen_addrs: vec!["/ip4/127.0.0.1/tcp/9000".parse().unwrap()],
+            observed_addrs: Vec::new(),
+            mdns_error: None,
+            discovered_peers: 0,
+            connected_peers: 0,
+            self_dial: Some(Err("timed out".to_string())),
+        };
+        assert!(!report.passed());
+        assert!(report.to_string().contains("loopback dial:  FAILED (timed out)"));
+        assert!(report.to_string().contains("mDNS:           running, 0 peers discovered"));
+
+        // Discovery failing doesn't fail the test; peers can still dial us
+        report.mdns_error = Some("no multicast socket".to_string());
+        assert!(report.to_string().contains("mDNS:           unavailable (no multicast socket)"));
+
+        report.self_dial = Some(Ok("connected and completed the handshake".to_string()));
+        assert!(report.passed());
+        assert!(report.to_string().ends_with("Result: ok"));
+    }
+}
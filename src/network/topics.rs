@@ -0,0 +1,158 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::types::ChannelId;
+use libp2p::gossipsub::TopicHash;
+use libp2p::PeerId;
+use std::collections::{HashMap, HashSet};
+
+/// The gossipsub topics we're subscribed to, and the peers subscribed to each
+///
+/// gossipsub tells us when a peer subscribes or unsubscribes, but not when a
+/// disconnect drops all of its subscriptions at once, so that is recorded
+/// here too. Each channel has a topic of its own that carries no messages:
+/// peers subscribe to it to show they're present in the channel.
+#[derive(Debug, Default)]
+pub struct Subscriptions {
+    ours: HashSet<TopicHash>,
+    peers: HashMap<TopicHash, HashSet<PeerId>>,
+    channels: HashMap<TopicHash, ChannelId>,
+}
+
+impl Subscriptions {
+    /// Record that we joined a topic; false if we already had
+    pub fn subscribe(&mut self, topic: TopicHash) -> bool {
+        self.ours.insert(topic)
+    }
+
+    /// Record that we joined a channel's presence topic; false if we already had
+    pub fn subscribe_channel(&mut self, topic: TopicHash, channel_id: ChannelId) -> bool {
+        self.channels.insert(topic.clone(), channel_id);
+        self.subscribe(topic)
+    }
+
+    /// Record that we left a topic; false if we weren't in it
+    pub fn unsubscribe(&mut self, topic: &TopicHash) -> bool {
+        self.channels.remove(topic);
+        self.ours.remove(topic)
+    }
+
+    /// The channel a presence topic belongs to
+    pub fn channel_of(&self, topic: &TopicHash) -> Option<ChannelId> {
+        self.channels.get(topic).copied()
+    }
+
+    pub fn is_subscribed(&self, topic: &TopicHash) -> bool {
+        self.ours.contains(topic)
+    }
+
+    /// Topics we're subscribed to
+    pub fn topics(&self) -> impl Iterator<Item = &TopicHash> {
+        self.ours.iter()
+    }
+
+    /// Number of peers subscribed to a topic
+    pub fn peer_count(&self, topic: &TopicHash) -> usize {
+        self.peers.get(topic).map_or(0, HashSet::len)
+    }
+
+    /// Record a peer joining a topic; true if that changed its peers
+    pub fn peer_subscribed(&mut self, peer: PeerId, topic: TopicHash) -> bool {
+        self.peers.entry(topic).or_default().insert(peer)
+    }
+
+    /// Record a peer leaving a topic; true if that changed its peers
+    pub fn peer_unsubscribed(&mut self, peer: &PeerId, topic: &TopicHash) -> bool {
+        let Some(peers) = self.peers.get_mut(topic) else {
+            return false;
+        };
+        let removed = peers.remove(peer);
+        if peers.is_empty() {
+            self.peers.remove(topic);
+        }
+        removed
+    }
+
+    /// Drop a disconnected peer from every topic, returning the topics it was in
+    pub fn peer_disconnected(&mut self, peer: &PeerId) -> Vec<TopicHash> {
+        let topics: Vec<TopicHash> = self
+            .peers
+            .iter()
+            .filter(|(_, peers)| peers.contains(peer))
+            .map(|(topic, _)| topic.clone())
+            .collect();
+        for topic in &topics {
+            self.peer_unsubscribed(peer, topic);
+        }
+        topics
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_peer_counts_follow_subscriptions_and_disconnects() {
+        let chat = TopicHash::from_raw("burrow-chat");
+        let other = TopicHash::from_raw("burrow-chat/other");
+        let (alice, bob) = (PeerId::random(), PeerId::random());
+        let mut subscriptions = Subscriptions::default();
+
+        assert!(subscriptions.subscribe(chat.clone()));
+        assert!(!subscriptions.subscribe(chat.clone()));
+        assert!(subscriptions.is_subscribed(&chat));
+
+        assert!(subscriptions.peer_subscribed(alice, chat.clone()));
+        assert!(!subscriptions.peer_subscribed(alice, chat.clone()));
+        subscriptions.peer_subscribed(bob, chat.clone());
+        subscriptions.peer_subscribed(alice, other.clone());
+        assert_eq!(subscriptions.peer_count(&chat), 2);
+
+        assert!(subscriptions.peer_unsubscribed(&bob, &chat));
+        assert!(!subscriptions.peer_unsubscribed(&bob, &chat));
+        assert_eq!(subscriptions.peer_count(&chat), 1);
+
+        let mut left = subscriptions.peer_disconnected(&alice);
+        left.sort_by(|a, b| a.as_str().cmp(b.as_str()));
+        assert_eq!(left, vec![chat.clone(), other.clone()]);
+        assert_eq!(subscriptions.peer_count(&chat), 0);
+        assert_eq!(subscriptions.peer_count(&other), 0);
+
+        assert!(subscriptions.unsubscribe(&chat));
+        assert!(!subscriptions.is_subscribed(&chat));
+    }
+
+    #[test]
+    fn test_channel_topics_map_back_to_channels() {
+        let channel_id = ChannelId::new();
+        let topic = TopicHash::from_raw(format!("burrow-chat/channel/{}", channel_id.0));
+        let peer = PeerId::random();
+        let mut subscriptions = Subscriptions::default();
+
+        assert!(subscriptions.subscribe_channel(topic.clone(), channel_id));
+        assert_eq!(subscriptions.channel_of(&topic), Some(channel_id));
+        assert_eq!(subscriptions.channel_of(&TopicHash::from_raw("burrow-chat")), None);
+
+        // The last listener leaving takes the count to zero, not away
+        subscriptions.peer_subscribed(peer, topic.clone());
+        assert_eq!(subscriptions.peer_count(&topic), 1);
+        subscriptions.peer_disconnected(&peer);
+        assert_eq!(subscriptions.peer_count(&topic), 0);
+
+        assert!(subscriptions.unsubscribe(&topic));
+        assert_eq!(subscriptions.channel_of(&topic), None);
+    }
+}
@@ -0,0 +1,651 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! The local node's message state, and sending on top of it
+//!
+//! The TUI drives a `BurrowNode` for everything it sends; scripts and tests
+//! can use one directly.
+
+use crate::config::ChannelRef;
+use crate::crdt::Timestamp;
+use crate::dag::{IngestMode, MessageDAG};
+use crate::network::{Audience, NetworkCommand};
+use crate::storage::Storage;
+use crate::transform::ContentPipeline;
+use crate::types::{
+    Channel, ChannelId, LamportClocks, Message, MessageContent, MessageId, PeerId, VectorClock,
+};
+use anyhow::{anyhow, bail, Result};
+use tokio::sync::mpsc;
+
+/// Recent messages loaded per channel at startup, besides its heads
+///
+/// The rest of a channel's history is loaded when it's opened.
+pub const STARTUP_WINDOW: usize = 500;
+
+/// What this peer has seen and sent: stored history, the DAG over it, and
+/// the clocks new messages are stamped with
+pub struct BurrowNode {
+    pub peer_id: PeerId,
+    pub storage: Storage,
+    pub dag: MessageDAG,
+    pub vector_clock: VectorClock,
+    pub lamport_clocks: LamportClocks,
+    /// Transforms applied to sent and received content
+    pub content_pipeline: ContentPipeline,
+    /// Whether sends may be encrypted, which needs a channel's members known
+    encrypting: bool,
+    commands: mpsc::UnboundedSender<NetworkCommand>,
+}
+
+impl BurrowNode {
+    /// Wrap loaded state; the clocks must already cover everything in the DAG
+    pub fn new(
+        peer_id: PeerId,
+        storage: Storage,
+        dag: MessageDAG,
+        vector_clock: VectorClock,
+        lamport_clocks: LamportClocks,
+        commands: mpsc::UnboundedSender<NetworkCommand>,
+    ) -> Self {
+        Self {
+            peer_id,
+            storage,
+            dag,
+            vector_clock,
+            lamport_clocks,
+            content_pipeline: ContentPipeline::with_builtins(),
+            encrypting: false,
+            commands,
+        }
+    }
+
+    /// Note that Signal sessions are set up, so direct channels go encrypted
+    pub fn set_encrypting(&mut self, encrypting: bool) {
+        self.encrypting = encrypting;
+    }
+
+    /// Why we can't post in a channel right now, if we can't
+    pub fn send_blocked_reason(&self, channel: &Channel) -> Option<&'static str> {
+        if channel.is_deleted() {
+            return Some("Channel was deleted");
+        }
+        // Encrypted send needs known recipients; wait for the channel state
+        if self.encrypting && channel.has_unknown_membership() {
+            return Some("Channel membership unknown (Ctrl+R to request channel info)");
+        }
+        if !channel.can_post(&self.peer_id) {
+            return Some("Only admins can post in this channel");
+        }
+        None
+    }
+
+    /// Send plain text to a stored channel, returning the created message
+    pub async fn send_message(&mut self, channel_id: ChannelId, text: &str) -> Result<Message> {
+        let channel = self
+            .storage
+            .get_channel(channel_id)
+            .await?
+            .ok_or_else(|| anyhow!("Unknown channel {}", channel_id.0))?;
+        self.publish(&channel, MessageContent::new(text), None, None).await
+    }
+
+    /// Create, store and send a message on top of the channel's heads
+    ///
+    /// When `quoted` is a message further back, it's added as a parent so
+    /// the message reads as a reply to it. `expires_at` is the HLC physical
+    /// time after which the message disappears, if it should. Fails without
+    /// sending anything when `send_blocked_reason` gives a reason; the
+    /// content goes through the outgoing pipeline first.
+    pub async fn publish(
+        &mut self,
+        channel: &Channel,
+        content: MessageContent,
+        quoted: Option<MessageId>,
+        expires_at: Option<u64>,
+    ) -> Result<Message> {
+        if let Some(reason) = self.send_blocked_reason(channel) {
+            bail!(reason);
+        }
+        let content = self.content_pipeline.outgoing(content);
+
+        let lamport_timestamp = self.lamport_clocks.tick(channel.id);
+        self.vector_clock.increment(self.peer_id);
+
+        let parent_hashes = match quoted {
+            Some(quoted) => self.dag.reply_parents(&channel.id, quoted),
+            None => self.dag.get_heads(&channel.id),
+        };
+
+        let mut message = Message::new(channel.id, self.peer_id, content, self.vector_clock.clone(), lamport_timestamp);
+        message.parent_hashes = parent_hashes;
+        message.expires_at = expires_at;
+
+        self.storage.store_message(&message).await?;
+        if let Err(e) = self.dag.ingest(message.clone(), IngestMode::Strict) {
+            tracing::warn!("Failed to add message to DAG: {}", e);
+        }
+        // Heads only speed up the next startup, so failing to save them isn't fatal
+        if let Err(e) = self.save_heads(channel.id).await {
+            tracing::warn!("Failed to save channel heads: {:#}", e);
+        }
+
+        self.send(channel, &message)?;
+        Ok(message)
+    }
+
+    /// Replace the content of one of our messages and tell peers
+    ///
+    /// `edited_at` comes from the HLC, and decides which edit wins when
+    /// several are made concurrently. Kind and priority stay as they were.
+    pub async fn edit_message(&mut self, message_id: MessageId, text: &str, edited_at: Timestamp) -> Result<Message> {
+        let current = self
+            .dag
+            .get_message(&message_id)
+            .ok_or_else(|| anyhow!("Unknown message {}", message_id.0))?;
+        if !current.editable_by(&self.peer_id) {
+            bail!("Message can't be edited");
+        }
+        let content = MessageContent {
+            text: text.to_string(),
+            ..current.current_content().clone()
+        };
+
+        let channel = self
+            .storage
+            .get_channel(current.channel_id)
+            .await?
+            .ok_or_else(|| anyhow!("Unknown channel {}", current.channel_id.0))?;
+
+        let message = self
+            .apply_edit(message_id, content.clone(), edited_at)
+            .await?
+            .ok_or_else(|| anyhow!("Edit changes nothing"))?;
+        // An edit goes wherever the message went, so it's no more public
+        self.commands.send(NetworkCommand::EditMessage {
+            channel_id: message.channel_id,
+            message_id,
+            content,
+            edited_at,
+            audience: self.audience(&channel),
+        })?;
+        Ok(message)
+    }
+
+    /// Apply an edit, ours or a peer's, storing it if it's now the latest
+    ///
+    /// Returns the edited message when what it shows changed. Edits of
+    /// messages we don't have are dropped.
+    pub async fn apply_edit(
+        &mut self,
+        message_id: MessageId,
+        content: MessageContent,
+        edited_at: Timestamp,
+    ) -> Result<Option<Message>> {
+        let mut message = match self.dag.get_message(&message_id) {
+            Some(message) => message.clone(),
+            None => match self.storage.get_message(message_id).await? {
+                Some(message) => message,
+                None => return Ok(None),
+            },
+        };
+        if !message.apply_edit(content, edited_at) {
+            return Ok(None);
+        }
+
+        self.storage.store_message_edit(&message).await?;
+        self.dag.update_edit(&message);
+        Ok(Some(message))
+    }
+
+    /// Save a channel's heads and orphans after its part of the DAG changed
+    pub async fn save_heads(&self, channel_id: ChannelId) -> Result<()> {
+        self.storage.store_heads(channel_id, &self.dag.get_heads(&channel_id), &self.dag.orphans_in(&channel_id)).await
+    }
+
+    /// Load whatever of a channel's stored history startup left out
+    pub async fn load_full_history(&mut self, channel_id: ChannelId) -> Result<()> {
+        load_full_history(&self.storage, &mut self.dag, channel_id).await
+    }
+
+    /// Hand a message to the network, as-is
+    ///
+    /// Also used to retry messages that didn't reach anyone: peers that
+    /// already have the message drop the copy.
+    pub fn send(&self, channel: &Channel, message: &Message) -> Result<()> {
+        let command = match self.audience(channel) {
+            Audience::Encrypted(recipient) => NetworkCommand::SendEncrypted {
+                message: message.clone(),
+                recipient,
+            },
+            Audience::Members(members) => NetworkCommand::SendToMembers {
+                message: message.clone(),
+                members,
+            },
+            Audience::Everyone => NetworkCommand::BroadcastMessage(message.clone()),
+        };
+        self.commands.send(command)?;
+        Ok(())
+    }
+
+    /// Who a channel's messages and edits go to
+    ///
+    /// Closed channels go straight to their members so the message doesn't
+    /// flood every peer on the shared topic; a direct channel's goes
+    /// encrypted to the other member.
+    pub fn audience(&self, channel: &Channel) -> Audience {
+        if let Some(recipient) = channel.direct_recipient(&self.peer_id) {
+            Audience::Encrypted(recipient)
+        } else if channel.is_closed(&self.peer_id) {
+            Audience::Members(channel.get_members())
+        } else {
+            Audience::Everyone
+        }
+    }
+}
+
+/// Build the DAG over the given channels' stored messages
+///
+/// Channels with saved heads only load those and their `STARTUP_WINDOW`
+/// most recent messages. A channel whose heads row is missing, unreadable
+/// or out of date is rebuilt from all its messages instead, and its heads
+/// are saved for next time when `save_heads` is set. Stored messages
+/// whose parents never arrived are held back as orphans until peers
+/// supply the parents.
+pub async fn load_dag(storage: &Storage, channels: &[ChannelId], save_heads: bool) -> Result<MessageDAG> {
+    let mut dag = MessageDAG::new();
+    for channel_id in channels {
+        if load_from_heads(storage, &mut dag, *channel_id).await? {
+            continue;
+        }
+        let channel_messages = storage.get_channel_messages(*channel_id).await?;
+        if let Err(e) = dag.ingest_all(channel_messages, IngestMode::BufferOrphans) {
+            tracing::warn!("Failed to load messages into DAG: {}", e);
+        }
+        if save_heads {
+            storage.store_heads(*channel_id, &dag.get_heads(channel_id), &dag.orphans_in(channel_id)).await?;
+        }
+    }
+    for channel_id in dag.channels_with_orphans() {
+        tracing::warn!(
+            "Channel {} has stored messages missing parents; they're hidden until the parents arrive",
+            channel_id.0
+        );
+    }
+    Ok(dag)
+}
+
+/// Load a channel from its saved heads, returning false if it needs a full rebuild
+async fn load_from_heads(storage: &Storage, dag: &mut MessageDAG, channel_id: ChannelId) -> Result<bool> {
+    let saved = match storage.get_heads(channel_id).await {
+        Ok(Some(saved)) => saved,
+        Ok(None) => return Ok(false),
+        Err(e) => {
+            tracing::warn!("Rebuilding channel {} from all its messages: {:#}", channel_id.0, e);
+            return Ok(false);
+        }
+    };
+
+    let mut window = storage.get_recent_channel_messages(channel_id, STARTUP_WINDOW).await?;
+    // A head on a branch nobody has added to lately can be older than the
+    // window, and an orphan can wait for its parents any length of time
+    let older: Vec<MessageId> = saved
+        .heads
+        .iter()
+        .chain(&saved.orphans)
+        .filter(|id| !window.iter().any(|m| m.id == **id))
+        .copied()
+        .collect();
+    window.extend(storage.get_messages_by_ids(&older).await?);
+    let stored = storage.get_channel_message_ids(channel_id).await?;
+
+    let loaded = dag.load_from_heads(channel_id, &saved.heads, &saved.orphans, &stored, window);
+    if !loaded {
+        tracing::info!("Saved heads of channel {} are out of date; rebuilding", channel_id.0);
+    }
+    Ok(loaded)
+}
+
+/// Clocks for new messages that carry on from stored history
+///
+/// Each channel's Lamport clock starts at its highest stored timestamp, so
+/// the next message sorts after everything there, orphans included. The
+/// vector clock merges the loaded messages' clocks: the heads are always
+/// loaded, and a message's clock covers those of its ancestors.
+pub async fn stored_clocks(storage: &Storage, dag: &MessageDAG, peer_id: PeerId) -> Result<(VectorClock, LamportClocks)> {
+    let mut lamport_clocks = LamportClocks::new();
+    for (channel_id, lamport) in storage.get_max_lamports().await? {
+        lamport_clocks.observe(channel_id, lamport);
+    }
+
+    let mut vector_clock = VectorClock::new();
+    vector_clock.increment(peer_id);
+    for message in dag.all_messages() {
+        vector_clock.merge(&message.vector_clock);
+    }
+    Ok((vector_clock, lamport_clocks))
+}
+
+/// Add the stored messages of a channel that `load_dag` left out
+pub async fn load_full_history(storage: &Storage, dag: &mut MessageDAG, channel_id: ChannelId) -> Result<()> {
+    if !dag.has_unloaded(&channel_id) {
+        return Ok(());
+    }
+    let channel_messages = storage.get_channel_messages(channel_id).await?;
+    if let Err(e) = dag.load_history(channel_messages) {
+        tracing::warn!("Failed to load messages into DAG: {}", e);
+    }
+    Ok(())
+}
+
+/// Make sure configured channels exist locally
+///
+/// Run before stored channels are loaded. An unknown ID gets a placeholder
+/// and a request for its state, so the real name and members arrive from
+/// peers and its messages sync like any other channel's. An unknown name
+/// creates a new channel (a different one on every node, so share IDs to
+/// follow the same channel everywhere). Deleted channels stay deleted.
+/// Returns the IDs of the channels to follow, whose topics the caller joins
+/// along with the rest.
+pub async fn ensure_channels(
+    storage: &Storage,
+    peer_id: PeerId,
+    wanted: &[ChannelRef],
+    commands: &mpsc::UnboundedSender<NetworkCommand>,
+) -> Result<Vec<ChannelId>> {
+    let mut followed = Vec::new();
+    for channel in wanted {
+        let channel_id = match channel {
+            ChannelRef::Id(id) => match storage.get_channel(*id).await? {
+                Some(channel) if channel.is_deleted() => {
+                    tracing::warn!("Configured channel {} was deleted; not joining it", id.0);
+                    continue;
+                }
+                Some(_) => *id,
+                None => {
+                    let placeholder = Channel::placeholder(*id, format!("channel-{}", id.short()), peer_id);
+                    storage.store_channel(&placeholder).await?;
+                    commands.send(NetworkCommand::RequestChannelState(*id))?;
+                    tracing::info!("Created placeholder for configured channel {}", id.0);
+                    *id
+                }
+            },
+            ChannelRef::Name(name) => {
+                let existing = storage.get_all_channels().await?.into_iter().find(|c| c.get_name() == name);
+                match existing {
+                    Some(channel) => channel.id,
+                    None => {
+                        let channel = Channel::new(name.clone(), peer_id);
+                        storage.store_channel(&channel).await?;
+                        commands.send(NetworkCommand::AnnounceChannel(channel.clone()))?;
+                        tracing::info!("Created configured channel {}", name);
+                        channel.id
+                    }
+                }
+            }
+        };
+        if !followed.contains(&channel_id) {
+            followed.push(channel_id);
+        }
+    }
+    Ok(followed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::create_network_channels;
+    use crate::storage::SavedHeads;
+    use crate::types::PostPolicy;
+
+    #[tokio::test]
+    async fn test_sent_message_builds_on_prior_heads() {
+        let storage = Storage::new(":memory:").await.unwrap();
+        let peer_id = PeerId::new();
+        let channel = Channel::new("general".to_string(), peer_id);
+        storage.store_channel(&channel).await.unwrap();
+
+        let (_, _, command_tx, mut command_rx) = create_network_channels();
+        let mut node =
+            BurrowNode::new(peer_id, storage, MessageDAG::new(), VectorClock::new(), LamportClocks::new(), command_tx);
+
+        let first = node.send_message(channel.id, "first").await.unwrap();
+        assert!(first.parent_hashes.is_empty());
+
+        let heads = node.dag.get_heads(&channel.id);
+        let second = node.send_message(channel.id, "second").await.unwrap();
+        assert_eq!(second.parent_hashes, heads);
+        assert_eq!(heads, vec![first.id]);
+        assert!(second.lamport_timestamp > first.lamport_timestamp);
+        assert!(first.vector_clock.happened_before(&second.vector_clock));
+
+        // Stored, in the DAG, and handed to the network as returned
+        assert!(node.storage.get_message(second.id).await.unwrap().is_some());
+        assert!(node.dag.has_message(&second.id));
+        for sent in [first, second] {
+            let Ok(NetworkCommand::BroadcastMessage(broadcast)) = command_rx.try_recv() else {
+                panic!("message not broadcast");
+            };
+            assert_eq!(broadcast.id, sent.id);
+        }
+
+        assert!(node.send_message(ChannelId::new(), "nowhere").await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_direct_channel_messages_sent_encrypted() {
+        let storage = Storage::new(":memory:").await.unwrap();
+        let (peer_id, other) = (PeerId::new(), PeerId::new());
+        let channel = Channel::new_peer_to_peer(peer_id, other);
+        storage.store_channel(&channel).await.unwrap();
+
+        let (_, _, command_tx, mut command_rx) = create_network_channels();
+        let mut node =
+            BurrowNode::new(peer_id, storage, MessageDAG::new(), VectorClock::new(), LamportClocks::new(), command_tx);
+        let sent = node.send_message(channel.id, "psst").await.unwrap();
+
+        let Ok(NetworkCommand::SendEncrypted { message, recipient }) = command_rx.try_recv() else {
+            panic!("direct message not sent encrypted");
+        };
+        assert_eq!((message.id, recipient), (sent.id, other));
+
+        // Its edits take the same way, never the open mesh
+        node.edit_message(sent.id, "psst!", Timestamp::new(10, 0, peer_id)).await.unwrap();
+        let Ok(NetworkCommand::EditMessage { audience, .. }) = command_rx.try_recv() else {
+            panic!("edit not sent");
+        };
+        assert_eq!(audience, Audience::Encrypted(other));
+
+        // With a third member it's no longer a pair, and goes to all of them
+        let mut widened = channel.clone();
+        widened.add_member(PeerId::new());
+        node.storage.store_channel(&widened).await.unwrap();
+        let sent = node.send_message(channel.id, "all of you").await.unwrap();
+        let Ok(NetworkCommand::SendToMembers { message, members }) = command_rx.try_recv() else {
+            panic!("widened direct channel not sent to its members");
+        };
+        assert_eq!((message.id, members.len()), (sent.id, 3));
+    }
+
+    #[tokio::test]
+    async fn test_sends_checked_and_transformed_by_the_node() {
+        let storage = Storage::new(":memory:").await.unwrap();
+        let (peer_id, admin) = (PeerId::new(), PeerId::new());
+        let mut announcements = Channel::new("news".to_string(), admin);
+        announcements.add_member(peer_id);
+        assert!(announcements.set_post_policy(PostPolicy::AdminsOnly, &admin));
+        storage.store_channel(&announcements).await.unwrap();
+        let mut gone = Channel::new("gone".to_string(), peer_id);
+        gone.delete();
+        storage.tombstone_channel(&gone).await.unwrap();
+        let general = Channel::new("general".to_string(), peer_id);
+        storage.store_channel(&general).await.unwrap();
+
+        let (_, _, command_tx, mut command_rx) = create_network_channels();
+        let mut node =
+            BurrowNode::new(peer_id, storage, MessageDAG::new(), VectorClock::new(), LamportClocks::new(), command_tx);
+
+        let refused = node.send_message(announcements.id, "hi").await.unwrap_err();
+        assert_eq!(refused.to_string(), "Only admins can post in this channel");
+        let refused = node.send_message(gone.id, "hi").await.unwrap_err();
+        assert_eq!(refused.to_string(), "Channel was deleted");
+
+        // Only the allowed send goes out, with its line endings normalized
+        let sent = node.send_message(general.id, "one\r\ntwo").await.unwrap();
+        assert_eq!(sent.content.text, "one\ntwo");
+        let Ok(NetworkCommand::BroadcastMessage(broadcast)) = command_rx.try_recv() else {
+            panic!("message not broadcast");
+        };
+        assert_eq!(broadcast.id, sent.id);
+        assert!(command_rx.try_recv().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_dag_reloads_from_saved_heads() {
+        let storage = Storage::new(":memory:").await.unwrap();
+        let peer_id = PeerId::new();
+        let channel = Channel::new("general".to_string(), peer_id);
+        storage.store_channel(&channel).await.unwrap();
+
+        let (_, _, command_tx, _command_rx) = create_network_channels();
+        let mut node =
+            BurrowNode::new(peer_id, storage, MessageDAG::new(), VectorClock::new(), LamportClocks::new(), command_tx);
+        node.send_message(channel.id, "first").await.unwrap();
+        let second = node.send_message(channel.id, "second").await.unwrap();
+        let saved_heads = |saved: Option<SavedHeads>| saved.map(|saved| saved.heads);
+        assert_eq!(saved_heads(node.storage.get_heads(channel.id).await.unwrap()), Some(vec![second.id]));
+
+        let dag = load_dag(&node.storage, &[channel.id], true).await.unwrap();
+        assert_eq!(dag.get_heads(&channel.id), vec![second.id]);
+        assert_eq!(dag.get_ordered_messages(&channel.id).len(), 2);
+
+        // Stored without saving the heads, as if the app died in between
+        let mut third = Message::new(channel.id, peer_id, MessageContent::new("third"), VectorClock::new(), 3);
+        third.parent_hashes = vec![second.id];
+        node.storage.store_message(&third).await.unwrap();
+
+        let dag = load_dag(&node.storage, &[channel.id], true).await.unwrap();
+        assert_eq!(dag.get_heads(&channel.id), vec![third.id]);
+        assert_eq!(saved_heads(node.storage.get_heads(channel.id).await.unwrap()), Some(vec![third.id]));
+    }
+
+    #[tokio::test]
+    async fn test_clocks_carry_on_after_restart() {
+        let storage = Storage::new(":memory:").await.unwrap();
+        let peer_id = PeerId::new();
+        let channel = Channel::new("general".to_string(), peer_id);
+        storage.store_channel(&channel).await.unwrap();
+
+        let mut vector_clock = VectorClock::new();
+        for _ in 0..5 {
+            vector_clock.increment(peer_id);
+        }
+        let earlier = Message::new(channel.id, peer_id, MessageContent::new("before"), vector_clock, 5);
+        storage.store_message(&earlier).await.unwrap();
+        // An orphan never makes it into the DAG, but its timestamp still counts
+        let mut orphan = Message::new(channel.id, PeerId::new(), MessageContent::new("late"), VectorClock::new(), 8);
+        orphan.parent_hashes = vec![MessageId::new()];
+        storage.store_message(&orphan).await.unwrap();
+
+        // Restart: everything comes from storage
+        let dag = load_dag(&storage, &[channel.id], true).await.unwrap();
+        let (vector_clock, lamport_clocks) = stored_clocks(&storage, &dag, peer_id).await.unwrap();
+        let (_, _, command_tx, _command_rx) = create_network_channels();
+        let mut node = BurrowNode::new(peer_id, storage, dag, vector_clock, lamport_clocks, command_tx);
+
+        let sent = node.send_message(channel.id, "after").await.unwrap();
+        assert!(sent.lamport_timestamp >= 9);
+        assert!(earlier.vector_clock.happened_before(&sent.vector_clock));
+        assert_eq!(sent.parent_hashes, vec![earlier.id]);
+    }
+
+    #[tokio::test]
+    async fn test_edits_apply_once_and_reach_the_network() {
+        let storage = Storage::new(":memory:").await.unwrap();
+        let peer_id = PeerId::new();
+        let channel = Channel::new("general".to_string(), peer_id);
+        storage.store_channel(&channel).await.unwrap();
+
+        let (_, _, command_tx, mut command_rx) = create_network_channels();
+        let mut node =
+            BurrowNode::new(peer_id, storage, MessageDAG::new(), VectorClock::new(), LamportClocks::new(), command_tx);
+        let sent = node.send_message(channel.id, "helo").await.unwrap();
+        let _ = command_rx.try_recv();
+
+        let edited = node.edit_message(sent.id, "hello", Timestamp::new(10, 0, peer_id)).await.unwrap();
+        assert_eq!(edited.current_content().text, "hello");
+        assert_eq!(node.dag.get_message(&sent.id).unwrap().current_content().text, "hello");
+        let stored = node.storage.get_message(sent.id).await.unwrap().unwrap();
+        assert_eq!(stored.current_content().text, "hello");
+        assert_eq!(stored.content.text, "helo");
+        let Ok(NetworkCommand::EditMessage { message_id, content, audience, .. }) = command_rx.try_recv() else {
+            panic!("edit not sent");
+        };
+        assert_eq!((message_id, content.text.as_str(), audience), (sent.id, "hello", Audience::Everyone));
+
+        // An older edit arriving late, a replay, and someone else's edit change nothing
+        let older = MessageContent::new("hi");
+        assert!(node.apply_edit(sent.id, older, Timestamp::new(5, 0, peer_id)).await.unwrap().is_none());
+        let replay = edited.current_content().clone();
+        assert!(node.apply_edit(sent.id, replay, Timestamp::new(10, 0, peer_id)).await.unwrap().is_none());
+        let forged = MessageContent::new("pwned");
+        assert!(node.apply_edit(sent.id, forged, Timestamp::new(20, 0, PeerId::new())).await.unwrap().is_none());
+        assert!(node.apply_edit(MessageId::new(), MessageContent::new("?"), Timestamp::new(30, 0, peer_id))
+            .await
+            .unwrap()
+            .is_none());
+        assert_eq!(node.dag.get_message(&sent.id).unwrap().current_content().text, "hello");
+    }
+
+    #[tokio::test]
+    async fn test_configured_channels_present_and_followed() {
+        let storage = Storage::new(":memory:").await.unwrap();
+        let peer_id = PeerId::new();
+        let stored = Channel::new("ops".to_string(), peer_id);
+        storage.store_channel(&stored).await.unwrap();
+        let unknown = ChannelId::new();
+
+        let wanted = crate::config::parse_channel_list(&format!(" ops, {}, ,releases,ops", unknown.0));
+        assert_eq!(wanted.len(), 4);
+
+        let (_, _, command_tx, mut command_rx) = create_network_channels();
+        let followed = ensure_channels(&storage, peer_id, &wanted, &command_tx).await.unwrap();
+        assert_eq!(followed.len(), 3);
+        assert_eq!(followed[..2], [stored.id, unknown]);
+
+        // The unknown ID is a placeholder until its state arrives; the new name is a channel of ours
+        let placeholder = storage.get_channel(unknown).await.unwrap().unwrap();
+        assert!(placeholder.has_unknown_membership());
+        let releases = storage.get_channel(followed[2]).await.unwrap().unwrap();
+        assert_eq!(releases.get_name(), "releases");
+
+        // Topics are left to the caller, which joins them with the rest
+        let mut requested = Vec::new();
+        while let Ok(command) = command_rx.try_recv() {
+            match command {
+                NetworkCommand::JoinChannel(_) => panic!("joined a topic"),
+                NetworkCommand::RequestChannelState(id) => requested.push(id),
+                _ => {}
+            }
+        }
+        assert_eq!(requested, vec![unknown]);
+
+        // Nothing new is created the second time round
+        let again = ensure_channels(&storage, peer_id, &wanted, &command_tx).await.unwrap();
+        assert_eq!(again, followed);
+        let channels = storage.get_all_channels().await.unwrap();
+        assert_eq!(channels.iter().filter(|c| c.get_name() == "releases").count(), 1);
+    }
+}
@@ -13,12 +13,20 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use crate::types::{Channel, ChannelId, Message, MessageId, PeerId};
-use serde::{Deserialize, Serialize};
-use std::collections::HashSet;
+use crate::crdt::Timestamp;
+use crate::encryption::session::PreKeyBundleParts;
+use crate::types::{Channel, ChannelId, Message, MessageContent, MessageId, PeerId};
+use std::collections::{HashMap, HashSet};
+
+mod wire;
+
+pub use wire::{WireError, WIRE_VERSION};
 
 /// Network protocol messages exchanged between peers
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// This is the in-memory form; what goes on the wire is defined separately
+/// in `wire`, so it doesn't change with these types' layout.
+#[derive(Debug, Clone)]
 pub enum NetworkMessage {
     /// A chat message to be broadcast to channel members
     ChatMessage(Message),
@@ -61,6 +69,12 @@ pub enum NetworkMessage {
         channel: Channel,
     },
 
+    /// A member deleted a channel (the deleter is `deleted_at.peer_id`)
+    ChannelTombstone {
+        channel_id: ChannelId,
+        deleted_at: Timestamp,
+    },
+
     // Phase 4: DAG Synchronization Messages
 
     /// Request specific messages by ID (to fill DAG gaps)
@@ -80,22 +94,94 @@ pub enum NetworkMessage {
     MessageInventory {
         channel_id: ChannelId,
         message_ids: HashSet<MessageId>,
+        /// When each edited message was last edited, so peers behind on an
+        /// edit ask for the message again
+        edits: HashMap<MessageId, Timestamp>,
     },
 
     /// Request message inventory from peers for anti-entropy
     InventoryRequest {
         channel_id: ChannelId,
     },
+
+    /// The author replaced the content of one of their messages
+    EditMessage {
+        channel_id: ChannelId,
+        message_id: MessageId,
+        content: MessageContent,
+        edited_at: Timestamp,
+    },
+
+    /// A direct channel's chat message or edit, encrypted for the other member
+    ///
+    /// `ciphertext` is a whole `ChatMessage` or `EditMessage` frame sealed
+    /// with the pairwise Signal session between `sender` and the recipient.
+    EncryptedChatMessage {
+        channel_id: ChannelId,
+        sender: PeerId,
+        ciphertext: Vec<u8>,
+    },
+
+    /// A peer's prekey bundle, to start a pairwise session with them from
+    PreKeyBundleAnnounce {
+        sender: PeerId,
+        bundle: PreKeyBundleParts,
+    },
+
+    /// Ask a peer we have no session with for a prekey bundle
+    PreKeyBundleRequest {
+        sender: PeerId,
+    },
+
+    /// Ask a peer for the data of an attached image, by its hash
+    AttachmentRequest {
+        hash: [u8; 32],
+    },
+
+    /// An attached image's data, answering an `AttachmentRequest`
+    AttachmentResponse {
+        hash: [u8; 32],
+        data: Vec<u8>,
+    },
 }
 
 impl NetworkMessage {
-    /// Serialize to bytes for network transmission
-    pub fn to_bytes(&self) -> Result<Vec<u8>, bincode::Error> {
-        bincode::serialize(self)
+    /// Serialize to a versioned frame for network transmission
+    pub fn to_bytes(&self) -> Result<Vec<u8>, WireError> {
+        wire::encode(self)
+    }
+
+    /// Deserialize a frame received from the network
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, WireError> {
+        wire::decode(bytes)
     }
 
-    /// Deserialize from bytes received from network
-    pub fn from_bytes(bytes: &[u8]) -> Result<Self, bincode::Error> {
-        bincode::deserialize(bytes)
+    /// The message ID of a chat message frame, if that's what it is
+    pub fn peek_message_id(bytes: &[u8]) -> Option<MessageId> {
+        wire::peek_message_id(bytes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{MessageContent, VectorClock};
+
+    #[test]
+    fn test_message_time_survives_encoding() {
+        let mut message = Message::new(
+            ChannelId::new(),
+            PeerId::new(),
+            MessageContent::new("hi"),
+            VectorClock::new(),
+            1,
+        );
+        message.created_at_ms = 1_700_000_000_123;
+
+        let bytes = NetworkMessage::ChatMessage(message).to_bytes().unwrap();
+        let NetworkMessage::ChatMessage(decoded) = NetworkMessage::from_bytes(&bytes).unwrap() else {
+            panic!("decoded to a different variant");
+        };
+        assert_eq!(decoded.created_at_ms, 1_700_000_000_123);
     }
 }
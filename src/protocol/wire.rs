@@ -0,0 +1,1025 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Wire encoding of `NetworkMessage`, separate from the domain types
+//!
+//! A frame is a version byte, a kind byte naming the message, then the
+//! bincode encoding of a DTO declared here. The DTOs only change together
+//! with `WIRE_VERSION`, so reordering or adding fields on `Message` or
+//! `Channel` (which storage encodes directly) can't silently change what
+//! peers receive. IDs travel as their 16 raw bytes and sets as sorted
+//! lists, so equal messages encode identically.
+//!
+//! Fields are positional, so a peer can't skip one it doesn't know: any
+//! change to a DTO, including a new enum variant a peer would refuse, needs
+//! a new `WIRE_VERSION`. `test_encoding_changes_bump_wire_version` pins the
+//! encoding of a sample of every frame kind to the version it belongs to.
+
+use super::NetworkMessage;
+use crate::crdt::{AuthoredRegister, HybridLogicalClock, LWWRegister, ORSet, Timestamp};
+use crate::encryption::session::PreKeyBundleParts;
+use crate::types::{
+    Channel, ChannelId, ChannelType, ImageRef, Message, MessageContent, MessageId, MessageKind, PeerId, PostPolicy,
+    Priority, SystemEvent, VectorClock,
+};
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::time::{Duration, UNIX_EPOCH};
+use uuid::Uuid;
+
+/// Version byte leading every frame
+///
+/// 2 added `/me` actions, which version 1 peers can't decode. 3 carries a
+/// message's latest edit with it, and edit times in inventories. 5 added
+/// image messages and the frames fetching their data.
+pub const WIRE_VERSION: u8 = 5;
+
+/// Kind bytes, one per `NetworkMessage` variant. Never reuse a retired value.
+mod kind {
+    pub const CHAT_MESSAGE: u8 = 1;
+    pub const SYNC_REQUEST: u8 = 2;
+    pub const SYNC_RESPONSE: u8 = 3;
+    pub const PEER_ANNOUNCE: u8 = 4;
+    pub const CHANNEL_ANNOUNCE: u8 = 5;
+    pub const CHANNEL_STATE_REQUEST: u8 = 6;
+    pub const CHANNEL_STATE_RESPONSE: u8 = 7;
+    pub const CHANNEL_UPDATE: u8 = 8;
+    pub const CHANNEL_TOMBSTONE: u8 = 9;
+    pub const MESSAGE_REQUEST: u8 = 10;
+    pub const MESSAGE_RESPONSE: u8 = 11;
+    pub const MESSAGE_INVENTORY: u8 = 12;
+    pub const INVENTORY_REQUEST: u8 = 13;
+    pub const EDIT_MESSAGE: u8 = 14;
+    pub const ENCRYPTED_CHAT_MESSAGE: u8 = 15;
+    pub const PREKEY_BUNDLE_ANNOUNCE: u8 = 16;
+    pub const PREKEY_BUNDLE_REQUEST: u8 = 17;
+    pub const ATTACHMENT_REQUEST: u8 = 18;
+    pub const ATTACHMENT_RESPONSE: u8 = 19;
+}
+
+/// Why a frame couldn't be encoded or decoded
+#[derive(Debug, thiserror::Error)]
+pub enum WireError {
+    #[error("Frame is too short")]
+    Truncated,
+
+    /// Sent by a peer speaking a different wire version
+    #[error("Unsupported wire version {0}")]
+    UnsupportedVersion(u8),
+
+    #[error("Unknown frame kind {0}")]
+    UnknownKind(u8),
+
+    /// A field holds a value this version doesn't know
+    #[error("Invalid {0} in frame")]
+    Invalid(&'static str),
+
+    #[error("Malformed frame body")]
+    Body(#[from] bincode::Error),
+}
+
+pub(super) fn encode(message: &NetworkMessage) -> Result<Vec<u8>, WireError> {
+    let mut frame = vec![WIRE_VERSION];
+    match message {
+        NetworkMessage::ChatMessage(message) => write(&mut frame, kind::CHAT_MESSAGE, &WireMessage::from(message)),
+        NetworkMessage::SyncRequest { channel_id, since_timestamp } => {
+            write(&mut frame, kind::SYNC_REQUEST, &(id(channel_id.0), since_timestamp))
+        }
+        NetworkMessage::SyncResponse { channel_id, messages } => {
+            write(&mut frame, kind::SYNC_RESPONSE, &(id(channel_id.0), wire_messages(messages)))
+        }
+        NetworkMessage::PeerAnnounce { peer_id, listen_addresses } => {
+            write(&mut frame, kind::PEER_ANNOUNCE, &(id(peer_id.0), listen_addresses))
+        }
+        NetworkMessage::ChannelAnnounce { channel } => {
+            write(&mut frame, kind::CHANNEL_ANNOUNCE, &WireChannel::from(channel))
+        }
+        NetworkMessage::ChannelStateRequest { channel_id } => {
+            write(&mut frame, kind::CHANNEL_STATE_REQUEST, &id(channel_id.0))
+        }
+        NetworkMessage::ChannelStateResponse { channel } => {
+            write(&mut frame, kind::CHANNEL_STATE_RESPONSE, &WireChannel::from(channel))
+        }
+        NetworkMessage::ChannelUpdate { channel } => {
+            write(&mut frame, kind::CHANNEL_UPDATE, &WireChannel::from(channel))
+        }
+        NetworkMessage::ChannelTombstone { channel_id, deleted_at } => {
+            write(&mut frame, kind::CHANNEL_TOMBSTONE, &(id(channel_id.0), WireTimestamp::from(*deleted_at)))
+        }
+        NetworkMessage::MessageRequest { channel_id, message_ids } => {
+            write(&mut frame, kind::MESSAGE_REQUEST, &(id(channel_id.0), ids(message_ids.iter().map(|m| m.0))))
+        }
+        NetworkMessage::MessageResponse { channel_id, messages } => {
+            write(&mut frame, kind::MESSAGE_RESPONSE, &(id(channel_id.0), wire_messages(messages)))
+        }
+        NetworkMessage::MessageInventory { channel_id, message_ids, edits } => {
+            let mut edits: Vec<(WireId, WireTimestamp)> =
+                edits.iter().map(|(message_id, at)| (id(message_id.0), WireTimestamp::from(*at))).collect();
+            edits.sort_unstable_by_key(|(message_id, _)| *message_id);
+            write(
+                &mut frame,
+                kind::MESSAGE_INVENTORY,
+                &(id(channel_id.0), ids(message_ids.iter().map(|m| m.0)), edits),
+            )
+        }
+        NetworkMessage::InventoryRequest { channel_id } => {
+            write(&mut frame, kind::INVENTORY_REQUEST, &id(channel_id.0))
+        }
+        NetworkMessage::EditMessage { channel_id, message_id, content, edited_at } => write(
+            &mut frame,
+            kind::EDIT_MESSAGE,
+            &(id(channel_id.0), id(message_id.0), WireContent::from(content), WireTimestamp::from(*edited_at)),
+        ),
+        NetworkMessage::EncryptedChatMessage { channel_id, sender, ciphertext } => {
+            write(&mut frame, kind::ENCRYPTED_CHAT_MESSAGE, &(id(channel_id.0), id(sender.0), ciphertext))
+        }
+        NetworkMessage::PreKeyBundleAnnounce { sender, bundle } => {
+            write(&mut frame, kind::PREKEY_BUNDLE_ANNOUNCE, &(id(sender.0), WirePreKeyBundle::from(bundle)))
+        }
+        NetworkMessage::PreKeyBundleRequest { sender } => write(&mut frame, kind::PREKEY_BUNDLE_REQUEST, &id(sender.0)),
+        NetworkMessage::AttachmentRequest { hash } => write(&mut frame, kind::ATTACHMENT_REQUEST, hash),
+        NetworkMessage::AttachmentResponse { hash, data } => {
+            write(&mut frame, kind::ATTACHMENT_RESPONSE, &(hash, data))
+        }
+    }?;
+    Ok(frame)
+}
+
+/// The ID of the message in a chat message frame, read without decoding the rest
+pub(super) fn peek_message_id(frame: &[u8]) -> Option<MessageId> {
+    match frame {
+        // The message ID is the first field of the body
+        [WIRE_VERSION, kind::CHAT_MESSAGE, body @ ..] => Some(MessageId(Uuid::from_slice(body.get(..16)?).ok()?)),
+        _ => None,
+    }
+}
+
+pub(super) fn decode(frame: &[u8]) -> Result<NetworkMessage, WireError> {
+    let [version, frame_kind, body @ ..] = frame else {
+        return Err(WireError::Truncated);
+    };
+    if *version != WIRE_VERSION {
+        return Err(WireError::UnsupportedVersion(*version));
+    }
+
+    Ok(match *frame_kind {
+        kind::CHAT_MESSAGE => NetworkMessage::ChatMessage(read::<WireMessage>(body)?.try_into()?),
+        kind::SYNC_REQUEST => {
+            let (channel_id, since_timestamp) = read::<(WireId, u64)>(body)?;
+            NetworkMessage::SyncRequest { channel_id: ChannelId(uuid(channel_id)), since_timestamp }
+        }
+        kind::SYNC_RESPONSE => {
+            let (channel_id, messages) = read::<(WireId, Vec<WireMessage>)>(body)?;
+            NetworkMessage::SyncResponse {
+                channel_id: ChannelId(uuid(channel_id)),
+                messages: domain_messages(messages)?,
+            }
+        }
+        kind::PEER_ANNOUNCE => {
+            let (peer_id, listen_addresses) = read::<(WireId, Vec<String>)>(body)?;
+            NetworkMessage::PeerAnnounce { peer_id: PeerId(uuid(peer_id)), listen_addresses }
+        }
+        kind::CHANNEL_ANNOUNCE => NetworkMessage::ChannelAnnounce { channel: read::<WireChannel>(body)?.try_into()? },
+        kind::CHANNEL_STATE_REQUEST => NetworkMessage::ChannelStateRequest { channel_id: ChannelId(uuid(read(body)?)) },
+        kind::CHANNEL_STATE_RESPONSE => {
+            NetworkMessage::ChannelStateResponse { channel: read::<WireChannel>(body)?.try_into()? }
+        }
+        kind::CHANNEL_UPDATE => NetworkMessage::ChannelUpdate { channel: read::<WireChannel>(body)?.try_into()? },
+        kind::CHANNEL_TOMBSTONE => {
+            let (channel_id, deleted_at) = read::<(WireId, WireTimestamp)>(body)?;
+            NetworkMessage::ChannelTombstone { channel_id: ChannelId(uuid(channel_id)), deleted_at: deleted_at.into() }
+        }
+        kind::MESSAGE_REQUEST => {
+            let (channel_id, message_ids) = read::<(WireId, Vec<WireId>)>(body)?;
+            NetworkMessage::MessageRequest {
+                channel_id: ChannelId(uuid(channel_id)),
+                message_ids: message_ids.into_iter().map(|m| MessageId(uuid(m))).collect(),
+            }
+        }
+        kind::MESSAGE_RESPONSE => {
+            let (channel_id, messages) = read::<(WireId, Vec<WireMessage>)>(body)?;
+            NetworkMessage::MessageResponse {
+                channel_id: ChannelId(uuid(channel_id)),
+                messages: domain_messages(messages)?,
+            }
+        }
+        kind::MESSAGE_INVENTORY => {
+            let (channel_id, message_ids, edits) = read::<(WireId, Vec<WireId>, Vec<(WireId, WireTimestamp)>)>(body)?;
+            NetworkMessage::MessageInventory {
+                channel_id: ChannelId(uuid(channel_id)),
+                message_ids: message_ids.into_iter().map(|m| MessageId(uuid(m))).collect(),
+                edits: edits.into_iter().map(|(message_id, at)| (MessageId(uuid(message_id)), at.into())).collect(),
+            }
+        }
+        kind::INVENTORY_REQUEST => NetworkMessage::InventoryRequest { channel_id: ChannelId(uuid(read(body)?)) },
+        kind::EDIT_MESSAGE => {
+            let (channel_id, message_id, content, edited_at) =
+                read::<(WireId, WireId, WireContent, WireTimestamp)>(body)?;
+            NetworkMessage::EditMessage {
+                channel_id: ChannelId(uuid(channel_id)),
+                message_id: MessageId(uuid(message_id)),
+                content: content.try_into()?,
+                edited_at: edited_at.into(),
+            }
+        }
+        kind::ENCRYPTED_CHAT_MESSAGE => {
+            let (channel_id, sender, ciphertext) = read::<(WireId, WireId, Vec<u8>)>(body)?;
+            NetworkMessage::EncryptedChatMessage {
+                channel_id: ChannelId(uuid(channel_id)),
+                sender: PeerId(uuid(sender)),
+                ciphertext,
+            }
+        }
+        kind::PREKEY_BUNDLE_ANNOUNCE => {
+            let (sender, bundle) = read::<(WireId, WirePreKeyBundle)>(body)?;
+            NetworkMessage::PreKeyBundleAnnounce { sender: PeerId(uuid(sender)), bundle: bundle.into() }
+        }
+        kind::PREKEY_BUNDLE_REQUEST => NetworkMessage::PreKeyBundleRequest { sender: PeerId(uuid(read(body)?)) },
+        kind::ATTACHMENT_REQUEST => NetworkMessage::AttachmentRequest { hash: read(body)? },
+        kind::ATTACHMENT_RESPONSE => {
+            let (hash, data) = read::<([u8; 32], Vec<u8>)>(body)?;
+            NetworkMessage::AttachmentResponse { hash, data }
+        }
+        other => return Err(WireError::UnknownKind(other)),
+    })
+}
+
+fn write<T: Serialize>(frame: &mut Vec<u8>, kind: u8, body: &T) -> Result<(), WireError> {
+    frame.push(kind);
+    bincode::serialize_into(frame, body)?;
+    Ok(())
+}
+
+fn read<T: DeserializeOwned>(body: &[u8]) -> Result<T, WireError> {
+    Ok(bincode::deserialize(body)?)
+}
+
+/// A UUID-based ID as its raw bytes
+type WireId = [u8; 16];
+
+fn id(uuid: Uuid) -> WireId {
+    *uuid.as_bytes()
+}
+
+fn uuid(id: WireId) -> Uuid {
+    Uuid::from_bytes(id)
+}
+
+/// IDs in a fixed order, whatever order they were held in
+fn ids(uuids: impl Iterator<Item = Uuid>) -> Vec<WireId> {
+    let mut ids: Vec<WireId> = uuids.map(id).collect();
+    ids.sort();
+    ids
+}
+
+fn wire_messages(messages: &[Message]) -> Vec<WireMessage> {
+    messages.iter().map(WireMessage::from).collect()
+}
+
+fn domain_messages(messages: Vec<WireMessage>) -> Result<Vec<Message>, WireError> {
+    messages.into_iter().map(Message::try_from).collect()
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireTimestamp {
+    physical: u64,
+    logical: u64,
+    peer: WireId,
+}
+
+impl From<Timestamp> for WireTimestamp {
+    fn from(timestamp: Timestamp) -> Self {
+        Self { physical: timestamp.physical, logical: timestamp.logical, peer: id(timestamp.peer_id.0) }
+    }
+}
+
+impl From<WireTimestamp> for Timestamp {
+    fn from(timestamp: WireTimestamp) -> Self {
+        Timestamp::new(timestamp.physical, timestamp.logical, PeerId(uuid(timestamp.peer)))
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireMessage {
+    id: WireId,
+    channel_id: WireId,
+    author: WireId,
+    /// The content as sent
+    content: WireContent,
+    /// Sorted by peer
+    vector_clock: Vec<(WireId, u64)>,
+    lamport_timestamp: u64,
+    /// Sorted; their order carries no meaning
+    parents: Vec<WireId>,
+    created_at_ms: u64,
+    expires_at: Option<u64>,
+    /// The latest edit, so history synced later shows it too
+    edit: Option<(WireContent, WireTimestamp)>,
+}
+
+/// Message content, as sent or as edited
+#[derive(Serialize, Deserialize)]
+struct WireContent {
+    text: String,
+    kind: WireKind,
+    /// 0 normal, 1 urgent
+    priority: u8,
+}
+
+/// What a message is. The variant's position is its tag on the wire, so
+/// new kinds only ever go at the end. Joined, Left and Renamed are retired:
+/// they're still written for old local history but refused when read.
+#[derive(Serialize, Deserialize)]
+enum WireKind {
+    Normal,
+    Joined { peer: WireId, tag: WireId },
+    Left { peer: WireId, tag: WireId },
+    Renamed { name: String, at: WireTimestamp },
+    Action,
+    Image { hash: [u8; 32], width: u32, height: u32, size: u64 },
+}
+
+impl From<&MessageContent> for WireContent {
+    fn from(content: &MessageContent) -> Self {
+        let kind = match &content.kind {
+            MessageKind::Normal => WireKind::Normal,
+            MessageKind::System(SystemEvent::Joined { peer, tag }) => {
+                WireKind::Joined { peer: id(peer.0), tag: id(*tag) }
+            }
+            MessageKind::System(SystemEvent::Left { peer, tag }) => WireKind::Left { peer: id(peer.0), tag: id(*tag) },
+            MessageKind::System(SystemEvent::Renamed { name, at }) => {
+                WireKind::Renamed { name: name.clone(), at: (*at).into() }
+            }
+            MessageKind::Action => WireKind::Action,
+            MessageKind::Image(image) => WireKind::Image {
+                hash: image.hash,
+                width: image.width,
+                height: image.height,
+                size: image.size,
+            },
+        };
+
+        Self {
+            text: content.text.clone(),
+            kind,
+            priority: match content.priority {
+                Priority::Normal => 0,
+                Priority::Urgent => 1,
+            },
+        }
+    }
+}
+
+impl TryFrom<WireContent> for MessageContent {
+    type Error = WireError;
+
+    fn try_from(content: WireContent) -> Result<Self, WireError> {
+        let kind = match content.kind {
+            WireKind::Normal => MessageKind::Normal,
+            // Each peer works out joins, leaves and renames from the channel
+            // state itself; taking them from a peer would let it forge them
+            WireKind::Joined { .. } | WireKind::Left { .. } | WireKind::Renamed { .. } => {
+                return Err(WireError::Invalid("message kind"));
+            }
+            WireKind::Action => MessageKind::Action,
+            WireKind::Image { hash, width, height, size } => MessageKind::Image(ImageRef { hash, width, height, size }),
+        };
+        let priority = match content.priority {
+            0 => Priority::Normal,
+            1 => Priority::Urgent,
+            _ => return Err(WireError::Invalid("priority")),
+        };
+        Ok(Self { text: content.text, kind, priority })
+    }
+}
+
+impl From<&Message> for WireMessage {
+    fn from(message: &Message) -> Self {
+        Self {
+            id: id(message.id.0),
+            channel_id: id(message.channel_id.0),
+            author: id(message.author.0),
+            content: WireContent::from(&message.content),
+            vector_clock: message.vector_clock.clocks.iter().map(|(peer, clock)| (id(peer.0), *clock)).collect(),
+            lamport_timestamp: message.lamport_timestamp,
+            parents: ids(message.parent_hashes.iter().map(|m| m.0)),
+            created_at_ms: message.created_at_ms,
+            expires_at: message.expires_at,
+            edit: message.edit.as_ref().map(|edit| (WireContent::from(edit.value()), edit.timestamp().into())),
+        }
+    }
+}
+
+impl TryFrom<WireMessage> for Message {
+    type Error = WireError;
+
+    fn try_from(message: WireMessage) -> Result<Self, WireError> {
+        // Honest clients pick the ID before the parents, so this is forged;
+        // refuse it here rather than let it wait on itself as an orphan
+        if message.parents.contains(&message.id) {
+            return Err(WireError::Invalid("message lists itself as a parent"));
+        }
+
+        let mut domain = Self {
+            id: MessageId(uuid(message.id)),
+            channel_id: ChannelId(uuid(message.channel_id)),
+            author: PeerId(uuid(message.author)),
+            content: message.content.try_into()?,
+            vector_clock: VectorClock {
+                clocks: message.vector_clock.into_iter().map(|(peer, clock)| (PeerId(uuid(peer)), clock)).collect(),
+            },
+            lamport_timestamp: message.lamport_timestamp,
+            parent_hashes: message.parents.into_iter().map(|m| MessageId(uuid(m))).collect(),
+            created_at_ms: message.created_at_ms,
+            expires_at: message.expires_at,
+            edit: None,
+        };
+        // An edit by anyone but the author is dropped, as it would be alone
+        if let Some((content, at)) = message.edit {
+            domain.apply_edit(content.try_into()?, at.into());
+        }
+        Ok(domain)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireRegister<T> {
+    value: T,
+    at: WireTimestamp,
+}
+
+impl<T> WireRegister<T> {
+    fn from_register<V: Clone>(register: &LWWRegister<V>, value: impl FnOnce(&V) -> T) -> Self {
+        Self { value: value(register.value()), at: register.timestamp().into() }
+    }
+
+    /// Each writer's latest write, sorted by writer
+    fn from_writes<V: Clone>(register: &AuthoredRegister<V>, value: impl Fn(&V) -> T) -> Vec<Self> {
+        let mut writes: Vec<Self> = register.writes().map(|write| Self::from_register(write, &value)).collect();
+        writes.sort_by_key(|write| write.at.peer);
+        writes
+    }
+}
+
+/// An OR-Set's elements with their add tags, and its removed tags, all sorted
+#[derive(Serialize, Deserialize)]
+struct WireSet {
+    elements: Vec<(WireId, Vec<WireId>)>,
+    removed: Vec<WireId>,
+}
+
+impl WireSet {
+    fn from_set<T: Eq + Hash + Clone>(set: &ORSet<T>, element_id: impl Fn(&T) -> Uuid) -> Self {
+        let mut elements: Vec<(WireId, Vec<WireId>)> =
+            set.entries().map(|(element, tags)| (id(element_id(element)), ids(tags.iter().copied()))).collect();
+        elements.sort();
+
+        Self { elements, removed: ids(set.removed_tags().iter().copied()) }
+    }
+
+    fn into_set<T: Eq + Hash + Clone>(self, element: impl Fn(Uuid) -> T) -> ORSet<T> {
+        let elements: HashMap<T, HashSet<Uuid>> = self
+            .elements
+            .into_iter()
+            .map(|(e, tags)| (element(uuid(e)), tags.into_iter().map(uuid).collect()))
+            .collect();
+        ORSet::from_parts(elements, self.removed.into_iter().map(uuid).collect())
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct WireChannel {
+    id: WireId,
+    name: WireRegister<String>,
+    /// 0 peer-to-peer, 1 group
+    channel_type: u8,
+    members: WireSet,
+    created_at_ms: u64,
+    /// The sender's HLC: whose it is and the latest time it reached
+    clock_peer: WireId,
+    clock: WireTimestamp,
+    tombstone: Option<WireTimestamp>,
+    /// Each admin's latest policy: 0 open, 1 admins only
+    post_policy: Vec<WireRegister<u8>>,
+    admins: WireSet,
+    pins: WireSet,
+    description: Vec<WireRegister<String>>,
+}
+
+impl From<&Channel> for WireChannel {
+    fn from(channel: &Channel) -> Self {
+        Self {
+            id: id(channel.id.0),
+            name: WireRegister::from_register(&channel.name, String::clone),
+            channel_type: match channel.channel_type {
+                ChannelType::PeerToPeer => 0,
+                ChannelType::Group => 1,
+            },
+            members: WireSet::from_set(&channel.members, |peer| peer.0),
+            created_at_ms: crate::types::unix_millis(channel.created_at),
+            clock_peer: id(channel.hlc.peer_id().0),
+            clock: channel.hlc.latest().into(),
+            tombstone: channel.tombstone.map(WireTimestamp::from),
+            post_policy: WireRegister::from_writes(&channel.post_policy, |policy| match policy {
+                PostPolicy::Open => 0,
+                PostPolicy::AdminsOnly => 1,
+            }),
+            admins: WireSet::from_set(&channel.admins, |peer| peer.0),
+            pins: WireSet::from_set(&channel.pins, |message| message.0),
+            description: WireRegister::from_writes(&channel.description, String::clone),
+        }
+    }
+}
+
+impl TryFrom<WireChannel> for Channel {
+    type Error = WireError;
+
+    fn try_from(channel: WireChannel) -> Result<Self, WireError> {
+        let channel_type = match channel.channel_type {
+            0 => ChannelType::PeerToPeer,
+            1 => ChannelType::Group,
+            _ => return Err(WireError::Invalid("channel type")),
+        };
+        let post_policy: AuthoredRegister<PostPolicy> = channel
+            .post_policy
+            .into_iter()
+            .map(|write| {
+                let policy = match write.value {
+                    0 => PostPolicy::Open,
+                    1 => PostPolicy::AdminsOnly,
+                    _ => return Err(WireError::Invalid("post policy")),
+                };
+                Ok(LWWRegister::new(policy, write.at.into()))
+            })
+            .collect::<Result<_, _>>()?;
+
+        Ok(Self {
+            id: ChannelId(uuid(channel.id)),
+            name: LWWRegister::new(channel.name.value, channel.name.at.into()),
+            channel_type,
+            members: channel.members.into_set(PeerId),
+            created_at: UNIX_EPOCH + Duration::from_millis(channel.created_at_ms),
+            hlc: HybridLogicalClock::with_latest(PeerId(uuid(channel.clock_peer)), channel.clock.into()),
+            tombstone: channel.tombstone.map(Timestamp::from),
+            post_policy,
+            admins: channel.admins.into_set(PeerId),
+            pins: channel.pins.into_set(MessageId),
+            description: channel
+                .description
+                .into_iter()
+                .map(|write| LWWRegister::new(write.value, write.at.into()))
+                .collect(),
+            degraded: false,
+        })
+    }
+}
+
+/// A prekey bundle's parts; keys stay in their serialized forms
+#[derive(Serialize, Deserialize)]
+struct WirePreKeyBundle {
+    registration_id: u32,
+    device_id: u32,
+    pre_key: Option<(u32, Vec<u8>)>,
+    signed_pre_key_id: u32,
+    signed_pre_key: Vec<u8>,
+    signed_pre_key_signature: Vec<u8>,
+    kyber_pre_key_id: u32,
+    kyber_pre_key: Vec<u8>,
+    kyber_pre_key_signature: Vec<u8>,
+    identity_key: Vec<u8>,
+}
+
+impl From<&PreKeyBundleParts> for WirePreKeyBundle {
+    fn from(bundle: &PreKeyBundleParts) -> Self {
+        let bundle = bundle.clone();
+        Self {
+            registration_id: bundle.registration_id,
+            device_id: bundle.device_id,
+            pre_key: bundle.pre_key,
+            signed_pre_key_id: bundle.signed_pre_key_id,
+            signed_pre_key: bundle.signed_pre_key,
+            signed_pre_key_signature: bundle.signed_pre_key_signature,
+            kyber_pre_key_id: bundle.kyber_pre_key_id,
+            kyber_pre_key: bundle.kyber_pre_key,
+            kyber_pre_key_signature: bundle.kyber_pre_key_signature,
+            identity_key: bundle.identity_key,
+        }
+    }
+}
+
+impl From<WirePreKeyBundle> for PreKeyBundleParts {
+    fn from(bundle: WirePreKeyBundle) -> Self {
+        Self {
+            registration_id: bundle.registration_id,
+            device_id: bundle.device_id,
+            pre_key: bundle.pre_key,
+            signed_pre_key_id: bundle.signed_pre_key_id,
+            signed_pre_key: bundle.signed_pre_key,
+            signed_pre_key_signature: bundle.signed_pre_key_signature,
+            kyber_pre_key_id: bundle.kyber_pre_key_id,
+            kyber_pre_key: bundle.kyber_pre_key,
+            kyber_pre_key_signature: bundle.kyber_pre_key_signature,
+            identity_key: bundle.identity_key,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sha2::{Digest, Sha256};
+
+    fn hex(bytes: &[u8]) -> String {
+        bytes.iter().map(|b| format!("{:02x}", b)).collect()
+    }
+
+    #[test]
+    fn test_chat_message_wire_bytes_pinned() {
+        let author = PeerId(Uuid::from_u128(3));
+        let mut vector_clock = VectorClock::new();
+        vector_clock.increment(author);
+        let message = Message {
+            id: MessageId(Uuid::from_u128(1)),
+            channel_id: ChannelId(Uuid::from_u128(2)),
+            author,
+            content: MessageContent::new("hi"),
+            vector_clock,
+            lamport_timestamp: 1,
+            parent_hashes: vec![MessageId(Uuid::from_u128(4))],
+            created_at_ms: 1_700_000_000_000,
+            expires_at: None,
+            edit: None,
+        };
+
+        // If this changes, peers on the previous encoding can't read us:
+        // bump WIRE_VERSION instead of editing the expectation
+        let expected = concat!(
+            "05",                               // wire version
+            "01",                               // kind: chat message
+            "00000000000000000000000000000001", // id
+            "00000000000000000000000000000002", // channel
+            "00000000000000000000000000000003", // author
+            "0200000000000000",                 // text length
+            "6869",                             // "hi"
+            "00000000",                         // kind: normal
+            "00",                               // priority: normal
+            "0100000000000000",                 // vector clock entries
+            "00000000000000000000000000000003", // ... peer
+            "0100000000000000",                 // ... clock
+            "0100000000000000",                 // lamport timestamp
+            "0100000000000000",                 // parents
+            "00000000000000000000000000000004", // ... parent
+            "0068e5cf8b010000",                 // created at (ms)
+            "00",                               // no expiry
+            "00",                               // not edited
+        );
+        let bytes = NetworkMessage::ChatMessage(message.clone()).to_bytes().unwrap();
+        assert_eq!(hex(&bytes), expected);
+
+        let NetworkMessage::ChatMessage(decoded) = NetworkMessage::from_bytes(&bytes).unwrap() else {
+            panic!("decoded to a different variant");
+        };
+        assert_eq!(decoded.canonical_bytes(), message.canonical_bytes());
+    }
+
+    /// Digest of `sample_frames` as encoded by each wire version so far
+    ///
+    /// When the test below fails, the encoding changed: bump `WIRE_VERSION`
+    /// and add its digest here. Never edit a line for a version peers run.
+    const ENCODING_DIGESTS: &[(u8, &str)] = &[
+        (1, "9d605787f1a7d92452227dd82bd04428f401f6c4e3a9178715a59aabf4454d89"),
+        (2, "f177a00e3e0bef77cab81fc548007402b3fcad65ef42c5b8d76a3804ef10f496"),
+        (3, "f0f8a745910f34862b9e88b0e867f5df998070fcb2b656e710287b8e34df81c1"),
+        (4, "b7ac04275366b83ca708e6fd0631ca6e3248cd9443cce725ff3ec798d75e54a6"),
+        (5, "46f42fa869e405e1f8ebf7c0c0e0c9478ab4b2bd20487935c3e5d6ea6aecae62"),
+    ];
+
+    /// A frame of every kind, and a message of every kind
+    fn sample_frames() -> Vec<NetworkMessage> {
+        let peer = PeerId(Uuid::from_u128(1));
+        let channel_id = ChannelId(Uuid::from_u128(2));
+        let message_id = MessageId(Uuid::from_u128(3));
+        let at = Timestamp::new(1_700_000_000_000, 1, peer);
+        let message = |kind: MessageKind| {
+            let mut vector_clock = VectorClock::new();
+            vector_clock.increment(peer);
+            Message {
+                id: message_id,
+                channel_id,
+                author: peer,
+                content: MessageContent { text: "hi".to_string(), kind, priority: Priority::Urgent },
+                vector_clock,
+                lamport_timestamp: 1,
+                parent_hashes: vec![MessageId(Uuid::from_u128(4))],
+                created_at_ms: 1_700_000_000_000,
+                expires_at: Some(1_700_000_060_000),
+                edit: None,
+            }
+        };
+        let mut edited = message(MessageKind::Normal);
+        edited.edit = Some(LWWRegister::new(MessageContent::new("edited"), at));
+        let messages = vec![
+            edited,
+            message(MessageKind::System(SystemEvent::Joined { peer, tag: Uuid::from_u128(5) })),
+            message(MessageKind::System(SystemEvent::Left { peer, tag: Uuid::from_u128(5) })),
+            message(MessageKind::System(SystemEvent::Renamed { name: "ops".to_string(), at })),
+            message(MessageKind::Action),
+            message(MessageKind::Image(ImageRef { hash: [9; 32], width: 640, height: 480, size: 2048 })),
+        ];
+        fn set<T: Eq + Hash + Clone>(element: T, tag: u128, removed: &[u128]) -> ORSet<T> {
+            ORSet::from_parts(
+                HashMap::from([(element, HashSet::from([Uuid::from_u128(tag)]))]),
+                removed.iter().map(|tag| Uuid::from_u128(*tag)).collect(),
+            )
+        }
+        let channel = Channel {
+            id: channel_id,
+            name: LWWRegister::new("general".to_string(), at),
+            channel_type: ChannelType::Group,
+            members: set(peer, 5, &[6]),
+            created_at: UNIX_EPOCH + Duration::from_millis(1_700_000_000_000),
+            hlc: HybridLogicalClock::with_latest(peer, at),
+            tombstone: Some(at),
+            post_policy: [LWWRegister::new(PostPolicy::AdminsOnly, at)].into_iter().collect(),
+            admins: set(peer, 7, &[]),
+            pins: set(message_id, 8, &[]),
+            description: [LWWRegister::new("standup".to_string(), at)].into_iter().collect(),
+            degraded: false,
+        };
+        let bundle = PreKeyBundleParts {
+            registration_id: 1,
+            device_id: 1,
+            pre_key: Some((2, vec![3])),
+            signed_pre_key_id: 4,
+            signed_pre_key: vec![5],
+            signed_pre_key_signature: vec![6],
+            kyber_pre_key_id: 7,
+            kyber_pre_key: vec![8],
+            kyber_pre_key_signature: vec![9],
+            identity_key: vec![10],
+        };
+
+        vec![
+            NetworkMessage::ChatMessage(messages[0].clone()),
+            NetworkMessage::SyncRequest { channel_id, since_timestamp: 1_700_000_000_000 },
+            NetworkMessage::SyncResponse { channel_id, messages: messages.clone() },
+            NetworkMessage::PeerAnnounce {
+                peer_id: peer,
+                listen_addresses: vec!["/ip4/127.0.0.1/tcp/9000".to_string()],
+            },
+            NetworkMessage::ChannelAnnounce { channel: channel.clone() },
+            NetworkMessage::ChannelStateRequest { channel_id },
+            NetworkMessage::ChannelStateResponse { channel: channel.clone() },
+            NetworkMessage::ChannelUpdate { channel },
+            NetworkMessage::ChannelTombstone { channel_id, deleted_at: at },
+            NetworkMessage::MessageRequest { channel_id, message_ids: vec![message_id] },
+            NetworkMessage::MessageResponse { channel_id, messages: vec![messages[0].clone()] },
+            NetworkMessage::MessageInventory {
+                channel_id,
+                message_ids: HashSet::from([message_id]),
+                edits: HashMap::from([(message_id, at)]),
+            },
+            NetworkMessage::InventoryRequest { channel_id },
+            NetworkMessage::EditMessage {
+                channel_id,
+                message_id,
+                content: MessageContent::new("edited"),
+                edited_at: at,
+            },
+            NetworkMessage::EncryptedChatMessage { channel_id, sender: peer, ciphertext: vec![1, 2, 3] },
+            NetworkMessage::PreKeyBundleAnnounce { sender: peer, bundle },
+            NetworkMessage::PreKeyBundleRequest { sender: peer },
+            NetworkMessage::AttachmentRequest { hash: [9; 32] },
+            NetworkMessage::AttachmentResponse { hash: [9; 32], data: vec![1, 2, 3] },
+        ]
+    }
+
+    #[test]
+    fn test_encoding_changes_bump_wire_version() {
+        let frames: Vec<Vec<u8>> = sample_frames().iter().map(|frame| frame.to_bytes().unwrap()).collect();
+        let kinds: Vec<u8> = frames.iter().map(|frame| frame[1]).collect();
+        assert_eq!(kinds, (1..=kind::ATTACHMENT_RESPONSE).collect::<Vec<_>>(), "every kind needs a sample");
+
+        let mut hasher = Sha256::new();
+        for frame in &frames {
+            hasher.update(frame);
+        }
+        let (version, digest) = ENCODING_DIGESTS.last().unwrap();
+        assert_eq!(*version, WIRE_VERSION, "pin the digest of the new wire version");
+        assert_eq!(
+            hex(&hasher.finalize()),
+            *digest,
+            "the wire encoding changed: bump WIRE_VERSION and add the new digest"
+        );
+    }
+
+    #[test]
+    fn test_message_id_peeked_from_chat_frames_only() {
+        let message = Message::new(ChannelId::new(), PeerId::new(), MessageContent::new("hi"), VectorClock::new(), 1);
+        let bytes = NetworkMessage::ChatMessage(message.clone()).to_bytes().unwrap();
+        assert_eq!(peek_message_id(&bytes), Some(message.id));
+        assert_eq!(peek_message_id(&bytes[..10]), None);
+
+        let request = NetworkMessage::InventoryRequest { channel_id: ChannelId::new() }.to_bytes().unwrap();
+        assert_eq!(peek_message_id(&request), None);
+    }
+
+    #[test]
+    fn test_inventory_request_wire_bytes_pinned() {
+        let request = NetworkMessage::InventoryRequest { channel_id: ChannelId(Uuid::from_u128(2)) };
+        assert_eq!(hex(&request.to_bytes().unwrap()), "050d00000000000000000000000000000002");
+    }
+
+    #[test]
+    fn test_channel_state_survives_encoding() {
+        let (alice, bob) = (PeerId::new(), PeerId::new());
+        let mut channel = Channel::new("general".to_string(), alice);
+        channel.add_member(bob);
+        let before_removal = channel.clone();
+        channel.remove_member(&bob);
+        channel.pin_message(MessageId::new());
+        channel.set_description("Release planning".to_string(), &alice);
+        channel.set_post_policy(PostPolicy::AdminsOnly, &alice);
+
+        let bytes = NetworkMessage::ChannelUpdate { channel: channel.clone() }.to_bytes().unwrap();
+        let NetworkMessage::ChannelUpdate { channel: decoded } = NetworkMessage::from_bytes(&bytes).unwrap() else {
+            panic!("decoded to a different variant");
+        };
+
+        assert_eq!(decoded.id, channel.id);
+        assert_eq!(decoded.get_name(), "general");
+        assert_eq!(decoded.description(), "Release planning");
+        assert_eq!(decoded.post_policy(), PostPolicy::AdminsOnly);
+        assert_eq!(decoded.get_members(), vec![alice]);
+        assert_eq!(decoded.pinned_messages(), channel.pinned_messages());
+        assert_eq!(decoded.hlc.latest(), channel.hlc.latest());
+
+        // The removed tag travels too, so Bob's old add can't come back
+        let mut merged = decoded.clone();
+        merged.merge(&before_removal);
+        assert!(!merged.members.contains(&bob));
+    }
+
+    #[test]
+    fn test_self_parent_rejected_on_decode() {
+        let mut message = Message::new(
+            ChannelId::new(),
+            PeerId::new(),
+            MessageContent::new("loop"),
+            VectorClock::new(),
+            1,
+        );
+        message.parent_hashes = vec![message.id];
+
+        let bytes = NetworkMessage::ChatMessage(message).to_bytes().unwrap();
+        assert!(matches!(NetworkMessage::from_bytes(&bytes), Err(WireError::Invalid(_))));
+    }
+
+    #[test]
+    fn test_system_messages_rejected_on_decode() {
+        let mut message =
+            Message::new(ChannelId::new(), PeerId::new(), MessageContent::new(""), VectorClock::new(), 1);
+        message.content.kind = MessageKind::System(SystemEvent::Joined { peer: PeerId::new(), tag: Uuid::new_v4() });
+
+        let bytes = NetworkMessage::ChatMessage(message).to_bytes().unwrap();
+        assert!(matches!(NetworkMessage::from_bytes(&bytes), Err(WireError::Invalid("message kind"))));
+    }
+
+    #[test]
+    fn test_edit_survives_encoding() {
+        let author = PeerId::new();
+        let edit = NetworkMessage::EditMessage {
+            channel_id: ChannelId::new(),
+            message_id: MessageId::new(),
+            content: MessageContent::from_input("/me fixed a typo").with_priority(Priority::Urgent),
+            edited_at: Timestamp::new(1_700_000_000_000, 2, author),
+        };
+
+        let bytes = edit.to_bytes().unwrap();
+        assert_eq!(bytes[1], kind::EDIT_MESSAGE);
+        let NetworkMessage::EditMessage { content, edited_at, .. } = NetworkMessage::from_bytes(&bytes).unwrap() else {
+            panic!("decoded to a different variant");
+        };
+        assert_eq!(content, MessageContent::from_input("/me fixed a typo").with_priority(Priority::Urgent));
+        assert_eq!(edited_at, Timestamp::new(1_700_000_000_000, 2, author));
+    }
+
+    #[test]
+    fn test_synced_message_carries_its_edit() {
+        let author = PeerId::new();
+        let mut message = Message::new(ChannelId::new(), author, MessageContent::new("tpyo"), VectorClock::new(), 1);
+        message.apply_edit(MessageContent::new("typo"), Timestamp::new(1_700_000_000_000, 1, author));
+
+        let bytes = NetworkMessage::SyncResponse { channel_id: message.channel_id, messages: vec![message.clone()] }
+            .to_bytes()
+            .unwrap();
+        let NetworkMessage::SyncResponse { messages, .. } = NetworkMessage::from_bytes(&bytes).unwrap() else {
+            panic!("decoded to a different variant");
+        };
+        assert_eq!(messages[0].current_content().text, "typo");
+        assert_eq!(messages[0].edit.as_ref().unwrap().timestamp(), Timestamp::new(1_700_000_000_000, 1, author));
+
+        // Someone else's edit is dropped on the way in, as a live one would be
+        let mut forged = WireMessage::from(&message);
+        let stranger = Timestamp::new(1_700_000_000_001, 0, PeerId::new());
+        forged.edit = Some((WireContent::from(&MessageContent::new("spam")), stranger.into()));
+        let decoded = Message::try_from(forged).unwrap();
+        assert!(decoded.edit.is_none());
+        assert_eq!(decoded.current_content().text, "tpyo");
+    }
+
+    #[test]
+    fn test_image_and_its_data_survive_encoding() {
+        let image = ImageRef { hash: [7; 32], width: 1920, height: 1080, size: 4096 };
+        let message =
+            Message::new(ChannelId::new(), PeerId::new(), MessageContent::image(image), VectorClock::new(), 1);
+
+        let bytes = NetworkMessage::ChatMessage(message.clone()).to_bytes().unwrap();
+        let NetworkMessage::ChatMessage(decoded) = NetworkMessage::from_bytes(&bytes).unwrap() else {
+            panic!("decoded to a different variant");
+        };
+        assert_eq!(decoded.content, message.content);
+
+        let bytes = NetworkMessage::AttachmentResponse { hash: image.hash, data: vec![0x89, b'P', b'N', b'G'] }
+            .to_bytes()
+            .unwrap();
+        assert_eq!(bytes[1], kind::ATTACHMENT_RESPONSE);
+        match NetworkMessage::from_bytes(&bytes).unwrap() {
+            NetworkMessage::AttachmentResponse { hash, data } => {
+                assert_eq!((hash, data), (image.hash, vec![0x89, b'P', b'N', b'G']));
+            }
+            _ => panic!("decoded to a different variant"),
+        }
+    }
+
+    #[test]
+    fn test_encrypted_message_keeps_ciphertext_opaque() {
+        let (channel_id, sender) = (ChannelId::new(), PeerId::new());
+        let ciphertext = vec![3, 0, 0xff, 42];
+        let bytes = NetworkMessage::EncryptedChatMessage { channel_id, sender, ciphertext: ciphertext.clone() }
+            .to_bytes()
+            .unwrap();
+
+        assert_eq!(bytes[1], kind::ENCRYPTED_CHAT_MESSAGE);
+        // Not a chat frame, so gossip doesn't derive an ID from it
+        assert_eq!(NetworkMessage::peek_message_id(&bytes), None);
+        match NetworkMessage::from_bytes(&bytes).unwrap() {
+            NetworkMessage::EncryptedChatMessage { channel_id: c, sender: s, ciphertext: sealed } => {
+                assert_eq!((c, s, sealed), (channel_id, sender, ciphertext));
+            }
+            _ => panic!("decoded to a different variant"),
+        }
+    }
+
+    #[test]
+    fn test_prekey_bundle_survives_encoding() {
+        let sender = PeerId::new();
+        let bundle = PreKeyBundleParts {
+            registration_id: 4242,
+            device_id: 1,
+            pre_key: Some((7, vec![5; 33])),
+            signed_pre_key_id: 7,
+            signed_pre_key: vec![5; 33],
+            signed_pre_key_signature: vec![9; 64],
+            kyber_pre_key_id: 7,
+            kyber_pre_key: vec![6; 1569],
+            kyber_pre_key_signature: vec![8; 64],
+            identity_key: vec![5; 33],
+        };
+
+        let bytes = NetworkMessage::PreKeyBundleAnnounce { sender, bundle: bundle.clone() }.to_bytes().unwrap();
+        assert_eq!(bytes[1], kind::PREKEY_BUNDLE_ANNOUNCE);
+        match NetworkMessage::from_bytes(&bytes).unwrap() {
+            NetworkMessage::PreKeyBundleAnnounce { sender: s, bundle: b } => assert_eq!((s, b), (sender, bundle)),
+            _ => panic!("decoded to a different variant"),
+        }
+
+        let bytes = NetworkMessage::PreKeyBundleRequest { sender }.to_bytes().unwrap();
+        let decoded = NetworkMessage::from_bytes(&bytes).unwrap();
+        assert!(matches!(decoded, NetworkMessage::PreKeyBundleRequest { sender: s } if s == sender));
+    }
+
+    #[test]
+    fn test_other_versions_and_kinds_rejected() {
+        let mut bytes = NetworkMessage::InventoryRequest { channel_id: ChannelId::new() }.to_bytes().unwrap();
+        bytes[1] = 0xff;
+        assert!(matches!(NetworkMessage::from_bytes(&bytes), Err(WireError::UnknownKind(0xff))));
+        bytes[0] = WIRE_VERSION + 1;
+        assert!(matches!(NetworkMessage::from_bytes(&bytes), Err(WireError::UnsupportedVersion(_))));
+        assert!(matches!(NetworkMessage::from_bytes(&[WIRE_VERSION]), Err(WireError::Truncated)));
+    }
+}
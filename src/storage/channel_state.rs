@@ -0,0 +1,294 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+//! Encoding of `Channel` for the `crdt_state` column
+//!
+//! Blobs start with a tag byte and a layout version, then the bincode of
+//! `Channel` as that version laid it out. Blobs written before the tag existed start with the
+//! channel ID's length prefix instead; they're decoded by trying each layout
+//! `Channel` has had, newest first, and filling fields added since with
+//! their defaults.
+
+use crate::crdt::{HybridLogicalClock, LWWRegister, ORSet, Timestamp};
+use crate::types::{Channel, ChannelId, ChannelType, MessageId, PeerId, PostPolicy};
+use anyhow::{bail, Result};
+use bincode::Options;
+use serde::de::DeserializeOwned;
+use serde::Deserialize;
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+use std::time::SystemTime;
+use uuid::Uuid;
+
+/// Leads every tagged blob. Untagged blobs start with the low byte of the
+/// channel ID's length (16), so the two can't be confused
+const TAG: u8 = 0xC5;
+
+/// Layout of the `Channel` fields after the tag. Bump it whenever a field is
+/// added, removed or reordered, and teach `decode` the previous layout
+pub const VERSION: u8 = 2;
+
+/// Encode a channel for storage
+pub fn encode(channel: &Channel) -> Result<Vec<u8>, bincode::Error> {
+    let mut blob = vec![TAG, VERSION];
+    blob.extend(bincode::serialize(channel)?);
+    Ok(blob)
+}
+
+/// Decode a stored channel, tagged or from before the tag
+pub fn decode(blob: &[u8]) -> Result<Channel> {
+    match blob {
+        [TAG, VERSION, state @ ..] => Ok(strict(state)?),
+        [TAG, 1, state @ ..] => Ok(decode_single_writer(state)?),
+        [TAG, version, ..] => bail!("Channel state has unknown layout version {}", version),
+        _ => decode_untagged(blob),
+    }
+}
+
+/// Bincode as `bincode::serialize` writes it, but refusing leftover bytes so
+/// that an older, shorter layout never decodes as a prefix of a newer one
+fn strict<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, bincode::Error> {
+    bincode::DefaultOptions::new()
+        .with_fixint_encoding()
+        .reject_trailing_bytes()
+        .deserialize(bytes)
+}
+
+/// Fields every layout starts with; `S` is the members set's layout
+#[derive(Deserialize)]
+struct Head<S> {
+    id: ChannelId,
+    name: LWWRegister<String>,
+    channel_type: ChannelType,
+    members: S,
+    created_at: SystemTime,
+    hlc: HybridLogicalClock,
+}
+
+/// An OR-Set from before removed tags were kept
+#[derive(Deserialize)]
+struct TaglessSet<T: Eq + Hash> {
+    elements: HashMap<T, HashSet<Uuid>>,
+}
+
+impl<T: Eq + Hash + Clone> From<TaglessSet<T>> for ORSet<T> {
+    fn from(set: TaglessSet<T>) -> Self {
+        ORSet::from_parts(set.elements, HashSet::new())
+    }
+}
+
+impl<S: Into<ORSet<PeerId>>> Head<S> {
+    /// The channel with the fields added after this layout at their defaults:
+    /// open posting, the creator as sole admin, no pins and no description.
+    /// Registers from before each writer's write was kept become that one write
+    fn upgrade(
+        self,
+        tombstone: Option<Timestamp>,
+        post_policy: Option<LWWRegister<PostPolicy>>,
+        admins: Option<ORSet<PeerId>>,
+        pins: Option<ORSet<MessageId>>,
+        description: Option<LWWRegister<String>>,
+    ) -> Channel {
+        let admins = admins.unwrap_or_else(|| {
+            let mut admins = ORSet::new();
+            admins.add(self.hlc.peer_id());
+            admins
+        });
+        Channel {
+            id: self.id,
+            name: self.name,
+            channel_type: self.channel_type,
+            members: self.members.into(),
+            created_at: self.created_at,
+            hlc: self.hlc,
+            tombstone,
+            post_policy: post_policy.into_iter().collect(),
+            admins,
+            pins: pins.unwrap_or_default(),
+            description: description.into_iter().collect(),
+            degraded: false,
+        }
+    }
+}
+
+type Current = Head<ORSet<PeerId>>;
+type Tagless = Head<TaglessSet<PeerId>>;
+
+/// The first tagged layout: a single post policy and description register
+type SingleWriter = (
+    Current,
+    Option<Timestamp>,
+    LWWRegister<PostPolicy>,
+    ORSet<PeerId>,
+    ORSet<MessageId>,
+    LWWRegister<String>,
+);
+
+fn decode_single_writer(state: &[u8]) -> Result<Channel, bincode::Error> {
+    let (head, tombstone, post_policy, admins, pins, description) = strict::<SingleWriter>(state)?;
+    Ok(head.upgrade(tombstone, Some(post_policy), Some(admins), Some(pins), Some(description)))
+}
+
+/// Decode a blob written before the tag, by the layouts `Channel` has had
+fn decode_untagged(blob: &[u8]) -> Result<Channel> {
+    // Pins, description and everything before them, as in the first tagged layout
+    let error = match decode_single_writer(blob) {
+        Ok(channel) => return Ok(channel),
+        Err(e) => e,
+    };
+    // Before descriptions
+    if let Ok((head, tombstone, post_policy, admins, pins)) = strict::<(
+        Current,
+        Option<Timestamp>,
+        LWWRegister<PostPolicy>,
+        ORSet<PeerId>,
+        ORSet<MessageId>,
+    )>(blob)
+    {
+        return Ok(head.upgrade(tombstone, Some(post_policy), Some(admins), Some(pins), None));
+    }
+    // Before pins
+    if let Ok((head, tombstone, post_policy, admins)) =
+        strict::<(Current, Option<Timestamp>, LWWRegister<PostPolicy>, ORSet<PeerId>)>(blob)
+    {
+        return Ok(head.upgrade(tombstone, Some(post_policy), Some(admins), None, None));
+    }
+    // Before post policies and admins
+    if let Ok((head, tombstone)) = strict::<(Current, Option<Timestamp>)>(blob) {
+        return Ok(head.upgrade(tombstone, None, None, None, None));
+    }
+    // Before OR-Sets kept removed tags
+    if let Ok((head, tombstone)) = strict::<(Tagless, Option<Timestamp>)>(blob) {
+        return Ok(head.upgrade(tombstone, None, None, None, None));
+    }
+    // Before tombstones
+    if let Ok(head) = strict::<Tagless>(blob) {
+        return Ok(head.upgrade(None, None, None, None, None));
+    }
+    Err(error.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Serialize;
+
+    /// The first layout: no tombstone, and members without removed tags
+    #[derive(Serialize)]
+    struct Original<'a> {
+        id: ChannelId,
+        name: &'a LWWRegister<String>,
+        channel_type: ChannelType,
+        members: HashMap<PeerId, HashSet<Uuid>>,
+        created_at: SystemTime,
+        hlc: &'a HybridLogicalClock,
+    }
+
+    /// The first tagged layout, from before each writer's write was kept
+    fn single_writer(channel: &Channel, policy: PostPolicy, description: &str) -> Vec<u8> {
+        let at = channel.name.timestamp();
+        bincode::serialize(&(
+            channel.id,
+            &channel.name,
+            channel.channel_type,
+            &channel.members,
+            channel.created_at,
+            &channel.hlc,
+            channel.tombstone,
+            LWWRegister::new(policy, at),
+            &channel.admins,
+            &channel.pins,
+            LWWRegister::new(description.to_string(), at),
+        ))
+        .unwrap()
+    }
+
+    fn original(channel: &Channel) -> Vec<u8> {
+        let members = channel
+            .members
+            .entries()
+            .map(|(peer, tags)| (*peer, tags.clone()))
+            .collect();
+        bincode::serialize(&Original {
+            id: channel.id,
+            name: &channel.name,
+            channel_type: channel.channel_type,
+            members,
+            created_at: channel.created_at,
+            hlc: &channel.hlc,
+        })
+        .unwrap()
+    }
+
+    #[test]
+    fn test_tagged_roundtrip() {
+        let mut channel = Channel::new("general".to_string(), PeerId::new());
+        channel.add_member(PeerId::new());
+        let creator = channel.hlc.peer_id();
+        channel.set_description("Daily standup".to_string(), &creator);
+
+        let decoded = decode(&encode(&channel).unwrap()).unwrap();
+        assert_eq!(decoded.id, channel.id);
+        assert_eq!(decoded.description(), "Daily standup");
+        assert_eq!(decoded.get_members().len(), 2);
+    }
+
+    #[test]
+    fn test_untagged_single_writer_layout_decodes() {
+        let mut channel = Channel::new("general".to_string(), PeerId::new());
+        channel.pins.add(MessageId::new());
+
+        let decoded = decode(&single_writer(&channel, PostPolicy::Open, "")).unwrap();
+        assert_eq!(decoded.id, channel.id);
+        assert_eq!(decoded.pins.len(), 1);
+    }
+
+    #[test]
+    fn test_single_writer_layout_keeps_its_writes() {
+        let channel = Channel::new("announcements".to_string(), PeerId::new());
+        let mut blob = vec![TAG, 1];
+        blob.extend(single_writer(&channel, PostPolicy::AdminsOnly, "Release notes"));
+
+        let decoded = decode(&blob).unwrap();
+        assert_eq!(decoded.post_policy(), PostPolicy::AdminsOnly);
+        assert_eq!(decoded.description(), "Release notes");
+    }
+
+    #[test]
+    fn test_original_layout_upgrades_with_defaults() {
+        let creator = PeerId::new();
+        let member = PeerId::new();
+        let mut channel = Channel::new("general".to_string(), creator);
+        channel.add_member(member);
+
+        let decoded = decode(&original(&channel)).unwrap();
+        assert_eq!(decoded.id, channel.id);
+        assert_eq!(decoded.get_name(), "general");
+        assert!(decoded.members.contains(&creator));
+        assert!(decoded.members.contains(&member));
+        assert!(decoded.tombstone.is_none());
+        assert_eq!(decoded.post_policy(), PostPolicy::Open);
+        assert!(decoded.admins.contains(&creator));
+        assert!(decoded.pins.is_empty());
+        assert_eq!(decoded.description(), "");
+    }
+
+    #[test]
+    fn test_unknown_version_rejected() {
+        let channel = Channel::new("general".to_string(), PeerId::new());
+        let mut blob = encode(&channel).unwrap();
+        blob[1] = VERSION + 1;
+        assert!(decode(&blob).is_err());
+    }
+}
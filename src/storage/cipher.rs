@@ -0,0 +1,144 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{anyhow, Context, Result};
+use chacha20poly1305::aead::{Aead, AeadCore, KeyInit, OsRng};
+use chacha20poly1305::{XChaCha20Poly1305, XNonce};
+use libp2p::identity::Keypair;
+
+/// Marks a `content` column value as encrypted; anything else is legacy plaintext
+const ENCRYPTED_PREFIX: &str = "enc1:";
+
+/// `LIKE` pattern matching encrypted `content` values, so scans for
+/// plaintext rows (or encrypted ones) can filter in the query
+pub const ENCRYPTED_PATTERN: &str = "enc1:%";
+
+/// Domain separating this key from anything else derived from the identity
+const KEY_DOMAIN: &[u8] = b"burrow content-at-rest v1";
+
+const NONCE_LEN: usize = 24;
+
+/// Encrypts message content before it's written to the database
+///
+/// The key is derived from the local identity, so the database alone doesn't
+/// reveal what was said. Encrypted values are stored as text so the column
+/// type doesn't change and rows written before encryption stay readable.
+#[derive(Clone)]
+pub struct ContentCipher {
+    cipher: XChaCha20Poly1305,
+}
+
+impl ContentCipher {
+    /// Derive the content key from an identity keypair
+    pub fn from_identity(keypair: &Keypair) -> Result<Self> {
+        let key = keypair
+            .derive_secret(KEY_DOMAIN)
+            .context("Content encryption needs an Ed25519 identity")?;
+        Ok(Self::from_key(&key))
+    }
+
+    pub fn from_key(key: &[u8; 32]) -> Self {
+        Self {
+            cipher: XChaCha20Poly1305::new(key.into()),
+        }
+    }
+
+    /// Encrypt serialized content for storage
+    pub fn encrypt(&self, plaintext: &str) -> Result<String> {
+        let sealed = self.seal(plaintext.as_bytes())?;
+        Ok(format!("{}{}", ENCRYPTED_PREFIX, to_hex(&sealed)))
+    }
+
+    /// Decrypt a stored value, passing legacy plaintext through unchanged
+    pub fn decrypt(&self, stored: &str) -> Result<String> {
+        let Some(hex) = stored.strip_prefix(ENCRYPTED_PREFIX) else {
+            return Ok(stored.to_string());
+        };
+
+        let sealed = from_hex(hex).context("Encrypted content is not valid hex")?;
+        let plaintext = self.open(&sealed)?;
+        String::from_utf8(plaintext).context("Decrypted content is not UTF-8")
+    }
+
+    /// Encrypt binary data (attachments), as the nonce then the ciphertext
+    pub fn seal(&self, plaintext: &[u8]) -> Result<Vec<u8>> {
+        let nonce = XChaCha20Poly1305::generate_nonce(&mut OsRng);
+        let ciphertext = self
+            .cipher
+            .encrypt(&nonce, plaintext)
+            .map_err(|_| anyhow!("Failed to encrypt message content"))?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend_from_slice(&ciphertext);
+        Ok(sealed)
+    }
+
+    /// Undo `seal`
+    pub fn open(&self, sealed: &[u8]) -> Result<Vec<u8>> {
+        if sealed.len() < NONCE_LEN {
+            return Err(anyhow!("Encrypted content is truncated"));
+        }
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        self.cipher
+            .decrypt(XNonce::from_slice(nonce), ciphertext)
+            .map_err(|_| anyhow!("Encrypted content failed to decrypt (wrong identity or corrupt row)"))
+    }
+}
+
+/// Whether a stored `content` value is already encrypted
+pub fn is_encrypted(stored: &str) -> bool {
+    stored.starts_with(ENCRYPTED_PREFIX)
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>> {
+    if hex.len() % 2 != 0 {
+        return Err(anyhow!("odd length"));
+    }
+    (0..hex.len())
+        .step_by(2)
+        .map(|i| u8::from_str_radix(&hex[i..i + 2], 16).map_err(Into::into))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip_and_legacy_plaintext() {
+        let cipher = ContentCipher::from_identity(&Keypair::generate_ed25519()).unwrap();
+
+        let sealed = cipher.encrypt(r#"{"text":"hello"}"#).unwrap();
+        assert!(is_encrypted(&sealed));
+        assert_eq!(ENCRYPTED_PATTERN, format!("{}%", ENCRYPTED_PREFIX));
+        assert!(!sealed.contains("hello"));
+        assert_eq!(cipher.decrypt(&sealed).unwrap(), r#"{"text":"hello"}"#);
+
+        assert_eq!(cipher.decrypt(r#"{"text":"old"}"#).unwrap(), r#"{"text":"old"}"#);
+    }
+
+    #[test]
+    fn test_other_identity_cannot_decrypt() {
+        let ours = ContentCipher::from_identity(&Keypair::generate_ed25519()).unwrap();
+        let theirs = ContentCipher::from_identity(&Keypair::generate_ed25519()).unwrap();
+
+        let sealed = ours.encrypt("secret").unwrap();
+        assert!(theirs.decrypt(&sealed).is_err());
+    }
+}
@@ -0,0 +1,100 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::types::MessageId;
+
+/// Why a storage operation failed
+///
+/// Lets callers tell failures worth retrying (another connection holds the
+/// lock) from ones that won't go away (a damaged row).
+#[derive(Debug, thiserror::Error)]
+pub enum StorageError {
+    /// The database is locked by another connection, or every pooled
+    /// connection is in use; retrying later may succeed
+    #[error("{context} (database busy)")]
+    Busy {
+        context: &'static str,
+        #[source]
+        source: sqlx::Error,
+    },
+
+    /// Any other database failure, e.g. a write to a read-only archive
+    #[error("{context}")]
+    Database {
+        context: &'static str,
+        #[source]
+        source: sqlx::Error,
+    },
+
+    /// A stored message can't be read back
+    #[error("Stored message {message_id:?} is unreadable: {reason}")]
+    Corrupt { message_id: MessageId, reason: String },
+
+    /// A value couldn't be encoded for storage
+    #[error("Failed to encode {what}")]
+    Encode {
+        what: &'static str,
+        #[source]
+        source: bincode::Error,
+    },
+
+    /// Failures from operations without their own variant yet
+    #[error(transparent)]
+    Other(#[from] anyhow::Error),
+}
+
+impl StorageError {
+    /// Wrap a database error, telling lock contention apart from other failures
+    pub fn database(context: &'static str, source: sqlx::Error) -> Self {
+        if is_busy(&source) {
+            Self::Busy { context, source }
+        } else {
+            Self::Database { context, source }
+        }
+    }
+
+    /// Whether retrying the same operation later may succeed
+    pub fn is_transient(&self) -> bool {
+        matches!(self, Self::Busy { .. })
+    }
+}
+
+/// SQLITE_BUSY and SQLITE_LOCKED, including their extended codes
+fn is_busy(error: &sqlx::Error) -> bool {
+    match error {
+        sqlx::Error::PoolTimedOut => true,
+        sqlx::Error::Database(e) => e
+            .code()
+            .and_then(|code| code.parse::<i32>().ok())
+            .is_some_and(|code| matches!(code & 0xff, 5 | 6)),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_only_busy_errors_are_transient() {
+        assert!(StorageError::database("Failed to store message", sqlx::Error::PoolTimedOut).is_transient());
+        assert!(!StorageError::database("Failed to store message", sqlx::Error::RowNotFound).is_transient());
+        assert!(!StorageError::Corrupt {
+            message_id: MessageId::new(),
+            reason: "corrupt vector clock".to_string(),
+        }
+        .is_transient());
+    }
+}
@@ -13,21 +13,123 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use crate::types::{Channel, ChannelId, ChannelType, Message, MessageId, PeerId, VectorClock};
+mod channel_state;
+mod cipher;
+mod error;
+
+pub use cipher::ContentCipher;
+pub use error::StorageError;
+
+use crate::crdt::{LWWRegister, Timestamp};
+use crate::encryption::keys::{generate_identity_keypair, generate_registration_id};
+use crate::encryption::storage::SignalStore;
+use crate::encryption::IdentityKeyPair;
+use crate::types::{
+    attachment_hash, Channel, ChannelId, ChannelType, Message, MessageContent, MessageId, PeerId, SystemEvent,
+    VectorClock,
+};
 use anyhow::{Context, Result};
+use libp2p::futures::stream::{self, StreamExt, TryStreamExt};
 use sqlx::sqlite::{SqliteConnectOptions, SqlitePool, SqlitePoolOptions};
 use sqlx::Row;
+use std::collections::{HashMap, HashSet};
 use std::path::Path;
-use std::time::UNIX_EPOCH;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// Default number of message reads `get_messages_by_ids` runs at once
+pub const DEFAULT_FETCH_CONCURRENCY: usize = 4;
+
+/// Message columns holding content sealed with `seal_content`
+const CONTENT_COLUMNS: [&str; 2] = ["content", "edit_content"];
+
+/// How busy a channel is: its message count and newest message time
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChannelActivity {
+    pub message_count: u64,
+    pub last_message_at: Option<SystemTime>,
+}
+
+impl ChannelActivity {
+    /// Account for a newly added message
+    pub fn record(&mut self, created_at: SystemTime) {
+        self.message_count += 1;
+        self.last_message_at = self.last_message_at.max(Some(created_at));
+    }
+}
+
+/// What `rekey_content` did
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Rekeyed {
+    /// Values now encrypted under the new key
+    pub rekeyed: usize,
+    /// Values the old key couldn't open, left unchanged
+    pub unreadable: usize,
+}
+
+/// The newest message the user has seen in a channel
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ReadMarker {
+    pub message_id: MessageId,
+    /// Creation time of that message, for when the message itself is gone
+    pub created_at_ms: u64,
+}
+
+/// A channel's DAG frontier as of its last change
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SavedHeads {
+    pub heads: Vec<MessageId>,
+    /// Messages still waiting for a parent; not ancestors of the heads
+    pub orphans: Vec<MessageId>,
+}
+
+/// A stored message row that failed to decode
+#[derive(Debug, Clone)]
+pub struct UnreadableMessage {
+    /// SQLite rowid, usable for manual repair even when the ID is damaged
+    pub rowid: i64,
+    pub message_id: Option<MessageId>,
+    pub error: String,
+}
+
+/// What `store_messages` did with each message of a batch
+#[derive(Debug, Default)]
+pub struct StoreSummary {
+    /// Newly stored
+    pub stored: Vec<MessageId>,
+    /// Already stored, and left as they were
+    pub duplicates: Vec<MessageId>,
+    /// Not stored; worth fetching again
+    pub failed: Vec<(MessageId, StorageError)>,
+}
+
+impl StoreSummary {
+    /// Whether the message is in storage now, new or not
+    pub fn is_stored(&self, message_id: &MessageId) -> bool {
+        self.stored.contains(message_id) || self.duplicates.contains(message_id)
+    }
+
+    /// Fail with the first error, for callers that need the whole batch stored
+    pub fn into_result(mut self) -> Result<Self, StorageError> {
+        if self.failed.is_empty() {
+            Ok(self)
+        } else {
+            Err(self.failed.swap_remove(0).1)
+        }
+    }
+}
 
 /// Storage layer for persisting messages and channels
 pub struct Storage {
     pool: SqlitePool,
+    /// Encrypts the `content` column; None stores and reads plaintext
+    content_cipher: Option<ContentCipher>,
+    /// Message reads run at once when fetching by ID
+    fetch_concurrency: usize,
 }
 
 impl Storage {
     /// Create a new storage instance with the given database path
-    pub async fn new<P: AsRef<Path>>(db_path: P) -> Result<Self> {
+    pub async fn new<P: AsRef<Path>>(db_path: P) -> Result<Self, StorageError> {
         let path_str = db_path.as_ref().to_str().unwrap_or("");
         let is_memory = path_str == ":memory:";
 
@@ -42,9 +144,13 @@ impl Storage {
             .max_connections(5)
             .connect(connect_str)
             .await
-            .context("Failed to connect to database")?;
+            .map_err(|e| StorageError::database("Failed to connect to database", e))?;
 
-        let storage = Self { pool };
+        let storage = Self {
+            pool,
+            content_cipher: None,
+            fetch_concurrency: DEFAULT_FETCH_CONCURRENCY,
+        };
 
         // Initialize schema
         storage.initialize_schema().await?;
@@ -52,6 +158,28 @@ impl Storage {
         Ok(storage)
     }
 
+    /// Open an existing database read-only, for browsing an archive
+    ///
+    /// The schema is left untouched and every write fails, so the archive is
+    /// never mutated.
+    pub async fn open_read_only<P: AsRef<Path>>(db_path: P) -> Result<Self> {
+        let options = SqliteConnectOptions::new()
+            .filename(db_path.as_ref())
+            .read_only(true);
+
+        let pool = SqlitePoolOptions::new()
+            .max_connections(5)
+            .connect_with(options)
+            .await
+            .with_context(|| format!("Failed to open archive {:?}", db_path.as_ref()))?;
+
+        Ok(Self {
+            pool,
+            content_cipher: None,
+            fetch_concurrency: DEFAULT_FETCH_CONCURRENCY,
+        })
+    }
+
     /// Initialize the database schema
     async fn initialize_schema(&self) -> Result<()> {
         // Use a single connection for all schema operations to ensure they see each other's changes
@@ -85,7 +213,9 @@ impl Storage {
                 vector_clock BLOB NOT NULL,
                 lamport_timestamp INTEGER NOT NULL,
                 parent_hashes BLOB NOT NULL,
-                created_at INTEGER NOT NULL
+                created_at INTEGER NOT NULL,
+                created_at_ms INTEGER,
+                expires_at INTEGER
             )
             "#
         )
@@ -122,6 +252,35 @@ impl Storage {
         .await
         .context("Failed to create peers table")?;
 
+        // Messages hidden from the local view only (never propagated)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS hidden_messages (
+                message_id BLOB PRIMARY KEY NOT NULL,
+                hidden_at INTEGER NOT NULL
+            )
+            "#
+        )
+        .execute(&mut *conn)
+        .await
+        .context("Failed to create hidden_messages table")?;
+
+        // Joins, leaves and renames seen locally, shown between the messages
+        // (never propagated; each peer works them out from the channel state)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS channel_events (
+                id BLOB PRIMARY KEY NOT NULL,
+                channel_id BLOB NOT NULL,
+                event BLOB NOT NULL,
+                seen_at_ms INTEGER NOT NULL
+            )
+            "#
+        )
+        .execute(&mut *conn)
+        .await
+        .context("Failed to create channel_events table")?;
+
         // Phase 5: Create encryption tables
         sqlx::query(
             r#"
@@ -140,7 +299,8 @@ impl Storage {
             r#"
             CREATE TABLE IF NOT EXISTS pre_keys (
                 pre_key_id INTEGER PRIMARY KEY NOT NULL,
-                record BLOB NOT NULL
+                record BLOB NOT NULL,
+                created_at INTEGER
             )
             "#
         )
@@ -161,6 +321,19 @@ impl Storage {
         .await
         .context("Failed to create signed_pre_keys table")?;
 
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS kyber_pre_keys (
+                kyber_pre_key_id INTEGER PRIMARY KEY NOT NULL,
+                record BLOB NOT NULL,
+                timestamp INTEGER NOT NULL
+            )
+            "#
+        )
+        .execute(&mut *conn)
+        .await
+        .context("Failed to create kyber_pre_keys table")?;
+
         sqlx::query(
             r#"
             CREATE TABLE IF NOT EXISTS sessions (
@@ -189,6 +362,129 @@ impl Storage {
         .await
         .context("Failed to create sender_keys table")?;
 
+        // Our own Signal identity; a single row, created on first start
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS signal_identity (
+                id INTEGER PRIMARY KEY NOT NULL CHECK (id = 1),
+                identity_key_pair BLOB NOT NULL,
+                registration_id INTEGER NOT NULL
+            )
+            "#
+        )
+        .execute(&mut *conn)
+        .await
+        .context("Failed to create signal_identity table")?;
+
+        // Listen addresses peers announced, so they can be redialed on restart
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS peer_addresses (
+                peer_id TEXT NOT NULL,
+                address TEXT NOT NULL,
+                announced_at INTEGER NOT NULL,
+                PRIMARY KEY (peer_id, address)
+            )
+            "#
+        )
+        .execute(&mut *conn)
+        .await
+        .context("Failed to create peer_addresses table")?;
+
+        // Unsent input per channel, restored when returning to the channel
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS drafts (
+                channel_id BLOB PRIMARY KEY NOT NULL,
+                text TEXT NOT NULL,
+                updated_at INTEGER NOT NULL
+            )
+            "#
+        )
+        .execute(&mut *conn)
+        .await
+        .context("Failed to create drafts table")?;
+
+        // Local UI preferences (never synced)
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS settings (
+                key TEXT PRIMARY KEY NOT NULL,
+                value TEXT NOT NULL
+            )
+            "#
+        )
+        .execute(&mut *conn)
+        .await
+        .context("Failed to create settings table")?;
+
+        // Last message seen per channel, for unread counts across restarts
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS channel_read_state (
+                channel_id BLOB PRIMARY KEY NOT NULL,
+                last_read_id BLOB NOT NULL,
+                last_read_ms INTEGER NOT NULL
+            )
+            "#
+        )
+        .execute(&mut *conn)
+        .await
+        .context("Failed to create channel_read_state table")?;
+
+        // Channels whose normal messages don't notify
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS muted_channels (
+                channel_id BLOB PRIMARY KEY NOT NULL,
+                muted_at INTEGER NOT NULL
+            )
+            "#
+        )
+        .execute(&mut *conn)
+        .await
+        .context("Failed to create muted_channels table")?;
+
+        // Words that notify in a channel even while it's muted, one per line
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS channel_keywords (
+                channel_id BLOB PRIMARY KEY NOT NULL,
+                keywords TEXT NOT NULL
+            )
+            "#
+        )
+        .execute(&mut *conn)
+        .await
+        .context("Failed to create channel_keywords table")?;
+
+        // DAG heads as of the last change, so startup can skip most of the history
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS heads (
+                channel_id BLOB PRIMARY KEY NOT NULL,
+                message_ids BLOB NOT NULL
+            )
+            "#
+        )
+        .execute(&mut *conn)
+        .await
+        .context("Failed to create heads table")?;
+
+        // Attached images' data by hash, encrypted like message content
+        sqlx::query(
+            r#"
+            CREATE TABLE IF NOT EXISTS attachments (
+                hash BLOB PRIMARY KEY NOT NULL,
+                data BLOB NOT NULL,
+                encrypted INTEGER NOT NULL
+            )
+            "#
+        )
+        .execute(&mut *conn)
+        .await
+        .context("Failed to create attachments table")?;
+
         // Release connection before running migrations
         drop(conn);
 
@@ -200,29 +496,281 @@ impl Storage {
 
     /// Migrate existing database schema to latest version
     async fn migrate_schema(&self) -> Result<()> {
-        // Phase 4: Schema migrations disabled - schema.sql now contains all required columns
-        // For production databases from earlier phases, manual migration will be needed
+        // Disappearing messages
+        self.add_column_if_missing("messages", "expires_at", "INTEGER").await?;
+
+        // Millisecond creation times; rows from before only know the second
+        self.add_column_if_missing("messages", "created_at_ms", "INTEGER").await?;
+        sqlx::query("UPDATE messages SET created_at_ms = created_at * 1000 WHERE created_at_ms IS NULL")
+            .execute(&self.pool)
+            .await
+            .context("Failed to backfill message creation times")?;
+        sqlx::query(
+            "CREATE INDEX IF NOT EXISTS idx_messages_channel_time_ms ON messages(channel_id, created_at_ms)"
+        )
+        .execute(&self.pool)
+        .await
+        .context("Failed to create messages channel time index")?;
+
+        // Message edits: the latest edited content and its HLC timestamp
+        self.add_column_if_missing("messages", "edit_content", "TEXT").await?;
+        self.add_column_if_missing("messages", "edited_at", "BLOB").await?;
+
+        // Orphans saved with the heads; rows from before have none and get rebuilt
+        self.add_column_if_missing("heads", "orphan_ids", "BLOB").await?;
+
+        // When one-time prekeys were handed out, so unused ones can be pruned.
+        // Older ones count from now, giving their handshakes the usual grace
+        self.add_column_if_missing("pre_keys", "created_at", "INTEGER").await?;
+        let now_ms = SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as i64;
+        sqlx::query("UPDATE pre_keys SET created_at = ? WHERE created_at IS NULL")
+            .bind(now_ms)
+            .execute(&self.pool)
+            .await
+            .context("Failed to backfill prekey creation times")?;
+
+        Ok(())
+    }
+
+    /// Add a nullable column to a table created by an older version
+    async fn add_column_if_missing(&self, table: &str, column: &str, definition: &str) -> Result<()> {
+        let exists: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM pragma_table_info(?) WHERE name = ?"
+        )
+        .bind(table)
+        .bind(column)
+        .fetch_one(&self.pool)
+        .await
+        .with_context(|| format!("Failed to inspect {} table", table))?;
+
+        if exists == 0 {
+            sqlx::query(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+                .execute(&self.pool)
+                .await
+                .with_context(|| format!("Failed to add {}.{}", table, column))?;
+        }
+
         Ok(())
     }
 
+    /// Encrypt message content from now on, with a key from the local identity
+    ///
+    /// Rows already in the database are left as they are; call
+    /// `encrypt_stored_content` to convert them.
+    pub fn set_content_cipher(&mut self, cipher: ContentCipher) {
+        self.content_cipher = Some(cipher);
+    }
+
+    /// Set how many message reads `get_messages_by_ids` runs at once
+    ///
+    /// Reads beyond the connection pool's size wait for a free connection.
+    pub fn set_fetch_concurrency(&mut self, concurrency: usize) {
+        self.fetch_concurrency = concurrency.max(1);
+    }
+
+    /// Signal protocol stores for our identity, kept in this database
+    pub fn signal_store(&self, identity_key_pair: IdentityKeyPair, registration_id: u32) -> SignalStore {
+        SignalStore::new(self.pool.clone(), identity_key_pair, registration_id)
+    }
+
+    /// Signal protocol stores for the identity saved in this database,
+    /// generating and saving one on first use
+    ///
+    /// The identity has to outlive restarts: peers pin the identity key
+    /// they first saw, and reject sessions from a new one.
+    pub async fn load_signal_store(&self) -> Result<SignalStore> {
+        let (identity_key_pair, registration_id) = match self.get_signal_identity().await? {
+            Some(identity) => identity,
+            None => {
+                let identity_key_pair = generate_identity_keypair()?;
+                let registration_id = generate_registration_id();
+                // Another start racing us keeps its identity; load whichever won
+                sqlx::query(
+                    "INSERT OR IGNORE INTO signal_identity (id, identity_key_pair, registration_id) VALUES (1, ?, ?)"
+                )
+                .bind(&identity_key_pair.serialize()[..])
+                .bind(registration_id as i64)
+                .execute(&self.pool)
+                .await
+                .context("Failed to save Signal identity")?;
+                tracing::info!("Generated a new Signal identity");
+                self.get_signal_identity().await?.context("Signal identity missing after saving it")?
+            }
+        };
+        Ok(self.signal_store(identity_key_pair, registration_id))
+    }
+
+    /// Our saved Signal identity and registration ID, if one was created
+    pub async fn get_signal_identity(&self) -> Result<Option<(IdentityKeyPair, u32)>> {
+        let row = sqlx::query("SELECT identity_key_pair, registration_id FROM signal_identity WHERE id = 1")
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to load Signal identity")?;
+
+        row.map(|row| {
+            let key_pair: Vec<u8> = row.try_get("identity_key_pair")?;
+            let registration_id: i64 = row.try_get("registration_id")?;
+            let identity_key_pair =
+                IdentityKeyPair::try_from(&key_pair[..]).context("Stored Signal identity is corrupt")?;
+            Ok((identity_key_pair, registration_id as u32))
+        })
+        .transpose()
+    }
+
+    /// Encrypt the content of every message still stored in plaintext
+    ///
+    /// Runs in one transaction so an interrupted migration leaves every row
+    /// either untouched or fully converted. Returns how many rows changed.
+    /// Only plaintext rows are read, so once a database is converted this
+    /// reads nothing on later starts.
+    pub async fn encrypt_stored_content(&self) -> Result<usize> {
+        let Some(cipher) = &self.content_cipher else {
+            return Ok(0);
+        };
+        let empty_content = serde_json::to_string(&MessageContent::new(String::new()))?;
+
+        let mut tx = self.pool.begin().await?;
+        let mut converted = 0;
+        for column in CONTENT_COLUMNS {
+            let rows = sqlx::query(&format!(
+                "SELECT rowid, {column} AS stored FROM messages WHERE {column} != ? AND {column} NOT LIKE ?"
+            ))
+            .bind(&empty_content)
+            .bind(cipher::ENCRYPTED_PATTERN)
+            .fetch_all(&mut *tx)
+            .await
+            .context("Failed to read stored content")?;
+
+            for row in rows {
+                let rowid: i64 = row.try_get("rowid")?;
+                let content: String = row.try_get("stored")?;
+                sqlx::query(&format!("UPDATE messages SET {column} = ? WHERE rowid = ?"))
+                    .bind(cipher.encrypt(&content)?)
+                    .bind(rowid)
+                    .execute(&mut *tx)
+                    .await
+                    .context("Failed to encrypt stored content")?;
+                converted += 1;
+            }
+        }
+
+        tx.commit().await.context("Failed to commit content encryption")?;
+        Ok(converted)
+    }
+
+    /// Re-encrypt all stored content under a new key, then use that key
+    ///
+    /// Needed when the identity changes, since the old key is derived from
+    /// the identity being replaced. `switch_key` runs once every row is
+    /// re-encrypted and before the change commits, so the database and the
+    /// identity holding the key change together: if it fails, no row changes.
+    /// Rows the current key can't open are left as they are and counted.
+    pub async fn rekey_content(
+        &mut self,
+        cipher: ContentCipher,
+        switch_key: impl FnOnce() -> Result<()>,
+    ) -> Result<Rekeyed> {
+        let mut tx = self.pool.begin().await?;
+        let mut rekeyed = Rekeyed::default();
+        for column in CONTENT_COLUMNS {
+            let rows = sqlx::query(&format!("SELECT rowid, {column} AS stored FROM messages WHERE {column} LIKE ?"))
+                .bind(cipher::ENCRYPTED_PATTERN)
+                .fetch_all(&mut *tx)
+                .await
+                .context("Failed to read stored content")?;
+
+            for row in rows {
+                let rowid: i64 = row.try_get("rowid")?;
+                let stored: String = row.try_get("stored")?;
+                let plaintext = match self.open_content(&stored) {
+                    Ok(plaintext) => plaintext,
+                    Err(e) => {
+                        tracing::warn!("Message row {} can't be decrypted for re-keying: {}", rowid, e);
+                        rekeyed.unreadable += 1;
+                        continue;
+                    }
+                };
+                sqlx::query(&format!("UPDATE messages SET {column} = ? WHERE rowid = ?"))
+                    .bind(cipher.encrypt(&plaintext)?)
+                    .bind(rowid)
+                    .execute(&mut *tx)
+                    .await
+                    .context("Failed to re-encrypt stored content")?;
+                rekeyed.rekeyed += 1;
+            }
+        }
+
+        // An attachment the old key can't open is dropped; peers still have it
+        let rows = sqlx::query("SELECT hash, data FROM attachments WHERE encrypted = 1")
+            .fetch_all(&mut *tx)
+            .await
+            .context("Failed to read stored attachments")?;
+        for row in rows {
+            let hash: Vec<u8> = row.try_get("hash")?;
+            let sealed: Vec<u8> = row.try_get("data")?;
+            match self.content_cipher.as_ref().map(|old| old.open(&sealed)) {
+                Some(Ok(data)) => {
+                    sqlx::query("UPDATE attachments SET data = ? WHERE hash = ?")
+                        .bind(cipher.seal(&data)?)
+                        .bind(&hash)
+                        .execute(&mut *tx)
+                        .await
+                        .context("Failed to re-encrypt stored attachment")?;
+                }
+                _ => {
+                    tracing::warn!("Dropping an attachment that can't be decrypted for re-keying");
+                    sqlx::query("DELETE FROM attachments WHERE hash = ?")
+                        .bind(&hash)
+                        .execute(&mut *tx)
+                        .await
+                        .context("Failed to drop unreadable attachment")?;
+                }
+            }
+        }
+
+        switch_key()?;
+        tx.commit().await.context("Failed to commit re-encrypted content")?;
+        self.content_cipher = Some(cipher);
+        Ok(rekeyed)
+    }
+
+    /// Serialize content for the `content` column, encrypting it if enabled
+    ///
+    /// Empty content (expired messages) stays plaintext: there's nothing to
+    /// hide, and `expire_messages` matches on it.
+    fn seal_content(&self, content: &MessageContent) -> Result<String> {
+        let json = serde_json::to_string(content)?;
+        match &self.content_cipher {
+            Some(cipher) if !content.text.is_empty() => cipher.encrypt(&json),
+            _ => Ok(json),
+        }
+    }
+
+    /// Undo `seal_content`; plaintext rows from before encryption read as-is
+    fn open_content(&self, stored: &str) -> Result<String> {
+        match &self.content_cipher {
+            Some(cipher) => cipher.decrypt(stored),
+            None if cipher::is_encrypted(stored) => {
+                Err(anyhow::anyhow!("Content is encrypted and no identity key is loaded"))
+            }
+            None => Ok(stored.to_string()),
+        }
+    }
+
     /// Store a message
-    pub async fn store_message(&self, message: &Message) -> Result<()> {
+    pub async fn store_message(&self, message: &Message) -> Result<(), StorageError> {
         let id_bytes = message.id.0.as_bytes();
         let channel_id_bytes = message.channel_id.0.as_bytes();
         let author_bytes = message.author.0.as_bytes();
-        let content_json = serde_json::to_string(&message.content)?;
-        let vector_clock_bytes = bincode::serialize(&message.vector_clock)?;
-        let parent_hashes_bytes = bincode::serialize(&message.parent_hashes)?;
-        let created_at = message
-            .created_at
-            .duration_since(UNIX_EPOCH)
-            .unwrap_or_default()
-            .as_secs() as i64;
+        let content_json = self.seal_content(&message.content)?;
+        let (vector_clock_bytes, parent_hashes_bytes) = encode_links(message)?;
+        // Whole seconds stay in `created_at` for older versions reading the file
+        let created_at = (message.created_at_ms / 1000) as i64;
 
         sqlx::query(
             r#"
-            INSERT INTO messages (id, channel_id, author, content, vector_clock, lamport_timestamp, parent_hashes, created_at)
-            VALUES (?, ?, ?, ?, ?, ?, ?, ?)
+            INSERT INTO messages (id, channel_id, author, content, vector_clock, lamport_timestamp, parent_hashes, created_at, created_at_ms, expires_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
             "#,
         )
         .bind(&id_bytes[..])
@@ -233,31 +781,37 @@ impl Storage {
         .bind(message.lamport_timestamp as i64)
         .bind(parent_hashes_bytes)
         .bind(created_at)
+        .bind(message.created_at_ms as i64)
+        .bind(message.expires_at.map(|t| t as i64))
         .execute(&self.pool)
         .await
-        .context("Failed to store message")?;
+        .map_err(|e| StorageError::database("Failed to store message", e))?;
 
         Ok(())
     }
 
     /// Get a message by ID
-    pub async fn get_message(&self, message_id: MessageId) -> Result<Option<Message>> {
+    pub async fn get_message(&self, message_id: MessageId) -> Result<Option<Message>, StorageError> {
         let id_bytes = message_id.0.as_bytes();
 
         let row = sqlx::query(
             r#"
-            SELECT id, channel_id, author, content, vector_clock, lamport_timestamp, parent_hashes, created_at
+            SELECT id, channel_id, author, content, vector_clock, lamport_timestamp, parent_hashes, COALESCE(created_at_ms, created_at * 1000) AS created_at_ms, expires_at, edit_content, edited_at
             FROM messages
             WHERE id = ?
             "#,
         )
         .bind(&id_bytes[..])
         .fetch_optional(&self.pool)
-        .await?;
+        .await
+        .map_err(|e| StorageError::database("Failed to load message", e))?;
 
         match row {
             Some(row) => {
-                let message = self.row_to_message(row)?;
+                let message = self.row_to_message(row).map_err(|e| StorageError::Corrupt {
+                    message_id,
+                    reason: format!("{:#}", e),
+                })?;
                 Ok(Some(message))
             }
             None => Ok(None),
@@ -265,47 +819,109 @@ impl Storage {
     }
 
     /// Get all messages for a channel, ordered by creation time
-    pub async fn get_channel_messages(&self, channel_id: ChannelId) -> Result<Vec<Message>> {
+    pub async fn get_channel_messages(&self, channel_id: ChannelId) -> Result<Vec<Message>, StorageError> {
         let channel_id_bytes = channel_id.0.as_bytes();
 
         let rows = sqlx::query(
             r#"
-            SELECT id, channel_id, author, content, vector_clock, lamport_timestamp, parent_hashes, created_at
+            SELECT id, channel_id, author, content, vector_clock, lamport_timestamp, parent_hashes, COALESCE(created_at_ms, created_at * 1000) AS created_at_ms, expires_at, edit_content, edited_at
             FROM messages
             WHERE channel_id = ?
-            ORDER BY created_at ASC, lamport_timestamp ASC
+            ORDER BY created_at_ms ASC, lamport_timestamp ASC
             "#,
         )
         .bind(&channel_id_bytes[..])
         .fetch_all(&self.pool)
-        .await?;
+        .await
+        .map_err(|e| StorageError::database("Failed to load channel messages", e))?;
+
+        Ok(self.rows_to_channel_messages(channel_id, rows))
+    }
+
+    /// Get the `limit` messages of a channel received most recently, oldest first
+    ///
+    /// Goes by the order they were stored in rather than the creation time
+    /// their author claims, which a peer can set to anything.
+    pub async fn get_recent_channel_messages(
+        &self,
+        channel_id: ChannelId,
+        limit: usize,
+    ) -> Result<Vec<Message>, StorageError> {
+        let channel_id_bytes = channel_id.0.as_bytes();
+
+        let rows = sqlx::query(
+            r#"
+            SELECT id, channel_id, author, content, vector_clock, lamport_timestamp, parent_hashes, COALESCE(created_at_ms, created_at * 1000) AS created_at_ms, expires_at, edit_content, edited_at
+            FROM messages
+            WHERE channel_id = ?
+            ORDER BY rowid DESC
+            LIMIT ?
+            "#,
+        )
+        .bind(&channel_id_bytes[..])
+        .bind(limit as i64)
+        .fetch_all(&self.pool)
+        .await
+        .map_err(|e| StorageError::database("Failed to load recent channel messages", e))?;
+
+        let mut messages = self.rows_to_channel_messages(channel_id, rows);
+        messages.reverse();
+        Ok(messages)
+    }
 
+    fn rows_to_channel_messages(&self, channel_id: ChannelId, rows: Vec<sqlx::sqlite::SqliteRow>) -> Vec<Message> {
+        // One damaged row (e.g. a write cut short by a crash) must not make the
+        // whole channel unloadable, so unreadable rows are logged and skipped
         let mut messages = Vec::new();
         for row in rows {
-            messages.push(self.row_to_message(row)?);
+            match self.row_to_message(row) {
+                Ok(message) => messages.push(message),
+                Err(e) => tracing::warn!("Skipping unreadable message in channel {:?}: {:#}", channel_id, e),
+            }
         }
-
-        Ok(messages)
+        messages
     }
 
     /// Helper to convert a database row to a Message
+    ///
+    /// Never panics on malformed rows: type mismatches and truncated blobs are
+    /// returned as errors naming the bad column.
     fn row_to_message(&self, row: sqlx::sqlite::SqliteRow) -> Result<Message> {
-        let id_bytes: Vec<u8> = row.get("id");
-        let channel_id_bytes: Vec<u8> = row.get("channel_id");
-        let author_bytes: Vec<u8> = row.get("author");
-        let content_json: String = row.get("content");
-        let vector_clock_bytes: Vec<u8> = row.get("vector_clock");
-        let lamport_timestamp: i64 = row.get("lamport_timestamp");
-        let parent_hashes_bytes: Vec<u8> = row.get("parent_hashes");
-        let created_at: i64 = row.get("created_at");
-
-        let id = MessageId(uuid::Uuid::from_slice(&id_bytes)?);
-        let channel_id = ChannelId(uuid::Uuid::from_slice(&channel_id_bytes)?);
-        let author = PeerId(uuid::Uuid::from_slice(&author_bytes)?);
-        let content = serde_json::from_str(&content_json)?;
-        let vector_clock: VectorClock = bincode::deserialize(&vector_clock_bytes)?;
-        let parent_hashes: Vec<MessageId> = bincode::deserialize(&parent_hashes_bytes)?;
-        let created_at = UNIX_EPOCH + std::time::Duration::from_secs(created_at as u64);
+        let id_bytes: Vec<u8> = row.try_get("id")?;
+        let channel_id_bytes: Vec<u8> = row.try_get("channel_id")?;
+        let author_bytes: Vec<u8> = row.try_get("author")?;
+        let content_json: String = row.try_get("content")?;
+        let vector_clock_bytes: Vec<u8> = row.try_get("vector_clock")?;
+        let lamport_timestamp: i64 = row.try_get("lamport_timestamp")?;
+        let parent_hashes_bytes: Vec<u8> = row.try_get("parent_hashes")?;
+        let created_at_ms: i64 = row.try_get("created_at_ms")?;
+        let expires_at: Option<i64> = row.try_get("expires_at")?;
+        let edit_content: Option<String> = row.try_get("edit_content")?;
+        let edited_at: Option<Vec<u8>> = row.try_get("edited_at")?;
+
+        let id = MessageId(uuid::Uuid::from_slice(&id_bytes).context("Invalid message id")?);
+        let channel_id = ChannelId(uuid::Uuid::from_slice(&channel_id_bytes).context("Invalid channel id")?);
+        let author = PeerId(uuid::Uuid::from_slice(&author_bytes).context("Invalid author")?);
+        let content_json = self.open_content(&content_json)
+            .with_context(|| format!("Message {:?} has unreadable content", id))?;
+        let content = serde_json::from_str(&content_json)
+            .with_context(|| format!("Message {:?} has invalid content", id))?;
+        let vector_clock: VectorClock = bincode::deserialize(&vector_clock_bytes)
+            .with_context(|| format!("Message {:?} has a corrupt vector clock", id))?;
+        let parent_hashes: Vec<MessageId> = bincode::deserialize(&parent_hashes_bytes)
+            .with_context(|| format!("Message {:?} has corrupt parent hashes", id))?;
+        let edit = match (edit_content, edited_at) {
+            (Some(edit_content), Some(edited_at)) => {
+                let edit_json = self.open_content(&edit_content)
+                    .with_context(|| format!("Message {:?} has an unreadable edit", id))?;
+                let edit_content = serde_json::from_str(&edit_json)
+                    .with_context(|| format!("Message {:?} has an invalid edit", id))?;
+                let edited_at: Timestamp = bincode::deserialize(&edited_at)
+                    .with_context(|| format!("Message {:?} has a corrupt edit time", id))?;
+                Some(LWWRegister::new(edit_content, edited_at))
+            }
+            _ => None,
+        };
 
         Ok(Message {
             id,
@@ -315,25 +931,192 @@ impl Storage {
             vector_clock,
             lamport_timestamp: lamport_timestamp as u64,
             parent_hashes,
-            created_at,
+            created_at_ms: created_at_ms as u64,
+            expires_at: expires_at.map(|t| t as u64),
+            edit,
         })
     }
 
-    /// Store a channel with CRDT state
-    pub async fn store_channel(&self, channel: &Channel) -> Result<()> {
-        let id_bytes = channel.id.0.as_bytes();
-        let channel_type_str = match channel.channel_type {
-            ChannelType::PeerToPeer => "PeerToPeer",
-            ChannelType::Group => "Group",
-        };
+    /// Check every stored message and report the rows that can't be read
+    ///
+    /// Reads skip such rows, so this is how they get surfaced for repair.
+    pub async fn scan_messages(&self) -> Result<Vec<UnreadableMessage>> {
+        let rows = sqlx::query(
+            r#"
+            SELECT rowid, id, channel_id, author, content, vector_clock, lamport_timestamp, parent_hashes, COALESCE(created_at_ms, created_at * 1000) AS created_at_ms, expires_at, edit_content, edited_at
+            FROM messages
+            "#,
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to scan messages")?;
 
-        // Cache the name and members for quick display
-        let name = channel.get_name().clone();
-        let members = channel.get_members();
-        let members_bytes = bincode::serialize(&members)?;
+        let mut unreadable = Vec::new();
+        for row in rows {
+            let rowid: i64 = row.try_get("rowid")?;
+            let message_id = row
+                .try_get::<Vec<u8>, _>("id")
+                .ok()
+                .and_then(|bytes| uuid::Uuid::from_slice(&bytes).ok())
+                .map(MessageId);
+
+            if let Err(e) = self.row_to_message(row) {
+                unreadable.push(UnreadableMessage {
+                    rowid,
+                    message_id,
+                    error: format!("{:#}", e),
+                });
+            }
+        }
 
-        // Serialize the full CRDT state
-        let crdt_state = bincode::serialize(channel)?;
+        Ok(unreadable)
+    }
+
+    /// Wipe the content of every message that expired by `now_ms` (HLC physical time)
+    ///
+    /// Rows are kept as tombstones so parent links stay intact. Returns the
+    /// IDs of messages that were wiped by this sweep.
+    pub async fn expire_messages(&self, now_ms: u64) -> Result<Vec<MessageId>> {
+        let empty_content = serde_json::to_string(&MessageContent::new(String::new()))?;
+
+        let rows = sqlx::query(
+            "SELECT id, content, edit_content FROM messages \
+             WHERE expires_at IS NOT NULL AND expires_at <= ? AND content != ?"
+        )
+        .bind(now_ms as i64)
+        .bind(&empty_content)
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to find expired messages")?;
+
+        let mut ids = Vec::new();
+        let mut images = Vec::new();
+        for row in rows {
+            let id_bytes: Vec<u8> = row.get("id");
+            ids.push(MessageId(uuid::Uuid::from_slice(&id_bytes)?));
+            for column in CONTENT_COLUMNS {
+                let stored: Option<String> = row.get(column);
+                images.extend(stored.and_then(|stored| self.stored_image(&stored)));
+            }
+        }
+
+        if !ids.is_empty() {
+            sqlx::query(
+                "UPDATE messages SET content = ?, edit_content = NULL, edited_at = NULL WHERE expires_at IS NOT NULL AND expires_at <= ?"
+            )
+                .bind(&empty_content)
+                .bind(now_ms as i64)
+                .execute(&self.pool)
+                .await
+                .context("Failed to expire messages")?;
+        }
+
+        // Their images disappear with them
+        for hash in images {
+            sqlx::query("DELETE FROM attachments WHERE hash = ?")
+                .bind(&hash[..])
+                .execute(&self.pool)
+                .await
+                .context("Failed to expire attachment")?;
+        }
+
+        Ok(ids)
+    }
+
+    /// The hash of the image in sealed content, if it shows one
+    fn stored_image(&self, stored: &str) -> Option<[u8; 32]> {
+        let content: MessageContent = serde_json::from_str(&self.open_content(stored).ok()?).ok()?;
+        content.image_ref().map(|image| image.hash)
+    }
+
+    /// Store an attached image's data under its hash, encrypted if enabled
+    ///
+    /// Data already stored under the hash is kept.
+    pub async fn store_attachment(&self, data: &[u8]) -> Result<[u8; 32]> {
+        let hash = attachment_hash(data);
+        let (stored, encrypted) = match &self.content_cipher {
+            Some(cipher) => (cipher.seal(data)?, true),
+            None => (data.to_vec(), false),
+        };
+        sqlx::query("INSERT OR IGNORE INTO attachments (hash, data, encrypted) VALUES (?, ?, ?)")
+            .bind(&hash[..])
+            .bind(stored)
+            .bind(encrypted)
+            .execute(&self.pool)
+            .await
+            .context("Failed to store attachment")?;
+        Ok(hash)
+    }
+
+    /// An attached image's data, if it's stored
+    pub async fn get_attachment(&self, hash: &[u8; 32]) -> Result<Option<Vec<u8>>> {
+        let Some(row) = sqlx::query("SELECT data, encrypted FROM attachments WHERE hash = ?")
+            .bind(&hash[..])
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to read attachment")?
+        else {
+            return Ok(None);
+        };
+
+        let data: Vec<u8> = row.try_get("data")?;
+        let encrypted: bool = row.try_get("encrypted")?;
+        if !encrypted {
+            return Ok(Some(data));
+        }
+        match &self.content_cipher {
+            Some(cipher) => Ok(Some(cipher.open(&data)?)),
+            None => Err(anyhow::anyhow!("Attachment is encrypted and no identity key is loaded")),
+        }
+    }
+
+    /// Hashes of every stored attachment
+    pub async fn attachment_hashes(&self) -> Result<HashSet<[u8; 32]>> {
+        let rows = sqlx::query("SELECT hash FROM attachments")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to list attachments")?;
+        rows.iter()
+            .map(|row| {
+                let hash: Vec<u8> = row.try_get("hash")?;
+                hash.try_into().map_err(|_| anyhow::anyhow!("Stored attachment hash is not 32 bytes"))
+            })
+            .collect()
+    }
+
+    /// Save a message's latest edit, as applied with `Message::apply_edit`
+    ///
+    /// Does nothing for a message that isn't stored.
+    pub async fn store_message_edit(&self, message: &Message) -> Result<()> {
+        let Some(edit) = &message.edit else {
+            return Ok(());
+        };
+        sqlx::query("UPDATE messages SET edit_content = ?, edited_at = ? WHERE id = ?")
+            .bind(self.seal_content(edit.value())?)
+            .bind(bincode::serialize(&edit.timestamp())?)
+            .bind(&message.id.0.as_bytes()[..])
+            .execute(&self.pool)
+            .await
+            .context("Failed to store message edit")?;
+
+        Ok(())
+    }
+
+    /// Store a channel with CRDT state
+    pub async fn store_channel(&self, channel: &Channel) -> Result<()> {
+        let id_bytes = channel.id.0.as_bytes();
+        let channel_type_str = match channel.channel_type {
+            ChannelType::PeerToPeer => "PeerToPeer",
+            ChannelType::Group => "Group",
+        };
+
+        // Cache the name and members for quick display
+        let name = channel.get_name().clone();
+        let members = channel.get_members();
+        let members_bytes = bincode::serialize(&members)?;
+
+        // Serialize the full CRDT state
+        let crdt_state = channel_state::encode(channel)?;
 
         let created_at = channel
             .created_at
@@ -381,52 +1164,31 @@ impl Storage {
         .await?;
 
         match row {
-            Some(row) => {
-                // Try to deserialize from crdt_state first (Phase 3+)
-                let crdt_state_bytes: Option<Vec<u8>> = row.try_get("crdt_state").ok().flatten();
-
-                if let Some(state_bytes) = crdt_state_bytes {
-                    if let Ok(channel) = bincode::deserialize::<Channel>(&state_bytes) {
-                        return Ok(Some(channel));
-                    }
-                }
-
-                // Fall back to old format (Phase 2) - reconstruct Channel with CRDTs
-                let id_bytes: Vec<u8> = row.get("id");
-                let name: String = row.get("name");
-                let channel_type_str: String = row.get("channel_type");
-                let members_bytes: Vec<u8> = row.get("members");
-                let created_at: i64 = row.get("created_at");
-
-                let id = ChannelId(uuid::Uuid::from_slice(&id_bytes)?);
-                let channel_type = match channel_type_str.as_str() {
-                    "PeerToPeer" => ChannelType::PeerToPeer,
-                    "Group" => ChannelType::Group,
-                    _ => ChannelType::Group,
-                };
-                let old_members: Vec<PeerId> = bincode::deserialize(&members_bytes)?;
-                let created_at = UNIX_EPOCH + std::time::Duration::from_secs(created_at as u64);
-
-                // Create a new channel with CRDT state from old data
-                // Use first member as creator, or generate a placeholder peer
-                let creator = old_members.first().copied().unwrap_or_else(PeerId::new);
-                let mut channel = Channel::placeholder(id, name, creator);
-                channel.channel_type = channel_type;
-                channel.created_at = created_at;
-
-                // Add all members to the ORSet
-                for member in old_members {
-                    channel.add_member(member);
-                }
-
-                Ok(Some(channel))
-            }
+            Some(row) => Ok(Some(self.row_to_channel(row)?)),
             None => Ok(None),
         }
     }
 
-    /// Get all channels
+    /// Get all channels, excluding deleted (tombstoned) ones
     pub async fn get_all_channels(&self) -> Result<Vec<Channel>> {
+        let mut channels = self.fetch_all_channels().await?;
+        channels.retain(|channel| !channel.is_deleted());
+        Ok(channels)
+    }
+
+    /// Get the IDs of all deleted (tombstoned) channels
+    pub async fn get_deleted_channel_ids(&self) -> Result<HashSet<ChannelId>> {
+        Ok(self
+            .fetch_all_channels()
+            .await?
+            .into_iter()
+            .filter(|channel| channel.is_deleted())
+            .map(|channel| channel.id)
+            .collect())
+    }
+
+    /// Fetch every channel row, including tombstoned channels
+    async fn fetch_all_channels(&self) -> Result<Vec<Channel>> {
         let rows = sqlx::query(
             r#"
             SELECT id, name, channel_type, members, created_at, crdt_state
@@ -439,47 +1201,68 @@ impl Storage {
 
         let mut channels = Vec::new();
         for row in rows {
-            // Try to deserialize from crdt_state first (Phase 3+)
-            let crdt_state_bytes: Option<Vec<u8>> = row.try_get("crdt_state").ok().flatten();
+            channels.push(self.row_to_channel(row)?);
+        }
 
-            if let Some(state_bytes) = crdt_state_bytes {
-                if let Ok(channel) = bincode::deserialize::<Channel>(&state_bytes) {
-                    channels.push(channel);
-                    continue;
+        Ok(channels)
+    }
+
+    /// Helper to convert a database row to a Channel
+    ///
+    /// Rows without `crdt_state` are legacy (Phase 2) rows and are rebuilt from the
+    /// cached columns. If `crdt_state` is present but fails to decode, the same
+    /// reconstruction is used as a best effort and the channel is flagged degraded.
+    /// Blobs written by older versions are upgraded by `channel_state::decode`.
+    fn row_to_channel(&self, row: sqlx::sqlite::SqliteRow) -> Result<Channel> {
+        let crdt_state_bytes: Option<Vec<u8>> = row.try_get("crdt_state").ok().flatten();
+
+        let degraded = match crdt_state_bytes {
+            Some(state_bytes) => match channel_state::decode(&state_bytes) {
+                Ok(channel) => return Ok(channel),
+                Err(e) => {
+                    let id_bytes: Vec<u8> = row.get("id");
+                    tracing::error!(
+                        "Corrupt CRDT state for channel {:?} ({} bytes): {} - reconstructing from cached columns",
+                        uuid::Uuid::from_slice(&id_bytes).ok(),
+                        state_bytes.len(),
+                        e
+                    );
+                    true
                 }
-            }
+            },
+            None => false,
+        };
 
-            // Fall back to old format (Phase 2) - reconstruct Channel with CRDTs
-            let id_bytes: Vec<u8> = row.get("id");
-            let name: String = row.get("name");
-            let channel_type_str: String = row.get("channel_type");
-            let members_bytes: Vec<u8> = row.get("members");
-            let created_at: i64 = row.get("created_at");
-
-            let id = ChannelId(uuid::Uuid::from_slice(&id_bytes)?);
-            let channel_type = match channel_type_str.as_str() {
-                "PeerToPeer" => ChannelType::PeerToPeer,
-                "Group" => ChannelType::Group,
-                _ => ChannelType::Group,
-            };
-            let old_members: Vec<PeerId> = bincode::deserialize(&members_bytes)?;
-            let created_at = UNIX_EPOCH + std::time::Duration::from_secs(created_at as u64);
-
-            // Create a new channel with CRDT state from old data
-            let creator = old_members.first().copied().unwrap_or_else(PeerId::new);
-            let mut channel = Channel::placeholder(id, name, creator);
-            channel.channel_type = channel_type;
-            channel.created_at = created_at;
-
-            // Add all members to the ORSet
-            for member in old_members {
-                channel.add_member(member);
-            }
+        // Reconstruct Channel with CRDTs from the cached columns
+        let id_bytes: Vec<u8> = row.get("id");
+        let name: String = row.get("name");
+        let channel_type_str: String = row.get("channel_type");
+        let members_bytes: Vec<u8> = row.get("members");
+        let created_at: i64 = row.get("created_at");
 
-            channels.push(channel);
+        let id = ChannelId(uuid::Uuid::from_slice(&id_bytes)?);
+        let channel_type = match channel_type_str.as_str() {
+            "PeerToPeer" => ChannelType::PeerToPeer,
+            "Group" => ChannelType::Group,
+            _ => ChannelType::Group,
+        };
+        let old_members: Vec<PeerId> = bincode::deserialize(&members_bytes)?;
+        let created_at = UNIX_EPOCH + Duration::from_secs(created_at as u64);
+
+        // Create a new channel with CRDT state from old data
+        // Use first member as creator, or generate a placeholder peer
+        let creator = old_members.first().copied().unwrap_or_else(PeerId::new);
+        let mut channel = Channel::placeholder(id, name, creator);
+        channel.channel_type = channel_type;
+        channel.created_at = created_at;
+        channel.degraded = degraded;
+
+        // Add all members to the ORSet
+        for member in old_members {
+            channel.add_member(member);
         }
 
-        Ok(channels)
+        Ok(channel)
     }
 
     /// Delete a channel and all its messages
@@ -492,6 +1275,36 @@ impl Storage {
             .execute(&self.pool)
             .await?;
 
+        sqlx::query("DELETE FROM drafts WHERE channel_id = ?")
+            .bind(&id_bytes[..])
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("DELETE FROM channel_events WHERE channel_id = ?")
+            .bind(&id_bytes[..])
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("DELETE FROM channel_read_state WHERE channel_id = ?")
+            .bind(&id_bytes[..])
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("DELETE FROM muted_channels WHERE channel_id = ?")
+            .bind(&id_bytes[..])
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("DELETE FROM channel_keywords WHERE channel_id = ?")
+            .bind(&id_bytes[..])
+            .execute(&self.pool)
+            .await?;
+
+        sqlx::query("DELETE FROM heads WHERE channel_id = ?")
+            .bind(&id_bytes[..])
+            .execute(&self.pool)
+            .await?;
+
         // Delete channel
         sqlx::query("DELETE FROM channels WHERE id = ?")
             .bind(&id_bytes[..])
@@ -501,146 +1314,1378 @@ impl Storage {
         Ok(())
     }
 
-    // Phase 4: DAG-specific query methods
-
-    /// Get messages by a list of IDs (for DAG synchronization)
-    pub async fn get_messages_by_ids(&self, message_ids: &[MessageId]) -> Result<Vec<Message>> {
-        if message_ids.is_empty() {
-            return Ok(Vec::new());
-        }
+    /// Store a deleted channel's tombstone and drop its messages
+    ///
+    /// The channel row is kept so later announcements merge into the
+    /// tombstone instead of recreating the channel.
+    pub async fn tombstone_channel(&self, channel: &Channel) -> Result<()> {
+        self.store_channel(channel).await?;
 
-        let mut messages = Vec::new();
-        for message_id in message_ids {
-            if let Some(message) = self.get_message(*message_id).await? {
-                messages.push(message);
-            }
-        }
+        let id_bytes = channel.id.0.as_bytes();
+        sqlx::query("DELETE FROM messages WHERE channel_id = ?")
+            .bind(&id_bytes[..])
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete channel messages")?;
+        sqlx::query("DELETE FROM drafts WHERE channel_id = ?")
+            .bind(&id_bytes[..])
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete channel draft")?;
+        sqlx::query("DELETE FROM channel_events WHERE channel_id = ?")
+            .bind(&id_bytes[..])
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete channel events")?;
+        sqlx::query("DELETE FROM channel_read_state WHERE channel_id = ?")
+            .bind(&id_bytes[..])
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete channel read state")?;
+        sqlx::query("DELETE FROM muted_channels WHERE channel_id = ?")
+            .bind(&id_bytes[..])
+            .execute(&self.pool)
+            .await
+            .context("Failed to unmute channel")?;
+        sqlx::query("DELETE FROM channel_keywords WHERE channel_id = ?")
+            .bind(&id_bytes[..])
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete channel keywords")?;
+        sqlx::query("DELETE FROM heads WHERE channel_id = ?")
+            .bind(&id_bytes[..])
+            .execute(&self.pool)
+            .await
+            .context("Failed to delete channel heads")?;
 
-        Ok(messages)
+        Ok(())
     }
 
-    /// Check if a message exists
-    pub async fn has_message(&self, message_id: MessageId) -> Result<bool> {
+    /// Hide a message from the local view (peers are not affected)
+    pub async fn hide_message(&self, message_id: MessageId) -> Result<()> {
         let id_bytes = message_id.0.as_bytes();
+        let hidden_at = std::time::SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
 
-        let count: i64 = sqlx::query_scalar(
-            "SELECT COUNT(*) FROM messages WHERE id = ?"
-        )
-        .bind(&id_bytes[..])
-        .fetch_one(&self.pool)
-        .await?;
+        sqlx::query("INSERT OR IGNORE INTO hidden_messages (message_id, hidden_at) VALUES (?, ?)")
+            .bind(&id_bytes[..])
+            .bind(hidden_at)
+            .execute(&self.pool)
+            .await
+            .context("Failed to hide message")?;
 
-        Ok(count > 0)
+        Ok(())
     }
 
-    /// Get all message IDs for a channel (for inventory)
-    pub async fn get_channel_message_ids(&self, channel_id: ChannelId) -> Result<Vec<MessageId>> {
-        let channel_id_bytes = channel_id.0.as_bytes();
+    /// Record a join, leave or rename seen in a channel, returning false if
+    /// it was recorded before
+    pub async fn store_channel_event(&self, channel_id: ChannelId, event: &SystemEvent) -> Result<bool> {
+        let event_bytes = bincode::serialize(event)?;
+        let seen_at_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis() as i64;
 
-        let rows = sqlx::query(
-            "SELECT id FROM messages WHERE channel_id = ?"
+        let result = sqlx::query(
+            "INSERT OR IGNORE INTO channel_events (id, channel_id, event, seen_at_ms) VALUES (?, ?, ?, ?)"
         )
-        .bind(&channel_id_bytes[..])
-        .fetch_all(&self.pool)
-        .await?;
+        .bind(&event.id(channel_id).as_bytes()[..])
+        .bind(&channel_id.0.as_bytes()[..])
+        .bind(event_bytes)
+        .bind(seen_at_ms)
+        .execute(&self.pool)
+        .await
+        .context("Failed to store channel event")?;
 
-        let mut ids = Vec::new();
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Every channel's recorded joins, leaves and renames, oldest first, with
+    /// when each was seen
+    pub async fn get_channel_events(&self) -> Result<HashMap<ChannelId, Vec<(SystemTime, SystemEvent)>>> {
+        let rows = sqlx::query("SELECT channel_id, event, seen_at_ms FROM channel_events ORDER BY seen_at_ms")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to load channel events")?;
+
+        let mut events: HashMap<ChannelId, Vec<_>> = HashMap::new();
         for row in rows {
-            let id_bytes: Vec<u8> = row.get("id");
-            let id = MessageId(uuid::Uuid::from_slice(&id_bytes)?);
-            ids.push(id);
+            let id_bytes: Vec<u8> = row.try_get("channel_id")?;
+            let event_bytes: Vec<u8> = row.try_get("event")?;
+            let seen_at_ms: i64 = row.try_get("seen_at_ms")?;
+            events.entry(ChannelId(uuid::Uuid::from_slice(&id_bytes)?)).or_default().push((
+                UNIX_EPOCH + Duration::from_millis(seen_at_ms as u64),
+                bincode::deserialize(&event_bytes)?,
+            ));
         }
 
-        Ok(ids)
+        Ok(events)
     }
 
-    /// Store multiple messages efficiently (for bulk DAG sync)
-    pub async fn store_messages(&self, messages: &[Message]) -> Result<()> {
-        for message in messages {
-            // Use INSERT OR IGNORE to skip duplicates
-            let id_bytes = message.id.0.as_bytes();
-            let channel_id_bytes = message.channel_id.0.as_bytes();
-            let author_bytes = message.author.0.as_bytes();
-            let content_json = serde_json::to_string(&message.content)?;
-            let vector_clock_bytes = bincode::serialize(&message.vector_clock)?;
-            let parent_hashes_bytes = bincode::serialize(&message.parent_hashes)?;
-            let created_at = message
-                .created_at
-                .duration_since(UNIX_EPOCH)
-                .unwrap_or_default()
-                .as_secs() as i64;
-
-            sqlx::query(
-                r#"
-                INSERT OR IGNORE INTO messages (id, channel_id, author, content, vector_clock, lamport_timestamp, parent_hashes, created_at)
-                VALUES (?, ?, ?, ?, ?, ?, ?, ?)
-                "#,
-            )
-            .bind(&id_bytes[..])
-            .bind(&channel_id_bytes[..])
-            .bind(&author_bytes[..])
-            .bind(content_json)
-            .bind(vector_clock_bytes)
-            .bind(message.lamport_timestamp as i64)
-            .bind(parent_hashes_bytes)
-            .bind(created_at)
+    /// Unhide all locally hidden messages, returning how many were restored
+    pub async fn unhide_all_messages(&self) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM hidden_messages")
             .execute(&self.pool)
             .await
-            .context("Failed to store message")?;
-        }
+            .context("Failed to unhide messages")?;
 
-        Ok(())
+        Ok(result.rows_affected())
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::types::{MessageContent, VectorClock};
+    /// Get the IDs of all locally hidden messages
+    pub async fn get_hidden_message_ids(&self) -> Result<HashSet<MessageId>> {
+        let rows = sqlx::query("SELECT message_id FROM hidden_messages")
+            .fetch_all(&self.pool)
+            .await?;
 
-    #[tokio::test]
-    async fn test_channel_crud() {
-        let storage = Storage::new(":memory:").await.unwrap();
+        let mut ids = HashSet::new();
+        for row in rows {
+            let id_bytes: Vec<u8> = row.get("message_id");
+            ids.insert(MessageId(uuid::Uuid::from_slice(&id_bytes)?));
+        }
 
-        let peer_id = PeerId::new();
-        let channel = Channel::new("test-channel".to_string(), peer_id);
-        storage.store_channel(&channel).await.unwrap();
+        Ok(ids)
+    }
 
-        let retrieved = storage.get_channel(channel.id).await.unwrap().unwrap();
-        assert_eq!(retrieved.id, channel.id);
-        assert_eq!(retrieved.get_name(), channel.get_name());
+    /// Replace the stored listen addresses of a peer (libp2p peer ID and multiaddrs as strings)
+    pub async fn store_peer_addresses(&self, peer_id: &str, addresses: &[String]) -> Result<()> {
+        let announced_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
 
-        let all_channels = storage.get_all_channels().await.unwrap();
-        assert_eq!(all_channels.len(), 1);
+        let mut tx = self.pool.begin().await?;
+        sqlx::query("DELETE FROM peer_addresses WHERE peer_id = ?")
+            .bind(peer_id)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to clear peer addresses")?;
+        for address in addresses {
+            sqlx::query("INSERT OR IGNORE INTO peer_addresses (peer_id, address, announced_at) VALUES (?, ?, ?)")
+                .bind(peer_id)
+                .bind(address)
+                .bind(announced_at)
+                .execute(&mut *tx)
+                .await
+                .context("Failed to store peer address")?;
+        }
+        tx.commit().await?;
+
+        Ok(())
     }
 
-    #[tokio::test]
-    async fn test_message_crud() {
-        let storage = Storage::new(":memory:").await.unwrap();
+    /// Get the stored listen addresses of every known peer
+    pub async fn get_peer_addresses(&self) -> Result<HashMap<String, Vec<String>>> {
+        let rows = sqlx::query("SELECT peer_id, address FROM peer_addresses ORDER BY announced_at DESC")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to load peer addresses")?;
 
-        let peer_id = PeerId::new();
-        let channel = Channel::new("test-channel".to_string(), peer_id);
-        storage.store_channel(&channel).await.unwrap();
-        let mut vector_clock = VectorClock::new();
-        vector_clock.increment(peer_id);
+        let mut peers: HashMap<String, Vec<String>> = HashMap::new();
+        for row in rows {
+            peers.entry(row.get("peer_id")).or_default().push(row.get("address"));
+        }
 
-        let message = Message::new(
-            channel.id,
-            peer_id,
-            MessageContent {
-                text: "Hello, world!".to_string(),
-            },
-            vector_clock,
-            1,
-        );
+        Ok(peers)
+    }
 
-        storage.store_message(&message).await.unwrap();
+    /// Save a channel's unsent input, removing the draft if it's empty
+    pub async fn store_draft(&self, channel_id: ChannelId, text: &str) -> Result<()> {
+        let id_bytes = channel_id.0.as_bytes();
 
-        let retrieved = storage.get_message(message.id).await.unwrap().unwrap();
-        assert_eq!(retrieved.id, message.id);
-        assert_eq!(retrieved.content.text, "Hello, world!");
+        if text.is_empty() {
+            sqlx::query("DELETE FROM drafts WHERE channel_id = ?")
+                .bind(&id_bytes[..])
+                .execute(&self.pool)
+                .await
+                .context("Failed to clear draft")?;
+            return Ok(());
+        }
 
-        let channel_messages = storage.get_channel_messages(channel.id).await.unwrap();
-        assert_eq!(channel_messages.len(), 1);
+        let updated_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+
+        sqlx::query(
+            r#"
+            INSERT INTO drafts (channel_id, text, updated_at) VALUES (?, ?, ?)
+            ON CONFLICT(channel_id) DO UPDATE SET text = excluded.text, updated_at = excluded.updated_at
+            "#
+        )
+        .bind(&id_bytes[..])
+        .bind(text)
+        .bind(updated_at)
+        .execute(&self.pool)
+        .await
+        .context("Failed to store draft")?;
+
+        Ok(())
+    }
+
+    /// Get every saved draft
+    pub async fn get_drafts(&self) -> Result<HashMap<ChannelId, String>> {
+        let rows = sqlx::query("SELECT channel_id, text FROM drafts")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to load drafts")?;
+
+        let mut drafts = HashMap::new();
+        for row in rows {
+            let id_bytes: Vec<u8> = row.try_get("channel_id")?;
+            drafts.insert(ChannelId(uuid::Uuid::from_slice(&id_bytes)?), row.try_get("text")?);
+        }
+
+        Ok(drafts)
+    }
+
+    /// Remember the newest message seen in a channel
+    pub async fn store_read_marker(&self, channel_id: ChannelId, marker: ReadMarker) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO channel_read_state (channel_id, last_read_id, last_read_ms) VALUES (?, ?, ?)
+            ON CONFLICT(channel_id) DO UPDATE SET
+                last_read_id = excluded.last_read_id,
+                last_read_ms = excluded.last_read_ms
+            "#
+        )
+        .bind(&channel_id.0.as_bytes()[..])
+        .bind(&marker.message_id.0.as_bytes()[..])
+        .bind(marker.created_at_ms as i64)
+        .execute(&self.pool)
+        .await
+        .context("Failed to store read marker")?;
+
+        Ok(())
+    }
+
+    /// Save several channels' read markers in one transaction
+    ///
+    /// Used by "mark all as read", so a crash can't leave some channels
+    /// cleared and others not.
+    pub async fn store_read_markers(&self, markers: &[(ChannelId, ReadMarker)]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        for (channel_id, marker) in markers {
+            sqlx::query(
+                r#"
+                INSERT INTO channel_read_state (channel_id, last_read_id, last_read_ms) VALUES (?, ?, ?)
+                ON CONFLICT(channel_id) DO UPDATE SET
+                    last_read_id = excluded.last_read_id,
+                    last_read_ms = excluded.last_read_ms
+                "#
+            )
+            .bind(&channel_id.0.as_bytes()[..])
+            .bind(&marker.message_id.0.as_bytes()[..])
+            .bind(marker.created_at_ms as i64)
+            .execute(&mut *tx)
+            .await
+            .context("Failed to store read marker")?;
+        }
+        tx.commit().await.context("Failed to commit read markers")?;
+
+        Ok(())
+    }
+
+    /// Get the read marker of every channel that has one
+    pub async fn get_read_markers(&self) -> Result<HashMap<ChannelId, ReadMarker>> {
+        let rows = sqlx::query("SELECT channel_id, last_read_id, last_read_ms FROM channel_read_state")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to load read markers")?;
+
+        let mut markers = HashMap::new();
+        for row in rows {
+            let channel_id: Vec<u8> = row.try_get("channel_id")?;
+            let message_id: Vec<u8> = row.try_get("last_read_id")?;
+            let last_read_ms: i64 = row.try_get("last_read_ms")?;
+            markers.insert(
+                ChannelId(uuid::Uuid::from_slice(&channel_id)?),
+                ReadMarker {
+                    message_id: MessageId(uuid::Uuid::from_slice(&message_id)?),
+                    created_at_ms: last_read_ms as u64,
+                },
+            );
+        }
+
+        Ok(markers)
+    }
+
+    /// Mute or unmute a channel's notifications
+    pub async fn set_channel_muted(&self, channel_id: ChannelId, muted: bool) -> Result<()> {
+        let id_bytes = channel_id.0.as_bytes();
+        if muted {
+            let muted_at = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_secs() as i64;
+            sqlx::query("INSERT OR IGNORE INTO muted_channels (channel_id, muted_at) VALUES (?, ?)")
+                .bind(&id_bytes[..])
+                .bind(muted_at)
+                .execute(&self.pool)
+                .await
+                .context("Failed to mute channel")?;
+        } else {
+            sqlx::query("DELETE FROM muted_channels WHERE channel_id = ?")
+                .bind(&id_bytes[..])
+                .execute(&self.pool)
+                .await
+                .context("Failed to unmute channel")?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the IDs of all muted channels
+    pub async fn get_muted_channels(&self) -> Result<HashSet<ChannelId>> {
+        let rows = sqlx::query("SELECT channel_id FROM muted_channels")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to load muted channels")?;
+
+        let mut ids = HashSet::new();
+        for row in rows {
+            let id_bytes: Vec<u8> = row.get("channel_id");
+            ids.insert(ChannelId(uuid::Uuid::from_slice(&id_bytes)?));
+        }
+
+        Ok(ids)
+    }
+
+    /// Replace a channel's notification keywords; an empty list removes them
+    pub async fn set_channel_keywords(&self, channel_id: ChannelId, keywords: &[String]) -> Result<()> {
+        let id_bytes = channel_id.0.as_bytes();
+        if keywords.is_empty() {
+            sqlx::query("DELETE FROM channel_keywords WHERE channel_id = ?")
+                .bind(&id_bytes[..])
+                .execute(&self.pool)
+                .await
+                .context("Failed to clear channel keywords")?;
+        } else {
+            sqlx::query(
+                "INSERT INTO channel_keywords (channel_id, keywords) VALUES (?, ?) ON CONFLICT(channel_id) DO UPDATE SET keywords = excluded.keywords"
+            )
+            .bind(&id_bytes[..])
+            .bind(keywords.join("\n"))
+            .execute(&self.pool)
+            .await
+            .context("Failed to store channel keywords")?;
+        }
+
+        Ok(())
+    }
+
+    /// Get the notification keywords of every channel that has some
+    pub async fn get_channel_keywords(&self) -> Result<HashMap<ChannelId, Vec<String>>> {
+        let rows = sqlx::query("SELECT channel_id, keywords FROM channel_keywords")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to load channel keywords")?;
+
+        let mut keywords = HashMap::new();
+        for row in rows {
+            let id_bytes: Vec<u8> = row.try_get("channel_id")?;
+            let text: String = row.try_get("keywords")?;
+            keywords.insert(
+                ChannelId(uuid::Uuid::from_slice(&id_bytes)?),
+                text.lines().map(str::to_string).collect(),
+            );
+        }
+
+        Ok(keywords)
+    }
+
+    /// Save a channel's current DAG heads and orphans
+    pub async fn store_heads(&self, channel_id: ChannelId, heads: &[MessageId], orphans: &[MessageId]) -> Result<()> {
+        let heads_bytes = bincode::serialize(heads)?;
+        let orphans_bytes = bincode::serialize(orphans)?;
+        sqlx::query(
+            r#"
+            INSERT INTO heads (channel_id, message_ids, orphan_ids) VALUES (?, ?, ?)
+            ON CONFLICT(channel_id) DO UPDATE SET message_ids = excluded.message_ids, orphan_ids = excluded.orphan_ids
+            "#,
+        )
+        .bind(&channel_id.0.as_bytes()[..])
+        .bind(heads_bytes)
+        .bind(orphans_bytes)
+        .execute(&self.pool)
+        .await
+        .context("Failed to store channel heads")?;
+
+        Ok(())
+    }
+
+    /// Get a channel's saved DAG heads and orphans, or None if none were
+    /// saved (or only heads were, by an older version)
+    ///
+    /// A row that doesn't decode is an error; callers fall back to
+    /// rebuilding the DAG from every stored message.
+    pub async fn get_heads(&self, channel_id: ChannelId) -> Result<Option<SavedHeads>> {
+        let row = sqlx::query("SELECT message_ids, orphan_ids FROM heads WHERE channel_id = ?")
+            .bind(&channel_id.0.as_bytes()[..])
+            .fetch_optional(&self.pool)
+            .await
+            .context("Failed to load channel heads")?;
+        let Some(row) = row else {
+            return Ok(None);
+        };
+
+        let heads_bytes: Vec<u8> = row.get("message_ids");
+        let orphans_bytes: Option<Vec<u8>> = row.get("orphan_ids");
+        let Some(orphans_bytes) = orphans_bytes else {
+            return Ok(None);
+        };
+        Ok(Some(SavedHeads {
+            heads: bincode::deserialize(&heads_bytes).context("Corrupt channel heads")?,
+            orphans: bincode::deserialize(&orphans_bytes).context("Corrupt channel orphans")?,
+        }))
+    }
+
+    /// Save a local setting
+    pub async fn store_setting(&self, key: &str, value: &str) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO settings (key, value) VALUES (?, ?) ON CONFLICT(key) DO UPDATE SET value = excluded.value"
+        )
+        .bind(key)
+        .bind(value)
+        .execute(&self.pool)
+        .await
+        .with_context(|| format!("Failed to store setting {}", key))?;
+
+        Ok(())
+    }
+
+    /// Get a local setting, if it was ever saved
+    pub async fn get_setting(&self, key: &str) -> Result<Option<String>> {
+        sqlx::query_scalar("SELECT value FROM settings WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await
+            .with_context(|| format!("Failed to load setting {}", key))
+    }
+
+    // Phase 4: DAG-specific query methods
+
+    /// Message count and newest message time for every channel with messages
+    pub async fn get_channel_activity(&self) -> Result<HashMap<ChannelId, ChannelActivity>> {
+        let rows = sqlx::query(
+            "SELECT channel_id, COUNT(*) AS message_count, MAX(COALESCE(created_at_ms, created_at * 1000)) AS last_message_at FROM messages GROUP BY channel_id"
+        )
+        .fetch_all(&self.pool)
+        .await
+        .context("Failed to load channel activity")?;
+
+        let mut activity = HashMap::new();
+        for row in rows {
+            let channel_id_bytes: Vec<u8> = row.try_get("channel_id")?;
+            let message_count: i64 = row.try_get("message_count")?;
+            let last_message_at: i64 = row.try_get("last_message_at")?;
+
+            activity.insert(
+                ChannelId(uuid::Uuid::from_slice(&channel_id_bytes)?),
+                ChannelActivity {
+                    message_count: message_count as u64,
+                    last_message_at: Some(UNIX_EPOCH + Duration::from_millis(last_message_at as u64)),
+                },
+            );
+        }
+
+        Ok(activity)
+    }
+
+    /// Get the highest Lamport timestamp stored in each channel
+    pub async fn get_max_lamports(&self) -> Result<HashMap<ChannelId, u64>> {
+        let rows = sqlx::query("SELECT channel_id, MAX(lamport_timestamp) AS lamport FROM messages GROUP BY channel_id")
+            .fetch_all(&self.pool)
+            .await
+            .context("Failed to load Lamport timestamps")?;
+
+        let mut lamports = HashMap::new();
+        for row in rows {
+            let id_bytes: Vec<u8> = row.try_get("channel_id")?;
+            let lamport: i64 = row.try_get("lamport")?;
+            lamports.insert(ChannelId(uuid::Uuid::from_slice(&id_bytes)?), lamport as u64);
+        }
+
+        Ok(lamports)
+    }
+
+    /// Get messages by a list of IDs (for DAG synchronization)
+    pub async fn get_messages_by_ids(&self, message_ids: &[MessageId]) -> Result<Vec<Message>, StorageError> {
+        if message_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        // Lookups run concurrently, so messages come back in no particular order
+        let messages: Vec<Option<Message>> = stream::iter(message_ids)
+            .map(|message_id| async move {
+                match self.get_message(*message_id).await {
+                    // Don't let one damaged row fail the whole batch
+                    Err(e @ StorageError::Corrupt { .. }) => {
+                        tracing::warn!("Skipping message: {}", e);
+                        Ok(None)
+                    }
+                    result => result,
+                }
+            })
+            .buffer_unordered(self.fetch_concurrency)
+            .try_collect()
+            .await?;
+
+        Ok(messages.into_iter().flatten().collect())
+    }
+
+    /// Check if a message exists
+    pub async fn has_message(&self, message_id: MessageId) -> Result<bool, StorageError> {
+        let id_bytes = message_id.0.as_bytes();
+
+        let count: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM messages WHERE id = ?"
+        )
+        .bind(&id_bytes[..])
+        .fetch_one(&self.pool)
+        .await
+        .map_err(|e| StorageError::database("Failed to look up message", e))?;
+
+        Ok(count > 0)
+    }
+
+    /// Get all message IDs for a channel (for inventory)
+    pub async fn get_channel_message_ids(&self, channel_id: ChannelId) -> Result<Vec<MessageId>> {
+        let channel_id_bytes = channel_id.0.as_bytes();
+
+        let rows = sqlx::query(
+            "SELECT id FROM messages WHERE channel_id = ?"
+        )
+        .bind(&channel_id_bytes[..])
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut ids = Vec::new();
+        for row in rows {
+            let id_bytes: Vec<u8> = row.get("id");
+            let id = MessageId(uuid::Uuid::from_slice(&id_bytes)?);
+            ids.push(id);
+        }
+
+        Ok(ids)
+    }
+
+    /// When each edited message in a channel was last edited
+    pub async fn get_channel_edit_times(&self, channel_id: ChannelId) -> Result<HashMap<MessageId, Timestamp>> {
+        let rows = sqlx::query("SELECT id, edited_at FROM messages WHERE channel_id = ? AND edited_at IS NOT NULL")
+            .bind(&channel_id.0.as_bytes()[..])
+            .fetch_all(&self.pool)
+            .await?;
+
+        let mut edits = HashMap::new();
+        for row in rows {
+            let id_bytes: Vec<u8> = row.get("id");
+            let edited_at: Vec<u8> = row.get("edited_at");
+            edits.insert(MessageId(uuid::Uuid::from_slice(&id_bytes)?), bincode::deserialize(&edited_at)?);
+        }
+
+        Ok(edits)
+    }
+
+    /// Store multiple messages efficiently (for bulk DAG sync)
+    ///
+    /// Each message is stored on its own, so one that fails doesn't stop the
+    /// rest; the summary says which were stored, which were already there,
+    /// and which failed.
+    pub async fn store_messages(&self, messages: &[Message]) -> StoreSummary {
+        let mut summary = StoreSummary::default();
+        for message in messages {
+            match self.insert_message_if_new(message).await {
+                Ok(true) => summary.stored.push(message.id),
+                Ok(false) => summary.duplicates.push(message.id),
+                Err(e) => summary.failed.push((message.id, e)),
+            }
+        }
+        summary
+    }
+
+    /// Insert a message unless one with its ID is stored, returning whether it was new
+    async fn insert_message_if_new(&self, message: &Message) -> Result<bool, StorageError> {
+        let id_bytes = message.id.0.as_bytes();
+        let channel_id_bytes = message.channel_id.0.as_bytes();
+        let author_bytes = message.author.0.as_bytes();
+        let content_json = self.seal_content(&message.content)?;
+        let (vector_clock_bytes, parent_hashes_bytes) = encode_links(message)?;
+        let created_at = (message.created_at_ms / 1000) as i64;
+
+        let result = sqlx::query(
+            r#"
+            INSERT OR IGNORE INTO messages (id, channel_id, author, content, vector_clock, lamport_timestamp, parent_hashes, created_at, created_at_ms, expires_at)
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            "#,
+        )
+        .bind(&id_bytes[..])
+        .bind(&channel_id_bytes[..])
+        .bind(&author_bytes[..])
+        .bind(content_json)
+        .bind(vector_clock_bytes)
+        .bind(message.lamport_timestamp as i64)
+        .bind(parent_hashes_bytes)
+        .bind(created_at)
+        .bind(message.created_at_ms as i64)
+        .bind(message.expires_at.map(|t| t as i64))
+        .execute(&self.pool)
+        .await
+        .map_err(|e| StorageError::database("Failed to store message", e))?;
+
+        Ok(result.rows_affected() > 0)
+    }
+}
+
+/// Encode a message's vector clock and parent hashes for their blob columns
+fn encode_links(message: &Message) -> Result<(Vec<u8>, Vec<u8>), StorageError> {
+    let vector_clock = bincode::serialize(&message.vector_clock)
+        .map_err(|source| StorageError::Encode { what: "vector clock", source })?;
+    let parent_hashes = bincode::serialize(&message.parent_hashes)
+        .map_err(|source| StorageError::Encode { what: "parent hashes", source })?;
+    Ok((vector_clock, parent_hashes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ImageRef, MessageKind, Priority, VectorClock};
+
+    #[tokio::test]
+    async fn test_channel_crud() {
+        let storage = Storage::new(":memory:").await.unwrap();
+
+        let peer_id = PeerId::new();
+        let channel = Channel::new("test-channel".to_string(), peer_id);
+        storage.store_channel(&channel).await.unwrap();
+
+        let retrieved = storage.get_channel(channel.id).await.unwrap().unwrap();
+        assert_eq!(retrieved.id, channel.id);
+        assert_eq!(retrieved.get_name(), channel.get_name());
+
+        let all_channels = storage.get_all_channels().await.unwrap();
+        assert_eq!(all_channels.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_message_crud() {
+        let storage = Storage::new(":memory:").await.unwrap();
+
+        let peer_id = PeerId::new();
+        let channel = Channel::new("test-channel".to_string(), peer_id);
+        storage.store_channel(&channel).await.unwrap();
+        let mut vector_clock = VectorClock::new();
+        vector_clock.increment(peer_id);
+
+        let message = Message::new(
+            channel.id,
+            peer_id,
+            MessageContent::new("Hello, world!"),
+            vector_clock,
+            1,
+        );
+
+        storage.store_message(&message).await.unwrap();
+
+        let retrieved = storage.get_message(message.id).await.unwrap().unwrap();
+        assert_eq!(retrieved.id, message.id);
+        assert_eq!(retrieved.content.text, "Hello, world!");
+
+        let channel_messages = storage.get_channel_messages(channel.id).await.unwrap();
+        assert_eq!(channel_messages.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_created_at_keeps_milliseconds() {
+        let storage = Storage::new(":memory:").await.unwrap();
+
+        let peer_id = PeerId::new();
+        let channel = Channel::new("test-channel".to_string(), peer_id);
+        storage.store_channel(&channel).await.unwrap();
+
+        // Same second, different milliseconds: storage must keep them apart
+        let content = |text: &str| MessageContent::new(text);
+        let mut first = Message::new(channel.id, peer_id, content("first"), VectorClock::new(), 2);
+        first.created_at_ms = 1_700_000_000_250;
+        let mut second = Message::new(channel.id, peer_id, content("second"), VectorClock::new(), 1);
+        second.created_at_ms = 1_700_000_000_750;
+        storage.store_messages(&[second.clone(), first.clone()]).await.into_result().unwrap();
+
+        let stored = storage.get_message(first.id).await.unwrap().unwrap();
+        assert_eq!(stored.created_at_ms, 1_700_000_000_250);
+
+        let ids: Vec<_> = storage
+            .get_channel_messages(channel.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        assert_eq!(ids, vec![first.id, second.id]);
+    }
+
+    #[tokio::test]
+    async fn test_content_encrypted_at_rest() {
+        // A file of its own: the migration would touch every test's rows in
+        // the shared in-memory database
+        let db_path = std::env::temp_dir().join(format!("burrow-encrypted-{}.db", uuid::Uuid::now_v7()));
+        std::fs::File::create(&db_path).unwrap();
+        let mut storage = Storage::new(&db_path).await.unwrap();
+
+        let peer_id = PeerId::new();
+        let channel = Channel::new("test-channel".to_string(), peer_id);
+        storage.store_channel(&channel).await.unwrap();
+        let content = |text: &str| MessageContent::new(text);
+        let legacy = Message::new(channel.id, peer_id, content("written before"), VectorClock::new(), 1);
+        storage.store_message(&legacy).await.unwrap();
+
+        let keypair = libp2p::identity::Keypair::generate_ed25519();
+        storage.set_content_cipher(ContentCipher::from_identity(&keypair).unwrap());
+        let fresh = Message::new(channel.id, peer_id, content("written after"), VectorClock::new(), 2);
+        storage.store_message(&fresh).await.unwrap();
+
+        // Legacy plaintext still reads, then the migration converts it once
+        assert_eq!(storage.get_message(legacy.id).await.unwrap().unwrap().content.text, "written before");
+        assert_eq!(storage.encrypt_stored_content().await.unwrap(), 1);
+        assert_eq!(storage.encrypt_stored_content().await.unwrap(), 0);
+
+        let raw: Vec<String> = sqlx::query_scalar("SELECT content FROM messages")
+            .fetch_all(&storage.pool)
+            .await
+            .unwrap();
+        assert_eq!(raw.len(), 2);
+        assert!(raw.iter().all(|content| !content.contains("written")));
+
+        let texts: Vec<_> = storage
+            .get_channel_messages(channel.id)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.content.text)
+            .collect();
+        assert_eq!(texts, vec!["written before", "written after"]);
+
+        storage.pool.close().await;
+        std::fs::remove_file(&db_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_expired_messages_keep_tombstone() {
+        let storage = Storage::new(":memory:").await.unwrap();
+
+        let peer_id = PeerId::new();
+        let channel = Channel::new("test-channel".to_string(), peer_id);
+        storage.store_channel(&channel).await.unwrap();
+
+        let content = |text: &str| MessageContent::new(text);
+        let mut parent = Message::new(channel.id, peer_id, content("gone soon"), VectorClock::new(), 1);
+        parent.expires_at = Some(1_000);
+        let mut child = Message::new(channel.id, peer_id, content("reply"), VectorClock::new(), 2);
+        child.parent_hashes = vec![parent.id];
+        child.expires_at = Some(5_000);
+        storage.store_messages(&[parent.clone(), child.clone()]).await.into_result().unwrap();
+
+        assert!(storage.expire_messages(999).await.unwrap().is_empty());
+        assert_eq!(storage.expire_messages(1_000).await.unwrap(), vec![parent.id]);
+        // Already wiped messages aren't reported again
+        assert!(storage.expire_messages(1_000).await.unwrap().is_empty());
+
+        let tombstone = storage.get_message(parent.id).await.unwrap().unwrap();
+        assert!(tombstone.content.text.is_empty());
+        assert_eq!(tombstone.expires_at, Some(1_000));
+
+        let child = storage.get_message(child.id).await.unwrap().unwrap();
+        assert_eq!(child.content.text, "reply");
+        assert_eq!(child.parent_hashes, vec![parent.id]);
+    }
+
+    #[tokio::test]
+    async fn test_attachments_encrypted_and_expired_with_their_message() {
+        let db_path = std::env::temp_dir().join(format!("burrow-attachments-{}.db", uuid::Uuid::now_v7()));
+        std::fs::File::create(&db_path).unwrap();
+        let mut storage = Storage::new(&db_path).await.unwrap();
+        let keypair = libp2p::identity::Keypair::generate_ed25519();
+        storage.set_content_cipher(ContentCipher::from_identity(&keypair).unwrap());
+
+        let data = b"\x89PNG pretend image data".to_vec();
+        let hash = storage.store_attachment(&data).await.unwrap();
+        assert_eq!(hash, attachment_hash(&data));
+        assert_eq!(storage.get_attachment(&hash).await.unwrap(), Some(data.clone()));
+        assert_eq!(storage.attachment_hashes().await.unwrap(), HashSet::from([hash]));
+
+        let raw: Vec<Vec<u8>> = sqlx::query_scalar("SELECT data FROM attachments")
+            .fetch_all(&storage.pool)
+            .await
+            .unwrap();
+        assert!(!raw[0].windows(data.len()).any(|window| window == data));
+
+        let peer_id = PeerId::new();
+        let channel = Channel::new("test-channel".to_string(), peer_id);
+        storage.store_channel(&channel).await.unwrap();
+        let image = ImageRef::for_png(&data, 2, 2);
+        let mut message = Message::new(channel.id, peer_id, MessageContent::image(image), VectorClock::new(), 1);
+        message.expires_at = Some(1_000);
+        storage.store_message(&message).await.unwrap();
+
+        assert_eq!(storage.expire_messages(1_000).await.unwrap(), vec![message.id]);
+        assert_eq!(storage.get_attachment(&hash).await.unwrap(), None);
+
+        storage.pool.close().await;
+        std::fs::remove_file(&db_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_channel_activity() {
+        let storage = Storage::new(":memory:").await.unwrap();
+
+        let peer_id = PeerId::new();
+        let busy = Channel::new("busy".to_string(), peer_id);
+        let quiet = Channel::new("quiet".to_string(), peer_id);
+        storage.store_channel(&busy).await.unwrap();
+        storage.store_channel(&quiet).await.unwrap();
+
+        let mut older = Message::new(busy.id, peer_id, MessageContent::new("a"), VectorClock::new(), 1);
+        older.created_at_ms = 1_000_000;
+        let mut newer = Message::new(busy.id, peer_id, MessageContent::new("b"), VectorClock::new(), 2);
+        newer.created_at_ms = 2_000_000;
+        storage.store_messages(&[older, newer]).await.into_result().unwrap();
+
+        let activity = storage.get_channel_activity().await.unwrap();
+        assert_eq!(
+            activity[&busy.id],
+            ChannelActivity {
+                message_count: 2,
+                last_message_at: Some(UNIX_EPOCH + Duration::from_secs(2_000)),
+            }
+        );
+        // Channels without messages have no entry
+        assert!(!activity.contains_key(&quiet.id));
+    }
+
+    #[tokio::test]
+    async fn test_read_only_storage_rejects_writes() {
+        let db_path = std::env::temp_dir().join(format!("burrow-archive-{}.db", uuid::Uuid::now_v7()));
+        std::fs::File::create(&db_path).unwrap();
+
+        let peer_id = PeerId::new();
+        let channel = Channel::new("archived".to_string(), peer_id);
+        {
+            let storage = Storage::new(&db_path).await.unwrap();
+            storage.store_channel(&channel).await.unwrap();
+            storage.pool.close().await;
+        }
+
+        let archive = Storage::open_read_only(&db_path).await.unwrap();
+        let channels = archive.get_all_channels().await.unwrap();
+        assert_eq!(channels.len(), 1);
+        assert_eq!(channels[0].get_name(), "archived");
+
+        let other = Channel::new("new".to_string(), peer_id);
+        assert!(archive.store_channel(&other).await.is_err());
+        assert_eq!(archive.get_all_channels().await.unwrap().len(), 1);
+
+        archive.pool.close().await;
+        std::fs::remove_file(&db_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_tombstoned_channel_kept_but_hidden() {
+        let storage = Storage::new(":memory:").await.unwrap();
+
+        let peer_id = PeerId::new();
+        let mut channel = Channel::new("doomed".to_string(), peer_id);
+        storage.store_channel(&channel).await.unwrap();
+
+        let message = Message::new(
+            channel.id,
+            peer_id,
+            MessageContent::new("bye"),
+            VectorClock::new(),
+            1,
+        );
+        storage.store_message(&message).await.unwrap();
+
+        channel.delete();
+        storage.tombstone_channel(&channel).await.unwrap();
+
+        assert!(storage.get_all_channels().await.unwrap().is_empty());
+        assert!(storage.get_channel_messages(channel.id).await.unwrap().is_empty());
+        assert!(storage.get_deleted_channel_ids().await.unwrap().contains(&channel.id));
+
+        let stored = storage.get_channel(channel.id).await.unwrap().unwrap();
+        assert!(stored.is_deleted());
+    }
+
+    #[tokio::test]
+    async fn test_hide_and_unhide_messages() {
+        let storage = Storage::new(":memory:").await.unwrap();
+
+        let spam = MessageId::new();
+        storage.hide_message(spam).await.unwrap();
+        storage.hide_message(spam).await.unwrap();
+
+        let hidden = storage.get_hidden_message_ids().await.unwrap();
+        assert_eq!(hidden.len(), 1);
+        assert!(hidden.contains(&spam));
+
+        assert_eq!(storage.unhide_all_messages().await.unwrap(), 1);
+        assert!(storage.get_hidden_message_ids().await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_corrupted_message_skipped_and_reported() {
+        let storage = Storage::new(":memory:").await.unwrap();
+
+        let peer_id = PeerId::new();
+        let channel = Channel::new("test-channel".to_string(), peer_id);
+        storage.store_channel(&channel).await.unwrap();
+
+        let mut vector_clock = VectorClock::new();
+        vector_clock.increment(peer_id);
+        let content = |text: &str| MessageContent::new(text);
+        let good = Message::new(channel.id, peer_id, content("intact"), vector_clock.clone(), 1);
+        let bad = Message::new(channel.id, peer_id, content("truncated"), vector_clock, 2);
+        storage.store_messages(&[good.clone(), bad.clone()]).await.into_result().unwrap();
+
+        // Simulate a write cut short partway through the vector clock blob
+        let clock_bytes = bincode::serialize(&bad.vector_clock).unwrap();
+        sqlx::query("UPDATE messages SET vector_clock = ? WHERE id = ?")
+            .bind(&clock_bytes[..clock_bytes.len() / 2])
+            .bind(&bad.id.0.as_bytes()[..])
+            .execute(&storage.pool)
+            .await
+            .unwrap();
+
+        let messages = storage.get_channel_messages(channel.id).await.unwrap();
+        assert_eq!(messages.len(), 1);
+        assert_eq!(messages[0].id, good.id);
+        let error = storage.get_message(bad.id).await.unwrap_err();
+        assert!(matches!(error, StorageError::Corrupt { message_id, .. } if message_id == bad.id));
+        assert!(!error.is_transient());
+
+        let unreadable = storage.scan_messages().await.unwrap();
+        assert_eq!(unreadable.len(), 1);
+        assert_eq!(unreadable[0].message_id, Some(bad.id));
+        assert!(unreadable[0].error.contains("vector clock"));
+    }
+
+    #[tokio::test]
+    async fn test_store_messages_reports_each_outcome() {
+        let storage = Storage::new(":memory:").await.unwrap();
+
+        let peer_id = PeerId::new();
+        let channel = Channel::new("test-channel".to_string(), peer_id);
+        storage.store_channel(&channel).await.unwrap();
+
+        let mut vector_clock = VectorClock::new();
+        vector_clock.increment(peer_id);
+        let message = |lamport| Message::new(channel.id, peer_id, MessageContent::new("hi"), vector_clock.clone(), lamport);
+        let (existing, new, failing) = (message(1), message(2), message(3));
+        storage.store_message(&existing).await.unwrap();
+
+        // Make the insert of one message fail, as a full disk or I/O error would
+        let trigger = format!(
+            "CREATE TRIGGER fail_store_{id} BEFORE INSERT ON messages WHEN NEW.id = x'{id}' BEGIN SELECT RAISE(ABORT, 'injected failure'); END",
+            id = failing.id.0.simple()
+        );
+        sqlx::query(&trigger).execute(&storage.pool).await.unwrap();
+
+        let summary = storage.store_messages(&[existing.clone(), failing.clone(), new.clone()]).await;
+        assert_eq!(summary.stored, vec![new.id]);
+        assert_eq!(summary.duplicates, vec![existing.id]);
+        assert_eq!(summary.failed.len(), 1);
+        assert_eq!(summary.failed[0].0, failing.id);
+        assert!(summary.is_stored(&existing.id) && summary.is_stored(&new.id) && !summary.is_stored(&failing.id));
+
+        // The messages after the failure were still stored
+        assert!(storage.get_message(new.id).await.unwrap().is_some());
+        assert!(storage.get_message(failing.id).await.unwrap().is_none());
+        assert!(summary.into_result().is_err());
+
+        let drop_trigger = format!("DROP TRIGGER fail_store_{}", failing.id.0.simple());
+        sqlx::query(&drop_trigger).execute(&storage.pool).await.unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_content_from_other_versions_loads() {
+        let storage = Storage::new(":memory:").await.unwrap();
+
+        let peer_id = PeerId::new();
+        let channel = Channel::new("test-channel".to_string(), peer_id);
+        storage.store_channel(&channel).await.unwrap();
+
+        let mut vector_clock = VectorClock::new();
+        vector_clock.increment(peer_id);
+        let legacy = Message::new(channel.id, peer_id, MessageContent::new("old"), vector_clock.clone(), 1);
+        let newer = Message::new(channel.id, peer_id, MessageContent::new("new"), vector_clock, 2);
+        storage.store_messages(&[legacy.clone(), newer.clone()]).await.into_result().unwrap();
+
+        // A row from before kinds and priorities, and one from a version with
+        // a kind, a priority and a field this one doesn't know
+        let rows = [
+            (legacy.id, r#"{"text":"old"}"#),
+            (newer.id, r#"{"text":"new","kind":{"Poll":{"options":["a","b"]}},"priority":"Critical","edited":true}"#),
+        ];
+        for (id, content) in rows {
+            sqlx::query("UPDATE messages SET content = ? WHERE id = ?")
+                .bind(content)
+                .bind(&id.0.as_bytes()[..])
+                .execute(&storage.pool)
+                .await
+                .unwrap();
+        }
+
+        let legacy = storage.get_message(legacy.id).await.unwrap().unwrap();
+        assert_eq!(legacy.content.text, "old");
+        assert_eq!(legacy.content.kind, MessageKind::Normal);
+        assert_eq!(legacy.content.priority, Priority::Normal);
+
+        let newer = storage.get_message(newer.id).await.unwrap().unwrap();
+        assert_eq!(newer.content.text, "new");
+        assert_eq!(newer.content.kind, MessageKind::Normal);
+        assert_eq!(newer.content.priority, Priority::Normal);
+
+        // Known values still load as themselves
+        let content: MessageContent = serde_json::from_str(r#"{"text":"!","priority":"Urgent"}"#).unwrap();
+        assert!(content.is_urgent());
+    }
+
+    #[tokio::test]
+    async fn test_messages_fetched_by_id_concurrently() {
+        let mut storage = Storage::new(":memory:").await.unwrap();
+        storage.set_fetch_concurrency(3);
+
+        let peer_id = PeerId::new();
+        let channel = Channel::new("test-channel".to_string(), peer_id);
+        storage.store_channel(&channel).await.unwrap();
+        let messages: Vec<Message> = (0..20)
+            .map(|i| Message::new(channel.id, peer_id, MessageContent::new(format!("m{}", i)), VectorClock::new(), i))
+            .collect();
+        storage.store_messages(&messages).await.into_result().unwrap();
+
+        // Unknown IDs are skipped; the rest come back in any order
+        let mut ids: Vec<MessageId> = messages.iter().map(|m| m.id).collect();
+        ids.push(MessageId::new());
+        let fetched: HashSet<MessageId> = storage
+            .get_messages_by_ids(&ids)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|m| m.id)
+            .collect();
+        assert_eq!(fetched, ids[..20].iter().copied().collect());
+    }
+
+    #[tokio::test]
+    async fn test_peer_addresses_replaced_on_announce() {
+        let storage = Storage::new(":memory:").await.unwrap();
+
+        let old = vec!["/ip4/10.0.0.5/tcp/9000".to_string()];
+        let new = vec!["/ip4/192.168.1.20/tcp/9000".to_string()];
+        storage.store_peer_addresses("12D3KooWPeer", &old).await.unwrap();
+        storage.store_peer_addresses("12D3KooWPeer", &new).await.unwrap();
+
+        let peers = storage.get_peer_addresses().await.unwrap();
+        assert_eq!(peers.len(), 1);
+        assert_eq!(peers["12D3KooWPeer"], new);
+    }
+
+    #[tokio::test]
+    async fn test_drafts_saved_and_cleared() {
+        let storage = Storage::new(":memory:").await.unwrap();
+        let channel_id = ChannelId::new();
+
+        storage.store_draft(channel_id, "half a thought").await.unwrap();
+        storage.store_draft(channel_id, "half a thought, finished").await.unwrap();
+        assert_eq!(storage.get_drafts().await.unwrap()[&channel_id], "half a thought, finished");
+
+        storage.store_draft(channel_id, "").await.unwrap();
+        assert!(!storage.get_drafts().await.unwrap().contains_key(&channel_id));
+    }
+
+    #[tokio::test]
+    async fn test_read_markers_survive_restart() {
+        let db_path = std::env::temp_dir().join(format!("burrow-read-{}.db", uuid::Uuid::now_v7()));
+        std::fs::File::create(&db_path).unwrap();
+
+        let channel_id = ChannelId::new();
+        let marker = ReadMarker {
+            message_id: MessageId::new(),
+            created_at_ms: 1_000,
+        };
+        let later = ReadMarker {
+            message_id: MessageId::new(),
+            created_at_ms: 2_000,
+        };
+        {
+            let storage = Storage::new(&db_path).await.unwrap();
+            storage.store_read_marker(channel_id, marker).await.unwrap();
+            storage.store_read_marker(channel_id, later).await.unwrap();
+            storage.pool.close().await;
+        }
+
+        let storage = Storage::new(&db_path).await.unwrap();
+        assert_eq!(storage.get_read_markers().await.unwrap()[&channel_id], later);
+
+        storage.delete_channel(channel_id).await.unwrap();
+        assert!(storage.get_read_markers().await.unwrap().is_empty());
+
+        storage.pool.close().await;
+        std::fs::remove_file(&db_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_rekey_skips_unreadable_rows_and_waits_for_the_key_switch() {
+        let db_path = std::env::temp_dir().join(format!("burrow-rekey-{}.db", uuid::Uuid::now_v7()));
+        std::fs::File::create(&db_path).unwrap();
+        let mut storage = Storage::new(&db_path).await.unwrap();
+
+        let peer_id = PeerId::new();
+        let channel = Channel::new("test-channel".to_string(), peer_id);
+        storage.store_channel(&channel).await.unwrap();
+        let cipher = |keypair: &libp2p::identity::Keypair| ContentCipher::from_identity(keypair).unwrap();
+        let (stray, old, new) = (
+            libp2p::identity::Keypair::generate_ed25519(),
+            libp2p::identity::Keypair::generate_ed25519(),
+            libp2p::identity::Keypair::generate_ed25519(),
+        );
+
+        // One row sealed under a key we no longer have
+        storage.set_content_cipher(cipher(&stray));
+        let lost = Message::new(channel.id, peer_id, MessageContent::new("lost"), VectorClock::new(), 1);
+        storage.store_message(&lost).await.unwrap();
+        storage.set_content_cipher(cipher(&old));
+        let kept = Message::new(channel.id, peer_id, MessageContent::new("kept"), VectorClock::new(), 2);
+        storage.store_message(&kept).await.unwrap();
+
+        // A failed key switch leaves every row as it was
+        let failed = storage.rekey_content(cipher(&new), || anyhow::bail!("disk full")).await;
+        assert!(failed.is_err());
+        assert_eq!(storage.get_message(kept.id).await.unwrap().unwrap().content.text, "kept");
+
+        let rekeyed = storage.rekey_content(cipher(&new), || Ok(())).await.unwrap();
+        assert_eq!(rekeyed, Rekeyed { rekeyed: 1, unreadable: 1 });
+        assert_eq!(storage.get_message(kept.id).await.unwrap().unwrap().content.text, "kept");
+
+        storage.pool.close().await;
+        std::fs::remove_file(&db_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_signal_identity_survives_restart() {
+        let db_path = std::env::temp_dir().join(format!("burrow-signal-id-{}.db", uuid::Uuid::now_v7()));
+        std::fs::File::create(&db_path).unwrap();
+
+        let first = {
+            let storage = Storage::new(&db_path).await.unwrap();
+            assert!(storage.get_signal_identity().await.unwrap().is_none());
+            let store = storage.load_signal_store().await.unwrap();
+            let identity = (store.identity_key_pair().await, store.registration_id().await);
+            storage.pool.close().await;
+            identity
+        };
+
+        let storage = Storage::new(&db_path).await.unwrap();
+        let store = storage.load_signal_store().await.unwrap();
+        assert_eq!(store.identity_key_pair().await.serialize(), first.0.serialize());
+        assert_eq!(store.registration_id().await, first.1);
+
+        storage.pool.close().await;
+        std::fs::remove_file(&db_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_read_markers_stored_together_survive_restart() {
+        let db_path = std::env::temp_dir().join(format!("burrow-read-all-{}.db", uuid::Uuid::now_v7()));
+        std::fs::File::create(&db_path).unwrap();
+
+        let marker = |created_at_ms| ReadMarker {
+            message_id: MessageId::new(),
+            created_at_ms,
+        };
+        let markers = vec![(ChannelId::new(), marker(1_000)), (ChannelId::new(), marker(2_000))];
+        {
+            let storage = Storage::new(&db_path).await.unwrap();
+            storage.store_read_marker(markers[0].0, marker(500)).await.unwrap();
+            storage.store_read_markers(&markers).await.unwrap();
+            storage.pool.close().await;
+        }
+
+        let storage = Storage::new(&db_path).await.unwrap();
+        assert_eq!(storage.get_read_markers().await.unwrap(), markers.into_iter().collect::<HashMap<_, _>>());
+
+        storage.pool.close().await;
+        std::fs::remove_file(&db_path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_muted_channels_round_trip() {
+        let storage = Storage::new(":memory:").await.unwrap();
+        let channel_id = ChannelId::new();
+
+        storage.set_channel_muted(channel_id, true).await.unwrap();
+        storage.set_channel_muted(channel_id, true).await.unwrap();
+        assert!(storage.get_muted_channels().await.unwrap().contains(&channel_id));
+
+        storage.set_channel_muted(channel_id, false).await.unwrap();
+        assert!(!storage.get_muted_channels().await.unwrap().contains(&channel_id));
+    }
+
+    #[tokio::test]
+    async fn test_channel_keywords_round_trip() {
+        let storage = Storage::new(":memory:").await.unwrap();
+        let channel_id = ChannelId::new();
+        let keywords = vec!["deploy".to_string(), "on call".to_string()];
+
+        storage.set_channel_keywords(channel_id, &keywords).await.unwrap();
+        assert_eq!(storage.get_channel_keywords().await.unwrap().get(&channel_id), Some(&keywords));
+
+        storage.set_channel_keywords(channel_id, &[]).await.unwrap();
+        assert!(!storage.get_channel_keywords().await.unwrap().contains_key(&channel_id));
+    }
+
+    #[tokio::test]
+    async fn test_message_edit_round_trip() {
+        let storage = Storage::new(":memory:").await.unwrap();
+        let author = PeerId::new();
+        let mut message = Message::new(ChannelId::new(), author, MessageContent::new("helo"), VectorClock::new(), 1);
+        storage.store_message(&message).await.unwrap();
+
+        let edited_at = Timestamp::new(1_700_000_000_000, 0, author);
+        assert!(message.apply_edit(MessageContent::new("hello"), edited_at));
+        storage.store_message_edit(&message).await.unwrap();
+
+        let loaded = storage.get_message(message.id).await.unwrap().unwrap();
+        assert_eq!(loaded.content.text, "helo");
+        assert_eq!(loaded.current_content().text, "hello");
+        assert_eq!(loaded.edit.as_ref().map(|edit| edit.timestamp()), Some(edited_at));
+        assert_eq!(storage.get_channel_messages(message.channel_id).await.unwrap()[0].current_content().text, "hello");
+        assert_eq!(
+            storage.get_channel_edit_times(message.channel_id).await.unwrap(),
+            HashMap::from([(message.id, edited_at)])
+        );
+    }
+
+    #[tokio::test]
+    async fn test_heads_and_recent_messages() {
+        let storage = Storage::new(":memory:").await.unwrap();
+        let peer_id = PeerId::new();
+        let channel = Channel::new("heads".to_string(), peer_id);
+        storage.store_channel(&channel).await.unwrap();
+
+        // Recent means received last, whatever creation time the author claims
+        let mut messages = Vec::new();
+        for lamport in 1..=3 {
+            let mut message =
+                Message::new(channel.id, peer_id, MessageContent::new("hi"), VectorClock::new(), lamport);
+            message.created_at_ms = (4 - lamport) * 1000;
+            storage.store_message(&message).await.unwrap();
+            messages.push(message.id);
+        }
+        let recent: Vec<MessageId> = storage
+            .get_recent_channel_messages(channel.id, 2)
+            .await
+            .unwrap()
+            .iter()
+            .map(|m| m.id)
+            .collect();
+        assert_eq!(recent, messages[1..]);
+
+        assert_eq!(storage.get_heads(channel.id).await.unwrap(), None);
+        storage.store_heads(channel.id, &messages[2..], &messages[..1]).await.unwrap();
+        assert_eq!(
+            storage.get_heads(channel.id).await.unwrap(),
+            Some(SavedHeads { heads: messages[2..].to_vec(), orphans: messages[..1].to_vec() })
+        );
+
+        // Heads saved without orphans, by an older version, don't count
+        sqlx::query("UPDATE heads SET orphan_ids = NULL WHERE channel_id = ?")
+            .bind(&channel.id.0.as_bytes()[..])
+            .execute(&storage.pool)
+            .await
+            .unwrap();
+        assert_eq!(storage.get_heads(channel.id).await.unwrap(), None);
+        storage.store_heads(channel.id, &messages[2..], &[]).await.unwrap();
+
+        // A damaged row is reported rather than read as some other set of heads
+        sqlx::query("UPDATE heads SET message_ids = x'ff' WHERE channel_id = ?")
+            .bind(&channel.id.0.as_bytes()[..])
+            .execute(&storage.pool)
+            .await
+            .unwrap();
+        assert!(storage.get_heads(channel.id).await.is_err());
+
+        storage.delete_channel(channel.id).await.unwrap();
+        assert_eq!(storage.get_heads(channel.id).await.unwrap(), None);
+    }
+
+    #[tokio::test]
+    async fn test_channel_event_recorded_once() {
+        let storage = Storage::new(":memory:").await.unwrap();
+        let channel_id = ChannelId::new();
+        let event = SystemEvent::Joined { peer: PeerId::new(), tag: uuid::Uuid::new_v4() };
+
+        assert!(storage.store_channel_event(channel_id, &event).await.unwrap());
+        assert!(!storage.store_channel_event(channel_id, &event).await.unwrap());
+
+        let events = storage.get_channel_events().await.unwrap();
+        let recorded: Vec<&SystemEvent> = events[&channel_id].iter().map(|(_, e)| e).collect();
+        assert_eq!(recorded, vec![&event]);
+    }
+
+    #[tokio::test]
+    async fn test_settings_overwritten() {
+        let storage = Storage::new(":memory:").await.unwrap();
+        let key = format!("test-{}", uuid::Uuid::now_v7());
+
+        assert_eq!(storage.get_setting(&key).await.unwrap(), None);
+        storage.store_setting(&key, "25").await.unwrap();
+        storage.store_setting(&key, "40").await.unwrap();
+        assert_eq!(storage.get_setting(&key).await.unwrap().as_deref(), Some("40"));
+    }
+
+    #[tokio::test]
+    async fn test_truncated_crdt_state_flagged_degraded() {
+        let storage = Storage::new(":memory:").await.unwrap();
+
+        let creator = PeerId::new();
+        let member = PeerId::new();
+        let mut channel = Channel::new("general".to_string(), creator);
+        channel.add_member(member);
+        storage.store_channel(&channel).await.unwrap();
+
+        // Simulate a partial write of the CRDT blob
+        let state = channel_state::encode(&channel).unwrap();
+        sqlx::query("UPDATE channels SET crdt_state = ? WHERE id = ?")
+            .bind(&state[..state.len() / 2])
+            .bind(&channel.id.0.as_bytes()[..])
+            .execute(&storage.pool)
+            .await
+            .unwrap();
+
+        let retrieved = storage.get_channel(channel.id).await.unwrap().unwrap();
+        assert!(retrieved.degraded);
+        assert_eq!(retrieved.get_name(), "general");
+        assert!(retrieved.members.contains(&creator));
+        assert!(retrieved.members.contains(&member));
+    }
+
+    #[tokio::test]
+    async fn test_legacy_row_not_degraded() {
+        let storage = Storage::new(":memory:").await.unwrap();
+
+        let channel = Channel::new("legacy".to_string(), PeerId::new());
+        storage.store_channel(&channel).await.unwrap();
+        sqlx::query("UPDATE channels SET crdt_state = NULL WHERE id = ?")
+            .bind(&channel.id.0.as_bytes()[..])
+            .execute(&storage.pool)
+            .await
+            .unwrap();
+
+        let retrieved = storage.get_channel(channel.id).await.unwrap().unwrap();
+        assert!(!retrieved.degraded);
+        assert_eq!(retrieved.get_name(), "legacy");
     }
 }
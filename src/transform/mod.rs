@@ -0,0 +1,323 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Transforms applied to message content on the way out and on the way in
+//!
+//! Content features (shortcode expansion, normalization, filtering) are
+//! written as [`ContentTransform`]s and composed in a [`ContentPipeline`]
+//! instead of being edited into the send and receive paths one by one.
+//! Outgoing content runs through the transforms in registration order;
+//! incoming content runs through them in reverse, so a transform that wraps
+//! content on send sees it unwrapped first on receive.
+
+use crate::types::MessageContent;
+use std::borrow::Cow;
+
+/// One step of the content pipeline
+///
+/// Both directions default to passing content through unchanged, so a
+/// transform only implements the side it cares about.
+pub trait ContentTransform: Send + Sync {
+    /// Short name for logs
+    fn name(&self) -> &'static str;
+
+    /// Rewrite content we're about to send
+    fn outgoing(&self, content: MessageContent) -> MessageContent {
+        content
+    }
+
+    /// Rewrite content received from a peer, before it's stored
+    fn incoming(&self, content: MessageContent) -> MessageContent {
+        content
+    }
+}
+
+/// An ordered list of transforms
+#[derive(Default)]
+pub struct ContentPipeline {
+    transforms: Vec<Box<dyn ContentTransform>>,
+}
+
+impl ContentPipeline {
+    /// A pipeline with no transforms
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The transforms Burrow applies by default
+    pub fn with_builtins() -> Self {
+        Self::new()
+            .with(StripControlSequences)
+            .with(NormalizeLineEndings)
+            .with(EmojiShortcodes)
+    }
+
+    /// Append a transform after the ones already registered
+    pub fn with(mut self, transform: impl ContentTransform + 'static) -> Self {
+        self.transforms.push(Box::new(transform));
+        self
+    }
+
+    /// Names of the registered transforms, in order
+    pub fn names(&self) -> Vec<&'static str> {
+        self.transforms.iter().map(|t| t.name()).collect()
+    }
+
+    pub fn outgoing(&self, content: MessageContent) -> MessageContent {
+        self.transforms.iter().fold(content, |content, t| t.outgoing(content))
+    }
+
+    pub fn incoming(&self, content: MessageContent) -> MessageContent {
+        self.transforms.iter().rev().fold(content, |content, t| t.incoming(content))
+    }
+}
+
+/// Turns `\r\n` and lone `\r` into `\n` in both directions
+///
+/// Peers on different platforms then render the same line breaks.
+pub struct NormalizeLineEndings;
+
+impl NormalizeLineEndings {
+    fn normalize(content: MessageContent) -> MessageContent {
+        if !content.text.contains('\r') {
+            return content;
+        }
+        MessageContent {
+            text: content.text.replace("\r\n", "\n").replace('\r', "\n"),
+            ..content
+        }
+    }
+}
+
+impl ContentTransform for NormalizeLineEndings {
+    fn name(&self) -> &'static str {
+        "normalize-line-endings"
+    }
+
+    fn outgoing(&self, content: MessageContent) -> MessageContent {
+        Self::normalize(content)
+    }
+
+    fn incoming(&self, content: MessageContent) -> MessageContent {
+        Self::normalize(content)
+    }
+}
+
+/// Removes terminal control sequences from incoming text
+///
+/// A peer could otherwise send ANSI escapes that recolour or clear the
+/// terminal, or bidi overrides that make text read differently than it
+/// sorts. Registered first so it runs last on receive, after line endings
+/// have been normalized.
+pub struct StripControlSequences;
+
+impl ContentTransform for StripControlSequences {
+    fn name(&self) -> &'static str {
+        "strip-control-sequences"
+    }
+
+    fn incoming(&self, content: MessageContent) -> MessageContent {
+        match strip_control(&content.text) {
+            Cow::Borrowed(_) => content,
+            Cow::Owned(text) => MessageContent { text, ..content },
+        }
+    }
+}
+
+/// Strip escape sequences and control characters from text for display
+///
+/// Newlines are kept and tabs become spaces. CSI sequences are dropped up to
+/// their final byte, OSC/DCS-style strings up to their terminator, and any
+/// other escape together with the character after it.
+pub fn strip_control(text: &str) -> Cow<'_, str> {
+    let needs_stripping = |c: char| (c.is_control() && c != '\n') || is_bidi_control(c);
+    if !text.contains(needs_stripping) {
+        return Cow::Borrowed(text);
+    }
+
+    let mut out = String::with_capacity(text.len());
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => out.push('\n'),
+            '\t' => out.push(' '),
+            '\x1b' => match chars.next() {
+                Some('[') => skip_csi(&mut chars),
+                Some(']' | 'P' | 'X' | '^' | '_') => skip_string(&mut chars),
+                _ => {}
+            },
+            '\u{9b}' => skip_csi(&mut chars),
+            '\u{90}' | '\u{98}' | '\u{9d}' | '\u{9e}' | '\u{9f}' => skip_string(&mut chars),
+            c if c.is_control() || is_bidi_control(c) => {}
+            c => out.push(c),
+        }
+    }
+    Cow::Owned(out)
+}
+
+/// Skip CSI parameter and intermediate bytes through the final byte
+fn skip_csi(chars: &mut impl Iterator<Item = char>) {
+    for c in chars {
+        if ('\u{40}'..='\u{7e}').contains(&c) {
+            break;
+        }
+    }
+}
+
+/// Skip a control string through BEL or the string terminator (`ESC \` or ST)
+fn skip_string(chars: &mut std::iter::Peekable<impl Iterator<Item = char>>) {
+    while let Some(c) = chars.next() {
+        match c {
+            '\x07' | '\u{9c}' => break,
+            '\x1b' if chars.peek() == Some(&'\\') => {
+                chars.next();
+                break;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Explicit directional embeddings, overrides and isolates
+fn is_bidi_control(c: char) -> bool {
+    matches!(c, '\u{202a}'..='\u{202e}' | '\u{2066}'..='\u{2069}')
+}
+
+/// Expands `:shortcode:` emoji in outgoing messages
+///
+/// Unknown shortcodes are left as typed. Only the sender expands, so peers
+/// always receive the same text.
+pub struct EmojiShortcodes;
+
+const SHORTCODES: &[(&str, &str)] = &[
+    ("smile", "😄"),
+    ("grin", "😁"),
+    ("joy", "😂"),
+    ("wink", "😉"),
+    ("heart", "❤️"),
+    ("thumbsup", "👍"),
+    ("thumbsdown", "👎"),
+    ("tada", "🎉"),
+    ("fire", "🔥"),
+    ("eyes", "👀"),
+    ("thinking", "🤔"),
+    ("wave", "👋"),
+    ("rocket", "🚀"),
+    ("check", "✅"),
+    ("x", "❌"),
+];
+
+impl ContentTransform for EmojiShortcodes {
+    fn name(&self) -> &'static str {
+        "emoji-shortcodes"
+    }
+
+    fn outgoing(&self, content: MessageContent) -> MessageContent {
+        if !content.text.contains(':') {
+            return content;
+        }
+
+        let mut text = String::with_capacity(content.text.len());
+        let mut rest = content.text.as_str();
+        while let Some(start) = rest.find(':') {
+            text.push_str(&rest[..start]);
+            let after = &rest[start + 1..];
+            let emoji = after.find(':').and_then(|end| {
+                let code = &after[..end];
+                SHORTCODES
+                    .iter()
+                    .find(|(name, _)| *name == code)
+                    .map(|(_, emoji)| (*emoji, end))
+            });
+            match emoji {
+                Some((emoji, end)) => {
+                    text.push_str(emoji);
+                    rest = &after[end + 1..];
+                }
+                None => {
+                    text.push(':');
+                    rest = after;
+                }
+            }
+        }
+        text.push_str(rest);
+
+        MessageContent { text, ..content }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn content(text: &str) -> MessageContent {
+        MessageContent::new(text)
+    }
+
+    /// Appends a marker on the way out and strips it on the way in
+    struct Tag(&'static str);
+
+    impl ContentTransform for Tag {
+        fn name(&self) -> &'static str {
+            self.0
+        }
+
+        fn outgoing(&self, content: MessageContent) -> MessageContent {
+            MessageContent::new(format!("{}[{}]", content.text, self.0))
+        }
+
+        fn incoming(&self, content: MessageContent) -> MessageContent {
+            let suffix = format!("[{}]", self.0);
+            MessageContent::new(content.text.strip_suffix(&suffix).unwrap_or(&content.text))
+        }
+    }
+
+    #[test]
+    fn test_transforms_compose_in_order() {
+        let pipeline = ContentPipeline::new().with(Tag("a")).with(Tag("b"));
+        assert_eq!(pipeline.names(), vec!["a", "b"]);
+
+        let sent = pipeline.outgoing(content("hi"));
+        assert_eq!(sent.text, "hi[a][b]");
+
+        // Incoming undoes the layers from the outside in
+        assert_eq!(pipeline.incoming(sent).text, "hi");
+    }
+
+    #[test]
+    fn test_builtins() {
+        let pipeline = ContentPipeline::with_builtins();
+
+        let sent = pipeline.outgoing(content("ship it :rocket:\r\nok :nope: 10:30"));
+        assert_eq!(sent.text, "ship it 🚀\nok :nope: 10:30");
+
+        // Shortcodes are only expanded by the sender
+        let received = pipeline.incoming(content("see :smile:\rbye"));
+        assert_eq!(received.text, "see :smile:\nbye");
+    }
+
+    #[test]
+    fn test_incoming_control_sequences_stripped() {
+        let pipeline = ContentPipeline::with_builtins();
+
+        let received = pipeline.incoming(content(
+            "\x1b[2J\x1b[31mred\x1b[0m\r\nnext\tline \x1b]0;pwned\x07title \u{202e}gnp.exe\x08",
+        ));
+        assert_eq!(received.text, "red\nnext line title gnp.exe");
+
+        assert!(matches!(strip_control("plain\ntext"), Cow::Borrowed(_)));
+        assert_eq!(strip_control("a\x1b]8;;https://x\x1b\\b\u{9b}1mc"), "abc");
+    }
+}
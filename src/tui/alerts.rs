@@ -0,0 +1,149 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::config::ConnectionNotices;
+use crate::types::{Message, PeerId};
+
+/// A connection event that may be worth a notification
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionNotice {
+    /// A peer turned up on the local network
+    Discovered,
+    /// An automatic dial (discovery, reconnect) failed
+    AutoDialFailed,
+    /// A peer connected, however the connection started
+    Connected,
+    /// A dial the user asked for is under way
+    ManualDialing,
+    /// A dial the user asked for failed
+    ManualDialFailed,
+}
+
+/// Whether a connection event should pop up a notification at this level
+pub fn should_notify_connection(level: ConnectionNotices, notice: ConnectionNotice) -> bool {
+    match notice {
+        ConnectionNotice::ManualDialFailed => true,
+        ConnectionNotice::Connected | ConnectionNotice::ManualDialing => level != ConnectionNotices::None,
+        ConnectionNotice::Discovered | ConnectionNotice::AutoDialFailed => level == ConnectionNotices::All,
+    }
+}
+
+/// Whether a message that just arrived should pop up a notification
+///
+/// Messages in the channel being read are already on screen, and muted
+/// channels stay quiet, except for urgent messages, which always notify.
+/// A message mentioning one of the channel's keywords (`keyword`) notifies
+/// even in a muted channel.
+pub fn should_notify(message: &Message, own: PeerId, selected: bool, muted: bool, keyword: bool) -> bool {
+    if message.author == own || message.content.is_system() {
+        return false;
+    }
+    message.content.is_urgent() || !(selected || (muted && !keyword))
+}
+
+/// The first of a channel's keywords that some text mentions, if any
+///
+/// Case is ignored and only whole words count, so "deploy" matches
+/// "Deploy done" but not "redeployed".
+pub fn keyword_hit<'a>(text: &str, keywords: &'a [String]) -> Option<&'a str> {
+    let text = text.to_lowercase();
+    keywords.iter().map(String::as_str).find(|keyword| {
+        let keyword = keyword.to_lowercase();
+        !keyword.is_empty()
+            && text.match_indices(&keyword).any(|(start, _)| {
+                let before = text[..start].chars().next_back();
+                let after = text[start + keyword.len()..].chars().next();
+                !before.is_some_and(char::is_alphanumeric) && !after.is_some_and(char::is_alphanumeric)
+            })
+    })
+}
+
+/// Parse the keyword editor's comma-separated input
+pub fn parse_keywords(input: &str) -> Vec<String> {
+    let mut keywords: Vec<String> = Vec::new();
+    for keyword in input.split(',').map(str::trim).filter(|k| !k.is_empty()) {
+        if !keywords.iter().any(|k| k.eq_ignore_ascii_case(keyword)) {
+            keywords.push(keyword.to_string());
+        }
+    }
+    keywords
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ChannelId, MessageContent, Priority, VectorClock};
+
+    fn message(author: PeerId, priority: Priority) -> Message {
+        Message::new(
+            ChannelId::new(),
+            author,
+            MessageContent::new("heads up").with_priority(priority),
+            VectorClock::new(),
+            1,
+        )
+    }
+
+    #[test]
+    fn test_urgent_bypasses_mute() {
+        let (me, them) = (PeerId::new(), PeerId::new());
+        let normal = message(them, Priority::Normal);
+        let urgent = message(them, Priority::Urgent);
+
+        assert!(should_notify(&normal, me, false, false, false));
+        assert!(!should_notify(&normal, me, false, true, false));
+        assert!(!should_notify(&normal, me, true, false, false));
+
+        assert!(should_notify(&urgent, me, false, true, false));
+        assert!(should_notify(&urgent, me, true, true, false));
+
+        // Our own urgent messages don't alert us
+        assert!(!should_notify(&message(me, Priority::Urgent), me, false, true, false));
+    }
+
+    #[test]
+    fn test_keyword_overrides_mute() {
+        let (me, them) = (PeerId::new(), PeerId::new());
+        let keywords = parse_keywords("Deploy, on call, , deploy");
+        assert_eq!(keywords, vec!["Deploy".to_string(), "on call".to_string()]);
+
+        assert_eq!(keyword_hit("deploy is done", &keywords), Some("Deploy"));
+        assert_eq!(keyword_hit("who's ON CALL tonight?", &keywords), Some("on call"));
+        assert_eq!(keyword_hit("we redeployed", &keywords), None);
+
+        let normal = message(them, Priority::Normal);
+        assert!(should_notify(&normal, me, false, true, true));
+        assert!(!should_notify(&normal, me, false, true, false));
+        // The channel being read stays quiet; the message is on screen
+        assert!(!should_notify(&normal, me, true, true, true));
+        assert!(!should_notify(&message(me, Priority::Normal), me, false, true, true));
+    }
+
+    #[test]
+    fn test_connection_notice_levels() {
+        use ConnectionNotice::*;
+        let shown = |level| {
+            [Discovered, AutoDialFailed, Connected, ManualDialing, ManualDialFailed]
+                .into_iter()
+                .filter(|notice| should_notify_connection(level, *notice))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(shown(ConnectionNotices::All).len(), 5);
+        assert_eq!(shown(ConnectionNotices::Manual), vec![Connected, ManualDialing, ManualDialFailed]);
+        // Even at "none", a dial the user asked for reports its failure
+        assert_eq!(shown(ConnectionNotices::None), vec![ManualDialFailed]);
+    }
+}
@@ -0,0 +1,138 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::types::{attachment_hash, ImageRef, MAX_IMAGE_BYTES};
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+/// How long to wait for an image before asking for it again
+const REFETCH_AFTER: Duration = Duration::from_secs(30);
+
+/// Where an attached image stands locally
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageState {
+    /// Stored, ready to open
+    Held,
+    /// Asked for, not arrived yet
+    Fetching,
+    /// Not stored and not being fetched
+    Missing,
+}
+
+/// Which attached images are stored, and which are being fetched
+///
+/// A message carries only its image's hash; the data is asked for over a
+/// direct frame from the peer that relayed the message. Whatever arrives is
+/// only kept if it was asked for and matches the hash.
+#[derive(Debug, Default)]
+pub struct Attachments {
+    held: HashSet<[u8; 32]>,
+    requested: HashMap<[u8; 32], Instant>,
+}
+
+impl Attachments {
+    /// Start from the images already stored
+    pub fn new(held: HashSet<[u8; 32]>) -> Self {
+        Self { held, requested: HashMap::new() }
+    }
+
+    /// Replace the set of stored images, e.g. after some expired
+    pub fn set_held(&mut self, held: HashSet<[u8; 32]>) {
+        self.held = held;
+    }
+
+    /// Note an image stored locally, such as one we pasted
+    pub fn hold(&mut self, hash: [u8; 32]) {
+        self.requested.remove(&hash);
+        self.held.insert(hash);
+    }
+
+    pub fn state(&self, image: &ImageRef) -> ImageState {
+        if self.held.contains(&image.hash) {
+            ImageState::Held
+        } else if self.requested.contains_key(&image.hash) {
+            ImageState::Fetching
+        } else {
+            ImageState::Missing
+        }
+    }
+
+    /// Whether to ask for an image now, noting the request if so
+    ///
+    /// Images we hold, that are too big to be sent, or that were asked for
+    /// recently aren't asked for.
+    pub fn should_request(&mut self, image: &ImageRef, now: Instant) -> bool {
+        if self.held.contains(&image.hash) || image.size > MAX_IMAGE_BYTES {
+            return false;
+        }
+        match self.requested.get(&image.hash) {
+            Some(at) if now.duration_since(*at) < REFETCH_AFTER => false,
+            _ => {
+                self.requested.insert(image.hash, now);
+                true
+            }
+        }
+    }
+
+    /// Whether data a peer sent is an image we asked for, to store and `hold`
+    pub fn expects(&self, hash: &[u8; 32], data: &[u8]) -> bool {
+        if !self.requested.contains_key(hash) || data.len() as u64 > MAX_IMAGE_BYTES {
+            return false;
+        }
+        if attachment_hash(data) != *hash {
+            tracing::warn!("Dropping attachment data that doesn't match its hash");
+            return false;
+        }
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_images_requested_once_until_retry() {
+        let image = ImageRef::for_png(b"png", 1, 1);
+        let mut attachments = Attachments::default();
+        let now = Instant::now();
+
+        assert_eq!(attachments.state(&image), ImageState::Missing);
+        assert!(attachments.should_request(&image, now));
+        assert_eq!(attachments.state(&image), ImageState::Fetching);
+        assert!(!attachments.should_request(&image, now + Duration::from_secs(1)));
+        assert!(attachments.should_request(&image, now + REFETCH_AFTER));
+
+        let held = ImageRef::for_png(b"ours", 1, 1);
+        attachments.hold(held.hash);
+        assert_eq!(attachments.state(&held), ImageState::Held);
+        assert!(!attachments.should_request(&held, now));
+    }
+
+    #[test]
+    fn test_only_requested_matching_data_accepted() {
+        let image = ImageRef::for_png(b"png", 1, 1);
+        let mut attachments = Attachments::default();
+        assert!(!attachments.expects(&image.hash, b"png"), "never asked for");
+
+        attachments.should_request(&image, Instant::now());
+        assert!(!attachments.expects(&image.hash, b"forged"));
+        assert!(attachments.expects(&image.hash, b"png"));
+
+        attachments.hold(image.hash);
+        assert_eq!(attachments.state(&image), ImageState::Held);
+        assert!(!attachments.expects(&image.hash, b"png"), "already held");
+    }
+}
@@ -0,0 +1,112 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use anyhow::{bail, Result};
+
+/// Access to the system clipboard, opened on first use
+///
+/// The handle is kept for the life of the app: on X11 the copied text is
+/// served by this process and disappears when the handle is dropped.
+#[derive(Default)]
+pub struct Clipboard {
+    inner: Option<arboard::Clipboard>,
+}
+
+impl Clipboard {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Copy text to the system clipboard
+    ///
+    /// Fails over SSH, where the system clipboard belongs to the remote host
+    /// rather than the user's machine, and when no clipboard is available.
+    pub fn copy(&mut self, text: &str) -> Result<()> {
+        if is_remote_session() {
+            bail!("the clipboard isn't reachable over SSH");
+        }
+
+        self.open()?.set_text(text)?;
+
+        Ok(())
+    }
+
+    /// Read an image from the system clipboard, encoded as PNG
+    ///
+    /// Fails like `copy` does, and when the clipboard holds no image.
+    pub fn image(&mut self) -> Result<ClipboardImage> {
+        if is_remote_session() {
+            bail!("the clipboard isn't reachable over SSH");
+        }
+
+        let image = match self.open()?.get_image() {
+            Ok(image) => image,
+            Err(arboard::Error::ContentNotAvailable) => bail!("the clipboard holds no image"),
+            Err(e) => return Err(e.into()),
+        };
+        let (width, height) = (u32::try_from(image.width)?, u32::try_from(image.height)?);
+        Ok(ClipboardImage {
+            png: encode_png(&image.bytes, width, height)?,
+            width,
+            height,
+        })
+    }
+
+    fn open(&mut self) -> Result<&mut arboard::Clipboard> {
+        Ok(match &mut self.inner {
+            Some(clipboard) => clipboard,
+            inner => inner.insert(arboard::Clipboard::new()?),
+        })
+    }
+}
+
+/// An image taken from the clipboard
+pub struct ClipboardImage {
+    pub png: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+}
+
+/// Encode RGBA pixels as a PNG, compressed as far as it goes
+fn encode_png(rgba: &[u8], width: u32, height: u32) -> Result<Vec<u8>> {
+    let mut png = Vec::new();
+    let mut encoder = png::Encoder::new(&mut png, width, height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    encoder.set_compression(png::Compression::Best);
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(rgba)?;
+    writer.finish()?;
+    Ok(png)
+}
+
+fn is_remote_session() -> bool {
+    std::env::var_os("SSH_CONNECTION").is_some() || std::env::var_os("SSH_TTY").is_some()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_images_encode_as_png() {
+        let rgba = [255, 0, 0, 255, 0, 0, 255, 128];
+        let png = encode_png(&rgba, 2, 1).unwrap();
+
+        let reader = png::Decoder::new(&png[..]).read_info().unwrap();
+        assert_eq!((reader.info().width, reader.info().height), (2, 1));
+        assert_eq!(reader.info().color_type, png::ColorType::Rgba);
+    }
+}
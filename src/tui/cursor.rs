@@ -0,0 +1,180 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::types::{Message, MessageId};
+
+/// The selected message in the rendered message list
+///
+/// Tracks the message by ID so the selection stays on the same message as
+/// others arrive or get reordered, and remembers the index it was last seen
+/// at so a vanished message hands the selection to its nearest neighbour.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MessageCursor {
+    selected: Option<(MessageId, usize)>,
+}
+
+impl MessageCursor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The selected message, if any
+    pub fn selected(&self) -> Option<MessageId> {
+        self.selected.map(|(id, _)| id)
+    }
+
+    /// Index of the selected message in the list it was last synced with
+    pub fn index(&self) -> Option<usize> {
+        self.selected.map(|(_, index)| index)
+    }
+
+    pub fn clear(&mut self) {
+        self.selected = None;
+    }
+
+    /// Select the newest message
+    pub fn select_last(&mut self, messages: &[Message]) {
+        self.select_index(messages, messages.len().checked_sub(1));
+    }
+
+    /// Move towards older messages, starting from the newest if nothing is selected
+    pub fn move_up(&mut self, messages: &[Message]) {
+        match self.index() {
+            Some(index) => self.select_index(messages, Some(index.saturating_sub(1))),
+            None => self.select_last(messages),
+        }
+    }
+
+    /// Move towards newer messages, stopping at the newest
+    pub fn move_down(&mut self, messages: &[Message]) {
+        match self.index() {
+            Some(index) => self.select_index(messages, Some((index + 1).min(messages.len().saturating_sub(1)))),
+            None => self.select_last(messages),
+        }
+    }
+
+    /// Revalidate the selection after the message list changed
+    ///
+    /// Follows the selected message to its new position; if it's gone (hidden,
+    /// or the list shrank) the message now at its old index is selected instead.
+    pub fn resync(&mut self, messages: &[Message]) {
+        let Some((id, index)) = self.selected else {
+            return;
+        };
+
+        match messages.iter().position(|m| m.id == id) {
+            Some(position) => self.selected = Some((id, position)),
+            None => self.select_index(messages, Some(index.min(messages.len().saturating_sub(1)))),
+        }
+    }
+
+    /// Select a message by ID, returning false (and changing nothing) if
+    /// it isn't in the list
+    pub fn select(&mut self, messages: &[Message], id: MessageId) -> bool {
+        match messages.iter().position(|m| m.id == id) {
+            Some(index) => {
+                self.selected = Some((id, index));
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn select_index(&mut self, messages: &[Message], index: Option<usize>) {
+        self.selected = index.and_then(|i| messages.get(i).map(|m| (m.id, i)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{ChannelId, MessageContent, PeerId, VectorClock};
+
+    fn messages(count: usize) -> Vec<Message> {
+        let channel = ChannelId::new();
+        let author = PeerId::new();
+        (0..count)
+            .map(|i| {
+                Message::new(
+                    channel,
+                    author,
+                    MessageContent::new(format!("message {}", i)),
+                    VectorClock::new(),
+                    i as u64,
+                )
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_moves_within_bounds() {
+        let list = messages(3);
+        let mut cursor = MessageCursor::new();
+
+        cursor.move_up(&list);
+        assert_eq!(cursor.index(), Some(2));
+        cursor.move_up(&list);
+        cursor.move_up(&list);
+        cursor.move_up(&list);
+        assert_eq!(cursor.selected(), Some(list[0].id));
+
+        cursor.move_down(&list);
+        cursor.move_down(&list);
+        cursor.move_down(&list);
+        assert_eq!(cursor.selected(), Some(list[2].id));
+
+        let mut empty = MessageCursor::new();
+        empty.move_up(&[]);
+        assert_eq!(empty.selected(), None);
+    }
+
+    #[test]
+    fn test_follows_message_as_new_ones_arrive() {
+        let mut list = messages(3);
+        let mut cursor = MessageCursor::new();
+        cursor.select_last(&list);
+        cursor.move_up(&list);
+        let selected = list[1].id;
+
+        // An older message synced in ahead of the selection shifts it down
+        let mut arrived = messages(1);
+        list.insert(0, arrived.remove(0));
+        cursor.resync(&list);
+
+        assert_eq!(cursor.selected(), Some(selected));
+        assert_eq!(cursor.index(), Some(2));
+    }
+
+    #[test]
+    fn test_vanished_message_selects_neighbour() {
+        let mut list = messages(3);
+        let mut cursor = MessageCursor::new();
+        cursor.select_last(&list);
+        cursor.move_up(&list);
+
+        list.remove(1);
+        cursor.resync(&list);
+        assert_eq!(cursor.selected(), Some(list[1].id));
+
+        // Removing the last message moves the selection back
+        list.remove(1);
+        cursor.resync(&list);
+        assert_eq!(cursor.selected(), Some(list[0].id));
+
+        list.clear();
+        cursor.resync(&list);
+        assert_eq!(cursor.selected(), None);
+    }
+}
@@ -0,0 +1,225 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::dag::MessageDAG;
+use crate::types::{ChannelId, Message, MessageId, PeerId};
+use std::collections::{HashMap, HashSet};
+
+/// Where one of our own messages stands on its way to peers
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DeliveryState {
+    /// Handed to the network, not yet seen by anyone
+    Pending,
+    /// The network couldn't hand it to any peer
+    Failed,
+    /// A peer's later message shows it had the message
+    Delivered,
+}
+
+/// Why a message can't be resent
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResendRefusal {
+    /// Someone else wrote it, or it was sent before this session
+    NotTracked,
+    /// A peer already has it
+    AlreadyDelivered,
+}
+
+/// A message sent this session
+#[derive(Debug, Clone, Copy)]
+struct Sent {
+    state: DeliveryState,
+    channel_id: ChannelId,
+    lamport: u64,
+}
+
+/// Delivery state of the messages sent this session
+///
+/// There are no explicit receipts: a message counts as delivered once
+/// another author's message in the same channel descends from it, since
+/// that author must have had it to build on it. Vector clocks can't tell
+/// this, as they're shared across channels.
+#[derive(Debug, Default)]
+pub struct DeliveryTracker {
+    sent: HashMap<MessageId, Sent>,
+}
+
+impl DeliveryTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Start tracking a message we just handed to the network
+    pub fn sent(&mut self, message: &Message) {
+        let sent = Sent {
+            state: DeliveryState::Pending,
+            channel_id: message.channel_id,
+            lamport: message.lamport_timestamp,
+        };
+        self.sent.insert(message.id, sent);
+    }
+
+    /// Record that the network couldn't deliver a message
+    ///
+    /// A message already known delivered stays that way.
+    pub fn failed(&mut self, message_id: &MessageId) {
+        if let Some(sent) = self.sent.get_mut(message_id).filter(|sent| sent.state == DeliveryState::Pending) {
+            sent.state = DeliveryState::Failed;
+        }
+    }
+
+    /// Learn from another author's message which of ours they had received
+    ///
+    /// Walks the message's ancestry in `dag`, stopping below the oldest of
+    /// our messages still waiting in its channel: an ancestor always has a
+    /// lower Lamport timestamp, so nothing further down can be one of them.
+    pub fn observe(&mut self, message: &Message, own: PeerId, dag: &MessageDAG) {
+        if message.author == own {
+            return;
+        }
+        let Some(floor) = self
+            .sent
+            .values()
+            .filter(|sent| sent.channel_id == message.channel_id && sent.state != DeliveryState::Delivered)
+            .map(|sent| sent.lamport)
+            .min()
+        else {
+            return;
+        };
+
+        let mut visited = HashSet::new();
+        let mut stack = message.parent_hashes.clone();
+        while let Some(id) = stack.pop() {
+            if !visited.insert(id) {
+                continue;
+            }
+            if let Some(sent) = self.sent.get_mut(&id) {
+                sent.state = DeliveryState::Delivered;
+            }
+            if let Some(parent) = dag.get_message(&id).filter(|parent| parent.lamport_timestamp > floor) {
+                stack.extend(parent.parent_hashes.iter().copied());
+            }
+        }
+    }
+
+    pub fn state(&self, message_id: &MessageId) -> Option<DeliveryState> {
+        self.sent.get(message_id).map(|sent| sent.state)
+    }
+
+    /// Reset a message to pending before sending it again
+    pub fn retry(&mut self, message_id: &MessageId) -> Result<(), ResendRefusal> {
+        match self.sent.get_mut(message_id) {
+            None => Err(ResendRefusal::NotTracked),
+            Some(Sent { state: DeliveryState::Delivered, .. }) => Err(ResendRefusal::AlreadyDelivered),
+            Some(sent) => {
+                sent.state = DeliveryState::Pending;
+                Ok(())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dag::IngestMode;
+    use crate::types::{MessageContent, VectorClock};
+
+    fn message(channel_id: ChannelId, author: PeerId, lamport: u64, parents: &[&Message]) -> Message {
+        let mut msg = Message::new(channel_id, author, MessageContent::new("hi"), VectorClock::new(), lamport);
+        msg.parent_hashes = parents.iter().map(|parent| parent.id).collect();
+        msg
+    }
+
+    #[test]
+    fn test_failed_message_can_be_retried_until_delivered() {
+        let own = PeerId::new();
+        let peer = PeerId::new();
+        let channel_id = ChannelId::new();
+        let sent = message(channel_id, own, 1, &[]);
+        let mut dag = MessageDAG::new();
+        dag.ingest(sent.clone(), IngestMode::Strict).unwrap();
+
+        let mut tracker = DeliveryTracker::new();
+        tracker.sent(&sent);
+        tracker.failed(&sent.id);
+        assert_eq!(tracker.state(&sent.id), Some(DeliveryState::Failed));
+
+        assert_eq!(tracker.retry(&sent.id), Ok(()));
+        assert_eq!(tracker.state(&sent.id), Some(DeliveryState::Pending));
+
+        // The peer's reply builds on our message, so it arrived
+        tracker.observe(&message(channel_id, peer, 2, &[&sent]), own, &dag);
+        assert_eq!(tracker.state(&sent.id), Some(DeliveryState::Delivered));
+        assert_eq!(tracker.retry(&sent.id), Err(ResendRefusal::AlreadyDelivered));
+
+        // A late failure report doesn't undo that
+        tracker.failed(&sent.id);
+        assert_eq!(tracker.state(&sent.id), Some(DeliveryState::Delivered));
+    }
+
+    #[test]
+    fn test_only_descendants_count_as_delivery() {
+        let own = PeerId::new();
+        let peer = PeerId::new();
+        let channel_id = ChannelId::new();
+        let first = message(channel_id, own, 1, &[]);
+        let theirs = message(channel_id, peer, 2, &[&first]);
+        let second = message(channel_id, own, 3, &[&theirs]);
+        let mut dag = MessageDAG::new();
+        for msg in [&first, &theirs, &second] {
+            dag.ingest(msg.clone(), IngestMode::Strict).unwrap();
+        }
+
+        let mut tracker = DeliveryTracker::new();
+        tracker.sent(&first);
+        tracker.sent(&second);
+
+        // Our own messages prove nothing; a peer's reply further down the
+        // chain covers everything it descends from
+        tracker.observe(&second, own, &dag);
+        assert_eq!(tracker.state(&first.id), Some(DeliveryState::Pending));
+        tracker.observe(&message(channel_id, peer, 3, &[&theirs]), own, &dag);
+        assert_eq!(tracker.state(&first.id), Some(DeliveryState::Delivered));
+        assert_eq!(tracker.state(&second.id), Some(DeliveryState::Pending));
+
+        tracker.observe(&message(channel_id, peer, 4, &[&second]), own, &dag);
+        assert_eq!(tracker.state(&second.id), Some(DeliveryState::Delivered));
+
+        assert_eq!(tracker.retry(&theirs.id), Err(ResendRefusal::NotTracked));
+    }
+
+    #[test]
+    fn test_other_channels_dont_acknowledge() {
+        let own = PeerId::new();
+        let peer = PeerId::new();
+        let (general, random) = (ChannelId::new(), ChannelId::new());
+        let in_general = message(general, own, 1, &[]);
+        let in_random = message(random, own, 1, &[]);
+        let mut dag = MessageDAG::new();
+        dag.ingest(in_general.clone(), IngestMode::Strict).unwrap();
+        dag.ingest(in_random.clone(), IngestMode::Strict).unwrap();
+
+        let mut tracker = DeliveryTracker::new();
+        tracker.sent(&in_general);
+        tracker.sent(&in_random);
+
+        // Vector clocks span channels, but a reply in random only shows
+        // that random's message arrived
+        tracker.observe(&message(random, peer, 2, &[&in_random]), own, &dag);
+        assert_eq!(tracker.state(&in_random.id), Some(DeliveryState::Delivered));
+        assert_eq!(tracker.state(&in_general.id), Some(DeliveryState::Pending));
+    }
+}
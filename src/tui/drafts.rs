@@ -0,0 +1,120 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::types::ChannelId;
+use std::collections::HashMap;
+
+/// Unsent input for each channel
+///
+/// The input box always holds the draft of the active channel; the others
+/// are stashed here until their channel is selected again.
+#[derive(Debug, Default)]
+pub struct Drafts {
+    stashed: HashMap<ChannelId, String>,
+    active: Option<ChannelId>,
+}
+
+impl Drafts {
+    /// Start from previously saved drafts
+    pub fn new(stashed: HashMap<ChannelId, String>) -> Self {
+        Self { stashed, active: None }
+    }
+
+    /// Make `channel` the active one, swapping drafts through `input`
+    ///
+    /// Returns the channel whose draft was stashed (so it can be saved), or
+    /// None if the active channel didn't change.
+    pub fn activate(&mut self, channel: Option<ChannelId>, input: &mut String) -> Option<ChannelId> {
+        if channel == self.active {
+            return None;
+        }
+
+        let previous = self.active.take();
+        if let Some(previous) = previous {
+            let draft = std::mem::take(input);
+            if draft.is_empty() {
+                self.stashed.remove(&previous);
+            } else {
+                self.stashed.insert(previous, draft);
+            }
+        }
+
+        *input = channel
+            .and_then(|id| self.stashed.remove(&id))
+            .unwrap_or_default();
+        self.active = channel;
+        previous
+    }
+
+    /// The channel whose draft is in the input box
+    pub fn active(&self) -> Option<ChannelId> {
+        self.active
+    }
+
+    /// The stashed draft of an inactive channel (empty if none)
+    pub fn get(&self, channel: &ChannelId) -> &str {
+        self.stashed.get(channel).map(String::as_str).unwrap_or("")
+    }
+
+    /// Forget a channel's draft (e.g. once the channel is deleted)
+    pub fn remove(&mut self, channel: &ChannelId) {
+        self.stashed.remove(channel);
+        if self.active == Some(*channel) {
+            self.active = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_switching_channels_preserves_drafts() {
+        let general = ChannelId::new();
+        let random = ChannelId::new();
+        let mut drafts = Drafts::default();
+        let mut input = String::new();
+
+        drafts.activate(Some(general), &mut input);
+        input.push_str("hello gen");
+
+        assert_eq!(drafts.activate(Some(random), &mut input), Some(general));
+        assert_eq!(input, "");
+        input.push_str("meanwhile in random");
+
+        drafts.activate(Some(general), &mut input);
+        assert_eq!(input, "hello gen");
+        assert_eq!(drafts.get(&random), "meanwhile in random");
+
+        drafts.activate(Some(random), &mut input);
+        assert_eq!(input, "meanwhile in random");
+        assert_eq!(drafts.get(&general), "hello gen");
+    }
+
+    #[test]
+    fn test_reselecting_same_channel_keeps_input() {
+        let general = ChannelId::new();
+        let mut drafts = Drafts::new(HashMap::from([(general, "saved".to_string())]));
+        let mut input = String::new();
+
+        drafts.activate(Some(general), &mut input);
+        assert_eq!(input, "saved");
+
+        input.push_str(" and more");
+        assert_eq!(drafts.activate(Some(general), &mut input), None);
+        assert_eq!(input, "saved and more");
+    }
+}
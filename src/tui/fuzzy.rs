@@ -0,0 +1,115 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+/// Bonus for a match at the start of the candidate or of a word
+const WORD_START_BONUS: i64 = 6;
+
+/// Bonus for a match directly following the previous match
+const CONSECUTIVE_BONUS: i64 = 8;
+
+/// Score how well a query fuzzily matches a candidate, or None if it doesn't
+///
+/// Query characters must appear in the candidate in order (case-insensitive,
+/// whitespace in the query is ignored). Matches at word starts and runs of
+/// consecutive matches score higher; skipped characters cost a point each.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<i64> {
+    let query: Vec<char> = query
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .flat_map(char::to_lowercase)
+        .collect();
+
+    let mut score = 0;
+    let mut matched = 0;
+    let mut previous_matched = false;
+    let mut previous_char = None;
+
+    for c in candidate.chars().flat_map(char::to_lowercase) {
+        if matched == query.len() {
+            break;
+        }
+
+        if c == query[matched] {
+            score += 1;
+            if previous_char.is_none_or(is_separator) {
+                score += WORD_START_BONUS;
+            }
+            if previous_matched {
+                score += CONSECUTIVE_BONUS;
+            }
+            matched += 1;
+            previous_matched = true;
+        } else {
+            score -= 1;
+            previous_matched = false;
+        }
+        previous_char = Some(c);
+    }
+
+    (matched == query.len()).then_some(score)
+}
+
+/// Indices of the candidates matching a query, best match first
+///
+/// Equal scores keep their original order, so an empty query lists everything as-is.
+pub fn rank_matches<'a>(query: &str, candidates: impl IntoIterator<Item = &'a str>) -> Vec<usize> {
+    let mut scored: Vec<(usize, i64)> = candidates
+        .into_iter()
+        .enumerate()
+        .filter_map(|(index, candidate)| fuzzy_score(query, candidate).map(|score| (index, score)))
+        .collect();
+
+    scored.sort_by(|a, b| b.1.cmp(&a.1));
+    scored.into_iter().map(|(index, _)| index).collect()
+}
+
+fn is_separator(c: char) -> bool {
+    c.is_whitespace() || matches!(c, '-' | '_' | '.' | '/' | '#')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_requires_ordered_subsequence() {
+        assert!(fuzzy_score("gnrl", "general").is_some());
+        assert!(fuzzy_score("GEN", "general").is_some());
+        assert!(fuzzy_score("lareneg", "general").is_none());
+        assert!(fuzzy_score("xyz", "general").is_none());
+        assert!(fuzzy_score("generals", "general").is_none());
+    }
+
+    #[test]
+    fn test_prefers_word_starts_and_runs() {
+        let candidates = ["random-devices", "dev", "d-e-v", "dev-ops"];
+        let ranked = rank_matches("dev", candidates);
+
+        assert_eq!(ranked[0], 1);
+        assert_eq!(ranked[1], 3);
+        assert_eq!(ranked.len(), 4);
+    }
+
+    #[test]
+    fn test_empty_query_keeps_order() {
+        assert_eq!(rank_matches("", ["b", "a", "c"]), vec![0, 1, 2]);
+        assert_eq!(rank_matches("  ", ["b", "a"]), vec![0, 1]);
+    }
+
+    #[test]
+    fn test_no_matches() {
+        assert!(rank_matches("zzz", ["general", "random"]).is_empty());
+    }
+}
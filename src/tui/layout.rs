@@ -0,0 +1,105 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+/// Narrowest and widest the channel list may be, as a share of the screen
+pub const MIN_SIDEBAR_PERCENT: u16 = 10;
+pub const MAX_SIDEBAR_PERCENT: u16 = 60;
+
+/// How much one Ctrl+Left/Right press resizes the channel list
+const SIDEBAR_STEP_PERCENT: u16 = 5;
+
+/// Columns the channel list never shrinks below, so names stay readable
+const MIN_SIDEBAR_COLUMNS: u16 = 16;
+
+/// Columns always left for the message pane
+const MIN_CONTENT_COLUMNS: u16 = 20;
+
+/// Width of the channel list relative to the message pane
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SidebarLayout {
+    percent: u16,
+}
+
+impl SidebarLayout {
+    /// Create a layout, clamping the share into the allowed range
+    pub fn new(percent: u16) -> Self {
+        Self {
+            percent: percent.clamp(MIN_SIDEBAR_PERCENT, MAX_SIDEBAR_PERCENT),
+        }
+    }
+
+    pub fn percent(&self) -> u16 {
+        self.percent
+    }
+
+    /// Widen the channel list by one step, returning whether it changed
+    pub fn widen(&mut self) -> bool {
+        self.resize(self.percent.saturating_add(SIDEBAR_STEP_PERCENT))
+    }
+
+    /// Narrow the channel list by one step, returning whether it changed
+    pub fn narrow(&mut self) -> bool {
+        self.resize(self.percent.saturating_sub(SIDEBAR_STEP_PERCENT))
+    }
+
+    /// Channel list width in columns for a screen `total` columns wide
+    ///
+    /// Keeps at least `MIN_SIDEBAR_COLUMNS` for the list and
+    /// `MIN_CONTENT_COLUMNS` for messages; on screens too narrow for both
+    /// the message pane wins.
+    pub fn width(&self, total: u16) -> u16 {
+        let width = (u32::from(total) * u32::from(self.percent) / 100) as u16;
+        width
+            .max(MIN_SIDEBAR_COLUMNS)
+            .min(total.saturating_sub(MIN_CONTENT_COLUMNS))
+    }
+
+    fn resize(&mut self, percent: u16) -> bool {
+        let resized = Self::new(percent);
+        let changed = resized != *self;
+        *self = resized;
+        changed
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_resize_clamped() {
+        let mut layout = SidebarLayout::new(MAX_SIDEBAR_PERCENT - 1);
+        assert!(layout.widen());
+        assert_eq!(layout.percent(), MAX_SIDEBAR_PERCENT);
+        assert!(!layout.widen());
+
+        let mut layout = SidebarLayout::new(0);
+        assert_eq!(layout.percent(), MIN_SIDEBAR_PERCENT);
+        assert!(!layout.narrow());
+    }
+
+    #[test]
+    fn test_width_guards() {
+        let layout = SidebarLayout::new(25);
+        assert_eq!(layout.width(200), 50);
+
+        // The list keeps a usable minimum on small screens...
+        assert_eq!(layout.width(80), MIN_SIDEBAR_COLUMNS + 4);
+        assert_eq!(SidebarLayout::new(MIN_SIDEBAR_PERCENT).width(80), MIN_SIDEBAR_COLUMNS);
+        // ...unless that would squeeze out the messages
+        assert_eq!(layout.width(30), 10);
+        assert_eq!(layout.width(10), 0);
+    }
+}
@@ -0,0 +1,223 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::transform::strip_control;
+use ratatui::style::{Modifier, Style};
+use ratatui::text::Span;
+use std::ffi::OsStr;
+use std::io;
+use std::ops::Range;
+use std::path::Path;
+use std::process::{Command, Stdio};
+
+/// URL schemes recognized as links (only these are ever opened)
+const SCHEMES: [&str; 2] = ["https://", "http://"];
+
+/// Find http(s) links in message text, returned as byte ranges
+pub fn find_links(text: &str) -> Vec<Range<usize>> {
+    let mut links = Vec::new();
+    let mut search_from = 0;
+
+    while let Some((start, scheme_len)) = next_scheme(text, search_from) {
+        let rest = &text[start..];
+        let len = rest
+            .find(|c: char| c.is_whitespace() || c.is_control() || matches!(c, '<' | '>' | '"'))
+            .unwrap_or(rest.len());
+        let end = start + trim_trailing_punctuation(&rest[..len]);
+
+        // Require something after the scheme
+        if end > start + scheme_len {
+            links.push(start..end);
+        }
+        search_from = start + len.max(1);
+    }
+
+    links
+}
+
+/// Split message text into spans, styling links separately
+///
+/// Control sequences are stripped again here in case stored text predates
+/// the receive-side filter. `link_index` counts links across the whole
+/// pane, so the one matching `selected_link` is highlighted.
+pub fn styled_spans(
+    text: &str,
+    text_style: Style,
+    link_style: Style,
+    selected_link: Option<usize>,
+    link_index: &mut usize,
+) -> Vec<Span<'static>> {
+    let text = strip_control(text);
+    let mut spans = Vec::new();
+    let mut pos = 0;
+    for range in find_links(&text) {
+        if range.start > pos {
+            spans.push(Span::styled(text[pos..range.start].to_string(), text_style));
+        }
+        let style = if selected_link == Some(*link_index) {
+            link_style.add_modifier(Modifier::REVERSED)
+        } else {
+            link_style
+        };
+        pos = range.end;
+        spans.push(Span::styled(text[range].to_string(), style));
+        *link_index += 1;
+    }
+    if pos < text.len() {
+        spans.push(Span::styled(text[pos..].to_string(), text_style));
+    }
+    spans
+}
+
+/// Open a link in the system browser
+///
+/// Only called on explicit user action; non-http(s) input is refused.
+pub fn open_link(url: &str) -> io::Result<()> {
+    if find_links(url).first() != Some(&(0..url.len())) {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            "Not an http(s) link",
+        ));
+    }
+
+    open_with_system(OsStr::new(url))
+}
+
+/// Open a file with the system's default application for it
+pub fn open_file(path: &Path) -> io::Result<()> {
+    open_with_system(path.as_os_str())
+}
+
+/// Hand `target` to the platform's opener
+///
+/// The opener is waited on from a thread of its own, so it's reaped once it
+/// exits without holding up the UI (`xdg-open` can stay until the browser
+/// quits); a failure it reports then is logged.
+fn open_with_system(target: &OsStr) -> io::Result<()> {
+    let (program, args): (&str, Vec<&OsStr>) = if cfg!(target_os = "macos") {
+        ("open", vec![target])
+    } else if cfg!(target_os = "windows") {
+        ("rundll32", vec![OsStr::new("url.dll,FileProtocolHandler"), target])
+    } else {
+        ("xdg-open", vec![target])
+    };
+
+    let mut child = Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+
+    std::thread::spawn(move || match child.wait() {
+        Ok(status) if !status.success() => tracing::warn!("{} exited with {}", program, status),
+        Ok(_) => {}
+        Err(e) => tracing::warn!("Failed to wait for {}: {}", program, e),
+    });
+
+    Ok(())
+}
+
+/// Find the next scheme occurrence that starts a word, as (offset, scheme length)
+fn next_scheme(text: &str, from: usize) -> Option<(usize, usize)> {
+    let lower = text[from..].to_ascii_lowercase();
+    SCHEMES
+        .iter()
+        .flat_map(|scheme| {
+            lower
+                .match_indices(*scheme)
+                .map(move |(i, _)| (from + i, scheme.len()))
+        })
+        .filter(|&(i, _)| {
+            text[..i]
+                .chars()
+                .next_back()
+                .is_none_or(|c| !c.is_alphanumeric())
+        })
+        .min()
+}
+
+/// Length of a candidate link with trailing sentence punctuation removed
+fn trim_trailing_punctuation(candidate: &str) -> usize {
+    let mut end = candidate.len();
+    while let Some(c) = candidate[..end].chars().next_back() {
+        let unbalanced_paren = c == ')' && !candidate[..end].contains('(');
+        if matches!(c, '.' | ',' | ';' | ':' | '!' | '?' | '\'' | ']' | '}') || unbalanced_paren {
+            end -= c.len_utf8();
+        } else {
+            break;
+        }
+    }
+    end
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn links_in(text: &str) -> Vec<&str> {
+        find_links(text).into_iter().map(|r| &text[r]).collect()
+    }
+
+    #[test]
+    fn test_finds_multiple_links() {
+        assert_eq!(
+            links_in("see https://example.com/a?b=1 and http://x.org/path"),
+            vec!["https://example.com/a?b=1", "http://x.org/path"]
+        );
+    }
+
+    #[test]
+    fn test_trims_trailing_punctuation() {
+        assert_eq!(links_in("go to https://example.com."), vec!["https://example.com"]);
+        assert_eq!(links_in("(https://example.com)"), vec!["https://example.com"]);
+        assert_eq!(
+            links_in("https://en.wikipedia.org/wiki/Rust_(language)"),
+            vec!["https://en.wikipedia.org/wiki/Rust_(language)"]
+        );
+    }
+
+    #[test]
+    fn test_ignores_non_links() {
+        assert!(links_in("no links here").is_empty());
+        assert!(links_in("https://").is_empty());
+        assert_eq!(links_in("http://x"), vec!["http://x"]);
+        assert!(links_in("javascript:alert(1) file:///etc/passwd").is_empty());
+        assert!(links_in("xhttps://example.com").is_empty());
+    }
+
+    #[test]
+    fn test_spans_carry_no_escape_sequences() {
+        let mut link_index = 0;
+        let spans = styled_spans(
+            "\x1b[31mhi\x1b[0m https://example.com/\x1b[8m x\u{9b}2J\ny",
+            Style::default(),
+            Style::default(),
+            Some(0),
+            &mut link_index,
+        );
+
+        let rendered: String = spans.iter().map(|s| s.content.as_ref()).collect();
+        assert_eq!(rendered, "hi https://example.com/ x\ny");
+        assert!(spans.iter().all(|s| !s.content.contains(|c: char| c.is_control() && c != '\n')));
+        assert_eq!(link_index, 1);
+    }
+
+    #[test]
+    fn test_open_refuses_non_http() {
+        assert!(open_link("file:///etc/passwd").is_err());
+        assert!(open_link("https://example.com; rm -rf ~").is_err());
+    }
+}
@@ -13,17 +13,59 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
+mod alerts;
+mod attachments;
+mod clipboard;
+mod cursor;
+mod delivery;
+mod drafts;
+mod fuzzy;
+mod layout;
+mod links;
+mod notifications;
+mod order;
+mod peers;
+mod scroll;
+mod selection;
+mod timestamps;
+mod unread;
+mod whoami;
+
+use crate::config::{Config, ConnectionNotices};
+use crate::control::{ControlCall, ControlCommand, RpcError};
+use crate::crdt::{HybridLogicalClock, Timestamp};
 use crate::dag::gossip::GossipManager;
-use crate::dag::MessageDAG;
+use crate::encryption::session::{ChannelProtection, SessionManager};
+use crate::dag::IngestMode;
+use crate::identity::nickname::{self, NicknameError};
+use crate::identity::{Identity, PublicBundle};
+use crate::logging::ERROR_LOG_TARGET;
+use crate::node::{self, BurrowNode};
 use crate::network::{NetworkCommand, NetworkEvent};
-use crate::network::peer::PeerManager;
-use crate::protocol::NetworkMessage;
-use crate::storage::Storage;
-use crate::types::{Channel, ChannelId, Message, MessageContent, PeerId, VectorClock};
+use crate::network::peer::{ConnectionQuality, Connectivity, PeerManager};
+use crate::storage::{ChannelActivity, Storage};
+use crate::transform::strip_control;
+use alerts::ConnectionNotice;
+use attachments::{Attachments, ImageState};
+use clipboard::Clipboard;
+use cursor::MessageCursor;
+use delivery::{DeliveryState, DeliveryTracker, ResendRefusal};
+use drafts::Drafts;
+use layout::SidebarLayout;
+use notifications::{NotificationCategory, NotificationLog, NotificationQueue};
+use order::ChannelOrder;
+use scroll::MessageScroll;
+use timestamps::TimestampStyle;
+use unread::UnreadTracker;
+use whoami::WhoAmI;
+use crate::types::{
+    AuthorClockCheck, AuthorClocks, Channel, ChannelId, ImageRef, Message, MessageContent, MessageId, PeerClocks,
+    PeerId, PostPolicy, Priority, SystemEvent, MAX_IMAGE_BYTES,
+};
 use anyhow::Result;
 use tokio::sync::mpsc;
 use crossterm::{
-    event::{self, Event, KeyCode, KeyEvent, KeyEventKind},
+    event::{self, DisableBracketedPaste, EnableBracketedPaste, Event, KeyCode, KeyEvent, KeyEventKind},
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -35,87 +77,257 @@ use ratatui::{
     widgets::{Block, Borders, Clear, List, ListItem, ListState, Paragraph, Wrap},
     Frame, Terminal,
 };
+use std::collections::{HashMap, HashSet};
 use std::io;
-use std::time::{Duration, Instant};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Maximum number of peers listed in the connect dialog
+const MAX_LISTED_PEERS: usize = 8;
+
+/// Most addresses peers have reported seeing us at that are remembered
+const MAX_OBSERVED_ADDRS: usize = 8;
+
+/// Maximum number of channels listed in the quick switcher
+const MAX_QUICK_SWITCH_RESULTS: usize = 10;
+
+/// Disappearing message timers cycled with Ctrl+T (after "off")
+const MESSAGE_TTLS: [Duration; 3] = [
+    Duration::from_secs(5 * 60),
+    Duration::from_secs(60 * 60),
+    Duration::from_secs(24 * 60 * 60),
+];
+
+/// Settings key for the channel list width
+const SIDEBAR_PERCENT_SETTING: &str = "sidebar_percent";
+
+/// Settings key for the user's channel list order
+const CHANNEL_ORDER_SETTING: &str = "channel_order";
+
+/// Marking more unread messages than this as read asks first
+const MARK_ALL_READ_CONFIRM_ABOVE: usize = 50;
+
+/// How often expired messages are wiped
+const EXPIRY_SWEEP_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Name of the personal channel created on first start, which can't be deleted
+const SELF_CHANNEL_NAME: &str = "me";
+
+/// Which pane receives keys in normal mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Focus {
+    Input,
+    Messages,
+}
 
 enum AppMode {
     Normal,
     Help,
     NewChannel,
+    RenameChannel,  // Editing the selected channel's name
+    EditDescription,  // Editing the selected channel's topic line
+    EditKeywords,  // Editing the selected channel's notification keywords
     ConnectPeer,
+    QuickSwitch,
+    CopyFallback,  // Shows text to select by hand when the clipboard is unavailable
+    NotificationLog,
+    WhoAmI,
+    Pinned,  // The selected channel's pinned messages
+    ConfirmMarkAllRead,
+    ConfirmDelete(ChannelId),  // Asks before deleting this channel
 }
 
 #[derive(Clone)]
 struct Notification {
     message: String,
     level: NotificationLevel,
-    timestamp: Instant,
+    timestamp: Instant,  // When raised; identifies the notification in the log
+    shown_at: Instant,  // When it was last put (back) on screen
+    sticky: bool,  // Stays until replaced by another notification
+    category: Option<NotificationCategory>,  // Combines with others of the same category
+    count: usize,  // How many notifications were combined into this one
 }
 
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq)]
 enum NotificationLevel {
     Info,
     Success,
     Error,
+    Urgent,  // An urgent message arrived
 }
 
 impl Notification {
     fn new(message: String, level: NotificationLevel) -> Self {
+        // Everything the user is shown as an error also lands in the error
+        // log, here alone; callers showing an error don't log it themselves
+        if matches!(level, NotificationLevel::Error) {
+            tracing::error!(target: ERROR_LOG_TARGET, kind = "notification", "{}", message);
+        }
+
+        let now = Instant::now();
         Self {
             message,
             level,
-            timestamp: Instant::now(),
+            timestamp: now,
+            shown_at: now,
+            sticky: false,
+            category: None,
+            count: 1,
+        }
+    }
+
+    fn sticky(message: String, level: NotificationLevel) -> Self {
+        Self {
+            sticky: true,
+            ..Self::new(message, level)
+        }
+    }
+
+    /// Mark the notification as one of a category that bursts combine into
+    fn coalescing(self, category: NotificationCategory) -> Self {
+        Self {
+            category: Some(category),
+            ..self
         }
     }
 
     fn is_expired(&self) -> bool {
-        self.timestamp.elapsed() > Duration::from_secs(5)
+        !self.sticky && self.shown_at.elapsed() > Duration::from_secs(5)
+    }
+
+    /// Whether `other` can be folded into this notification
+    fn coalesces_with(&self, other: &Notification) -> bool {
+        match (self.category, other.category) {
+            (Some(category), Some(other_category)) => category == other_category,
+            (None, None) => self.message == other.message && self.level == other.level,
+            _ => false,
+        }
+    }
+
+    /// The text to show, summarising any combined notifications
+    fn text(&self) -> String {
+        match self.category {
+            _ if self.count == 1 => self.message.clone(),
+            Some(category) => category.summary(self.count),
+            None => format!("{} (×{})", self.message, self.count),
+        }
     }
 }
 
 pub struct App {
-    storage: Storage,
+    node: BurrowNode,  // Stored history, the DAG and our clocks; sends messages
     peer_id: PeerId,
     libp2p_peer_id: libp2p::PeerId,
     channels: Vec<Channel>,
+    deleted_channels: HashSet<ChannelId>,  // Tombstoned; announcements and messages are ignored
+    channel_activity: HashMap<ChannelId, ChannelActivity>,  // Shown in the channel list
+    sort_channels_by_activity: bool,
+    channel_order: ChannelOrder,  // Used unless sorting by activity
     selected_channel: Option<usize>,
     messages: Vec<Message>,
-    dag: MessageDAG,  // Phase 4: DAG for causal ordering
+    quotes: HashMap<MessageId, Option<MessageId>>,  // The older message each shown one replies to, if any
+    selected_link: Option<usize>,  // Index into the links of the current channel's messages
+    message_cursor: MessageCursor,  // Target of per-message actions
+    message_scroll: MessageScroll,  // Scrollback of the message pane, by page or by line (Ctrl+Up/Down)
+    clipboard: Clipboard,
+    attachments: Attachments,  // Attached images stored here, and those being fetched
+    copy_fallback_text: String,
+    replying_to: Option<MessageId>,  // Message the next send quotes as a causal parent
+    editing: Option<(MessageId, String)>,  // Message the input replaces the text of, and the stashed draft
+    focus: Focus,
+    hidden_messages: HashSet<MessageId>,  // Hidden locally, kept in the DAG
+    channel_events: HashMap<ChannelId, Vec<(SystemTime, SystemEvent)>>,  // Joins, leaves and renames, as seen here
     gossip_manager: GossipManager,  // Phase 4: Gossip protocol for anti-entropy
     input: String,
-    lamport_clock: u64,
-    vector_clock: VectorClock,
+    drafts: Drafts,  // Unsent input of the channels not currently selected
+    unread: UnreadTracker,  // Unread counts per channel, from the saved read markers
+    muted_channels: HashSet<ChannelId>,  // Only urgent messages notify here
+    channel_keywords: HashMap<ChannelId, Vec<String>>,  // Words that notify even when muted
+    connection_notices: ConnectionNotices,  // Which connection events notify
+    channel_listeners: HashMap<ChannelId, usize>,  // Peers present in each channel's topic
+    presence_topics: HashSet<ChannelId>,  // Channels whose topic we've joined
+    followed_channels: HashSet<ChannelId>,  // Joined whether or not we're a member (BURROW_CHANNELS)
+    author_clocks: AuthorClocks,  // Per-author ordering check for received messages
+    peer_clocks: PeerClocks,  // What each peer had seen as of its latest message
+    delivery: DeliveryTracker,  // Whether our messages sent this session reached anyone
+    show_seen_by: bool,  // Show "seen by k/n" next to messages
+    timestamp_style: TimestampStyle,  // Send times as time of day or age, toggled with Alt+C
+    clock: HybridLogicalClock,  // Shared time base for message expiry
+    message_ttls: HashMap<ChannelId, Duration>,  // Disappearing message timer per channel
+    last_expiry_sweep: Instant,
     channel_list_state: ListState,
+    sidebar: SidebarLayout,  // Channel list width, resized with Ctrl+Left/Right
+    show_peers: bool,  // Connected-peers panel above the status bar, toggled with Ctrl+F
     mode: AppMode,
     new_channel_input: String,
+    rename_input: String,
+    description_input: String,
+    keywords_input: String,
     connect_peer_input: String,
+    quick_switch_input: String,
+    quick_switch_selected: usize,  // Index into the current quick switcher matches
+    pinned_selected: usize,  // Index into the selected channel's pins
     network_event_rx: mpsc::UnboundedReceiver<NetworkEvent>,
     network_command_tx: mpsc::UnboundedSender<NetworkCommand>,
+    control_rx: Option<mpsc::UnboundedReceiver<ControlCall>>,  // Requests from the control socket, if enabled
     peer_manager: PeerManager,
     listen_addrs: Vec<String>,
-    notification: Option<Notification>,
+    observed_addrs: Vec<String>,  // How peers see us, from identify
+    identity_fingerprint: Option<String>,  // Public key fingerprint, shown in the whoami panel
+    public_bundle: Option<PublicBundle>,  // Public keys to share out of band, copied from the whoami panel
+    data_dir: Option<PathBuf>,
+    whoami: WhoAmI,  // Rows of the whoami panel while it's open
+    pending_dials: HashSet<String>,
+    notifications: NotificationQueue,  // On screen now, waiting their turn, and the recent log
+    session_manager: Option<Arc<SessionManager>>,  // Pairwise Signal sessions, shared with the network
+    channel_protection: ChannelProtection,
+    read_only: bool,  // Archive viewer: browse only, no network or writes
 }
 
 impl App {
     pub async fn new(
+        config: &Config,
         storage: Storage,
         peer_id: PeerId,
         libp2p_peer_id: libp2p::PeerId,
         network_event_rx: mpsc::UnboundedReceiver<NetworkEvent>,
         network_command_tx: mpsc::UnboundedSender<NetworkCommand>,
     ) -> Result<Self> {
-        let mut vector_clock = VectorClock::new();
-        vector_clock.increment(peer_id);
+        // Channels this node is configured to always follow
+        let followed_channels = if !config.read_only && !config.startup_channels.is_empty() {
+            node::ensure_channels(&storage, peer_id, &config.startup_channels, &network_command_tx)
+                .await?
+                .into_iter()
+                .collect()
+        } else {
+            HashSet::new()
+        };
 
         let mut channels = storage.get_all_channels().await?;
 
         // Create default "self" channel if no channels exist
-        if channels.is_empty() {
-            let self_channel = Channel::new("me".to_string(), peer_id);
+        if channels.is_empty() && !config.read_only {
+            let self_channel = Channel::new(SELF_CHANNEL_NAME.to_string(), peer_id);
             storage.store_channel(&self_channel).await?;
             channels = storage.get_all_channels().await?;
         }
 
+        let deleted_channels = storage.get_deleted_channel_ids().await?;
+        let channel_activity = storage.get_channel_activity().await?;
+        let channel_order = match storage.get_setting(CHANNEL_ORDER_SETTING).await {
+            Ok(value) => value.map(|v| ChannelOrder::parse(&v)).unwrap_or_default(),
+            Err(e) => {
+                tracing::warn!("Failed to load channel order: {}", e);
+                ChannelOrder::default()
+            }
+        };
+        if config.sort_channels_by_activity {
+            sort_by_activity(&mut channels, &channel_activity);
+        } else {
+            channel_order.apply(&mut channels);
+        }
+
         // Select the first channel by default
         let selected_channel = if !channels.is_empty() { Some(0) } else { None };
         let mut channel_list_state = ListState::default();
@@ -123,51 +335,255 @@ impl App {
             channel_list_state.select(Some(0));
         }
 
-        // Phase 4: Initialize DAG with all messages from all channels
-        let mut dag = MessageDAG::new();
-        for channel in &channels {
-            let channel_messages = storage.get_channel_messages(channel.id).await?;
-            if let Err(e) = dag.load_messages(channel_messages) {
-                tracing::warn!("Failed to load messages into DAG: {}", e);
+        // Messages the user hid from their local view
+        let hidden_messages = match storage.get_hidden_message_ids().await {
+            Ok(ids) => ids,
+            // Archives from older versions may predate the hidden_messages table
+            Err(e) if config.read_only => {
+                tracing::warn!("Failed to load hidden messages from archive: {}", e);
+                HashSet::new()
             }
+            Err(e) => return Err(e),
+        };
+
+        // Phase 4: Initialize DAG from each channel's heads and recent messages;
+        // only the channel shown first gets its whole history right away
+        let channel_ids: Vec<ChannelId> = channels.iter().map(|c| c.id).collect();
+        let mut dag = node::load_dag(&storage, &channel_ids, !config.read_only).await?;
+        if let Some(channel) = selected_channel.and_then(|idx| channels.get(idx)) {
+            node::load_full_history(&storage, &mut dag, channel.id).await?;
+        }
+
+        // Rows damaged by an interrupted write are skipped above; surface them
+        let unreadable = storage.scan_messages().await?;
+        for row in &unreadable {
+            tracing::error!(
+                "Unreadable stored message (rowid {}, id {:?}): {}",
+                row.rowid,
+                row.message_id,
+                row.error
+            );
+        }
+        let mut notifications = NotificationQueue::new(NotificationLog::new(
+            config.notification_log_max_entries,
+            config.notification_log_max_age,
+        ));
+        if !unreadable.is_empty() {
+            notifications.push(Notification::new(
+                format!("{} stored messages are unreadable and were skipped (see log)", unreadable.len()),
+                NotificationLevel::Error,
+            ));
+        }
+
+        // Seed clocks from stored history so a restart never reuses our own
+        // vector clock values (peers would reject them as regressions) or
+        // stamps a message before ones already stored
+        let (vector_clock, lamport_clocks) = node::stored_clocks(&storage, &dag, peer_id).await?;
+        let mut author_clocks = AuthorClocks::new();
+        let mut peer_clocks = PeerClocks::new();
+        for message in dag.all_messages() {
+            author_clocks.observe(message);
+            peer_clocks.observe(message);
         }
 
         // Phase 4: Initialize gossip manager
-        let gossip_manager = GossipManager::new(network_command_tx.clone());
+        let gossip_manager = GossipManager::new(
+            network_command_tx.clone(),
+            config.max_outstanding_requests,
+            config.anti_entropy_fanout,
+        );
 
         // Load messages for the selected channel using DAG ordering
         let messages = if let Some(idx) = selected_channel {
             if let Some(channel) = channels.get(idx) {
-                dag.get_ordered_messages(&channel.id)
+                dag.get_visible_messages(&channel.id, &hidden_messages)
             } else {
                 Vec::new()
             }
         } else {
             Vec::new()
         };
+        let quotes = messages
+            .iter()
+            .map(|message| (message.id, dag.quoted_parents(message).first().copied()))
+            .collect();
+
+        // Images attached to stored messages
+        let attachments = match storage.attachment_hashes().await {
+            Ok(hashes) => Attachments::new(hashes),
+            // Archives from older versions may predate the attachments table
+            Err(e) if config.read_only => {
+                tracing::warn!("Failed to load attachments from archive: {}", e);
+                Attachments::default()
+            }
+            Err(e) => return Err(e),
+        };
+
+        // Restore the unsent input of every channel (archives are never written to)
+        let mut drafts = if config.read_only {
+            Drafts::default()
+        } else {
+            Drafts::new(storage.get_drafts().await?)
+        };
+        let mut input = String::new();
+        drafts.activate(selected_channel.and_then(|idx| channels.get(idx)).map(|c| c.id), &mut input);
+
+        // Joins, leaves and renames recorded in earlier sessions
+        let channel_events = match storage.get_channel_events().await {
+            Ok(events) => events,
+            // Archives from older versions may predate the channel_events table
+            Err(e) if config.read_only => {
+                tracing::warn!("Failed to load channel events from archive: {}", e);
+                HashMap::new()
+            }
+            Err(e) => return Err(e),
+        };
+
+        // Unread counts pick up where the last session left off
+        let read_markers = match storage.get_read_markers().await {
+            Ok(markers) => markers,
+            // Archives from older versions may predate the channel_read_state table
+            Err(e) if config.read_only => {
+                tracing::warn!("Failed to load read markers from archive: {}", e);
+                HashMap::new()
+            }
+            Err(e) => return Err(e),
+        };
+        let muted_channels = match storage.get_muted_channels().await {
+            Ok(ids) => ids,
+            // Archives from older versions may predate the muted_channels table
+            Err(e) if config.read_only => {
+                tracing::warn!("Failed to load muted channels from archive: {}", e);
+                HashSet::new()
+            }
+            Err(e) => return Err(e),
+        };
+        let channel_keywords = match storage.get_channel_keywords().await {
+            Ok(keywords) => keywords,
+            // Archives from older versions may predate the channel_keywords table
+            Err(e) if config.read_only => {
+                tracing::warn!("Failed to load channel keywords from archive: {}", e);
+                HashMap::new()
+            }
+            Err(e) => return Err(e),
+        };
+        let mut unread = UnreadTracker::new(read_markers);
+        for channel in &channels {
+            unread.recount(channel.id, &dag.get_visible_messages(&channel.id, &hidden_messages), peer_id);
+        }
+        if let Some(channel) = selected_channel.and_then(|idx| channels.get(idx)) {
+            if let Some(marker) = unread.mark_read(channel.id, &messages) {
+                if !config.read_only {
+                    storage.store_read_marker(channel.id, marker).await?;
+                }
+            }
+        }
+
+        // A width saved by resizing beats the configured default
+        let saved_sidebar_percent = match storage.get_setting(SIDEBAR_PERCENT_SETTING).await {
+            Ok(value) => value.and_then(|v| v.parse().ok()),
+            Err(e) => {
+                tracing::warn!("Failed to load sidebar width: {}", e);
+                None
+            }
+        };
+        let sidebar = SidebarLayout::new(saved_sidebar_percent.unwrap_or(config.sidebar_percent));
+
+        // Reconnect to peers that announced where they listen
+        let mut peer_manager = PeerManager::new();
+        if !config.read_only {
+            for (peer, addresses) in storage.get_peer_addresses().await? {
+                let Ok(peer) = peer.parse::<libp2p::PeerId>() else {
+                    continue;
+                };
+                let addresses: Vec<libp2p::Multiaddr> =
+                    addresses.iter().filter_map(|a| a.parse().ok()).collect();
+                peer_manager.record_addresses(peer, addresses.clone());
+                network_command_tx.send(NetworkCommand::RedialPeer { peer_id: peer, addresses })?;
+            }
+        }
 
-        Ok(Self {
+        let node = BurrowNode::new(
+            peer_id,
             storage,
+            dag,
+            vector_clock,
+            lamport_clocks,
+            network_command_tx.clone(),
+        );
+
+        let mut app = Self {
+            node,
             peer_id,
             libp2p_peer_id,
             channels,
+            deleted_channels,
+            channel_activity,
+            sort_channels_by_activity: config.sort_channels_by_activity,
+            channel_order,
             selected_channel,
             messages,
-            dag,
+            quotes,
+            selected_link: None,
+            message_cursor: MessageCursor::new(),
+            message_scroll: MessageScroll::new(),
+            clipboard: Clipboard::new(),
+            attachments,
+            copy_fallback_text: String::new(),
+            replying_to: None,
+            editing: None,
+            focus: Focus::Input,
+            hidden_messages,
+            channel_events,
             gossip_manager,
-            input: String::new(),
-            lamport_clock: 0,
-            vector_clock,
+            input,
+            drafts,
+            unread,
+            muted_channels,
+            channel_keywords,
+            connection_notices: config.connection_notices,
+            channel_listeners: HashMap::new(),
+            presence_topics: HashSet::new(),
+            followed_channels,
+            author_clocks,
+            peer_clocks,
+            delivery: DeliveryTracker::new(),
+            show_seen_by: config.show_seen_by,
+            timestamp_style: TimestampStyle::default(),
+            clock: HybridLogicalClock::new(peer_id),
+            message_ttls: HashMap::new(),
+            last_expiry_sweep: Instant::now(),
             channel_list_state,
+            sidebar,
+            show_peers: false,
             mode: AppMode::Normal,
             new_channel_input: String::new(),
+            rename_input: String::new(),
+            description_input: String::new(),
+            keywords_input: String::new(),
             connect_peer_input: String::new(),
+            quick_switch_input: String::new(),
+            quick_switch_selected: 0,
+            pinned_selected: 0,
             network_event_rx,
             network_command_tx,
-            peer_manager: PeerManager::new(),
+            control_rx: None,
+            peer_manager,
             listen_addrs: Vec::new(),
-            notification: None,
-        })
+            observed_addrs: Vec::new(),
+            identity_fingerprint: None,
+            public_bundle: None,
+            data_dir: None,
+            whoami: WhoAmI::default(),
+            pending_dials: HashSet::new(),
+            notifications,
+            session_manager: None,
+            channel_protection: ChannelProtection::Plaintext,
+            read_only: config.read_only,
+        };
+        app.join_channel_topics()?;
+
+        Ok(app)
     }
 
     /// Run the TUI application
@@ -175,16 +591,20 @@ impl App {
         // Setup terminal
         enable_raw_mode()?;
         let mut stdout = io::stdout();
-        execute!(stdout, EnterAlternateScreen)?;
+        execute!(stdout, EnterAlternateScreen, EnableBracketedPaste)?;
         let backend = CrosstermBackend::new(stdout);
         let mut terminal = Terminal::new(backend)?;
 
         // Run the app loop
         let result = self.run_loop(&mut terminal).await;
+        self.cancel_edit();
+        if let Some(channel_id) = self.drafts.active() {
+            self.save_draft(channel_id, self.input.clone()).await;
+        }
 
         // Restore terminal
         disable_raw_mode()?;
-        execute!(terminal.backend_mut(), LeaveAlternateScreen)?;
+        execute!(terminal.backend_mut(), DisableBracketedPaste, LeaveAlternateScreen)?;
         terminal.show_cursor()?;
 
         result
@@ -198,12 +618,14 @@ impl App {
         <B as ratatui::backend::Backend>::Error: Send + Sync + std::error::Error + 'static,
     {
         loop {
-            // Clear expired notifications
-            if let Some(ref notif) = self.notification {
-                if notif.is_expired() {
-                    self.notification = None;
-                }
+            // Move on from expired notifications
+            self.notifications.tick(Instant::now());
+
+            if self.last_expiry_sweep.elapsed() >= EXPIRY_SWEEP_INTERVAL {
+                self.expire_messages().await?;
             }
+            self.report_catch_ups();
+            self.report_sync_previews();
 
             terminal.draw(|f| self.ui(f))?;
 
@@ -211,12 +633,14 @@ impl App {
                 // Handle keyboard input
                 _ = tokio::time::sleep(Duration::from_millis(100)) => {
                     if event::poll(Duration::from_millis(0))? {
-                        if let Event::Key(key) = event::read()? {
-                            if key.kind == KeyEventKind::Press {
+                        match event::read()? {
+                            Event::Key(key) if key.kind == KeyEventKind::Press => {
                                 if self.handle_key_event(key).await? {
                                     break;
                                 }
                             }
+                            Event::Paste(text) => self.handle_paste(&text).await?,
+                            _ => {}
                         }
                     }
                 }
@@ -225,6 +649,11 @@ impl App {
                 Some(network_event) = self.network_event_rx.recv() => {
                     self.handle_network_event(network_event).await?;
                 }
+
+                // Handle control socket requests
+                Some(call) = recv_control(&mut self.control_rx) => {
+                    self.handle_control_call(call).await;
+                }
             }
         }
 
@@ -233,101 +662,493 @@ impl App {
 
     async fn handle_key_event(&mut self, key: KeyEvent) -> Result<bool> {
         match self.mode {
-            AppMode::Help => {
-                // Any key closes help
+            AppMode::Help | AppMode::CopyFallback | AppMode::NotificationLog => {
+                // Any key closes help and the overlays
                 self.mode = AppMode::Normal;
                 return Ok(false);
             }
             AppMode::NewChannel => {
                 return self.handle_new_channel_input(key).await;
             }
+            AppMode::RenameChannel => {
+                self.handle_rename_input(key).await?;
+                return Ok(false);
+            }
+            AppMode::EditDescription => {
+                self.handle_description_input(key).await?;
+                return Ok(false);
+            }
+            AppMode::EditKeywords => {
+                self.handle_keywords_input(key).await?;
+                return Ok(false);
+            }
             AppMode::ConnectPeer => {
                 return self.handle_connect_peer_input(key).await;
             }
+            AppMode::QuickSwitch => {
+                return self.handle_quick_switch_input(key).await;
+            }
+            AppMode::WhoAmI => {
+                self.handle_whoami_input(key);
+                return Ok(false);
+            }
+            AppMode::Pinned => {
+                self.handle_pinned_input(key).await?;
+                return Ok(false);
+            }
+            AppMode::ConfirmMarkAllRead => {
+                self.mode = AppMode::Normal;
+                if matches!(key.code, KeyCode::Enter | KeyCode::Char('y') | KeyCode::Char('Y')) {
+                    self.mark_all_read().await;
+                }
+                return Ok(false);
+            }
+            AppMode::ConfirmDelete(channel_id) => {
+                self.mode = AppMode::Normal;
+                // Only y deletes; Enter, like any other key, cancels
+                if matches!(key.code, KeyCode::Char('y') | KeyCode::Char('Y')) {
+                    self.delete_channel(channel_id).await?;
+                }
+                return Ok(false);
+            }
             AppMode::Normal => {
                 return self.handle_normal_input(key).await;
             }
         }
     }
 
+    /// Insert bracketed-paste text into the active input as a single edit
+    ///
+    /// Inputs are single-line, so line breaks become spaces and a pasted
+    /// newline never sends the message early.
+    async fn handle_paste(&mut self, text: &str) -> Result<()> {
+        // Terminals paste nothing when the clipboard holds only an image
+        if text.is_empty() {
+            return self.paste_image().await;
+        }
+
+        let text: String = text
+            .chars()
+            .map(|c| if c == '\n' || c == '\r' || c == '\t' { ' ' } else { c })
+            .filter(|c| !c.is_control())
+            .collect();
+
+        match self.mode {
+            AppMode::Normal if !self.read_only => self.input.push_str(&text),
+            AppMode::NewChannel => self.new_channel_input.push_str(&text),
+            AppMode::RenameChannel => self.rename_input.push_str(&text),
+            AppMode::EditDescription => self.description_input.push_str(&text),
+            AppMode::EditKeywords => self.keywords_input.push_str(&text),
+            AppMode::ConnectPeer => self.connect_peer_input.push_str(text.trim()),
+            AppMode::QuickSwitch => {
+                self.quick_switch_input.push_str(&text);
+                self.quick_switch_selected = 0;
+            }
+            AppMode::Normal
+            | AppMode::Help
+            | AppMode::CopyFallback
+            | AppMode::NotificationLog
+            | AppMode::WhoAmI
+            | AppMode::Pinned
+            | AppMode::ConfirmMarkAllRead
+            | AppMode::ConfirmDelete(_) => {}
+        }
+        Ok(())
+    }
+
+    /// Send the clipboard's image to the selected channel
+    ///
+    /// Bound to Alt+V, and to pasting in terminals that paste nothing when
+    /// the clipboard holds an image.
+    async fn paste_image(&mut self) -> Result<()> {
+        if !matches!(self.mode, AppMode::Normal) || self.read_only || self.editing.is_some() {
+            return Ok(());
+        }
+        let Some(channel) = self.selected_channel.and_then(|idx| self.channels.get(idx)) else {
+            return Ok(());
+        };
+        if let Some(reason) = self.node.send_blocked_reason(channel) {
+            self.notifications.push(Notification::new(reason.to_string(), NotificationLevel::Error));
+            return Ok(());
+        }
+        let channel = channel.clone();
+
+        let image = match self.clipboard.image() {
+            Ok(image) => image,
+            Err(e) => {
+                let notice = format!("Nothing to paste ({})", e);
+                self.notifications.push(Notification::new(notice, NotificationLevel::Info));
+                return Ok(());
+            }
+        };
+        if image.png.len() as u64 > MAX_IMAGE_BYTES {
+            self.notifications.push(Notification::new(
+                format!(
+                    "Image is too large to send ({} KiB, at most {} KiB)",
+                    image.png.len().div_ceil(1024),
+                    MAX_IMAGE_BYTES / 1024
+                ),
+                NotificationLevel::Error,
+            ));
+            return Ok(());
+        }
+
+        let hash = self.node.storage.store_attachment(&image.png).await?;
+        self.attachments.hold(hash);
+        let content = MessageContent::image(ImageRef::for_png(&image.png, image.width, image.height));
+        self.publish_message(&channel, content, self.replying_to).await?;
+
+        self.replying_to = None;
+        self.message_scroll.to_bottom();
+
+        Ok(())
+    }
+
+    /// Ask the peer that handed us a message for its image, if we lack it
+    fn fetch_image(&mut self, message: &Message, source: libp2p::PeerId) -> Result<()> {
+        let Some(image) = message.current_content().image_ref() else {
+            return Ok(());
+        };
+        if self.attachments.should_request(image, Instant::now()) {
+            self.network_command_tx.send(NetworkCommand::RequestAttachment { hash: image.hash, peers: vec![source] })?;
+        }
+        Ok(())
+    }
+
     async fn handle_network_event(&mut self, event: NetworkEvent) -> Result<()> {
         match event {
-            NetworkEvent::PeerConnected(peer_id) => {
-                tracing::info!("Peer connected: {}", peer_id);
-                self.peer_manager.add_peer(peer_id, None);
-                let peer_str = peer_id.to_string();
-                let peer_short = if peer_str.len() > 12 {
-                    format!("{}...{}", &peer_str[..6], &peer_str[peer_str.len()-6..])
-                } else {
-                    peer_str
+            NetworkEvent::PeerConnected { peer_id, address } => {
+                tracing::info!("Peer connected: {} via {}", peer_id, address);
+                // Replaces the "no peers" notice if we were offline
+                let connectivity = self.peer_manager.add_peer(peer_id, Some(address));
+                let notification = match connectivity {
+                    Connectivity::Online => Notification::new(
+                        format!("Back online: connected to peer {}", short_peer_id(&peer_id)),
+                        NotificationLevel::Success,
+                    ),
+                    _ => Notification::new(
+                        format!("Connected to peer {}", short_peer_id(&peer_id)),
+                        NotificationLevel::Success,
+                    )
+                    .coalescing(NotificationCategory::PeerConnected),
                 };
-                self.notification = Some(Notification::new(
-                    format!("Connected to peer {}", peer_short),
-                    NotificationLevel::Success,
-                ));
+                if self.shows_connection(ConnectionNotice::Connected) {
+                    self.notifications.push(notification);
+                } else {
+                    self.notifications.clear_sticky();
+                }
+                // Requests for placeholders' state made while offline (at
+                // startup, say) reached nobody; ask again now someone can
+                // answer, along with the parents stored history is missing
+                if connectivity == Connectivity::Online {
+                    self.request_placeholder_states();
+                    self.request_missing_parents();
+                }
 
-                // Phase 4: Request inventory for all channels to detect missing messages
-                for channel in &self.channels {
-                    if let Err(e) = self.gossip_manager.request_inventory(channel.id) {
-                        tracing::error!("Failed to request inventory: {}", e);
-                    }
+                // Phase 4: Compare inventories with a few peers, the new one among them
+                let channel_ids: Vec<ChannelId> = self.channels.iter().map(|c| c.id).collect();
+                let connected: Vec<libp2p::PeerId> =
+                    self.peer_manager.get_all_peers().iter().map(|p| p.peer_id).collect();
+                if let Err(e) = self.gossip_manager.anti_entropy_round(&channel_ids, &connected, Some(peer_id)) {
+                    tracing::error!("Failed to request inventory: {}", e);
                 }
             }
             NetworkEvent::PeerDisconnected(peer_id) => {
                 tracing::info!("Peer disconnected: {}", peer_id);
-                self.peer_manager.remove_peer(&peer_id);
+                if self.peer_manager.remove_peer(&peer_id) == Connectivity::Offline {
+                    // Local messages stay in the DAG and sync via anti-entropy later
+                    self.notifications.push(Notification::sticky(
+                        "No peers connected — messages will send when someone connects".to_string(),
+                        NotificationLevel::Info,
+                    ));
+                }
+            }
+            NetworkEvent::MessageUndelivered(message_id) => {
+                tracing::info!("Message {:?} not delivered to any peer yet", message_id);
+                self.delivery.failed(&message_id);
+                // While offline, the "no peers" notice already says so
+                if self.peer_manager.peer_count() > 0 {
+                    self.notifications.push(Notification::new(
+                        "Message not delivered yet; it will sync when peers catch up".to_string(),
+                        NotificationLevel::Info,
+                    ));
+                }
+            }
+            NetworkEvent::EncryptionUnavailable { channel_id, peer_id } => {
+                let channel_name = self
+                    .channels
+                    .iter()
+                    .find(|c| c.id == channel_id)
+                    .map(|c| c.get_name().clone())
+                    .unwrap_or_else(|| "a direct channel".to_string());
+                self.notifications.push(Notification::new(
+                    format!(
+                        "No working encryption session with {} in {}; messages wait until there is one",
+                        peer_id.short(),
+                        channel_name
+                    ),
+                    NotificationLevel::Error,
+                ));
+                self.refresh_channel_protection().await;
+            }
+            NetworkEvent::DecryptFailed { channel_id, peer_id } => {
+                let channel_name = self
+                    .channels
+                    .iter()
+                    .find(|c| c.id == channel_id)
+                    .map(|c| c.get_name().clone())
+                    .unwrap_or_else(|| "a direct channel".to_string());
+                self.notifications.push(Notification::new(
+                    format!(
+                        "A message from {} in {} couldn't be decrypted and was dropped",
+                        peer_id.short(),
+                        channel_name
+                    ),
+                    NotificationLevel::Error,
+                ));
+            }
+            NetworkEvent::SessionEstablished(peer_id) => {
+                tracing::debug!("Encryption session established with {}", peer_id.short());
+                self.refresh_channel_protection().await;
+            }
+            NetworkEvent::PeerAnnounced { peer_id, addresses } => {
+                tracing::debug!("Peer {} listens on {:?}", peer_id, addresses);
+                let address_strings: Vec<String> = addresses.iter().map(|a| a.to_string()).collect();
+                self.peer_manager.record_addresses(peer_id, addresses);
+                if !address_strings.is_empty() {
+                    if let Err(e) = self.node.storage.store_peer_addresses(&peer_id.to_string(), &address_strings).await {
+                        tracing::error!("Failed to store peer addresses: {}", e);
+                    }
+                }
+            }
+            NetworkEvent::PeerIdentified { peer_id, agent_version, protocol_version, observed_addr } => {
+                tracing::debug!(
+                    "Peer {} runs {} ({}) and sees us at {}",
+                    peer_id,
+                    agent_version,
+                    protocol_version,
+                    observed_addr
+                );
+                self.peer_manager.set_identify_info(&peer_id, agent_version, protocol_version);
+
+                let observed_addr = observed_addr.to_string();
+                if !self.observed_addrs.contains(&observed_addr) {
+                    if self.observed_addrs.len() == MAX_OBSERVED_ADDRS {
+                        self.observed_addrs.remove(0);
+                    }
+                    self.observed_addrs.push(observed_addr);
+                }
+            }
+            NetworkEvent::PeerDiscovered(peer_id) => {
+                tracing::debug!("Discovered peer {} on the local network", peer_id);
+                if self.shows_connection(ConnectionNotice::Discovered) {
+                    self.notifications.push(Notification::new(
+                        format!("Discovered peer {} on the local network", short_peer_id(&peer_id)),
+                        NotificationLevel::Info,
+                    ));
+                }
+            }
+            NetworkEvent::AutoDialFailed { peer_id, error } => {
+                tracing::debug!("Automatic dial to {:?} failed: {}", peer_id, error);
+                if self.shows_connection(ConnectionNotice::AutoDialFailed) {
+                    let peer = peer_id.map_or_else(|| "a discovered peer".to_string(), |p| short_peer_id(&p));
+                    self.notifications.push(Notification::new(
+                        format!("Automatic connection to {} failed: {}", peer, error),
+                        NotificationLevel::Error,
+                    ));
+                }
+            }
+            NetworkEvent::PeerPinged { peer_id, rtt } => {
+                self.peer_manager.record_ping(&peer_id, rtt);
+            }
+            NetworkEvent::SelfDialed { reachable, detail } => {
+                tracing::debug!("Self-dial (reachable: {}): {}", reachable, detail);
+            }
+            NetworkEvent::TopicPeersChanged { topic, peers } => {
+                tracing::debug!("{} peer(s) on topic {}", peers, topic);
+            }
+            NetworkEvent::TopicPeers { channel_id, count } => {
+                tracing::debug!("{} peer(s) in channel {:?}", count, channel_id);
+                self.channel_listeners.insert(channel_id, count);
+            }
+            NetworkEvent::MessageEdited { channel_id, message_id, content, edited_at } => {
+                if self.deleted_channels.contains(&channel_id) {
+                    return Ok(());
+                }
+
+                match self.apply_remote_edit(message_id, content, edited_at).await {
+                    Ok(Some(_)) => {
+                        if self.selected_channel.and_then(|idx| self.channels.get(idx)).map(|c| c.id) == Some(channel_id) {
+                            self.reload_current_channel_messages().await?;
+                        }
+                    }
+                    Ok(None) => tracing::debug!("Ignoring edit of {:?}: unknown, stale or not by the author", message_id),
+                    Err(e) => tracing::error!("Failed to store edit of {:?}: {}", message_id, e),
+                }
             }
-            NetworkEvent::MessageReceived(message) => {
+            NetworkEvent::MessageReceived { mut message, source } => {
                 tracing::info!("Message received: {:?}", message.id);
+                self.peer_manager.record_message(&source);
+
+                if self.deleted_channels.contains(&message.channel_id) {
+                    tracing::debug!("Ignoring message for deleted channel {:?}", message.channel_id);
+                    return Ok(());
+                }
+
+                // Resends and overlapping syncs deliver messages we already
+                // have; only an edit riding along with one is news
+                if self.node.dag.has_message(&message.id) {
+                    if let Some(edit) = message.edit.take() {
+                        let (content, edited_at) = (edit.value().clone(), edit.timestamp());
+                        if let Err(e) = self.apply_remote_edit(message.id, content, edited_at).await {
+                            tracing::error!("Failed to store edit of {:?}: {}", message.id, e);
+                        }
+                    }
+                    if let Err(e) = self.gossip_manager.handle_messages_received(&[message.id]) {
+                        tracing::error!("Failed to update message requests: {}", e);
+                    }
+                    return Ok(());
+                }
+
+                // Live posts must respect the channel's post policy. Synced history
+                // isn't checked, since it may predate the channel being locked
+                if let Some(channel) = self.channels.iter().find(|c| c.id == message.channel_id) {
+                    if !channel.can_post(&message.author) {
+                        tracing::warn!(
+                            target: ERROR_LOG_TARGET,
+                            kind = "rejected_message",
+                            "Dropping message {:?} from non-admin {} in admins-only channel {}",
+                            message.id,
+                            message.author.short(),
+                            channel.get_name()
+                        );
+                        if let Err(e) = self.gossip_manager.handle_messages_received(&[message.id]) {
+                            tracing::error!("Failed to update message requests: {}", e);
+                        }
+                        return Ok(());
+                    }
+                }
+
+                // Messages may arrive in any order, but an author never reuses
+                // their own clock or goes back below a message they built on
+                match self.author_clocks.observe(&message) {
+                    AuthorClockCheck::Advanced | AuthorClockCheck::Duplicate => {}
+                    AuthorClockCheck::Late => tracing::debug!(
+                        "Message {:?} from {} arrived after later ones",
+                        message.id,
+                        message.author.short()
+                    ),
+                    check @ (AuthorClockCheck::Conflict(_) | AuthorClockCheck::Regression) => {
+                        tracing::warn!(
+                            target: ERROR_LOG_TARGET,
+                            kind = "rejected_message",
+                            "Dropping message {:?} from {}: author clock {} is inconsistent ({:?})",
+                            message.id,
+                            message.author.short(),
+                            message.vector_clock.get(&message.author),
+                            check
+                        );
+                        // Asking again would only bring the same message back
+                        if let Err(e) = self.gossip_manager.handle_messages_received(&[message.id]) {
+                            tracing::error!("Failed to update message requests: {}", e);
+                        }
+                        return Ok(());
+                    }
+                }
 
                 // Check if channel exists, create it if not
                 let channel_exists = self.channels.iter().any(|c| c.id == message.channel_id);
                 if !channel_exists {
                     tracing::info!("Creating placeholder channel for {}", message.channel_id.0);
-                    // Create a placeholder channel with a temporary name
-                    // In Phase 3, we'll properly sync channel metadata via CRDTs
-                    let channel_id_short = message.channel_id.0.to_string();
-                    let channel_name = format!("channel-{}", &channel_id_short[..8]);
+                    // Create a placeholder channel with a temporary name; the real name
+                    // replaces it once the channel state arrives
+                    let channel_name = Channel::placeholder_name(message.channel_id, message.author);
                     let channel = Channel::placeholder(message.channel_id, channel_name.clone(), message.author);
 
-                    if let Err(e) = self.storage.store_channel(&channel).await {
+                    if let Err(e) = self.node.storage.store_channel(&channel).await {
                         tracing::error!("Failed to create placeholder channel: {}", e);
                     } else {
-                        self.channels = self.storage.get_all_channels().await?;
-                        self.notification = Some(Notification::new(
-                            format!("New channel discovered: {}", channel_name),
-                            NotificationLevel::Info,
-                        ));
+                        self.refresh_channels().await?;
+                        self.notifications.push(
+                            Notification::new(
+                                format!("New channel discovered: {}", channel_name),
+                                NotificationLevel::Info,
+                            )
+                            .coalescing(NotificationCategory::ChannelDiscovered),
+                        );
+
+                        if let Err(e) = self.network_command_tx.send(NetworkCommand::RequestChannelState(message.channel_id)) {
+                            tracing::error!("Failed to request channel state: {}", e);
+                        }
                     }
                 }
 
+                message.content = self.node.content_pipeline.incoming(message.content.clone());
+                // Its edit, if it carries one, goes through the edit path once it's stored
+                let carried_edit = message.edit.take();
+
+                // Expiry is judged on the HLC, so every replica agrees on
+                // when the message disappears regardless of local wall clocks
+                self.observe_message_time(&message);
+                if message.is_expired(self.clock.physical_now()) {
+                    message.expire();
+                }
+
                 // Store the message
-                if let Err(e) = self.storage.store_message(&message).await {
-                    tracing::error!("Failed to store message: {}", e);
-                    self.notification = Some(Notification::new(
+                if let Err(e) = self.node.storage.store_message(&message).await {
+                    self.notifications.push(Notification::new(
                         format!("Failed to store message: {}", e),
                         NotificationLevel::Error,
                     ));
                 } else {
                     // Update vector clock
-                    self.vector_clock.merge(&message.vector_clock);
+                    self.node.vector_clock.merge(&message.vector_clock);
+                    self.peer_clocks.observe(&message);
+                    self.delivery.observe(&message, self.peer_id, &self.node.dag);
+
+                    // Update the channel's lamport clock
+                    self.node.lamport_clocks.observe(message.channel_id, message.lamport_timestamp);
+                    self.record_activity(&message);
 
-                    // Update lamport clock
-                    if message.lamport_timestamp >= self.lamport_clock {
-                        self.lamport_clock = message.lamport_timestamp + 1;
+                    if !self.node.dag.has_message(&message.id) {
+                        self.unread.observe(&message, self.peer_id);
+                        self.notify_message(&message);
                     }
 
                     // Phase 4: Add message to DAG
-                    if let Err(e) = self.dag.add_message(message.clone()) {
-                        tracing::warn!("Failed to add message to DAG: {} - message may have missing parents", e);
-                        // Store missing parent for later resolution via gossip
+                    // Messages ahead of their parents wait in the orphan pool,
+                    // and the missing parents are requested now rather than at
+                    // the next anti-entropy round
+                    if let Err(e) = self.node.dag.ingest(message.clone(), IngestMode::BufferOrphans) {
+                        tracing::warn!("Failed to add message to DAG: {}", e);
+                    } else if self.node.dag.is_orphan(&message.id) {
+                        let channel_id = message.channel_id;
+                        if let Err(e) = self.gossip_manager.detect_and_request_missing(channel_id, &self.node.dag) {
+                            tracing::error!("Failed to request missing parents: {}", e);
+                        }
+                    }
+                    if let Err(e) = self.node.save_heads(message.channel_id).await {
+                        tracing::warn!("Failed to save channel heads: {:#}", e);
+                    }
+                    if let Some(edit) = carried_edit {
+                        let (content, edited_at) = (edit.value().clone(), edit.timestamp());
+                        if let Err(e) = self.apply_remote_edit(message.id, content, edited_at).await {
+                            tracing::error!("Failed to store edit of {:?}: {}", message.id, e);
+                        }
                     }
 
+                    if let Err(e) = self.gossip_manager.handle_messages_received(&[message.id]) {
+                        tracing::error!("Failed to update message requests: {}", e);
+                    }
+                    self.fetch_image(&message, source)?;
+
                     // If it's for the currently selected channel, reload messages in DAG order
                     if let Some(idx) = self.selected_channel {
                         if let Some(channel) = self.channels.get(idx) {
                             if message.channel_id == channel.id {
-                                self.messages = self.dag.get_ordered_messages(&channel.id);
+                                self.show_messages(channel.id);
+                                self.message_cursor.resync(&self.messages);
+                                self.mark_selected_read().await;
                             }
                         }
                     }
@@ -339,38 +1160,58 @@ impl App {
             }
             NetworkEvent::ConnectionDialing { address } => {
                 tracing::info!("Dialing peer at {}", address);
-                self.notification = Some(Notification::new(
-                    format!("Connecting to {}...", address),
-                    NotificationLevel::Info,
-                ));
+                self.pending_dials.insert(address.clone());
+                if self.shows_connection(ConnectionNotice::ManualDialing) {
+                    self.notifications.push(Notification::new(
+                        format!("Connecting to {}...", address),
+                        NotificationLevel::Info,
+                    ));
+                }
+            }
+            NetworkEvent::DialSucceeded { address } => {
+                self.pending_dials.remove(&address);
+            }
+            NetworkEvent::ConnectionCancelled { address } => {
+                tracing::info!("Cancelled connection to {}", address);
+                self.pending_dials.remove(&address);
+                // Take down the "Connecting..." notice, if one was shown
+                if self.shows_connection(ConnectionNotice::ManualDialing) {
+                    self.notifications.dismiss();
+                }
             }
             NetworkEvent::ConnectionFailed { address, error } => {
                 tracing::warn!("Connection failed to {}: {}", address, error);
-                self.notification = Some(Notification::new(
-                    format!("Connection failed to {}: {}", address, error),
-                    NotificationLevel::Error,
-                ));
+                self.pending_dials.remove(&address);
+                if self.shows_connection(ConnectionNotice::ManualDialFailed) {
+                    self.notifications.push(Notification::new(
+                        format!("Connection failed to {}: {}", address, error),
+                        NotificationLevel::Error,
+                    ));
+                }
             }
             NetworkEvent::ChannelAnnounced(channel) => {
                 tracing::info!("Channel announced: {}", channel.get_name());
 
                 // Check if we already have this channel
-                if let Some(existing) = self.channels.iter_mut().find(|c| c.id == channel.id) {
-                    // Merge the CRDT state
-                    existing.merge(&channel);
-                    if let Err(e) = self.storage.store_channel(existing).await {
-                        tracing::error!("Failed to update channel: {}", e);
-                    }
+                if self.deleted_channels.contains(&channel.id) {
+                    tracing::debug!("Ignoring announcement for deleted channel {:?}", channel.id);
+                } else if channel.is_deleted() {
+                    self.store_deleted_channel(&channel).await?;
+                } else if self.channels.iter().any(|c| c.id == channel.id) {
+                    self.merge_known_channel(&channel).await?;
                 } else {
                     // New channel, add it
-                    if let Err(e) = self.storage.store_channel(&channel).await {
+                    if let Err(e) = self.node.storage.store_channel(&channel).await {
                         tracing::error!("Failed to store new channel: {}", e);
                     } else {
-                        self.channels = self.storage.get_all_channels().await?;
-                        self.notification = Some(Notification::new(
-                            format!("New channel: {}", channel.get_name()),
-                            NotificationLevel::Info,
-                        ));
+                        self.refresh_channels().await?;
+                        self.notifications.push(
+                            Notification::new(
+                                format!("New channel: {}", channel.get_name()),
+                                NotificationLevel::Info,
+                            )
+                            .coalescing(NotificationCategory::NewChannel),
+                        );
                     }
                 }
             }
@@ -378,16 +1219,17 @@ impl App {
                 tracing::info!("Channel state received: {}", channel.get_name());
 
                 // Merge with existing channel or add as new
-                if let Some(existing) = self.channels.iter_mut().find(|c| c.id == channel.id) {
-                    existing.merge(&channel);
-                    if let Err(e) = self.storage.store_channel(existing).await {
-                        tracing::error!("Failed to update channel: {}", e);
-                    }
+                if self.deleted_channels.contains(&channel.id) {
+                    tracing::debug!("Ignoring state for deleted channel {:?}", channel.id);
+                } else if channel.is_deleted() {
+                    self.store_deleted_channel(&channel).await?;
+                } else if self.channels.iter().any(|c| c.id == channel.id) {
+                    self.merge_known_channel(&channel).await?;
                 } else {
-                    if let Err(e) = self.storage.store_channel(&channel).await {
+                    if let Err(e) = self.node.storage.store_channel(&channel).await {
                         tracing::error!("Failed to store channel: {}", e);
                     } else {
-                        self.channels = self.storage.get_all_channels().await?;
+                        self.refresh_channels().await?;
                     }
                 }
             }
@@ -395,28 +1237,45 @@ impl App {
                 tracing::info!("Channel updated: {}", channel.get_name());
 
                 // Merge the update
-                if let Some(existing) = self.channels.iter_mut().find(|c| c.id == channel.id) {
-                    existing.merge(&channel);
-                    if let Err(e) = self.storage.store_channel(existing).await {
-                        tracing::error!("Failed to update channel: {}", e);
-                    }
-                    // Refresh the channel list from storage
-                    self.channels = self.storage.get_all_channels().await?;
+                self.merge_known_channel(&channel).await?;
+            }
+            NetworkEvent::ChannelTombstoneReceived { channel_id, deleted_at } => {
+                tracing::info!("Channel tombstone received for {:?}", channel_id);
+
+                let Some(channel) = self.channels.iter_mut().find(|c| c.id == channel_id) else {
+                    return Ok(());
+                };
+
+                if channel.apply_tombstone(deleted_at) {
+                    let channel = channel.clone();
+                    self.node.storage.tombstone_channel(&channel).await?;
+                    self.forget_deleted_channel(channel_id).await?;
+                    self.notifications.push(Notification::new(
+                        format!("Channel {} was deleted by {}", channel.get_name(), deleted_at.peer_id.short()),
+                        NotificationLevel::Info,
+                    ));
+                } else {
+                    tracing::warn!(
+                        "Ignoring tombstone for {} from non-member {}",
+                        channel.get_name(),
+                        deleted_at.peer_id.short()
+                    );
                 }
             }
             NetworkEvent::ChannelStateRequested { channel_id, requesting_peer: _ } => {
                 tracing::info!("Channel state requested for {:?}", channel_id);
 
-                // Find the channel and send it back
-                if let Some(channel) = self.channels.iter().find(|c| c.id == channel_id) {
-                    let network_msg = NetworkMessage::ChannelStateResponse {
-                        channel: channel.clone()
-                    };
-                    if let Ok(bytes) = network_msg.to_bytes() {
-                        // Send via gossipsub (we'll need to import NetworkMessage)
-                        // For now, just log it - the proper implementation would send via the network
-                        tracing::debug!("Would send channel state response for {}", channel.get_name());
-                        // TODO: Send via command channel to network layer
+                // Find the channel and send it back (tombstones too, so peers converge)
+                let channel = match self.channels.iter().find(|c| c.id == channel_id) {
+                    Some(channel) => Some(channel.clone()),
+                    None if self.deleted_channels.contains(&channel_id) => {
+                        self.node.storage.get_channel(channel_id).await?
+                    }
+                    None => None,
+                };
+                if let Some(channel) = channel {
+                    if let Err(e) = self.network_command_tx.send(NetworkCommand::RespondWithChannelState(channel)) {
+                        tracing::error!("Failed to send channel state: {}", e);
                     }
                 }
             }
@@ -427,60 +1286,317 @@ impl App {
                 if let Err(e) = self.gossip_manager.handle_message_request(
                     channel_id,
                     message_ids,
-                    &self.storage,
+                    &self.node.storage,
                 ).await {
                     tracing::error!("Failed to handle message request: {}", e);
                 }
             }
-            NetworkEvent::MessagesReceived { channel_id, messages } => {
+            NetworkEvent::MessagesReceived { channel_id, mut messages, source } => {
                 tracing::info!("Received {} messages from peer", messages.len());
 
-                // Store messages
-                if let Err(e) = self.storage.store_messages(&messages).await {
-                    tracing::error!("Failed to store received messages: {}", e);
-                } else {
-                    // Add messages to DAG
-                    for message in &messages {
-                        if let Err(e) = self.dag.add_message(message.clone()) {
-                            tracing::warn!("Failed to add message to DAG: {}", e);
-                        }
+                if self.deleted_channels.contains(&channel_id) {
+                    tracing::debug!("Ignoring messages for deleted channel {:?}", channel_id);
+                    return Ok(());
+                }
+
+                // Edits ride along with synced history; they're applied through
+                // the edit path once their messages are stored, which also
+                // brings up to date messages we had without the edit
+                let mut carried_edits = Vec::new();
+                for message in &mut messages {
+                    message.content = self.node.content_pipeline.incoming(message.content.clone());
+                    if let Some(edit) = message.edit.take() {
+                        carried_edits.push((message.id, edit));
                     }
+                }
 
-                    // If it's for the currently selected channel, reload messages
-                    if let Some(idx) = self.selected_channel {
-                        if let Some(channel) = self.channels.get(idx) {
-                            if channel.id == channel_id {
-                                self.messages = self.dag.get_ordered_messages(&channel.id);
-                            }
-                        }
+                // Store messages, keeping those that made it to disk. The
+                // failed ones stay wanted, and are requested again by the next
+                // catch-up or inventory exchange
+                let summary = self.node.storage.store_messages(&messages).await;
+                for (message_id, e) in &summary.failed {
+                    tracing::error!("Failed to store received message {:?}: {}", message_id, e);
+                }
+                messages.retain(|message| summary.is_stored(&message.id));
+
+                // Add messages to DAG. Sync backfills history, so older
+                // author clocks are expected here and only advance the tracker
+                for message in &messages {
+                    self.node.lamport_clocks.observe(message.channel_id, message.lamport_timestamp);
+                    self.author_clocks.observe(message);
+                    self.peer_clocks.observe(message);
+                    self.delivery.observe(message, self.peer_id, &self.node.dag);
+                    self.observe_message_time(message);
+                    if !self.node.dag.has_message(&message.id) {
+                        self.record_activity(message);
+                        self.unread.observe(message, self.peer_id);
+                    }
+                    if let Err(e) = self.node.dag.ingest(message.clone(), IngestMode::BufferOrphans) {
+                        tracing::warn!("Failed to add message to DAG: {}", e);
                     }
+                    self.fetch_image(message, source)?;
                 }
-            }
-            NetworkEvent::InventoryReceived { channel_id, message_ids, from_peer: _ } => {
-                tracing::debug!("Received inventory with {} messages", message_ids.len());
-                if let Err(e) = self.gossip_manager.handle_inventory(
-                    channel_id,
-                    message_ids,
-                    &self.dag,
-                ) {
-                    tracing::error!("Failed to handle inventory: {}", e);
+                if !messages.is_empty() {
+                    if let Err(e) = self.node.save_heads(channel_id).await {
+                        tracing::warn!("Failed to save channel heads: {:#}", e);
+                    }
+                }
+                for (message_id, edit) in carried_edits {
+                    if !summary.is_stored(&message_id) {
+                        continue;
+                    }
+                    if let Err(e) = self.apply_remote_edit(message_id, edit.value().clone(), edit.timestamp()).await {
+                        tracing::error!("Failed to store edit of {:?}: {}", message_id, e);
+                    }
+                }
+
+                // Free request slots for the messages that arrived
+                let message_ids: Vec<_> = messages.iter().map(|m| m.id).collect();
+                if let Err(e) = self.gossip_manager.handle_messages_received(&message_ids) {
+                    tracing::error!("Failed to update message requests: {}", e);
+                }
+
+                // If it's for the currently selected channel, reload messages
+                if let Some(idx) = self.selected_channel {
+                    if let Some(channel) = self.channels.get(idx) {
+                        if channel.id == channel_id {
+                            self.show_messages(channel.id);
+                            self.message_cursor.resync(&self.messages);
+                            self.mark_selected_read().await;
+                        }
+                    }
+                }
+            }
+            NetworkEvent::InventoryReceived { channel_id, message_ids, edits, from_peer } => {
+                tracing::debug!("Received inventory with {} messages", message_ids.len());
+                if self.deleted_channels.contains(&channel_id) {
+                    return Ok(());
+                }
+                // Asked for by a sync preview: compared, but nothing fetched
+                if self.gossip_manager.preview_inventory(channel_id, from_peer, &message_ids) {
+                    return Ok(());
+                }
+                let our_edits = match self.node.storage.get_channel_edit_times(channel_id).await {
+                    Ok(our_edits) => our_edits,
+                    Err(e) => {
+                        tracing::error!("Failed to load edit times: {}", e);
+                        return Ok(());
+                    }
+                };
+                if let Err(e) = self.gossip_manager.handle_inventory(
+                    channel_id,
+                    message_ids,
+                    &edits,
+                    &our_edits,
+                    &self.node.dag,
+                ) {
+                    tracing::error!("Failed to handle inventory: {}", e);
                 }
             }
             NetworkEvent::InventoryRequested { channel_id, requesting_peer: _ } => {
                 tracing::debug!("Inventory requested for channel {:?}", channel_id);
                 if let Err(e) = self.gossip_manager.send_inventory(
                     channel_id,
-                    &self.storage,
+                    &self.node.storage,
                 ).await {
                     tracing::error!("Failed to send inventory: {}", e);
                 }
             }
+            NetworkEvent::AttachmentRequested { hash, requesting_peer } => {
+                // Only a peer that saw the message knows the hash to ask for
+                match self.node.storage.get_attachment(&hash).await {
+                    Ok(Some(data)) => {
+                        let peer = requesting_peer;
+                        self.network_command_tx.send(NetworkCommand::SendAttachment { hash, data, peer })?;
+                    }
+                    Ok(None) => tracing::debug!("{} asked for an attachment we don't have", requesting_peer),
+                    Err(e) => tracing::error!("Failed to read attachment: {}", e),
+                }
+            }
+            NetworkEvent::AttachmentReceived { hash, data } => {
+                if !self.attachments.expects(&hash, &data) {
+                    return Ok(());
+                }
+                match self.node.storage.store_attachment(&data).await {
+                    Ok(_) => self.attachments.hold(hash),
+                    Err(e) => tracing::error!("Failed to store attachment: {}", e),
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Merge remote CRDT state into a channel we already know about,
+    /// notifying when this resolves the channel's name (e.g. a placeholder)
+    async fn merge_known_channel(&mut self, channel: &Channel) -> Result<()> {
+        let Some(existing) = self.channels.iter_mut().find(|c| c.id == channel.id) else {
+            return Ok(());
+        };
+
+        let before = existing.clone();
+        let old_name = existing.get_name().clone();
+        existing.merge(channel);
+
+        // The merged state may carry a member's tombstone
+        if existing.is_deleted() {
+            let existing = existing.clone();
+            self.node.storage.tombstone_channel(&existing).await?;
+            self.forget_deleted_channel(existing.id).await?;
+            self.notifications.push(Notification::new(
+                format!("Channel {} was deleted", old_name),
+                NotificationLevel::Info,
+            ));
+            return Ok(());
+        }
+
+        if let Err(e) = self.node.storage.store_channel(existing).await {
+            tracing::error!("Failed to update channel: {}", e);
+        }
+
+        if existing.get_name() != &old_name {
+            self.notifications.push(Notification::new(
+                format!("Channel {} is now {}", old_name, existing.get_name()),
+                NotificationLevel::Info,
+            ));
+        }
+
+        let channel_id = existing.id;
+        for event in existing.system_events_since(&before) {
+            self.record_system_event(channel_id, event).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Record a channel that arrived already deleted, so it can't be resurrected
+    async fn store_deleted_channel(&mut self, channel: &Channel) -> Result<()> {
+        self.node.storage.tombstone_channel(channel).await?;
+        self.deleted_channels.insert(channel.id);
+        Ok(())
+    }
+
+    /// Delete a channel locally and tell peers (only members may delete)
+    async fn delete_channel(&mut self, channel_id: ChannelId) -> Result<()> {
+        let Some(channel) = self.channels.iter_mut().find(|c| c.id == channel_id) else {
+            return Ok(());
+        };
+
+        if !channel.get_members().contains(&self.peer_id) {
+            self.notifications.push(Notification::new(
+                format!("Only members can delete {}", channel.get_name()),
+                NotificationLevel::Error,
+            ));
+            return Ok(());
+        }
+
+        let deleted_at = channel.delete();
+        let channel = channel.clone();
+        self.node.storage.tombstone_channel(&channel).await?;
+
+        if let Err(e) = self.network_command_tx.send(NetworkCommand::BroadcastChannelTombstone {
+            channel_id,
+            deleted_at,
+        }) {
+            tracing::error!("Failed to broadcast channel tombstone: {}", e);
+        }
+
+        self.forget_deleted_channel(channel_id).await?;
+        self.notifications.push(Notification::new(
+            format!("Deleted channel {}", channel.get_name()),
+            NotificationLevel::Success,
+        ));
+
+        Ok(())
+    }
+
+    /// Whether this is the channel only we are in, created on first start
+    ///
+    /// It's found by its name, so it can't be renamed or deleted.
+    fn is_personal_channel(&self, channel: &Channel) -> bool {
+        channel.get_name() == SELF_CHANNEL_NAME && channel.get_members() == [self.peer_id]
+    }
+
+    /// Ask before deleting the selected channel, unless it's the personal one
+    fn confirm_delete_selected_channel(&mut self) {
+        if self.read_only {
+            return;
+        }
+        let Some(channel) = self.selected_channel.and_then(|idx| self.channels.get(idx)) else {
+            return;
+        };
+        if self.is_personal_channel(channel) {
+            self.notifications.push(Notification::new(
+                "Your personal channel can't be deleted".to_string(),
+                NotificationLevel::Error,
+            ));
+            return;
+        }
+
+        self.mode = AppMode::ConfirmDelete(channel.id);
+    }
+
+    /// Drop a deleted channel from the channel list, keeping the selection if possible
+    async fn forget_deleted_channel(&mut self, channel_id: ChannelId) -> Result<()> {
+        self.deleted_channels.insert(channel_id);
+        self.node.dag.remove_channel(&channel_id);
+        self.gossip_manager.forget_channel(&channel_id);
+        self.drafts.remove(&channel_id);
+        self.unread.remove(&channel_id);
+        self.channel_listeners.remove(&channel_id);
+        self.presence_topics.remove(&channel_id);
+        if !self.read_only {
+            self.network_command_tx.send(NetworkCommand::LeaveChannel(channel_id))?;
         }
 
+        let previous = self.channel_ids();
+        self.channels = self.node.storage.get_all_channels().await?;
+        self.arrange_channels();
+        self.resync_selection(&previous).await
+    }
+
+    /// The listed channels' IDs, in list order
+    fn channel_ids(&self) -> Vec<ChannelId> {
+        self.channels.iter().map(|c| c.id).collect()
+    }
+
+    /// Point the selection back at the selected channel after the channel list changed
+    ///
+    /// `previous` is the list's IDs before the change. If the selected channel
+    /// is gone, a neighbour takes its place and its messages are loaded.
+    async fn resync_selection(&mut self, previous: &[ChannelId]) -> Result<()> {
+        if self.reselect(previous) {
+            self.messages.clear();
+            self.quotes.clear();
+            self.load_messages().await?;
+        }
         Ok(())
     }
 
+    /// Update the selection index for a changed channel list, returning
+    /// whether a different channel (or none) is now selected
+    fn reselect(&mut self, previous: &[ChannelId]) -> bool {
+        let before = self.selected_channel.and_then(|idx| previous.get(idx)).copied();
+        let index = selection::resync(previous, self.selected_channel, &self.channels);
+        self.selected_channel = index;
+        self.channel_list_state.select(index);
+        index.and_then(|idx| self.channels.get(idx)).map(|c| c.id) != before
+    }
+
     async fn handle_normal_input(&mut self, key: KeyEvent) -> Result<bool> {
+        if self.focus == Focus::Messages && self.handle_message_pane_key(&key).await? {
+            return Ok(false);
+        }
+
+        // Archive mode only allows browsing
+        if self.read_only && is_write_action(&key) {
+            self.notifications.push(Notification::new(
+                "Read-only archive: sending and networking are disabled".to_string(),
+                NotificationLevel::Info,
+            ));
+            return Ok(false);
+        }
+
         match key.code {
             KeyCode::Char('q') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
                 return Ok(true); // Exit
@@ -491,6 +1607,15 @@ impl App {
             KeyCode::Char('h') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
                 self.mode = AppMode::Help;
             }
+            KeyCode::Char('g') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                self.mode = AppMode::NotificationLog;
+            }
+            KeyCode::Char('w') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                self.open_whoami();
+            }
+            KeyCode::Char('f') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                self.show_peers = !self.show_peers;
+            }
             KeyCode::Char('n') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
                 // Open new channel modal
                 self.mode = AppMode::NewChannel;
@@ -501,14 +1626,130 @@ impl App {
                 self.mode = AppMode::ConnectPeer;
                 self.connect_peer_input.clear();
             }
+            KeyCode::Char('r') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                self.request_channel_info()?;
+            }
+            KeyCode::Char('s') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                self.catch_up_selected_channel()?;
+            }
+            KeyCode::Char('s') if key.modifiers.contains(event::KeyModifiers::ALT) => {
+                self.preview_sync_selected_channel().await?;
+            }
+            KeyCode::Char('k') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                // Open channel quick switcher
+                self.mode = AppMode::QuickSwitch;
+                self.quick_switch_input.clear();
+                self.quick_switch_selected = 0;
+            }
+            KeyCode::Esc if self.editing.is_some() => {
+                self.cancel_edit();
+            }
+            KeyCode::Esc if self.replying_to.is_some() => {
+                self.replying_to = None;
+            }
+            KeyCode::Esc if !self.pending_dials.is_empty() => {
+                // Give up on pending peer connections
+                self.network_command_tx.send(NetworkCommand::CancelAllDials)?;
+            }
+            KeyCode::Char('l') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                self.select_next_link();
+            }
+            KeyCode::Char('o') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                self.open_selected_link();
+            }
+            KeyCode::Char('x') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                self.hide_selected_message().await?;
+            }
+            KeyCode::Char('u') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                self.unhide_all_messages().await?;
+            }
+            KeyCode::Char('t') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                self.cycle_message_ttl();
+            }
+            KeyCode::Char('a') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                self.toggle_post_policy().await?;
+            }
+            KeyCode::Char('b') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                self.toggle_mute_selected_channel().await?;
+            }
+            KeyCode::Char('d') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                self.confirm_delete_selected_channel();
+            }
+            KeyCode::Char('y') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                self.copy_selected_message(false);
+            }
+            KeyCode::Char('e') if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                self.edit_selected_message();
+            }
+            KeyCode::Tab => {
+                // Focus the message pane to pick a message for per-message actions
+                self.focus = Focus::Messages;
+                self.message_cursor.select_last(&self.messages);
+            }
+            KeyCode::Left if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                if self.sidebar.narrow() {
+                    self.save_sidebar_width().await;
+                }
+            }
+            KeyCode::Right if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                if self.sidebar.widen() {
+                    self.save_sidebar_width().await;
+                }
+            }
+            KeyCode::Char('r') if key.modifiers.contains(event::KeyModifiers::ALT) => {
+                if self.unread.total() > MARK_ALL_READ_CONFIRM_ABOVE {
+                    self.mode = AppMode::ConfirmMarkAllRead;
+                } else {
+                    self.mark_all_read().await;
+                }
+            }
+            KeyCode::Char('n') if key.modifiers.contains(event::KeyModifiers::ALT) => {
+                self.open_rename_editor();
+            }
+            KeyCode::Char('t') if key.modifiers.contains(event::KeyModifiers::ALT) => {
+                self.open_description_editor();
+            }
+            KeyCode::Char('k') if key.modifiers.contains(event::KeyModifiers::ALT) => {
+                self.open_keywords_editor();
+            }
+            KeyCode::Char('c') if key.modifiers.contains(event::KeyModifiers::ALT) => {
+                self.timestamp_style = self.timestamp_style.toggled();
+            }
+            KeyCode::Char('v') if key.modifiers.contains(event::KeyModifiers::ALT) => {
+                self.paste_image().await?;
+            }
+            KeyCode::Up if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                self.message_scroll.line_up();
+            }
+            KeyCode::Down if key.modifiers.contains(event::KeyModifiers::CONTROL) => {
+                self.message_scroll.line_down();
+            }
+            KeyCode::Up if key.modifiers.contains(event::KeyModifiers::ALT) => {
+                self.move_selected_channel(true).await;
+            }
+            KeyCode::Down if key.modifiers.contains(event::KeyModifiers::ALT) => {
+                self.move_selected_channel(false).await;
+            }
+            KeyCode::PageUp => {
+                self.message_scroll.page_up();
+            }
+            KeyCode::PageDown => {
+                self.message_scroll.page_down();
+            }
             KeyCode::Up => {
                 self.select_previous_channel().await?;
             }
             KeyCode::Down => {
                 self.select_next_channel().await?;
             }
+            KeyCode::Enter if self.editing.is_some() => {
+                self.submit_edit().await?;
+            }
+            KeyCode::Enter if key.modifiers.contains(event::KeyModifiers::ALT) => {
+                self.send_message(Priority::Urgent).await?;
+            }
             KeyCode::Enter => {
-                self.send_message().await?;
+                self.send_message(Priority::Normal).await?;
             }
             KeyCode::Backspace => {
                 self.input.pop();
@@ -549,449 +1790,2796 @@ impl App {
         Ok(false)
     }
 
-    async fn handle_connect_peer_input(&mut self, key: KeyEvent) -> Result<bool> {
+    /// Start renaming the selected channel, if we're a member
+    fn open_rename_editor(&mut self) {
+        if self.read_only {
+            return;
+        }
+        let Some(channel) = self.selected_channel.and_then(|idx| self.channels.get(idx)) else {
+            return;
+        };
+        let refusal = if self.is_personal_channel(channel) {
+            Some("Your personal channel can't be renamed")
+        } else if !channel.members.contains(&self.peer_id) {
+            Some("Only members can rename the channel")
+        } else {
+            None
+        };
+        if let Some(refusal) = refusal {
+            self.notifications.push(Notification::new(refusal.to_string(), NotificationLevel::Error));
+            return;
+        }
+
+        self.rename_input = channel.get_name().clone();
+        self.mode = AppMode::RenameChannel;
+    }
+
+    async fn handle_rename_input(&mut self, key: KeyEvent) -> Result<()> {
         match key.code {
             KeyCode::Esc => {
-                // Cancel peer connection
                 self.mode = AppMode::Normal;
-                self.connect_peer_input.clear();
+                self.rename_input.clear();
             }
             KeyCode::Enter => {
-                // Connect to the peer
-                if !self.connect_peer_input.is_empty() {
-                    self.connect_to_peer().await?;
-                }
                 self.mode = AppMode::Normal;
-                self.connect_peer_input.clear();
+                let name = std::mem::take(&mut self.rename_input);
+                self.rename_channel(name.trim().to_string()).await?;
             }
             KeyCode::Backspace => {
-                self.connect_peer_input.pop();
+                self.rename_input.pop();
             }
             KeyCode::Char(c) => {
-                self.connect_peer_input.push(c);
+                self.rename_input.push(c);
             }
             _ => {}
         }
 
-        Ok(false)
-    }
-
-    async fn connect_to_peer(&mut self) -> Result<()> {
-        // Parse the multiaddr and send connect command
-        if let Ok(addr) = self.connect_peer_input.parse() {
-            self.network_command_tx.send(NetworkCommand::ConnectToPeer(addr))?;
-            tracing::info!("Connecting to peer at {}", self.connect_peer_input);
-        } else {
-            tracing::warn!("Invalid multiaddr: {}", self.connect_peer_input);
-        }
-
         Ok(())
     }
 
-    async fn create_channel_from_modal(&mut self) -> Result<()> {
-        let channel = Channel::new(self.new_channel_input.clone(), self.peer_id);
-        let channel_id = channel.id;
-        self.storage.store_channel(&channel).await?;
-        self.channels = self.storage.get_all_channels().await?;
-
-        // Announce the new channel to the network
-        if let Err(e) = self.network_command_tx.send(NetworkCommand::AnnounceChannel(channel.clone())) {
-            tracing::error!("Failed to announce channel: {}", e);
-        } else {
-            tracing::info!("Announced new channel: {}", channel.get_name());
+    /// Rename the selected channel and share the change
+    ///
+    /// The name is an LWW register, so if another member renames it at the
+    /// same time every peer settles on the later rename.
+    async fn rename_channel(&mut self, name: String) -> Result<()> {
+        let name = match nickname::normalize_nickname(&name) {
+            Ok(name) => name,
+            Err(NicknameError::Empty) => return Ok(()),
+            Err(e) => {
+                self.notifications.push(Notification::new(
+                    format!("Can't rename the channel: {}", e),
+                    NotificationLevel::Error,
+                ));
+                return Ok(());
+            }
+        };
+        let Some(channel) = self.selected_channel.and_then(|idx| self.channels.get_mut(idx)) else {
+            return Ok(());
+        };
+        if *channel.get_name() == name {
+            return Ok(());
         }
 
-        // Find and select the newly created channel
-        if let Some(index) = self.channels.iter().position(|c| c.id == channel_id) {
-            self.selected_channel = Some(index);
-            self.channel_list_state.select(Some(index));
-            self.load_messages().await?;
+        let before = channel.clone();
+        channel.set_name(name);
+        let channel = channel.clone();
+        self.node.storage.store_channel(&channel).await?;
+        if let Err(e) = self.network_command_tx.send(NetworkCommand::BroadcastChannelUpdate(channel.clone())) {
+            tracing::error!("Failed to broadcast channel update: {}", e);
         }
+        for event in channel.system_events_since(&before) {
+            self.record_system_event(channel.id, event).await?;
+        }
+        self.notifications.push(Notification::new(
+            format!("Channel renamed to {}", channel.get_name()),
+            NotificationLevel::Success,
+        ));
 
         Ok(())
     }
 
-    async fn select_next_channel(&mut self) -> Result<()> {
-        if self.channels.is_empty() {
-            return Ok(());
+    /// Start editing the selected channel's description, if we're a member
+    fn open_description_editor(&mut self) {
+        if self.read_only {
+            return;
         }
-
-        let next = match self.selected_channel {
-            Some(i) => {
-                if i >= self.channels.len() - 1 {
-                    0
-                } else {
-                    i + 1
-                }
-            }
-            None => 0,
+        let Some(channel) = self.selected_channel.and_then(|idx| self.channels.get(idx)) else {
+            return;
         };
+        if !channel.members.contains(&self.peer_id) {
+            self.notifications.push(Notification::new(
+                "Only members can change the channel description".to_string(),
+                NotificationLevel::Error,
+            ));
+            return;
+        }
 
-        self.selected_channel = Some(next);
-        self.channel_list_state.select(Some(next));
-        self.load_messages().await?;
+        self.description_input = channel.description().to_string();
+        self.mode = AppMode::EditDescription;
+    }
+
+    async fn handle_description_input(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Esc => {
+                self.mode = AppMode::Normal;
+                self.description_input.clear();
+            }
+            KeyCode::Enter => {
+                self.mode = AppMode::Normal;
+                let description = std::mem::take(&mut self.description_input);
+                self.set_channel_description(description.trim().to_string()).await?;
+            }
+            KeyCode::Backspace => {
+                self.description_input.pop();
+            }
+            KeyCode::Char(c) => {
+                self.description_input.push(c);
+            }
+            _ => {}
+        }
 
         Ok(())
     }
 
-    async fn select_previous_channel(&mut self) -> Result<()> {
-        if self.channels.is_empty() {
+    /// Set the selected channel's description and share the change
+    async fn set_channel_description(&mut self, description: String) -> Result<()> {
+        let description = nickname::normalize_description(&description);
+        let Some(channel) = self.selected_channel.and_then(|idx| self.channels.get_mut(idx)) else {
             return Ok(());
-        }
-
-        let prev = match self.selected_channel {
-            Some(i) => {
-                if i == 0 {
-                    self.channels.len() - 1
-                } else {
-                    i - 1
-                }
-            }
-            None => self.channels.len() - 1,
         };
+        if channel.description() == description || !channel.set_description(description, &self.peer_id) {
+            return Ok(());
+        }
 
-        self.selected_channel = Some(prev);
-        self.channel_list_state.select(Some(prev));
-        self.load_messages().await?;
+        let channel = channel.clone();
+        self.node.storage.store_channel(&channel).await?;
+        if let Err(e) = self.network_command_tx.send(NetworkCommand::BroadcastChannelUpdate(channel)) {
+            tracing::error!("Failed to broadcast channel update: {}", e);
+        }
+        self.notifications.push(Notification::new(
+            "Channel description updated".to_string(),
+            NotificationLevel::Success,
+        ));
 
         Ok(())
     }
 
-    async fn load_messages(&mut self) -> Result<()> {
-        if let Some(idx) = self.selected_channel {
-            if let Some(channel) = self.channels.get(idx) {
-                // Phase 4: Use DAG ordering instead of raw storage order
-                self.messages = self.dag.get_ordered_messages(&channel.id);
-            }
+    /// Start editing the selected channel's notification keywords
+    fn open_keywords_editor(&mut self) {
+        if self.read_only {
+            return;
         }
+        let Some(channel) = self.selected_channel.and_then(|idx| self.channels.get(idx)) else {
+            return;
+        };
 
-        Ok(())
+        self.keywords_input = self.channel_keywords.get(&channel.id).map(|k| k.join(", ")).unwrap_or_default();
+        self.mode = AppMode::EditKeywords;
     }
 
-    // Phase 4: Helper to reload current channel messages
-    async fn reload_current_channel_messages(&mut self) -> Result<()> {
-        if let Some(idx) = self.selected_channel {
-            if let Some(channel) = self.channels.get(idx) {
-                self.messages = self.dag.get_ordered_messages(&channel.id);
+    async fn handle_keywords_input(&mut self, key: KeyEvent) -> Result<()> {
+        match key.code {
+            KeyCode::Esc => {
+                self.mode = AppMode::Normal;
+                self.keywords_input.clear();
+            }
+            KeyCode::Enter => {
+                self.mode = AppMode::Normal;
+                let input = std::mem::take(&mut self.keywords_input);
+                self.set_channel_keywords(alerts::parse_keywords(&input)).await?;
+            }
+            KeyCode::Backspace => {
+                self.keywords_input.pop();
             }
+            KeyCode::Char(c) => {
+                self.keywords_input.push(c);
+            }
+            _ => {}
         }
+
         Ok(())
     }
 
-    async fn send_message(&mut self) -> Result<()> {
-        if self.input.is_empty() {
+    /// Set the selected channel's notification keywords (local only)
+    async fn set_channel_keywords(&mut self, keywords: Vec<String>) -> Result<()> {
+        let Some(channel) = self.selected_channel.and_then(|idx| self.channels.get(idx)) else {
             return Ok(());
+        };
+        let channel_id = channel.id;
+        let message = if keywords.is_empty() {
+            format!("No keywords for {}", channel.get_name())
+        } else {
+            format!("Keywords for {}: {}", channel.get_name(), keywords.join(", "))
+        };
+
+        self.node.storage.set_channel_keywords(channel_id, &keywords).await?;
+        if keywords.is_empty() {
+            self.channel_keywords.remove(&channel_id);
+        } else {
+            self.channel_keywords.insert(channel_id, keywords);
         }
+        self.notifications.push(Notification::new(message, NotificationLevel::Success));
 
-        if let Some(idx) = self.selected_channel {
-            if let Some(channel) = self.channels.get(idx) {
-                // Increment clocks
-                self.lamport_clock += 1;
-                self.vector_clock.increment(self.peer_id);
-
-                // Phase 4: Get DAG heads to set as parents
-                let parent_hashes = self.dag.get_heads(&channel.id);
-
-                let mut message = Message::new(
-                    channel.id,
-                    self.peer_id,
-                    MessageContent {
-                        text: self.input.clone(),
-                    },
-                    self.vector_clock.clone(),
-                    self.lamport_clock,
-                );
-                message.parent_hashes = parent_hashes;
-
-                self.storage.store_message(&message).await?;
+        Ok(())
+    }
 
-                // Phase 4: Add message to DAG
-                if let Err(e) = self.dag.add_message(message.clone()) {
-                    tracing::warn!("Failed to add message to DAG: {}", e);
+    async fn handle_connect_peer_input(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Esc => {
+                // Cancel peer connection
+                self.mode = AppMode::Normal;
+                self.connect_peer_input.clear();
+            }
+            KeyCode::Enter => {
+                // Connect to the peer
+                if !self.connect_peer_input.is_empty() {
+                    self.connect_to_peer().await?;
                 }
+                self.mode = AppMode::Normal;
+                self.connect_peer_input.clear();
+            }
+            KeyCode::Backspace => {
+                self.connect_peer_input.pop();
+            }
+            KeyCode::Char(c) => {
+                self.connect_peer_input.push(c);
+            }
+            _ => {}
+        }
+
+        Ok(false)
+    }
 
-                // Reload messages in DAG order
-                self.reload_current_channel_messages().await?;
+    fn handle_whoami_input(&mut self, key: KeyEvent) {
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.whoami.select_previous(),
+            KeyCode::Down | KeyCode::Char('j') => self.whoami.select_next(),
+            KeyCode::Enter | KeyCode::Char('y') => {
+                if let Some(value) = self.whoami.selected_value().map(str::to_string) {
+                    self.copy_text(value, "Copied to clipboard");
+                }
+            }
+            _ => self.mode = AppMode::Normal,
+        }
+    }
 
-                // Broadcast to network
-                self.network_command_tx.send(NetworkCommand::BroadcastMessage(message))?;
+    async fn handle_quick_switch_input(&mut self, key: KeyEvent) -> Result<bool> {
+        let matches = self.quick_switch_matches();
 
-                self.input.clear();
+        match key.code {
+            KeyCode::Esc => {
+                // Cancel, leaving the current channel selected
+                self.mode = AppMode::Normal;
+                self.quick_switch_input.clear();
+            }
+            KeyCode::Enter => {
+                // Jump to the highlighted channel; with no matches, keep typing
+                if let Some(&index) = matches.get(self.quick_switch_selected) {
+                    self.select_channel(index).await?;
+                    self.mode = AppMode::Normal;
+                    self.quick_switch_input.clear();
+                }
+            }
+            KeyCode::Up => {
+                if !matches.is_empty() {
+                    self.quick_switch_selected =
+                        (self.quick_switch_selected + matches.len() - 1) % matches.len();
+                }
+            }
+            KeyCode::Down => {
+                if !matches.is_empty() {
+                    self.quick_switch_selected = (self.quick_switch_selected + 1) % matches.len();
+                }
+            }
+            KeyCode::Backspace => {
+                self.quick_switch_input.pop();
+                self.quick_switch_selected = 0;
+            }
+            KeyCode::Char(c) => {
+                self.quick_switch_input.push(c);
+                self.quick_switch_selected = 0;
             }
+            _ => {}
         }
 
-        Ok(())
+        Ok(false)
     }
 
-    fn ui(&mut self, f: &mut Frame) {
-        // Main layout: content area + status bar at bottom
-        let main_chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Min(3), Constraint::Length(1)])
-            .split(f.area());
+    /// Channel indices matching the quick switcher query, best first
+    fn quick_switch_matches(&self) -> Vec<usize> {
+        let mut matches = fuzzy::rank_matches(
+            &self.quick_switch_input,
+            self.channels.iter().map(|c| c.get_name().as_str()),
+        );
+        matches.truncate(MAX_QUICK_SWITCH_RESULTS);
+        matches
+    }
 
-        // Content area with horizontal split
-        let chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([Constraint::Percentage(25), Constraint::Percentage(75)])
-            .split(main_chunks[0]);
+    async fn select_channel(&mut self, index: usize) -> Result<()> {
+        self.selected_channel = Some(index);
+        self.channel_list_state.select(Some(index));
+        self.load_messages().await
+    }
 
-        // Left panel: channel list
-        self.render_channel_list(f, chunks[0]);
+    async fn connect_to_peer(&mut self) -> Result<()> {
+        // Parse the multiaddr and send connect command
+        if let Ok(addr) = self.connect_peer_input.parse() {
+            self.network_command_tx.send(NetworkCommand::ConnectToPeer(addr))?;
+            tracing::info!("Connecting to peer at {}", self.connect_peer_input);
+        } else {
+            tracing::warn!("Invalid multiaddr: {}", self.connect_peer_input);
+        }
 
-        // Right panel: messages and input
-        let right_chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([Constraint::Min(3), Constraint::Length(3)])
-            .split(chunks[1]);
+        Ok(())
+    }
 
-        self.render_messages(f, right_chunks[0]);
-        self.render_input(f, right_chunks[1]);
+    /// Whether a connection event should notify at the configured level
+    fn shows_connection(&self, notice: ConnectionNotice) -> bool {
+        alerts::should_notify_connection(self.connection_notices, notice)
+    }
 
-        // Status bar at bottom
-        self.render_status_bar(f, main_chunks[1]);
+    /// Reload the channel list from storage, keeping the selected channel selected
+    async fn refresh_channels(&mut self) -> Result<()> {
+        let previous = self.channel_ids();
+        self.channels = self.node.storage.get_all_channels().await?;
+        self.arrange_channels();
+        self.resync_selection(&previous).await?;
 
-        // Render modals on top
-        match self.mode {
-            AppMode::Help => {
-                self.render_help(f, f.area());
+        self.join_channel_topics()
+    }
+
+    /// Join the presence topics of the channels we're a member of, follow or
+    /// have open, leaving any others joined before
+    ///
+    /// Joining a topic tells its channel's members we're listening, so the
+    /// channels we've only heard of stay unjoined until opened. Safe to
+    /// repeat whenever the channel list or the selection changes.
+    fn join_channel_topics(&mut self) -> Result<()> {
+        if self.read_only {
+            return Ok(());
+        }
+        let open = self.selected_channel.and_then(|idx| self.channels.get(idx)).map(|c| c.id);
+        let wanted: HashSet<ChannelId> = self
+            .channels
+            .iter()
+            .filter(|c| {
+                c.members.contains(&self.peer_id) || self.followed_channels.contains(&c.id) || Some(c.id) == open
+            })
+            .map(|c| c.id)
+            .collect();
+        for channel_id in wanted.difference(&self.presence_topics) {
+            self.network_command_tx.send(NetworkCommand::JoinChannel(*channel_id))?;
+        }
+        for channel_id in self.presence_topics.difference(&wanted) {
+            self.network_command_tx.send(NetworkCommand::LeaveChannel(*channel_id))?;
+            self.channel_listeners.remove(channel_id);
+        }
+        self.presence_topics = wanted;
+        Ok(())
+    }
+
+    /// Ask peers for the state of every channel only known as a placeholder
+    fn request_placeholder_states(&self) {
+        for channel in self.channels.iter().filter(|c| c.has_unknown_membership()) {
+            if let Err(e) = self.network_command_tx.send(NetworkCommand::RequestChannelState(channel.id)) {
+                tracing::error!("Failed to request channel state: {}", e);
             }
-            AppMode::NewChannel => {
-                self.render_new_channel_modal(f, f.area());
+        }
+    }
+
+    /// Ask peers for the missing parents of messages held back as orphans
+    ///
+    /// Stored history whose parents never arrived loads as orphans, hidden
+    /// until the parents do.
+    fn request_missing_parents(&mut self) {
+        for channel_id in self.node.dag.channels_with_orphans() {
+            if let Err(e) = self.gossip_manager.detect_and_request_missing(channel_id, &self.node.dag) {
+                tracing::error!("Failed to request missing parents: {}", e);
             }
-            AppMode::ConnectPeer => {
-                self.render_connect_peer_modal(f, f.area());
+        }
+    }
+
+    /// Put the channel list in the configured order
+    fn arrange_channels(&mut self) {
+        if self.sort_channels_by_activity {
+            sort_by_activity(&mut self.channels, &self.channel_activity);
+        } else {
+            self.channel_order.apply(&mut self.channels);
+        }
+    }
+
+    /// Move the selected channel one place up or down the list and remember the order
+    async fn move_selected_channel(&mut self, up: bool) {
+        if self.sort_channels_by_activity {
+            self.notifications.push(Notification::new(
+                "Channels are sorted by activity (BURROW_SORT_CHANNELS_BY_ACTIVITY)".to_string(),
+                NotificationLevel::Info,
+            ));
+            return;
+        }
+        let Some(index) = self.selected_channel else {
+            return;
+        };
+        let previous = self.channel_ids();
+        if self.channel_order.move_channel(&mut self.channels, index, up).is_none() {
+            return;
+        }
+
+        self.reselect(&previous);
+        if self.read_only {
+            return;
+        }
+        if let Err(e) = self.node.storage.store_setting(CHANNEL_ORDER_SETTING, &self.channel_order.to_setting()).await {
+            tracing::error!("Failed to save channel order: {}", e);
+        }
+    }
+
+    /// Count a newly added message towards its channel's activity
+    fn record_activity(&mut self, message: &Message) {
+        self.channel_activity
+            .entry(message.channel_id)
+            .or_default()
+            .record(message.created_at());
+
+        if self.sort_channels_by_activity {
+            // Reordering keeps every channel, so the selection never changes channel
+            let previous = self.channel_ids();
+            sort_by_activity(&mut self.channels, &self.channel_activity);
+            self.reselect(&previous);
+        }
+    }
+
+    async fn create_channel_from_modal(&mut self) -> Result<()> {
+        let name = match nickname::normalize_nickname(&self.new_channel_input) {
+            Ok(name) => name,
+            Err(NicknameError::Empty) => return Ok(()),
+            Err(e) => {
+                self.notifications.push(Notification::new(
+                    format!("Can't create the channel: {}", e),
+                    NotificationLevel::Error,
+                ));
+                return Ok(());
             }
-            AppMode::Normal => {}
+        };
+        let channel = Channel::new(name, self.peer_id);
+        let channel_id = channel.id;
+        self.node.storage.store_channel(&channel).await?;
+        self.refresh_channels().await?;
+
+        // Announce the new channel to the network
+        if let Err(e) = self.network_command_tx.send(NetworkCommand::AnnounceChannel(channel.clone())) {
+            tracing::error!("Failed to announce channel: {}", e);
+        } else {
+            tracing::info!("Announced new channel: {}", channel.get_name());
         }
 
-        // Render notification on top of everything
-        if let Some(ref notif) = self.notification {
-            self.render_notification(f, f.area(), notif);
+        // Find and select the newly created channel
+        if let Some(index) = self.channels.iter().position(|c| c.id == channel_id) {
+            self.selected_channel = Some(index);
+            self.channel_list_state.select(Some(index));
+            self.load_messages().await?;
         }
+
+        Ok(())
     }
 
-    fn render_channel_list(&mut self, f: &mut Frame, area: Rect) {
-        let items: Vec<ListItem> = self
-            .channels
-            .iter()
-            .map(|channel| {
-                use crate::types::ChannelType;
+    async fn select_next_channel(&mut self) -> Result<()> {
+        if self.channels.is_empty() {
+            return Ok(());
+        }
 
-                // Choose icon based on channel type
-                let icon = match channel.channel_type {
-                    ChannelType::PeerToPeer => "@",
-                    ChannelType::Group => "#",
-                };
+        let next = match self.selected_channel {
+            Some(i) => {
+                if i >= self.channels.len() - 1 {
+                    0
+                } else {
+                    i + 1
+                }
+            }
+            None => 0,
+        };
 
-                // Show member count for groups
-                let members = channel.get_members();
-                let member_info = if channel.channel_type == ChannelType::Group && !members.is_empty() {
-                    format!(" ({})", members.len())
+        self.selected_channel = Some(next);
+        self.channel_list_state.select(Some(next));
+        self.load_messages().await?;
+
+        Ok(())
+    }
+
+    async fn select_previous_channel(&mut self) -> Result<()> {
+        if self.channels.is_empty() {
+            return Ok(());
+        }
+
+        let prev = match self.selected_channel {
+            Some(i) => {
+                if i == 0 {
+                    self.channels.len() - 1
                 } else {
-                    String::new()
-                };
+                    i - 1
+                }
+            }
+            None => self.channels.len() - 1,
+        };
 
-                let content = Line::from(vec![Span::styled(
-                    format!("{} {}{}", icon, channel.get_name(), member_info),
-                    Style::default().fg(Color::White),
-                )]);
-                ListItem::new(content)
-            })
-            .collect();
+        self.selected_channel = Some(prev);
+        self.channel_list_state.select(Some(prev));
+        self.load_messages().await?;
 
-        let peer_count = self.peer_manager.peer_count();
-        let title = if peer_count > 0 {
-            format!(" Channels ({} peers connected) ", peer_count)
-        } else {
-            " Channels (no peers) ".to_string()
+        Ok(())
+    }
+
+    /// Check whether a channel has all the messages we know of
+    ///
+    /// Called for every listed channel on every frame, so both halves are
+    /// kept up to date as messages arrive rather than worked out here.
+    fn is_channel_synced(&self, channel_id: &ChannelId) -> bool {
+        !self.node.dag.has_orphans(channel_id) && self.gossip_manager.is_channel_synced(channel_id)
+    }
+
+    /// Handle keys aimed at the focused message pane, returning whether the key was used
+    ///
+    /// Ctrl shortcuts fall through to normal handling so they work from
+    /// either pane; plain typing is swallowed rather than landing in the input.
+    async fn handle_message_pane_key(&mut self, key: &KeyEvent) -> Result<bool> {
+        if key.modifiers.contains(event::KeyModifiers::CONTROL) {
+            return Ok(false);
+        }
+
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => self.message_cursor.move_up(&self.messages),
+            KeyCode::Down | KeyCode::Char('j') => self.message_cursor.move_down(&self.messages),
+            KeyCode::Char('y') => self.copy_selected_message(false),
+            KeyCode::Char('Y') => self.copy_selected_message(true),
+            KeyCode::Char('r') => self.reply_to_selected_message(),
+            KeyCode::Char('R') => self.resend_selected_message()?,
+            KeyCode::Char('p') => self.toggle_pin_selected_message().await?,
+            KeyCode::Char('P') => self.open_pinned(),
+            KeyCode::Char('o') => self.open_selected_image().await?,
+            KeyCode::Esc | KeyCode::Tab => {
+                self.focus = Focus::Input;
+                self.message_cursor.clear();
+            }
+            KeyCode::Char(_) | KeyCode::Enter | KeyCode::Backspace => {}
+            _ => return Ok(false),
+        }
+
+        Ok(true)
+    }
+
+    /// The message per-message actions apply to: the cursor's, else the newest
+    fn target_message(&self) -> Option<&Message> {
+        match self.message_cursor.selected() {
+            Some(id) => self.messages.iter().find(|m| m.id == id),
+            None => self.messages.last(),
+        }
+    }
+
+    /// Copy the selected (or newest) message's text to the system clipboard
+    ///
+    /// With `with_header`, the author and time are prepended. If the clipboard
+    /// can't be reached (e.g. over SSH) the text is shown in an overlay instead.
+    fn copy_selected_message(&mut self, with_header: bool) {
+        let Some(message) = self.target_message() else {
+            return;
         };
 
-        let list = List::new(items)
-            .block(
-                Block::default()
-                    .title(title)
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::Cyan)),
-            )
-            .highlight_style(
-                Style::default()
-                    .bg(Color::DarkGray)
-                    .add_modifier(Modifier::BOLD),
+        let text = if with_header {
+            format!(
+                "[{}] {}: {}",
+                timestamps::date_time(message.created_at()),
+                message.author.short(),
+                message.current_content().text
             )
-            .highlight_symbol("► ");
+        } else {
+            message.current_content().text.clone()
+        };
+
+        self.copy_text(text, "Message copied to clipboard");
+    }
+
+    /// Open the selected (or newest) message's image in the system viewer
+    ///
+    /// The image is written to a temporary file for the viewer. One that
+    /// hasn't arrived is asked for again from every connected peer.
+    async fn open_selected_image(&mut self) -> Result<()> {
+        let Some(image) = self.target_message().and_then(|m| m.current_content().image_ref()).copied() else {
+            self.notifications.push(Notification::new("No image selected".to_string(), NotificationLevel::Info));
+            return Ok(());
+        };
+
+        let data = match self.node.storage.get_attachment(&image.hash).await {
+            Ok(data) => data,
+            Err(e) => {
+                tracing::warn!("Failed to read attachment: {}", e);
+                None
+            }
+        };
+        let Some(data) = data else {
+            let notice = if self.read_only {
+                "Image wasn't received before this archive was made"
+            } else if self.attachments.should_request(&image, Instant::now()) {
+                let peers = self.peer_manager.get_all_peers().iter().map(|peer| peer.peer_id).collect();
+                self.network_command_tx.send(NetworkCommand::RequestAttachment { hash: image.hash, peers })?;
+                "Image not received yet; asking connected peers"
+            } else {
+                "Image not received yet"
+            };
+            self.notifications.push(Notification::new(notice.to_string(), NotificationLevel::Info));
+            return Ok(());
+        };
+
+        let name: String = image.hash[..8].iter().map(|b| format!("{:02x}", b)).collect();
+        let path = std::env::temp_dir().join(format!("burrow-{}.png", name));
+        self.notifications.push(match std::fs::write(&path, &data).and_then(|()| links::open_file(&path)) {
+            Ok(()) => Notification::new("Opened image".to_string(), NotificationLevel::Success),
+            Err(e) => {
+                tracing::warn!("Failed to open image {:?}: {}", path, e);
+                Notification::new(format!("Failed to open image: {}", e), NotificationLevel::Error)
+            }
+        });
+        Ok(())
+    }
+
+    /// Copy text to the system clipboard, showing it in an overlay if the
+    /// clipboard can't be reached
+    fn copy_text(&mut self, text: String, done: &str) {
+        match self.clipboard.copy(&text) {
+            Ok(()) => {
+                self.notifications.push(Notification::new(done.to_string(), NotificationLevel::Success));
+            }
+            Err(e) => {
+                tracing::info!("Clipboard unavailable, showing text instead: {}", e);
+                self.copy_fallback_text = text;
+                self.mode = AppMode::CopyFallback;
+            }
+        }
+    }
+
+    /// Record identity details that only `main` knows, for the whoami panel
+    pub fn describe_identity(&mut self, identity: &Identity, public_bundle: PublicBundle, data_dir: PathBuf) {
+        self.identity_fingerprint = Some(identity.fingerprint());
+        self.public_bundle = Some(public_bundle);
+        self.data_dir = Some(data_dir);
+    }
+
+    /// Show the full identity details, refreshed from the current addresses
+    fn open_whoami(&mut self) {
+        self.whoami = WhoAmI::new(
+            &self.libp2p_peer_id,
+            &self.peer_id,
+            self.identity_fingerprint.as_deref(),
+            self.public_bundle.as_ref().map(|bundle| bundle.to_json()).as_deref(),
+            &self.listen_addrs,
+            &self.observed_addrs,
+            self.data_dir.as_deref(),
+        );
+        self.mode = AppMode::WhoAmI;
+    }
+
+    /// Quote the selected message in the next message sent, and return to the input
+    fn reply_to_selected_message(&mut self) {
+        if self.read_only {
+            return;
+        }
+        let Some(message_id) = self.message_cursor.selected() else {
+            return;
+        };
+
+        self.replying_to = Some(message_id);
+        self.focus = Focus::Input;
+        self.message_cursor.clear();
+    }
+
+    /// Load the selected (or newest) message into the input to change its text
+    ///
+    /// Only our own messages can be edited. The draft being typed is put
+    /// aside and comes back once the edit is sent or cancelled.
+    fn edit_selected_message(&mut self) {
+        if self.read_only || self.editing.is_some() {
+            return;
+        }
+        let Some(message) = self.target_message() else {
+            return;
+        };
+        if !message.editable_by(&self.peer_id) {
+            self.notifications.push(Notification::new(
+                "Only your own messages can be edited".to_string(),
+                NotificationLevel::Error,
+            ));
+            return;
+        }
+
+        let (message_id, text) = (message.id, message.current_content().text.clone());
+        let draft = std::mem::replace(&mut self.input, text);
+        self.editing = Some((message_id, draft));
+        self.replying_to = None;
+        self.focus = Focus::Input;
+        self.message_cursor.clear();
+    }
+
+    /// Leave edit mode, putting the stashed draft back in the input
+    fn cancel_edit(&mut self) {
+        if let Some((_, draft)) = self.editing.take() {
+            self.input = draft;
+        }
+    }
+
+    /// Replace the edited message's text with the input and tell peers
+    async fn submit_edit(&mut self) -> Result<()> {
+        let Some((message_id, _)) = self.editing else {
+            return Ok(());
+        };
+        let text = self.node.content_pipeline.outgoing(MessageContent::new(self.input.clone())).text;
+        let unchanged = self.node.dag.get_message(&message_id).is_some_and(|m| m.current_content().text == text);
+        if !text.is_empty() && !unchanged {
+            let edited_at = self.clock.tick();
+            if let Err(e) = self.node.edit_message(message_id, &text, edited_at).await {
+                self.notifications.push(Notification::new(
+                    format!("Failed to edit message: {}", e),
+                    NotificationLevel::Error,
+                ));
+                return Ok(());
+            }
+            self.reload_current_channel_messages().await?;
+        }
+
+        self.cancel_edit();
+        Ok(())
+    }
+
+    /// Send the selected message again if it hasn't reached anyone yet
+    ///
+    /// The stored message goes out unchanged, keeping its ID and place in
+    /// the DAG; peers that already have it drop the copy. Whether it got
+    /// anywhere comes back later: gossipsub refuses bytes it published
+    /// recently, which the network reports as undelivered.
+    fn resend_selected_message(&mut self) -> Result<()> {
+        if self.read_only {
+            return Ok(());
+        }
+        let Some(message) = self.message_cursor.selected().and_then(|id| self.node.dag.get_message(&id)).cloned() else {
+            return Ok(());
+        };
+        let Some(channel) = self.channels.iter().find(|c| c.id == message.channel_id) else {
+            return Ok(());
+        };
+
+        if let Err(refusal) = self.delivery.retry(&message.id) {
+            let text = match refusal {
+                ResendRefusal::NotTracked => "Only your own messages sent this session can be resent",
+                ResendRefusal::AlreadyDelivered => "Message already reached a peer",
+            };
+            self.notifications.push(Notification::new(text.to_string(), NotificationLevel::Info));
+            return Ok(());
+        }
+
+        self.node.send(channel, &message)?;
+        self.notifications.push(Notification::new("Resending message".to_string(), NotificationLevel::Info));
+        Ok(())
+    }
+
+    /// Pin the selected message in its channel, or unpin it if it's pinned
+    async fn toggle_pin_selected_message(&mut self) -> Result<()> {
+        let Some(message_id) = self.message_cursor.selected() else {
+            return Ok(());
+        };
+        let pinned = self
+            .selected_channel
+            .and_then(|idx| self.channels.get(idx))
+            .is_some_and(|c| c.is_pinned(&message_id));
+        self.set_pinned(message_id, !pinned).await
+    }
+
+    /// Pin or unpin a message in the selected channel and share the change
+    async fn set_pinned(&mut self, message_id: MessageId, pinned: bool) -> Result<()> {
+        if self.read_only {
+            return Ok(());
+        }
+        let Some(channel) = self.selected_channel.and_then(|idx| self.channels.get_mut(idx)) else {
+            return Ok(());
+        };
+
+        let message = if pinned {
+            channel.pin_message(message_id);
+            "Message pinned (P to list pins)"
+        } else {
+            channel.unpin_message(&message_id);
+            "Message unpinned"
+        };
+
+        let channel = channel.clone();
+        self.node.storage.store_channel(&channel).await?;
+        if let Err(e) = self.network_command_tx.send(NetworkCommand::BroadcastChannelUpdate(channel)) {
+            tracing::error!("Failed to broadcast channel update: {}", e);
+        }
+        self.notifications.push(Notification::new(message.to_string(), NotificationLevel::Info));
+
+        Ok(())
+    }
+
+    /// Show the selected channel's pinned messages
+    ///
+    /// Pins can arrive before the messages they point at; those are
+    /// requested from peers and fill in once they arrive.
+    fn open_pinned(&mut self) {
+        let Some(channel) = self.selected_channel.and_then(|idx| self.channels.get(idx)) else {
+            return;
+        };
+
+        let missing: Vec<MessageId> = channel
+            .pinned_messages()
+            .into_iter()
+            .filter(|id| !self.node.dag.has_message(id))
+            .collect();
+        if !missing.is_empty() && !self.read_only {
+            if let Err(e) = self.gossip_manager.request_messages(channel.id, missing) {
+                tracing::error!("Failed to request pinned messages: {}", e);
+            }
+        }
+
+        self.pinned_selected = 0;
+        self.mode = AppMode::Pinned;
+    }
+
+    async fn handle_pinned_input(&mut self, key: KeyEvent) -> Result<()> {
+        let pinned = self
+            .selected_channel
+            .and_then(|idx| self.channels.get(idx))
+            .map(|c| c.pinned_messages())
+            .unwrap_or_default();
+
+        match key.code {
+            KeyCode::Up | KeyCode::Char('k') => {
+                self.pinned_selected = self.pinned_selected.saturating_sub(1);
+            }
+            KeyCode::Down | KeyCode::Char('j') => {
+                self.pinned_selected = (self.pinned_selected + 1).min(pinned.len().saturating_sub(1));
+            }
+            KeyCode::Enter => {
+                // Jump to the message in the conversation, if it's there
+                let Some(&message_id) = pinned.get(self.pinned_selected) else {
+                    return Ok(());
+                };
+                if self.message_cursor.select(&self.messages, message_id) {
+                    self.focus = Focus::Messages;
+                    self.mode = AppMode::Normal;
+                } else {
+                    self.notifications.push(Notification::new(
+                        "That message hasn't arrived yet".to_string(),
+                        NotificationLevel::Info,
+                    ));
+                }
+            }
+            KeyCode::Char('p') => {
+                let Some(&message_id) = pinned.get(self.pinned_selected) else {
+                    return Ok(());
+                };
+                self.set_pinned(message_id, false).await?;
+                self.pinned_selected = self.pinned_selected.min(pinned.len().saturating_sub(2));
+            }
+            _ => self.mode = AppMode::Normal,
+        }
+
+        Ok(())
+    }
+
+    /// Hide the selected (or newest) message in the current channel from the local view
+    async fn hide_selected_message(&mut self) -> Result<()> {
+        let Some(message_id) = self.target_message().map(|m| m.id) else {
+            return Ok(());
+        };
+
+        self.node.storage.hide_message(message_id).await?;
+        self.hidden_messages.insert(message_id);
+        self.reload_current_channel_messages().await?;
+
+        self.notifications.push(Notification::new(
+            "Message hidden locally (Ctrl+U to unhide all)".to_string(),
+            NotificationLevel::Info,
+        ));
+
+        Ok(())
+    }
+
+    /// Restore every locally hidden message
+    async fn unhide_all_messages(&mut self) -> Result<()> {
+        let restored = self.node.storage.unhide_all_messages().await?;
+        self.hidden_messages.clear();
+        self.reload_current_channel_messages().await?;
+
+        self.notifications.push(Notification::new(
+            format!("Unhid {} messages", restored),
+            NotificationLevel::Info,
+        ));
+
+        Ok(())
+    }
+
+    /// All links in the current channel's messages, in display order, with
+    /// the index of the message each is in
+    ///
+    /// Found in the same stripped text the message pane renders, so indices
+    /// line up with the highlighted link. System messages render without link
+    /// styling, so their links aren't counted.
+    fn message_links(&self) -> Vec<(usize, String)> {
+        self.messages
+            .iter()
+            .enumerate()
+            .filter(|(_, msg)| !msg.content.is_system())
+            .flat_map(|(index, msg)| {
+                let text = strip_control(&msg.current_content().text);
+                links::find_links(&text)
+                    .into_iter()
+                    .map(|range| (index, text[range].to_string()))
+                    .collect::<Vec<_>>()
+            })
+            .collect()
+    }
+
+    /// Links Ctrl+L and Ctrl+O act on, as (index into `message_links`, URL)
+    ///
+    /// With the message pane focused, only the links in the message under the
+    /// cursor; otherwise every link in the channel.
+    fn selectable_links(&self) -> Vec<(usize, String)> {
+        let focused = self.message_cursor.index().filter(|_| self.focus == Focus::Messages);
+        self.message_links()
+            .into_iter()
+            .enumerate()
+            .filter(|(_, (index, _))| focused.is_none_or(|focused| *index == focused))
+            .map(|(link, (_, url))| (link, url))
+            .collect()
+    }
+
+    /// Cycle the link selection down the channel (or the message under the
+    /// cursor), wrapping back to the first link
+    fn select_next_link(&mut self) {
+        let links = self.selectable_links();
+        let Some((first, _)) = links.first() else {
+            self.selected_link = None;
+            return;
+        };
+
+        let position = self.selected_link.and_then(|selected| links.iter().position(|(link, _)| *link == selected));
+        self.selected_link = match position {
+            Some(i) if i + 1 < links.len() => Some(links[i + 1].0),
+            _ => Some(*first),
+        };
+    }
+
+    /// Open the selected link in the system browser
+    fn open_selected_link(&mut self) {
+        let Some(url) = self
+            .selected_link
+            .and_then(|selected| self.selectable_links().into_iter().find(|(link, _)| *link == selected))
+            .map(|(_, url)| url)
+        else {
+            self.notifications.push(Notification::new(
+                "No link selected (Ctrl+L to select)".to_string(),
+                NotificationLevel::Info,
+            ));
+            return;
+        };
+
+        self.notifications.push(match links::open_link(&url) {
+            Ok(()) => Notification::new(format!("Opened {}", url), NotificationLevel::Success),
+            Err(e) => {
+                tracing::warn!("Failed to open {}: {}", url, e);
+                Notification::new(format!("Failed to open {}: {}", url, e), NotificationLevel::Error)
+            }
+        });
+    }
+
+    async fn load_messages(&mut self) -> Result<()> {
+        self.selected_link = None;
+        self.message_cursor.clear();
+        self.replying_to = None;
+        self.cancel_edit();
+        self.message_scroll.to_bottom();
+
+        // Stash the input as the previous channel's draft and restore this one's
+        let selected_id = self.selected_channel.and_then(|idx| self.channels.get(idx)).map(|c| c.id);
+        if let Some(previous) = self.drafts.activate(selected_id, &mut self.input) {
+            let draft = self.drafts.get(&previous).to_string();
+            self.save_draft(previous, draft).await;
+        }
+
+        if let Some(channel_id) = selected_id {
+            // Startup only loaded recent messages of channels not shown yet
+            if let Err(e) = self.node.load_full_history(channel_id).await {
+                tracing::error!("Failed to load channel history: {:#}", e);
+            }
+            // Phase 4: Use DAG ordering instead of raw storage order
+            self.show_messages(channel_id);
+        }
+
+        if self.focus == Focus::Messages {
+            self.message_cursor.select_last(&self.messages);
+        }
+
+        self.mark_selected_read().await;
+        self.refresh_channel_protection().await;
+
+        self.join_channel_topics()
+    }
+
+    /// Remember the channel list width for the next start
+    async fn save_sidebar_width(&self) {
+        if self.read_only {
+            return;
+        }
+        let percent = self.sidebar.percent().to_string();
+        if let Err(e) = self.node.storage.store_setting(SIDEBAR_PERCENT_SETTING, &percent).await {
+            tracing::error!("Failed to save sidebar width: {}", e);
+        }
+    }
+
+    /// Mark the selected channel read up to its newest message
+    async fn mark_selected_read(&mut self) {
+        let Some(channel_id) = self.selected_channel.and_then(|idx| self.channels.get(idx)).map(|c| c.id) else {
+            return;
+        };
+        let Some(marker) = self.unread.mark_read(channel_id, &self.messages) else {
+            return;
+        };
+        if self.read_only {
+            return;
+        }
+        if let Err(e) = self.node.storage.store_read_marker(channel_id, marker).await {
+            tracing::error!("Failed to save read marker: {}", e);
+        }
+    }
+
+    /// Mark every channel read up to its newest message
+    async fn mark_all_read(&mut self) {
+        let unread = self.unread.total();
+        if unread == 0 {
+            self.notifications.push(Notification::new(
+                "No unread messages".to_string(),
+                NotificationLevel::Info,
+            ));
+            return;
+        }
+
+        let mut markers = Vec::new();
+        for channel_id in self.unread.unread_channels() {
+            let messages = self.node.dag.get_visible_messages(&channel_id, &self.hidden_messages);
+            if let Some(marker) = self.unread.mark_read(channel_id, &messages) {
+                markers.push((channel_id, marker));
+            }
+        }
+
+        if !self.read_only {
+            if let Err(e) = self.node.storage.store_read_markers(&markers).await {
+                tracing::error!("Failed to save read markers: {}", e);
+            }
+        }
+        self.notifications.push(Notification::new(
+            format!("Marked {} messages as read", unread),
+            NotificationLevel::Success,
+        ));
+    }
+
+    /// Persist a channel's draft so it survives a restart
+    async fn save_draft(&self, channel_id: ChannelId, text: String) {
+        if self.read_only {
+            return;
+        }
+        if let Err(e) = self.node.storage.store_draft(channel_id, &text).await {
+            tracing::error!("Failed to save draft: {}", e);
+        }
+    }
+
+    /// Recompute the encryption status shown for the selected channel
+    async fn refresh_channel_protection(&mut self) {
+        let channel = self.selected_channel.and_then(|idx| self.channels.get(idx));
+
+        self.channel_protection = match (&self.session_manager, channel) {
+            (Some(session_manager), Some(channel)) => session_manager
+                .channel_protection(channel, &self.peer_id)
+                .await
+                .unwrap_or_else(|e| {
+                    tracing::warn!("Failed to read session state: {}", e);
+                    ChannelProtection::Plaintext
+                }),
+            _ => ChannelProtection::Plaintext,
+        };
+    }
+
+    /// Ask peers for the selected channel's state (name and members)
+    fn request_channel_info(&mut self) -> Result<()> {
+        let Some(channel) = self.selected_channel.and_then(|idx| self.channels.get(idx)) else {
+            return Ok(());
+        };
+
+        self.network_command_tx.send(NetworkCommand::RequestChannelState(channel.id))?;
+        self.notifications.push(Notification::new(
+            format!("Requested channel info for {}", channel.get_name()),
+            NotificationLevel::Info,
+        ));
+
+        Ok(())
+    }
+
+    /// Resync the selected channel from every connected peer right away
+    fn catch_up_selected_channel(&mut self) -> Result<()> {
+        let Some(channel) = self.selected_channel.and_then(|idx| self.channels.get(idx)) else {
+            return Ok(());
+        };
+
+        let peer_count = self.peer_manager.peer_count();
+        if peer_count == 0 {
+            self.notifications.push(Notification::new(
+                "No peers connected to catch up from".to_string(),
+                NotificationLevel::Info,
+            ));
+            return Ok(());
+        }
+
+        self.gossip_manager.catch_up(channel.id, &self.node.dag)?;
+        self.notifications.push(Notification::new(
+            format!("Catching up on {} from {} peers...", channel.get_name(), peer_count),
+            NotificationLevel::Info,
+        ));
+
+        Ok(())
+    }
+
+    /// Compare the selected channel with every connected peer, without syncing
+    async fn preview_sync_selected_channel(&mut self) -> Result<()> {
+        let Some(channel) = self.selected_channel.and_then(|idx| self.channels.get(idx)) else {
+            return Ok(());
+        };
+
+        let connected: Vec<libp2p::PeerId> = self.peer_manager.get_all_peers().iter().map(|p| p.peer_id).collect();
+        if connected.is_empty() {
+            self.notifications.push(Notification::new(
+                "No peers connected to compare with".to_string(),
+                NotificationLevel::Info,
+            ));
+            return Ok(());
+        }
+
+        let ours = self.node.storage.get_channel_message_ids(channel.id).await?.into_iter().collect();
+        self.gossip_manager.preview_sync(channel.id, ours, &connected)?;
+        self.notifications.push(Notification::new(
+            format!("Comparing {} with {} peers...", channel.get_name(), connected.len()),
+            NotificationLevel::Info,
+        ));
+
+        Ok(())
+    }
+
+    /// Say what finished sync previews found
+    fn report_sync_previews(&mut self) {
+        for (channel_id, report) in self.gossip_manager.finish_sync_previews() {
+            let name = self
+                .channels
+                .iter()
+                .find(|c| c.id == channel_id)
+                .map(|c| c.get_name().clone())
+                .unwrap_or_else(|| "channel".to_string());
+            let mut peers: Vec<String> = report
+                .diffs
+                .iter()
+                .map(|(peer, diff)| match (diff.we_lack, diff.they_lack) {
+                    (0, 0) => format!("{} in sync", short_peer_id(peer)),
+                    (we_lack, they_lack) => {
+                        format!("{} has {} we lack, lacks {} of ours", short_peer_id(peer), we_lack, they_lack)
+                    }
+                })
+                .collect();
+            if report.unanswered > 0 {
+                peers.push(format!("{} didn't answer", report.unanswered));
+            }
+            self.notifications.push(Notification::new(
+                format!("Sync preview of {}: {}", name, peers.join("; ")),
+                NotificationLevel::Info,
+            ));
+        }
+    }
+
+    /// Say how finished catch-ups went
+    fn report_catch_ups(&mut self) {
+        for (channel_id, progress) in self.gossip_manager.finish_catch_ups() {
+            let name = self
+                .channels
+                .iter()
+                .find(|c| c.id == channel_id)
+                .map(|c| c.get_name().clone())
+                .unwrap_or_else(|| "channel".to_string());
+            let notification = match (progress.requested, progress.missing()) {
+                (0, _) => Notification::new(format!("{} is up to date", name), NotificationLevel::Success),
+                (_, 0) => Notification::new(
+                    format!("Caught up on {}: fetched {} messages", name, progress.received),
+                    NotificationLevel::Success,
+                ),
+                (requested, missing) => Notification::new(
+                    format!("Catch-up on {} timed out: {} of {} messages still missing", name, missing, requested),
+                    NotificationLevel::Error,
+                ),
+            };
+            self.notifications.push(notification);
+        }
+    }
+
+    /// Apply a peer's edit, sent live or carried by a synced message
+    async fn apply_remote_edit(
+        &mut self,
+        message_id: MessageId,
+        content: MessageContent,
+        edited_at: Timestamp,
+    ) -> Result<Option<Message>> {
+        self.clock.update(edited_at);
+        let content = self.node.content_pipeline.incoming(content);
+        self.node.apply_edit(message_id, content, edited_at).await
+    }
+
+    /// Show a channel's visible messages in DAG order
+    ///
+    /// Working out which older message a reply quotes walks the DAG, so
+    /// it's done here once per message rather than on every frame.
+    fn show_messages(&mut self, channel_id: ChannelId) {
+        self.messages = self.node.dag.get_visible_messages(&channel_id, &self.hidden_messages);
+        let previous = std::mem::take(&mut self.quotes);
+        let dag = &self.node.dag;
+        self.quotes = self
+            .messages
+            .iter()
+            .map(|message| {
+                let quoted = match previous.get(&message.id) {
+                    Some(quoted) => *quoted,
+                    None => dag.quoted_parents(message).first().copied(),
+                };
+                (message.id, quoted)
+            })
+            .collect();
+    }
+
+    // Phase 4: Helper to reload current channel messages
+    async fn reload_current_channel_messages(&mut self) -> Result<()> {
+        if let Some(idx) = self.selected_channel {
+            if let Some(channel) = self.channels.get(idx) {
+                self.show_messages(channel.id);
+                self.message_cursor.resync(&self.messages);
+            }
+        }
+        Ok(())
+    }
+
+    /// Cycle the selected channel's disappearing message timer (off -> 5m -> 1h -> 1d -> off)
+    fn cycle_message_ttl(&mut self) {
+        let Some(channel_id) = self.selected_channel.and_then(|idx| self.channels.get(idx)).map(|c| c.id) else {
+            return;
+        };
+
+        let next = match self.message_ttls.get(&channel_id) {
+            None => Some(MESSAGE_TTLS[0]),
+            Some(ttl) => MESSAGE_TTLS
+                .iter()
+                .position(|t| t == ttl)
+                .and_then(|i| MESSAGE_TTLS.get(i + 1))
+                .copied(),
+        };
+
+        let message = match next {
+            Some(ttl) => {
+                self.message_ttls.insert(channel_id, ttl);
+                format!("New messages disappear after {}", format_duration(ttl))
+            }
+            None => {
+                self.message_ttls.remove(&channel_id);
+                "Disappearing messages off".to_string()
+            }
+        };
+        self.notifications.push(Notification::new(message, NotificationLevel::Info));
+    }
+
+    /// Switch the selected channel between open posting and admins-only
+    async fn toggle_post_policy(&mut self) -> Result<()> {
+        let Some(channel) = self.selected_channel.and_then(|idx| self.channels.get_mut(idx)) else {
+            return Ok(());
+        };
+
+        let (policy, message) = match channel.post_policy() {
+            PostPolicy::Open => (PostPolicy::AdminsOnly, "Only admins can post in this channel now"),
+            PostPolicy::AdminsOnly => (PostPolicy::Open, "Everyone can post in this channel now"),
+        };
+        if !channel.set_post_policy(policy, &self.peer_id) {
+            self.notifications.push(Notification::new(
+                "Only channel admins can change who may post".to_string(),
+                NotificationLevel::Error,
+            ));
+            return Ok(());
+        }
+
+        let channel = channel.clone();
+        self.node.storage.store_channel(&channel).await?;
+        if let Err(e) = self.network_command_tx.send(NetworkCommand::BroadcastChannelUpdate(channel)) {
+            tracing::error!("Failed to broadcast channel update: {}", e);
+        }
+        self.notifications.push(Notification::new(message.to_string(), NotificationLevel::Info));
+
+        Ok(())
+    }
+
+    /// Mute or unmute the selected channel; urgent messages notify either way
+    async fn toggle_mute_selected_channel(&mut self) -> Result<()> {
+        let Some(channel) = self.selected_channel.and_then(|idx| self.channels.get(idx)) else {
+            return Ok(());
+        };
+        let channel_id = channel.id;
+        let muted = !self.muted_channels.contains(&channel_id);
+        let message = if muted {
+            format!("Muted {} (urgent messages still notify)", channel.get_name())
+        } else {
+            format!("Unmuted {}", channel.get_name())
+        };
+
+        self.node.storage.set_channel_muted(channel_id, muted).await?;
+        if muted {
+            self.muted_channels.insert(channel_id);
+        } else {
+            self.muted_channels.remove(&channel_id);
+        }
+        self.notifications.push(Notification::new(message, NotificationLevel::Info));
+
+        Ok(())
+    }
+
+    /// Pop up a notification for a newly arrived message, if it deserves one
+    fn notify_message(&mut self, message: &Message) {
+        let selected = self
+            .selected_channel
+            .and_then(|idx| self.channels.get(idx))
+            .is_some_and(|c| c.id == message.channel_id);
+        let muted = self.muted_channels.contains(&message.channel_id);
+        let keyword = self
+            .channel_keywords
+            .get(&message.channel_id)
+            .and_then(|keywords| alerts::keyword_hit(&message.content.text, keywords));
+        if !alerts::should_notify(message, self.peer_id, selected, muted, keyword.is_some()) {
+            return;
+        }
+
+        let channel_name = self
+            .channels
+            .iter()
+            .find(|c| c.id == message.channel_id)
+            .map_or("unknown channel", |c| c.get_name().as_str());
+        let notification = if message.content.is_urgent() {
+            Notification::new(
+                format!(
+                    "Urgent in {} from {}: {}",
+                    channel_name,
+                    message.author.short(),
+                    quote_snippet(&message.content.text)
+                ),
+                NotificationLevel::Urgent,
+            )
+        } else if let Some(keyword) = keyword {
+            // Spelled out so a keyword hit isn't folded into "new message"
+            Notification::new(
+                format!(
+                    "\"{}\" in {} from {}: {}",
+                    keyword,
+                    channel_name,
+                    message.author.short(),
+                    quote_snippet(&message.content.text)
+                ),
+                NotificationLevel::Info,
+            )
+        } else {
+            Notification::new(
+                format!("New message in {} from {}", channel_name, message.author.short()),
+                NotificationLevel::Info,
+            )
+            .coalescing(NotificationCategory::NewMessage)
+        };
+        self.notifications.push(notification);
+    }
+
+    /// Advance the expiry clock past a message's creation time
+    fn observe_message_time(&mut self, message: &Message) {
+        self.clock.update(Timestamp::new(message.created_at_ms, 0, message.author));
+    }
+
+    /// Wipe the content of messages whose timer ran out, keeping DAG tombstones
+    async fn expire_messages(&mut self) -> Result<()> {
+        self.last_expiry_sweep = Instant::now();
+        let now = self.clock.physical_now();
+
+        let expired = self.node.dag.expire_messages(now);
+        if !self.read_only {
+            if let Err(e) = self.node.storage.expire_messages(now).await {
+                tracing::error!("Failed to expire stored messages: {}", e);
+            }
+        }
+
+        if !expired.is_empty() {
+            tracing::debug!("Expired {} messages", expired.len());
+            // Expired images were deleted with their messages
+            match self.node.storage.attachment_hashes().await {
+                Ok(hashes) => self.attachments.set_held(hashes),
+                Err(e) => tracing::warn!("Failed to reload attachments: {}", e),
+            }
+            self.selected_link = None;
+            self.reload_current_channel_messages().await?;
+        }
+
+        Ok(())
+    }
+
+    async fn send_message(&mut self, priority: Priority) -> Result<()> {
+        if self.input.is_empty() {
+            return Ok(());
+        }
+
+        let Some(channel) = self.selected_channel.and_then(|idx| self.channels.get(idx)) else {
+            return Ok(());
+        };
+        if let Some(reason) = self.node.send_blocked_reason(channel) {
+            self.notifications.push(Notification::new(reason.to_string(), NotificationLevel::Error));
+            return Ok(());
+        }
+
+        let channel = channel.clone();
+        let content = MessageContent::from_input(&self.input).with_priority(priority);
+        self.publish_message(&channel, content, self.replying_to).await?;
+
+        self.input.clear();
+        self.replying_to = None;
+        self.message_scroll.to_bottom();
+
+        Ok(())
+    }
+
+    /// Create, store and send a message, optionally quoting an earlier one
+    async fn publish_message(&mut self, channel: &Channel, content: MessageContent, quoted: Option<MessageId>) -> Result<Message> {
+        let expires_at =
+            self.message_ttls.get(&channel.id).map(|ttl| self.clock.tick().physical + ttl.as_millis() as u64);
+        let message = self.node.publish(channel, content, quoted, expires_at).await?;
+
+        self.author_clocks.observe(&message);
+        self.peer_clocks.observe(&message);
+        self.record_activity(&message);
+        self.delivery.sent(&message);
+
+        // Reload messages in DAG order
+        self.reload_current_channel_messages().await?;
+
+        Ok(message)
+    }
+
+    /// Show a channel change in the conversation
+    ///
+    /// Changes are worked out locally from the channel's state and never
+    /// sent, so a peer can't make up joins or renames. Seeing the same change
+    /// again (in a later merge) doesn't add a second line.
+    async fn record_system_event(&mut self, channel_id: ChannelId, event: SystemEvent) -> Result<()> {
+        if self.node.storage.store_channel_event(channel_id, &event).await? {
+            self.channel_events.entry(channel_id).or_default().push((SystemTime::now(), event));
+        }
+        Ok(())
+    }
+
+    /// Use the Signal sessions the network encrypts direct channels with,
+    /// for the protection status and identity shown
+    pub async fn set_session_manager(&mut self, sessions: Arc<SessionManager>) {
+        self.session_manager = Some(sessions);
+        self.node.set_encrypting(true);
+        self.refresh_channel_protection().await;
+    }
+
+    /// Take requests from a control socket (see `crate::control`)
+    pub fn attach_control(&mut self, calls: mpsc::UnboundedReceiver<ControlCall>) {
+        self.control_rx = Some(calls);
+    }
+
+    async fn handle_control_call(&mut self, call: ControlCall) {
+        let result = self.run_control_command(call.command).await;
+        // The client may have hung up while we worked
+        let _ = call.reply.send(result);
+    }
+
+    async fn run_control_command(&mut self, command: ControlCommand) -> Result<serde_json::Value, RpcError> {
+        use crate::types::ChannelType;
+
+        let rejected = |message: String| RpcError::new(RpcError::REJECTED, message);
+        let internal = |e: anyhow::Error| RpcError::new(RpcError::INTERNAL_ERROR, format!("{:#}", e));
+
+        match command {
+            ControlCommand::ListChannels => {
+                let channels: Vec<_> = self
+                    .channels
+                    .iter()
+                    .map(|c| {
+                        serde_json::json!({
+                            "id": c.id.0.to_string(),
+                            "name": c.get_name(),
+                            "type": match c.channel_type {
+                                ChannelType::Group => "group",
+                                ChannelType::PeerToPeer => "direct",
+                            },
+                            "members": c.get_members().len(),
+                        })
+                    })
+                    .collect();
+                Ok(channels.into())
+            }
+            ControlCommand::ListPeers => {
+                let peers: Vec<_> = self
+                    .peer_manager
+                    .get_all_peers()
+                    .into_iter()
+                    .map(|p| {
+                        serde_json::json!({
+                            "peer_id": p.peer_id.to_string(),
+                            "addresses": p.addresses.iter().map(|a| a.to_string()).collect::<Vec<_>>(),
+                            "agent_version": p.agent_version,
+                            "quality": self.peer_manager.quality(&p.peer_id).map(|q| q.to_string()),
+                            "rtt_ms": p.rtt.map(|rtt| rtt.as_millis() as u64),
+                        })
+                    })
+                    .collect();
+                Ok(peers.into())
+            }
+            ControlCommand::SendMessage { channel, text } => {
+                if self.read_only {
+                    return Err(rejected("Read-only archive".to_string()));
+                }
+                if text.is_empty() {
+                    return Err(RpcError::new(RpcError::INVALID_PARAMS, "Message text is empty"));
+                }
+                let channel = self
+                    .channels
+                    .iter()
+                    .find(|c| c.id.0.to_string() == channel)
+                    .or_else(|| self.channels.iter().find(|c| c.get_name() == channel))
+                    .cloned()
+                    .ok_or_else(|| rejected(format!("No channel {:?}", channel)))?;
+                if let Some(reason) = self.node.send_blocked_reason(&channel) {
+                    return Err(rejected(reason.to_string()));
+                }
+
+                let message = self
+                    .publish_message(&channel, MessageContent::new(text), None)
+                    .await
+                    .map_err(internal)?;
+                Ok(serde_json::json!({ "message_id": message.id.0.to_string() }))
+            }
+            ControlCommand::Connect { address } => {
+                if self.read_only {
+                    return Err(rejected("Read-only archive".to_string()));
+                }
+                let addr: libp2p::Multiaddr = address
+                    .parse()
+                    .map_err(|e| RpcError::new(RpcError::INVALID_PARAMS, format!("Invalid multiaddr: {}", e)))?;
+                self.network_command_tx
+                    .send(NetworkCommand::ConnectToPeer(addr))
+                    .map_err(|e| internal(e.into()))?;
+                Ok(serde_json::json!({ "dialing": address }))
+            }
+        }
+    }
+
+    fn ui(&mut self, f: &mut Frame) {
+        // Main layout: content area, the peers panel if shown, status bar at bottom
+        let peers_height = if self.show_peers { peers::panel_height(self.peer_manager.peer_count()) } else { 0 };
+        let main_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(peers_height), Constraint::Length(1)])
+            .split(f.area());
+
+        // Content area with horizontal split
+        let sidebar_width = self.sidebar.width(main_chunks[0].width);
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Length(sidebar_width), Constraint::Min(0)])
+            .split(main_chunks[0]);
+
+        // Left panel: channel list
+        self.render_channel_list(f, chunks[0]);
+
+        // Right panel: messages and input
+        let right_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(3), Constraint::Length(3)])
+            .split(chunks[1]);
+
+        self.render_messages(f, right_chunks[0]);
+        self.render_input(f, right_chunks[1]);
+
+        if self.show_peers {
+            self.render_peers_panel(f, main_chunks[1]);
+        }
+
+        // Status bar at bottom
+        self.render_status_bar(f, main_chunks[2]);
+
+        // Render modals on top
+        match self.mode {
+            AppMode::Help => {
+                self.render_help(f, f.area());
+            }
+            AppMode::NewChannel => {
+                self.render_new_channel_modal(f, f.area());
+            }
+            AppMode::RenameChannel => {
+                self.render_rename_modal(f, f.area());
+            }
+            AppMode::EditDescription => {
+                self.render_description_modal(f, f.area());
+            }
+            AppMode::EditKeywords => {
+                self.render_keywords_modal(f, f.area());
+            }
+            AppMode::ConnectPeer => {
+                self.render_connect_peer_modal(f, f.area());
+            }
+            AppMode::QuickSwitch => {
+                self.render_quick_switch_modal(f, f.area());
+            }
+            AppMode::CopyFallback => {
+                self.render_copy_fallback(f, f.area());
+            }
+            AppMode::NotificationLog => {
+                self.render_notification_log(f, f.area());
+            }
+            AppMode::WhoAmI => {
+                self.render_whoami(f, f.area());
+            }
+            AppMode::Pinned => {
+                self.render_pinned(f, f.area());
+            }
+            AppMode::ConfirmMarkAllRead => {
+                self.render_confirm_mark_all_read(f, f.area());
+            }
+            AppMode::ConfirmDelete(channel_id) => {
+                self.render_confirm_delete(f, f.area(), channel_id);
+            }
+            AppMode::Normal => {}
+        }
+
+        // Render notification on top of everything
+        if let Some(notif) = self.notifications.current() {
+            self.render_notification(f, f.area(), notif);
+        }
+    }
+
+    fn render_channel_list(&mut self, f: &mut Frame, area: Rect) {
+        // Lookalike names get their short channel ID so one can't pass for another
+        let names = nickname::disambiguate(
+            &self.channels.iter().map(|c| (c.id, c.get_name().clone())).collect(),
+            ChannelId::short,
+        );
+        let items: Vec<ListItem> = self
+            .channels
+            .iter()
+            .map(|channel| {
+                use crate::types::ChannelType;
+
+                // Choose icon based on channel type
+                let icon = match channel.channel_type {
+                    ChannelType::PeerToPeer => "@",
+                    ChannelType::Group => "#",
+                };
+
+                // Show member count for groups
+                let members = channel.get_members();
+                let member_info = if channel.channel_type == ChannelType::Group && !members.is_empty() {
+                    format!(" ({})", members.len())
+                } else {
+                    String::new()
+                };
+
+                // Synced: no missing parents and nothing outstanding from peers
+                let sync_indicator = if let Some(progress) = self.gossip_manager.catch_up_progress(&channel.id) {
+                    Span::styled(
+                        format!(" ⟳ {}/{}", progress.received, progress.requested),
+                        Style::default().fg(Color::Yellow),
+                    )
+                } else if self.is_channel_synced(&channel.id) {
+                    Span::styled(" ✓", Style::default().fg(Color::Green))
+                } else {
+                    Span::styled(" ⟳", Style::default().fg(Color::Yellow))
+                };
+
+                let unread = self.unread.count(&channel.id);
+                let name_style = if unread > 0 {
+                    Style::default().fg(Color::White).add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(Color::White)
+                };
+
+                let mut spans = vec![
+                    Span::styled(format!("{} {}{}", icon, names[&channel.id], member_info), name_style),
+                    sync_indicator,
+                ];
+                if unread > 0 {
+                    spans.push(Span::styled(format!(" ({} unread)", unread), Style::default().fg(Color::Cyan)));
+                }
+                if channel.has_unknown_membership() {
+                    spans.push(Span::styled(" (members unknown)", Style::default().fg(Color::DarkGray)));
+                }
+                if channel.degraded {
+                    spans.push(Span::styled(" (degraded)", Style::default().fg(Color::Red)));
+                }
+                if channel.post_policy() == PostPolicy::AdminsOnly {
+                    spans.push(Span::styled(" (announcements)", Style::default().fg(Color::Magenta)));
+                }
+                if self.muted_channels.contains(&channel.id) {
+                    spans.push(Span::styled(" (muted)", Style::default().fg(Color::DarkGray)));
+                }
+
+                let activity = self.channel_activity.get(&channel.id).copied().unwrap_or_default();
+                let activity_text = match activity.last_message_at {
+                    Some(last) => {
+                        let ago = SystemTime::now().duration_since(last).unwrap_or_default();
+                        format!("  {} · {}", activity.message_count, format_duration(ago))
+                    }
+                    None => "  no messages".to_string(),
+                };
+                spans.push(Span::styled(activity_text, Style::default().fg(Color::DarkGray)));
+
+                let content = Line::from(spans);
+                ListItem::new(content)
+            })
+            .collect();
+
+        let peer_count = self.peer_manager.peer_count();
+        let title = if peer_count > 0 {
+            format!(" Channels ({} peers connected) ", peer_count)
+        } else {
+            " Channels (no peers) ".to_string()
+        };
+
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .highlight_style(
+                Style::default()
+                    .bg(Color::DarkGray)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("► ");
+
+        f.render_stateful_widget(list, area, &mut self.channel_list_state);
+    }
+
+    fn render_messages(&mut self, f: &mut Frame, area: Rect) {
+        use crate::types::ChannelType;
+
+        let channel_title = self
+            .selected_channel
+            .and_then(|idx| self.channels.get(idx))
+            .map(|c| {
+                let icon = match c.channel_type {
+                    ChannelType::PeerToPeer => "@",
+                    ChannelType::Group => "#",
+                };
+                let members = c.get_members();
+                let member_info = if c.has_unknown_membership() {
+                    " (members unknown)".to_string()
+                } else if c.channel_type == ChannelType::Group {
+                    format!(" ({} members)", members.len())
+                } else {
+                    String::new()
+                };
+                format!("{} {}{}", icon, c.get_name(), member_info)
+            })
+            .unwrap_or_else(|| "No channel selected".to_string());
+
+        let unknown_membership = self
+            .selected_channel
+            .and_then(|idx| self.channels.get(idx))
+            .is_some_and(|c| c.has_unknown_membership());
+
+        let (protection_text, protection_color) = match self.channel_protection {
+            _ if unknown_membership => ("no known members".to_string(), Color::DarkGray),
+            ChannelProtection::Plaintext => ("plaintext".to_string(), Color::Red),
+            ChannelProtection::ForwardSecret => ("forward secrecy".to_string(), Color::Green),
+        };
+        let mut title_spans = vec![
+            Span::raw(format!(" {} ", channel_title)),
+            Span::styled(format!("[{}] ", protection_text), Style::default().fg(protection_color)),
+        ];
+        // Unknown until the network reports the channel's topic
+        let listeners = self
+            .selected_channel
+            .and_then(|idx| self.channels.get(idx))
+            .and_then(|c| self.channel_listeners.get(&c.id));
+        match listeners {
+            Some(0) => title_spans.push(Span::styled("[no peers here] ", Style::default().fg(Color::Yellow))),
+            Some(count) => title_spans.push(Span::styled(
+                format!("[{} {} here] ", count, if *count == 1 { "peer" } else { "peers" }),
+                Style::default().fg(Color::Green),
+            )),
+            None => {}
+        }
+        if self.message_scroll.is_scrolled_back() {
+            title_spans.push(Span::styled(
+                "[scrolled back, PgDn: newer] ",
+                Style::default().fg(Color::Yellow),
+            ));
+        }
+        let title = Line::from(title_spans);
+
+        let text_style = Style::default().fg(Color::White);
+        let link_style = Style::default().fg(Color::Cyan).add_modifier(Modifier::UNDERLINED);
+
+        let mut link_index = 0;
+        let mut messages: Vec<Line> = Vec::new();
+        let description = self
+            .selected_channel
+            .and_then(|idx| self.channels.get(idx))
+            .map(|c| c.description())
+            .filter(|d| !d.is_empty());
+        if let Some(description) = description {
+            messages.push(Line::from(Span::styled(
+                strip_control(description).into_owned(),
+                Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC),
+            )));
+        }
+        if unknown_membership {
+            messages.push(Line::from(Span::styled(
+                "Membership unknown: press Ctrl+R to request channel info",
+                Style::default().fg(Color::Yellow).add_modifier(Modifier::ITALIC),
+            )));
+        }
+        let now = self.clock.physical_now();
+        let now_time = UNIX_EPOCH + Duration::from_millis(now);
+        let selected_style = Style::default().bg(Color::DarkGray);
+        // "Seen by" needs a known member list to count against
+        let seen_by_members = self
+            .selected_channel
+            .and_then(|idx| self.channels.get(idx))
+            .filter(|c| self.show_seen_by && !c.has_unknown_membership())
+            .map(|c| c.get_members())
+            .filter(|members| members.len() > 1);
+        let pins: HashSet<MessageId> = self
+            .selected_channel
+            .and_then(|idx| self.channels.get(idx))
+            .map(|c| c.pinned_messages().into_iter().collect())
+            .unwrap_or_default();
+        // Joins, leaves and renames have no author line
+        let system_line = |at: SystemTime, text: &str| {
+            Line::from(vec![
+                Span::styled(
+                    format!("[{}] ", self.timestamp_style.format(at, now_time)),
+                    Style::default().fg(Color::DarkGray),
+                ),
+                Span::styled(
+                    format!("— {}", strip_control(text)),
+                    Style::default().fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+                ),
+            ])
+        };
+        // ... placed among the messages by when they were seen
+        let mut events = self
+            .selected_channel
+            .and_then(|idx| self.channels.get(idx))
+            .and_then(|c| self.channel_events.get(&c.id))
+            .map(|events| events.as_slice())
+            .unwrap_or_default()
+            .iter()
+            .peekable();
+        for (index, msg) in self.messages.iter().enumerate() {
+            let selected = self.focus == Focus::Messages && self.message_cursor.index() == Some(index);
+
+            while let Some((at, event)) = events.next_if(|(at, _)| *at <= msg.created_at()) {
+                messages.push(system_line(*at, &event.to_string()));
+            }
+
+            let sent = Span::styled(
+                format!("[{}] ", self.timestamp_style.format(msg.created_at(), now_time)),
+                Style::default().fg(Color::DarkGray),
+            );
+
+            // Older versions kept channel changes in the history itself
+            if msg.content.is_system() {
+                let line = system_line(msg.created_at(), &msg.content.text);
+                messages.push(if selected { line.style(selected_style) } else { line });
+                continue;
+            }
+
+            let is_own = msg.author == self.peer_id;
+            let author_color = if is_own { Color::Green } else { Color::Blue };
+
+            // A reply to an older message shows what it quoted
+            let quoted = self.quotes.get(&msg.id).copied().flatten();
+            if let Some(quoted) = quoted.and_then(|id| self.node.dag.get_message(&id)) {
+                messages.push(Line::from(Span::styled(
+                    format!("  ↳ re {}: {}", quoted.author.short(), quote_snippet(&quoted.current_content().text)),
+                    Style::default().fg(Color::DarkGray),
+                )));
+            }
+
+            // "/me" actions read as a sentence about the author
+            let is_action = msg.current_content().is_action();
+            let mut spans = vec![sent];
+            spans.push(if is_action {
+                Span::styled(
+                    format!("* {} ", msg.author.0.simple()),
+                    Style::default().fg(author_color).add_modifier(Modifier::ITALIC),
+                )
+            } else {
+                Span::styled(
+                    format!("[{}] ", msg.author.0.simple()),
+                    Style::default().fg(author_color).add_modifier(Modifier::BOLD),
+                )
+            });
+
+            let text_style = if msg.content.is_urgent() {
+                spans.push(Span::styled(
+                    "URGENT ",
+                    Style::default().fg(Color::White).bg(Color::Red).add_modifier(Modifier::BOLD),
+                ));
+                text_style.fg(Color::LightRed).add_modifier(Modifier::BOLD)
+            } else {
+                text_style
+            };
+            let text_style = if is_action { text_style.add_modifier(Modifier::ITALIC) } else { text_style };
+
+            // Expired messages remain as tombstones with their content wiped
+            if msg.expires_at.is_some() && msg.content.text.is_empty() {
+                spans.push(Span::styled(
+                    "(message expired)",
+                    Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC),
+                ));
+                let line = Line::from(spans);
+                messages.push(if selected { line.style(selected_style) } else { line });
+                continue;
+            }
+
+            // Split the text around links so they can be styled
+            spans.extend(links::styled_spans(
+                &msg.current_content().text,
+                text_style,
+                link_style,
+                self.selected_link,
+                &mut link_index,
+            ));
+
+            if msg.is_edited() {
+                spans.push(Span::styled("  (edited)", Style::default().fg(Color::DarkGray)));
+            }
+
+            if let Some(image) = msg.current_content().image_ref() {
+                let status = match self.attachments.state(image) {
+                    ImageState::Held => "  (o to open)",
+                    ImageState::Fetching => "  (fetching…)",
+                    ImageState::Missing => "  (not received; o to ask again)",
+                };
+                spans.push(Span::styled(status, Style::default().fg(Color::DarkGray)));
+            }
+
+            if let Some(expires_at) = msg.expires_at {
+                let remaining = Duration::from_millis(expires_at.saturating_sub(now));
+                spans.push(Span::styled(
+                    format!("  (disappears in {})", format_duration(remaining)),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+
+            if let Some(members) = &seen_by_members {
+                let seen = self.peer_clocks.seen_by(msg, members).len();
+                spans.push(Span::styled(
+                    format!("  · seen by {}/{}", seen, members.len()),
+                    Style::default().fg(Color::DarkGray),
+                ));
+            }
+
+            if pins.contains(&msg.id) {
+                spans.push(Span::styled("  📌", Style::default().fg(Color::Yellow)));
+            }
+
+            if self.delivery.state(&msg.id) == Some(DeliveryState::Failed) {
+                spans.push(Span::styled(
+                    "  ✗ not delivered (R: resend)",
+                    Style::default().fg(Color::Red),
+                ));
+            }
+
+            let line = Line::from(spans);
+            messages.push(if selected { line.style(selected_style) } else { line });
+        }
+        for (at, event) in events {
+            messages.push(system_line(*at, &event.to_string()));
+        }
+
+        let border_color = if self.focus == Focus::Messages { Color::Yellow } else { Color::Cyan };
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(border_color));
+        let inner = block.inner(area);
+        let paragraph = Paragraph::new(messages).wrap(Wrap { trim: false });
+
+        // Scrolling is by wrapped line, so the extent depends on the pane width
+        let mut scroll = self.message_scroll;
+        scroll.fit(paragraph.line_count(inner.width), inner.height);
+        f.render_widget(paragraph.block(block).scroll((scroll.offset(), 0)), area);
+        self.message_scroll = scroll;
+    }
+
+    fn render_input(&self, f: &mut Frame, area: Rect) {
+        let channel = self.selected_channel.and_then(|idx| self.channels.get(idx));
+        let can_post = channel.is_none_or(|c| c.can_post(&self.peer_id));
+        let ttl = channel.and_then(|c| self.message_ttls.get(&c.id));
+
+        if !can_post {
+            // Readers of an announcement channel get a greyed-out box instead of a prompt
+            let paragraph = Paragraph::new("")
+                .block(
+                    Block::default()
+                        .title(" Announcements: only admins can post (Ctrl+H: help, Ctrl+Q: quit) ")
+                        .borders(Borders::ALL)
+                        .border_style(Style::default().fg(Color::DarkGray)),
+                )
+                .style(Style::default().fg(Color::DarkGray));
+            f.render_widget(paragraph, area);
+            return;
+        }
+
+        let input_text = format!("> {}", self.input);
+        let quoted = self.replying_to.and_then(|id| self.node.dag.get_message(&id));
+        let title = if self.read_only {
+            " Read-only archive (↑/↓: browse, Ctrl+H: help, Ctrl+Q: quit) ".to_string()
+        } else if self.editing.is_some() {
+            " Editing message (Enter: save, Esc: cancel) ".to_string()
+        } else if let Some(quoted) = quoted {
+            format!(
+                " Replying to {}: {} (Esc: cancel) ",
+                quoted.author.short(),
+                quote_snippet(&quoted.current_content().text)
+            )
+        } else if let Some(ttl) = ttl {
+            format!(" Input (disappearing after {}, Ctrl+T: change) ", format_duration(*ttl))
+        } else {
+            " Input (Enter: send, Ctrl+H: help, Ctrl+Q: quit) ".to_string()
+        };
+
+        let paragraph = Paragraph::new(input_text)
+            .block(
+                Block::default()
+                    .title(title)
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Yellow)),
+            )
+            .style(Style::default().fg(Color::White));
+
+        f.render_widget(paragraph, area);
+    }
+
+    /// List connected peers with how long they've been connected and where
+    ///
+    /// Read from the peer manager on every draw, so it follows peers
+    /// connecting and disconnecting as it happens.
+    fn render_peers_panel(&self, f: &mut Frame, area: Rect) {
+        let peers = self.peer_manager.get_all_peers();
+        let rows = peers::panel_rows(&peers, SystemTime::now());
+
+        let mut lines: Vec<Line> = rows
+            .iter()
+            .map(|row| {
+                Line::from(vec![
+                    Span::styled(format!("{} ", row.peer), Style::default().fg(Color::Blue)),
+                    Span::styled(format!("{:>4} ", row.uptime), Style::default().fg(Color::Green)),
+                    Span::styled(row.address.clone(), Style::default().fg(Color::Gray)),
+                ])
+            })
+            .collect();
+        if peers.is_empty() {
+            lines.push(Line::from(Span::styled("No peers connected", Style::default().fg(Color::DarkGray))));
+        } else if peers.len() > rows.len() {
+            lines.push(Line::from(Span::styled(
+                format!("...and {} more", peers.len() - rows.len()),
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+
+        let panel = Paragraph::new(lines).block(
+            Block::default()
+                .title(format!(" Connected Peers ({}) ", peers.len()))
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(Color::Cyan)),
+        );
+        f.render_widget(panel, area);
+    }
+
+    fn render_status_bar(&self, f: &mut Frame, area: Rect) {
+        let peer_id_short = short_peer_id(&self.libp2p_peer_id);
+
+        // Get first listen address or show count
+        let listen_info = if self.read_only {
+            "Archive (offline)".to_string()
+        } else if self.listen_addrs.is_empty() {
+            "Starting...".to_string()
+        } else if self.listen_addrs.len() == 1 {
+            self.listen_addrs[0].clone()
+        } else {
+            format!("{} addresses", self.listen_addrs.len())
+        };
+
+        // Connected peers count
+        let peer_count = self.peer_manager.peer_count();
+        let peers_text = if peer_count == 1 {
+            "1 peer".to_string()
+        } else {
+            format!("{} peers", peer_count)
+        };
+
+        let status_text = format!(
+            " ID: {} | Listening: {} | Connected: {} ",
+            peer_id_short, listen_info, peers_text
+        );
+
+        let status = Paragraph::new(status_text)
+            .style(Style::default().bg(Color::DarkGray).fg(Color::White));
+
+        f.render_widget(status, area);
+    }
+
+    fn render_notification(&self, f: &mut Frame, area: Rect, notification: &Notification) {
+        // Position notification at the top center
+        let notification_area = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Length(3), Constraint::Min(0)])
+            .split(area)[0];
+
+        let horizontal_layout = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(20),
+                Constraint::Percentage(60),
+                Constraint::Percentage(20),
+            ])
+            .split(notification_area);
+
+        let notif_area = horizontal_layout[1];
+
+        // Choose color based on level
+        let (border_color, text_color) = match notification.level {
+            NotificationLevel::Info => (Color::Cyan, Color::White),
+            NotificationLevel::Success => (Color::Green, Color::White),
+            NotificationLevel::Error => (Color::Red, Color::White),
+            NotificationLevel::Urgent => (Color::Magenta, Color::White),
+        };
+
+        let notification_widget = Paragraph::new(notification.text())
+            .block(
+                Block::default()
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(border_color))
+            )
+            .style(Style::default().fg(text_color).bg(Color::Black))
+            .wrap(Wrap { trim: false });
+
+        f.render_widget(notification_widget, notif_area);
+    }
+
+    fn render_new_channel_modal(&self, f: &mut Frame, area: Rect) {
+        // Clear the entire screen to remove underlying UI
+        f.render_widget(Clear, area);
+
+        let text = vec![
+            Line::from(""),
+            Line::from(vec![Span::styled(
+                "Create New Channel",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )]),
+            Line::from(""),
+            Line::from("Enter channel name:"),
+            Line::from(""),
+            Line::from(vec![
+                Span::raw("> "),
+                Span::styled(
+                    &self.new_channel_input,
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::styled("_", Style::default().fg(Color::Gray)),
+            ]),
+            Line::from(""),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("Enter", Style::default().fg(Color::Green)),
+                Span::raw(" to create  "),
+                Span::styled("Esc", Style::default().fg(Color::Red)),
+                Span::raw(" to cancel"),
+            ]),
+        ];
+
+        let paragraph = Paragraph::new(text)
+            .block(
+                Block::default()
+                    .title(" New Channel ")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .wrap(Wrap { trim: false });
+
+        // Center the modal
+        let vertical_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(30),
+                Constraint::Length(12),
+                Constraint::Percentage(30),
+            ])
+            .split(area);
+
+        let horizontal_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(20),
+                Constraint::Percentage(60),
+                Constraint::Percentage(20),
+            ])
+            .split(vertical_chunks[1]);
+
+        f.render_widget(paragraph, horizontal_chunks[1]);
+    }
+
+    fn render_rename_modal(&self, f: &mut Frame, area: Rect) {
+        f.render_widget(Clear, area);
+
+        let text = vec![
+            Line::from(""),
+            Line::from(vec![Span::styled(
+                "Rename Channel",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )]),
+            Line::from(""),
+            Line::from("New name, shown to every member:"),
+            Line::from(""),
+            Line::from(vec![
+                Span::raw("> "),
+                Span::styled(
+                    &self.rename_input,
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::styled("_", Style::default().fg(Color::Gray)),
+            ]),
+            Line::from(""),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("Enter", Style::default().fg(Color::Green)),
+                Span::raw(" to rename  "),
+                Span::styled("Esc", Style::default().fg(Color::Red)),
+                Span::raw(" to cancel"),
+            ]),
+        ];
+
+        let paragraph = Paragraph::new(text)
+            .block(
+                Block::default()
+                    .title(" Rename Channel ")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .wrap(Wrap { trim: false });
+
+        // Center the modal
+        let vertical_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(30),
+                Constraint::Length(12),
+                Constraint::Percentage(30),
+            ])
+            .split(area);
+
+        let horizontal_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(20),
+                Constraint::Percentage(60),
+                Constraint::Percentage(20),
+            ])
+            .split(vertical_chunks[1]);
+
+        f.render_widget(paragraph, horizontal_chunks[1]);
+    }
+
+    fn render_description_modal(&self, f: &mut Frame, area: Rect) {
+        f.render_widget(Clear, area);
+
+        let text = vec![
+            Line::from(""),
+            Line::from(vec![Span::styled(
+                "Channel Description",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )]),
+            Line::from(""),
+            Line::from("Shown under the channel name (leave empty to clear):"),
+            Line::from(""),
+            Line::from(vec![
+                Span::raw("> "),
+                Span::styled(
+                    &self.description_input,
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::styled("_", Style::default().fg(Color::Gray)),
+            ]),
+            Line::from(""),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("Enter", Style::default().fg(Color::Green)),
+                Span::raw(" to save  "),
+                Span::styled("Esc", Style::default().fg(Color::Red)),
+                Span::raw(" to cancel"),
+            ]),
+        ];
+
+        let paragraph = Paragraph::new(text)
+            .block(
+                Block::default()
+                    .title(" Edit Description ")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .wrap(Wrap { trim: false });
+
+        // Center the modal
+        let vertical_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(30),
+                Constraint::Length(12),
+                Constraint::Percentage(30),
+            ])
+            .split(area);
+
+        let horizontal_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(20),
+                Constraint::Percentage(60),
+                Constraint::Percentage(20),
+            ])
+            .split(vertical_chunks[1]);
+
+        f.render_widget(paragraph, horizontal_chunks[1]);
+    }
+
+    fn render_keywords_modal(&self, f: &mut Frame, area: Rect) {
+        f.render_widget(Clear, area);
+
+        let text = vec![
+            Line::from(""),
+            Line::from(vec![Span::styled(
+                "Notification Keywords",
+                Style::default()
+                    .fg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD),
+            )]),
+            Line::from(""),
+            Line::from("Comma-separated; these notify even while the channel is muted:"),
+            Line::from(""),
+            Line::from(vec![
+                Span::raw("> "),
+                Span::styled(
+                    &self.keywords_input,
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::styled("_", Style::default().fg(Color::Gray)),
+            ]),
+            Line::from(""),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("Enter", Style::default().fg(Color::Green)),
+                Span::raw(" to save  "),
+                Span::styled("Esc", Style::default().fg(Color::Red)),
+                Span::raw(" to cancel"),
+            ]),
+        ];
+
+        let paragraph = Paragraph::new(text)
+            .block(
+                Block::default()
+                    .title(" Edit Keywords ")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .wrap(Wrap { trim: false });
+
+        // Center the modal
+        let vertical_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(30),
+                Constraint::Length(12),
+                Constraint::Percentage(30),
+            ])
+            .split(area);
+
+        let horizontal_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(20),
+                Constraint::Percentage(60),
+                Constraint::Percentage(20),
+            ])
+            .split(vertical_chunks[1]);
+
+        f.render_widget(paragraph, horizontal_chunks[1]);
+    }
+
+    fn render_quick_switch_modal(&self, f: &mut Frame, area: Rect) {
+        // Clear the entire screen to remove underlying UI
+        f.render_widget(Clear, area);
+
+        let mut text = vec![
+            Line::from(""),
+            Line::from(vec![
+                Span::raw("> "),
+                Span::styled(
+                    &self.quick_switch_input,
+                    Style::default().fg(Color::Yellow),
+                ),
+                Span::styled("_", Style::default().fg(Color::Gray)),
+            ]),
+            Line::from(""),
+        ];
+
+        let matches = self.quick_switch_matches();
+        if matches.is_empty() {
+            text.push(Line::from(Span::styled(
+                "No matching channels",
+                Style::default().fg(Color::DarkGray),
+            )));
+        }
+        for (position, &index) in matches.iter().enumerate() {
+            let name = format!("  {}", self.channels[index].get_name());
+            let style = if position == self.quick_switch_selected {
+                Style::default()
+                    .fg(Color::Black)
+                    .bg(Color::Cyan)
+                    .add_modifier(Modifier::BOLD)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            text.push(Line::from(Span::styled(name, style)));
+        }
+
+        text.push(Line::from(""));
+        text.push(Line::from(vec![
+            Span::styled("Enter", Style::default().fg(Color::Green)),
+            Span::raw(" to jump  "),
+            Span::styled("↑/↓", Style::default().fg(Color::Yellow)),
+            Span::raw(" to choose  "),
+            Span::styled("Esc", Style::default().fg(Color::Red)),
+            Span::raw(" to cancel"),
+        ]));
+
+        let paragraph = Paragraph::new(text)
+            .block(
+                Block::default()
+                    .title(" Jump to Channel ")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .wrap(Wrap { trim: false });
+
+        // Center the modal
+        let vertical_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(20),
+                Constraint::Length(MAX_QUICK_SWITCH_RESULTS as u16 + 7),
+                Constraint::Percentage(20),
+            ])
+            .split(area);
+
+        let horizontal_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(25),
+                Constraint::Percentage(50),
+                Constraint::Percentage(25),
+            ])
+            .split(vertical_chunks[1]);
+
+        f.render_widget(paragraph, horizontal_chunks[1]);
+    }
+
+    fn render_copy_fallback(&self, f: &mut Frame, area: Rect) {
+        let text = vec![
+            Line::from(Span::styled(
+                "Clipboard unavailable. Select the text below to copy it:",
+                Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC),
+            )),
+            Line::from(""),
+            Line::from(self.copy_fallback_text.as_str()),
+        ];
+
+        let paragraph = Paragraph::new(text)
+            .block(
+                Block::default()
+                    .title(" Copy Message (any key to close) ")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .wrap(Wrap { trim: false });
+
+        // Center the overlay
+        let vertical_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(25),
+                Constraint::Percentage(50),
+                Constraint::Percentage(25),
+            ])
+            .split(area);
+
+        let horizontal_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(15),
+                Constraint::Percentage(70),
+                Constraint::Percentage(15),
+            ])
+            .split(vertical_chunks[1]);
+
+        f.render_widget(Clear, horizontal_chunks[1]);
+        f.render_widget(paragraph, horizontal_chunks[1]);
+    }
+
+    fn render_notification_log(&self, f: &mut Frame, area: Rect) {
+        let log = self.notifications.log();
+        let lines: Vec<Line> = if log.is_empty() {
+            vec![Line::from(Span::styled(
+                "No notifications yet",
+                Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC),
+            ))]
+        } else {
+            log.newest_first()
+                .map(|entry| {
+                    let color = match entry.notification.level {
+                        NotificationLevel::Info => Color::White,
+                        NotificationLevel::Success => Color::Green,
+                        NotificationLevel::Error => Color::Red,
+                        NotificationLevel::Urgent => Color::Magenta,
+                    };
+                    Line::from(vec![
+                        Span::styled(
+                            format!("{}  ", timestamps::date_time(entry.raised_at)),
+                            Style::default().fg(Color::DarkGray),
+                        ),
+                        Span::styled(entry.notification.text(), Style::default().fg(color)),
+                    ])
+                })
+                .collect()
+        };
+
+        let paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title(" Notifications (newest first, any key to close) ")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Cyan)),
+            )
+            .wrap(Wrap { trim: false });
+
+        // Center the overlay
+        let vertical_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(15),
+                Constraint::Percentage(70),
+                Constraint::Percentage(15),
+            ])
+            .split(area);
+
+        let horizontal_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(15),
+                Constraint::Percentage(70),
+                Constraint::Percentage(15),
+            ])
+            .split(vertical_chunks[1]);
+
+        f.render_widget(Clear, horizontal_chunks[1]);
+        f.render_widget(paragraph, horizontal_chunks[1]);
+    }
+
+    fn render_pinned(&self, f: &mut Frame, area: Rect) {
+        let pinned = self
+            .selected_channel
+            .and_then(|idx| self.channels.get(idx))
+            .map(|c| c.pinned_messages())
+            .unwrap_or_default();
+
+        let mut lines: Vec<Line> = Vec::new();
+        if pinned.is_empty() {
+            lines.push(Line::from(Span::styled(
+                "No pinned messages (p in the message pane pins one)",
+                Style::default().fg(Color::Gray).add_modifier(Modifier::ITALIC),
+            )));
+        }
+        for (i, message_id) in pinned.iter().enumerate() {
+            let text = match self.node.dag.get_message(message_id) {
+                Some(message) => format!("{}: {}", message.author.short(), quote_snippet(&message.current_content().text)),
+                None => "(not received yet, requested from peers)".to_string(),
+            };
+            let style = if i == self.pinned_selected {
+                Style::default().fg(Color::Black).bg(Color::Cyan)
+            } else {
+                Style::default().fg(Color::White)
+            };
+            lines.push(Line::from(Span::styled(text, style)));
+        }
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "↑/↓ select  Enter jump to message  p unpin  any other key to close",
+            Style::default().fg(Color::DarkGray),
+        )));
+
+        let paragraph = Paragraph::new(lines)
+            .block(
+                Block::default()
+                    .title(" Pinned Messages ")
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(Color::Yellow)),
+            )
+            .wrap(Wrap { trim: false });
+
+        // Center the overlay
+        let vertical_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(20),
+                Constraint::Length(pinned.len().max(1) as u16 + 4),
+                Constraint::Percentage(20),
+            ])
+            .split(area);
+
+        let horizontal_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(15),
+                Constraint::Percentage(70),
+                Constraint::Percentage(15),
+            ])
+            .split(vertical_chunks[1]);
 
-        f.render_stateful_widget(list, area, &mut self.channel_list_state);
+        f.render_widget(Clear, horizontal_chunks[1]);
+        f.render_widget(paragraph, horizontal_chunks[1]);
     }
 
-    fn render_messages(&self, f: &mut Frame, area: Rect) {
-        use crate::types::ChannelType;
-
-        let channel_title = self
-            .selected_channel
-            .and_then(|idx| self.channels.get(idx))
-            .map(|c| {
-                let icon = match c.channel_type {
-                    ChannelType::PeerToPeer => "@",
-                    ChannelType::Group => "#",
-                };
-                let members = c.get_members();
-                let member_info = if c.channel_type == ChannelType::Group && !members.is_empty() {
-                    format!(" ({} members)", members.len())
+    fn render_whoami(&self, f: &mut Frame, area: Rect) {
+        let label_width = self.whoami.rows().iter().map(|(label, _)| label.len()).max().unwrap_or(0);
+        let mut lines: Vec<Line> = self
+            .whoami
+            .rows()
+            .iter()
+            .enumerate()
+            .map(|(i, (label, value))| {
+                let value_style = if i == self.whoami.selected() {
+                    Style::default().fg(Color::Black).bg(Color::Cyan)
                 } else {
-                    String::new()
+                    Style::default().fg(Color::White)
                 };
-                format!("{} {}{}", icon, c.get_name(), member_info)
-            })
-            .unwrap_or_else(|| "No channel selected".to_string());
-
-        let messages: Vec<Line> = self
-            .messages
-            .iter()
-            .map(|msg| {
-                let is_own = msg.author == self.peer_id;
-                let author_color = if is_own { Color::Green } else { Color::Blue };
-
                 Line::from(vec![
-                    Span::styled(
-                        format!("[{}] ", msg.author.0.simple()),
-                        Style::default().fg(author_color).add_modifier(Modifier::BOLD),
-                    ),
-                    Span::styled(&msg.content.text, Style::default().fg(Color::White)),
+                    Span::styled(format!("{:<width$}  ", label, width = label_width), Style::default().fg(Color::Gray)),
+                    Span::styled(value.clone(), value_style),
                 ])
             })
             .collect();
+        lines.push(Line::from(""));
+        lines.push(Line::from(Span::styled(
+            "↑/↓ select  Enter copy  any other key to close",
+            Style::default().fg(Color::DarkGray),
+        )));
 
-        let paragraph = Paragraph::new(messages)
+        let paragraph = Paragraph::new(lines)
             .block(
                 Block::default()
-                    .title(format!(" {} ", channel_title))
+                    .title(" Who Am I ")
                     .borders(Borders::ALL)
                     .border_style(Style::default().fg(Color::Cyan)),
             )
             .wrap(Wrap { trim: false });
 
-        f.render_widget(paragraph, area);
+        // Center the overlay
+        let vertical_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Percentage(20),
+                Constraint::Length(self.whoami.rows().len() as u16 + 4),
+                Constraint::Percentage(20),
+            ])
+            .split(area);
+
+        let horizontal_chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(10),
+                Constraint::Percentage(80),
+                Constraint::Percentage(10),
+            ])
+            .split(vertical_chunks[1]);
+
+        f.render_widget(Clear, horizontal_chunks[1]);
+        f.render_widget(paragraph, horizontal_chunks[1]);
     }
 
-    fn render_input(&self, f: &mut Frame, area: Rect) {
-        let input_text = format!("> {}", self.input);
+    fn render_confirm_mark_all_read(&self, f: &mut Frame, area: Rect) {
+        let text = vec![
+            Line::from(""),
+            Line::from(format!(
+                "Mark {} unread messages in {} channels as read?",
+                self.unread.total(),
+                self.unread.unread_channels().len()
+            )),
+            Line::from(""),
+            Line::from(vec![
+                Span::styled("y", Style::default().fg(Color::Green)),
+                Span::raw(" to confirm  "),
+                Span::styled("any other key", Style::default().fg(Color::Red)),
+                Span::raw(" to cancel"),
+            ]),
+        ];
 
-        let paragraph = Paragraph::new(input_text)
+        let paragraph = Paragraph::new(text)
             .block(
                 Block::default()
-                    .title(" Input (Enter: send, Ctrl+H: help, Ctrl+Q: quit) ")
+                    .title(" Mark All as Read ")
                     .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::Yellow)),
+                    .border_style(Style::default().fg(Color::Cyan)),
             )
-            .style(Style::default().fg(Color::White));
-
-        f.render_widget(paragraph, area);
-    }
-
-    fn render_status_bar(&self, f: &mut Frame, area: Rect) {
-        // Shorten peer ID for display (first 8 chars)
-        let peer_id_str = self.libp2p_peer_id.to_string();
-        let peer_id_short = if peer_id_str.len() > 12 {
-            format!("{}...{}", &peer_id_str[..6], &peer_id_str[peer_id_str.len()-6..])
-        } else {
-            peer_id_str
-        };
-
-        // Get first listen address or show count
-        let listen_info = if self.listen_addrs.is_empty() {
-            "Starting...".to_string()
-        } else if self.listen_addrs.len() == 1 {
-            self.listen_addrs[0].clone()
-        } else {
-            format!("{} addresses", self.listen_addrs.len())
-        };
-
-        // Connected peers count
-        let peer_count = self.peer_manager.peer_count();
-        let peers_text = if peer_count == 1 {
-            "1 peer".to_string()
-        } else {
-            format!("{} peers", peer_count)
-        };
-
-        let status_text = format!(
-            " ID: {} | Listening: {} | Connected: {} ",
-            peer_id_short, listen_info, peers_text
-        );
-
-        let status = Paragraph::new(status_text)
-            .style(Style::default().bg(Color::DarkGray).fg(Color::White));
-
-        f.render_widget(status, area);
-    }
+            .wrap(Wrap { trim: false });
 
-    fn render_notification(&self, f: &mut Frame, area: Rect, notification: &Notification) {
-        // Position notification at the top center
-        let notification_area = Layout::default()
+        // Center the modal
+        let vertical_chunks = Layout::default()
             .direction(Direction::Vertical)
-            .constraints([Constraint::Length(3), Constraint::Min(0)])
-            .split(area)[0];
+            .constraints([
+                Constraint::Percentage(35),
+                Constraint::Length(6),
+                Constraint::Percentage(35),
+            ])
+            .split(area);
 
-        let horizontal_layout = Layout::default()
+        let horizontal_chunks = Layout::default()
             .direction(Direction::Horizontal)
             .constraints([
                 Constraint::Percentage(20),
                 Constraint::Percentage(60),
                 Constraint::Percentage(20),
             ])
-            .split(notification_area);
-
-        let notif_area = horizontal_layout[1];
-
-        // Choose color based on level
-        let (border_color, text_color) = match notification.level {
-            NotificationLevel::Info => (Color::Cyan, Color::White),
-            NotificationLevel::Success => (Color::Green, Color::White),
-            NotificationLevel::Error => (Color::Red, Color::White),
-        };
-
-        let notification_widget = Paragraph::new(notification.message.clone())
-            .block(
-                Block::default()
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(border_color))
-            )
-            .style(Style::default().fg(text_color).bg(Color::Black))
-            .wrap(Wrap { trim: false });
+            .split(vertical_chunks[1]);
 
-        f.render_widget(notification_widget, notif_area);
+        f.render_widget(Clear, horizontal_chunks[1]);
+        f.render_widget(paragraph, horizontal_chunks[1]);
     }
 
-    fn render_new_channel_modal(&self, f: &mut Frame, area: Rect) {
-        // Clear the entire screen to remove underlying UI
-        f.render_widget(Clear, area);
-
+    fn render_confirm_delete(&self, f: &mut Frame, area: Rect, channel_id: ChannelId) {
+        let name = self
+            .channels
+            .iter()
+            .find(|c| c.id == channel_id)
+            .map(|c| strip_control(c.get_name()).into_owned())
+            .unwrap_or_default();
         let text = vec![
             Line::from(""),
-            Line::from(vec![Span::styled(
-                "Create New Channel",
-                Style::default()
-                    .fg(Color::Cyan)
-                    .add_modifier(Modifier::BOLD),
-            )]),
-            Line::from(""),
-            Line::from("Enter channel name:"),
-            Line::from(""),
-            Line::from(vec![
-                Span::raw("> "),
-                Span::styled(
-                    &self.new_channel_input,
-                    Style::default().fg(Color::Yellow),
-                ),
-                Span::styled("_", Style::default().fg(Color::Gray)),
-            ]),
-            Line::from(""),
+            Line::from(format!("Delete channel {} and its messages for every member?", name)),
             Line::from(""),
             Line::from(vec![
-                Span::styled("Enter", Style::default().fg(Color::Green)),
-                Span::raw(" to create  "),
-                Span::styled("Esc", Style::default().fg(Color::Red)),
+                Span::styled("y", Style::default().fg(Color::Green)),
+                Span::raw(" to confirm  "),
+                Span::styled("any other key", Style::default().fg(Color::Red)),
                 Span::raw(" to cancel"),
             ]),
         ];
@@ -999,9 +4587,9 @@ impl App {
         let paragraph = Paragraph::new(text)
             .block(
                 Block::default()
-                    .title(" New Channel ")
+                    .title(" Delete Channel ")
                     .borders(Borders::ALL)
-                    .border_style(Style::default().fg(Color::Cyan)),
+                    .border_style(Style::default().fg(Color::Red)),
             )
             .wrap(Wrap { trim: false });
 
@@ -1009,9 +4597,9 @@ impl App {
         let vertical_chunks = Layout::default()
             .direction(Direction::Vertical)
             .constraints([
-                Constraint::Percentage(30),
-                Constraint::Length(12),
-                Constraint::Percentage(30),
+                Constraint::Percentage(35),
+                Constraint::Length(6),
+                Constraint::Percentage(35),
             ])
             .split(area);
 
@@ -1024,6 +4612,7 @@ impl App {
             ])
             .split(vertical_chunks[1]);
 
+        f.render_widget(Clear, horizontal_chunks[1]);
         f.render_widget(paragraph, horizontal_chunks[1]);
     }
 
@@ -1038,7 +4627,7 @@ impl App {
             "Starting network...".to_string()
         };
 
-        let text = vec![
+        let mut text = vec![
             Line::from(""),
             Line::from(vec![Span::styled(
                 "Connect to Peer",
@@ -1077,6 +4666,29 @@ impl App {
             )]),
         ];
 
+        // List connected peers with their link quality and the Burrow
+        // version they reported via identify
+        let peers = self.peer_manager.get_all_peers();
+        for peer in peers.iter().take(MAX_LISTED_PEERS) {
+            let agent = peer.agent_version.as_deref().unwrap_or("identifying...");
+            let mut line = vec![Span::styled(
+                format!("  {} ", short_peer_id(&peer.peer_id)),
+                Style::default().fg(Color::Blue),
+            )];
+            if let Some(quality) = self.peer_manager.quality(&peer.peer_id) {
+                let color = match quality {
+                    ConnectionQuality::Good => Color::Green,
+                    ConnectionQuality::Fair => Color::Yellow,
+                    ConnectionQuality::Poor => Color::Red,
+                };
+                let rtt = peer.rtt.map(|rtt| format!(" {}ms", rtt.as_millis())).unwrap_or_default();
+                line.push(Span::styled(format!("● {}{} ", quality, rtt), Style::default().fg(color)));
+            }
+            line.push(Span::styled(agent.to_string(), Style::default().fg(Color::Gray)));
+            text.push(Line::from(line));
+        }
+        let listed_peers = peers.len().min(MAX_LISTED_PEERS) as u16;
+
         let paragraph = Paragraph::new(text)
             .block(
                 Block::default()
@@ -1091,7 +4703,7 @@ impl App {
             .direction(Direction::Vertical)
             .constraints([
                 Constraint::Percentage(20),
-                Constraint::Length(18),
+                Constraint::Length(18 + listed_peers),
                 Constraint::Percentage(20),
             ])
             .split(area);
@@ -1124,6 +4736,18 @@ impl App {
                 Span::styled("Ctrl+H", Style::default().fg(Color::Yellow)),
                 Span::raw("       Show this help menu"),
             ]),
+            Line::from(vec![
+                Span::styled("Ctrl+G", Style::default().fg(Color::Yellow)),
+                Span::raw("       Show recent notifications"),
+            ]),
+            Line::from(vec![
+                Span::styled("Ctrl+W", Style::default().fg(Color::Yellow)),
+                Span::raw("       Show your identity details (copyable)"),
+            ]),
+            Line::from(vec![
+                Span::styled("Ctrl+F", Style::default().fg(Color::Yellow)),
+                Span::raw("       Show/hide connected peers panel"),
+            ]),
             Line::from(""),
             Line::from(vec![Span::styled(
                 "Channel Management:",
@@ -1139,6 +4763,62 @@ impl App {
                 Span::styled("↑/↓   ", Style::default().fg(Color::Yellow)),
                 Span::raw("       Navigate between channels"),
             ]),
+            Line::from(vec![
+                Span::styled("Alt+↑/↓", Style::default().fg(Color::Yellow)),
+                Span::raw("      Move channel up/down the list"),
+            ]),
+            Line::from(vec![
+                Span::styled("Ctrl+K", Style::default().fg(Color::Yellow)),
+                Span::raw("       Jump to channel (fuzzy search)"),
+            ]),
+            Line::from(vec![
+                Span::styled("Ctrl+←/→", Style::default().fg(Color::Yellow)),
+                Span::raw("     Resize channel list"),
+            ]),
+            Line::from(vec![
+                Span::styled("Ctrl+R", Style::default().fg(Color::Yellow)),
+                Span::raw("       Request channel info from peers"),
+            ]),
+            Line::from(vec![
+                Span::styled("Ctrl+S", Style::default().fg(Color::Yellow)),
+                Span::raw("       Catch up: resync channel from all peers now"),
+            ]),
+            Line::from(vec![
+                Span::styled("Alt+S", Style::default().fg(Color::Yellow)),
+                Span::raw("        Sync preview: compare channel with each peer, fetch nothing"),
+            ]),
+            Line::from(vec![
+                Span::styled("Ctrl+A", Style::default().fg(Color::Yellow)),
+                Span::raw("       Toggle admins-only posting (admins only)"),
+            ]),
+            Line::from(vec![
+                Span::styled("Ctrl+B", Style::default().fg(Color::Yellow)),
+                Span::raw("       Mute/unmute channel notifications (urgent still notify)"),
+            ]),
+            Line::from(vec![
+                Span::styled("Ctrl+D", Style::default().fg(Color::Yellow)),
+                Span::raw("       Delete channel for all members (asks first)"),
+            ]),
+            Line::from(vec![
+                Span::styled("Alt+R", Style::default().fg(Color::Yellow)),
+                Span::raw("        Mark all channels as read"),
+            ]),
+            Line::from(vec![
+                Span::styled("Alt+N", Style::default().fg(Color::Yellow)),
+                Span::raw("        Rename channel (members only)"),
+            ]),
+            Line::from(vec![
+                Span::styled("Alt+T", Style::default().fg(Color::Yellow)),
+                Span::raw("        Edit channel description (members only)"),
+            ]),
+            Line::from(vec![
+                Span::styled("Alt+K", Style::default().fg(Color::Yellow)),
+                Span::raw("        Edit channel keywords (notify even when muted)"),
+            ]),
+            Line::from(vec![
+                Span::styled("Alt+C", Style::default().fg(Color::Yellow)),
+                Span::raw("        Show message times as time of day (local) or age"),
+            ]),
             Line::from(""),
             Line::from(vec![Span::styled(
                 "Networking:",
@@ -1150,6 +4830,10 @@ impl App {
                 Span::styled("Ctrl+P", Style::default().fg(Color::Yellow)),
                 Span::raw("       Connect to peer (opens dialog)"),
             ]),
+            Line::from(vec![
+                Span::styled("Esc   ", Style::default().fg(Color::Yellow)),
+                Span::raw("       Cancel pending peer connection"),
+            ]),
             Line::from(""),
             Line::from(vec![Span::styled(
                 "Messaging:",
@@ -1161,6 +4845,14 @@ impl App {
                 Span::styled("Enter ", Style::default().fg(Color::Yellow)),
                 Span::raw("       Send message (when channel selected)"),
             ]),
+            Line::from(vec![
+                Span::styled("Alt+Enter", Style::default().fg(Color::Yellow)),
+                Span::raw("    Send as urgent (highlighted, notifies even when muted)"),
+            ]),
+            Line::from(vec![
+                Span::styled("Alt+V ", Style::default().fg(Color::Yellow)),
+                Span::raw("       Send the clipboard's image (or paste with nothing to paste)"),
+            ]),
             Line::from(vec![
                 Span::styled("Type  ", Style::default().fg(Color::Yellow)),
                 Span::raw("       Start typing to compose message"),
@@ -1169,6 +4861,62 @@ impl App {
                 Span::styled("Bksp  ", Style::default().fg(Color::Yellow)),
                 Span::raw("       Delete character"),
             ]),
+            Line::from(vec![
+                Span::styled("PgUp/PgDn", Style::default().fg(Color::Yellow)),
+                Span::raw("    Scroll back through messages / towards the newest"),
+            ]),
+            Line::from(vec![
+                Span::styled("Ctrl+↑/↓", Style::default().fg(Color::Yellow)),
+                Span::raw("     Scroll messages by one line"),
+            ]),
+            Line::from(vec![
+                Span::styled("Tab   ", Style::default().fg(Color::Yellow)),
+                Span::raw("       Select messages (j/k or ↑/↓, Esc to return)"),
+            ]),
+            Line::from(vec![
+                Span::styled("Ctrl+Y", Style::default().fg(Color::Yellow)),
+                Span::raw("       Copy selected (or latest) message (y / Y in message pane)"),
+            ]),
+            Line::from(vec![
+                Span::styled("Ctrl+E", Style::default().fg(Color::Yellow)),
+                Span::raw("       Edit your selected (or latest) message (Esc: cancel)"),
+            ]),
+            Line::from(vec![
+                Span::styled("r     ", Style::default().fg(Color::Yellow)),
+                Span::raw("       Reply to the selected message (in message pane)"),
+            ]),
+            Line::from(vec![
+                Span::styled("R     ", Style::default().fg(Color::Yellow)),
+                Span::raw("       Resend your selected undelivered message (in message pane)"),
+            ]),
+            Line::from(vec![
+                Span::styled("p / P ", Style::default().fg(Color::Yellow)),
+                Span::raw("       Pin/unpin selected message / list pins (in message pane)"),
+            ]),
+            Line::from(vec![
+                Span::styled("o     ", Style::default().fg(Color::Yellow)),
+                Span::raw("       Open the selected message's image (in message pane)"),
+            ]),
+            Line::from(vec![
+                Span::styled("Ctrl+T", Style::default().fg(Color::Yellow)),
+                Span::raw("       Cycle disappearing message timer"),
+            ]),
+            Line::from(vec![
+                Span::styled("Ctrl+X", Style::default().fg(Color::Yellow)),
+                Span::raw("       Hide selected (or latest) message locally"),
+            ]),
+            Line::from(vec![
+                Span::styled("Ctrl+U", Style::default().fg(Color::Yellow)),
+                Span::raw("       Unhide all hidden messages"),
+            ]),
+            Line::from(vec![
+                Span::styled("Ctrl+L", Style::default().fg(Color::Yellow)),
+                Span::raw("       Select link (press again to cycle)"),
+            ]),
+            Line::from(vec![
+                Span::styled("Ctrl+O", Style::default().fg(Color::Yellow)),
+                Span::raw("       Open selected link in browser"),
+            ]),
             Line::from(""),
             Line::from(vec![Span::styled(
                 "Application:",
@@ -1225,3 +4973,75 @@ impl App {
         f.render_widget(paragraph, horizontal_chunks[1]);
     }
 }
+
+/// Shorten a libp2p peer ID for display (first and last 6 chars)
+fn short_peer_id(peer_id: &libp2p::PeerId) -> String {
+    let peer_str = peer_id.to_string();
+    if peer_str.len() > 12 {
+        format!("{}...{}", &peer_str[..6], &peer_str[peer_str.len() - 6..])
+    } else {
+        peer_str
+    }
+}
+
+/// Order channels by most recent message, channels without messages last
+///
+/// The sort is stable, so ties keep the storage order.
+fn sort_by_activity(channels: &mut [Channel], activity: &HashMap<ChannelId, ChannelActivity>) {
+    channels.sort_by_key(|channel| {
+        std::cmp::Reverse(activity.get(&channel.id).and_then(|a| a.last_message_at))
+    });
+}
+
+/// First few words of a quoted message, for reply markers
+fn quote_snippet(text: &str) -> String {
+    const MAX_CHARS: usize = 40;
+    let text = strip_control(text);
+    if text.is_empty() {
+        return "(message expired)".to_string();
+    }
+    if text.chars().count() <= MAX_CHARS {
+        return text.to_string();
+    }
+    let mut snippet: String = text.chars().take(MAX_CHARS).collect();
+    snippet.push('…');
+    snippet
+}
+
+/// Compact human-readable duration, rounded down to the largest whole unit
+fn format_duration(duration: Duration) -> String {
+    let secs = duration.as_secs();
+    if secs >= 24 * 60 * 60 {
+        format!("{}d", secs / (24 * 60 * 60))
+    } else if secs >= 60 * 60 {
+        format!("{}h", secs / (60 * 60))
+    } else if secs >= 60 {
+        format!("{}m", secs / 60)
+    } else {
+        format!("{}s", secs)
+    }
+}
+
+/// Next control request, or never when no control socket is attached
+async fn recv_control(calls: &mut Option<mpsc::UnboundedReceiver<ControlCall>>) -> Option<ControlCall> {
+    match calls {
+        Some(calls) => calls.recv().await,
+        None => std::future::pending().await,
+    }
+}
+
+/// Keys that send, create, hide or connect, which archive mode refuses
+///
+/// Alt chords only view or browse, except pasting an image, which sends it.
+/// Those that edit channels refuse archive mode themselves.
+fn is_write_action(key: &KeyEvent) -> bool {
+    let ctrl = key.modifiers.contains(event::KeyModifiers::CONTROL);
+    let alt = key.modifiers.contains(event::KeyModifiers::ALT);
+    match key.code {
+        KeyCode::Char('n' | 'p' | 'r' | 's' | 'x' | 'u' | 't' | 'a' | 'b' | 'd') if ctrl => true,
+        KeyCode::Char('v') if alt => true,
+        KeyCode::Enter | KeyCode::Backspace => true,
+        KeyCode::Char(_) => !ctrl && !alt,
+        _ => false,
+    }
+}
@@ -0,0 +1,332 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use super::Notification;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant, SystemTime};
+
+/// Similar notifications arriving within this long of each other are combined
+const COALESCE_WINDOW: Duration = Duration::from_secs(3);
+
+/// Shortest time a notification stays up before the next one replaces it
+const MIN_DISPLAY: Duration = Duration::from_secs(1);
+
+/// Most notifications waiting for their turn; the oldest are dropped beyond this
+const MAX_PENDING: usize = 8;
+
+/// Notifications about different things that still read well as one summary
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationCategory {
+    ChannelDiscovered,
+    NewChannel,
+    PeerConnected,
+    NewMessage,
+}
+
+impl NotificationCategory {
+    /// Text for `count` combined notifications of this category
+    pub fn summary(self, count: usize) -> String {
+        match self {
+            Self::ChannelDiscovered => format!("{} new channels discovered", count),
+            Self::NewChannel => format!("{} new channels", count),
+            Self::PeerConnected => format!("Connected to {} peers", count),
+            Self::NewMessage => format!("{} new messages", count),
+        }
+    }
+}
+
+/// The notification on screen plus those waiting to be shown
+///
+/// A notification that matches the one on screen (same text, or same
+/// category) within `COALESCE_WINDOW` is folded into it instead of replacing
+/// it, and anything else waits until the current one has been up for
+/// `MIN_DISPLAY`, so bursts of activity don't make the banner flicker.
+/// Every notification goes into the log as it's pushed, including those
+/// dropped before their turn came.
+pub struct NotificationQueue {
+    current: Option<Notification>,
+    pending: VecDeque<Notification>,
+    log: NotificationLog,
+}
+
+impl NotificationQueue {
+    pub fn new(log: NotificationLog) -> Self {
+        Self {
+            current: None,
+            pending: VecDeque::new(),
+            log,
+        }
+    }
+
+    /// The notification to show, if any
+    pub fn current(&self) -> Option<&Notification> {
+        self.current.as_ref()
+    }
+
+    pub fn push(&mut self, notification: Notification) {
+        let now = notification.timestamp;
+
+        let similar = self
+            .current
+            .iter_mut()
+            .chain(self.pending.iter_mut())
+            .find(|queued| {
+                queued.coalesces_with(&notification)
+                    && now.saturating_duration_since(queued.shown_at) <= COALESCE_WINDOW
+            });
+        if let Some(queued) = similar {
+            queued.count += 1;
+            queued.shown_at = now;
+            self.log.update(queued);
+            return;
+        }
+
+        self.log.push(&notification);
+        match &self.current {
+            Some(current) if now.saturating_duration_since(current.shown_at) < MIN_DISPLAY => {
+                self.pending.push_back(notification);
+                while self.pending.len() > MAX_PENDING {
+                    self.pending.pop_front();
+                }
+            }
+            _ => self.current = Some(notification),
+        }
+    }
+
+    /// Notifications pushed recently, shown or not
+    pub fn log(&self) -> &NotificationLog {
+        &self.log
+    }
+
+    /// Retire the current notification once it expired or someone is
+    /// waiting, and drop log entries past their age
+    pub fn tick(&mut self, now: Instant) {
+        self.log.sweep(now);
+        let Some(current) = &self.current else {
+            return;
+        };
+
+        let waited = now.saturating_duration_since(current.shown_at) >= MIN_DISPLAY;
+        if current.is_expired() || (waited && !self.pending.is_empty()) {
+            self.current = self.pending.pop_front().map(|mut next| {
+                next.shown_at = now;
+                next
+            });
+        }
+    }
+
+    /// Take down the current notification if it's sticky, since whatever it
+    /// reported is over
+    pub fn clear_sticky(&mut self) {
+        if self.current.as_ref().is_some_and(|current| current.sticky) {
+            self.dismiss();
+        }
+    }
+
+    /// Dismiss the current notification, showing the next one waiting
+    pub fn dismiss(&mut self) {
+        let now = Instant::now();
+        self.current = self.pending.pop_front().map(|mut next| {
+            next.shown_at = now;
+            next
+        });
+    }
+}
+
+/// A notification as it was raised, kept for the history view
+#[derive(Clone)]
+pub struct LoggedNotification {
+    pub notification: Notification,
+    pub raised_at: SystemTime,
+}
+
+/// Recent notifications, oldest first
+///
+/// Bounded by both entry count and age so a long-running session doesn't
+/// grow it without limit: pushing past `max_entries` evicts the oldest
+/// entry, and `sweep` drops entries older than `max_age`.
+pub struct NotificationLog {
+    entries: VecDeque<LoggedNotification>,
+    max_entries: usize,
+    max_age: Duration,
+}
+
+impl NotificationLog {
+    pub fn new(max_entries: usize, max_age: Duration) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            max_entries,
+            max_age,
+        }
+    }
+
+    /// Record a new notification
+    pub fn push(&mut self, notification: &Notification) {
+        self.entries.push_back(LoggedNotification {
+            notification: notification.clone(),
+            raised_at: SystemTime::now(),
+        });
+        while self.entries.len() > self.max_entries {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Replace the entry for a notification that had more combined into it
+    ///
+    /// Entries are matched on the notification's creation instant; one
+    /// already evicted or swept stays gone.
+    pub fn update(&mut self, notification: &Notification) {
+        let entry = self
+            .entries
+            .iter_mut()
+            .rev()
+            .find(|entry| entry.notification.timestamp == notification.timestamp);
+        if let Some(entry) = entry {
+            entry.notification = notification.clone();
+        }
+    }
+
+    /// Drop entries older than the maximum age
+    pub fn sweep(&mut self, now: Instant) {
+        while self
+            .entries
+            .front()
+            .is_some_and(|oldest| now.saturating_duration_since(oldest.notification.timestamp) > self.max_age)
+        {
+            self.entries.pop_front();
+        }
+    }
+
+    /// Entries from newest to oldest
+    pub fn newest_first(&self) -> impl Iterator<Item = &LoggedNotification> {
+        self.entries.iter().rev()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tui::NotificationLevel;
+
+    fn queue() -> NotificationQueue {
+        NotificationQueue::new(NotificationLog::new(10, Duration::from_secs(60)))
+    }
+
+    fn notification(message: &str, age: Duration) -> Notification {
+        let timestamp = Instant::now() - age;
+        Notification {
+            message: message.to_string(),
+            level: NotificationLevel::Info,
+            timestamp,
+            shown_at: timestamp,
+            sticky: false,
+            category: None,
+            count: 1,
+        }
+    }
+
+    #[test]
+    fn test_oldest_evicted_past_max_entries() {
+        let mut log = NotificationLog::new(2, Duration::from_secs(60));
+        let first = notification("first", Duration::from_secs(3));
+
+        log.push(&first);
+        assert_eq!(log.len(), 1);
+
+        log.push(&notification("second", Duration::from_secs(2)));
+        log.push(&notification("third", Duration::from_secs(1)));
+
+        let messages: Vec<_> = log.newest_first().map(|e| e.notification.message.as_str()).collect();
+        assert_eq!(messages, vec!["third", "second"]);
+    }
+
+    #[test]
+    fn test_sweep_drops_old_entries() {
+        let mut log = NotificationLog::new(10, Duration::from_secs(60));
+        log.push(&notification("stale", Duration::from_secs(120)));
+        log.push(&notification("recent", Duration::from_secs(5)));
+
+        log.sweep(Instant::now());
+
+        let messages: Vec<_> = log.newest_first().map(|e| e.notification.message.as_str()).collect();
+        assert_eq!(messages, vec!["recent"]);
+    }
+
+    #[test]
+    fn test_burst_coalesced_by_category() {
+        let mut queue = queue();
+        for name in ["general", "random", "dev"] {
+            let mut discovered = notification(&format!("New channel discovered: {}", name), Duration::ZERO);
+            discovered.category = Some(NotificationCategory::ChannelDiscovered);
+            queue.push(discovered);
+        }
+
+        assert_eq!(queue.current().unwrap().text(), "3 new channels discovered");
+        assert!(queue.pending.is_empty());
+    }
+
+    #[test]
+    fn test_identical_notifications_counted() {
+        let mut queue = queue();
+        queue.push(notification("Message not delivered yet", Duration::ZERO));
+        queue.push(notification("Message not delivered yet", Duration::ZERO));
+
+        assert_eq!(queue.current().unwrap().text(), "Message not delivered yet (×2)");
+    }
+
+    #[test]
+    fn test_next_notification_waits_for_min_display() {
+        let mut queue = queue();
+        queue.push(notification("first", Duration::ZERO));
+        queue.push(notification("second", Duration::ZERO));
+
+        // Too soon to replace the first one
+        queue.tick(Instant::now());
+        assert_eq!(queue.current().unwrap().message, "first");
+
+        queue.tick(Instant::now() + MIN_DISPLAY);
+        assert_eq!(queue.current().unwrap().message, "second");
+    }
+
+    #[test]
+    fn test_log_follows_coalesced_notification() {
+        let mut queue = queue();
+        queue.push(notification("Message not delivered yet", Duration::ZERO));
+        queue.push(notification("Message not delivered yet", Duration::ZERO));
+
+        let texts: Vec<_> = queue.log().newest_first().map(|e| e.notification.text()).collect();
+        assert_eq!(texts, vec!["Message not delivered yet (×2)"]);
+    }
+
+    #[test]
+    fn test_log_records_notifications_never_shown() {
+        let mut queue = queue();
+        for n in 0..MAX_PENDING + 2 {
+            queue.push(notification(&format!("notice {}", n), Duration::ZERO));
+        }
+
+        // Some were dropped from the queue before their turn, but not from the log
+        assert_eq!(queue.pending.len(), MAX_PENDING);
+        assert_eq!(queue.log().len(), MAX_PENDING + 2);
+    }
+}
@@ -0,0 +1,111 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::types::{Channel, ChannelId};
+
+/// The user's own order for the channel list
+///
+/// Local only, never shared with peers. Channels missing from the saved
+/// order (ones that appeared since) go after the ordered ones, in the order
+/// storage lists them; IDs of channels that are gone are skipped, and
+/// dropped the next time a channel is moved.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ChannelOrder {
+    ids: Vec<ChannelId>,
+}
+
+impl ChannelOrder {
+    /// Read a saved order, skipping anything that isn't a channel ID
+    pub fn parse(value: &str) -> Self {
+        Self {
+            ids: value
+                .split(',')
+                .filter_map(|id| uuid::Uuid::parse_str(id.trim()).ok())
+                .map(ChannelId)
+                .collect(),
+        }
+    }
+
+    /// The order as saved in settings
+    pub fn to_setting(&self) -> String {
+        self.ids.iter().map(|id| id.0.to_string()).collect::<Vec<_>>().join(",")
+    }
+
+    /// Sort `channels` into this order
+    pub fn apply(&self, channels: &mut [Channel]) {
+        channels.sort_by_key(|channel| {
+            self.ids
+                .iter()
+                .position(|id| *id == channel.id)
+                .unwrap_or(usize::MAX)
+        });
+    }
+
+    /// Move the channel at `index` one place up or down the list, and make
+    /// the resulting list the saved order
+    ///
+    /// Returns the channel's new index, or None if it's already at that end.
+    pub fn move_channel(&mut self, channels: &mut [Channel], index: usize, up: bool) -> Option<usize> {
+        let target = if up { index.checked_sub(1)? } else { index + 1 };
+        if target >= channels.len() {
+            return None;
+        }
+
+        channels.swap(index, target);
+        self.ids = channels.iter().map(|c| c.id).collect();
+        Some(target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PeerId;
+
+    fn channels(names: &[&str]) -> Vec<Channel> {
+        let creator = PeerId::new();
+        names.iter().map(|name| Channel::new(name.to_string(), creator)).collect()
+    }
+
+    fn names(channels: &[Channel]) -> Vec<&str> {
+        channels.iter().map(|c| c.get_name().as_str()).collect()
+    }
+
+    #[test]
+    fn test_new_channels_appended_and_gone_ones_skipped() {
+        let mut list = channels(&["a", "b", "c"]);
+        let mut order = ChannelOrder::default();
+        order.move_channel(&mut list, 2, true);
+        assert_eq!(names(&list), ["a", "c", "b"]);
+
+        // Reload: "a" was deleted, "d" appeared; storage lists newest first
+        let saved = ChannelOrder::parse(&order.to_setting());
+        assert_eq!(saved, order);
+        let mut reloaded: Vec<Channel> = channels(&["d"]).into_iter().chain(list[1..].iter().cloned()).collect();
+        reloaded.swap(1, 2);
+        saved.apply(&mut reloaded);
+        assert_eq!(names(&reloaded), ["c", "b", "d"]);
+    }
+
+    #[test]
+    fn test_move_stops_at_the_ends() {
+        let mut list = channels(&["a", "b"]);
+        let mut order = ChannelOrder::parse("not-an-id,");
+        assert_eq!(order.move_channel(&mut list, 0, true), None);
+        assert_eq!(order.move_channel(&mut list, 1, false), None);
+        assert_eq!(order.move_channel(&mut list, 0, false), Some(1));
+        assert_eq!(names(&list), ["b", "a"]);
+    }
+}
@@ -0,0 +1,106 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::network::peer::PeerInfo;
+use std::time::SystemTime;
+
+use super::{format_duration, short_peer_id};
+
+/// Most peers listed in the panel before the rest are summed up
+const MAX_PANEL_ROWS: usize = 6;
+
+/// One connected peer, as the peers panel lists it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PeerRow {
+    pub peer: String,
+    pub uptime: String,
+    pub address: String,
+}
+
+/// Rows for the peers panel, longest connected first
+///
+/// Ordered by connection time so rows keep their place as peers come and
+/// go, rather than following the peer manager's map order. Peers beyond
+/// `MAX_PANEL_ROWS` are left out; the caller reports how many.
+pub fn panel_rows(peers: &[&PeerInfo], now: SystemTime) -> Vec<PeerRow> {
+    let mut peers = peers.to_vec();
+    peers.sort_by_key(|peer| (peer.connected_at, peer.peer_id.to_string()));
+
+    peers
+        .into_iter()
+        .take(MAX_PANEL_ROWS)
+        .map(|peer| PeerRow {
+            peer: short_peer_id(&peer.peer_id),
+            // A clock stepped back since the connection shows as just connected
+            uptime: format_duration(now.duration_since(peer.connected_at).unwrap_or_default()),
+            address: peer.addresses.first().map_or_else(|| "unknown address".to_string(), |a| a.to_string()),
+        })
+        .collect()
+}
+
+/// Height of the peers panel with its border: a row per listed peer, one
+/// for the peers left out, and one for "no peers" when there are none
+pub fn panel_height(peer_count: usize) -> u16 {
+    let rows = match peer_count {
+        0 => 1,
+        n if n > MAX_PANEL_ROWS => MAX_PANEL_ROWS + 1,
+        n => n,
+    };
+    rows as u16 + 2
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::peer::PeerManager;
+    use std::time::Duration;
+
+    #[test]
+    fn test_rows_ordered_by_connection_time() {
+        let mut manager = PeerManager::new();
+        let first = libp2p::PeerId::random();
+        let second = libp2p::PeerId::random();
+        manager.add_peer(first, Some("/ip4/192.168.1.5/tcp/9000".parse().unwrap()));
+        std::thread::sleep(Duration::from_millis(5));
+        manager.add_peer(second, None);
+
+        let connected_at = manager.get_peer(&first).unwrap().connected_at;
+        let rows = panel_rows(&manager.get_all_peers(), connected_at + Duration::from_secs(150));
+        assert_eq!(
+            rows[0],
+            PeerRow {
+                peer: short_peer_id(&first),
+                uptime: "2m".to_string(),
+                address: "/ip4/192.168.1.5/tcp/9000".to_string(),
+            }
+        );
+        assert_eq!(rows[1].peer, short_peer_id(&second));
+        assert_eq!(rows[1].address, "unknown address");
+
+        // A disconnected peer's row goes, and the others keep their order
+        manager.remove_peer(&first);
+        let rows = panel_rows(&manager.get_all_peers(), connected_at);
+        assert_eq!(rows.len(), 1);
+        assert_eq!(rows[0].uptime, "0s");
+    }
+
+    #[test]
+    fn test_panel_height_fits_rows() {
+        assert_eq!(panel_height(0), 3);
+        assert_eq!(panel_height(2), 4);
+        assert_eq!(panel_height(MAX_PANEL_ROWS), MAX_PANEL_ROWS as u16 + 2);
+        assert_eq!(panel_height(40), MAX_PANEL_ROWS as u16 + 3);
+    }
+}
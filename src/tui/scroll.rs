@@ -0,0 +1,166 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+/// How far the message pane is scrolled back from the newest messages
+///
+/// Sticks to the bottom, so new messages come into view, until the user
+/// scrolls up; scrolling back down to the end sticks again. The extent is
+/// only known once rendered, so `fit` is called with it on every frame.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MessageScroll {
+    offset: u16,  // First wrapped line shown
+    max: u16,  // Offset that shows the last line at the bottom
+    page: u16,  // Rows scrolled by a page step
+    follow: bool,  // Keep the newest messages in view
+}
+
+impl Default for MessageScroll {
+    fn default() -> Self {
+        Self {
+            offset: 0,
+            max: 0,
+            page: 1,
+            follow: true,
+        }
+    }
+}
+
+impl MessageScroll {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Row offset to render the message paragraph at
+    pub fn offset(&self) -> u16 {
+        self.offset
+    }
+
+    /// Whether the view is scrolled up away from the newest messages
+    pub fn is_scrolled_back(&self) -> bool {
+        !self.follow
+    }
+
+    /// Fit the offset to `lines` wrapped lines shown `height` rows at a time
+    pub fn fit(&mut self, lines: usize, height: u16) {
+        self.max = u16::try_from(lines.saturating_sub(usize::from(height))).unwrap_or(u16::MAX);
+        self.page = height.saturating_sub(1).max(1);
+        self.offset = if self.follow { self.max } else { self.offset.min(self.max) };
+    }
+
+    /// Scroll one page towards older messages
+    pub fn page_up(&mut self) {
+        self.scroll_to(self.offset.saturating_sub(self.page));
+    }
+
+    /// Scroll one page towards newer messages, stopping at the newest
+    pub fn page_down(&mut self) {
+        self.scroll_to(self.offset.saturating_add(self.page));
+    }
+
+    /// Scroll one line towards older messages
+    pub fn line_up(&mut self) {
+        self.scroll_to(self.offset.saturating_sub(1));
+    }
+
+    /// Scroll one line towards newer messages, stopping at the newest
+    pub fn line_down(&mut self) {
+        self.scroll_to(self.offset.saturating_add(1));
+    }
+
+    /// Jump back to the newest messages and follow them again
+    pub fn to_bottom(&mut self) {
+        self.scroll_to(self.max);
+    }
+
+    fn scroll_to(&mut self, offset: u16) {
+        self.offset = offset.min(self.max);
+        self.follow = self.offset == self.max;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_follows_new_messages_until_scrolled_back() {
+        let mut scroll = MessageScroll::new();
+        scroll.fit(5, 10);
+        assert_eq!(scroll.offset(), 0);
+
+        // Overflowing content keeps the last line at the bottom
+        scroll.fit(30, 10);
+        assert_eq!(scroll.offset(), 20);
+
+        scroll.page_up();
+        assert_eq!(scroll.offset(), 11);
+        assert!(scroll.is_scrolled_back());
+
+        // More messages arriving leave the view where the user put it
+        scroll.fit(40, 10);
+        assert_eq!(scroll.offset(), 11);
+
+        scroll.page_down();
+        scroll.page_down();
+        assert_eq!(scroll.offset(), 29);
+        assert!(scroll.is_scrolled_back());
+        scroll.page_down();
+        assert_eq!(scroll.offset(), 30);
+        assert!(!scroll.is_scrolled_back());
+
+        scroll.fit(45, 10);
+        assert_eq!(scroll.offset(), 35);
+    }
+
+    #[test]
+    fn test_offset_clamped_to_content() {
+        let mut scroll = MessageScroll::new();
+        scroll.fit(30, 10);
+        for _ in 0..5 {
+            scroll.page_up();
+        }
+        assert_eq!(scroll.offset(), 0);
+
+        // Content shrinking under the view (a channel switch, hidden
+        // messages) can't leave it past the end
+        scroll.page_down();
+        scroll.fit(12, 10);
+        assert_eq!(scroll.offset(), 2);
+        scroll.fit(3, 10);
+        assert_eq!(scroll.offset(), 0);
+
+        scroll.to_bottom();
+        assert!(!scroll.is_scrolled_back());
+    }
+
+    #[test]
+    fn test_line_steps_stop_at_both_ends() {
+        let mut scroll = MessageScroll::new();
+        scroll.fit(12, 10);
+        scroll.line_down();
+        assert_eq!(scroll.offset(), 2);
+
+        scroll.line_up();
+        scroll.line_up();
+        assert_eq!(scroll.offset(), 0);
+        scroll.line_up();
+        assert_eq!(scroll.offset(), 0);
+        assert!(scroll.is_scrolled_back());
+
+        scroll.line_down();
+        scroll.line_down();
+        assert!(!scroll.is_scrolled_back());
+    }
+}
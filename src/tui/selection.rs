@@ -0,0 +1,91 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::types::{Channel, ChannelId};
+
+/// Where the selection belongs after the channel list changed
+///
+/// `previous` holds the list's channel IDs before the change and `selected`
+/// the index into it. The same channel stays selected wherever it moved to;
+/// if it's gone, the nearest channel after it in the old list that survived
+/// is chosen, then the nearest before it. No selection stays that way.
+pub fn resync(previous: &[ChannelId], selected: Option<usize>, channels: &[Channel]) -> Option<usize> {
+    let selected = selected?;
+    if channels.is_empty() {
+        return None;
+    }
+    let position = |id: &ChannelId| channels.iter().position(|c| c.id == *id);
+
+    // An index past the old list can only come from a stale selection
+    let Some(id) = previous.get(selected) else {
+        return Some(selected.min(channels.len() - 1));
+    };
+
+    position(id)
+        .or_else(|| previous[selected + 1..].iter().find_map(position))
+        .or_else(|| previous[..selected].iter().rev().find_map(position))
+        .or(Some(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::PeerId;
+
+    fn channels(count: usize) -> Vec<Channel> {
+        let creator = PeerId::new();
+        (0..count).map(|i| Channel::new(format!("channel {}", i), creator)).collect()
+    }
+
+    fn ids(channels: &[Channel]) -> Vec<ChannelId> {
+        channels.iter().map(|c| c.id).collect()
+    }
+
+    #[test]
+    fn test_deleting_selected_channel_selects_a_neighbour() {
+        let mut list = channels(4);
+        let previous = ids(&list);
+
+        // The selected channel is deleted mid-session
+        list.remove(1);
+        let index = resync(&previous, Some(1), &list);
+        assert_eq!(index.map(|i| list[i].id), Some(previous[2]));
+
+        // Deleting the last one falls back to the one before it
+        let previous = ids(&list);
+        list.pop();
+        let index = resync(&previous, Some(2), &list);
+        assert_eq!(index.map(|i| list[i].id), Some(previous[1]));
+
+        // Deleting the only channel leaves nothing selected
+        let mut list = channels(1);
+        let previous = ids(&list);
+        list.clear();
+        assert_eq!(resync(&previous, Some(0), &list), None);
+    }
+
+    #[test]
+    fn test_selection_follows_the_channel_when_reordered() {
+        let mut list = channels(3);
+        let previous = ids(&list);
+        list.reverse();
+        list.push(channels(1).remove(0));
+
+        assert_eq!(resync(&previous, Some(0), &list), Some(2));
+        assert_eq!(resync(&previous, None, &list), None);
+        // A stale index is clamped rather than left pointing past the end
+        assert_eq!(resync(&previous, Some(7), &list), Some(3));
+    }
+}
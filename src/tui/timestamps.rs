@@ -0,0 +1,111 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use chrono::{DateTime, Local, TimeZone, Utc};
+use std::fmt;
+use std::time::SystemTime;
+
+use super::format_duration;
+
+/// How the message list shows when each message was sent
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TimestampStyle {
+    /// Time of day, `HH:MM` in local time
+    #[default]
+    Absolute,
+    /// Time since sending, such as `2m ago`
+    Relative,
+}
+
+impl TimestampStyle {
+    pub fn toggled(self) -> Self {
+        match self {
+            Self::Absolute => Self::Relative,
+            Self::Relative => Self::Absolute,
+        }
+    }
+
+    /// Format a message's send time as seen at `now`
+    ///
+    /// A peer whose clock runs ahead can send messages from the future;
+    /// those show as just sent rather than a negative age.
+    pub fn format(self, sent: SystemTime, now: SystemTime) -> String {
+        self.format_in(sent, now, &Local)
+    }
+
+    fn format_in<Tz: TimeZone>(self, sent: SystemTime, now: SystemTime, zone: &Tz) -> String
+    where
+        Tz::Offset: fmt::Display,
+    {
+        match self {
+            Self::Absolute => format_in(sent, zone, "%H:%M"),
+            Self::Relative => match now.duration_since(sent) {
+                Ok(age) if age.as_secs() >= 60 => format!("{} ago", format_duration(age)),
+                _ => "now".to_string(),
+            },
+        }
+    }
+}
+
+/// Format a time as `YYYY-MM-DD HH:MM` in local time
+pub fn date_time(time: SystemTime) -> String {
+    format_in(time, &Local, "%Y-%m-%d %H:%M")
+}
+
+/// Format a time in `zone`, with `format` in strftime syntax
+fn format_in<Tz: TimeZone>(time: SystemTime, zone: &Tz, format: &str) -> String
+where
+    Tz::Offset: fmt::Display,
+{
+    DateTime::<Utc>::from(time).with_timezone(zone).format(format).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+    use std::time::{Duration, UNIX_EPOCH};
+
+    #[test]
+    fn test_absolute_and_relative_times() {
+        // 2023-11-14 22:13:20 UTC
+        let sent = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let now = sent + Duration::from_secs(150);
+        assert_eq!(TimestampStyle::Absolute.format_in(sent, now, &Utc), "22:13");
+        assert_eq!(TimestampStyle::Relative.format(sent, now), "2m ago");
+        assert_eq!(TimestampStyle::Relative.format(sent, sent + Duration::from_secs(59)), "now");
+        assert_eq!(TimestampStyle::Relative.format(sent, sent + Duration::from_secs(3 * 86_400)), "3d ago");
+        assert_eq!(TimestampStyle::Absolute.toggled(), TimestampStyle::Relative);
+    }
+
+    #[test]
+    fn test_future_send_times_do_not_panic() {
+        let now = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let skewed = now + Duration::from_secs(600);
+        assert_eq!(TimestampStyle::Relative.format(skewed, now), "now");
+        assert_eq!(TimestampStyle::Absolute.format_in(skewed, now, &Utc), "22:23");
+        assert_eq!(TimestampStyle::Absolute.format_in(UNIX_EPOCH - Duration::from_secs(60), now, &Utc), "23:59");
+    }
+
+    #[test]
+    fn test_times_shown_in_the_given_zone() {
+        // 2023-11-14 22:13:20 UTC is already the 15th in Tokyo
+        let sent = UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+        let tokyo = FixedOffset::east_opt(9 * 3_600).unwrap();
+        assert_eq!(TimestampStyle::Absolute.format_in(sent, sent, &tokyo), "07:13");
+        assert_eq!(format_in(sent, &tokyo, "%Y-%m-%d %H:%M"), "2023-11-15 07:13");
+        assert_eq!(format_in(sent, &Utc, "%Y-%m-%d %H:%M"), "2023-11-14 22:13");
+    }
+}
@@ -0,0 +1,236 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use crate::storage::ReadMarker;
+use crate::types::{ChannelId, Message, PeerId};
+use std::collections::HashMap;
+
+/// Unread message counts, measured from each channel's read marker
+///
+/// A message is unread if someone else wrote it and it comes after the
+/// newest message the user has seen in its channel: in DAG order when
+/// counting history, and by arrival for messages received since. Authors'
+/// clocks play no part, so a skewed one can't hide a message. Channels
+/// never opened have no marker, so everything others wrote there is unread.
+#[derive(Debug, Default)]
+pub struct UnreadTracker {
+    markers: HashMap<ChannelId, ReadMarker>,
+    counts: HashMap<ChannelId, usize>,
+}
+
+impl UnreadTracker {
+    /// Start from previously saved read markers
+    pub fn new(markers: HashMap<ChannelId, ReadMarker>) -> Self {
+        Self {
+            markers,
+            counts: HashMap::new(),
+        }
+    }
+
+    /// Recompute a channel's count from its full history, in DAG order
+    pub fn recount(&mut self, channel: ChannelId, messages: &[Message], own: PeerId) {
+        let unread = &messages[read_up_to(messages, self.markers.get(&channel))..];
+        let count = unread.iter().filter(|m| m.author != own).count();
+        self.counts.insert(channel, count);
+    }
+
+    /// Count a message that just arrived, unless we wrote it
+    ///
+    /// It arrived after the marker was set, so the user hasn't seen it
+    /// wherever it lands in the DAG.
+    pub fn observe(&mut self, message: &Message, own: PeerId) {
+        if message.author != own {
+            *self.counts.entry(message.channel_id).or_default() += 1;
+        }
+    }
+
+    /// Mark everything in `messages`, in DAG order, as read
+    ///
+    /// Returns the new marker when it moved forward, so it can be saved.
+    pub fn mark_read(&mut self, channel: ChannelId, messages: &[Message]) -> Option<ReadMarker> {
+        self.counts.remove(&channel);
+
+        let newest = messages.last()?;
+        if read_up_to(messages, self.markers.get(&channel)) == messages.len() {
+            return None;
+        }
+
+        let marker = ReadMarker {
+            message_id: newest.id,
+            created_at_ms: newest.created_at_ms,
+        };
+        self.markers.insert(channel, marker);
+        Some(marker)
+    }
+
+    /// Unread messages across all channels
+    pub fn total(&self) -> usize {
+        self.counts.values().sum()
+    }
+
+    /// Channels with at least one unread message
+    pub fn unread_channels(&self) -> Vec<ChannelId> {
+        self.counts
+            .iter()
+            .filter(|(_, count)| **count > 0)
+            .map(|(channel, _)| *channel)
+            .collect()
+    }
+
+    /// Number of unread messages in a channel
+    pub fn count(&self, channel: &ChannelId) -> usize {
+        self.counts.get(channel).copied().unwrap_or(0)
+    }
+
+    /// Forget a channel (e.g. once it's deleted)
+    pub fn remove(&mut self, channel: &ChannelId) {
+        self.markers.remove(channel);
+        self.counts.remove(channel);
+    }
+}
+
+/// How many of `messages`, in DAG order, the marker covers
+///
+/// Everything up to the marked message is read. If that message isn't
+/// there (it expired, or was hidden), its creation time is all there is
+/// to go on.
+fn read_up_to(messages: &[Message], marker: Option<&ReadMarker>) -> usize {
+    let Some(marker) = marker else {
+        return 0;
+    };
+    match messages.iter().position(|m| m.id == marker.message_id) {
+        Some(index) => index + 1,
+        None => messages
+            .iter()
+            .rposition(|m| (m.created_at_ms, m.id) <= (marker.created_at_ms, marker.message_id))
+            .map_or(0, |index| index + 1),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{MessageContent, VectorClock};
+
+    fn message(channel: ChannelId, author: PeerId, created_at_ms: u64) -> Message {
+        let mut message = Message::new(
+            channel,
+            author,
+            MessageContent::new(format!("at {}", created_at_ms)),
+            VectorClock::new(),
+            created_at_ms,
+        );
+        message.created_at_ms = created_at_ms;
+        message
+    }
+
+    #[test]
+    fn test_counts_from_marker() {
+        let channel = ChannelId::new();
+        let me = PeerId::new();
+        let them = PeerId::new();
+        let history = vec![
+            message(channel, them, 1_000),
+            message(channel, them, 2_000),
+            message(channel, me, 3_000),
+            message(channel, them, 4_000),
+        ];
+
+        let mut unread = UnreadTracker::default();
+        unread.recount(channel, &history, me);
+        assert_eq!(unread.count(&channel), 3);
+
+        let marker = unread.mark_read(channel, &history[..2]).unwrap();
+        assert_eq!(marker.message_id, history[1].id);
+
+        // A restart rebuilds the count from the saved marker
+        let mut restarted = UnreadTracker::new(HashMap::from([(channel, marker)]));
+        restarted.recount(channel, &history, me);
+        assert_eq!(restarted.count(&channel), 1);
+
+        restarted.observe(&message(channel, them, 5_000), me);
+        restarted.observe(&message(channel, me, 6_000), me);
+        assert_eq!(restarted.count(&channel), 2);
+    }
+
+    #[test]
+    fn test_mark_all_read_clears_counts_after_restart() {
+        let me = PeerId::new();
+        let them = PeerId::new();
+        let histories: HashMap<ChannelId, Vec<Message>> = (0..3)
+            .map(|_| {
+                let channel = ChannelId::new();
+                (channel, vec![message(channel, them, 1_000), message(channel, them, 2_000)])
+            })
+            .collect();
+
+        let mut unread = UnreadTracker::default();
+        for (channel, history) in &histories {
+            unread.recount(*channel, history, me);
+        }
+        assert_eq!(unread.total(), 6);
+        assert_eq!(unread.unread_channels().len(), 3);
+
+        let markers: HashMap<ChannelId, ReadMarker> = unread
+            .unread_channels()
+            .into_iter()
+            .filter_map(|channel| Some((channel, unread.mark_read(channel, &histories[&channel])?)))
+            .collect();
+        assert_eq!(unread.total(), 0);
+
+        // The saved markers leave nothing unread on the next start
+        let mut restarted = UnreadTracker::new(markers);
+        for (channel, history) in &histories {
+            restarted.recount(*channel, history, me);
+        }
+        assert_eq!(restarted.total(), 0);
+    }
+
+    #[test]
+    fn test_marker_only_moves_forward() {
+        let channel = ChannelId::new();
+        let them = PeerId::new();
+        let history = vec![message(channel, them, 1_000), message(channel, them, 2_000)];
+
+        let mut unread = UnreadTracker::default();
+        assert!(unread.mark_read(channel, &history).is_some());
+        assert!(unread.mark_read(channel, &history).is_none());
+        assert!(unread.mark_read(channel, &history[..1]).is_none());
+
+    }
+
+    #[test]
+    fn test_skewed_clocks_dont_hide_messages() {
+        let channel = ChannelId::new();
+        let me = PeerId::new();
+        let them = PeerId::new();
+        let mut history = vec![message(channel, them, 2_000)];
+
+        let mut unread = UnreadTracker::default();
+        let marker = unread.mark_read(channel, &history).unwrap();
+
+        // A clock running behind stamps the reply before what it answers
+        let behind = message(channel, them, 1_000);
+        unread.observe(&behind, me);
+        assert_eq!(unread.count(&channel), 1);
+
+        // Counting history again goes by DAG order, not the stamps
+        history.push(behind);
+        let mut restarted = UnreadTracker::new(HashMap::from([(channel, marker)]));
+        restarted.recount(channel, &history, me);
+        assert_eq!(restarted.count(&channel), 1);
+        assert_eq!(restarted.mark_read(channel, &history).unwrap().message_id, history[1].id);
+    }
+}
@@ -0,0 +1,117 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+use std::path::Path;
+
+/// Details of the local identity, shown together for support and verification
+///
+/// Each value is its own row so the selected one can be copied on its own.
+#[derive(Debug, Clone, Default)]
+pub struct WhoAmI {
+    rows: Vec<(&'static str, String)>,
+    selected: usize,
+}
+
+impl WhoAmI {
+    pub fn new(
+        libp2p_peer_id: &libp2p::PeerId,
+        app_peer_id: &crate::types::PeerId,
+        fingerprint: Option<&str>,
+        public_bundle: Option<&str>,
+        listen_addrs: &[String],
+        observed_addrs: &[String],
+        data_dir: Option<&Path>,
+    ) -> Self {
+        let mut rows = vec![
+            ("Peer ID", libp2p_peer_id.to_string()),
+            ("App peer ID", app_peer_id.0.to_string()),
+        ];
+        if let Some(fingerprint) = fingerprint {
+            rows.push(("Key fingerprint", fingerprint.to_string()));
+        }
+        if let Some(public_bundle) = public_bundle {
+            rows.push(("Public keys", public_bundle.to_string()));
+        }
+        rows.extend(listen_addrs.iter().map(|a| ("Listening on", a.clone())));
+        rows.extend(observed_addrs.iter().map(|a| ("Seen by peers as", a.clone())));
+        if let Some(data_dir) = data_dir {
+            rows.push(("Data directory", data_dir.display().to_string()));
+        }
+
+        Self { rows, selected: 0 }
+    }
+
+    pub fn rows(&self) -> &[(&'static str, String)] {
+        &self.rows
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    /// Value of the selected row, to copy
+    pub fn selected_value(&self) -> Option<&str> {
+        self.rows.get(self.selected).map(|(_, value)| value.as_str())
+    }
+
+    pub fn select_next(&mut self) {
+        if !self.rows.is_empty() {
+            self.selected = (self.selected + 1) % self.rows.len();
+        }
+    }
+
+    pub fn select_previous(&mut self) {
+        if !self.rows.is_empty() {
+            self.selected = (self.selected + self.rows.len() - 1) % self.rows.len();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rows_and_selection() {
+        let libp2p_peer_id = libp2p::PeerId::random();
+        let app_peer_id = crate::types::PeerId::from_libp2p(&libp2p_peer_id);
+        let listen = vec!["/ip4/127.0.0.1/tcp/9000".to_string(), "/ip4/10.0.0.2/tcp/9000".to_string()];
+        let observed = vec!["/ip4/203.0.113.7/tcp/41000".to_string()];
+
+        let mut whoami = WhoAmI::new(
+            &libp2p_peer_id,
+            &app_peer_id,
+            Some("ab12 cd34"),
+            Some(r#"{"peer_id":"12D3"}"#),
+            &listen,
+            &observed,
+            Some(Path::new("/tmp/burrow")),
+        );
+
+        let labels: Vec<_> = whoami.rows().iter().map(|(label, _)| *label).collect();
+        assert_eq!(
+            labels,
+            vec!["Peer ID", "App peer ID", "Key fingerprint", "Public keys", "Listening on", "Listening on", "Seen by peers as", "Data directory"]
+        );
+        assert_eq!(whoami.selected_value(), Some(libp2p_peer_id.to_string().as_str()));
+
+        // Selection wraps both ways
+        whoami.select_previous();
+        assert_eq!(whoami.selected_value(), Some("/tmp/burrow"));
+        whoami.select_next();
+        whoami.select_next();
+        assert_eq!(whoami.selected_value(), Some(app_peer_id.0.to_string().as_str()));
+    }
+}
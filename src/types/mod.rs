@@ -13,12 +13,20 @@
 // You should have received a copy of the GNU Affero General Public License
 // along with this program.  If not, see <https://www.gnu.org/licenses/>.
 
-use crate::crdt::{HybridLogicalClock, LWWRegister, ORSet};
+use crate::crdt::{AuthoredRegister, HybridLogicalClock, LWWRegister, ORSet, Timestamp};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::time::SystemTime;
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::fmt;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use uuid::Uuid;
 
+/// Number of hex digits shown in short peer/channel IDs
+const SHORT_ID_CHARS: usize = 8;
+
+/// Prefix of `Message::canonical_bytes`, versioning the layout
+const CANONICAL_MESSAGE_TAG: &[u8] = b"burrow message v1";
+
 /// Peer identifier derived from libp2p PeerId (public key hash)
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize, PartialOrd, Ord)]
 pub struct PeerId(pub Uuid);
@@ -45,6 +53,15 @@ impl PeerId {
 
         Self(Uuid::from_bytes(uuid_bytes))
     }
+
+    /// Short display form of the ID
+    ///
+    /// Uses the trailing hex digits, since the leading bytes of a libp2p-derived
+    /// ID are a multihash prefix shared by every peer.
+    pub fn short(&self) -> String {
+        let hex = self.0.simple().to_string();
+        hex[hex.len() - SHORT_ID_CHARS..].to_string()
+    }
 }
 
 impl Default for PeerId {
@@ -61,6 +78,15 @@ impl ChannelId {
     pub fn new() -> Self {
         Self(Uuid::now_v7())
     }
+
+    /// Short display form of the ID
+    ///
+    /// Uses the random tail of the UUID v7; the head is a timestamp, so
+    /// channels created close together would look alike.
+    pub fn short(&self) -> String {
+        let hex = self.0.simple().to_string();
+        hex[hex.len() - SHORT_ID_CHARS..].to_string()
+    }
 }
 
 impl Default for ChannelId {
@@ -86,15 +112,19 @@ impl Default for MessageId {
 }
 
 /// Vector clock for causal ordering
+///
+/// Kept sorted by peer so equal clocks always serialize to the same bytes.
+/// bincode encodes a `BTreeMap` exactly like the `HashMap` this used to be,
+/// so stored rows and older peers still decode.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct VectorClock {
-    pub clocks: HashMap<PeerId, u64>,
+    pub clocks: BTreeMap<PeerId, u64>,
 }
 
 impl VectorClock {
     pub fn new() -> Self {
         Self {
-            clocks: HashMap::new(),
+            clocks: BTreeMap::new(),
         }
     }
 
@@ -146,6 +176,12 @@ impl VectorClock {
     pub fn concurrent(&self, other: &VectorClock) -> bool {
         !self.happened_before(other) && !other.happened_before(self)
     }
+
+    /// Check if `other` happened before or equals this clock, i.e. this
+    /// clock covers every event `other` does
+    pub fn has_seen(&self, other: &VectorClock) -> bool {
+        other.clocks.iter().all(|(peer_id, &clock)| self.get(peer_id) >= clock)
+    }
 }
 
 impl Default for VectorClock {
@@ -154,10 +190,371 @@ impl Default for VectorClock {
     }
 }
 
+/// Per-channel Lamport clocks
+///
+/// Each channel's clock only advances on sends and receives within that channel,
+/// so activity in one conversation doesn't inflate timestamps in another.
+#[derive(Debug, Clone, Default)]
+pub struct LamportClocks {
+    clocks: HashMap<ChannelId, u64>,
+}
+
+impl LamportClocks {
+    pub fn new() -> Self {
+        Self {
+            clocks: HashMap::new(),
+        }
+    }
+
+    /// Advance a channel's clock for a local send and return the new timestamp
+    pub fn tick(&mut self, channel_id: ChannelId) -> u64 {
+        let clock = self.clocks.entry(channel_id).or_insert(0);
+        *clock += 1;
+        *clock
+    }
+
+    /// Fold in a timestamp observed on a message in a channel
+    pub fn observe(&mut self, channel_id: ChannelId, timestamp: u64) {
+        let clock = self.clocks.entry(channel_id).or_insert(0);
+        *clock = (*clock).max(timestamp);
+    }
+
+    /// Get the current clock value for a channel
+    pub fn get(&self, channel_id: &ChannelId) -> u64 {
+        self.clocks.get(channel_id).copied().unwrap_or(0)
+    }
+}
+
+/// How a message's author clock fits with the author's other messages
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AuthorClockCheck {
+    /// Beyond every clock seen from the author in the channel
+    Advanced,
+    /// Below the newest one seen, at a clock not taken yet: it arrived out of
+    /// order (gossip reordering, or sync carried a later message first)
+    Late,
+    /// Already recorded under this ID
+    Duplicate,
+    /// Another message from the author already has this clock: the author
+    /// equivocated or replayed it with changes
+    Conflict(MessageId),
+    /// The clock is 0, or doesn't advance past a parent from the same
+    /// author, so the author can't have sent it honestly
+    Regression,
+}
+
+impl AuthorClockCheck {
+    /// Whether the message should be kept
+    pub fn accepted(self) -> bool {
+        matches!(self, Self::Advanced | Self::Late | Self::Duplicate)
+    }
+}
+
+/// Own vector clock components seen from each author, per channel
+///
+/// An author increments their own component for every message they send, so
+/// no two of their messages share a value, and a message always shows a
+/// higher one than the author's messages it builds on. Messages may still
+/// arrive in any order; only breaking those rules marks a replayed,
+/// equivocating or buggy sender.
+#[derive(Debug, Clone, Default)]
+pub struct AuthorClocks {
+    seen: HashMap<(ChannelId, PeerId), BTreeMap<u64, MessageId>>,
+    /// Author and own clock of every recorded message, to check parents against
+    recorded: HashMap<MessageId, (PeerId, u64)>,
+}
+
+impl AuthorClocks {
+    pub fn new() -> Self {
+        Self {
+            seen: HashMap::new(),
+            recorded: HashMap::new(),
+        }
+    }
+
+    /// Check a message against its author's others, recording it if accepted
+    pub fn observe(&mut self, message: &Message) -> AuthorClockCheck {
+        let check = self.check(message);
+        if check.accepted() {
+            let own_clock = message.vector_clock.get(&message.author);
+            self.seen
+                .entry((message.channel_id, message.author))
+                .or_default()
+                .insert(own_clock, message.id);
+            self.recorded.insert(message.id, (message.author, own_clock));
+        }
+        check
+    }
+
+    fn check(&self, message: &Message) -> AuthorClockCheck {
+        let own_clock = message.vector_clock.get(&message.author);
+        if self.recorded.contains_key(&message.id) {
+            return AuthorClockCheck::Duplicate;
+        }
+
+        let builds_on_later = message.parent_hashes.iter().any(|parent| {
+            self.recorded
+                .get(parent)
+                .is_some_and(|(author, clock)| *author == message.author && *clock >= own_clock)
+        });
+        if own_clock == 0 || builds_on_later {
+            return AuthorClockCheck::Regression;
+        }
+
+        let seen = self.seen.get(&(message.channel_id, message.author));
+        match seen.and_then(|seen| seen.get(&own_clock)) {
+            Some(existing) => AuthorClockCheck::Conflict(*existing),
+            None if own_clock < self.last_seen(message.channel_id, message.author) => AuthorClockCheck::Late,
+            None => AuthorClockCheck::Advanced,
+        }
+    }
+
+    /// Get the newest clock component seen from an author in a channel
+    pub fn last_seen(&self, channel_id: ChannelId, author: PeerId) -> u64 {
+        self.seen
+            .get(&(channel_id, author))
+            .and_then(|seen| seen.last_key_value())
+            .map_or(0, |(clock, _)| *clock)
+    }
+}
+
+/// The latest vector clock known for each peer, from the messages they sent
+///
+/// A peer's clock when it sent a message covers everything it had received,
+/// so a message whose clock it has seen was causally observed by that peer.
+/// This says nothing about messages the peer saw since its last send.
+#[derive(Debug, Clone, Default)]
+pub struct PeerClocks {
+    clocks: HashMap<PeerId, VectorClock>,
+}
+
+impl PeerClocks {
+    pub fn new() -> Self {
+        Self {
+            clocks: HashMap::new(),
+        }
+    }
+
+    /// Learn from a message what its author had seen when sending it
+    pub fn observe(&mut self, message: &Message) {
+        self.clocks
+            .entry(message.author)
+            .or_default()
+            .merge(&message.vector_clock);
+    }
+
+    /// Whether `peer` had causally observed `message` as of its latest message
+    pub fn has_seen(&self, peer: &PeerId, message: &Message) -> bool {
+        *peer == message.author
+            || self
+                .clocks
+                .get(peer)
+                .is_some_and(|clock| clock.has_seen(&message.vector_clock))
+    }
+
+    /// The peers among `peers` known to have observed `message`
+    pub fn seen_by<'a>(&self, message: &Message, peers: impl IntoIterator<Item = &'a PeerId>) -> Vec<PeerId> {
+        peers
+            .into_iter()
+            .filter(|peer| self.has_seen(peer, message))
+            .copied()
+            .collect()
+    }
+}
+
 /// Message content (plaintext for Phase 1, will be encrypted later)
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// Stored as JSON. Rows from older versions lack fields added since, and
+/// rows written by newer versions may carry kinds or priorities this one
+/// doesn't know; both load with the missing or unknown parts at their
+/// defaults, and unknown fields are ignored.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
 pub struct MessageContent {
     pub text: String,
+    /// Rows stored before kinds existed have no `kind` and load as `Normal`
+    #[serde(default, deserialize_with = "or_default")]
+    pub kind: MessageKind,
+    #[serde(default, deserialize_with = "or_default")]
+    pub priority: Priority,
+}
+
+/// Deserialize a value, or its default if it's one this version can't read
+///
+/// Needs a self-describing format such as JSON, where the unreadable value
+/// can be skipped; content only travels between peers as wire DTOs.
+fn or_default<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: Deserialize<'de> + Default,
+{
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Lenient<T> {
+        Known(T),
+        Unknown(serde::de::IgnoredAny),
+    }
+
+    Ok(match Lenient::<T>::deserialize(deserializer)? {
+        Lenient::Known(value) => value,
+        Lenient::Unknown(_) => T::default(),
+    })
+}
+
+impl MessageContent {
+    /// A normal message typed by a user
+    pub fn new(text: impl Into<String>) -> Self {
+        Self {
+            text: text.into(),
+            kind: MessageKind::Normal,
+            priority: Priority::Normal,
+        }
+    }
+
+    /// Content for what the user typed: `/me waves` becomes an action
+    pub fn from_input(input: &str) -> Self {
+        match input.strip_prefix("/me ").map(str::trim_start) {
+            Some(action) if !action.is_empty() => Self {
+                text: action.to_string(),
+                kind: MessageKind::Action,
+                priority: Priority::Normal,
+            },
+            _ => Self::new(input),
+        }
+    }
+
+    pub fn with_priority(self, priority: Priority) -> Self {
+        Self { priority, ..self }
+    }
+
+    pub fn is_system(&self) -> bool {
+        matches!(self.kind, MessageKind::System(_))
+    }
+
+    pub fn is_urgent(&self) -> bool {
+        self.priority == Priority::Urgent
+    }
+
+    pub fn is_action(&self) -> bool {
+        self.kind == MessageKind::Action
+    }
+
+    /// Content for an image, described in text for where it can't be shown
+    pub fn image(image: ImageRef) -> Self {
+        Self {
+            text: format!("[image {}×{}, {} KiB]", image.width, image.height, image.size.div_ceil(1024)),
+            kind: MessageKind::Image(image),
+            priority: Priority::Normal,
+        }
+    }
+
+    /// The image this content shows, if it's one
+    pub fn image_ref(&self) -> Option<&ImageRef> {
+        match &self.kind {
+            MessageKind::Image(image) => Some(image),
+            _ => None,
+        }
+    }
+}
+
+/// How insistently a message asks for attention
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum Priority {
+    #[default]
+    Normal,
+    /// Highlighted, and notified even in muted channels
+    Urgent,
+}
+
+/// What a message is, which decides how it's rendered
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum MessageKind {
+    #[default]
+    Normal,
+    /// A channel change, as older versions added to the DAG. Only read from
+    /// local history; peers can't send one
+    System(SystemEvent),
+    /// Sent with `/me`, describing what the author does
+    Action,
+    /// A pasted image; the text describes it
+    Image(ImageRef),
+}
+
+/// Largest image that can be attached, in bytes of PNG
+///
+/// An image travels whole in one direct frame, whose encoding can take two
+/// bytes per byte of data, and frames over 1 MiB are refused.
+pub const MAX_IMAGE_BYTES: u64 = 256 * 1024;
+
+/// An image attached to a message, by the SHA-256 of its PNG data
+///
+/// Only this reference is sent with the message. The data is stored apart
+/// and fetched from peers that have it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct ImageRef {
+    pub hash: [u8; 32],
+    pub width: u32,
+    pub height: u32,
+    /// Size of the PNG data in bytes
+    pub size: u64,
+}
+
+impl ImageRef {
+    /// Describe PNG data of the given dimensions
+    pub fn for_png(data: &[u8], width: u32, height: u32) -> Self {
+        Self {
+            hash: attachment_hash(data),
+            width,
+            height,
+            size: data.len() as u64,
+        }
+    }
+}
+
+/// The hash attachments are stored and requested by
+pub fn attachment_hash(data: &[u8]) -> [u8; 32] {
+    Sha256::digest(data).into()
+}
+
+/// A channel change shown in the conversation
+///
+/// Worked out locally from the channel's state, never sent. Each event
+/// carries what makes it unique (the member's add tag, the rename's
+/// timestamp), so seeing the same change again doesn't show it twice.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum SystemEvent {
+    Joined { peer: PeerId, tag: Uuid },
+    Left { peer: PeerId, tag: Uuid },
+    Renamed { name: String, at: Timestamp },
+}
+
+impl SystemEvent {
+    /// The ID of this event in a channel
+    pub fn id(&self, channel_id: ChannelId) -> Uuid {
+        let mut hasher = Sha256::new();
+        hasher.update(b"burrow system event v1");
+        hasher.update(channel_id.0.as_bytes());
+        hasher.update(bincode::serialize(self).expect("system events always serialize"));
+        let digest = hasher.finalize();
+
+        let mut bytes = [0u8; 16];
+        bytes.copy_from_slice(&digest[..16]);
+        uuid::Builder::from_custom_bytes(bytes).into_uuid()
+    }
+}
+
+impl fmt::Display for SystemEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SystemEvent::Joined { peer, .. } => write!(f, "@{} joined", peer.short()),
+            SystemEvent::Left { peer, .. } => write!(f, "@{} left", peer.short()),
+            SystemEvent::Renamed { name, .. } => write!(f, "channel renamed to {}", name),
+        }
+    }
+}
+
+/// Milliseconds since the Unix epoch (0 for earlier times)
+pub fn unix_millis(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH).unwrap_or_default().as_millis() as u64
 }
 
 /// A message with causal ordering metadata
@@ -170,7 +567,18 @@ pub struct Message {
     pub vector_clock: VectorClock,
     pub lamport_timestamp: u64,
     pub parent_hashes: Vec<MessageId>, // For DAG structure (Phase 4)
-    pub created_at: SystemTime,
+    /// Creation time in milliseconds since the Unix epoch
+    ///
+    /// A plain integer rather than `SystemTime`, whose serde form is
+    /// platform-shaped, so every peer and the database keep full precision.
+    pub created_at_ms: u64,
+    /// HLC physical time (ms) after which the message disappears on every replica
+    pub expires_at: Option<u64>,
+    /// The author's latest edit of the content, if any
+    ///
+    /// `content` stays as sent, since it's what the ID and the wire form
+    /// describe; edits travel on their own and the latest one wins.
+    pub edit: Option<LWWRegister<MessageContent>>,
     // Signature will be added in Phase 5
 }
 
@@ -190,9 +598,99 @@ impl Message {
             vector_clock,
             lamport_timestamp,
             parent_hashes: Vec::new(),
-            created_at: SystemTime::now(),
+            created_at_ms: unix_millis(SystemTime::now()),
+            expires_at: None,
+            edit: None,
         }
     }
+
+    /// Stable bytes identifying this message's contents, for signing and hashing
+    ///
+    /// Equal messages give identical bytes on every peer and every run:
+    /// fields go in a fixed order spelled out here rather than the struct's,
+    /// the vector clock is sorted, and parents are sorted since their order
+    /// carries no meaning.
+    pub fn canonical_bytes(&self) -> Vec<u8> {
+        let mut parent_hashes = self.parent_hashes.clone();
+        parent_hashes.sort();
+
+        let mut bytes = CANONICAL_MESSAGE_TAG.to_vec();
+        bincode::serialize_into(
+            &mut bytes,
+            &(
+                self.id,
+                self.channel_id,
+                self.author,
+                &self.content,
+                &self.vector_clock,
+                self.lamport_timestamp,
+                parent_hashes,
+                self.created_at_ms,
+                self.expires_at,
+            ),
+        )
+        .expect("messages always serialize");
+        bytes
+    }
+
+    /// Creation time as a `SystemTime`
+    pub fn created_at(&self) -> SystemTime {
+        UNIX_EPOCH + Duration::from_millis(self.created_at_ms)
+    }
+
+    /// Whether the message has disappeared as of the given HLC physical time
+    pub fn is_expired(&self, now_ms: u64) -> bool {
+        self.expires_at.is_some_and(|expires_at| expires_at <= now_ms)
+    }
+
+    /// Drop the content of an expired message
+    ///
+    /// The message itself stays behind as a tombstone so the DAG keeps its
+    /// parent links.
+    pub fn expire(&mut self) {
+        self.content = MessageContent::new(String::new());
+        self.edit = None;
+    }
+
+    /// The content to show: the latest edit, or the content as sent
+    pub fn current_content(&self) -> &MessageContent {
+        self.edit.as_ref().map_or(&self.content, |edit| edit.value())
+    }
+
+    pub fn is_edited(&self) -> bool {
+        self.edit.is_some()
+    }
+
+    /// Whether `peer_id` may edit this message
+    ///
+    /// Only the author edits a message, and system messages and expired
+    /// ones (whose content is gone) can't be edited.
+    pub fn editable_by(&self, peer_id: &PeerId) -> bool {
+        *peer_id == self.author
+            && !self.content.is_system()
+            && !(self.expires_at.is_some() && self.content.text.is_empty())
+    }
+
+    /// Whether an edit made at `at` may apply to this message
+    pub fn accepts_edit(&self, at: Timestamp) -> bool {
+        self.editable_by(&at.peer_id)
+    }
+
+    /// Apply an edit, keeping whichever edit has the latest timestamp
+    ///
+    /// Edits may arrive in any order and more than once; every replica ends
+    /// up showing the same one. Returns whether the shown content changed.
+    pub fn apply_edit(&mut self, content: MessageContent, at: Timestamp) -> bool {
+        if !self.accepts_edit(at) {
+            return false;
+        }
+        let before = self.current_content().clone();
+        match &mut self.edit {
+            Some(edit) => edit.set(content, at),
+            None => self.edit = Some(LWWRegister::new(content, at)),
+        }
+        *self.current_content() != before
+    }
 }
 
 /// Channel type
@@ -204,6 +702,17 @@ pub enum ChannelType {
     Group,
 }
 
+/// Who may post in a channel
+///
+/// Ordered only so LWW ties between policies resolve the same way everywhere.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+pub enum PostPolicy {
+    /// Any member may post
+    Open,
+    /// Only admins may post; everyone else reads (announcement channels)
+    AdminsOnly,
+}
+
 /// Channel metadata with CRDT state for conflict-free replication
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Channel {
@@ -213,7 +722,24 @@ pub struct Channel {
     pub members: ORSet<PeerId>,          // Observed-Remove Set for membership
     pub created_at: SystemTime,
     pub hlc: HybridLogicalClock,         // For generating timestamps
+    /// When (and by whom) the channel was deleted. Sticky: once set, merging
+    /// an older announcement can't resurrect the channel
+    pub tombstone: Option<Timestamp>,
+    /// Policy writes; the newest by a current admin is in force, Open if none
+    pub post_policy: AuthoredRegister<PostPolicy>,
+    /// Peers allowed to post in AdminsOnly channels and to change the policy
+    pub admins: ORSet<PeerId>,
+    /// Pinned messages; any member may pin or unpin, and a concurrent
+    /// pin wins over an unpin
+    pub pins: ORSet<MessageId>,
+    /// Topic line shown under the name: the newest write by a current
+    /// member, empty when there's none
+    pub description: AuthoredRegister<String>,
     // Encryption keys will be added in Phase 5
+    /// Set when the stored CRDT state was corrupt and this channel was rebuilt
+    /// from cached columns (local only, never serialized)
+    #[serde(skip)]
+    pub degraded: bool,
 }
 
 impl Channel {
@@ -225,6 +751,9 @@ impl Channel {
         let mut members = ORSet::new();
         members.add(creator);
 
+        let mut admins = ORSet::new();
+        admins.add(creator);
+
         Self {
             id: ChannelId::new(),
             name: LWWRegister::new(name, timestamp),
@@ -232,6 +761,12 @@ impl Channel {
             members,
             created_at: SystemTime::now(),
             hlc,
+            tombstone: None,
+            post_policy: AuthoredRegister::new(),
+            admins,
+            pins: ORSet::new(),
+            description: AuthoredRegister::new(),
+            degraded: false,
         }
     }
 
@@ -253,24 +788,45 @@ impl Channel {
             members,
             created_at: SystemTime::now(),
             hlc,
+            tombstone: None,
+            post_policy: AuthoredRegister::new(),
+            admins: ORSet::new(),
+            pins: ORSet::new(),
+            description: AuthoredRegister::new(),
+            degraded: false,
         }
     }
 
     /// Create a placeholder channel (for received messages from unknown channels)
+    ///
+    /// The name is written at the zero timestamp so that the real name from a
+    /// later channel announcement always wins the LWW merge.
     pub fn placeholder(id: ChannelId, name: String, creator: PeerId) -> Self {
-        let mut hlc = HybridLogicalClock::new(creator);
-        let timestamp = hlc.tick();
+        let hlc = HybridLogicalClock::new(creator);
 
         Self {
             id,
-            name: LWWRegister::new(name, timestamp),
+            name: LWWRegister::new(name, Timestamp::new(0, 0, creator)),
             channel_type: ChannelType::Group,
             members: ORSet::new(),  // Unknown members initially
             created_at: SystemTime::now(),
             hlc,
+            tombstone: None,
+            post_policy: AuthoredRegister::new(),
+            admins: ORSet::new(),
+            pins: ORSet::new(),
+            description: AuthoredRegister::new(),
+            degraded: false,
         }
     }
 
+    /// Name shown for a placeholder channel until its real name is known
+    ///
+    /// Uses the short channel ID and the discovering author.
+    pub fn placeholder_name(id: ChannelId, author: PeerId) -> String {
+        format!("channel-{} (via {})", id.short(), author.short())
+    }
+
     /// Get the current channel name
     pub fn get_name(&self) -> &String {
         self.name.value()
@@ -282,12 +838,39 @@ impl Channel {
         self.name.set(new_name, timestamp);
     }
 
+    /// Get the channel description (empty if none was set)
+    pub fn description(&self) -> &str {
+        self.description
+            .latest(|peer| self.members.contains(peer))
+            .map_or("", |write| write.value())
+    }
+
+    /// Change the description, returning false (and changing nothing) if
+    /// `by` isn't a member
+    pub fn set_description(&mut self, description: String, by: &PeerId) -> bool {
+        if !self.members.contains(by) {
+            return false;
+        }
+        let timestamp = self.tick_as(*by);
+        self.description.set(description, timestamp);
+        true
+    }
+
+    /// A timestamp for a change `by` makes, ordered after everything this
+    /// channel has seen and attributed to them rather than the creator
+    fn tick_as(&mut self, by: PeerId) -> Timestamp {
+        Timestamp { peer_id: by, ..self.hlc.tick() }
+    }
+
     /// Add a member to the channel
     pub fn add_member(&mut self, peer_id: PeerId) -> Uuid {
         self.members.add(peer_id)
     }
 
     /// Remove a member from the channel
+    ///
+    /// Only the add tags observed here are removed, so a concurrent re-add by
+    /// another peer survives the merge.
     pub fn remove_member(&mut self, peer_id: &PeerId) {
         self.members.remove(peer_id);
     }
@@ -297,10 +880,193 @@ impl Channel {
         self.members.elements()
     }
 
+    /// Add an admin to the channel
+    pub fn add_admin(&mut self, peer_id: PeerId) -> Uuid {
+        self.admins.add(peer_id)
+    }
+
+    /// Remove an admin from the channel
+    pub fn remove_admin(&mut self, peer_id: &PeerId) {
+        self.admins.remove(peer_id);
+    }
+
+    pub fn is_admin(&self, peer_id: &PeerId) -> bool {
+        self.admins.contains(peer_id)
+    }
+
+    /// Pin a message; it may not have arrived here yet
+    pub fn pin_message(&mut self, message_id: MessageId) -> Uuid {
+        self.pins.add(message_id)
+    }
+
+    /// Unpin a message (only the pins observed here)
+    pub fn unpin_message(&mut self, message_id: &MessageId) {
+        self.pins.remove(message_id);
+    }
+
+    pub fn is_pinned(&self, message_id: &MessageId) -> bool {
+        self.pins.contains(message_id)
+    }
+
+    /// Pinned message IDs, oldest message first
+    pub fn pinned_messages(&self) -> Vec<MessageId> {
+        let mut pinned = self.pins.elements();
+        pinned.sort();
+        pinned
+    }
+
+    /// Get the current post policy
+    pub fn post_policy(&self) -> PostPolicy {
+        self.post_policy
+            .latest(|peer| self.is_admin(peer))
+            .map_or(PostPolicy::Open, |write| *write.value())
+    }
+
+    /// Change who may post, returning false (and changing nothing) if
+    /// `by` isn't an admin
+    pub fn set_post_policy(&mut self, policy: PostPolicy, by: &PeerId) -> bool {
+        if !self.is_admin(by) {
+            return false;
+        }
+        let timestamp = self.tick_as(*by);
+        self.post_policy.set(policy, timestamp);
+        true
+    }
+
+    /// Whether the peer may post in this channel under its post policy
+    ///
+    /// Message authors aren't authenticated yet, so for received messages
+    /// this only keeps honest peers in line until messages are signed.
+    pub fn can_post(&self, peer_id: &PeerId) -> bool {
+        match self.post_policy() {
+            PostPolicy::Open => true,
+            PostPolicy::AdminsOnly => self.is_admin(peer_id),
+        }
+    }
+
+    /// Whether messages should only reach members rather than every peer
+    ///
+    /// Direct channels always are. A group counts as closed once it has
+    /// members besides us; until then (e.g. a channel only its creator has
+    /// joined) anyone who sees it may post, so it stays open.
+    pub fn is_closed(&self, local_peer: &PeerId) -> bool {
+        match self.channel_type {
+            ChannelType::PeerToPeer => true,
+            ChannelType::Group => {
+                self.members.contains(local_peer) && self.members.elements().len() > 1
+            }
+        }
+    }
+
+    /// The other member of a direct channel we're in, whom its messages go
+    /// to encrypted
+    ///
+    /// None for groups, and for a direct channel whose members aren't
+    /// exactly us and one other peer; those are sent like closed groups.
+    pub fn direct_recipient(&self, local_peer: &PeerId) -> Option<PeerId> {
+        if self.channel_type != ChannelType::PeerToPeer || !self.members.contains(local_peer) {
+            return None;
+        }
+        match self.get_members().into_iter().filter(|member| member != local_peer).collect::<Vec<_>>()[..] {
+            [recipient] => Some(recipient),
+            _ => None,
+        }
+    }
+
+    /// Whether no members are known (a placeholder, or everyone was removed)
+    ///
+    /// Member-dependent actions can't reach anyone until the channel state arrives.
+    pub fn has_unknown_membership(&self) -> bool {
+        self.members.elements().is_empty()
+    }
+
+    /// Mark the channel deleted, returning the tombstone timestamp
+    pub fn delete(&mut self) -> Timestamp {
+        if let Some(deleted_at) = self.tombstone {
+            return deleted_at;
+        }
+        let deleted_at = self.hlc.tick();
+        self.tombstone = Some(deleted_at);
+        deleted_at
+    }
+
+    /// Whether the channel has been deleted
+    pub fn is_deleted(&self) -> bool {
+        self.tombstone.is_some()
+    }
+
+    /// Apply a tombstone received from a peer
+    ///
+    /// Only members may delete a channel; returns false (and changes nothing)
+    /// if the deleting peer isn't one.
+    pub fn apply_tombstone(&mut self, deleted_at: Timestamp) -> bool {
+        if !self.members.contains(&deleted_at.peer_id) {
+            return false;
+        }
+        self.tombstone = self.tombstone.max(Some(deleted_at));
+        self.hlc.update(deleted_at);
+        true
+    }
+
+    /// The joins, leaves and renames between an earlier state and this one
+    ///
+    /// Learning a placeholder's real name or a channel's first member list
+    /// isn't a change, so neither produces events.
+    pub fn system_events_since(&self, before: &Channel) -> Vec<SystemEvent> {
+        let mut events = Vec::new();
+
+        if !before.has_unknown_membership() {
+            let first_tag = |members: &ORSet<PeerId>, peer: &PeerId| {
+                members.tags(peer).and_then(|tags| tags.iter().min().copied())
+            };
+            let mut joined: Vec<PeerId> =
+                self.members.elements().into_iter().filter(|p| !before.members.contains(p)).collect();
+            joined.sort();
+            for peer in joined {
+                if let Some(tag) = first_tag(&self.members, &peer) {
+                    events.push(SystemEvent::Joined { peer, tag });
+                }
+            }
+            let mut left: Vec<PeerId> =
+                before.members.elements().into_iter().filter(|p| !self.members.contains(p)).collect();
+            left.sort();
+            for peer in left {
+                if let Some(tag) = first_tag(&before.members, &peer) {
+                    events.push(SystemEvent::Left { peer, tag });
+                }
+            }
+        }
+
+        // Placeholders carry a zero timestamp, so any real name replaces them
+        if self.get_name() != before.get_name() && before.name.timestamp().physical != 0 {
+            events.push(SystemEvent::Renamed {
+                name: self.get_name().clone(),
+                at: self.name.timestamp(),
+            });
+        }
+
+        events
+    }
+
     /// Merge another channel's state (for CRDT synchronization)
     pub fn merge(&mut self, other: &Channel) {
         self.name.merge(&other.name);
+        // Writes by peers who may not make them are kept, and skipped when
+        // read, so replicas agree whatever order they learn of removals in
+        self.description.merge(&other.description);
+        // The creator fixes the type and nobody can change it. Only a copy
+        // that doesn't know the channel yet (a placeholder, which assumes
+        // Group) takes the type from the first copy with members
+        if self.has_unknown_membership() && !other.has_unknown_membership() {
+            self.channel_type = other.channel_type;
+        }
         self.members.merge(&other.members);
+        self.admins.merge(&other.admins);
+        self.pins.merge(&other.pins);
+        self.post_policy.merge(&other.post_policy);
+        if let Some(deleted_at) = other.tombstone {
+            self.apply_tombstone(deleted_at);
+        }
         // Update HLC with the remote timestamp
         let remote_ts = other.hlc.latest();
         self.hlc.update(remote_ts);
@@ -361,4 +1127,642 @@ mod tests {
         assert_eq!(vc1.get(&peer1), 2);
         assert_eq!(vc1.get(&peer2), 3);
     }
+
+    #[test]
+    fn test_seen_follows_happened_before() {
+        let channel = ChannelId::new();
+        let (alice, bob, carol) = (PeerId::new(), PeerId::new(), PeerId::new());
+        let content = |text: &str| MessageContent::new(text);
+
+        // Alice posts; Bob replies after receiving it; Carol posts concurrently
+        let mut alice_clock = VectorClock::new();
+        alice_clock.increment(alice);
+        let hello = Message::new(channel, alice, content("hello"), alice_clock.clone(), 1);
+
+        let mut bob_clock = alice_clock.clone();
+        bob_clock.increment(bob);
+        let reply = Message::new(channel, bob, content("hi"), bob_clock, 2);
+
+        let mut carol_clock = VectorClock::new();
+        carol_clock.increment(carol);
+        let aside = Message::new(channel, carol, content("meanwhile"), carol_clock, 1);
+
+        assert!(hello.vector_clock.happened_before(&reply.vector_clock));
+        assert!(reply.vector_clock.has_seen(&hello.vector_clock));
+        assert!(reply.vector_clock.has_seen(&reply.vector_clock));
+        assert!(!hello.vector_clock.has_seen(&reply.vector_clock));
+        assert!(!aside.vector_clock.has_seen(&hello.vector_clock));
+
+        let mut peer_clocks = PeerClocks::new();
+        for message in [&hello, &reply, &aside] {
+            peer_clocks.observe(message);
+        }
+
+        // The author always counts; Bob's reply proves he saw it; Carol's doesn't
+        let members = [alice, bob, carol];
+        assert_eq!(peer_clocks.seen_by(&hello, &members), vec![alice, bob]);
+        assert_eq!(peer_clocks.seen_by(&aside, &members), vec![carol]);
+    }
+
+    #[test]
+    fn test_lamport_clocks_independent_per_channel() {
+        let mut clocks = LamportClocks::new();
+        let busy = ChannelId::new();
+        let quiet = ChannelId::new();
+
+        clocks.observe(busy, 41);
+        assert_eq!(clocks.tick(busy), 42);
+
+        // Activity in the busy channel doesn't leak into the quiet one
+        assert_eq!(clocks.get(&quiet), 0);
+        assert_eq!(clocks.tick(quiet), 1);
+
+        // Observing an older timestamp never moves a clock backwards
+        clocks.observe(busy, 7);
+        assert_eq!(clocks.get(&busy), 42);
+    }
+
+    fn message_with_clock(channel_id: ChannelId, author: PeerId, own_clock: u64) -> Message {
+        let mut vector_clock = VectorClock::new();
+        vector_clock.clocks.insert(author, own_clock);
+        Message::new(
+            channel_id,
+            author,
+            MessageContent::new(String::new()),
+            vector_clock,
+            own_clock,
+        )
+    }
+
+    #[test]
+    fn test_author_clocks_accept_in_order() {
+        let mut clocks = AuthorClocks::new();
+        let channel = ChannelId::new();
+        let alice = PeerId::new();
+        let bob = PeerId::new();
+
+        assert_eq!(clocks.observe(&message_with_clock(channel, alice, 1)), AuthorClockCheck::Advanced);
+        assert_eq!(clocks.observe(&message_with_clock(channel, alice, 2)), AuthorClockCheck::Advanced);
+        // Gaps are fine (the author may have posted in other channels)
+        assert_eq!(clocks.observe(&message_with_clock(channel, alice, 5)), AuthorClockCheck::Advanced);
+        // Other authors are tracked independently
+        assert_eq!(clocks.observe(&message_with_clock(channel, bob, 1)), AuthorClockCheck::Advanced);
+
+        assert_eq!(clocks.last_seen(channel, alice), 5);
+    }
+
+    #[test]
+    fn test_author_clocks_accept_late_arrivals() {
+        let mut clocks = AuthorClocks::new();
+        let (channel, alice) = (ChannelId::new(), PeerId::new());
+        let m1 = message_with_clock(channel, alice, 1);
+        let mut m2 = message_with_clock(channel, alice, 2);
+        m2.parent_hashes = vec![m1.id];
+
+        // m2 overtook m1 on the way here; both are kept
+        assert_eq!(clocks.observe(&m2), AuthorClockCheck::Advanced);
+        assert_eq!(clocks.observe(&m1), AuthorClockCheck::Late);
+        assert_eq!(clocks.observe(&m1), AuthorClockCheck::Duplicate);
+        assert_eq!(clocks.last_seen(channel, alice), 2);
+    }
+
+    #[test]
+    fn test_author_clocks_reject_regression() {
+        let mut clocks = AuthorClocks::new();
+        let channel = ChannelId::new();
+        let other_channel = ChannelId::new();
+        let alice = PeerId::new();
+
+        let first = message_with_clock(channel, alice, 3);
+        assert_eq!(clocks.observe(&first), AuthorClockCheck::Advanced);
+        // Another message at the same clock is an equivocation
+        assert_eq!(clocks.observe(&message_with_clock(channel, alice, 3)), AuthorClockCheck::Conflict(first.id));
+        // Building on the author's own later message with a lower clock can't be honest
+        let mut behind = message_with_clock(channel, alice, 2);
+        behind.parent_hashes = vec![first.id];
+        assert_eq!(clocks.observe(&behind), AuthorClockCheck::Regression);
+        // A message without the author's own component is never valid
+        assert_eq!(clocks.observe(&message_with_clock(channel, alice, 0)), AuthorClockCheck::Regression);
+        assert_eq!(clocks.last_seen(channel, alice), 3);
+
+        // Clocks are judged per channel
+        assert_eq!(clocks.observe(&message_with_clock(other_channel, alice, 3)), AuthorClockCheck::Advanced);
+    }
+
+    #[test]
+    fn test_message_expiry() {
+        let mut message = message_with_clock(ChannelId::new(), PeerId::new(), 1);
+        message.content.text = "secret".to_string();
+        assert!(!message.is_expired(u64::MAX));
+
+        message.expires_at = Some(1_000);
+        assert!(!message.is_expired(999));
+        assert!(message.is_expired(1_000));
+
+        message.expire();
+        assert!(message.content.text.is_empty());
+        assert_eq!(message.expires_at, Some(1_000));
+    }
+
+    #[test]
+    fn test_equal_messages_have_identical_canonical_bytes() {
+        let peers: Vec<PeerId> = (0..8).map(|_| PeerId::new()).collect();
+        let parents = vec![MessageId::new(), MessageId::new(), MessageId::new()];
+        let author = PeerId(Uuid::from_u128(1));
+
+        // Built independently, with clock entries and parents in different orders
+        let build = |peers: Vec<PeerId>, parents: Vec<MessageId>| {
+            let mut clock = VectorClock::new();
+            for peer in peers {
+                clock.increment(peer);
+            }
+            let mut message = Message::new(ChannelId(Uuid::nil()), author, MessageContent::new("hi"), clock, 7);
+            message.id = MessageId(Uuid::nil());
+            message.parent_hashes = parents;
+            message.created_at_ms = 1_700_000_000_000;
+            message
+        };
+        let first = build(peers.clone(), parents.clone());
+        let second = build(peers.into_iter().rev().collect(), parents.into_iter().rev().collect());
+        assert_eq!(first.canonical_bytes(), second.canonical_bytes());
+
+        let mut edited = first.clone();
+        edited.content.text = "hi!".to_string();
+        assert_ne!(first.canonical_bytes(), edited.canonical_bytes());
+    }
+
+    #[test]
+    fn test_vector_clock_decodes_from_hash_map_encoding() {
+        let peer = PeerId::new();
+        let legacy = bincode::serialize(&HashMap::from([(peer, 3u64)])).unwrap();
+        let clock: VectorClock = bincode::deserialize(&legacy).unwrap();
+        assert_eq!(clock.get(&peer), 3);
+        assert_eq!(bincode::serialize(&clock).unwrap(), legacy);
+    }
+
+    #[test]
+    fn test_placeholder_takes_announced_name() {
+        let creator = PeerId::new();
+        let author = PeerId::new();
+
+        let announced = Channel::new("general".to_string(), creator);
+        // Placeholder is created after the announcement was originally made
+        let mut placeholder = Channel::placeholder(
+            announced.id,
+            Channel::placeholder_name(announced.id, author),
+            author,
+        );
+        assert!(placeholder.get_name().contains(&author.short()));
+
+        placeholder.merge(&announced);
+
+        assert_eq!(placeholder.get_name(), "general");
+        assert!(placeholder.members.contains(&creator));
+    }
+
+    #[test]
+    fn test_reannounce_does_not_resurrect_tombstoned_channel() {
+        let creator = PeerId::new();
+        let announced = Channel::new("general".to_string(), creator);
+
+        let mut local = announced.clone();
+        local.delete();
+        assert!(local.is_deleted());
+
+        // A peer re-announces the pre-deletion state
+        local.merge(&announced);
+        assert!(local.is_deleted());
+
+        // Even with a later name change
+        let mut renamed = announced.clone();
+        renamed.set_name("still-here".to_string());
+        local.merge(&renamed);
+        assert!(local.is_deleted());
+
+        // And the tombstone propagates to replicas that merge it in
+        let mut remote = announced.clone();
+        remote.merge(&local);
+        assert!(remote.is_deleted());
+    }
+
+    #[test]
+    fn test_tombstone_requires_member() {
+        let creator = PeerId::new();
+        let outsider = PeerId::new();
+        let mut channel = Channel::new("general".to_string(), creator);
+
+        assert!(!channel.apply_tombstone(Timestamp::new(1, 0, outsider)));
+        assert!(!channel.is_deleted());
+
+        assert!(channel.apply_tombstone(Timestamp::new(1, 0, creator)));
+        assert!(channel.is_deleted());
+    }
+
+    #[test]
+    fn test_closed_channels() {
+        let alice = PeerId::new();
+        let bob = PeerId::new();
+
+        assert!(Channel::new_peer_to_peer(alice, bob).is_closed(&alice));
+
+        // A group only its creator has joined is open to anyone who sees it
+        let mut group = Channel::new("general".to_string(), alice);
+        assert!(!group.is_closed(&alice));
+
+        group.add_member(bob);
+        assert!(group.is_closed(&alice));
+        assert!(group.is_closed(&bob));
+        // Non-members don't get directed delivery
+        assert!(!group.is_closed(&PeerId::new()));
+    }
+
+    #[test]
+    fn test_admins_only_posting() {
+        let alice = PeerId::new();
+        let bob = PeerId::new();
+
+        let mut announcements = Channel::new("announcements".to_string(), alice);
+        announcements.add_member(bob);
+        assert!(announcements.can_post(&bob));
+
+        // Only admins may lock the channel
+        assert!(!announcements.set_post_policy(PostPolicy::AdminsOnly, &bob));
+        assert!(announcements.set_post_policy(PostPolicy::AdminsOnly, &alice));
+        assert!(announcements.can_post(&alice));
+        assert!(!announcements.can_post(&bob));
+
+        announcements.add_admin(bob);
+        assert!(announcements.can_post(&bob));
+    }
+
+    #[test]
+    fn test_post_policy_merge_ignores_non_admins() {
+        let alice = PeerId::new();
+        let mallory = PeerId::new();
+
+        let mut channel = Channel::new("announcements".to_string(), alice);
+        channel.set_post_policy(PostPolicy::AdminsOnly, &alice);
+
+        // A non-admin's later write loses even though it's newer
+        let mut forged = channel.clone();
+        forged.post_policy.set(PostPolicy::Open, Timestamp::new(u64::MAX, 0, mallory));
+        channel.merge(&forged);
+        assert_eq!(channel.post_policy(), PostPolicy::AdminsOnly);
+
+        // Replicas converge on an admin's change
+        let mut replica = channel.clone();
+        replica.set_post_policy(PostPolicy::Open, &alice);
+        channel.merge(&replica);
+        assert_eq!(channel.post_policy(), PostPolicy::Open);
+    }
+
+    #[test]
+    fn test_policy_and_description_stamped_by_their_author() {
+        let (alice, bob) = (PeerId::new(), PeerId::new());
+        let mut channel = Channel::new("announcements".to_string(), alice);
+        channel.add_member(bob);
+        channel.add_admin(bob);
+
+        assert!(channel.set_post_policy(PostPolicy::AdminsOnly, &bob));
+        assert!(channel.set_description("Release notes".to_string(), &bob));
+        let policy_at = channel.post_policy.latest(|_| true).unwrap().timestamp();
+        let description_at = channel.description.latest(|_| true).unwrap().timestamp();
+        assert_eq!(policy_at.peer_id, bob);
+        assert_eq!(description_at.peer_id, bob);
+        assert!(policy_at < description_at, "later changes still stamp later");
+    }
+
+    #[test]
+    fn test_post_policy_converges_whatever_order_admin_removal_arrives() {
+        let (alice, bob) = (PeerId::new(), PeerId::new());
+        let mut base = Channel::new("announcements".to_string(), alice);
+        base.add_member(bob);
+        base.add_admin(bob);
+        base.set_post_policy(PostPolicy::AdminsOnly, &alice);
+
+        // Bob opens the channel while Alice, concurrently, removes him as admin
+        let mut bobs = base.clone();
+        assert!(bobs.set_post_policy(PostPolicy::Open, &bob));
+        let mut alices = base.clone();
+        alices.remove_admin(&bob);
+
+        // One replica hears of Bob's change first, the other of his removal
+        let mut early = base.clone();
+        early.merge(&bobs);
+        assert_eq!(early.post_policy(), PostPolicy::Open);
+        early.merge(&alices);
+        let mut late = base.clone();
+        late.merge(&alices);
+        late.merge(&bobs);
+
+        // Either way a removed admin's write no longer counts
+        assert_eq!(early.post_policy(), PostPolicy::AdminsOnly);
+        assert_eq!(late.post_policy(), PostPolicy::AdminsOnly);
+    }
+
+    #[test]
+    fn test_description_merge_ignores_non_members() {
+        let (alice, mallory) = (PeerId::new(), PeerId::new());
+        let mut channel = Channel::new("general".to_string(), alice);
+        channel.set_description("Release planning".to_string(), &alice);
+
+        let mut forged = channel.clone();
+        forged.description.set("Spam".to_string(), Timestamp::new(u64::MAX, 0, mallory));
+        channel.merge(&forged);
+        assert_eq!(channel.description(), "Release planning");
+    }
+
+    #[test]
+    fn test_unknown_membership() {
+        let creator = PeerId::new();
+        let id = ChannelId::new();
+
+        let mut placeholder = Channel::placeholder(id, "placeholder".to_string(), creator);
+        assert!(placeholder.has_unknown_membership());
+
+        let mut channel = Channel::new("general".to_string(), creator);
+        assert!(!channel.has_unknown_membership());
+
+        // Removing everyone orphans the channel again
+        channel.remove_member(&creator);
+        assert!(channel.has_unknown_membership());
+
+        // Membership resolves once the channel state is merged in
+        let mut announced = Channel::new("general".to_string(), creator);
+        announced.id = id;
+        placeholder.merge(&announced);
+        assert!(!placeholder.has_unknown_membership());
+    }
+
+    #[test]
+    fn test_system_events_agree_across_replicas() {
+        let alice = PeerId::new();
+        let bob = PeerId::new();
+        let general = Channel::new("general".to_string(), alice);
+
+        // Bob joins and the channel is renamed on one replica
+        let mut changed = general.clone();
+        changed.add_member(bob);
+        changed.set_name("lobby".to_string());
+
+        let mut ours = general.clone();
+        ours.merge(&changed);
+        let mut theirs = general.clone();
+        theirs.merge(&changed);
+
+        let events = ours.system_events_since(&general);
+        assert_eq!(events.len(), 2);
+        assert_eq!(events[0].to_string(), format!("@{} joined", bob.short()));
+        assert_eq!(events[1].to_string(), "channel renamed to lobby");
+        assert_eq!(events, theirs.system_events_since(&general));
+        assert_eq!(events[0].message_id(general.id), theirs.system_events_since(&general)[0].message_id(general.id));
+
+        // Merging the same state again changes nothing
+        let before = ours.clone();
+        ours.merge(&changed);
+        assert!(ours.system_events_since(&before).is_empty());
+
+        // Leaving, then rejoining, is a new event with a new ID
+        let mut left = ours.clone();
+        left.remove_member(&bob);
+        let leave = left.system_events_since(&ours);
+        assert_eq!(leave.len(), 1);
+        assert!(matches!(&leave[0], SystemEvent::Left { peer, .. } if *peer == bob));
+        let mut rejoined = left.clone();
+        rejoined.add_member(bob);
+        let rejoin = rejoined.system_events_since(&left);
+        assert_ne!(rejoin[0].message_id(general.id), events[0].message_id(general.id));
+    }
+
+    #[test]
+    fn test_rename_converges_whatever_the_arrival_order() {
+        let (alice, bob) = (PeerId::new(), PeerId::new());
+        let general = Channel::new("general".to_string(), alice);
+
+        // Bob renames after seeing Alice's rename, so his is the later one
+        let mut ours = general.clone();
+        ours.set_name("lobby".to_string());
+        let mut theirs = general.clone();
+        theirs.merge(&ours);
+        theirs.set_name("hangout".to_string());
+        assert!(theirs.name.timestamp() > ours.name.timestamp());
+
+        // A replica that gets the later rename first still ends on it
+        let mut late = general.clone();
+        late.merge(&theirs);
+        late.merge(&ours);
+        let mut early = general.clone();
+        early.merge(&ours);
+        early.merge(&theirs);
+        ours.merge(&theirs);
+        for replica in [&late, &early, &ours] {
+            assert_eq!(replica.get_name(), "hangout");
+        }
+
+        // Renames at the same instant settle on the same name either way
+        let at = Timestamp::new(1_700_000_000_000, 0, alice);
+        let mut first = general.clone();
+        first.name = LWWRegister::new("alpha".to_string(), at);
+        let mut second = general.clone();
+        second.name = LWWRegister::new("beta".to_string(), Timestamp { peer_id: bob, ..at });
+        let mut one_way = first.clone();
+        one_way.merge(&second);
+        let mut other_way = second.clone();
+        other_way.merge(&first);
+        assert_eq!(one_way.get_name(), other_way.get_name());
+    }
+
+    #[test]
+    fn test_learning_placeholder_state_is_not_an_event() {
+        let creator = PeerId::new();
+        let announced = Channel::new("general".to_string(), creator);
+        let placeholder = Channel::placeholder(announced.id, "channel-x".to_string(), PeerId::new());
+
+        let mut resolved = placeholder.clone();
+        resolved.merge(&announced);
+        assert!(resolved.system_events_since(&placeholder).is_empty());
+    }
+
+    #[test]
+    fn test_legacy_content_loads_as_normal() {
+        let content: MessageContent = serde_json::from_str(r#"{"text":"hello"}"#).unwrap();
+        assert_eq!(content.kind, MessageKind::Normal);
+        assert!(!content.is_system());
+        assert_eq!(content.priority, Priority::Normal);
+    }
+
+    #[test]
+    fn test_me_prefix_makes_an_action() {
+        let waves = MessageContent::from_input("/me waves at everyone");
+        assert!(waves.is_action());
+        assert_eq!(waves.text, "waves at everyone");
+
+        let stored: MessageContent = serde_json::from_str(&serde_json::to_string(&waves).unwrap()).unwrap();
+        assert_eq!(stored, waves);
+
+        for plain in ["/me", "/me   ", "/meh", " /me waves", "hi /me"] {
+            let content = MessageContent::from_input(plain);
+            assert_eq!(content, MessageContent::new(plain), "{:?}", plain);
+        }
+    }
+
+    #[test]
+    fn test_image_content_describes_the_image() {
+        let image = ImageRef::for_png(b"not really a png", 640, 480);
+        assert_eq!(image.hash, attachment_hash(b"not really a png"));
+
+        let content = MessageContent::image(image);
+        assert_eq!(content.image_ref(), Some(&image));
+        assert_eq!(content.text, "[image 640×480, 1 KiB]");
+
+        let stored: MessageContent = serde_json::from_str(&serde_json::to_string(&content).unwrap()).unwrap();
+        assert_eq!(stored, content);
+        assert_eq!(MessageContent::new("hi").image_ref(), None);
+    }
+
+    #[test]
+    fn test_concurrent_pin_and_unpin_converge() {
+        let alice = PeerId::new();
+        let mut alice_copy = Channel::new("general".to_string(), alice);
+        let first = MessageId::new();
+        let second = MessageId::new();
+        alice_copy.pin_message(first);
+        alice_copy.pin_message(second);
+
+        let mut bob_copy = alice_copy.clone();
+
+        // Bob unpins the first while Alice, not having seen that, pins it again
+        bob_copy.unpin_message(&first);
+        alice_copy.pin_message(first);
+        // Both unpin the second independently
+        bob_copy.unpin_message(&second);
+        alice_copy.unpin_message(&second);
+
+        let mut alice_merged = alice_copy.clone();
+        alice_merged.merge(&bob_copy);
+        let mut bob_merged = bob_copy.clone();
+        bob_merged.merge(&alice_copy);
+
+        // The concurrent pin survives the unpin, the same on both sides
+        assert_eq!(alice_merged.pinned_messages(), vec![first]);
+        assert_eq!(bob_merged.pinned_messages(), vec![first]);
+
+        // An unpin of everything observed sticks through later merges
+        bob_merged.unpin_message(&first);
+        alice_merged.merge(&bob_merged);
+        assert!(!alice_merged.is_pinned(&first));
+        bob_merged.merge(&alice_copy);
+        assert!(bob_merged.pinned_messages().is_empty());
+    }
+
+    #[test]
+    fn test_concurrent_description_edits_converge() {
+        let (alice, bob) = (PeerId::new(), PeerId::new());
+        let mut alice_copy = Channel::new("general".to_string(), alice);
+        alice_copy.add_member(bob);
+        let mut bob_copy = alice_copy.clone();
+        // A stranger's copy never saw Bob join
+        let mut stranger_copy = Channel::new("general".to_string(), PeerId::new());
+
+        // Both edit without having seen the other's change
+        assert!(alice_copy.set_description("Release planning".to_string(), &alice));
+        assert!(bob_copy.set_description("Weekly sync notes".to_string(), &bob));
+        assert!(!stranger_copy.set_description("Spam".to_string(), &bob));
+
+        let mut alice_merged = alice_copy.clone();
+        alice_merged.merge(&bob_copy);
+        let mut bob_merged = bob_copy.clone();
+        bob_merged.merge(&alice_copy);
+
+        // Whichever edit has the later timestamp wins, the same on both sides
+        assert_eq!(alice_merged.description(), bob_merged.description());
+        let written_at = |channel: &Channel| channel.description.latest(|_| true).unwrap().timestamp();
+        let winner = if written_at(&alice_copy) > written_at(&bob_copy) {
+            "Release planning"
+        } else {
+            "Weekly sync notes"
+        };
+        assert_eq!(alice_merged.description(), winner);
+
+        // A later edit replaces it everywhere
+        assert!(alice_merged.set_description(String::new(), &alice));
+        bob_merged.merge(&alice_merged);
+        assert_eq!(bob_merged.description(), "");
+    }
+
+    #[test]
+    fn test_message_edits_converge_in_any_order() {
+        let author = PeerId::new();
+        let original = Message::new(ChannelId::new(), author, MessageContent::new("helo"), VectorClock::new(), 1);
+        let first = (MessageContent::new("hello"), Timestamp::new(10, 0, author));
+        let second = (MessageContent::new("hello!"), Timestamp::new(11, 0, author));
+
+        let mut in_order = original.clone();
+        assert!(in_order.apply_edit(first.0.clone(), first.1));
+        assert!(in_order.apply_edit(second.0.clone(), second.1));
+        let mut reversed = original.clone();
+        assert!(reversed.apply_edit(second.0.clone(), second.1));
+        assert!(!reversed.apply_edit(first.0.clone(), first.1));
+
+        assert_eq!(in_order.current_content().text, "hello!");
+        assert_eq!(reversed.current_content().text, "hello!");
+        assert_eq!(reversed.content.text, "helo");
+        assert_eq!(reversed.canonical_bytes(), original.canonical_bytes());
+
+        // Only the author edits, and expiry takes the edit with it
+        assert!(!reversed.apply_edit(MessageContent::new("spam"), Timestamp::new(12, 0, PeerId::new())));
+        reversed.expires_at = Some(5);
+        reversed.expire();
+        assert!(!reversed.is_edited());
+        assert!(!reversed.apply_edit(MessageContent::new("back"), Timestamp::new(13, 0, author)));
+    }
+
+    #[test]
+    fn test_placeholder_merge_agrees_on_type() {
+        let (alice, bob) = (PeerId::new(), PeerId::new());
+        let direct = Channel::new_peer_to_peer(alice, bob);
+        let placeholder = Channel::placeholder(direct.id, "channel-x".to_string(), bob);
+        assert_eq!(placeholder.channel_type, ChannelType::Group);
+
+        let mut resolved = placeholder.clone();
+        resolved.merge(&direct);
+        let mut announced = direct.clone();
+        announced.merge(&placeholder);
+
+        assert_eq!(resolved.channel_type, ChannelType::PeerToPeer);
+        assert_eq!(announced.channel_type, ChannelType::PeerToPeer);
+
+        // A group stays a group when it meets its own placeholder
+        let group = Channel::new("general".to_string(), alice);
+        let mut resolved = Channel::placeholder(group.id, "channel-y".to_string(), bob);
+        resolved.merge(&group);
+        assert_eq!(resolved.channel_type, ChannelType::Group);
+
+        // Once known, no peer's copy can change the type either way
+        let mut forged = direct.clone();
+        forged.id = group.id;
+        resolved.merge(&forged);
+        assert_eq!(resolved.channel_type, ChannelType::Group);
+        let mut forged = group.clone();
+        forged.id = direct.id;
+        announced.merge(&forged);
+        assert_eq!(announced.channel_type, ChannelType::PeerToPeer);
+    }
+
+    #[test]
+    fn test_direct_recipient_needs_exactly_two_members() {
+        let (alice, bob, carol) = (PeerId::new(), PeerId::new(), PeerId::new());
+        let mut direct = Channel::new_peer_to_peer(alice, bob);
+        assert_eq!(direct.direct_recipient(&alice), Some(bob));
+        assert_eq!(direct.direct_recipient(&bob), Some(alice));
+        assert_eq!(direct.direct_recipient(&carol), None);
+
+        // A third member turns it into something only a group send can reach
+        direct.add_member(carol);
+        assert_eq!(direct.direct_recipient(&alice), None);
+
+        let mut group = Channel::new("pair".to_string(), alice);
+        group.add_member(bob);
+        assert_eq!(group.direct_recipient(&alice), None);
+    }
 }
@@ -0,0 +1,306 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! A headless Burrow node for the integration tests
+//!
+//! Runs a real `Network` on loopback behind the app's own `BurrowNode`,
+//! and handles network events the way the app does, minus the UI.
+
+use anyhow::{bail, Result};
+use burrow::dag::gossip::{GossipManager, DEFAULT_ANTI_ENTROPY_FANOUT, DEFAULT_MAX_OUTSTANDING_REQUESTS};
+use burrow::dag::{IngestMode, MessageDAG};
+use burrow::encryption::session::SessionManager;
+use burrow::network::{create_network_channels, Network, NetworkCommand, NetworkEvent, NetworkNamespace};
+use burrow::node::BurrowNode;
+use burrow::storage::Storage;
+use burrow::types::{Channel, ChannelId, LamportClocks, Message, MessageContent, PeerId, VectorClock};
+use libp2p::identity::Keypair;
+use libp2p::multiaddr::Protocol;
+use libp2p::Multiaddr;
+use std::collections::{HashMap, HashSet};
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+
+/// How long a test waits for the network before giving up
+pub const TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Run a test on a `LocalSet`, which the network's event loop needs
+pub async fn run_local<F: std::future::Future>(test: F) -> F::Output {
+    tokio::task::LocalSet::new().run_until(test).await
+}
+
+/// A namespace no other test (or running Burrow) shares, so mDNS can't
+/// connect nodes from different tests
+pub fn isolated_namespace() -> NetworkNamespace {
+    NetworkNamespace::new(&format!("test-{}", uuid::Uuid::now_v7()))
+}
+
+pub struct Node {
+    /// Storage, DAG and clocks, and the sending the app does through them
+    pub node: BurrowNode,
+    /// Channels as last stored
+    pub channels: HashMap<ChannelId, Channel>,
+    gossip: GossipManager,
+    command_tx: mpsc::UnboundedSender<NetworkCommand>,
+    event_rx: mpsc::UnboundedReceiver<NetworkEvent>,
+    network: JoinHandle<()>,
+    db_path: PathBuf,
+    pub listen_addrs: Vec<Multiaddr>,
+    pub connected: HashSet<libp2p::PeerId>,
+    /// Peers on the chat topic, as last reported by the network
+    pub topic_peers: usize,
+    /// Peers present in each joined channel, as last reported by the network
+    pub channel_peers: HashMap<ChannelId, usize>,
+    /// Peers we've started an encryption session with from their prekey bundle
+    pub sessions: HashSet<PeerId>,
+}
+
+impl Node {
+    /// Start a node listening on an OS-assigned port
+    pub async fn start(namespace: &NetworkNamespace) -> Result<Self> {
+        let keypair = Keypair::generate_ed25519();
+        let peer_id = PeerId::from_libp2p(&keypair.public().to_peer_id());
+
+        let db_path = std::env::temp_dir().join(format!("burrow-it-{}.db", uuid::Uuid::now_v7()));
+        std::fs::File::create(&db_path)?;
+        let storage = Storage::new(&db_path).await?;
+
+        // Direct channels are encrypted with sessions from the stored Signal identity, as in the app
+        let (event_tx, event_rx, command_tx, command_rx) = create_network_channels();
+        let mut network = Network::new(keypair, event_tx, command_rx, namespace.clone()).await?;
+        network.set_session_manager(Arc::new(SessionManager::new(storage.load_signal_store().await?)));
+        network.listen(0)?;
+        let network = tokio::task::spawn_local(async move {
+            let _ = network.run().await;
+        });
+
+        let mut node = Self {
+            node: BurrowNode::new(
+                peer_id,
+                storage,
+                MessageDAG::new(),
+                VectorClock::new(),
+                LamportClocks::new(),
+                command_tx.clone(),
+            ),
+            channels: HashMap::new(),
+            gossip: GossipManager::new(command_tx.clone(), DEFAULT_MAX_OUTSTANDING_REQUESTS, DEFAULT_ANTI_ENTROPY_FANOUT),
+            command_tx,
+            event_rx,
+            network,
+            db_path,
+            listen_addrs: Vec::new(),
+            connected: HashSet::new(),
+            topic_peers: 0,
+            channel_peers: HashMap::new(),
+            sessions: HashSet::new(),
+        };
+        node.run_until(|node| node.loopback_addr().is_some()).await?;
+        Ok(node)
+    }
+
+    /// The listen address other nodes in this process can dial
+    pub fn loopback_addr(&self) -> Option<Multiaddr> {
+        self.listen_addrs
+            .iter()
+            .find(|addr| addr.iter().any(|p| matches!(p, Protocol::Ip4(ip) if ip.is_loopback())))
+            .cloned()
+    }
+
+    /// Dial `other`, then run both until each sees the other on the chat topic
+    pub async fn connect(&mut self, other: &mut Node) -> Result<()> {
+        let Some(addr) = other.loopback_addr() else {
+            bail!("Node has no loopback listen address");
+        };
+        self.command_tx.send(NetworkCommand::ConnectToPeer(addr))?;
+        run_both_until(self, other, |a, b| a.topic_peers > 0 && b.topic_peers > 0).await
+    }
+
+    /// Create a channel and announce it
+    pub async fn create_channel(&mut self, name: &str) -> Result<Channel> {
+        let channel = Channel::new(name.to_string(), self.node.peer_id);
+        self.node.storage.store_channel(&channel).await?;
+        self.channels.insert(channel.id, channel.clone());
+        self.command_tx.send(NetworkCommand::AnnounceChannel(channel.clone()))?;
+        Ok(channel)
+    }
+
+    /// Post a message on top of our heads and send it, as the app does
+    pub async fn post(&mut self, channel_id: ChannelId, text: &str) -> Result<Message> {
+        self.node.send_message(channel_id, text).await
+    }
+
+    /// Post a message in a direct channel, sent encrypted to the other member
+    pub async fn post_direct(&mut self, channel: &Channel, text: &str) -> Result<Message> {
+        self.node.publish(channel, MessageContent::new(text), None, None).await
+    }
+
+    /// Send an already posted message again, as the app's resend does
+    pub fn resend(&self, message: &Message) -> Result<()> {
+        let Some(channel) = self.channels.get(&message.channel_id) else {
+            bail!("Unknown channel {}", message.channel_id.0);
+        };
+        self.node.send(channel, message)
+    }
+
+    /// Join a channel's presence topic, as the app does for every listed channel
+    pub fn join_channel(&self, channel_id: ChannelId) -> Result<()> {
+        self.command_tx.send(NetworkCommand::JoinChannel(channel_id))?;
+        Ok(())
+    }
+
+    /// Leave a channel's presence topic, as the app does when one is deleted
+    pub fn leave_channel(&self, channel_id: ChannelId) -> Result<()> {
+        self.command_tx.send(NetworkCommand::LeaveChannel(channel_id))?;
+        Ok(())
+    }
+
+    /// Ask peers what they have for a channel, as the app does on connect
+    pub fn request_inventory(&self, channel_id: ChannelId) -> Result<()> {
+        self.gossip.request_inventory(channel_id)
+    }
+
+    /// IDs of a channel's messages in DAG order
+    pub fn ordered_ids(&self, channel_id: &ChannelId) -> Vec<burrow::types::MessageId> {
+        self.node.dag.get_ordered_messages(channel_id).iter().map(|m| m.id).collect()
+    }
+
+    /// Handle events until `done` holds, failing after `TIMEOUT`
+    pub async fn run_until(&mut self, done: impl Fn(&Node) -> bool) -> Result<()> {
+        tokio::time::timeout(TIMEOUT, async {
+            while !done(self) {
+                self.step().await?;
+            }
+            Ok::<_, anyhow::Error>(())
+        })
+        .await?
+    }
+
+    /// Wait for and handle one network event
+    async fn step(&mut self) -> Result<()> {
+        let Some(event) = self.event_rx.recv().await else {
+            bail!("Network stopped");
+        };
+        self.handle(event).await
+    }
+
+    async fn handle(&mut self, event: NetworkEvent) -> Result<()> {
+        match event {
+            NetworkEvent::ListeningOn(addr) => self.listen_addrs.push(addr),
+            NetworkEvent::PeerConnected { peer_id, .. } => {
+                self.connected.insert(peer_id);
+            }
+            NetworkEvent::PeerDisconnected(peer_id) => {
+                self.connected.remove(&peer_id);
+            }
+            NetworkEvent::TopicPeersChanged { peers, .. } => self.topic_peers = peers,
+            NetworkEvent::TopicPeers { channel_id, count } => {
+                self.channel_peers.insert(channel_id, count);
+            }
+            NetworkEvent::SessionEstablished(peer_id) => {
+                self.sessions.insert(peer_id);
+            }
+            NetworkEvent::ChannelAnnounced(channel)
+            | NetworkEvent::ChannelStateReceived(channel)
+            | NetworkEvent::ChannelUpdated(channel) => {
+                let merged = match self.channels.remove(&channel.id) {
+                    Some(mut existing) => {
+                        existing.merge(&channel);
+                        existing
+                    }
+                    None => channel,
+                };
+                self.node.storage.store_channel(&merged).await?;
+                self.channels.insert(merged.id, merged);
+            }
+            NetworkEvent::MessageReceived { message, .. } => {
+                if !self.node.dag.has_message(&message.id) {
+                    self.ensure_channel(&message).await?;
+                    self.node.storage.store_message(&message).await?;
+                    self.node.vector_clock.merge(&message.vector_clock);
+                    self.node.lamport_clocks.observe(message.channel_id, message.lamport_timestamp);
+                    self.node.dag.ingest(message.clone(), IngestMode::BufferOrphans)?;
+                    self.gossip.handle_messages_received(&[message.id])?;
+                }
+            }
+            NetworkEvent::MessagesReceived { messages, .. } => {
+                for message in &messages {
+                    self.ensure_channel(message).await?;
+                }
+                self.node.storage.store_messages(&messages).await.into_result()?;
+                for message in &messages {
+                    self.node.vector_clock.merge(&message.vector_clock);
+                    self.node.lamport_clocks.observe(message.channel_id, message.lamport_timestamp);
+                }
+                let message_ids: Vec<_> = messages.iter().map(|m| m.id).collect();
+                self.node.dag.ingest_all(messages, IngestMode::BufferOrphans)?;
+                self.gossip.handle_messages_received(&message_ids)?;
+            }
+            NetworkEvent::InventoryRequested { channel_id, .. } => {
+                self.gossip.send_inventory(channel_id, &self.node.storage).await?;
+            }
+            NetworkEvent::InventoryReceived { channel_id, message_ids, edits, .. } => {
+                let our_edits = self.node.storage.get_channel_edit_times(channel_id).await?;
+                self.gossip.handle_inventory(channel_id, message_ids, &edits, &our_edits, &self.node.dag)?;
+            }
+            NetworkEvent::MessageRequested { channel_id, message_ids, .. } => {
+                self.gossip.handle_message_request(channel_id, message_ids, &self.node.storage).await?;
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    /// Store a placeholder for a message's channel if we haven't heard of
+    /// it yet, as the app does, so we can post there too
+    async fn ensure_channel(&mut self, message: &Message) -> Result<()> {
+        if self.channels.contains_key(&message.channel_id) {
+            return Ok(());
+        }
+        let name = Channel::placeholder_name(message.channel_id, message.author);
+        let placeholder = Channel::placeholder(message.channel_id, name, message.author);
+        self.node.storage.store_channel(&placeholder).await?;
+        self.channels.insert(placeholder.id, placeholder);
+        Ok(())
+    }
+}
+
+impl Drop for Node {
+    fn drop(&mut self) {
+        self.network.abort();
+        let _ = std::fs::remove_file(&self.db_path);
+    }
+}
+
+/// Handle both nodes' events until `done` holds, failing after `TIMEOUT`
+///
+/// Each node only makes progress while it's being stepped, so two nodes
+/// talking to each other have to be run together.
+pub async fn run_both_until(a: &mut Node, b: &mut Node, done: impl Fn(&Node, &Node) -> bool) -> Result<()> {
+    tokio::time::timeout(TIMEOUT, async {
+        while !done(a, b) {
+            tokio::select! {
+                Some(event) = a.event_rx.recv() => a.handle(event).await?,
+                Some(event) = b.event_rx.recv() => b.handle(event).await?,
+                else => bail!("Network stopped"),
+            }
+        }
+        Ok::<_, anyhow::Error>(())
+    })
+    .await?
+}
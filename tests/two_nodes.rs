@@ -0,0 +1,183 @@
+// Copyright (C) 2026 Burrow Contributors
+//
+// This program is free software: you can redistribute it and/or modify
+// it under the terms of the GNU Affero General Public License as published
+// by the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// This program is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU Affero General Public License for more details.
+//
+// You should have received a copy of the GNU Affero General Public License
+// along with this program.  If not, see <https://www.gnu.org/licenses/>.
+
+//! Two nodes on loopback, talking through the real network stack
+
+mod common;
+
+use anyhow::Result;
+use burrow::types::Channel;
+use common::{isolated_namespace, run_both_until, run_local, Node};
+use std::collections::HashSet;
+
+#[tokio::test]
+async fn test_message_arrives_stored_and_ordered_identically() -> Result<()> {
+    run_local(async {
+        let namespace = isolated_namespace();
+        let mut alice = Node::start(&namespace).await?;
+        let mut bob = Node::start(&namespace).await?;
+        alice.connect(&mut bob).await?;
+
+        let channel = alice.create_channel("general").await?;
+        let first = alice.post(channel.id, "hello bob").await?;
+        run_both_until(&mut alice, &mut bob, |_, bob| bob.node.dag.has_message(&first.id)).await?;
+
+        // Bob's reply builds on Alice's message, so both sides must agree on order
+        let reply = bob.post(channel.id, "hi alice").await?;
+        assert_eq!(reply.parent_hashes, vec![first.id]);
+        run_both_until(&mut alice, &mut bob, |alice, _| alice.node.dag.has_message(&reply.id)).await?;
+
+        assert_eq!(alice.ordered_ids(&channel.id), vec![first.id, reply.id]);
+        assert_eq!(alice.ordered_ids(&channel.id), bob.ordered_ids(&channel.id));
+
+        let stored = bob.node.storage.get_message(first.id).await?.expect("message stored on the receiver");
+        assert_eq!(stored.content.text, "hello bob");
+        assert_eq!(stored.author, alice.node.peer_id);
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_channel_announce_reaches_peer() -> Result<()> {
+    run_local(async {
+        let namespace = isolated_namespace();
+        let mut alice = Node::start(&namespace).await?;
+        let mut bob = Node::start(&namespace).await?;
+        alice.connect(&mut bob).await?;
+
+        let channel = alice.create_channel("announced").await?;
+        run_both_until(&mut alice, &mut bob, |_, bob| bob.channels.contains_key(&channel.id)).await?;
+
+        let known = bob.node.storage.get_channel(channel.id).await?.expect("announced channel stored");
+        assert_eq!(known.get_name(), "announced");
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_channel_listener_counts_follow_peers() -> Result<()> {
+    run_local(async {
+        let namespace = isolated_namespace();
+        let mut alice = Node::start(&namespace).await?;
+        let mut bob = Node::start(&namespace).await?;
+        alice.connect(&mut bob).await?;
+
+        let channel = alice.create_channel("presence").await?;
+        alice.join_channel(channel.id)?;
+        run_both_until(&mut alice, &mut bob, |alice, _| alice.channel_peers.get(&channel.id) == Some(&0)).await?;
+
+        bob.join_channel(channel.id)?;
+        run_both_until(&mut alice, &mut bob, |alice, bob| {
+            alice.channel_peers.get(&channel.id) == Some(&1) && bob.channel_peers.get(&channel.id) == Some(&1)
+        })
+        .await?;
+
+        // Bob leaving takes Alice's count back to zero
+        bob.leave_channel(channel.id)?;
+        run_both_until(&mut alice, &mut bob, |alice, _| alice.channel_peers.get(&channel.id) == Some(&0)).await?;
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_history_syncs_after_partition() -> Result<()> {
+    run_local(async {
+        let namespace = isolated_namespace();
+
+        // Alice posts while nobody else is around
+        let mut alice = Node::start(&namespace).await?;
+        let channel = alice.create_channel("offline").await?;
+        let mut posted = Vec::new();
+        for i in 0..5 {
+            posted.push(alice.post(channel.id, &format!("message {}", i)).await?.id);
+        }
+
+        // Bob shows up later and catches up through anti-entropy
+        let mut bob = Node::start(&namespace).await?;
+        bob.connect(&mut alice).await?;
+        bob.request_inventory(channel.id)?;
+        run_both_until(&mut bob, &mut alice, |bob, _| posted.iter().all(|id| bob.node.dag.has_message(id))).await?;
+
+        assert_eq!(bob.ordered_ids(&channel.id), posted);
+        assert_eq!(bob.ordered_ids(&channel.id), alice.ordered_ids(&channel.id));
+
+        let stored: HashSet<_> = bob.node.storage.get_channel_message_ids(channel.id).await?.into_iter().collect();
+        assert_eq!(stored, posted.iter().copied().collect());
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_resent_message_is_not_duplicated() -> Result<()> {
+    run_local(async {
+        let namespace = isolated_namespace();
+
+        // Posted with nobody around, so the first send reaches no one
+        let mut alice = Node::start(&namespace).await?;
+        let channel = alice.create_channel("retry").await?;
+        let first = alice.post(channel.id, "anyone there?").await?;
+
+        let mut bob = Node::start(&namespace).await?;
+        alice.connect(&mut bob).await?;
+        alice.resend(&first)?;
+        run_both_until(&mut alice, &mut bob, |_, bob| bob.node.dag.has_message(&first.id)).await?;
+
+        // Resending again, after it arrived, changes nothing on either side
+        alice.resend(&first)?;
+        let next = alice.post(channel.id, "there you are").await?;
+        run_both_until(&mut alice, &mut bob, |_, bob| bob.node.dag.has_message(&next.id)).await?;
+
+        let expected = vec![first.id, next.id];
+        assert_eq!(alice.ordered_ids(&channel.id), expected);
+        assert_eq!(bob.ordered_ids(&channel.id), expected);
+        assert_eq!(alice.node.storage.get_channel_message_ids(channel.id).await?.len(), 2);
+        assert_eq!(bob.node.storage.get_channel_message_ids(channel.id).await?.len(), 2);
+        Ok(())
+    })
+    .await
+}
+
+#[tokio::test]
+async fn test_direct_message_travels_encrypted() -> Result<()> {
+    run_local(async {
+        let namespace = isolated_namespace();
+        let mut alice = Node::start(&namespace).await?;
+        let mut bob = Node::start(&namespace).await?;
+        alice.connect(&mut bob).await?;
+
+        // Each side hands the other a prekey bundle on connecting
+        run_both_until(&mut alice, &mut bob, |alice, bob| {
+            alice.sessions.contains(&bob.node.peer_id) && bob.sessions.contains(&alice.node.peer_id)
+        })
+        .await?;
+
+        // Encrypted frames are never sent without a session, so arriving at all means they were sealed
+        let channel = Channel::new_peer_to_peer(alice.node.peer_id, bob.node.peer_id);
+        let first = alice.post_direct(&channel, "just between us").await?;
+        run_both_until(&mut alice, &mut bob, |_, bob| bob.node.dag.has_message(&first.id)).await?;
+        let reply = bob.post_direct(&channel, "likewise").await?;
+        run_both_until(&mut alice, &mut bob, |alice, _| alice.node.dag.has_message(&reply.id)).await?;
+
+        let stored = bob.node.storage.get_message(first.id).await?.expect("message stored on the receiver");
+        assert_eq!(stored.content.text, "just between us");
+        assert_eq!(alice.ordered_ids(&channel.id), vec![first.id, reply.id]);
+        Ok(())
+    })
+    .await
+}